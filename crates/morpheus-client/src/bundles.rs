@@ -0,0 +1,94 @@
+//! Loading a version's WASM module and JS glue as a verified pair.
+
+use crate::errors::{ClientError, Result};
+use crate::MorpheusClient;
+use morpheus_core::bundle::BundleManifest;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// A version's module and glue, checked against its manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bundle {
+    pub manifest: BundleManifest,
+    pub wasm: Vec<u8>,
+    pub glue: String,
+}
+
+/// Resolves versions to [`Bundle`]s, caching them by the integrity hashes
+/// of the pair, so a hot reload never combines one version's module with
+/// another's glue and versions that rebuilt to identical files are only
+/// downloaded once.
+///
+/// Created by [`MorpheusClient::bundle_loader`].
+#[derive(Debug, Clone)]
+pub struct BundleLoader {
+    client: MorpheusClient,
+    cache: Arc<Mutex<HashMap<String, Arc<Bundle>>>>,
+}
+
+impl BundleLoader {
+    pub(crate) fn new(client: MorpheusClient) -> Self {
+        Self {
+            client,
+            cache: Arc::default(),
+        }
+    }
+
+    /// The bundle of `version_id`.
+    pub async fn load(&self, version_id: usize) -> Result<Arc<Bundle>> {
+        let manifest = self.client.bundle(version_id).await?;
+        self.load_manifest(manifest).await
+    }
+
+    /// The bundle `manifest` describes, e.g. from a render response.
+    pub async fn load_manifest(&self, manifest: BundleManifest) -> Result<Arc<Bundle>> {
+        let key = manifest.key();
+        if let Some(bundle) = self.cache.lock().unwrap().get(&key) {
+            return Ok(bundle.clone());
+        }
+
+        let wasm = self.client.bytes(&manifest.wasm.url).await?;
+        let glue = String::from_utf8(self.client.bytes(&manifest.glue.url).await?)
+            .map_err(|_| ClientError::Integrity("JS glue is not UTF-8".to_string()))?;
+        manifest
+            .verify(&wasm, &glue)
+            .map_err(|e| ClientError::Integrity(e.to_string()))?;
+
+        let bundle = Arc::new(Bundle { manifest, wasm, glue });
+        self.cache.lock().unwrap().insert(key, bundle.clone());
+        Ok(bundle)
+    }
+
+    /// Number of distinct pairs cached.
+    pub fn cached(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::serve;
+    use axum::routing::get;
+    use axum::{Json, Router};
+
+    #[tokio::test]
+    async fn test_mismatched_or_unreadable_files_are_never_cached() {
+        let app = Router::new()
+            .route("/api/versions/1/component.wasm", get(|| async { b"tampered".to_vec() }))
+            .route("/api/versions/1/component.js", get(|| async { "glue" }))
+            .route("/api/versions/2/component.wasm", get(|| async { b"wasm".to_vec() }))
+            .route("/api/versions/2/component.js", get(|| async { vec![0xff, 0xfe] }))
+            .route(
+                "/api/versions/2/bundle",
+                get(|| async { Json(BundleManifest::new(2, "main", b"wasm", "glue")) }),
+            );
+        let loader = serve(app).await.bundle_loader();
+
+        let tampered = loader.load_manifest(BundleManifest::new(1, "main", b"wasm", "glue")).await;
+        assert!(matches!(tampered, Err(ClientError::Integrity(message)) if message.contains("WASM module")));
+        let binary = loader.load(2).await;
+        assert!(matches!(binary, Err(ClientError::Integrity(message)) if message == "JS glue is not UTF-8"));
+        assert_eq!(loader.cached(), 0);
+    }
+}
@@ -0,0 +1,42 @@
+//! Error types for the client.
+
+use thiserror::Error;
+
+/// Errors from calling a Morpheus server.
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The request couldn't be sent or the response couldn't be read.
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The server answered with an error status.
+    #[error("Server returned {status}: {message}")]
+    Api {
+        /// HTTP status code.
+        status: u16,
+        /// The server's `error` message.
+        message: String,
+        /// The whole error body, e.g. with `conflict` details on 409.
+        body: serde_json::Value,
+    },
+
+    /// A downloaded file didn't match its bundle manifest.
+    #[error("Integrity check failed: {0}")]
+    Integrity(String),
+
+    /// A response body didn't match the expected shape.
+    #[error("Unexpected response: {0}")]
+    Decode(#[from] serde_json::Error),
+}
+
+impl ClientError {
+    /// HTTP status of a [`ClientError::Api`] error.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ClientError::Api { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
@@ -0,0 +1,76 @@
+//! Following a generation job.
+
+use crate::errors::Result;
+use crate::reloads::{push, take_event};
+use crate::types::Job;
+
+/// Server-sent `job` events from `GET /api/jobs/{id}/events`.
+///
+/// Created by [`MorpheusClient::watch_job`](crate::MorpheusClient::watch_job).
+#[derive(Debug)]
+pub struct JobStream {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+}
+
+impl JobStream {
+    pub(crate) fn new(response: reqwest::Response) -> Self {
+        Self {
+            response,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Wait for the job's next status; `None` once it has finished and the
+    /// server closed the stream.
+    pub async fn next(&mut self) -> Result<Option<Job>> {
+        loop {
+            if let Some(data) = take_event(&mut self.buffer, "job") {
+                return Ok(Some(serde_json::from_str(&data)?));
+            }
+            match self.response.chunk().await? {
+                Some(chunk) => push(&mut self.buffer, &chunk),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Wait for the job to finish, returning its final state.
+    pub async fn finished(mut self) -> Result<Option<Job>> {
+        let mut last = None;
+        while let Some(job) = self.next().await? {
+            let done = job.status.is_finished();
+            last = Some(job);
+            if done {
+                break;
+            }
+        }
+        Ok(last.filter(|job| job.status.is_finished()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::serve;
+    use crate::types::JobStatus;
+    use axum::routing::get;
+    use axum::Router;
+
+    #[tokio::test]
+    async fn test_streams_that_end_early_have_no_final_state() {
+        let app = Router::new().route(
+            "/api/jobs/j1/events",
+            get(|| async {
+                ": keep-alive\n\n\
+                 event: reload\ndata: {\"version_id\":1}\n\n\
+                 event: job\ndata: {\"id\":\"j1\",\"status\":\"compiling\",\"iteration\":1}\n\n"
+            }),
+        );
+        let client = serve(app).await;
+
+        let mut events = client.watch_job("j1").await.unwrap();
+        assert_eq!(events.next().await.unwrap().map(|job| job.status), Some(JobStatus::Compiling));
+        assert_eq!(events.next().await.unwrap(), None);
+        assert_eq!(client.watch_job("j1").await.unwrap().finished().await.unwrap(), None);
+    }
+}
@@ -0,0 +1,361 @@
+//! # Morpheus Client
+//!
+//! Typed async client for a running Morpheus server, for native tools,
+//! tests and other services that drive generation without a browser.
+//!
+//! ```rust,no_run
+//! use morpheus_client::{GenerateRequest, MorpheusClient};
+//!
+//! # async fn run() -> morpheus_client::Result<()> {
+//! let morpheus = MorpheusClient::new("http://127.0.0.1:3002");
+//!
+//! let generated = morpheus.generate(&GenerateRequest::new("A counter with + and - buttons")).await?;
+//! println!("version {:?} after {} attempts", generated.version_id, generated.iterations);
+//!
+//! let mut reloads = morpheus.subscribe_reloads().await?;
+//! while let Some(reload) = reloads.next().await? {
+//!     println!("{} v{} is live", reload.name, reload.version_id);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod bundles;
+pub mod errors;
+pub mod jobs;
+pub mod reloads;
+pub mod types;
+
+pub use bundles::{Bundle, BundleLoader};
+pub use errors::{ClientError, Result};
+pub use jobs::JobStream;
+pub use reloads::ReloadStream;
+pub use types::*;
+
+use morpheus_core::bundle::BundleManifest;
+use morpheus_core::component::ComponentMetadata;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Client for the Morpheus HTTP API.
+///
+/// Cheap to clone; clones share a connection pool.
+#[derive(Debug, Clone)]
+pub struct MorpheusClient {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl MorpheusClient {
+    /// Client for the server at `base_url`, e.g. `http://127.0.0.1:3002`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Use a preconfigured HTTP client (timeouts, proxies, default headers).
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Server the client talks to.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Check the server is up.
+    pub async fn health(&self) -> Result<Value> {
+        self.send(self.http.get(self.url("/api/health"))).await
+    }
+
+    /// Generate a component, waiting until it compiles or the server gives up.
+    ///
+    /// A failed generation is not an error: check
+    /// [`GenerateResponse::success`]. A conflict with a newer version is a
+    /// [`ClientError::Api`] with status 409.
+    pub async fn generate(&self, request: &GenerateRequest) -> Result<GenerateResponse> {
+        self.post("/api/generate", request).await
+    }
+
+    /// Queue a generation and return at once; follow it with [`job`](Self::job)
+    /// or [`watch_job`](Self::watch_job).
+    pub async fn submit(&self, request: &GenerateRequest) -> Result<Job> {
+        self.post("/api/jobs", request).await
+    }
+
+    /// A job's status, and its result once done.
+    pub async fn job(&self, id: &str) -> Result<Job> {
+        self.send(self.http.get(self.url(&format!("/api/jobs/{}", id)))).await
+    }
+
+    /// Cancel a job that hasn't started saving its version.
+    pub async fn cancel_job(&self, id: &str) -> Result<Job> {
+        self.send(self.http.delete(self.url(&format!("/api/jobs/{}", id)))).await
+    }
+
+    /// Follow a job's status changes until it finishes.
+    pub async fn watch_job(&self, id: &str) -> Result<JobStream> {
+        let response = self.http.get(self.url(&format!("/api/jobs/{}/events", id))).send().await?;
+        Ok(JobStream::new(check(response).await?))
+    }
+
+    /// Version history and the current state.
+    pub async fn history(&self) -> Result<HistoryResponse> {
+        self.send(self.http.get(self.url("/api/history"))).await
+    }
+
+    /// Make `version_id` current again, restoring its state.
+    pub async fn rollback(&self, version_id: usize) -> Result<RollbackResponse> {
+        self.post("/api/rollback", &json!({ "version_id": version_id })).await
+    }
+
+    /// `version_id`'s module as a patch against `from`, a version the
+    /// caller already has.
+    pub async fn patch(&self, version_id: usize, from: usize) -> Result<PatchResponse> {
+        let path = format!("/api/versions/{}/patch?from={}", version_id, from);
+        self.send(self.http.get(self.url(&path))).await
+    }
+
+    /// `version_id`'s module and glue: URLs and integrity hashes.
+    pub async fn bundle(&self, version_id: usize) -> Result<BundleManifest> {
+        self.send(self.http.get(self.url(&format!("/api/versions/{}/bundle", version_id)))).await
+    }
+
+    /// A loader that fetches bundles and caches them by content; clones of
+    /// the loader share the cache.
+    pub fn bundle_loader(&self) -> BundleLoader {
+        BundleLoader::new(self.clone())
+    }
+
+    /// Rate the live version from 1 to 5; the next generation of that
+    /// component sees the feedback.
+    pub async fn feedback(&self, rating: u8, text: &str) -> Result<FeedbackResponse> {
+        self.post("/api/feedback", &json!({ "rating": rating, "text": text })).await
+    }
+
+    /// Components loaded on the server.
+    pub async fn components(&self) -> Result<Vec<ComponentMetadata>> {
+        self.send(self.http.get(self.url("/api/components"))).await
+    }
+
+    /// Replace the current component state.
+    pub async fn update_state(&self, state: &Value) -> Result<()> {
+        let _: Value = self.post("/api/state", &json!({ "state": state })).await?;
+        Ok(())
+    }
+
+    /// Follow versions as they go live, whether from generation, rollback,
+    /// review or a scheduled activation.
+    pub async fn subscribe_reloads(&self) -> Result<ReloadStream> {
+        let response = self.http.get(self.url("/api/reloads")).send().await?;
+        Ok(ReloadStream::new(check(response).await?))
+    }
+
+    async fn bytes(&self, path: &str) -> Result<Vec<u8>> {
+        let response = check(self.http.get(self.url(path)).send().await?).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    async fn post<B: Serialize + ?Sized, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {
+        self.send(self.http.post(self.url(path)).json(body)).await
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T> {
+        let response = check(request.send().await?).await?;
+        Ok(serde_json::from_slice(&response.bytes().await?)?)
+    }
+}
+
+/// Turn an error status into [`ClientError::Api`].
+async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body: Value = serde_json::from_slice(&response.bytes().await?).unwrap_or(Value::Null);
+    let message = body["error"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| status.canonical_reason().unwrap_or("error").to_string());
+    Err(ClientError::Api {
+        status: status.as_u16(),
+        message,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use std::sync::Arc;
+
+    pub(crate) async fn serve(app: Router) -> MorpheusClient {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        MorpheusClient::new(format!("http://{}/", addr))
+    }
+
+    #[tokio::test]
+    async fn test_typed_requests_and_responses() {
+        let app = Router::new()
+            .route(
+                "/api/generate",
+                post(|Json(body): Json<Value>| async move {
+                    assert_eq!(body["prompt"], "counter");
+                    assert_eq!(body["component"], "widget");
+                    assert_eq!(body["on_conflict"], "rebase");
+                    Json(json!({ "success": true, "version_id": 3, "iterations": 2, "logs": ["ok"], "slots": [] }))
+                }),
+            )
+            .route(
+                "/api/versions/3/patch",
+                get(|query: axum::extract::RawQuery| async move {
+                    assert_eq!(query.0.as_deref(), Some("from=2"));
+                    Json(json!({ "version_id": 3, "base_version_id": 2, "wasm_size": 900, "patch_size": 40 }))
+                }),
+            )
+            .route(
+                "/api/history",
+                get(|| async {
+                    Json(json!({
+                        "versions": [{ "id": 0, "name": "main", "is_current": true, "review": { "status": "approved" } }],
+                        "current_state": { "count": 1 },
+                    }))
+                }),
+            );
+        let client = serve(app).await;
+
+        let request = GenerateRequest::new("counter")
+            .with_component("widget")
+            .with_base_version(2, ConflictStrategy::Rebase);
+        let generated = client.generate(&request).await.unwrap();
+        assert!(generated.success);
+        assert_eq!(generated.version_id, Some(3));
+
+        let patch = client.patch(3, 2).await.unwrap();
+        assert_eq!((patch.base_version_id, patch.patch_size), (2, 40));
+
+        let history = client.history().await.unwrap();
+        assert!(history.versions[0].is_current);
+        assert!(history.versions[0].review.is_approved());
+        assert_eq!(history.current_state, Some(json!({ "count": 1 })));
+    }
+
+    #[tokio::test]
+    async fn test_error_status_carries_server_message() {
+        let app = Router::new().route(
+            "/api/rollback",
+            post(|| async {
+                let body = json!({ "error": "Version 4 was committed since base version 2", "conflict": {} });
+                (StatusCode::CONFLICT, Json(body))
+            }),
+        );
+        let client = serve(app).await;
+
+        let error = client.rollback(2).await.unwrap_err();
+
+        assert_eq!(error.status(), Some(409));
+        assert!(error.to_string().contains("committed since base version 2"));
+        assert!(matches!(error, ClientError::Api { body, .. } if body["conflict"].is_object()));
+    }
+
+    #[tokio::test]
+    async fn test_jobs() {
+        let app = Router::new()
+            .route(
+                "/api/jobs",
+                post(|| async { Json(json!({ "id": "j1", "status": "queued", "position": 0, "prompt": "counter" })) }),
+            )
+            .route(
+                "/api/jobs/j1",
+                get(|| async { Json(json!({ "id": "j1", "status": "compiling", "iteration": 2 })) })
+                    .delete(|| async { Json(json!({ "id": "j1", "status": "cancelled" })) }),
+            )
+            .route(
+                "/api/jobs/j1/events",
+                get(|| async {
+                    "event: job\ndata: {\"id\":\"j1\",\"status\":\"awaiting_ai\",\"iteration\":1}\n\n\
+                     event: job\ndata: {\"id\":\"j1\",\"status\":\"done\",\"result\":{\"success\":true,\"version_id\":4}}\n\n"
+                }),
+            );
+        let client = serve(app).await;
+
+        let job = client.submit(&GenerateRequest::new("counter")).await.unwrap();
+        assert_eq!((job.status, job.position), (JobStatus::Queued, Some(0)));
+
+        let job = client.job("j1").await.unwrap();
+        assert_eq!((job.status, job.iteration), (JobStatus::Compiling, 2));
+        assert_eq!(client.cancel_job("j1").await.unwrap().status, JobStatus::Cancelled);
+
+        let mut events = client.watch_job("j1").await.unwrap();
+        assert_eq!(events.next().await.unwrap().map(|j| j.status), Some(JobStatus::AwaitingAi));
+        let done = events.finished().await.unwrap().unwrap();
+        assert_eq!(done.result.and_then(|r| r.version_id), Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_bundle_loader_verifies_and_caches_pairs() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static DOWNLOADS: AtomicUsize = AtomicUsize::new(0);
+
+        let manifest = |id: usize, glue: &str| Json(BundleManifest::new(id, "main", b"wasm", glue));
+        let file = |body: &'static str| {
+            get(move || async move {
+                DOWNLOADS.fetch_add(1, Ordering::SeqCst);
+                body
+            })
+        };
+        let app = Router::new()
+            .route("/api/versions/1/bundle", get(move || async move { manifest(1, "glue") }))
+            // Rebuilt to identical files
+            .route("/api/versions/2/bundle", get(move || async move { manifest(2, "glue") }))
+            // Published a manifest for glue the server doesn't serve
+            .route("/api/versions/3/bundle", get(move || async move { manifest(3, "glue v3") }))
+            .route("/api/versions/1/component.wasm", file("wasm"))
+            .route("/api/versions/1/component.js", file("glue"))
+            .route("/api/versions/3/component.wasm", file("wasm"))
+            .route("/api/versions/3/component.js", file("glue v2"));
+        let loader = serve(app).await.bundle_loader();
+
+        let first = loader.load(1).await.unwrap();
+        assert_eq!((first.wasm.as_slice(), first.glue.as_str()), (&b"wasm"[..], "glue"));
+        let second = loader.load(2).await.unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(DOWNLOADS.load(Ordering::SeqCst), 2);
+
+        let error = loader.load(3).await.unwrap_err();
+        assert!(matches!(error, ClientError::Integrity(message) if message.contains("JS glue does not match")));
+        assert_eq!(loader.cached(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_reloads() {
+        let app = Router::new().route(
+            "/api/reloads",
+            get(|| async {
+                ": keep-alive\n\n\
+                 event: reload\ndata: {\"version_id\":1,\"name\":\"main\",\"activated_at\":\"2024-01-01T00:00:00Z\"}\n\n\
+                 event: reload\ndata: {\"version_id\":2,\"name\":\"main\",\"activated_at\":\"2024-01-01T00:01:00Z\"}\n\n"
+            }),
+        );
+        let client = serve(app).await;
+
+        let mut reloads = client.subscribe_reloads().await.unwrap();
+
+        assert_eq!(reloads.next().await.unwrap().map(|r| r.version_id), Some(1));
+        assert_eq!(reloads.next().await.unwrap().map(|r| r.version_id), Some(2));
+        assert_eq!(reloads.next().await.unwrap(), None);
+    }
+}
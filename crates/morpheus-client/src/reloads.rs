@@ -0,0 +1,85 @@
+//! Subscription to version activations.
+
+use crate::errors::Result;
+use crate::types::ReloadEvent;
+
+/// Server-sent `reload` events from `GET /api/reloads`.
+///
+/// Created by [`MorpheusClient::subscribe_reloads`](crate::MorpheusClient::subscribe_reloads).
+#[derive(Debug)]
+pub struct ReloadStream {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+}
+
+impl ReloadStream {
+    pub(crate) fn new(response: reqwest::Response) -> Self {
+        Self {
+            response,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Wait for the next version to go live; `None` once the server closes
+    /// the stream.
+    pub async fn next(&mut self) -> Result<Option<ReloadEvent>> {
+        loop {
+            if let Some(data) = take_event(&mut self.buffer, "reload") {
+                return Ok(Some(serde_json::from_str(&data)?));
+            }
+            match self.response.chunk().await? {
+                Some(chunk) => push(&mut self.buffer, &chunk),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Append received bytes, normalizing CRLF line endings to LF.
+pub(crate) fn push(buffer: &mut Vec<u8>, chunk: &[u8]) {
+    buffer.extend(chunk.iter().filter(|&&b| b != b'\r'));
+}
+
+/// Remove complete events from the front of `buffer`, returning the data of
+/// the first one named `name`. Comments (keep-alives) and other events are
+/// skipped.
+pub(crate) fn take_event(buffer: &mut Vec<u8>, name: &str) -> Option<String> {
+    loop {
+        let end = buffer.windows(2).position(|w| w == b"\n\n")?;
+        let block: Vec<u8> = buffer.drain(..end + 2).collect();
+        let block = String::from_utf8_lossy(&block);
+
+        let mut event = "message";
+        let mut data = Vec::new();
+        for line in block.lines() {
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => event = value,
+                "data" => data.push(value),
+                _ => {}
+            }
+        }
+        if event == name && !data.is_empty() {
+            return Some(data.join("\n"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_event_skips_comments_and_partial_events() {
+        let mut buffer = Vec::new();
+        push(&mut buffer, b": keep-alive\n\nevent: reload\ndata: {\"version_id\":2}\n\nevent: rel");
+
+        assert_eq!(take_event(&mut buffer, "reload").as_deref(), Some("{\"version_id\":2}"));
+        assert_eq!(take_event(&mut buffer, "reload"), None);
+        assert_eq!(buffer, b"event: rel");
+
+        push(&mut buffer, b"oad\r\ndata: a\r\ndata: b\r\n\r\n");
+        assert_eq!(take_event(&mut buffer, "reload").as_deref(), Some("a\nb"));
+    }
+}
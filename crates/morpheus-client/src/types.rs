@@ -0,0 +1,394 @@
+//! Request and response bodies.
+//!
+//! These mirror the server's API types (see `GET /api/openapi.json`).
+//! Responses tolerate missing and unknown fields, so a client keeps working
+//! against a newer or older server.
+
+use morpheus_core::bundle::BundleManifest;
+use morpheus_core::component::Provenance;
+use morpheus_core::manifest::SlotDecl;
+use morpheus_core::permissions::Permissions;
+use morpheus_core::review::Review;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Request to generate a component.
+#[derive(Debug, Clone, Serialize)]
+pub struct GenerateRequest {
+    /// What to build or change.
+    pub prompt: String,
+
+    /// Component name (the server defaults to `main`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+
+    /// Existing components to embed into named slots.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub slots: Vec<SlotDecl>,
+
+    /// Version this request is based on; if a newer one was committed in
+    /// the meantime the server reports a conflict.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_version_id: Option<usize>,
+
+    /// What the server does on conflict.
+    pub on_conflict: ConflictStrategy,
+
+    /// Permissions the component needs (none by default).
+    pub permissions: Permissions,
+}
+
+/// How the server resolves a concurrent modification.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Fail with a 409.
+    #[default]
+    Reject,
+
+    /// Re-prompt the AI against the new current version.
+    Rebase,
+}
+
+impl GenerateRequest {
+    /// Generate the `main` component from `prompt`.
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            component: None,
+            slots: Vec::new(),
+            base_version_id: None,
+            on_conflict: ConflictStrategy::default(),
+            permissions: Permissions::default(),
+        }
+    }
+
+    /// Target a named component.
+    pub fn with_component(mut self, name: impl Into<String>) -> Self {
+        self.component = Some(name.into());
+        self
+    }
+
+    /// Embed `component` into the slot `name`.
+    pub fn with_slot(mut self, name: impl Into<String>, component: impl Into<String>) -> Self {
+        self.slots.push(SlotDecl {
+            name: name.into(),
+            component: component.into(),
+        });
+        self
+    }
+
+    /// Base the request on `version_id`, resolving conflicts with `strategy`.
+    pub fn with_base_version(mut self, version_id: usize, strategy: ConflictStrategy) -> Self {
+        self.base_version_id = Some(version_id);
+        self.on_conflict = strategy;
+        self
+    }
+
+    /// Request permissions for the component.
+    pub fn with_permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = permissions;
+        self
+    }
+}
+
+/// Result of a generation request.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct GenerateResponse {
+    /// Whether a version was produced.
+    pub success: bool,
+
+    /// The new version.
+    pub version_id: Option<usize>,
+
+    /// The compiled module, base64-encoded.
+    pub wasm_base64: Option<String>,
+
+    /// State carried over from the previous version.
+    pub restored_state: Option<Value>,
+
+    /// Why generation failed.
+    pub error: Option<String>,
+
+    /// Compile attempts made.
+    pub iterations: u32,
+
+    /// Progress messages from the server.
+    pub logs: Vec<String>,
+}
+
+/// Version history and the current state.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HistoryResponse {
+    /// Every version, oldest first.
+    pub versions: Vec<VersionSummary>,
+
+    /// State of the current version.
+    pub current_state: Option<Value>,
+}
+
+/// One version in the history.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct VersionSummary {
+    /// Version ID, also its position in the history.
+    pub id: usize,
+
+    /// Component name.
+    pub name: String,
+
+    /// What the version does.
+    pub description: String,
+
+    /// When the version was created (RFC 3339).
+    pub created_at: String,
+
+    /// Whether this is the version currently running.
+    pub is_current: bool,
+
+    /// Whether the AI wrote it.
+    pub ai_generated: bool,
+
+    /// Review status and comments.
+    pub review: Review,
+
+    /// AI-written summary of what changed.
+    pub changelog: Option<ChangelogEntry>,
+
+    /// Guardrails the version exceeded.
+    pub guardrail_violations: Vec<String>,
+
+    /// Sign-off to activate it anyway.
+    pub guardrail_override: Option<GuardrailOverride>,
+
+    /// Where the code came from.
+    pub provenance: Provenance,
+
+    /// How many users rated the version.
+    pub feedback_count: usize,
+
+    /// Their average rating, from 1 to 5.
+    pub average_rating: Option<f64>,
+}
+
+/// Human-readable summary of a version's changes.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ChangelogEntry {
+    /// Version the changes are relative to.
+    pub base_version_id: usize,
+
+    /// The changes, as bullet points.
+    pub summary: String,
+
+    /// When the summary was written (RFC 3339).
+    pub generated_at: String,
+}
+
+/// Sign-off to activate a version that exceeds guardrails.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct GuardrailOverride {
+    /// Who signed off.
+    pub by: String,
+
+    /// Why.
+    pub reason: String,
+
+    /// When (RFC 3339).
+    pub at: String,
+}
+
+/// Result of a rollback.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RollbackResponse {
+    /// Whether the version is now current.
+    pub success: bool,
+
+    /// The version rolled back to.
+    pub version_id: usize,
+
+    /// The restored module, base64-encoded.
+    pub wasm_base64: String,
+
+    /// The version's saved state.
+    pub restored_state: Option<Value>,
+
+    /// Why the rollback was refused (e.g. the version awaits review).
+    pub error: Option<String>,
+}
+
+/// A version's module as a patch against another version.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PatchResponse {
+    /// The version the patch produces.
+    pub version_id: usize,
+
+    /// The version the patch applies to.
+    pub base_version_id: usize,
+
+    /// Size of the full module.
+    pub wasm_size: usize,
+
+    /// Size of the patch.
+    pub patch_size: usize,
+
+    /// The patch (see `morpheus_core::delta`), base64-encoded.
+    pub patch_base64: String,
+}
+
+/// Feedback recorded on a version.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FeedbackResponse {
+    /// The version rated.
+    pub version_id: usize,
+
+    /// How many ratings it has.
+    pub feedback_count: usize,
+
+    /// Their average, from 1 to 5.
+    pub average_rating: f64,
+}
+
+/// A version going live.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ReloadEvent {
+    /// The version now running.
+    pub version_id: usize,
+
+    /// Its name.
+    pub name: String,
+
+    /// When it went live (RFC 3339).
+    pub activated_at: String,
+
+    /// Its module, glue and assets, for [`crate::BundleLoader::load_manifest`].
+    pub bundle: Option<BundleManifest>,
+}
+
+/// A queued generation.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Job {
+    /// Job ID, for polling and cancelling.
+    pub id: String,
+
+    /// Where the job is in the pipeline.
+    pub status: JobStatus,
+
+    /// Current compile attempt, from 1 (0 while queued).
+    pub iteration: u32,
+
+    /// Jobs ahead of this one while queued.
+    pub position: Option<usize>,
+
+    /// What was asked for.
+    pub prompt: String,
+
+    /// When the job was queued (RFC 3339).
+    pub created_at: String,
+
+    /// When it started running (RFC 3339).
+    pub started_at: Option<String>,
+
+    /// When it finished (RFC 3339).
+    pub finished_at: Option<String>,
+
+    /// Outcome once [`JobStatus::Done`].
+    pub result: Option<GenerateResponse>,
+
+    /// Why the job failed.
+    pub error: Option<String>,
+}
+
+/// Where a job is in the pipeline.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for earlier jobs.
+    #[default]
+    Queued,
+
+    /// Waiting for the AI to write code.
+    AwaitingAi,
+
+    /// Compiling what the AI wrote.
+    Compiling,
+
+    /// Saving the new version; can no longer be cancelled.
+    Saving,
+
+    /// Finished; check [`GenerateResponse::success`].
+    Done,
+
+    /// Stopped by an error.
+    Failed,
+
+    /// Cancelled before it finished.
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job will change no further.
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Done | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_requests_only_send_what_is_set() {
+        let request = serde_json::to_value(GenerateRequest::new("counter")).unwrap();
+        assert_eq!(request["prompt"], "counter");
+        assert_eq!(request["on_conflict"], "reject");
+        for field in ["component", "slots", "base_version_id"] {
+            assert!(request.get(field).is_none(), "{}", field);
+        }
+
+        let request = GenerateRequest::new("dashboard")
+            .with_component("dashboard")
+            .with_slot("main", "chart")
+            .with_base_version(4, ConflictStrategy::Rebase);
+        let request = serde_json::to_value(request).unwrap();
+        assert_eq!(request["component"], "dashboard");
+        assert_eq!(request["slots"], json!([{ "name": "main", "component": "chart" }]));
+        assert_eq!((request["base_version_id"].clone(), request["on_conflict"].clone()), (json!(4), json!("rebase")));
+    }
+
+    #[test]
+    fn test_responses_tolerate_missing_and_unknown_fields() {
+        let version: VersionSummary = serde_json::from_value(json!({ "id": 2, "added_later": true })).unwrap();
+        assert_eq!((version.id, version.name.as_str(), version.changelog.is_none()), (2, "", true));
+
+        let job: Job = serde_json::from_value(json!({ "id": "j1", "status": "awaiting_ai" })).unwrap();
+        assert_eq!((job.status, job.iteration, job.result), (JobStatus::AwaitingAi, 0, None));
+        assert!(serde_json::from_value::<Job>(json!({ "status": "paused" })).is_err());
+    }
+
+    #[test]
+    fn test_only_terminal_statuses_are_finished() {
+        let finished: Vec<JobStatus> = [
+            JobStatus::Queued,
+            JobStatus::AwaitingAi,
+            JobStatus::Compiling,
+            JobStatus::Saving,
+            JobStatus::Done,
+            JobStatus::Failed,
+            JobStatus::Cancelled,
+        ]
+        .into_iter()
+        .filter(|status| status.is_finished())
+        .collect();
+        assert_eq!(finished, [JobStatus::Done, JobStatus::Failed, JobStatus::Cancelled]);
+    }
+}
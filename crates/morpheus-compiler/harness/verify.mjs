@@ -0,0 +1,114 @@
+// Headless browser verification harness, run by morpheus-compiler's
+// `Verifier` as `node verify.mjs <dir>`.
+//
+// <dir> holds a component's wasm-bindgen web glue (component.js) and module
+// (component_bg.wasm). The harness serves them to headless Chrome, renders
+// the component into <main id="app">, times the first render, counts the
+// long tasks until then, audits the result with axe-core and prints it all
+// as JSON on stdout. puppeteer and axe-core are resolved from the working
+// directory.
+
+import { createRequire } from 'node:module';
+import { createServer } from 'node:http';
+import { readFile, stat } from 'node:fs/promises';
+import { basename, extname, join } from 'node:path';
+
+const require = createRequire(join(process.cwd(), 'verify.cjs'));
+const puppeteer = require('puppeteer');
+const AXE = require.resolve('axe-core/axe.min.js');
+
+const dir = process.argv[2];
+const RENDER_TIMEOUT_MS = 10_000;
+const TYPES = { '.js': 'text/javascript', '.wasm': 'application/wasm' };
+
+const PAGE = `<!DOCTYPE html>
+<html lang="en">
+<head><meta charset="utf-8"><title>Morpheus verification</title>
+<style>
+/* The default theme's tokens, written in by verify.rs */
+__MORPHEUS_THEME__
+</style>
+</head>
+<body>
+<main id="app"></main>
+<script type="module">
+// Components translate their text and read theme tokens and the color scheme
+// through these host imports; the harness shows the English fallback in the
+// default theme, in light mode
+window.morpheus = {
+    t: (key, fallback, args) => {
+        const values = JSON.parse(args || 'null') || {};
+        return (fallback ?? key).replace(/\\{([^{}]+)\\}/g, (placeholder, name) => String(values[name] ?? placeholder));
+    },
+    themeToken: (name) => getComputedStyle(document.documentElement)
+        .getPropertyValue('--morpheus-' + name.replaceAll('.', '-')).trim(),
+    colorScheme: () => 'light'
+};
+let longTasks = 0;
+const observer = new PerformanceObserver((list) => { longTasks += list.getEntries().length; });
+observer.observe({ type: 'longtask', buffered: true });
+const start = performance.now();
+try {
+    const { default: init, ...component } = await import('./component.js');
+    await init('./component_bg.wasm');
+    if (typeof component.render === 'function') {
+        document.getElementById('app').innerHTML = component.render();
+    }
+    const firstRenderMs = performance.now() - start;
+    // Long tasks are reported after they end
+    await new Promise((resolve) => setTimeout(resolve, 100));
+    longTasks += observer.takeRecords().length;
+    window.__morpheusVerify = { ok: true, firstRenderMs, longTasks };
+} catch (error) {
+    window.__morpheusVerify = { ok: false, error: String(error) };
+}
+</script>
+</body>
+</html>`;
+
+// Serves the page, and the component's files by name only
+const server = createServer(async (request, response) => {
+    const name = basename(new URL(request.url, 'http://localhost').pathname);
+    try {
+        const body = name ? await readFile(join(dir, name)) : PAGE;
+        response.writeHead(200, { 'Content-Type': TYPES[extname(name)] ?? 'text/html' });
+        response.end(body);
+    } catch {
+        response.writeHead(404);
+        response.end();
+    }
+});
+await new Promise((resolve) => server.listen(0, '127.0.0.1', resolve));
+
+const browser = await puppeteer.launch({ headless: true, args: ['--no-sandbox'] });
+try {
+    const page = await browser.newPage();
+    await page.goto(`http://127.0.0.1:${server.address().port}/`);
+    const rendered = await page.waitForFunction(() => window.__morpheusVerify, { timeout: RENDER_TIMEOUT_MS });
+    const outcome = await rendered.jsonValue();
+    if (!outcome.ok) throw new Error(`Component failed to render: ${outcome.error}`);
+
+    await page.addScriptTag({ path: AXE });
+    const audit = await page.evaluate(() => window.axe.run('#app'));
+    const html = await page.$eval('#app', (app) => app.innerHTML);
+    const sizes = await Promise.all(['component.js', 'component_bg.wasm'].map((name) => stat(join(dir, name))));
+
+    process.stdout.write(JSON.stringify({
+        html,
+        accessibility: audit.violations.map((violation) => ({
+            rule: violation.id,
+            impact: violation.impact ?? 'minor',
+            help: violation.help,
+            help_url: violation.helpUrl ?? null,
+            targets: violation.nodes.map((node) => node.target.join(' '))
+        })),
+        performance: {
+            first_render_ms: outcome.firstRenderMs,
+            bundle_bytes: sizes.reduce((total, size) => total + size.size, 0),
+            long_tasks: outcome.longTasks
+        }
+    }));
+} finally {
+    await browser.close();
+    server.close();
+}
@@ -0,0 +1,206 @@
+//! RustSec advisory checks for generated projects.
+//!
+//! Generated components pull in real crates, and any of them may have a
+//! known vulnerability. After a successful build the compiler can run
+//! [`cargo audit`](https://github.com/rustsec/rustsec/tree/main/cargo-audit)
+//! against the project's `Cargo.lock`, which checks every locked crate
+//! against the RustSec advisory database. Depending on the
+//! [`AdvisoryPolicy`], findings are reported as warnings or fail the build.
+
+use crate::{CompilationError, Severity};
+use morpheus_core::errors::{MorpheusError, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+/// What to do when dependencies have known vulnerabilities.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AdvisoryPolicy {
+    /// Don't check.
+    #[default]
+    Off,
+
+    /// Report advisories as warnings; the build succeeds.
+    Warn,
+
+    /// Fail the build if any advisory applies.
+    Deny,
+}
+
+/// A RustSec advisory affecting a locked dependency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Advisory {
+    /// Advisory ID, e.g. `RUSTSEC-2020-0071`.
+    pub id: String,
+
+    /// Affected crate.
+    pub package: String,
+
+    /// Locked version of the crate.
+    pub version: String,
+
+    /// Short description.
+    pub title: String,
+
+    /// Version requirements that fix the issue.
+    pub patched: Vec<String>,
+}
+
+/// The parts of `cargo audit --json` output we use.
+#[derive(Deserialize)]
+struct Report {
+    vulnerabilities: Vulnerabilities,
+}
+
+#[derive(Deserialize)]
+struct Vulnerabilities {
+    #[serde(default)]
+    list: Vec<Vulnerability>,
+}
+
+#[derive(Deserialize)]
+struct Vulnerability {
+    advisory: ReportAdvisory,
+    package: ReportPackage,
+    #[serde(default)]
+    versions: ReportVersions,
+}
+
+#[derive(Deserialize)]
+struct ReportAdvisory {
+    id: String,
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct ReportPackage {
+    name: String,
+    version: String,
+}
+
+#[derive(Deserialize, Default)]
+struct ReportVersions {
+    #[serde(default)]
+    patched: Vec<String>,
+}
+
+impl Advisory {
+    /// Report the advisory as a diagnostic against `Cargo.lock`.
+    pub fn to_diagnostic(&self, severity: Severity) -> CompilationError {
+        let fix = if self.patched.is_empty() {
+            "no patched version available".to_string()
+        } else {
+            format!("upgrade to {}", self.patched.join(" or "))
+        };
+        CompilationError {
+            message: format!(
+                "{}: {} {} - {} ({})",
+                self.id, self.package, self.version, self.title, fix
+            ),
+            file: Some("Cargo.lock".to_string()),
+            line: None,
+            column: None,
+            severity,
+        }
+    }
+}
+
+/// Parse the JSON report written by `cargo audit --json`.
+pub fn parse_report(json: &str) -> Result<Vec<Advisory>> {
+    let report: Report = serde_json::from_str(json)?;
+    Ok(report
+        .vulnerabilities
+        .list
+        .into_iter()
+        .map(|v| Advisory {
+            id: v.advisory.id,
+            package: v.package.name,
+            version: v.package.version,
+            title: v.advisory.title,
+            patched: v.versions.patched,
+        })
+        .collect())
+}
+
+/// Check a project's lockfile against the RustSec database.
+///
+/// Requires `cargo-audit` (`cargo install cargo-audit`).
+pub async fn audit(project_dir: &Path) -> Result<Vec<Advisory>> {
+    let output = tokio::process::Command::new("cargo")
+        .args(["audit", "--json", "--file", "Cargo.lock"])
+        .current_dir(project_dir)
+        .output()
+        .await
+        .map_err(|e| MorpheusError::CompilationError(format!("Failed to run cargo audit: {}", e)))?;
+
+    // cargo audit exits non-zero when it finds vulnerabilities, so judge by
+    // whether it produced a report
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_report(&stdout).map_err(|_| {
+        MorpheusError::CompilationError(format!(
+            "cargo audit failed (install with: cargo install cargo-audit): {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"{
+        "database": {"advisory-count": 600},
+        "lockfile": {"dependency-count": 120},
+        "vulnerabilities": {
+            "found": true,
+            "count": 1,
+            "list": [{
+                "advisory": {
+                    "id": "RUSTSEC-2020-0071",
+                    "package": "time",
+                    "title": "Potential segfault in the time crate",
+                    "url": "https://github.com/time-rs/time/issues/293"
+                },
+                "versions": {"patched": [">=0.2.23"], "unaffected": ["=0.2.0"]},
+                "package": {"name": "time", "version": "0.1.45"}
+            }]
+        },
+        "warnings": {}
+    }"#;
+
+    #[test]
+    fn test_parse_report() {
+        let advisories = parse_report(REPORT).expect("Failed to parse");
+
+        assert_eq!(
+            advisories,
+            vec![Advisory {
+                id: "RUSTSEC-2020-0071".to_string(),
+                package: "time".to_string(),
+                version: "0.1.45".to_string(),
+                title: "Potential segfault in the time crate".to_string(),
+                patched: vec![">=0.2.23".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_clean_report() {
+        let advisories =
+            parse_report(r#"{"vulnerabilities": {"found": false, "count": 0, "list": []}}"#).unwrap();
+
+        assert!(advisories.is_empty());
+        assert!(parse_report("error: not a report").is_err());
+    }
+
+    #[test]
+    fn test_to_diagnostic() {
+        let advisory = &parse_report(REPORT).unwrap()[0];
+
+        let diagnostic = advisory.to_diagnostic(Severity::Warning);
+
+        assert_eq!(diagnostic.severity, Severity::Warning);
+        assert_eq!(diagnostic.file.as_deref(), Some("Cargo.lock"));
+        assert!(diagnostic.message.starts_with("RUSTSEC-2020-0071: time 0.1.45"));
+        assert!(diagnostic.message.contains("upgrade to >=0.2.23"));
+    }
+}
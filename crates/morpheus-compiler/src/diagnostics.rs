@@ -0,0 +1,85 @@
+//! Diagnostics without a build.
+//!
+//! A full build takes seconds and stops at the first failing step, and its
+//! errors come back as text. A review pane showing AI-generated code wants
+//! every error and warning, each at its place in the source, and soon:
+//! [`Compiler::diagnostics`](crate::Compiler::diagnostics) runs only
+//! `cargo check --message-format=json` and [`parse`] turns its messages
+//! into [`CompilationError`]s.
+
+use crate::fix::{CargoMessage, Diagnostic, SOURCE_FILE};
+use crate::{CompilationError, Severity};
+
+/// Every diagnostic rustc reported on the component's source, in order.
+///
+/// Each points at its primary span, with its error code and any help or
+/// notes attached; summaries such as "aborting due to 2 previous errors"
+/// are left out.
+pub fn parse(output: &str) -> Vec<CompilationError> {
+    let mut diagnostics = Vec::new();
+    for line in output.lines() {
+        let Ok(message) = serde_json::from_str::<CargoMessage>(line) else { continue };
+        let Some(diagnostic) = message.message.filter(|_| message.reason == "compiler-message") else {
+            continue;
+        };
+        if diagnostic.spans.is_empty() && is_summary(&diagnostic.message) {
+            continue;
+        }
+
+        let mut text = match &diagnostic.code {
+            Some(code) => format!("{}: {}", code.code, diagnostic.message),
+            None => diagnostic.message.clone(),
+        };
+        for child in &diagnostic.children {
+            text.push_str(&format!("\n{}: {}", child.level, child.message));
+        }
+        let primary = diagnostic.spans.iter().find(|s| s.is_primary && s.file_name == SOURCE_FILE);
+        diagnostics.push(CompilationError {
+            message: text,
+            file: primary.map(|s| s.file_name.clone()),
+            line: primary.map(|s| s.line_start),
+            column: primary.map(|s| s.column_start),
+            severity: severity(&diagnostic),
+        });
+    }
+    diagnostics
+}
+
+fn severity(diagnostic: &Diagnostic) -> Severity {
+    match diagnostic.level.as_str() {
+        "error" | "error: internal compiler error" => Severity::Error,
+        "warning" => Severity::Warning,
+        _ => Severity::Note,
+    }
+}
+
+/// Whether a spanless message only sums up the others.
+fn is_summary(message: &str) -> bool {
+    message.starts_with("aborting due to") || message.ends_with("emitted")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Trimmed `cargo check --message-format=json` output for a source
+    /// with a type error and an unused variable
+    const OUTPUT: &str = r#"{"reason":"compiler-artifact","package_id":"serde 1.0.0"}
+{"reason":"compiler-message","message":{"message":"mismatched types","code":{"code":"E0308"},"level":"error","spans":[{"file_name":"src/lib.rs","byte_start":40,"byte_end":45,"line_start":3,"column_start":18,"is_primary":true}],"children":[{"message":"expected `u32`, found `&str`","level":"note","children":[],"spans":[]}]}}
+{"reason":"compiler-message","message":{"message":"unused variable: `x`","code":{"code":"unused_variables"},"level":"warning","spans":[{"file_name":"src/lib.rs","byte_start":30,"byte_end":31,"line_start":2,"column_start":9,"is_primary":true}],"children":[{"message":"if this is intentional, prefix it with an underscore: `_x`","level":"help","children":[],"spans":[]}]}}
+{"reason":"compiler-message","message":{"message":"aborting due to 1 previous error; 1 warning emitted","code":null,"level":"error","spans":[],"children":[]}}
+{"reason":"build-finished","success":false}"#;
+
+    #[test]
+    fn test_every_diagnostic_is_kept_at_its_place() {
+        let diagnostics = parse(OUTPUT);
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].message, "E0308: mismatched types\nnote: expected `u32`, found `&str`");
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (Some(3), Some(18)));
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert!(diagnostics[1].message.ends_with("help: if this is intentional, prefix it with an underscore: `_x`"));
+        assert_eq!(diagnostics[1].file.as_deref(), Some("src/lib.rs"));
+    }
+}
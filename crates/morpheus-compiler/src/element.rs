@@ -0,0 +1,347 @@
+//! Custom element output.
+//!
+//! Wraps a compiled component as a standard Web Component, so it can be
+//! dropped into a page built with any framework (or none):
+//!
+//! ```html
+//! <script type="module" src="/api/versions/3/element.js?tag=morpheus-counter"></script>
+//! <morpheus-counter count="5" step="2"></morpheus-counter>
+//! ```
+//!
+//! The wrapper is a self-contained ES module with the WASM binary and JS glue
+//! inlined. Each element gets its own instance of the component and renders
+//! into its light DOM, so page styles apply. Attributes are the component's
+//! props: the prop `start_count` is the attribute `start-count`, values are
+//! parsed as JSON where they can be, and on every change the props are
+//! merged over the component's default state and handed to its
+//! `restore_state(json)` export before `render()`. Events the component
+//! emits through `morpheus.emitEvent(name, payload)` are dispatched from the
+//! element as bubbling `CustomEvent`s with the payload as `detail`.
+//!
+//! ```rust
+//! use morpheus_compiler::element::CustomElement;
+//!
+//! let element = CustomElement::new("morpheus-counter")
+//!     .unwrap()
+//!     .props(["count", "step"])
+//!     .events(["count-changed"]);
+//! assert_eq!(element.class_name(), "MorpheusCounter");
+//! assert!(element.wrap("AGFzbQEAAAA=", "export default async function init() {}").contains("customElements.define"));
+//! ```
+
+use morpheus_core::catalog::ComponentDescription;
+use morpheus_core::errors::{MorpheusError, Result};
+
+/// Prefix of the tags [`tag_for`] derives from component names.
+pub const TAG_PREFIX: &str = "morpheus-";
+
+/// Names the HTML standard reserves, though they look like custom elements.
+const RESERVED_TAGS: [&str; 8] = [
+    "annotation-xml",
+    "color-profile",
+    "font-face",
+    "font-face-src",
+    "font-face-uri",
+    "font-face-format",
+    "font-face-name",
+    "missing-glyph",
+];
+
+/// The custom element tag for a component name, e.g. `counter` becomes
+/// `morpheus-counter`.
+pub fn tag_for(component: &str) -> String {
+    let mut tag = String::from(TAG_PREFIX);
+    for c in component.chars() {
+        match c {
+            'a'..='z' | '0'..='9' | '-' | '.' => tag.push(c),
+            'A'..='Z' => tag.push(c.to_ascii_lowercase()),
+            _ => tag.push('-'),
+        }
+    }
+    tag
+}
+
+/// A component packaged as a custom element; see the [module docs](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomElement {
+    pub tag: String,
+    /// Props, exposed as attributes and properties.
+    pub props: Vec<String>,
+    /// Events the component emits, for documentation; every emitted event
+    /// is dispatched.
+    pub events: Vec<String>,
+    /// State the props are merged over.
+    pub defaults: serde_json::Value,
+}
+
+impl CustomElement {
+    /// A custom element named `tag`, which must be a valid custom element
+    /// name: lowercase, starting with a letter and containing a hyphen.
+    pub fn new(tag: impl Into<String>) -> Result<Self> {
+        let tag = tag.into();
+        let valid = tag.starts_with(|c: char| c.is_ascii_lowercase())
+            && tag.contains('-')
+            && tag
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '.' | '_'))
+            && !RESERVED_TAGS.contains(&tag.as_str());
+        if !valid {
+            return Err(MorpheusError::Other(format!(
+                "'{}' is not a valid custom element name (lowercase, starting with a letter, with a hyphen)",
+                tag
+            )));
+        }
+        Ok(Self {
+            tag,
+            props: Vec::new(),
+            events: Vec::new(),
+            defaults: serde_json::Value::Object(Default::default()),
+        })
+    }
+
+    /// A custom element for a component that described itself: its state's
+    /// fields become props, with the example state as defaults, and its
+    /// `emits` the events.
+    pub fn from_description(tag: impl Into<String>, description: &ComponentDescription) -> Result<Self> {
+        let mut element = Self::new(tag)?.events(description.emits.iter().cloned());
+        if let serde_json::Value::Object(state) = &description.state {
+            element.props = state.keys().cloned().collect();
+            element.defaults = description.state.clone();
+        }
+        Ok(element)
+    }
+
+    pub fn props<S: Into<String>>(mut self, props: impl IntoIterator<Item = S>) -> Self {
+        self.props = props.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn events<S: Into<String>>(mut self, events: impl IntoIterator<Item = S>) -> Self {
+        self.events = events.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// State the props are merged over; ignored unless a JSON object.
+    pub fn defaults(mut self, defaults: serde_json::Value) -> Self {
+        if defaults.is_object() {
+            self.defaults = defaults;
+        }
+        self
+    }
+
+    /// Name of the element's class, e.g. `MorpheusCounter`.
+    pub fn class_name(&self) -> String {
+        self.tag
+            .split(['-', '.', '_'])
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                let mut chars = part.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Attribute of a prop: lowercase, with hyphens for underscores.
+    pub fn attribute(prop: &str) -> String {
+        prop.replace('_', "-").to_ascii_lowercase()
+    }
+
+    /// The ES module defining the element, for a component compiled to
+    /// `wasm_base64` with wasm-bindgen glue `js_glue`.
+    pub fn wrap(&self, wasm_base64: &str, js_glue: &str) -> String {
+        let props: Vec<(&str, String)> = self
+            .props
+            .iter()
+            .map(|prop| (prop.as_str(), Self::attribute(prop)))
+            .collect();
+        let attributes: Vec<&str> = props.iter().map(|(_, attr)| attr.as_str()).collect();
+        let header = format!(
+            "// <{}>: a Morpheus component as a custom element.\n// Attributes: {}. Events: {}.\n",
+            self.tag,
+            if attributes.is_empty() { "none".to_string() } else { attributes.join(", ") },
+            if self.events.is_empty() { "none declared".to_string() } else { self.events.join(", ") },
+        );
+        header
+            + &WRAPPER
+                .replace("__CLASS__", &self.class_name())
+                .replace("__TAG__", &js(&self.tag))
+                .replace("__PROPS__", &js(&props))
+                .replace("__DEFAULTS__", &js(&self.defaults))
+                .replace("__WASM__", &js(wasm_base64))
+                // Last, so placeholders in the glue are left alone
+                .replace("__GLUE__", &js(js_glue))
+    }
+}
+
+/// A value as a JavaScript literal. JSON is valid JavaScript; `<` is escaped
+/// so the module can also be inlined in a `<script>` element.
+fn js<T: serde::Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default().replace('<', "\\u003c")
+}
+
+/// The wrapper module; `__NAME__` placeholders are filled in by
+/// [`CustomElement::wrap`].
+const WRAPPER: &str = r#"const TAG = __TAG__;
+const PROPS = __PROPS__;
+const DEFAULTS = __DEFAULTS__;
+const GLUE = __GLUE__;
+const compiled = WebAssembly.compile(Uint8Array.from(atob(__WASM__), c => c.charCodeAt(0)));
+
+// Host imports are global, so calls into a component record which element
+// is active; events emitted outside those calls have no element to go to
+const host = (globalThis.__morpheusElements ??= { active: null });
+globalThis.morpheus ??= {
+    emitEvent(name, payload) {
+        host.active?.dispatchEvent(new CustomEvent(name, { detail: JSON.parse(payload || 'null'), bubbles: true, composed: true }));
+    },
+    setState(state) {
+        host.active?.dispatchEvent(new CustomEvent('morpheus-state', { detail: JSON.parse(state || 'null'), bubbles: true, composed: true }));
+    }
+};
+
+function parse(value) {
+    if (value === null) return undefined;
+    try {
+        return JSON.parse(value);
+    } catch {
+        return value;
+    }
+}
+
+class __CLASS__ extends HTMLElement {
+    static observedAttributes = PROPS.map(([, attribute]) => attribute);
+    #module = null;
+    #loading = null;
+
+    connectedCallback() {
+        this.#loading ??= this.#load();
+        this.#loading.then(() => this.#update(), (error) => console.error(`<${TAG}> failed to load:`, error));
+    }
+
+    attributeChangedCallback() {
+        if (this.#module) this.#update();
+    }
+
+    // Each element imports its own copy of the glue, and so gets its own
+    // instance of the component
+    async #load() {
+        const url = URL.createObjectURL(new Blob([GLUE], { type: 'application/javascript' }));
+        try {
+            const module = await import(url);
+            await module.default(await compiled);
+            this.#module = module;
+        } finally {
+            URL.revokeObjectURL(url);
+        }
+    }
+
+    // Current props, from the attributes
+    get props() {
+        const props = {};
+        for (const [prop, attribute] of PROPS) {
+            const value = parse(this.getAttribute(attribute));
+            if (value !== undefined) props[prop] = value;
+        }
+        return props;
+    }
+
+    // Call one of the component's exports, then re-render
+    call(name, ...args) {
+        const fn = this.#module?.[name];
+        if (typeof fn !== 'function') throw new Error(`<${TAG}> has no export ${name}`);
+        const result = this.#run(() => fn(...args));
+        this.#render();
+        return result;
+    }
+
+    #run(fn) {
+        const previous = host.active;
+        host.active = this;
+        try {
+            return fn();
+        } finally {
+            host.active = previous;
+        }
+    }
+
+    #update() {
+        if (typeof this.#module.restore_state === 'function') {
+            const state = JSON.stringify({ ...DEFAULTS, ...this.props });
+            this.#run(() => this.#module.restore_state(state));
+        }
+        this.#render();
+    }
+
+    #render() {
+        if (typeof this.#module?.render === 'function') {
+            this.innerHTML = this.#run(() => this.#module.render());
+        }
+    }
+}
+
+// Props are also properties: element.count = 3 sets the count attribute
+for (const [prop, attribute] of PROPS) {
+    Object.defineProperty(__CLASS__.prototype, prop, {
+        get() {
+            return parse(this.getAttribute(attribute));
+        },
+        set(value) {
+            this.setAttribute(attribute, typeof value === 'string' ? value : JSON.stringify(value));
+        }
+    });
+}
+
+if (!customElements.get(TAG)) customElements.define(TAG, __CLASS__);
+export default __CLASS__;
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_validation() {
+        assert!(CustomElement::new("morpheus-counter").is_ok());
+        assert!(CustomElement::new("x-1.2_b").is_ok());
+        for bad in ["counter", "Morpheus-Counter", "1-counter", "font-face", "my counter-x"] {
+            assert!(CustomElement::new(bad).is_err(), "{}", bad);
+        }
+        assert_eq!(tag_for("Sales Chart"), "morpheus-sales-chart");
+        assert!(CustomElement::new(tag_for("main")).is_ok());
+    }
+
+    #[test]
+    fn test_from_description() {
+        let description = ComponentDescription::from_json(
+            r#"{"exports": ["render"], "emits": ["count-changed"], "state": {"count": 0, "start_step": 1}}"#,
+        )
+        .unwrap();
+
+        let element = CustomElement::from_description("morpheus-counter", &description).unwrap();
+
+        assert_eq!(element.props, ["count", "start_step"]);
+        assert_eq!(element.events, ["count-changed"]);
+        assert_eq!(element.defaults["start_step"], 1);
+        assert_eq!(CustomElement::attribute("start_step"), "start-step");
+    }
+
+    #[test]
+    fn test_wrapper_module() {
+        let element = CustomElement::new("morpheus-counter")
+            .unwrap()
+            .props(["count", "start_step"])
+            .defaults(serde_json::json!({ "count": 0, "start_step": 1 }));
+
+        let module = element.wrap("AGFzbQEAAAA=", "export default async function init() {} // </script>");
+
+        assert!(module.contains("class MorpheusCounter extends HTMLElement"));
+        assert!(module.contains(r#"const PROPS = [["count","count"],["start_step","start-step"]];"#));
+        assert!(module.contains(r#"const DEFAULTS = {"count":0,"start_step":1};"#));
+        assert!(module.contains(r#"const TAG = "morpheus-counter";"#));
+        assert!(module.contains("customElements.define(TAG, MorpheusCounter)"));
+        assert!(!module.contains("</script>"));
+    }
+}
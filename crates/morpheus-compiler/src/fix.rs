@@ -0,0 +1,248 @@
+//! Mechanical pre-compile fixes.
+//!
+//! AI output often fails on trivia: an unused import, a missing `use` for a
+//! standard type. rustc already knows the fix, and spending an AI round-trip
+//! on it is slow and costs tokens. Before building, the compiler can run
+//! `cargo check` and apply rustc's suggestions itself, like `cargo fix`
+//! does, but also for code that doesn't compile yet.
+//!
+//! Only suggestions rustc marks machine-applicable are taken, plus imports
+//! when rustc names exactly one candidate.
+
+use morpheus_core::errors::{MorpheusError, Result};
+use serde::Deserialize;
+use std::path::Path;
+use tokio::fs;
+
+/// File the generated project's source lives in.
+pub(crate) const SOURCE_FILE: &str = "src/lib.rs";
+
+/// Check-and-fix rounds; fixing one problem can reveal another.
+pub const MAX_PASSES: usize = 3;
+
+/// A suggestion from rustc: edits that must be applied together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    /// What the fix does, e.g. "unused import: `std::fmt`: remove the whole `use` item".
+    pub description: String,
+
+    /// Byte ranges of the source and their replacements.
+    pub edits: Vec<Edit>,
+}
+
+/// Replace `start..end` of the source with `replacement`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    /// Byte offset of the start of the range.
+    pub start: usize,
+
+    /// Byte offset just past the range; equal to `start` for insertions.
+    pub end: usize,
+
+    /// Text to put in place of the range.
+    pub replacement: String,
+}
+
+/// The parts of `cargo check --message-format=json` output we use (see
+/// also [`crate::diagnostics`]).
+#[derive(Deserialize)]
+pub(crate) struct CargoMessage {
+    pub reason: String,
+    pub message: Option<Diagnostic>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Diagnostic {
+    pub message: String,
+    /// `error`, `warning`, `note`, `help`, ...
+    #[serde(default)]
+    pub level: String,
+    #[serde(default)]
+    pub code: Option<DiagnosticCode>,
+    #[serde(default)]
+    pub children: Vec<Diagnostic>,
+    #[serde(default)]
+    pub spans: Vec<Span>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct DiagnosticCode {
+    /// e.g. `E0308` or `unused_variables`
+    pub code: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Span {
+    pub file_name: String,
+    pub byte_start: usize,
+    pub byte_end: usize,
+    #[serde(default)]
+    pub line_start: usize,
+    #[serde(default)]
+    pub column_start: usize,
+    #[serde(default)]
+    pub is_primary: bool,
+    pub suggested_replacement: Option<String>,
+    pub suggestion_applicability: Option<String>,
+}
+
+/// Collect the fixes we trust from `cargo check --message-format=json` output.
+pub fn suggestions(output: &str) -> Vec<Fix> {
+    let mut fixes: Vec<Fix> = Vec::new();
+    for line in output.lines() {
+        let Ok(message) = serde_json::from_str::<CargoMessage>(line) else { continue };
+        let Some(diagnostic) = message.message.filter(|_| message.reason == "compiler-message") else {
+            continue;
+        };
+        for child in &diagnostic.children {
+            let Some(edits) = trusted_edits(child) else { continue };
+            let fix = Fix {
+                description: format!("{}: {}", diagnostic.message, child.message),
+                edits,
+            };
+            // rustc repeats a missing import for every use of the name
+            if !fixes.iter().any(|f| f.edits == fix.edits) {
+                fixes.push(fix);
+            }
+        }
+    }
+    fixes
+}
+
+/// The edits of a suggestion, if it's one we apply without asking.
+fn trusted_edits(suggestion: &Diagnostic) -> Option<Vec<Edit>> {
+    let spans: Vec<&Span> = suggestion.spans.iter().filter(|s| s.suggested_replacement.is_some()).collect();
+    if spans.is_empty() || spans.iter().any(|s| s.file_name != SOURCE_FILE) {
+        return None;
+    }
+    let applicable = spans.iter().all(|s| s.suggestion_applicability.as_deref() == Some("MachineApplicable"));
+    // rustc says "consider importing one of these items" when there are several
+    let single_import = suggestion.message.starts_with("consider importing this ")
+        && spans.len() == 1
+        && spans[0].byte_start == spans[0].byte_end
+        && spans[0].suggested_replacement.as_deref().is_some_and(|r| r.starts_with("use "));
+    if !applicable && !single_import {
+        return None;
+    }
+    Some(
+        spans
+            .into_iter()
+            .map(|s| Edit {
+                start: s.byte_start,
+                end: s.byte_end,
+                replacement: s.suggested_replacement.clone().unwrap_or_default(),
+            })
+            .collect(),
+    )
+}
+
+/// Apply `fixes` to `source`, returning the new source and the descriptions
+/// of the fixes applied.
+///
+/// Fixes that overlap an earlier one or don't line up with the source are
+/// skipped.
+pub fn apply(source: &str, fixes: &[Fix]) -> (String, Vec<String>) {
+    let overlaps = |a: &Edit, b: &Edit| a.start < b.end && b.start < a.end;
+
+    let mut accepted: Vec<&Edit> = Vec::new();
+    let mut applied = Vec::new();
+    for fix in fixes {
+        let valid = fix.edits.iter().all(|e| {
+            e.start <= e.end
+                && e.end <= source.len()
+                && source.is_char_boundary(e.start)
+                && source.is_char_boundary(e.end)
+        });
+        if !valid || fix.edits.iter().any(|e| accepted.iter().any(|a| overlaps(a, e))) {
+            continue;
+        }
+        accepted.extend(&fix.edits);
+        applied.push(fix.description.clone());
+    }
+
+    // Apply from the end so earlier offsets stay valid
+    accepted.sort_by_key(|e| std::cmp::Reverse((e.start, e.end)));
+    let mut fixed = source.to_string();
+    for edit in accepted {
+        fixed.replace_range(edit.start..edit.end, &edit.replacement);
+    }
+    (fixed, applied)
+}
+
+/// Check the project and apply rustc's fixes to its source in place,
+/// returning the descriptions of the fixes applied.
+pub async fn autofix(project_dir: &Path) -> Result<Vec<String>> {
+    let source_path = project_dir.join(SOURCE_FILE);
+    let mut applied = Vec::new();
+    for _ in 0..MAX_PASSES {
+        let output = tokio::process::Command::new("cargo")
+            .args(["check", "--message-format=json", "--target", "wasm32-unknown-unknown"])
+            .current_dir(project_dir)
+            .output()
+            .await
+            .map_err(|e| MorpheusError::CompilationError(format!("Failed to run cargo check: {}", e)))?;
+        let fixes = suggestions(&String::from_utf8_lossy(&output.stdout));
+        if fixes.is_empty() {
+            break;
+        }
+
+        let source = fs::read_to_string(&source_path)
+            .await
+            .map_err(|e| MorpheusError::CompilationError(format!("Failed to read source: {}", e)))?;
+        let (fixed, descriptions) = apply(&source, &fixes);
+        if descriptions.is_empty() {
+            break;
+        }
+        fs::write(&source_path, fixed)
+            .await
+            .map_err(|e| MorpheusError::CompilationError(format!("Failed to write source: {}", e)))?;
+        applied.extend(descriptions);
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "use std::fmt;\npub fn f() -> usize {\n    let m: HashMap<u32, u32> = HashMap::new();\n    m.len()\n}\n";
+
+    /// Trimmed `cargo check --message-format=json` output for [`SOURCE`]
+    const OUTPUT: &str = r#"{"reason":"compiler-artifact","package_id":"serde 1.0.0"}
+{"reason":"compiler-message","message":{"message":"cannot find type `HashMap` in this scope","level":"error","spans":[],"children":[{"message":"consider importing this struct","level":"help","children":[],"spans":[{"file_name":"src/lib.rs","byte_start":0,"byte_end":0,"suggested_replacement":"use std::collections::HashMap;\n","suggestion_applicability":"MaybeIncorrect"}]}]}}
+{"reason":"compiler-message","message":{"message":"failed to resolve: use of undeclared type `HashMap`","level":"error","spans":[],"children":[{"message":"consider importing this struct","level":"help","children":[],"spans":[{"file_name":"src/lib.rs","byte_start":0,"byte_end":0,"suggested_replacement":"use std::collections::HashMap;\n","suggestion_applicability":"MaybeIncorrect"}]}]}}
+{"reason":"compiler-message","message":{"message":"unused import: `std::fmt`","level":"warning","spans":[],"children":[{"message":"`#[warn(unused_imports)]` on by default","level":"note","children":[],"spans":[]},{"message":"remove the whole `use` item","level":"help","children":[],"spans":[{"file_name":"src/lib.rs","byte_start":0,"byte_end":14,"suggested_replacement":"","suggestion_applicability":"MachineApplicable"}]}]}}
+{"reason":"build-finished","success":false}"#;
+
+    #[test]
+    fn test_suggestions_are_deduplicated() {
+        let fixes = suggestions(OUTPUT);
+
+        assert_eq!(fixes.len(), 2);
+        assert_eq!(fixes[0].description, "cannot find type `HashMap` in this scope: consider importing this struct");
+        assert_eq!(fixes[1].edits, [Edit { start: 0, end: 14, replacement: String::new() }]);
+    }
+
+    #[test]
+    fn test_apply() {
+        let (fixed, applied) = apply(SOURCE, &suggestions(OUTPUT));
+
+        assert_eq!(applied.len(), 2);
+        assert!(fixed.starts_with("use std::collections::HashMap;\npub fn f()"));
+        assert!(!fixed.contains("std::fmt"));
+    }
+
+    #[test]
+    fn test_untrusted_and_overlapping_fixes_are_skipped() {
+        let ambiguous = r#"{"reason":"compiler-message","message":{"message":"cannot find type `Rc`","spans":[],"children":[{"message":"consider importing one of these items","spans":[{"file_name":"src/lib.rs","byte_start":0,"byte_end":0,"suggested_replacement":"use std::rc::Rc;\n","suggestion_applicability":"MaybeIncorrect"},{"file_name":"src/lib.rs","byte_start":0,"byte_end":0,"suggested_replacement":"use alloc::rc::Rc;\n","suggestion_applicability":"MaybeIncorrect"}]}]}}"#;
+        assert!(suggestions(ambiguous).is_empty());
+
+        let edit = |start, end| Fix {
+            description: format!("{}..{}", start, end),
+            edits: vec![Edit { start, end, replacement: "x".to_string() }],
+        };
+        let (fixed, applied) = apply("abcdef", &[edit(1, 3), edit(2, 4), edit(4, 5), edit(5, 99)]);
+        assert_eq!(applied, ["1..3", "4..5"]);
+        assert_eq!(fixed, "axdxf");
+    }
+}
@@ -0,0 +1,135 @@
+//! Property-based fuzzing of the code that reads untrusted input.
+//!
+//! rustc's stderr and the modules we build (or are handed back) are parsed
+//! on every request, so malformed compiler output or a hostile module must
+//! produce an error, never a panic. Properties here generate rustc-like
+//! output and mutated modules; inputs that once broke something are kept
+//! under `testdata/corpus/` and replayed as regression tests.
+
+use crate::size::SizeBreakdown;
+use crate::snapshot::check_snapshottable;
+use crate::subprocess::SubprocessCompiler;
+use crate::transform::SizeReport;
+use proptest::prelude::*;
+use std::path::PathBuf;
+
+/// A small valid module the mutation strategies start from.
+const SEED_MODULE: &str = r#"
+    (module
+        (import "wasi_snapshot_preview1" "proc_exit" (func $exit (param i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 0) "hello")
+        (func $init (export "__morpheus_init"))
+        (func $render (export "render") (result i32) i32.const 42)
+    )
+"#;
+
+fn seed_module() -> Vec<u8> {
+    wat::parse_str(SEED_MODULE).unwrap()
+}
+
+/// Lines that look like rustc output, with the separators the parser
+/// splits on in awkward places.
+fn rustc_line() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just("error[E0308]: mismatched types".to_string()),
+        Just("error: could not compile `morpheus-component`".to_string()),
+        Just("warning: unused import: `std::fmt`".to_string()),
+        Just("  --> src/lib.rs:5:9".to_string()),
+        Just("   = help: consider importing this struct".to_string()),
+        Just("note: expected `u32`, found `&str`".to_string()),
+        "[ -~]{0,40}".prop_map(|s| format!("  --> {}", s)),
+        "[\\[\\]: a-z0-9E]{0,24}".prop_map(|s| format!("error{}", s)),
+        "[\\[\\]:]{0,6}error[\\[\\]:]{0,6}".prop_map(|s| s.to_string()),
+        any::<String>(),
+    ]
+}
+
+fn rustc_output() -> impl Strategy<Value = String> {
+    prop::collection::vec(rustc_line(), 0..16).prop_map(|lines| lines.join("\n"))
+}
+
+/// Modules: random bytes, random bytes after a valid header, and the seed
+/// module truncated and with bytes overwritten.
+fn module_bytes() -> impl Strategy<Value = Vec<u8>> {
+    let mutated = (
+        prop::collection::vec((any::<prop::sample::Index>(), any::<u8>()), 0..8),
+        any::<prop::sample::Index>(),
+    )
+        .prop_map(|(edits, cut)| {
+            let mut wasm = seed_module();
+            for (index, byte) in edits {
+                let i = index.index(wasm.len());
+                wasm[i] = byte;
+            }
+            wasm.truncate(cut.index(wasm.len() + 1));
+            wasm
+        });
+    prop_oneof![
+        prop::collection::vec(any::<u8>(), 0..128),
+        prop::collection::vec(any::<u8>(), 0..128).prop_map(|tail| [b"\0asm\x01\0\0\0".to_vec(), tail].concat()),
+        mutated,
+    ]
+}
+
+/// Run everything that inspects a module.
+fn inspect_module(wasm: &[u8]) {
+    let breakdown = SizeBreakdown::analyze(wasm);
+    assert_eq!(breakdown.total, wasm.len());
+    let sections: usize = breakdown.sections.iter().map(|(_, size)| size).sum();
+    assert!(sections <= wasm.len());
+    let _ = breakdown.render(3);
+    let _ = check_snapshottable(wasm);
+}
+
+fn corpus(kind: &str) -> Vec<(PathBuf, Vec<u8>)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/corpus").join(kind);
+    let mut entries: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("{}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    entries.into_iter().map(|path| (path.clone(), std::fs::read(path).unwrap())).collect()
+}
+
+proptest! {
+    #[test]
+    fn prop_parse_errors_never_panics(stderr in rustc_output()) {
+        let errors = SubprocessCompiler::parse_errors(&stderr);
+        // Unrecognized output still produces an error to show
+        prop_assert!(!errors.is_empty());
+    }
+
+    #[test]
+    fn prop_module_inspection_never_panics(wasm in module_bytes()) {
+        inspect_module(&wasm);
+    }
+}
+
+#[test]
+fn test_stderr_corpus() {
+    let cases = corpus("stderr");
+    assert!(!cases.is_empty());
+    for (path, bytes) in cases {
+        let errors = SubprocessCompiler::parse_errors(&String::from_utf8_lossy(&bytes));
+        assert!(!errors.is_empty(), "{}", path.display());
+    }
+}
+
+#[test]
+fn test_wasm_corpus() {
+    let cases = corpus("wasm");
+    assert!(!cases.is_empty());
+    for (_, wasm) in cases {
+        inspect_module(&wasm);
+    }
+}
+
+#[test]
+fn test_seed_module_is_understood() {
+    let wasm = seed_module();
+
+    assert!(check_snapshottable(&wasm).is_ok());
+    assert_eq!(SizeBreakdown::analyze(&wasm).functions.len(), 2);
+    assert!(SizeReport::sections(&wasm).iter().any(|(name, _)| name == "data"));
+}
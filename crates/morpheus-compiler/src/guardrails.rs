@@ -0,0 +1,434 @@
+//! Budget guardrails for AI modifications.
+//!
+//! A change can compile and still be a bad idea: a module that doubles in
+//! size, a "small tweak" that rewrites the whole file, a component that
+//! suddenly wants network access, or a function too tangled to review.
+//! Guardrails measure each change against configurable limits before it is
+//! activated; violations don't block compilation, but require an explicit
+//! override.
+//!
+//! Complexity is McCabe cyclomatic complexity computed from the `syn` AST:
+//! one plus the number of branch points (`if`, `match` arms beyond the first,
+//! loops, `&&`, `||` and `?`) in each function.
+
+use morpheus_core::permissions::Permissions;
+use std::fmt;
+use syn::visit::{self, Visit};
+
+/// Limits a change must stay within.
+///
+/// Every limit is optional; the default enforces nothing.
+#[derive(Debug, Clone, Default)]
+pub struct Guardrails {
+    /// Maximum growth of the WASM module, in bytes.
+    pub max_wasm_growth: Option<usize>,
+
+    /// Maximum number of source lines added plus removed.
+    pub max_lines_changed: Option<usize>,
+
+    /// Maximum cyclomatic complexity of any function.
+    pub max_complexity: Option<usize>,
+
+    /// Capabilities a change may not newly acquire (see
+    /// [`Permissions::capabilities`]). An entry also bans everything under it,
+    /// so `network` bans `network:*` and `network:example.com`.
+    pub banned_escalations: Vec<String>,
+}
+
+/// A proposed change, compared with the version it replaces.
+#[derive(Debug, Clone)]
+pub struct Change<'a> {
+    /// Source being replaced (`None` for a first version).
+    pub old_source: Option<&'a str>,
+
+    /// New source.
+    pub new_source: &'a str,
+
+    /// Size of the module being replaced.
+    pub old_wasm_size: Option<usize>,
+
+    /// Size of the new module.
+    pub new_wasm_size: usize,
+
+    /// Permissions of the version being replaced.
+    pub old_permissions: Option<&'a Permissions>,
+
+    /// Permissions the new version requests.
+    pub new_permissions: &'a Permissions,
+}
+
+/// A guardrail the change exceeds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// The module grew by more than allowed.
+    WasmGrowth { growth: usize, limit: usize },
+
+    /// Too many lines changed.
+    LinesChanged { changed: usize, limit: usize },
+
+    /// A function is too complex.
+    Complexity {
+        function: String,
+        complexity: usize,
+        limit: usize,
+    },
+
+    /// The change acquires a banned capability.
+    PermissionEscalation { capability: String },
+
+    /// The source couldn't be parsed for analysis.
+    Unanalyzable(String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::WasmGrowth { growth, limit } => {
+                write!(f, "WASM grew by {} bytes (limit {})", growth, limit)
+            }
+            Violation::LinesChanged { changed, limit } => {
+                write!(f, "{} lines changed (limit {})", changed, limit)
+            }
+            Violation::Complexity {
+                function,
+                complexity,
+                limit,
+            } => write!(
+                f,
+                "{}() has cyclomatic complexity {} (limit {})",
+                function, complexity, limit
+            ),
+            Violation::PermissionEscalation { capability } => {
+                write!(f, "acquires banned permission '{}'", capability)
+            }
+            Violation::Unanalyzable(reason) => write!(f, "could not analyze source: {}", reason),
+        }
+    }
+}
+
+/// Cyclomatic complexity of one function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionComplexity {
+    /// Function name (methods are `Type::method`).
+    pub name: String,
+
+    /// McCabe cyclomatic complexity.
+    pub complexity: usize,
+}
+
+impl Guardrails {
+    /// Limit WASM growth per change.
+    pub fn with_max_wasm_growth(mut self, bytes: usize) -> Self {
+        self.max_wasm_growth = Some(bytes);
+        self
+    }
+
+    /// Limit lines changed per change.
+    pub fn with_max_lines_changed(mut self, lines: usize) -> Self {
+        self.max_lines_changed = Some(lines);
+        self
+    }
+
+    /// Limit per-function cyclomatic complexity.
+    pub fn with_max_complexity(mut self, complexity: usize) -> Self {
+        self.max_complexity = Some(complexity);
+        self
+    }
+
+    /// Ban newly acquiring a capability.
+    pub fn with_banned_escalation(mut self, capability: impl Into<String>) -> Self {
+        self.banned_escalations.push(capability.into());
+        self
+    }
+
+    /// Check a change against every configured limit.
+    pub fn evaluate(&self, change: &Change<'_>) -> Vec<Violation> {
+        let mut violations = Vec::new();
+
+        if let (Some(limit), Some(old_size)) = (self.max_wasm_growth, change.old_wasm_size) {
+            let growth = change.new_wasm_size.saturating_sub(old_size);
+            if growth > limit {
+                violations.push(Violation::WasmGrowth { growth, limit });
+            }
+        }
+
+        if let (Some(limit), Some(old_source)) = (self.max_lines_changed, change.old_source) {
+            let changed = lines_changed(old_source, change.new_source);
+            if changed > limit {
+                violations.push(Violation::LinesChanged { changed, limit });
+            }
+        }
+
+        if let Some(limit) = self.max_complexity {
+            match cyclomatic_complexity(change.new_source) {
+                Ok(functions) => violations.extend(
+                    functions
+                        .into_iter()
+                        .filter(|f| f.complexity > limit)
+                        .map(|f| Violation::Complexity {
+                            function: f.name,
+                            complexity: f.complexity,
+                            limit,
+                        }),
+                ),
+                Err(e) => violations.push(Violation::Unanalyzable(e.to_string())),
+            }
+        }
+
+        let old_caps = change
+            .old_permissions
+            .map(Permissions::capabilities)
+            .unwrap_or_default();
+        for capability in change.new_permissions.capabilities() {
+            let banned = self.banned_escalations.iter().any(|ban| {
+                capability == *ban || capability.starts_with(&format!("{}:", ban))
+            });
+            if banned && !old_caps.contains(&capability) {
+                violations.push(Violation::PermissionEscalation { capability });
+            }
+        }
+
+        violations
+    }
+}
+
+/// Number of lines added plus removed between two sources.
+pub fn lines_changed(old: &str, new: &str) -> usize {
+    similar::TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .filter(|change| change.tag() != similar::ChangeTag::Equal)
+        .count()
+}
+
+/// Cyclomatic complexity of every function and method in `source`.
+pub fn cyclomatic_complexity(source: &str) -> Result<Vec<FunctionComplexity>, syn::Error> {
+    let file = syn::parse_file(source)?;
+    let mut visitor = ComplexityVisitor::default();
+    visitor.visit_file(&file);
+    Ok(visitor.functions)
+}
+
+#[derive(Default)]
+struct ComplexityVisitor {
+    functions: Vec<FunctionComplexity>,
+    /// Type of the impl block being visited, for method names.
+    impl_type: Option<String>,
+    /// Branch count of the function being visited.
+    branches: usize,
+}
+
+impl ComplexityVisitor {
+    fn measure(&mut self, name: String, visit_body: impl FnOnce(&mut Self)) {
+        let outer = std::mem::take(&mut self.branches);
+        visit_body(self);
+        self.functions.push(FunctionComplexity {
+            name,
+            complexity: self.branches + 1,
+        });
+        self.branches = outer;
+    }
+}
+
+impl<'ast> Visit<'ast> for ComplexityVisitor {
+    fn visit_item_fn(&mut self, item: &'ast syn::ItemFn) {
+        self.measure(item.sig.ident.to_string(), |v| visit::visit_item_fn(v, item));
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast syn::ItemImpl) {
+        let ty = &item.self_ty;
+        let outer = self.impl_type.replace(quote::quote!(#ty).to_string().replace(' ', ""));
+        visit::visit_item_impl(self, item);
+        self.impl_type = outer;
+    }
+
+    fn visit_impl_item_fn(&mut self, item: &'ast syn::ImplItemFn) {
+        let name = match &self.impl_type {
+            Some(ty) => format!("{}::{}", ty, item.sig.ident),
+            None => item.sig.ident.to_string(),
+        };
+        self.measure(name, |v| visit::visit_impl_item_fn(v, item));
+    }
+
+    fn visit_expr_if(&mut self, expr: &'ast syn::ExprIf) {
+        self.branches += 1;
+        visit::visit_expr_if(self, expr);
+    }
+
+    fn visit_expr_match(&mut self, expr: &'ast syn::ExprMatch) {
+        self.branches += expr.arms.len().saturating_sub(1);
+        visit::visit_expr_match(self, expr);
+    }
+
+    fn visit_expr_while(&mut self, expr: &'ast syn::ExprWhile) {
+        self.branches += 1;
+        visit::visit_expr_while(self, expr);
+    }
+
+    fn visit_expr_for_loop(&mut self, expr: &'ast syn::ExprForLoop) {
+        self.branches += 1;
+        visit::visit_expr_for_loop(self, expr);
+    }
+
+    fn visit_expr_loop(&mut self, expr: &'ast syn::ExprLoop) {
+        self.branches += 1;
+        visit::visit_expr_loop(self, expr);
+    }
+
+    fn visit_expr_try(&mut self, expr: &'ast syn::ExprTry) {
+        self.branches += 1;
+        visit::visit_expr_try(self, expr);
+    }
+
+    fn visit_expr_binary(&mut self, expr: &'ast syn::ExprBinary) {
+        if matches!(expr.op, syn::BinOp::And(_) | syn::BinOp::Or(_)) {
+            self.branches += 1;
+        }
+        visit::visit_expr_binary(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morpheus_core::permissions::NetworkPermissions;
+
+    const SIMPLE: &str = r#"
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub fn render() -> String {
+    "<p>Hello</p>".to_string()
+}
+"#;
+
+    const BRANCHY: &str = r#"
+pub fn classify(n: i32, flag: bool) -> &'static str {
+    if n < 0 && flag {
+        "negative"
+    } else if n == 0 {
+        "zero"
+    } else {
+        match n {
+            1 => "one",
+            2 => "two",
+            _ => "many",
+        }
+    }
+}
+
+struct Counter { count: u32 }
+
+impl Counter {
+    fn tick(&mut self) {
+        for _ in 0..3 {
+            self.count += 1;
+        }
+    }
+}
+"#;
+
+    fn change<'a>(old: &'a str, new: &'a str, permissions: &'a Permissions) -> Change<'a> {
+        Change {
+            old_source: Some(old),
+            new_source: new,
+            old_wasm_size: Some(10_000),
+            new_wasm_size: 10_500,
+            old_permissions: Some(permissions),
+            new_permissions: permissions,
+        }
+    }
+
+    #[test]
+    fn test_complexity() {
+        let functions = cyclomatic_complexity(BRANCHY).expect("Failed to parse");
+
+        assert_eq!(
+            functions,
+            vec![
+                // 1 + if + && + else if + 2 extra match arms
+                FunctionComplexity {
+                    name: "classify".to_string(),
+                    complexity: 6
+                },
+                FunctionComplexity {
+                    name: "Counter::tick".to_string(),
+                    complexity: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_default_guardrails_allow_everything() {
+        let perms = Permissions::default();
+        assert!(Guardrails::default().evaluate(&change(SIMPLE, BRANCHY, &perms)).is_empty());
+    }
+
+    #[test]
+    fn test_limits() {
+        let perms = Permissions::default();
+        let guardrails = Guardrails::default()
+            .with_max_wasm_growth(100)
+            .with_max_lines_changed(5)
+            .with_max_complexity(4);
+
+        let violations = guardrails.evaluate(&change(SIMPLE, BRANCHY, &perms));
+
+        assert!(violations.contains(&Violation::WasmGrowth { growth: 500, limit: 100 }));
+        assert!(violations.iter().any(|v| matches!(v, Violation::LinesChanged { limit: 5, .. })));
+        assert!(violations.iter().any(|v| matches!(
+            v,
+            Violation::Complexity { function, complexity: 6, .. } if function == "classify"
+        )));
+        assert_eq!(violations.len(), 3);
+    }
+
+    #[test]
+    fn test_first_version_skips_relative_limits() {
+        let perms = Permissions::default();
+        let guardrails = Guardrails::default().with_max_wasm_growth(0).with_max_lines_changed(0);
+        let first = Change {
+            old_source: None,
+            old_wasm_size: None,
+            old_permissions: None,
+            ..change(SIMPLE, BRANCHY, &perms)
+        };
+
+        assert!(guardrails.evaluate(&first).is_empty());
+    }
+
+    #[test]
+    fn test_banned_escalation() {
+        let old = Permissions::default();
+        let new = Permissions {
+            network: NetworkPermissions::AllowList(vec!["evil.example.com".to_string()]),
+            ..Permissions::default()
+        };
+        let guardrails = Guardrails::default().with_banned_escalation("network");
+
+        let violations = guardrails.evaluate(&Change {
+            old_permissions: Some(&old),
+            new_permissions: &new,
+            ..change(SIMPLE, SIMPLE, &old)
+        });
+        assert_eq!(
+            violations,
+            vec![Violation::PermissionEscalation {
+                capability: "network:evil.example.com".to_string()
+            }]
+        );
+
+        // Keeping an existing grant is not an escalation
+        assert!(guardrails.evaluate(&change(SIMPLE, SIMPLE, &new)).is_empty());
+    }
+
+    #[test]
+    fn test_unparseable_source() {
+        let perms = Permissions::default();
+        let guardrails = Guardrails::default().with_max_complexity(10);
+
+        let violations = guardrails.evaluate(&change(SIMPLE, "fn broken( {", &perms));
+
+        assert!(matches!(violations.as_slice(), [Violation::Unanalyzable(_)]));
+    }
+}
@@ -0,0 +1,237 @@
+//! Crates of the host application's own.
+//!
+//! Generated code can only use the crates in the generated project's
+//! manifest, so components kept redefining the app's domain types (and
+//! getting a field or two wrong) instead of using the real ones. A
+//! [`HostCrate`] adds one of the app's own crates, such as its types or its
+//! API client, to the manifest from a local path or a git repository, with a
+//! description the AI sees alongside the other available crates (see
+//! [`SubprocessCompiler::with_host_crates`](crate::SubprocessCompiler::with_host_crates)).
+//!
+//! Host crates are usually listed in a TOML file using Cargo's dependency
+//! syntax plus a `description`:
+//!
+//! ```rust
+//! use morpheus_compiler::host_crate::{CrateSource, HostCrate};
+//!
+//! let crates = HostCrate::parse_all(
+//!     r#"
+//!     [shop-types]
+//!     path = "types"
+//!     description = "Order, Customer and Invoice as the shop's API returns them"
+//!
+//!     [shop-client]
+//!     git = "https://github.com/acme/shop-client"
+//!     rev = "v1.2.0"
+//!     "#,
+//!     "/srv/shop".as_ref(),
+//! )
+//! .unwrap();
+//!
+//! // Crates come back sorted by name
+//! assert_eq!(crates[0].manifest_entry()["rev"].as_str(), Some("v1.2.0"));
+//! assert_eq!(crates[1].source, CrateSource::Path("/srv/shop/types".into()));
+//! ```
+//!
+//! Host types can also be shared one by one rather than as a crate:
+//! [`HostCrate::shared_types`] writes the
+//! [`SharedTypes`](morpheus_core::shared::SharedTypes) the host selected as
+//! the `morpheus_types` crate.
+
+use morpheus_core::errors::{MorpheusError, Result};
+use morpheus_core::shared::{self, SharedTypes};
+use std::path::{Path, PathBuf};
+
+/// Where a host crate comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CrateSource {
+    /// A directory on the build machine. Projects are built in a temporary
+    /// directory, so the path should be absolute.
+    Path(PathBuf),
+
+    /// A git repository, at `rev` (a commit, tag or branch) if given.
+    Git { url: String, rev: Option<String> },
+}
+
+/// A crate of the host application's, available to generated code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostCrate {
+    /// Crate name, as generated code depends on it.
+    pub name: String,
+
+    /// Where the crate comes from.
+    pub source: CrateSource,
+
+    /// Features to enable.
+    pub features: Vec<String>,
+
+    /// What the crate offers, for the AI's context.
+    pub description: Option<String>,
+}
+
+impl HostCrate {
+    /// A crate in a local directory.
+    pub fn path(name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self::new(name, CrateSource::Path(path.into()))
+    }
+
+    /// A crate in a git repository, at its default branch.
+    pub fn git(name: impl Into<String>, url: impl Into<String>) -> Self {
+        Self::new(name, CrateSource::Git { url: url.into(), rev: None })
+    }
+
+    fn new(name: impl Into<String>, source: CrateSource) -> Self {
+        Self {
+            name: name.into(),
+            source,
+            features: Vec::new(),
+            description: None,
+        }
+    }
+
+    /// Build a git crate at `rev`; ignored for path crates.
+    pub fn at_rev(mut self, rev: impl Into<String>) -> Self {
+        if let CrateSource::Git { rev: at, .. } = &mut self.source {
+            *at = Some(rev.into());
+        }
+        self
+    }
+
+    /// Enable `features`.
+    pub fn with_features(mut self, features: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.features = features.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Describe the crate to the AI.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// The crate's entry under `[dependencies]`, e.g.
+    /// `{ path = "/srv/shop/types" }`.
+    pub fn manifest_entry(&self) -> toml::Value {
+        let mut entry = toml::Table::new();
+        match &self.source {
+            CrateSource::Path(path) => {
+                entry.insert("path".to_string(), path.to_string_lossy().into_owned().into());
+            }
+            CrateSource::Git { url, rev } => {
+                entry.insert("git".to_string(), url.clone().into());
+                if let Some(rev) = rev {
+                    entry.insert("rev".to_string(), rev.clone().into());
+                }
+            }
+        }
+        if !self.features.is_empty() {
+            entry.insert("features".to_string(), self.features.clone().into());
+        }
+        toml::Value::Table(entry)
+    }
+
+    /// Read host crates from a TOML table of `name = { path | git, rev,
+    /// features, description }` entries, sorted by name. Relative paths are
+    /// resolved against `base`, usually the directory of the file they came
+    /// from.
+    pub fn parse_all(config: &str, base: &Path) -> Result<Vec<HostCrate>> {
+        let invalid = |name: &str, reason: &str| {
+            MorpheusError::InvalidState(format!("Invalid host crate '{}': {}", name, reason))
+        };
+        let config = config
+            .parse::<toml::Table>()
+            .map_err(|e| MorpheusError::InvalidState(format!("Invalid host crates: {}", e)))?;
+
+        let mut crates = Vec::new();
+        for (name, spec) in &config {
+            let Some(spec) = spec.as_table() else {
+                return Err(invalid(name, "expected a table"));
+            };
+            let text = |key: &str| spec.get(key).and_then(|v| v.as_str()).map(str::to_string);
+            let mut host_crate = match (text("path"), text("git")) {
+                (Some(path), None) => HostCrate::path(name, base.join(path)),
+                (None, Some(url)) => HostCrate::git(name, url),
+                _ => return Err(invalid(name, "needs exactly one of `path` or `git`")),
+            };
+            if let Some(rev) = text("rev") {
+                host_crate = host_crate.at_rev(rev);
+            }
+            if let Some(features) = spec.get("features") {
+                let features = features
+                    .as_array()
+                    .and_then(|f| f.iter().map(|f| f.as_str()).collect::<Option<Vec<_>>>())
+                    .ok_or_else(|| invalid(name, "`features` must be a list of strings"))?;
+                host_crate = host_crate.with_features(features);
+            }
+            host_crate.description = text("description");
+            crates.push(host_crate);
+        }
+        Ok(crates)
+    }
+
+    /// Write `types` as the `morpheus_types` crate in `dir`, replacing
+    /// whatever an earlier call wrote there.
+    pub fn shared_types(types: &SharedTypes, dir: &Path) -> Result<HostCrate> {
+        let manifest = format!(
+            "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+             [dependencies]\nserde = {{ version = \"1.0\", features = [\"derive\"] }}\nserde_json = \"1.0\"\n",
+            shared::CRATE_NAME
+        );
+        let write = |path: PathBuf, contents: String| {
+            std::fs::create_dir_all(dir.join("src"))
+                .and_then(|_| std::fs::write(&path, contents))
+                .map_err(|e| MorpheusError::CompilationError(format!("Failed to write {}: {}", path.display(), e)))
+        };
+        write(dir.join("Cargo.toml"), manifest)?;
+        write(dir.join("src").join("lib.rs"), types.crate_source())?;
+
+        Ok(HostCrate::path(shared::CRATE_NAME, dir).with_description(format!(
+            "types shared with the host app, version {}: {}",
+            types.version(),
+            types.names().join(", ")
+        )))
+    }
+
+    /// Read host crates from a TOML file (see [`HostCrate::parse_all`]).
+    pub fn load(path: &Path) -> Result<Vec<HostCrate>> {
+        let config = std::fs::read_to_string(path).map_err(|e| {
+            MorpheusError::InvalidState(format!("Failed to read host crates from {}: {}", path.display(), e))
+        })?;
+        Self::parse_all(&config, path.parent().unwrap_or(Path::new(".")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_crates_without_exactly_one_source() {
+        let both = "[types]\npath = \"types\"\ngit = \"https://example.com/types\"";
+        let neither = "[types]\ndescription = \"Domain types\"";
+        let features = "[types]\npath = \"types\"\nfeatures = \"serde\"";
+
+        for config in [both, neither, features] {
+            let error = HostCrate::parse_all(config, Path::new("/srv")).unwrap_err().to_string();
+            assert!(error.contains("host crate 'types'"), "{}", error);
+        }
+        assert!(HostCrate::parse_all("types = 1", Path::new("/srv")).is_err());
+    }
+
+    #[test]
+    fn test_shared_types_become_a_crate() {
+        let dir = std::env::temp_dir().join("morpheus-shared-types-test");
+        let types = SharedTypes::new().with::<morpheus_core::broker::Position>();
+
+        let host_crate = HostCrate::shared_types(&types, &dir).unwrap();
+
+        assert_eq!(host_crate.name, "morpheus_types");
+        assert_eq!(host_crate.source, CrateSource::Path(dir.clone()));
+        assert!(host_crate.description.unwrap().ends_with(&format!("version {}: Position", types.version())));
+        let manifest: toml::Table = std::fs::read_to_string(dir.join("Cargo.toml")).unwrap().parse().unwrap();
+        assert_eq!(manifest["package"]["name"].as_str(), Some("morpheus_types"));
+        let lib = std::fs::read_to_string(dir.join("src/lib.rs")).unwrap();
+        assert!(lib.contains("pub struct Position {"));
+        assert!(syn::parse_file(&lib).is_ok());
+    }
+}
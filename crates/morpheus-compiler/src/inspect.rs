@@ -0,0 +1,301 @@
+//! Inspection of compiled modules.
+//!
+//! When a component doesn't mount, the answer is usually in the module: it
+//! doesn't export `render`, it imports something the JS glue doesn't
+//! provide, or its memory can't grow. [`Inspection::of`] reports what a
+//! module exports and imports, its memories and tables, its custom sections
+//! and where its bytes go (see [`SizeBreakdown`]), and which wasm-bindgen
+//! version produced it, along with the problems that keep it from mounting.
+
+use crate::size::SizeBreakdown;
+use serde::Serialize;
+
+/// Modules the wasm-bindgen JS glue provides imports from.
+pub const GLUE_MODULES: [&str; 3] = ["wbg", "__wbindgen_placeholder__", "__wbindgen_externref_xform__"];
+
+/// Export the host calls to mount a component.
+pub const RENDER_EXPORT: &str = "render";
+
+/// What a module contains, and what keeps it from mounting.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Inspection {
+    /// Exported items, in module order.
+    pub exports: Vec<Item>,
+
+    /// Imported items, in module order.
+    pub imports: Vec<Import>,
+
+    /// Memories, imported ones first.
+    pub memories: Vec<Memory>,
+
+    /// Tables, imported ones first.
+    pub tables: Vec<Table>,
+
+    /// Custom sections and their sizes.
+    pub custom_sections: Vec<(String, usize)>,
+
+    /// Tools recorded in the `producers` section, e.g. `processed-by rustc 1.82.0`.
+    pub producers: Vec<Producer>,
+
+    /// Version of wasm-bindgen that processed the module; `"unknown"` when
+    /// it uses wasm-bindgen's imports without recording which version.
+    pub wasm_bindgen: Option<String>,
+
+    /// Where the module's bytes go.
+    pub size: SizeBreakdown,
+
+    /// Why the module wouldn't mount; empty when nothing is wrong.
+    pub problems: Vec<String>,
+}
+
+/// An exported item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Item {
+    pub name: String,
+
+    /// `func`, `table`, `memory`, `global` or `tag`.
+    pub kind: &'static str,
+}
+
+/// An imported item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Import {
+    pub module: String,
+
+    pub name: String,
+
+    /// `func`, `table`, `memory`, `global` or `tag`.
+    pub kind: &'static str,
+}
+
+/// A linear memory, sized in 64 KiB pages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Memory {
+    pub imported: bool,
+    pub initial_pages: u64,
+    pub maximum_pages: Option<u64>,
+    pub shared: bool,
+    pub memory64: bool,
+}
+
+/// A table, sized in elements.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Table {
+    pub imported: bool,
+
+    /// Element type, e.g. `funcref`.
+    pub element_type: String,
+    pub initial: u64,
+    pub maximum: Option<u64>,
+}
+
+/// A tool recorded in the `producers` section.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Producer {
+    /// `language`, `processed-by` or `sdk`.
+    pub field: String,
+    pub name: String,
+    pub version: String,
+}
+
+impl Inspection {
+    /// Inspect a module. Parsing stops at the first malformed section,
+    /// which is reported as a problem.
+    pub fn of(wasm: &[u8]) -> Self {
+        let mut inspection = Self {
+            size: SizeBreakdown::analyze(wasm),
+            ..Default::default()
+        };
+
+        for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+            let payload = match payload {
+                Ok(payload) => payload,
+                Err(e) => {
+                    inspection.problems.push(format!("Malformed module: {}", e));
+                    break;
+                }
+            };
+            match payload {
+                wasmparser::Payload::ImportSection(reader) => {
+                    for import in reader.into_imports().flatten() {
+                        match import.ty {
+                            wasmparser::TypeRef::Memory(ty) => inspection.memories.push(memory(ty, true)),
+                            wasmparser::TypeRef::Table(ty) => inspection.tables.push(table(ty, true)),
+                            _ => {}
+                        }
+                        inspection.imports.push(Import {
+                            module: import.module.to_string(),
+                            name: import.name.to_string(),
+                            kind: import_kind(&import.ty),
+                        });
+                    }
+                }
+                wasmparser::Payload::MemorySection(reader) => {
+                    inspection.memories.extend(reader.into_iter().flatten().map(|ty| memory(ty, false)));
+                }
+                wasmparser::Payload::TableSection(reader) => {
+                    inspection.tables.extend(reader.into_iter().flatten().map(|t| table(t.ty, false)));
+                }
+                wasmparser::Payload::ExportSection(reader) => {
+                    for export in reader.into_iter().flatten() {
+                        inspection.exports.push(Item {
+                            name: export.name.to_string(),
+                            kind: export_kind(export.kind),
+                        });
+                    }
+                }
+                wasmparser::Payload::CustomSection(reader) => {
+                    inspection.custom_sections.push((reader.name().to_string(), reader.data().len()));
+                    if let wasmparser::KnownCustom::Producers(reader) = reader.as_known() {
+                        for field in reader.into_iter().flatten() {
+                            for value in field.values.into_iter().flatten() {
+                                inspection.producers.push(Producer {
+                                    field: field.name.to_string(),
+                                    name: value.name.to_string(),
+                                    version: value.version.to_string(),
+                                });
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        inspection.wasm_bindgen = inspection.wasm_bindgen_version();
+        if inspection.problems.is_empty() {
+            if let Err(e) = wasmparser::Validator::new().validate_all(wasm) {
+                inspection.problems.push(format!("Invalid module: {}", e));
+            }
+        }
+        inspection.problems.extend(inspection.mount_problems());
+        inspection
+    }
+
+    fn wasm_bindgen_version(&self) -> Option<String> {
+        let recorded = self
+            .producers
+            .iter()
+            .find(|p| p.field == "processed-by" && p.name == "wasm-bindgen")
+            .map(|p| p.version.clone());
+        let uses_glue = self.imports.iter().any(|i| GLUE_MODULES.contains(&i.module.as_str()))
+            || self.custom_sections.iter().any(|(name, _)| name == "__wasm_bindgen_unstable");
+        recorded.or_else(|| uses_glue.then(|| "unknown".to_string()))
+    }
+
+    fn mount_problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !self.exports.iter().any(|e| e.name == RENDER_EXPORT && e.kind == "func") {
+            problems.push(format!("Doesn't export {}(), which the host calls to mount it", RENDER_EXPORT));
+        }
+        if self.wasm_bindgen.is_none() {
+            problems.push("Wasn't processed by wasm-bindgen, so there is no JS glue to load it with".to_string());
+        }
+        for import in &self.imports {
+            if !GLUE_MODULES.contains(&import.module.as_str()) {
+                problems.push(format!(
+                    "Imports {} `{}::{}`, which the JS glue doesn't provide",
+                    import.kind, import.module, import.name
+                ));
+            }
+        }
+        for memory in self.memories.iter().filter(|m| m.shared) {
+            let maximum = memory.maximum_pages.map_or("no".to_string(), |pages| pages.to_string());
+            problems.push(format!(
+                "Uses shared memory ({} pages maximum), which browsers only allow on cross-origin isolated pages",
+                maximum
+            ));
+        }
+        problems
+    }
+}
+
+fn memory(ty: wasmparser::MemoryType, imported: bool) -> Memory {
+    Memory {
+        imported,
+        initial_pages: ty.initial,
+        maximum_pages: ty.maximum,
+        shared: ty.shared,
+        memory64: ty.memory64,
+    }
+}
+
+fn table(ty: wasmparser::TableType, imported: bool) -> Table {
+    Table {
+        imported,
+        element_type: ty.element_type.to_string(),
+        initial: ty.initial,
+        maximum: ty.maximum,
+    }
+}
+
+fn import_kind(ty: &wasmparser::TypeRef) -> &'static str {
+    match ty {
+        wasmparser::TypeRef::Func(_) | wasmparser::TypeRef::FuncExact(_) => "func",
+        wasmparser::TypeRef::Table(_) => "table",
+        wasmparser::TypeRef::Memory(_) => "memory",
+        wasmparser::TypeRef::Global(_) => "global",
+        wasmparser::TypeRef::Tag(_) => "tag",
+    }
+}
+
+fn export_kind(kind: wasmparser::ExternalKind) -> &'static str {
+    match kind {
+        wasmparser::ExternalKind::Func | wasmparser::ExternalKind::FuncExact => "func",
+        wasmparser::ExternalKind::Table => "table",
+        wasmparser::ExternalKind::Memory => "memory",
+        wasmparser::ExternalKind::Global => "global",
+        wasmparser::ExternalKind::Tag => "tag",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspects_a_bindgen_module() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "wbg" "__wbindgen_throw" (func (param i32 i32)))
+                (memory (export "memory") 17 100)
+                (table 2 funcref)
+                (func (export "render"))
+                (@producers (processed-by "wasm-bindgen" "0.2.92 (2a4a49362)"))
+                (@custom "note" "hello"))"#,
+        )
+        .unwrap();
+
+        let inspection = Inspection::of(&wasm);
+
+        assert!(inspection.problems.is_empty(), "{:?}", inspection.problems);
+        assert_eq!(inspection.wasm_bindgen.as_deref(), Some("0.2.92 (2a4a49362)"));
+        assert_eq!(inspection.exports.iter().map(|e| (e.name.as_str(), e.kind)).collect::<Vec<_>>(), [
+            ("memory", "memory"),
+            ("render", "func")
+        ]);
+        assert_eq!(inspection.imports[0].name, "__wbindgen_throw");
+        assert_eq!((inspection.memories[0].initial_pages, inspection.memories[0].maximum_pages), (17, Some(100)));
+        assert_eq!(inspection.tables[0].element_type, "funcref");
+        assert!(inspection.custom_sections.contains(&("note".to_string(), 5)));
+        assert_eq!(inspection.size.total, wasm.len());
+    }
+
+    #[test]
+    fn test_explains_why_a_module_would_not_mount() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "now" (func (result f64)))
+                (func (export "main")))"#,
+        )
+        .unwrap();
+
+        let problems = Inspection::of(&wasm).problems;
+
+        assert_eq!(problems.len(), 3);
+        assert!(problems[0].contains("render()"));
+        assert!(problems[1].contains("wasm-bindgen"));
+        assert!(problems[2].contains("`env::now`"));
+        assert!(Inspection::of(b"\0asm\x01\0\0\0\x01").problems[0].starts_with("Malformed module"));
+    }
+}
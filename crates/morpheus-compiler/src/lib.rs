@@ -28,10 +28,41 @@
 
 use morpheus_core::errors::Result;
 use async_trait::async_trait;
+use serde::Serialize;
 
+pub mod advisories;
+pub mod diagnostics;
+pub mod element;
+pub mod fix;
+pub mod guardrails;
+pub mod host_crate;
+pub mod inspect;
+pub mod sbom;
+pub mod search;
+pub mod size;
+pub mod snapshot;
+pub mod source;
 pub mod subprocess;
+pub mod symbols;
+pub mod transform;
+pub mod verify;
 
-pub use subprocess::SubprocessCompiler;
+#[cfg(test)]
+mod fuzz;
+
+pub use advisories::{Advisory, AdvisoryPolicy};
+pub use element::CustomElement;
+pub use guardrails::{Guardrails, Violation};
+pub use host_crate::HostCrate;
+pub use inspect::Inspection;
+pub use sbom::Sbom;
+pub use search::SourceIndex;
+pub use size::{SizeBreakdown, SizeBudget};
+pub use snapshot::SnapshotOutcome;
+pub use subprocess::{Dependency, SubprocessCompiler, Target};
+pub use symbols::Symbol;
+pub use transform::{ArtifactTransform, SizeReport, WasmOpt};
+pub use verify::{Verification, Verifier};
 
 /// Result of compilation including both WASM binary and JavaScript glue code.
 #[derive(Debug, Clone)]
@@ -42,6 +73,29 @@ pub struct CompilationResult {
     /// JavaScript glue code generated by wasm-bindgen.
     /// This is required to load and interact with the WASM module.
     pub js_glue: String,
+
+    /// Outcome of pre-initialization, if snapshotting was enabled.
+    pub snapshot: Option<SnapshotOutcome>,
+
+    /// Dependency tree of the generated project, if its lockfile was readable.
+    pub sbom: Option<Sbom>,
+
+    /// Non-fatal findings, such as dependency advisories under
+    /// [`AdvisoryPolicy::Warn`], notes from [`ArtifactTransform`]s and the
+    /// pre-compile fixes applied.
+    pub diagnostics: Vec<CompilationError>,
+
+    /// The source actually built, if pre-compile fixes changed it (see
+    /// [`fix`]). Store this rather than the submitted source.
+    pub fixed_source: Option<String>,
+
+    /// Builds for the compiler's extra targets, e.g. Node.js or WASI (see
+    /// [`SubprocessCompiler::with_extra_targets`]).
+    pub variants: Vec<morpheus_core::artifact::Artifact>,
+
+    /// The browser build rendered and audited in headless Chrome, if the
+    /// compiler has a [`Verifier`] and verification ran.
+    pub verification: Option<Verification>,
 }
 
 /// A compiler that can turn Rust code into WASM modules.
@@ -56,10 +110,28 @@ pub trait Compiler {
     ///
     /// Faster than full compilation for quick validation.
     async fn check(&self, source: &str) -> Result<()>;
+
+    /// Every error and warning in the source, with locations, from a type
+    /// check alone (see [`diagnostics`]). Nothing is built.
+    ///
+    /// By default the failure of [`check`](Self::check) is the only
+    /// diagnostic.
+    async fn diagnostics(&self, source: &str) -> Result<Vec<CompilationError>> {
+        Ok(match self.check(source).await {
+            Ok(()) => Vec::new(),
+            Err(e) => vec![CompilationError {
+                message: e.to_string(),
+                file: None,
+                line: None,
+                column: None,
+                severity: Severity::Error,
+            }],
+        })
+    }
 }
 
 /// Compilation errors with source locations.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CompilationError {
     /// Error message from rustc.
     pub message: String,
@@ -77,7 +149,8 @@ pub struct CompilationError {
     pub severity: Severity,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Severity {
     Error,
     Warning,
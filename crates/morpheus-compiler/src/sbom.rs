@@ -0,0 +1,236 @@
+//! Software bill of materials for generated components.
+//!
+//! Every compile resolves a fresh dependency tree for the generated project.
+//! When an advisory lands for some crate, we need to know which running
+//! components pulled it in. After a successful build the compiler reads the
+//! project's `Cargo.lock` and records it as a [CycloneDX](https://cyclonedx.org)
+//! SBOM, which serializes to the standard CycloneDX JSON format.
+
+use morpheus_core::errors::{MorpheusError, Result};
+use serde::{Deserialize, Serialize};
+
+/// CycloneDX specification version produced.
+pub const SPEC_VERSION: &str = "1.5";
+
+/// A CycloneDX bill of materials.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sbom {
+    /// Always `CycloneDX`.
+    pub bom_format: String,
+
+    /// CycloneDX specification version.
+    pub spec_version: String,
+
+    /// Revision of this BOM.
+    pub version: u32,
+
+    /// What the BOM describes.
+    pub metadata: SbomMetadata,
+
+    /// Every crate in the dependency tree, excluding the root.
+    pub components: Vec<SbomComponent>,
+
+    /// Direct dependencies of each crate, by `bom-ref`.
+    pub dependencies: Vec<SbomDependency>,
+}
+
+/// BOM metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SbomMetadata {
+    /// The generated project itself.
+    pub component: SbomComponent,
+}
+
+/// A crate in the dependency tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SbomComponent {
+    /// Always `library` (or `application` for the root).
+    #[serde(rename = "type")]
+    pub kind: String,
+
+    /// Identifier used by [`SbomDependency`], `name@version`.
+    #[serde(rename = "bom-ref")]
+    pub bom_ref: String,
+
+    /// Crate name.
+    pub name: String,
+
+    /// Crate version.
+    pub version: String,
+
+    /// Package URL, e.g. `pkg:cargo/serde@1.0.210`.
+    pub purl: String,
+}
+
+/// Direct dependencies of one crate.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SbomDependency {
+    /// The depending crate.
+    #[serde(rename = "ref")]
+    pub bom_ref: String,
+
+    /// Crates it depends on.
+    pub depends_on: Vec<String>,
+}
+
+/// The parts of `Cargo.lock` the SBOM needs.
+#[derive(Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+impl SbomComponent {
+    fn new(kind: &str, name: &str, version: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            bom_ref: format!("{}@{}", name, version),
+            name: name.to_string(),
+            version: version.to_string(),
+            purl: format!("pkg:cargo/{}@{}", name, version),
+        }
+    }
+}
+
+impl Sbom {
+    /// Build an SBOM from the contents of a `Cargo.lock`.
+    ///
+    /// `root` names the project's own package, which becomes the BOM's
+    /// subject rather than one of its components.
+    pub fn from_lockfile(lockfile: &str, root: &str) -> Result<Self> {
+        let lockfile: Lockfile = toml::from_str(lockfile)
+            .map_err(|e| MorpheusError::Other(format!("Invalid Cargo.lock: {}", e)))?;
+
+        // Lockfile dependency entries omit the version when only one version
+        // of a crate is locked, so resolve names against the package list
+        let resolve = |entry: &str| -> String {
+            let mut parts = entry.split_whitespace();
+            let name = parts.next().unwrap_or_default();
+            match parts.next() {
+                Some(version) => format!("{}@{}", name, version),
+                None => lockfile
+                    .package
+                    .iter()
+                    .find(|p| p.name == name)
+                    .map(|p| format!("{}@{}", p.name, p.version))
+                    .unwrap_or_else(|| name.to_string()),
+            }
+        };
+
+        let root_package = lockfile.package.iter().find(|p| p.name == root).ok_or_else(|| {
+            MorpheusError::Other(format!("Cargo.lock has no package '{}'", root))
+        })?;
+
+        Ok(Self {
+            bom_format: "CycloneDX".to_string(),
+            spec_version: SPEC_VERSION.to_string(),
+            version: 1,
+            metadata: SbomMetadata {
+                component: SbomComponent::new("application", &root_package.name, &root_package.version),
+            },
+            components: lockfile
+                .package
+                .iter()
+                .filter(|p| p.name != root)
+                .map(|p| SbomComponent::new("library", &p.name, &p.version))
+                .collect(),
+            dependencies: lockfile
+                .package
+                .iter()
+                .map(|p| SbomDependency {
+                    bom_ref: format!("{}@{}", p.name, p.version),
+                    depends_on: p.dependencies.iter().map(|d| resolve(d)).collect(),
+                })
+                .collect(),
+        })
+    }
+
+    /// Find a crate in the tree, optionally at a specific version.
+    pub fn find(&self, name: &str, version: Option<&str>) -> Option<&SbomComponent> {
+        self.components
+            .iter()
+            .find(|c| c.name == name && version.is_none_or(|v| c.version == v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCKFILE: &str = r#"
+version = 3
+
+[[package]]
+name = "morpheus-component"
+version = "0.1.0"
+dependencies = [
+ "serde",
+ "wasm-bindgen 0.2.93",
+]
+
+[[package]]
+name = "serde"
+version = "1.0.210"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "wasm-bindgen"
+version = "0.2.93"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "serde",
+]
+"#;
+
+    #[test]
+    fn test_from_lockfile() {
+        let sbom = Sbom::from_lockfile(LOCKFILE, "morpheus-component").expect("Failed to build SBOM");
+
+        assert_eq!(sbom.metadata.component.bom_ref, "morpheus-component@0.1.0");
+        assert_eq!(sbom.components.len(), 2);
+        assert_eq!(sbom.components[0].purl, "pkg:cargo/serde@1.0.210");
+        assert_eq!(
+            sbom.dependencies[0].depends_on,
+            vec!["serde@1.0.210", "wasm-bindgen@0.2.93"]
+        );
+    }
+
+    #[test]
+    fn test_find() {
+        let sbom = Sbom::from_lockfile(LOCKFILE, "morpheus-component").unwrap();
+
+        assert!(sbom.find("serde", None).is_some());
+        assert!(sbom.find("serde", Some("1.0.210")).is_some());
+        assert!(sbom.find("serde", Some("1.0.0")).is_none());
+        assert!(sbom.find("morpheus-component", None).is_none());
+    }
+
+    #[test]
+    fn test_cyclonedx_wire_format() {
+        let sbom = Sbom::from_lockfile(LOCKFILE, "morpheus-component").unwrap();
+
+        let json = serde_json::to_value(&sbom).unwrap();
+
+        assert_eq!(json["bomFormat"], "CycloneDX");
+        assert_eq!(json["specVersion"], SPEC_VERSION);
+        assert_eq!(json["components"][0]["bom-ref"], "serde@1.0.210");
+        assert_eq!(json["components"][0]["type"], "library");
+        assert_eq!(json["dependencies"][0]["dependsOn"][0], "serde@1.0.210");
+    }
+
+    #[test]
+    fn test_missing_root() {
+        assert!(Sbom::from_lockfile(LOCKFILE, "other").is_err());
+        assert!(Sbom::from_lockfile("not [valid", "morpheus-component").is_err());
+    }
+}
@@ -0,0 +1,198 @@
+//! Search across stored component source.
+//!
+//! Finding where a function, type or Tailwind class is used means reading
+//! every component's source, and earlier versions too when the question is
+//! when something appeared. A [`SourceIndex`] holds each stored version's
+//! source once (stored versions never change) along with its symbols, by
+//! name (see [`crate::symbols`]): [`SourceIndex::grep`] finds the lines
+//! matching a regular expression, and [`SourceIndex::symbols`] finds the
+//! items defined under a name.
+//!
+//! ```rust
+//! use morpheus_compiler::search::SourceIndex;
+//! use regex::Regex;
+//!
+//! let mut index = SourceIndex::new();
+//! index.add(0, "header", "pub fn Header() -> String {\n    \"<h1 class='text-xl'>\".into()\n}\n");
+//! index.add(1, "footer", "pub fn Footer() -> String {\n    \"<p class='text-xl'>\".into()\n}\n");
+//!
+//! // Newest versions first
+//! let uses = index.grep(&Regex::new("text-xl").unwrap(), |_| true);
+//! assert_eq!((uses[0].component.as_str(), uses[0].line, uses[0].column), ("footer", 2, 16));
+//! assert_eq!(uses.len(), 2);
+//!
+//! let symbols = index.symbols("head", |_| true);
+//! assert_eq!((symbols[0].symbol.name.as_str(), symbols[0].version_id), ("Header", 0));
+//! ```
+
+use crate::symbols::{self, Symbol};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A line matching a search.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LineMatch {
+    pub version_id: usize,
+
+    /// Component the version belongs to.
+    pub component: String,
+
+    /// Line of the match (1-indexed).
+    pub line: usize,
+
+    /// Column where the first match on the line starts (1-indexed).
+    pub column: usize,
+
+    /// The whole line, without leading indentation.
+    pub text: String,
+}
+
+/// An item whose name matches a search.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SymbolMatch {
+    pub version_id: usize,
+
+    /// Component the version belongs to.
+    pub component: String,
+
+    /// The item, without its members.
+    pub symbol: Symbol,
+}
+
+/// Sources of stored versions, searchable by text and by symbol name.
+#[derive(Debug, Clone, Default)]
+pub struct SourceIndex {
+    sources: BTreeMap<usize, Indexed>,
+
+    /// Lowercased symbol names, to the versions and items defining them.
+    symbols: BTreeMap<String, Vec<(usize, Symbol)>>,
+}
+
+#[derive(Debug, Clone)]
+struct Indexed {
+    component: String,
+    text: String,
+}
+
+impl SourceIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index the source of a version. Sources that don't parse can still be
+    /// grepped, but contribute no symbols. Adding a version again replaces
+    /// it.
+    pub fn add(&mut self, version_id: usize, component: impl Into<String>, source: &str) {
+        if self.contains(version_id) {
+            for entries in self.symbols.values_mut() {
+                entries.retain(|(id, _)| *id != version_id);
+            }
+        }
+        let mut stack = symbols::outline(source).unwrap_or_default();
+        while let Some(mut symbol) = stack.pop() {
+            stack.append(&mut symbol.children);
+            if symbol.kind != "impl" {
+                self.symbols.entry(symbol.name.to_lowercase()).or_default().push((version_id, symbol));
+            }
+        }
+        self.sources.insert(
+            version_id,
+            Indexed {
+                component: component.into(),
+                text: source.to_string(),
+            },
+        );
+    }
+
+    /// Whether a version has been indexed.
+    pub fn contains(&self, version_id: usize) -> bool {
+        self.sources.contains_key(&version_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Lines matching `pattern` in the versions `include` accepts, newest
+    /// version first and in line order within a version.
+    pub fn grep(&self, pattern: &Regex, include: impl Fn(usize) -> bool) -> Vec<LineMatch> {
+        let mut matches = Vec::new();
+        for (&version_id, source) in self.sources.iter().rev().filter(|(id, _)| include(**id)) {
+            for (i, line) in source.text.lines().enumerate() {
+                let Some(found) = pattern.find(line) else { continue };
+                matches.push(LineMatch {
+                    version_id,
+                    component: source.component.clone(),
+                    line: i + 1,
+                    column: line[..found.start()].chars().count() + 1,
+                    text: line.trim_start().to_string(),
+                });
+            }
+        }
+        matches
+    }
+
+    /// Items whose name contains `query`, ignoring case, in the versions
+    /// `include` accepts: exact names first, then names starting with it,
+    /// newest version first within each.
+    pub fn symbols(&self, query: &str, include: impl Fn(usize) -> bool) -> Vec<SymbolMatch> {
+        let query = query.to_lowercase();
+        let mut matches: Vec<_> = self
+            .symbols
+            .iter()
+            .filter(|(name, _)| name.contains(&query))
+            .flat_map(|(name, entries)| entries.iter().map(move |entry| (name, entry)))
+            .filter(|(_, (id, _))| include(*id))
+            .collect();
+        matches.sort_by_key(|(name, (id, symbol))| {
+            (**name != query, !name.starts_with(&query), std::cmp::Reverse(*id), symbol.line)
+        });
+        matches
+            .into_iter()
+            .map(|(_, (version_id, symbol))| SymbolMatch {
+                version_id: *version_id,
+                component: self.sources.get(version_id).map(|s| s.component.clone()).unwrap_or_default(),
+                symbol: symbol.clone(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbols_rank_exact_names_first_and_skip_excluded_versions() {
+        let mut index = SourceIndex::new();
+        index.add(0, "list", "struct TodoList { todo: Todo }\nstruct Todo;\n");
+        index.add(1, "list", "struct TodoList { todo: Todo }\nstruct Todo;\nimpl Todo { fn todo_count() {} }\n");
+        index.add(2, "broken", "struct Todo {");
+
+        let found: Vec<_> = index
+            .symbols("TODO", |id| id != 0)
+            .into_iter()
+            .map(|m| (m.version_id, m.symbol.kind, m.symbol.name))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (1, "field", "todo".to_string()),
+                (1, "struct", "Todo".to_string()),
+                (1, "struct", "TodoList".to_string()),
+                (1, "method", "todo_count".to_string()),
+            ]
+        );
+        assert!(index.grep(&Regex::new(r"struct Todo \{").unwrap(), |_| true).iter().any(|m| m.version_id == 2));
+
+        // Re-adding a version replaces its symbols
+        index.add(1, "list", "fn render() {}\n");
+        assert_eq!(index.symbols("todo", |id| id == 1), []);
+        assert_eq!(index.len(), 3);
+    }
+}
@@ -0,0 +1,263 @@
+//! Module size analysis and budgets.
+//!
+//! Every hot-reload ships the whole module to the browser, so size matters.
+//! [`SizeBreakdown`] attributes a module's bytes to its sections and, like
+//! [twiggy](https://github.com/rustwasm/twiggy)'s `top`, to individual
+//! functions and data segments. [`SizeBudget`] is an
+//! [`ArtifactTransform`] that fails builds over a byte budget with that
+//! breakdown in the error, so the AI sees what to slim down on its retry.
+
+use crate::transform::{ArtifactTransform, SizeReport};
+use morpheus_core::errors::{MorpheusError, Result};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+/// Contributors listed in budget errors.
+pub const DEFAULT_TOP: usize = 10;
+
+/// A function or data segment and the bytes it takes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Contributor {
+    /// Function name from the name section or exports, else `func[index]`;
+    /// `data[index]` for data segments.
+    pub name: String,
+
+    /// Bytes of the function body or segment contents.
+    pub size: usize,
+}
+
+/// Where a module's bytes go.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SizeBreakdown {
+    /// Module size.
+    pub total: usize,
+
+    /// Bytes per section, largest first.
+    pub sections: Vec<(String, usize)>,
+
+    /// Function bodies, largest first.
+    pub functions: Vec<Contributor>,
+
+    /// Data segments, largest first.
+    pub data: Vec<Contributor>,
+}
+
+impl SizeBreakdown {
+    /// Analyze a module. Parsing stops at the first malformed section.
+    pub fn analyze(wasm: &[u8]) -> Self {
+        let mut imported_functions = 0;
+        let mut bodies = Vec::new();
+        let mut data = Vec::new();
+        let mut names: BTreeMap<u32, String> = BTreeMap::new();
+        let mut exports: BTreeMap<u32, String> = BTreeMap::new();
+
+        for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+            let Ok(payload) = payload else { break };
+            match payload {
+                wasmparser::Payload::ImportSection(reader) => {
+                    imported_functions += reader
+                        .into_imports()
+                        .flatten()
+                        .filter(|import| {
+                            matches!(import.ty, wasmparser::TypeRef::Func(_) | wasmparser::TypeRef::FuncExact(_))
+                        })
+                        .count() as u32;
+                }
+                wasmparser::Payload::ExportSection(reader) => {
+                    for export in reader.into_iter().flatten() {
+                        if export.kind == wasmparser::ExternalKind::Func {
+                            exports.entry(export.index).or_insert_with(|| export.name.to_string());
+                        }
+                    }
+                }
+                wasmparser::Payload::CodeSectionEntry(body) => bodies.push(body.range().len()),
+                wasmparser::Payload::DataSection(reader) => {
+                    for (index, segment) in reader.into_iter().enumerate() {
+                        let Ok(segment) = segment else { break };
+                        data.push(Contributor {
+                            name: format!("data[{}]", index),
+                            size: segment.data.len(),
+                        });
+                    }
+                }
+                wasmparser::Payload::CustomSection(reader) => {
+                    if let wasmparser::KnownCustom::Name(reader) = reader.as_known() {
+                        for name in reader.into_iter().flatten() {
+                            if let wasmparser::Name::Function(map) = name {
+                                for naming in map.into_iter().flatten() {
+                                    names.insert(naming.index, naming.name.to_string());
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut functions: Vec<Contributor> = bodies
+            .into_iter()
+            .enumerate()
+            .map(|(i, size)| {
+                let index = imported_functions + i as u32;
+                let name = names
+                    .remove(&index)
+                    .or_else(|| exports.remove(&index))
+                    .unwrap_or_else(|| format!("func[{}]", index));
+                Contributor { name, size }
+            })
+            .collect();
+        functions.sort_by_key(|c| Reverse(c.size));
+        data.sort_by_key(|c| Reverse(c.size));
+
+        Self {
+            total: wasm.len(),
+            sections: SizeReport::sections(wasm),
+            functions,
+            data,
+        }
+    }
+
+    /// The `n` largest functions and data segments together.
+    pub fn top(&self, n: usize) -> Vec<&Contributor> {
+        let mut all: Vec<&Contributor> = self.functions.iter().chain(&self.data).collect();
+        all.sort_by_key(|c| Reverse(c.size));
+        all.truncate(n);
+        all
+    }
+
+    /// Human-readable report of the sections and the `n` largest contributors.
+    pub fn render(&self, n: usize) -> String {
+        let percent = |size: usize| size as f64 * 100.0 / self.total.max(1) as f64;
+        let mut out = String::from("Sections:\n");
+        for (name, size) in &self.sections {
+            out.push_str(&format!("  {:>9} bytes {:>5.1}%  {}\n", size, percent(*size), name));
+        }
+        out.push_str("Largest contributors:\n");
+        for contributor in self.top(n) {
+            out.push_str(&format!(
+                "  {:>9} bytes {:>5.1}%  {}\n",
+                contributor.size,
+                percent(contributor.size),
+                contributor.name
+            ));
+        }
+        out
+    }
+}
+
+/// Fails builds whose module exceeds a byte budget.
+///
+/// Place it last, so the budget applies to the module that ships.
+#[derive(Debug, Clone, Copy)]
+pub struct SizeBudget {
+    max_bytes: usize,
+    top: usize,
+}
+
+impl SizeBudget {
+    /// Allow modules up to `max_bytes`.
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            top: DEFAULT_TOP,
+        }
+    }
+
+    /// List `n` contributors in errors instead of [`DEFAULT_TOP`].
+    pub fn with_top(mut self, n: usize) -> Self {
+        self.top = n;
+        self
+    }
+
+    /// The budget in bytes.
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+}
+
+impl ArtifactTransform for SizeBudget {
+    fn name(&self) -> &str {
+        "size-budget"
+    }
+
+    fn transform(&self, wasm: Vec<u8>) -> Result<Vec<u8>> {
+        if wasm.len() <= self.max_bytes {
+            return Ok(wasm);
+        }
+        let breakdown = SizeBreakdown::analyze(&wasm);
+        Err(MorpheusError::CompilationError(format!(
+            "Module is {} bytes, over the {} byte budget by {} bytes.\n{}\n💡 Make the component smaller: \
+             drop dependencies and features it doesn't need, avoid large string and table constants, \
+             and simplify or merge the largest functions above.",
+            wasm.len(),
+            self.max_bytes,
+            wasm.len() - self.max_bytes,
+            breakdown.render(self.top)
+        )))
+    }
+
+    fn inspect(&self, wasm: &[u8]) -> Vec<String> {
+        vec![format!(
+            "{} of {} bytes ({:.0}%)",
+            wasm.len(),
+            self.max_bytes,
+            wasm.len() as f64 * 100.0 / self.max_bytes.max(1) as f64
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module() -> Vec<u8> {
+        wat::parse_str(
+            r#"(module
+                (import "env" "log" (func $log (param i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "a fairly long string constant in the data section")
+                (data (i32.const 64) "short")
+                (func $small (export "render") (result i32) i32.const 1)
+                (func $large (param i32) (result i32)
+                    local.get 0 i32.const 1 i32.add i32.const 2 i32.mul
+                    i32.const 3 i32.add i32.const 4 i32.mul i32.const 5 i32.sub)
+                (func (result i32) i32.const 2 i32.const 3 i32.add))"#,
+        )
+        .expect("Invalid WAT")
+    }
+
+    #[test]
+    fn test_breakdown_names_and_ranks_contributors() {
+        let breakdown = SizeBreakdown::analyze(&module());
+
+        let names: Vec<_> = breakdown.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["large", "func[3]", "small"]);
+        assert_eq!(breakdown.data[0].name, "data[0]");
+        assert_eq!(breakdown.data[0].size, 49);
+        assert_eq!(breakdown.top(1)[0].name, "data[0]");
+        assert!(breakdown.render(3).contains("data[0]"));
+    }
+
+    #[test]
+    fn test_exports_name_functions_without_name_section() {
+        // Without `$name`s, wat emits no name section
+        let wasm = wat::parse_str(r#"(module (func (export "render") (result i32) i32.const 1))"#).unwrap();
+        assert_eq!(SizeBreakdown::analyze(&wasm).functions[0].name, "render");
+    }
+
+    #[test]
+    fn test_budget() {
+        let wasm = module();
+
+        let within = SizeBudget::new(wasm.len());
+        assert_eq!(within.transform(wasm.clone()).unwrap(), wasm);
+        assert_eq!(within.inspect(&wasm), [format!("{} of {} bytes (100%)", wasm.len(), wasm.len())]);
+
+        let error = SizeBudget::new(100).transform(wasm).unwrap_err().to_string();
+        assert!(error.contains("over the 100 byte budget"));
+        assert!(error.contains("Largest contributors"));
+        assert!(error.contains("large"));
+    }
+}
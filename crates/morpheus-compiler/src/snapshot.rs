@@ -0,0 +1,200 @@
+//! Build-time pre-initialization snapshots.
+//!
+//! Instantiating a large component and running its initialization on every
+//! hot-reload is slow. When snapshotting is enabled, the compiler runs
+//! [Wizer](https://github.com/bytecodealliance/wizer) on the built module:
+//! Wizer instantiates it, calls its `__morpheus_init` export, and writes out
+//! a new module whose memory and globals already contain the initialized
+//! state. The shipped artifact then starts with initialization done.
+//!
+//! Only modules that export `__morpheus_init` and have no non-WASI imports
+//! can be snapshotted; everything else is shipped unchanged. Wizer cannot
+//! provide wasm-bindgen's JS imports, and wasm-pack output always imports
+//! from `wbg` (at least `__wbindgen_throw`), so in practice browser builds
+//! ([`Target::Web`](crate::subprocess::Target::Web)) are never snapshotted:
+//! this is for headless builds, which have no JavaScript glue.
+
+use morpheus_core::errors::{MorpheusError, Result};
+use std::path::Path;
+use std::process::Command;
+use tokio::fs;
+
+/// Export that performs one-time initialization before the snapshot is taken.
+pub const INIT_EXPORT: &str = "__morpheus_init";
+
+/// Outcome of attempting to pre-initialize a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotOutcome {
+    /// The module was replaced with its pre-initialized snapshot.
+    Snapshotted {
+        /// Module size before snapshotting.
+        original_size: usize,
+        /// Module size after snapshotting.
+        snapshot_size: usize,
+    },
+
+    /// The module can't be snapshotted and was left unchanged.
+    Skipped(String),
+}
+
+/// Check whether a module can be pre-initialized.
+///
+/// Returns `Err` with the reason when it can't.
+pub fn check_snapshottable(wasm: &[u8]) -> std::result::Result<(), String> {
+    let mut has_init = false;
+
+    for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+        match payload.map_err(|e| format!("invalid module: {}", e))? {
+            wasmparser::Payload::ImportSection(reader) => {
+                for import in reader.into_imports() {
+                    let import = import.map_err(|e| format!("invalid import: {}", e))?;
+                    if !import.module.starts_with("wasi_") {
+                        return Err(format!(
+                            "imports {}::{}, which is unavailable during pre-initialization",
+                            import.module, import.name
+                        ));
+                    }
+                }
+            }
+            wasmparser::Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export.map_err(|e| format!("invalid export: {}", e))?;
+                    if export.name == INIT_EXPORT && export.kind == wasmparser::ExternalKind::Func {
+                        has_init = true;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if has_init {
+        Ok(())
+    } else {
+        Err(format!("does not export {}()", INIT_EXPORT))
+    }
+}
+
+/// Check if `wizer` is available.
+pub fn check_wizer() -> Result<()> {
+    let wizer = Command::new("wizer").arg("--version").output();
+    match wizer {
+        Ok(output) if output.status.success() => Ok(()),
+        _ => Err(MorpheusError::CompilationError(
+            "wizer not found. Install with: cargo install wizer --all-features".to_string(),
+        )),
+    }
+}
+
+/// Pre-initialize the module at `wasm_path` in place.
+pub async fn preinitialize(wasm_path: &Path) -> Result<SnapshotOutcome> {
+    let wasm = fs::read(wasm_path).await.map_err(|e| {
+        MorpheusError::CompilationError(format!("Failed to read WASM for snapshot: {}", e))
+    })?;
+
+    if let Err(reason) = check_snapshottable(&wasm) {
+        return Ok(SnapshotOutcome::Skipped(reason));
+    }
+
+    check_wizer()?;
+
+    let snapshot_path = wasm_path.with_extension("snapshot.wasm");
+    let output = tokio::process::Command::new("wizer")
+        .arg(wasm_path)
+        .arg("-o")
+        .arg(&snapshot_path)
+        .args(["--init-func", INIT_EXPORT])
+        .output()
+        .await
+        .map_err(|e| MorpheusError::CompilationError(format!("Failed to run wizer: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(MorpheusError::CompilationError(format!(
+            "Pre-initialization failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let snapshot = fs::read(&snapshot_path).await.map_err(|e| {
+        MorpheusError::CompilationError(format!("Failed to read snapshot: {}", e))
+    })?;
+    fs::rename(&snapshot_path, wasm_path).await.map_err(|e| {
+        MorpheusError::CompilationError(format!("Failed to replace module with snapshot: {}", e))
+    })?;
+
+    Ok(SnapshotOutcome::Snapshotted {
+        original_size: wasm.len(),
+        snapshot_size: snapshot.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(wat: &str) -> Vec<u8> {
+        wat::parse_str(wat).expect("Invalid WAT")
+    }
+
+    #[test]
+    fn test_snapshottable_module() {
+        let wasm = module(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "__morpheus_init"))
+                (func (export "render")))"#,
+        );
+
+        assert_eq!(check_snapshottable(&wasm), Ok(()));
+    }
+
+    #[test]
+    fn test_missing_init_export() {
+        let wasm = module(r#"(module (func (export "render")))"#);
+
+        let reason = check_snapshottable(&wasm).unwrap_err();
+        assert!(reason.contains("__morpheus_init"));
+    }
+
+    #[test]
+    fn test_wasm_bindgen_imports_not_snapshottable() {
+        let wasm = module(
+            r#"(module
+                (import "wbg" "__wbindgen_throw" (func (param i32 i32)))
+                (func (export "__morpheus_init")))"#,
+        );
+
+        let reason = check_snapshottable(&wasm).unwrap_err();
+        assert!(reason.contains("wbg::__wbindgen_throw"));
+    }
+
+    #[test]
+    fn test_wasi_imports_allowed() {
+        let wasm = module(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+                (func (export "__morpheus_init")))"#,
+        );
+
+        assert_eq!(check_snapshottable(&wasm), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_module() {
+        let reason = check_snapshottable(&[0x00, 0x61, 0x73]).unwrap_err();
+        assert!(reason.contains("invalid module"));
+    }
+
+    #[tokio::test]
+    async fn test_preinitialize_skips_unsuitable_module() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join("component.wasm");
+        let wasm = module(r#"(module (func (export "render")))"#);
+        fs::write(&path, &wasm).await.expect("Failed to write test module");
+
+        let outcome = preinitialize(&path).await.expect("Preinitialize failed");
+
+        assert!(matches!(outcome, SnapshotOutcome::Skipped(_)));
+        assert_eq!(fs::read(&path).await.unwrap(), wasm);
+    }
+}
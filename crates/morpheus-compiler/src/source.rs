@@ -0,0 +1,181 @@
+//! Source normalization and formatting.
+//!
+//! AI output comes with stray markdown fences, CRLF line endings, trailing
+//! whitespace and whatever layout the model felt like. Stored as-is, every
+//! version diff is full of that noise. [`tidy`] normalizes accepted source
+//! and runs `rustfmt` on it, and [`with_header`] stamps it with a standard
+//! header comment recording its provenance, so diffs between versions show
+//! real changes only.
+
+use morpheus_core::component::{Author, Provenance};
+use morpheus_core::errors::{MorpheusError, Result};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+
+/// Prefix of the header lines written by [`with_header`].
+pub const HEADER_PREFIX: &str = "// morpheus: ";
+
+/// Longest prompt excerpt in the header, in characters.
+const HEADER_PROMPT_CHARS: usize = 100;
+
+/// Normalize line endings and whitespace, and drop markdown fences and any
+/// previous header.
+pub fn normalize(source: &str) -> String {
+    let source = source.trim_start_matches('\u{feff}').replace("\r\n", "\n").replace('\r', "\n");
+
+    let mut lines: Vec<&str> = Vec::new();
+    for line in source.lines() {
+        if line.trim_start().starts_with("```") {
+            continue;
+        }
+        if lines.iter().all(|l| l.is_empty()) && line.starts_with(HEADER_PREFIX) {
+            continue;
+        }
+        let line = line.trim_end();
+        // Keep at most one blank line in a row
+        if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+/// Format `source` with `rustfmt`.
+///
+/// Fails if `rustfmt` is missing or the source doesn't parse.
+pub async fn rustfmt(source: &str) -> Result<String> {
+    let mut child = tokio::process::Command::new("rustfmt")
+        .args(["--edition", "2021"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| MorpheusError::CompilationError(format!("Failed to run rustfmt: {}", e)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(source.as_bytes())
+            .await
+            .map_err(|e| MorpheusError::CompilationError(format!("Failed to write to rustfmt: {}", e)))?;
+    }
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| MorpheusError::CompilationError(format!("Failed to run rustfmt: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(MorpheusError::CompilationError(format!(
+            "rustfmt failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|e| MorpheusError::CompilationError(format!("rustfmt returned invalid UTF-8: {}", e)))
+}
+
+/// Normalize `source` and format it with `rustfmt` where possible.
+///
+/// Without `rustfmt`, or if it rejects the source, the normalized source is
+/// returned unformatted.
+pub async fn tidy(source: &str) -> String {
+    let normalized = normalize(source);
+    match rustfmt(&normalized).await {
+        Ok(formatted) => normalize(&formatted),
+        Err(_) => normalized,
+    }
+}
+
+/// Replace any header on `source` with one describing `component` and its
+/// provenance.
+pub fn with_header(source: &str, component: &str, provenance: &Provenance) -> String {
+    let mut header = vec![format!("component `{}`", component)];
+
+    let mut origin = match provenance.author {
+        Author::Ai => "written by AI".to_string(),
+        Author::Human => "written by hand".to_string(),
+        Author::Unknown => "author unknown".to_string(),
+    };
+    if let Some(model) = &provenance.model {
+        origin.push_str(&format!(" ({})", model));
+    }
+    if let Some(parent) = provenance.parent_version {
+        origin.push_str(&format!(", derived from version {}", parent));
+    }
+    if let Some(component) = &provenance.forked_from {
+        origin.push_str(&format!(", forked from `{}`", component));
+    }
+    header.push(origin);
+
+    if let Some(prompt) = &provenance.prompt {
+        let prompt = prompt.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut excerpt: String = prompt.chars().take(HEADER_PROMPT_CHARS).collect();
+        if excerpt.len() < prompt.len() {
+            excerpt.push_str("...");
+        }
+        header.push(format!("prompt: {}", excerpt));
+    }
+
+    let mut out: String = header.iter().map(|line| format!("{}{}\n", HEADER_PREFIX, line)).collect();
+    out.push('\n');
+    out.push_str(&normalize(source));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_strips_noise() {
+        let raw = "\u{feff}```rust\r\nuse std::fmt;   \r\n\r\n\r\n\r\nfn main() {}\r\n```\r\n\n\n";
+        assert_eq!(normalize(raw), "use std::fmt;\n\nfn main() {}\n");
+        assert_eq!(normalize(&normalize(raw)), normalize(raw));
+    }
+
+    #[test]
+    fn test_header_is_replaced_not_stacked() {
+        let provenance = Provenance::ai("Make a\n  counter", "test-model").with_parent(2);
+        let once = with_header("fn render() {}\n", "main", &provenance);
+        assert_eq!(
+            once,
+            "// morpheus: component `main`\n\
+             // morpheus: written by AI (test-model), derived from version 2\n\
+             // morpheus: prompt: Make a counter\n\
+             \n\
+             fn render() {}\n"
+        );
+
+        // The AI echoes the header back when modifying a component
+        let again = with_header(&once, "main", &Provenance::human());
+        assert_eq!(again.matches(HEADER_PREFIX).count(), 2);
+        assert!(again.contains("written by hand"));
+    }
+
+    #[test]
+    fn test_long_prompts_are_truncated() {
+        let provenance = Provenance::ai("x".repeat(500), "m");
+        let header = with_header("", "main", &provenance);
+        assert!(header.contains(&format!("prompt: {}...", "x".repeat(HEADER_PROMPT_CHARS))));
+    }
+
+    #[tokio::test]
+    async fn test_tidy_falls_back_on_unparseable_source() {
+        let broken = "fn main( {\r\n";
+        assert_eq!(tidy(broken).await, "fn main( {\n");
+    }
+
+    #[tokio::test]
+    async fn test_tidy_formats_when_rustfmt_is_available() {
+        if rustfmt("").await.is_err() {
+            return;
+        }
+        assert_eq!(tidy("fn main(){let x=1;}").await, "fn main() {\n    let x = 1;\n}\n");
+    }
+}
@@ -6,18 +6,172 @@
 //! This is the simplest approach and uses standard tooling. While not the
 //! fastest (compilation takes 5-10 seconds), it's reliable and gets us
 //! started quickly.
-
+//!
+//! UI components are built with `wasm-pack` for the browser. Headless
+//! components ([`Target::Headless`]) are plain `cargo` builds for
+//! `wasm32-unknown-unknown` with no JavaScript glue, to run server-side.
+//!
+//! The same job can also build for extra targets
+//! ([`SubprocessCompiler::with_extra_targets`]): a Node.js package or a WASI
+//! module, returned as [`CompilationResult::variants`](crate::CompilationResult::variants).
+//!
+//! Besides the standard crates, the manifest can include crates of the host
+//! application's own ([`SubprocessCompiler::with_host_crates`]).
+
+use crate::advisories::{self, AdvisoryPolicy};
+use crate::diagnostics;
+use crate::fix;
+use crate::host_crate::HostCrate;
+use crate::sbom::Sbom;
+use crate::snapshot;
+use crate::transform::{ArtifactTransform, Pipeline};
+use crate::verify::Verifier;
 use crate::{CompilationError, Compiler, Severity};
 use async_trait::async_trait;
+use morpheus_core::artifact::{Artifact, ArtifactTarget};
 use morpheus_core::errors::{MorpheusError, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use tokio::fs;
 
+/// Package name of the generated project.
+const PACKAGE_NAME: &str = "morpheus-component";
+
+/// Manifest of a browser component.
+const WEB_MANIFEST: &str = r#"
+[package]
+name = "morpheus-component"
+version = "0.1.0"
+edition = "2021"
+
+[lib]
+crate-type = ["cdylib"]
+
+[dependencies]
+leptos = { version = "0.6", features = ["csr"] }
+wasm-bindgen = "0.2"
+console_error_panic_hook = "0.1"
+serde = { version = "1.0", features = ["derive"] }
+serde_json = "1.0"
+web-sys = { version = "0.3", features = ["Window", "Document", "Element", "HtmlElement"] }
+
+[profile.release]
+opt-level = "z"
+lto = true
+codegen-units = 1
+panic = "abort"
+"#;
+
+/// Manifest of a headless component: no wasm-bindgen, so the module has no
+/// imports and runs under any WASM engine.
+const HEADLESS_MANIFEST: &str = r#"
+[package]
+name = "morpheus-component"
+version = "0.1.0"
+edition = "2021"
+
+[lib]
+crate-type = ["cdylib"]
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+serde_json = "1.0"
+
+[profile.release]
+opt-level = "s"
+lto = true
+codegen-units = 1
+panic = "abort"
+"#;
+
+/// What a component is built to run in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Target {
+    /// The browser, via wasm-bindgen (Leptos and web-sys available).
+    #[default]
+    Web,
+
+    /// The server, under wasmtime (only serde and serde_json available).
+    Headless,
+}
+
+/// A crate generated code can use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    /// Crate name.
+    pub name: String,
+
+    /// Version requirement, e.g. `0.3`.
+    pub version: String,
+
+    /// Enabled features; for `web-sys`, the only DOM APIs available.
+    pub features: Vec<String>,
+
+    /// What the crate offers, for crates of the host app's own.
+    pub description: Option<String>,
+}
+
+/// Read the `[dependencies]` of a Cargo manifest.
+pub fn parse_dependencies(manifest: &str) -> Vec<Dependency> {
+    let Ok(manifest) = manifest.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    let Some(dependencies) = manifest.get("dependencies").and_then(|d| d.as_table()) else {
+        return Vec::new();
+    };
+    dependencies
+        .iter()
+        .map(|(name, spec)| {
+            let version = match spec {
+                toml::Value::String(version) => version.clone(),
+                _ => spec.get("version").and_then(|v| v.as_str()).unwrap_or("*").to_string(),
+            };
+            let features = spec
+                .get("features")
+                .and_then(|f| f.as_array())
+                .map(|f| f.iter().filter_map(|f| f.as_str().map(str::to_string)).collect())
+                .unwrap_or_default();
+            Dependency {
+                name: name.clone(),
+                version,
+                features,
+                description: None,
+            }
+        })
+        .collect()
+}
+
 /// Compiler that spawns `wasm-pack` as subprocess.
 pub struct SubprocessCompiler {
     /// Working directory for temporary build artifacts.
     work_dir: PathBuf,
+
+    /// Pre-initialize built modules with Wizer (see [`crate::snapshot`]).
+    snapshot: bool,
+
+    /// Versions of the tools that build modules, for provenance.
+    toolchain: String,
+
+    /// How to treat RustSec advisories against locked dependencies.
+    advisory_policy: AdvisoryPolicy,
+
+    /// What modules are built to run in.
+    target: Target,
+
+    /// Steps applied to built modules (see [`crate::transform`]).
+    transforms: Pipeline,
+
+    /// Apply rustc's mechanical fixes before building (see [`crate::fix`]).
+    autofix: bool,
+
+    /// Targets built in the same job, besides the primary one.
+    extra_targets: Vec<ArtifactTarget>,
+
+    /// Crates of the host application's, added to the manifest.
+    host_crates: Vec<HostCrate>,
+
+    /// Renders and audits browser builds (see [`crate::verify`]).
+    verifier: Option<Verifier>,
 }
 
 impl SubprocessCompiler {
@@ -30,7 +184,150 @@ impl SubprocessCompiler {
             MorpheusError::CompilationError(format!("Failed to create work directory: {}", e))
         })?;
 
-        Ok(Self { work_dir })
+        Ok(Self {
+            work_dir,
+            snapshot: false,
+            toolchain: Self::toolchain_fingerprint(),
+            advisory_policy: AdvisoryPolicy::Off,
+            target: Target::Web,
+            transforms: Pipeline::default(),
+            autofix: false,
+            extra_targets: Vec::new(),
+            host_crates: Vec::new(),
+            verifier: None,
+        })
+    }
+
+    /// Toolchain that builds modules, e.g. `rustc 1.82.0 (...), wasm-pack 0.13.1`.
+    pub fn toolchain(&self) -> &str {
+        &self.toolchain
+    }
+
+    /// Enable build-time pre-initialization snapshots.
+    ///
+    /// Modules exporting `__morpheus_init` are snapshotted after the build so
+    /// hot-reload skips their initialization; other modules are unaffected.
+    /// That includes every [`Target::Web`] build, whose wasm-bindgen imports
+    /// Wizer can't provide (see [`crate::snapshot`]).
+    pub fn with_snapshotting(mut self, enabled: bool) -> Self {
+        self.snapshot = enabled;
+        self
+    }
+
+    /// Check dependencies against the RustSec database after each build.
+    ///
+    /// Requires `cargo-audit`. Under [`AdvisoryPolicy::Deny`] a missing tool
+    /// fails the build too, since the check can't be skipped silently.
+    pub fn with_advisory_policy(mut self, policy: AdvisoryPolicy) -> Self {
+        self.advisory_policy = policy;
+        self
+    }
+
+    /// Build modules for `target` instead of the browser.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// What modules are built to run in.
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    /// Also build for `targets` in each job. Extra builds skip
+    /// pre-initialization and transforms, and one failing only adds a
+    /// warning; the primary build still succeeds.
+    pub fn with_extra_targets(mut self, targets: impl IntoIterator<Item = ArtifactTarget>) -> Self {
+        self.extra_targets.clear();
+        for target in targets {
+            let primary = self.target == Target::Web && target == ArtifactTarget::Web;
+            if !primary && !self.extra_targets.contains(&target) {
+                self.extra_targets.push(target);
+            }
+        }
+        self
+    }
+
+    /// Targets built in each job besides the primary one.
+    pub fn extra_targets(&self) -> &[ArtifactTarget] {
+        &self.extra_targets
+    }
+
+    /// Pass built modules through `transform`, after any earlier ones.
+    pub fn with_transform(mut self, transform: impl ArtifactTransform + 'static) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    /// Render each browser build in headless Chrome and audit it for
+    /// accessibility after it is built (see [`crate::verify`]).
+    ///
+    /// Builds that can't be verified still succeed, with a warning.
+    pub fn with_verifier(mut self, verifier: Verifier) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Apply rustc's machine-applicable suggestions before building.
+    ///
+    /// Costs a `cargo check` per build but saves an AI round-trip on unused
+    /// or missing imports. The fixed source is returned in
+    /// [`crate::CompilationResult::fixed_source`].
+    pub fn with_autofix(mut self, enabled: bool) -> Self {
+        self.autofix = enabled;
+        self
+    }
+
+    /// Let generated code use crates of the host application's own, such as
+    /// its domain types or API client. A host crate named like a standard
+    /// one replaces it.
+    pub fn with_host_crates(mut self, crates: impl IntoIterator<Item = HostCrate>) -> Self {
+        self.host_crates.extend(crates);
+        self
+    }
+
+    /// Crates of the host application's available to generated code.
+    pub fn host_crates(&self) -> &[HostCrate] {
+        &self.host_crates
+    }
+
+    /// Crates generated code can use, for the AI's context.
+    pub fn dependencies(&self) -> Vec<Dependency> {
+        let mut dependencies = parse_dependencies(&self.manifest());
+        for dependency in &mut dependencies {
+            dependency.description = self
+                .host_crates
+                .iter()
+                .find(|c| c.name == dependency.name)
+                .and_then(|c| c.description.clone());
+        }
+        dependencies
+    }
+
+    /// Manifest of the generated project.
+    fn manifest(&self) -> String {
+        let manifest = match self.target {
+            Target::Web => WEB_MANIFEST,
+            Target::Headless => HEADLESS_MANIFEST,
+        };
+        if self.host_crates.is_empty() {
+            return manifest.to_string();
+        }
+
+        let mut manifest: toml::Table = manifest.parse().expect("built-in manifest is valid TOML");
+        let dependencies = manifest
+            .get_mut("dependencies")
+            .and_then(|d| d.as_table_mut())
+            .expect("built-in manifest has dependencies");
+        for host_crate in &self.host_crates {
+            dependencies.insert(host_crate.name.clone(), host_crate.manifest_entry());
+        }
+        manifest.to_string()
+    }
+
+    /// Names of the transforms applied to built modules, in order.
+    pub fn transforms(&self) -> Vec<String> {
+        self.transforms.names()
     }
 
     /// Check if required tools are available.
@@ -54,6 +351,63 @@ impl SubprocessCompiler {
         Ok(())
     }
 
+    /// Identify the installed `rustc` and `wasm-pack` by their version output.
+    ///
+    /// Tools that can't be run are reported as `<tool> unavailable`.
+    fn toolchain_fingerprint() -> String {
+        ["rustc", "wasm-pack"]
+            .iter()
+            .map(|tool| {
+                Command::new(tool)
+                    .arg("--version")
+                    .output()
+                    .ok()
+                    .filter(|output| output.status.success())
+                    .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+                    .unwrap_or_else(|| format!("{} unavailable", tool))
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Run the advisory check required by the policy.
+    ///
+    /// Returns advisories as warnings, or an error if the policy denies them.
+    async fn check_advisories(&self, project_dir: &Path) -> Result<Vec<CompilationError>> {
+        let severity = match self.advisory_policy {
+            AdvisoryPolicy::Off => return Ok(Vec::new()),
+            AdvisoryPolicy::Warn => Severity::Warning,
+            AdvisoryPolicy::Deny => Severity::Error,
+        };
+
+        let found = match advisories::audit(project_dir).await {
+            Ok(found) => found,
+            Err(e) if severity == Severity::Warning => {
+                return Ok(vec![CompilationError {
+                    message: format!("Advisories not checked: {}", e),
+                    file: None,
+                    line: None,
+                    column: None,
+                    severity: Severity::Warning,
+                }]);
+            }
+            Err(e) => return Err(e),
+        };
+
+        let diagnostics: Vec<_> = found.iter().map(|a| a.to_diagnostic(severity)).collect();
+        if severity == Severity::Error && !diagnostics.is_empty() {
+            return Err(MorpheusError::CompilationError(format!(
+                "Dependencies have known vulnerabilities:\n{}",
+                diagnostics
+                    .iter()
+                    .map(|d| d.message.clone())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )));
+        }
+        Ok(diagnostics)
+    }
+
     /// Create a temporary project directory for compilation.
     async fn create_project(&self, source: &str) -> Result<PathBuf> {
         // Create unique directory for this compilation
@@ -80,39 +434,96 @@ impl SubprocessCompiler {
             .map_err(|e| MorpheusError::CompilationError(format!("Failed to write source: {}", e)))?;
 
         // Create Cargo.toml
-        let cargo_toml = r#"
-[package]
-name = "morpheus-component"
-version = "0.1.0"
-edition = "2021"
+        fs::write(project_dir.join("Cargo.toml"), self.manifest())
+            .await
+            .map_err(|e| MorpheusError::CompilationError(format!("Failed to write Cargo.toml: {}", e)))?;
 
-[lib]
-crate-type = ["cdylib"]
+        Ok(project_dir)
+    }
 
-[dependencies]
-leptos = { version = "0.6", features = ["csr"] }
-wasm-bindgen = "0.2"
-console_error_panic_hook = "0.1"
-serde = { version = "1.0", features = ["derive"] }
-serde_json = "1.0"
-web-sys = { version = "0.3", features = ["Window", "Document", "Element", "HtmlElement"] }
+    /// Build the project, returning the paths of the module and its JS glue.
+    async fn build(&self, project_dir: &Path) -> Result<(PathBuf, Option<PathBuf>)> {
+        let (program, args): (&str, &[&str]) = match self.target {
+            Target::Web => ("wasm-pack", &["build", "--target", "web", "--release"]),
+            Target::Headless => ("cargo", &["build", "--release", "--target", "wasm32-unknown-unknown"]),
+        };
+        Self::run_build(program, args, project_dir).await?;
+
+        Ok(match self.target {
+            Target::Web => (
+                project_dir.join("pkg/morpheus_component_bg.wasm"),
+                Some(project_dir.join("pkg/morpheus_component.js")),
+            ),
+            Target::Headless => (
+                project_dir.join("target/wasm32-unknown-unknown/release/morpheus_component.wasm"),
+                None,
+            ),
+        })
+    }
 
-[profile.release]
-opt-level = "z"
-lto = true
-codegen-units = 1
-panic = "abort"
-"#;
+    /// Build the project for an extra target, next to the primary build.
+    async fn build_variant(project_dir: &Path, target: ArtifactTarget) -> Result<Artifact> {
+        let out_dir = format!("pkg-{}", target.name());
+        let (wasm_path, js_path) = match target {
+            ArtifactTarget::Web | ArtifactTarget::Nodejs => {
+                let args = ["build", "--target", target.name(), "--release", "--out-dir", &out_dir];
+                Self::run_build("wasm-pack", &args, project_dir).await?;
+                (
+                    project_dir.join(&out_dir).join("morpheus_component_bg.wasm"),
+                    Some(project_dir.join(&out_dir).join("morpheus_component.js")),
+                )
+            }
+            ArtifactTarget::Wasi => {
+                let args = ["build", "--release", "--target", "wasm32-wasip1"];
+                Self::run_build("cargo", &args, project_dir).await?;
+                (project_dir.join("target/wasm32-wasip1/release/morpheus_component.wasm"), None)
+            }
+        };
 
-        fs::write(project_dir.join("Cargo.toml"), cargo_toml)
+        let wasm = fs::read(&wasm_path).await.map_err(|e| {
+            MorpheusError::CompilationError(format!("Failed to read {} WASM: {}", target, e))
+        })?;
+        let js_glue = match js_path {
+            Some(js_path) => fs::read_to_string(&js_path).await.map_err(|e| {
+                MorpheusError::CompilationError(format!("Failed to read {} JS glue: {}", target, e))
+            })?,
+            None => String::new(),
+        };
+        Ok(Artifact::new(target, &wasm, js_glue))
+    }
+
+    /// Run a build command in the project, turning a failure into readable
+    /// compilation errors.
+    async fn run_build(program: &str, args: &[&str], project_dir: &Path) -> Result<()> {
+        let output = tokio::process::Command::new(program)
+            .args(args)
+            .current_dir(project_dir)
+            .output()
             .await
-            .map_err(|e| MorpheusError::CompilationError(format!("Failed to write Cargo.toml: {}", e)))?;
+            .map_err(|e| MorpheusError::CompilationError(format!("Failed to run {}: {}", program, e)))?;
 
-        Ok(project_dir)
+        // Check for compilation errors
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let errors = Self::parse_errors(&stderr);
+
+            // Format errors for user
+            let error_msg = errors
+                .iter()
+                .map(|e| e.message.clone())
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            return Err(MorpheusError::CompilationError(format!(
+                "Compilation failed:\n{}",
+                error_msg
+            )));
+        }
+        Ok(())
     }
 
     /// Parse rustc error output into structured, user-friendly errors.
-    fn parse_errors(stderr: &str) -> Vec<CompilationError> {
+    pub(crate) fn parse_errors(stderr: &str) -> Vec<CompilationError> {
         let mut errors = Vec::new();
         let mut current_error: Option<CompilationError> = None;
         let mut help_text = String::new();
@@ -141,7 +552,7 @@ panic = "abort"
 
                 // Extract error code and message
                 let message = if let Some(bracket_start) = line.find("[") {
-                    if let Some(bracket_end) = line.find("]:") {
+                    if let Some(bracket_end) = line[bracket_start..].find("]:").map(|i| bracket_start + i) {
                         let error_code = &line[bracket_start+1..bracket_end];
                         let error_message = &line[bracket_end+2..].trim();
                         format!("{}: {}", error_code, error_message)
@@ -277,49 +688,123 @@ panic = "abort"
 #[async_trait]
 impl Compiler for SubprocessCompiler {
     async fn compile(&self, source: &str) -> Result<crate::CompilationResult> {
-        // Check tools are available
-        Self::check_tools()?;
+        // Check tools are available (headless builds only need cargo)
+        if self.target == Target::Web {
+            Self::check_tools()?;
+        }
 
         // Create temporary project
         let project_dir = self.create_project(source).await?;
 
-        // Compile with wasm-pack
-        let output = tokio::process::Command::new("wasm-pack")
-            .args(&["build", "--target", "web", "--release"])
-            .current_dir(&project_dir)
-            .output()
-            .await
-            .map_err(|e| MorpheusError::CompilationError(format!("Failed to run wasm-pack: {}", e)))?;
-
-        // Check for compilation errors
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let errors = Self::parse_errors(&stderr);
+        // Apply mechanical fixes; if that fails, the build reports the problem
+        let fixes = if self.autofix {
+            fix::autofix(&project_dir).await.unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let fixed_source = if fixes.is_empty() {
+            None
+        } else {
+            fs::read_to_string(project_dir.join("src/lib.rs")).await.ok()
+        };
 
-            // Format errors for user
-            let error_msg = errors
-                .iter()
-                .map(|e| e.message.clone())
-                .collect::<Vec<_>>()
-                .join("\n");
+        // Compile with wasm-pack, or cargo for headless components
+        let (wasm_path, js_path) = self.build(&project_dir).await?;
 
-            return Err(MorpheusError::CompilationError(format!(
-                "Compilation failed:\n{}",
-                error_msg
-            )));
-        }
+        // Pre-initialize if enabled
+        let snapshot = if self.snapshot {
+            Some(snapshot::preinitialize(&wasm_path).await?)
+        } else {
+            None
+        };
 
         // Read compiled WASM
-        let wasm_path = project_dir.join("pkg/morpheus_component_bg.wasm");
         let wasm_bytes = fs::read(&wasm_path).await.map_err(|e| {
             MorpheusError::CompilationError(format!("Failed to read compiled WASM: {}", e))
         })?;
 
+        // Apply post-compile transforms
+        let (wasm_bytes, notes) = if self.transforms.is_empty() {
+            (wasm_bytes, Vec::new())
+        } else {
+            let transforms = self.transforms.clone();
+            let applied = tokio::task::spawn_blocking(move || transforms.apply(wasm_bytes))
+                .await
+                .map_err(|e| MorpheusError::CompilationError(format!("Transforms panicked: {}", e)))?;
+            match applied {
+                Ok(applied) => applied,
+                Err(e) => {
+                    let _ = fs::remove_dir_all(&project_dir).await;
+                    return Err(e);
+                }
+            }
+        };
+
         // Read JavaScript glue code
-        let js_path = project_dir.join("pkg/morpheus_component.js");
-        let js_glue = fs::read_to_string(&js_path).await.map_err(|e| {
-            MorpheusError::CompilationError(format!("Failed to read JS glue code: {}", e))
-        })?;
+        let js_glue = match js_path {
+            Some(js_path) => fs::read_to_string(&js_path).await.map_err(|e| {
+                MorpheusError::CompilationError(format!("Failed to read JS glue code: {}", e))
+            })?,
+            None => String::new(),
+        };
+
+        // Build the extra targets; the primary build stands on its own
+        let mut variants = Vec::new();
+        let mut variant_warnings = Vec::new();
+        for &target in &self.extra_targets {
+            match Self::build_variant(&project_dir, target).await {
+                Ok(artifact) => variants.push(artifact),
+                Err(e) => variant_warnings.push(CompilationError {
+                    message: format!("{} build skipped: {}", target, e),
+                    file: None,
+                    line: None,
+                    column: None,
+                    severity: Severity::Warning,
+                }),
+            }
+        }
+
+        // Render and audit the browser build
+        let mut verification = None;
+        let mut verify_warnings = Vec::new();
+        if let (Some(verifier), Target::Web) = (&self.verifier, self.target) {
+            match verifier.verify(&wasm_bytes, &js_glue, &project_dir.join("verify")).await {
+                Ok(verified) => verification = Some(verified),
+                Err(e) => verify_warnings.push(CompilationError {
+                    message: format!("Not verified in a browser: {}", e),
+                    file: None,
+                    line: None,
+                    column: None,
+                    severity: Severity::Warning,
+                }),
+            }
+        }
+
+        // Record the resolved dependency tree
+        let sbom = match fs::read_to_string(project_dir.join("Cargo.lock")).await {
+            Ok(lockfile) => Sbom::from_lockfile(&lockfile, PACKAGE_NAME).ok(),
+            Err(_) => None,
+        };
+
+        // Check dependencies for known vulnerabilities
+        let mut diagnostics = match self.check_advisories(&project_dir).await {
+            Ok(diagnostics) => diagnostics,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&project_dir).await;
+                return Err(e);
+            }
+        };
+
+        diagnostics.extend(notes);
+        diagnostics.extend(variant_warnings);
+        diagnostics.extend(verify_warnings);
+        diagnostics.extend(fixes.into_iter().map(|fix| CompilationError {
+            message: format!("auto-fix: {}", fix),
+            file: Some("src/lib.rs".to_string()),
+            line: None,
+            column: None,
+            severity: Severity::Note,
+        }));
 
         // Clean up temporary directory (optional - could cache)
         let _ = fs::remove_dir_all(&project_dir).await;
@@ -327,6 +812,12 @@ impl Compiler for SubprocessCompiler {
         Ok(crate::CompilationResult {
             wasm_bytes,
             js_glue,
+            snapshot,
+            sbom,
+            diagnostics,
+            fixed_source,
+            variants,
+            verification,
         })
     }
 
@@ -355,6 +846,29 @@ impl Compiler for SubprocessCompiler {
 
         Ok(())
     }
+
+    async fn diagnostics(&self, source: &str) -> Result<Vec<CompilationError>> {
+        let project_dir = self.create_project(source).await?;
+
+        let output = tokio::process::Command::new("cargo")
+            .args(["check", "--message-format=json", "--target", "wasm32-unknown-unknown"])
+            .current_dir(&project_dir)
+            .output()
+            .await
+            .map_err(|e| MorpheusError::CompilationError(format!("Failed to run cargo check: {}", e)));
+        let _ = fs::remove_dir_all(&project_dir).await;
+        let output = output?;
+
+        let found = diagnostics::parse(&String::from_utf8_lossy(&output.stdout));
+        if found.is_empty() && !output.status.success() {
+            // Cargo failed before rustc had anything to say, e.g. resolving dependencies
+            return Err(MorpheusError::CompilationError(format!(
+                "Type check failed:\n{}",
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+        Ok(found)
+    }
 }
 
 #[cfg(test)]
@@ -381,6 +895,67 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_toolchain_fingerprint_names_both_tools() {
+        let fingerprint = SubprocessCompiler::toolchain_fingerprint();
+
+        assert!(fingerprint.contains("rustc"));
+        assert!(fingerprint.contains("wasm-pack"));
+    }
+
+    #[tokio::test]
+    async fn test_dependencies_follow_the_target() {
+        let compiler = match SubprocessCompiler::new().await {
+            Ok(c) => c,
+            Err(_) => return,
+        };
+
+        let web = compiler.dependencies();
+        let web_sys = web.iter().find(|d| d.name == "web-sys").expect("web-sys missing");
+        assert_eq!(web_sys.version, "0.3");
+        assert!(web_sys.features.contains(&"Document".to_string()));
+
+        let headless = compiler.with_target(Target::Headless).dependencies();
+        let names: Vec<_> = headless.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["serde", "serde_json"]);
+    }
+
+    #[tokio::test]
+    async fn test_host_crates_join_the_manifest() {
+        let compiler = match SubprocessCompiler::new().await {
+            Ok(c) => c,
+            Err(_) => return,
+        };
+        let types = HostCrate::path("shop-types", "/srv/shop/types").with_description("Order and Customer types");
+        let serde = HostCrate::git("serde", "https://github.com/acme/serde").at_rev("fork");
+
+        let compiler = compiler.with_target(Target::Headless).with_host_crates([types, serde]);
+        let manifest: toml::Table = compiler.manifest().parse().unwrap();
+        assert_eq!(manifest["dependencies"]["shop-types"]["path"].as_str(), Some("/srv/shop/types"));
+        assert_eq!(manifest["dependencies"]["serde"]["rev"].as_str(), Some("fork"));
+        assert_eq!(manifest["lib"]["crate-type"][0].as_str(), Some("cdylib"));
+
+        let dependencies = compiler.dependencies();
+        let names: Vec<_> = dependencies.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["serde", "serde_json", "shop-types"]);
+        assert_eq!(dependencies[2].description.as_deref(), Some("Order and Customer types"));
+    }
+
+    #[tokio::test]
+    async fn test_extra_targets_skip_the_primary() {
+        let compiler = match SubprocessCompiler::new().await {
+            Ok(c) => c,
+            Err(_) => return,
+        };
+        use ArtifactTarget::*;
+
+        let compiler = compiler.with_extra_targets([Web, Nodejs, Wasi, Nodejs]);
+        assert_eq!(compiler.extra_targets(), [Nodejs, Wasi]);
+
+        let headless = compiler.with_target(Target::Headless).with_extra_targets([Web]);
+        assert_eq!(headless.extra_targets(), [Web]);
+    }
+
     #[tokio::test]
     async fn test_compile_hello_world() {
         let compiler = match SubprocessCompiler::new().await {
@@ -411,6 +986,32 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_compile_headless() {
+        let compiler = match SubprocessCompiler::new().await {
+            Ok(c) => c.with_target(Target::Headless),
+            Err(_) => return,
+        };
+
+        let source = r#"
+            #[no_mangle]
+            pub extern "C" fn answer() -> i32 {
+                42
+            }
+        "#;
+
+        match compiler.compile(source).await {
+            Ok(result) => {
+                assert!(result.wasm_bytes.starts_with(b"\0asm"));
+                assert!(result.js_glue.is_empty());
+            }
+            Err(e) => {
+                // Needs the wasm32 target and network access for dependencies
+                println!("✗ Compilation failed: {}", e);
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_compile_error() {
         let compiler = match SubprocessCompiler::new().await {
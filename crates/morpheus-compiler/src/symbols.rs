@@ -0,0 +1,220 @@
+//! Symbols of a component's source, for code review.
+//!
+//! Reviewing AI-generated code means finding your way around it: what it
+//! defines, and where the thing under the cursor comes from. [`outline`]
+//! lists the items in a source file with their positions, nested the way
+//! they are in the file (methods under their `impl`, fields and variants
+//! under their type), and [`definition`] finds where a name used in the
+//! file is defined. Both work on the parsed source alone, without a build.
+
+use morpheus_core::errors::{MorpheusError, Result};
+use serde::Serialize;
+use syn::spanned::Spanned;
+
+/// An item defined in the source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Symbol {
+    pub name: String,
+
+    /// `fn`, `struct`, `enum`, `trait`, `impl`, `mod`, `const`, `static`,
+    /// `type`, `macro`, `method`, `field` or `variant`.
+    pub kind: &'static str,
+
+    /// Line of the name (1-indexed).
+    pub line: usize,
+
+    /// Column of the name (1-indexed).
+    pub column: usize,
+
+    /// Last line of the whole item.
+    pub end_line: usize,
+
+    /// Items inside this one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Symbol>,
+}
+
+/// The items of `source`, in file order.
+///
+/// Fails if the source doesn't parse.
+pub fn outline(source: &str) -> Result<Vec<Symbol>> {
+    let file = syn::parse_file(source)
+        .map_err(|e| MorpheusError::CompilationError(format!("Failed to parse source: {}", e)))?;
+    Ok(items(&file.items))
+}
+
+/// Where the name at `line` and `column` (1-indexed) is defined, if it is
+/// one of the source's own items. Types and functions are preferred over
+/// fields and methods of the same name.
+pub fn definition(source: &str, line: usize, column: usize) -> Option<Symbol> {
+    let name = word_at(source, line, column)?;
+    let symbols = outline(source).ok()?;
+    let mut found: Vec<Symbol> = Vec::new();
+    let mut stack: Vec<&Symbol> = symbols.iter().collect();
+    while let Some(symbol) = stack.pop() {
+        if symbol.name == name && symbol.kind != "impl" {
+            found.push(Symbol {
+                children: Vec::new(),
+                ..symbol.clone()
+            });
+        }
+        stack.extend(&symbol.children);
+    }
+    found.into_iter().min_by_key(|s| (matches!(s.kind, "method" | "field" | "variant"), s.line))
+}
+
+/// The identifier at `line` and `column`, if there is one.
+fn word_at(source: &str, line: usize, column: usize) -> Option<&str> {
+    let text = source.lines().nth(line.checked_sub(1)?)?;
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let at = text.char_indices().nth(column.checked_sub(1)?).map(|(i, _)| i)?;
+    let start = text[..at].rfind(|c| !is_ident(c)).map_or(0, |i| i + 1);
+    let end = text[at..].find(|c| !is_ident(c)).map_or(text.len(), |i| at + i);
+    let word = &text[start..end];
+    (!word.is_empty() && !word.starts_with(|c: char| c.is_ascii_digit())).then_some(word)
+}
+
+fn items(items: &[syn::Item]) -> Vec<Symbol> {
+    items.iter().filter_map(item).collect()
+}
+
+fn item(item: &syn::Item) -> Option<Symbol> {
+    let symbol = match item {
+        syn::Item::Fn(f) => symbol(&f.sig.ident, "fn", item),
+        syn::Item::Struct(s) => symbol(&s.ident, "struct", item).with(fields(&s.fields)),
+        syn::Item::Enum(e) => symbol(&e.ident, "enum", item).with(
+            e.variants
+                .iter()
+                .map(|v| symbol(&v.ident, "variant", v).with(fields(&v.fields)))
+                .collect(),
+        ),
+        syn::Item::Trait(t) => symbol(&t.ident, "trait", item).with(
+            t.items
+                .iter()
+                .filter_map(|i| match i {
+                    syn::TraitItem::Fn(f) => Some(symbol(&f.sig.ident, "method", i)),
+                    syn::TraitItem::Const(c) => Some(symbol(&c.ident, "const", i)),
+                    syn::TraitItem::Type(t) => Some(symbol(&t.ident, "type", i)),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        syn::Item::Impl(i) => {
+            let ty = &i.self_ty;
+            let name = match &i.trait_ {
+                Some((_, path, _)) => format!("impl {} for {}", tokens(path), tokens(ty)),
+                None => format!("impl {}", tokens(ty)),
+            };
+            let start = i.impl_token.span.start();
+            Symbol {
+                name,
+                kind: "impl",
+                line: start.line,
+                column: start.column + 1,
+                end_line: item.span().end().line,
+                children: i
+                    .items
+                    .iter()
+                    .filter_map(|i| match i {
+                        syn::ImplItem::Fn(f) => Some(symbol(&f.sig.ident, "method", i)),
+                        syn::ImplItem::Const(c) => Some(symbol(&c.ident, "const", i)),
+                        syn::ImplItem::Type(t) => Some(symbol(&t.ident, "type", i)),
+                        _ => None,
+                    })
+                    .collect(),
+            }
+        }
+        syn::Item::Mod(m) => {
+            let children = m.content.as_ref().map(|(_, content)| items(content)).unwrap_or_default();
+            symbol(&m.ident, "mod", item).with(children)
+        }
+        syn::Item::Const(c) => symbol(&c.ident, "const", item),
+        syn::Item::Static(s) => symbol(&s.ident, "static", item),
+        syn::Item::Type(t) => symbol(&t.ident, "type", item),
+        syn::Item::Macro(m) => symbol(m.ident.as_ref()?, "macro", item),
+        _ => return None,
+    };
+    Some(symbol)
+}
+
+fn fields(fields: &syn::Fields) -> Vec<Symbol> {
+    fields
+        .iter()
+        .filter_map(|f| Some(symbol(f.ident.as_ref()?, "field", f)))
+        .collect()
+}
+
+fn symbol(ident: &syn::Ident, kind: &'static str, item: &impl Spanned) -> Symbol {
+    let start = ident.span().start();
+    Symbol {
+        name: ident.to_string(),
+        kind,
+        line: start.line,
+        column: start.column + 1,
+        end_line: item.span().end().line,
+        children: Vec::new(),
+    }
+}
+
+fn tokens(tokens: &impl quote::ToTokens) -> String {
+    tokens.to_token_stream().to_string().replace(" < ", "<").replace(" >", ">").replace(" ,", ",")
+}
+
+impl Symbol {
+    fn with(mut self, children: Vec<Symbol>) -> Self {
+        self.children = children;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"use leptos::*;
+
+/// A todo
+#[derive(Clone)]
+pub struct Todo {
+    pub title: String,
+    done: bool,
+}
+
+impl Todo {
+    pub fn toggle(&mut self) {
+        self.done = !self.done;
+    }
+}
+
+#[component]
+pub fn TodoList() -> impl IntoView {
+    let todo = Todo { title: "Buy milk".to_string(), done: false };
+    view! { <p>{todo.title}</p> }
+}
+"#;
+
+    #[test]
+    fn test_outline_nests_members_under_their_items() {
+        let symbols = outline(SOURCE).unwrap();
+
+        let summary: Vec<_> = symbols.iter().map(|s| (s.kind, s.name.as_str(), s.line, s.end_line)).collect();
+        assert_eq!(summary, [("struct", "Todo", 5, 8), ("impl", "impl Todo", 10, 14), ("fn", "TodoList", 17, 20)]);
+        assert_eq!((symbols[0].children[1].name.as_str(), symbols[0].children[1].column), ("done", 5));
+        assert_eq!(symbols[1].children[0].kind, "method");
+        assert!(outline("fn broken( {").is_err());
+    }
+
+    #[test]
+    fn test_definition_of_the_name_under_the_cursor() {
+        // `Todo` in `let todo = Todo { .. }`
+        let todo = definition(SOURCE, 18, 17).unwrap();
+        assert_eq!((todo.kind, todo.line, todo.column), ("struct", 5, 12));
+
+        // `title` in `todo.title`
+        let title = definition(SOURCE, 19, 24).unwrap();
+        assert_eq!((title.kind, title.line), ("field", 6));
+
+        assert_eq!(definition(SOURCE, 18, 9), None, "local variables aren't items");
+        assert_eq!(definition(SOURCE, 99, 1), None);
+    }
+}
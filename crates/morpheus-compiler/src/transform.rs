@@ -0,0 +1,311 @@
+//! Post-compile artifact transforms.
+//!
+//! Once a module is built (and snapshotted, if enabled), the compiler passes
+//! it through its [`ArtifactTransform`]s in order. Transforms can rewrite
+//! the module (optimization, instrumentation, watermarking) or leave it
+//! alone and report on it; each one's output must still be a valid module.
+//!
+//! ```rust,ignore
+//! use morpheus_compiler::transform::{SizeReport, WasmOpt};
+//!
+//! let compiler = SubprocessCompiler::new()
+//!     .await?
+//!     .with_transform(WasmOpt::new())
+//!     .with_transform(SizeReport);
+//! ```
+//!
+//! Transforms run on a blocking thread, so they may call external tools.
+
+use crate::{CompilationError, Severity};
+use morpheus_core::errors::{MorpheusError, Result};
+use std::collections::BTreeMap;
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// A step applied to every compiled module.
+pub trait ArtifactTransform: Send + Sync {
+    /// Short name, used in errors and diagnostics.
+    fn name(&self) -> &str;
+
+    /// Return the transformed module.
+    fn transform(&self, wasm: Vec<u8>) -> Result<Vec<u8>>;
+
+    /// Findings about the transformed module, reported as notes.
+    fn inspect(&self, _wasm: &[u8]) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Transforms applied in order.
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    transforms: Vec<Arc<dyn ArtifactTransform>>,
+}
+
+impl Pipeline {
+    /// Append a transform.
+    pub fn push(&mut self, transform: impl ArtifactTransform + 'static) {
+        self.transforms.push(Arc::new(transform));
+    }
+
+    /// Whether there is nothing to apply.
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    /// Names of the transforms, in order.
+    pub fn names(&self) -> Vec<String> {
+        self.transforms.iter().map(|t| t.name().to_string()).collect()
+    }
+
+    /// Run every transform, returning the final module and their notes.
+    ///
+    /// Fails if a transform fails or produces an invalid module.
+    pub fn apply(&self, mut wasm: Vec<u8>) -> Result<(Vec<u8>, Vec<CompilationError>)> {
+        let mut notes = Vec::new();
+        for transform in &self.transforms {
+            let name = transform.name();
+            wasm = transform.transform(wasm).map_err(|e| {
+                let detail = match e {
+                    MorpheusError::CompilationError(message) => message,
+                    other => other.to_string(),
+                };
+                MorpheusError::CompilationError(format!("Transform '{}' failed: {}", name, detail))
+            })?;
+            wasmparser::validate(&wasm).map_err(|e| {
+                MorpheusError::CompilationError(format!("Transform '{}' produced an invalid module: {}", name, e))
+            })?;
+            notes.extend(transform.inspect(&wasm).into_iter().map(|message| CompilationError {
+                message: format!("{}: {}", name, message),
+                file: None,
+                line: None,
+                column: None,
+                severity: Severity::Note,
+            }));
+        }
+        Ok((wasm, notes))
+    }
+}
+
+/// Optimizes modules with Binaryen's
+/// [`wasm-opt`](https://github.com/WebAssembly/binaryen).
+#[derive(Debug, Clone)]
+pub struct WasmOpt {
+    level: String,
+}
+
+impl WasmOpt {
+    /// Optimize for size (`-Oz`).
+    pub fn new() -> Self {
+        Self { level: "z".to_string() }
+    }
+
+    /// Optimize at `level`: `0`-`4`, `s` or `z`.
+    pub fn with_level(mut self, level: impl Into<String>) -> Self {
+        self.level = level.into();
+        self
+    }
+
+    /// Check if `wasm-opt` is available.
+    pub fn check_tool() -> Result<()> {
+        match Command::new("wasm-opt").arg("--version").output() {
+            Ok(output) if output.status.success() => Ok(()),
+            _ => Err(MorpheusError::CompilationError(
+                "wasm-opt not found. Install Binaryen: https://github.com/WebAssembly/binaryen".to_string(),
+            )),
+        }
+    }
+}
+
+impl Default for WasmOpt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArtifactTransform for WasmOpt {
+    fn name(&self) -> &str {
+        "wasm-opt"
+    }
+
+    fn transform(&self, wasm: Vec<u8>) -> Result<Vec<u8>> {
+        static RUNS: AtomicU64 = AtomicU64::new(0);
+
+        Self::check_tool()?;
+        let run = RUNS.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir();
+        let input = dir.join(format!("morpheus-wasm-opt-{}-{}.wasm", std::process::id(), run));
+        let output = input.with_extension("opt.wasm");
+        std::fs::write(&input, &wasm)
+            .map_err(|e| MorpheusError::CompilationError(format!("Failed to write module: {}", e)))?;
+
+        let result = Command::new("wasm-opt")
+            .arg(format!("-O{}", self.level))
+            // wasm-bindgen modules use these post-MVP features
+            .args(["--enable-bulk-memory", "--enable-mutable-globals", "--enable-sign-ext"])
+            .arg(&input)
+            .arg("-o")
+            .arg(&output)
+            .output();
+        let _ = std::fs::remove_file(&input);
+        let result = result.map_err(|e| MorpheusError::CompilationError(format!("Failed to run wasm-opt: {}", e)))?;
+        if !result.status.success() {
+            let _ = std::fs::remove_file(&output);
+            return Err(MorpheusError::CompilationError(String::from_utf8_lossy(&result.stderr).to_string()));
+        }
+
+        let optimized = std::fs::read(&output)
+            .map_err(|e| MorpheusError::CompilationError(format!("Failed to read optimized module: {}", e)));
+        let _ = std::fs::remove_file(&output);
+        optimized
+    }
+}
+
+/// Reports the module's size by section; leaves the module unchanged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SizeReport;
+
+impl SizeReport {
+    /// Bytes per section, largest first. Custom sections are listed by name.
+    pub fn sections(wasm: &[u8]) -> Vec<(String, usize)> {
+        let mut sizes: BTreeMap<String, usize> = BTreeMap::new();
+        for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+            let Ok(payload) = payload else { break };
+            let name = match &payload {
+                wasmparser::Payload::CustomSection(reader) => format!("custom \"{}\"", reader.name()),
+                _ => match payload.as_section() {
+                    Some((id, _)) => section_name(id).to_string(),
+                    None => continue,
+                },
+            };
+            // A truncated module declares more bytes than it has
+            if let Some((_, range)) = payload.as_section() {
+                *sizes.entry(name).or_default() += range.end.min(wasm.len()).saturating_sub(range.start);
+            }
+        }
+        let mut sections: Vec<_> = sizes.into_iter().collect();
+        sections.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        sections
+    }
+}
+
+impl ArtifactTransform for SizeReport {
+    fn name(&self) -> &str {
+        "size-report"
+    }
+
+    fn transform(&self, wasm: Vec<u8>) -> Result<Vec<u8>> {
+        Ok(wasm)
+    }
+
+    fn inspect(&self, wasm: &[u8]) -> Vec<String> {
+        let sections: Vec<String> = Self::sections(wasm)
+            .into_iter()
+            .map(|(name, size)| format!("{} {}", name, size))
+            .collect();
+        vec![format!("{} bytes ({})", wasm.len(), sections.join(", "))]
+    }
+}
+
+/// Name of a standard section.
+fn section_name(id: u8) -> &'static str {
+    match id {
+        1 => "type",
+        2 => "import",
+        3 => "function",
+        4 => "table",
+        5 => "memory",
+        6 => "global",
+        7 => "export",
+        8 => "start",
+        9 => "element",
+        10 => "code",
+        11 => "data",
+        12 => "data count",
+        13 => "tag",
+        _ => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module() -> Vec<u8> {
+        wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 0) "hello, world")
+                (func (export "render") (result i32) i32.const 42))"#,
+        )
+        .expect("Invalid WAT")
+    }
+
+    /// Appends a custom section, like a watermarker would
+    struct Watermark;
+
+    impl ArtifactTransform for Watermark {
+        fn name(&self) -> &str {
+            "watermark"
+        }
+
+        fn transform(&self, mut wasm: Vec<u8>) -> Result<Vec<u8>> {
+            let name = b"morpheus";
+            let payload = b"v1";
+            wasm.push(0);
+            wasm.push((1 + name.len() + payload.len()) as u8);
+            wasm.push(name.len() as u8);
+            wasm.extend_from_slice(name);
+            wasm.extend_from_slice(payload);
+            Ok(wasm)
+        }
+    }
+
+    /// Breaks the module
+    struct Truncate;
+
+    impl ArtifactTransform for Truncate {
+        fn name(&self) -> &str {
+            "truncate"
+        }
+
+        fn transform(&self, wasm: Vec<u8>) -> Result<Vec<u8>> {
+            Ok(wasm[..wasm.len() / 2].to_vec())
+        }
+    }
+
+    #[test]
+    fn test_transforms_run_in_order_and_report() {
+        let mut pipeline = Pipeline::default();
+        pipeline.push(Watermark);
+        pipeline.push(SizeReport);
+        assert_eq!(pipeline.names(), ["watermark", "size-report"]);
+
+        let original = module();
+        let (wasm, notes) = pipeline.apply(original.clone()).unwrap();
+        assert_eq!(wasm.len(), original.len() + 13);
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].severity, Severity::Note);
+        assert!(notes[0].message.starts_with(&format!("size-report: {} bytes", wasm.len())));
+        assert!(notes[0].message.contains("custom \"morpheus\" 11"));
+    }
+
+    #[test]
+    fn test_invalid_output_fails_the_build() {
+        let mut pipeline = Pipeline::default();
+        pipeline.push(Truncate);
+        let error = pipeline.apply(module()).unwrap_err().to_string();
+        assert!(error.contains("'truncate' produced an invalid module"));
+    }
+
+    #[test]
+    fn test_section_sizes() {
+        let sections = SizeReport::sections(&module());
+        let size = |name: &str| sections.iter().find(|(n, _)| n == name).map(|(_, s)| *s);
+        assert!(size("data").unwrap() > "hello, world".len());
+        assert!(size("code").is_some());
+        assert!(size("export").is_some());
+        assert!(sections.windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+}
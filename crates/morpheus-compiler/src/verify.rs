@@ -0,0 +1,184 @@
+//! Headless browser verification.
+//!
+//! Compiling proves a component type-checks, not that it renders anything
+//! usable. With a [`Verifier`], the compiler loads each browser build into
+//! headless Chrome after it is built: the harness (`harness/verify.mjs`)
+//! instantiates the module, renders it into a page, measures how long that
+//! took and audits the result with
+//! [axe-core](https://github.com/dequelabs/axe-core). The outcome is
+//! returned in [`crate::CompilationResult::verification`]; what to do about
+//! it is up to the caller (see [`morpheus_core::accessibility::introduced`]
+//! and [`morpheus_core::performance::PerformanceBudget`]).
+//!
+//! The harness runs under Node.js with `puppeteer` and `axe-core` installed
+//! in the verifier's directory:
+//!
+//! ```text
+//! mkdir verifier && cd verifier && npm install puppeteer axe-core
+//! ```
+//!
+//! ```rust,ignore
+//! let compiler = SubprocessCompiler::new().await?.with_verifier(Verifier::new("verifier"));
+//! ```
+//!
+//! A build that can't be verified (no Node.js, or the harness failed) still
+//! succeeds, with a warning.
+
+use morpheus_core::accessibility::AccessibilityViolation;
+use morpheus_core::errors::{MorpheusError, Result};
+use morpheus_core::performance::PerformanceMeasurements;
+use morpheus_core::theme::Theme;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+use tokio::fs;
+
+/// The harness script, written next to the files it verifies.
+const HARNESS: &str = include_str!("../harness/verify.mjs");
+
+/// Where the harness page declares the default theme's CSS variables, so
+/// components styled with them render (and are audited) in its colors.
+const THEME_PLACEHOLDER: &str = "__MORPHEUS_THEME__";
+
+/// How long launching the browser, rendering and auditing may take.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What the harness found in a rendered component.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Verification {
+    /// The markup `render()` produced, as the browser parsed it.
+    pub html: String,
+
+    /// axe-core rules the rendered markup fails.
+    pub accessibility: Vec<AccessibilityViolation>,
+
+    /// First-render time, bundle size and long tasks.
+    pub performance: PerformanceMeasurements,
+}
+
+/// Renders and audits browser builds in headless Chrome.
+#[derive(Debug, Clone)]
+pub struct Verifier {
+    /// Directory with `puppeteer` and `axe-core` in its `node_modules`.
+    dir: PathBuf,
+}
+
+impl Verifier {
+    /// Run the harness in `dir`, where `puppeteer` and `axe-core` are
+    /// installed.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Directory the harness runs in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Check that Node.js and the harness's packages are available.
+    pub fn check(&self) -> Result<()> {
+        match Command::new("node").arg("--version").output() {
+            Ok(output) if output.status.success() => {}
+            _ => {
+                return Err(MorpheusError::CompilationError(
+                    "node not found. Install Node.js: https://nodejs.org".to_string(),
+                ))
+            }
+        }
+        for package in ["puppeteer", "axe-core"] {
+            if !self.dir.join("node_modules").join(package).is_dir() {
+                return Err(MorpheusError::CompilationError(format!(
+                    "{} not installed in {}. Install with: npm install puppeteer axe-core",
+                    package,
+                    self.dir.display()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Render the module `wasm`, with its web glue `js_glue`, and audit it.
+    /// Files are written to `work_dir`, which is created if needed.
+    pub async fn verify(&self, wasm: &[u8], js_glue: &str, work_dir: &Path) -> Result<Verification> {
+        self.check()?;
+
+        let write_error =
+            |e: std::io::Error| MorpheusError::CompilationError(format!("Failed to write harness files: {}", e));
+        fs::create_dir_all(work_dir).await.map_err(write_error)?;
+        fs::write(work_dir.join("component_bg.wasm"), wasm).await.map_err(write_error)?;
+        fs::write(work_dir.join("component.js"), js_glue).await.map_err(write_error)?;
+        fs::write(work_dir.join("verify.mjs"), harness()).await.map_err(write_error)?;
+
+        let harness = tokio::process::Command::new("node")
+            .arg(work_dir.join("verify.mjs"))
+            .arg(work_dir)
+            .current_dir(&self.dir)
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(TIMEOUT, harness)
+            .await
+            .map_err(|_| MorpheusError::CompilationError(format!("Verification timed out after {:?}", TIMEOUT)))?
+            .map_err(|e| MorpheusError::CompilationError(format!("Failed to run verification harness: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(MorpheusError::CompilationError(format!(
+                "Verification failed:\n{}",
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+        parse_report(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+/// The harness script, with the default theme's variables filled in.
+fn harness() -> String {
+    HARNESS.replace(THEME_PLACEHOLDER, &Theme::light().css(":root"))
+}
+
+/// Parse the JSON the harness prints.
+pub fn parse_report(json: &str) -> Result<Verification> {
+    Ok(serde_json::from_str(json)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morpheus_core::accessibility::Impact;
+
+    #[test]
+    fn test_parse_report() {
+        let json = r#"{
+            "html": "<button></button>",
+            "accessibility": [{
+                "rule": "button-name",
+                "impact": "critical",
+                "help": "Buttons must have discernible text",
+                "help_url": "https://dequeuniversity.com/rules/axe/4.10/button-name",
+                "targets": ["button"]
+            }],
+            "performance": { "first_render_ms": 18.5, "bundle_bytes": 48213, "long_tasks": 0 }
+        }"#;
+        let verification = parse_report(json).unwrap();
+        assert_eq!(verification.html, "<button></button>");
+        assert_eq!(verification.accessibility[0].impact, Impact::Critical);
+        assert_eq!(verification.accessibility[0].targets, vec!["button"]);
+        assert_eq!(verification.performance.bundle_bytes, 48213);
+        assert!(parse_report("Error: Component failed to render").is_err());
+    }
+
+    #[test]
+    fn test_harness_page_declares_the_default_theme() {
+        let harness = harness();
+        assert!(!harness.contains(THEME_PLACEHOLDER));
+        assert!(harness.contains("<style>\n/* The default theme's tokens, written in by verify.rs */\n:root {\n"));
+        assert!(harness.contains("  --morpheus-color-primary: #4f46e5;\n"));
+    }
+
+    #[tokio::test]
+    async fn test_verifier_requires_its_packages() {
+        let dir = std::env::temp_dir().join("morpheus-verifier-missing-test");
+        let error = Verifier::new(&dir).verify(b"\0asm", "", &dir.join("work")).await.unwrap_err();
+        assert!(error.to_string().contains("not installed") || error.to_string().contains("node not found"));
+    }
+}
@@ -0,0 +1,66 @@
+//! Benchmarks for binary deltas between module versions.
+//!
+//! Each hot reload diffs the new module against the one the client has, so
+//! `diff` runs once per reload and `apply` once per client. Modules are
+//! synthetic: pseudo-random bytes, and a next version with a small change
+//! in the middle, like a successive AI iteration.
+//!
+//! Run with `cargo bench -p morpheus-core --bench delta`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use morpheus_core::delta;
+
+/// Module sizes: a small component, a typical one, and a heavy one.
+const SIZES: &[usize] = &[64 * 1024, 512 * 1024, 2 * 1024 * 1024];
+
+/// Deterministic pseudo-random bytes (xorshift).
+fn module(len: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+/// `base` with 200 bytes inserted and 32 bytes rewritten mid-module.
+fn next_version(base: &[u8]) -> Vec<u8> {
+    let middle = base.len() / 2;
+    let mut target = base[..middle].to_vec();
+    target.extend(module(200, 7));
+    target.extend(module(32, 11));
+    target.extend_from_slice(&base[middle + 32..]);
+    target
+}
+
+fn bench_diff(c: &mut Criterion) {
+    let mut group = c.benchmark_group("delta/diff");
+    for &size in SIZES {
+        let base = module(size, 42);
+        let target = next_version(&base);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| delta::diff(black_box(&base), black_box(&target)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_apply(c: &mut Criterion) {
+    let mut group = c.benchmark_group("delta/apply");
+    for &size in SIZES {
+        let base = module(size, 42);
+        let patch = delta::diff(&base, &next_version(&base));
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| delta::apply(black_box(&base), black_box(&patch)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_diff, bench_apply);
+criterion_main!(benches);
@@ -0,0 +1,151 @@
+//! Accessibility audits of rendered components.
+//!
+//! Generated components are checked in a headless browser with
+//! [axe-core](https://github.com/dequelabs/axe-core) before they become a
+//! version. Each failed rule is an [`AccessibilityViolation`], with axe's
+//! [`Impact`] and the selectors of the elements that fail it.
+//!
+//! A proposal is judged by what it *introduces*: a component that already
+//! had an unlabelled button shouldn't block every later change to it. Use
+//! [`introduced`] to compare an audit against the live version's, and
+//! [`describe`] to tell the AI what to fix.
+//!
+//! ```rust
+//! use morpheus_core::accessibility::{introduced, AccessibilityViolation, Impact};
+//!
+//! let live = vec![AccessibilityViolation::new("image-alt", Impact::Critical, "Images need alt text", ["img.logo"])];
+//! let proposed = vec![
+//!     AccessibilityViolation::new("image-alt", Impact::Critical, "Images need alt text", ["img.logo", "img.chart"]),
+//!     AccessibilityViolation::new("region", Impact::Moderate, "Content should be in landmarks", ["p"]),
+//! ];
+//!
+//! let new = introduced(&proposed, &live, Impact::Serious);
+//! assert_eq!(new.len(), 1);
+//! assert_eq!(new[0].targets, vec!["img.chart"]);
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// How much a violation gets in users' way, as axe-core rates it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Impact {
+    Minor,
+    Moderate,
+    Serious,
+    Critical,
+}
+
+impl std::fmt::Display for Impact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Impact::Minor => "minor",
+            Impact::Moderate => "moderate",
+            Impact::Serious => "serious",
+            Impact::Critical => "critical",
+        };
+        f.write_str(name)
+    }
+}
+
+/// An axe-core rule the rendered component fails.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AccessibilityViolation {
+    /// Rule ID, e.g. `button-name`.
+    pub rule: String,
+
+    pub impact: Impact,
+
+    /// What the rule asks for, e.g. "Buttons must have discernible text".
+    pub help: String,
+
+    /// Rule documentation.
+    #[serde(default)]
+    pub help_url: Option<String>,
+
+    /// CSS selectors of the failing elements.
+    pub targets: Vec<String>,
+}
+
+impl AccessibilityViolation {
+    pub fn new(
+        rule: impl Into<String>,
+        impact: Impact,
+        help: impl Into<String>,
+        targets: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            rule: rule.into(),
+            impact,
+            help: help.into(),
+            help_url: None,
+            targets: targets.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// The violations in `proposed` of at least `min_impact` that `baseline`
+/// doesn't have, each narrowed to the elements that newly fail it.
+pub fn introduced(
+    proposed: &[AccessibilityViolation],
+    baseline: &[AccessibilityViolation],
+    min_impact: Impact,
+) -> Vec<AccessibilityViolation> {
+    proposed
+        .iter()
+        .filter(|violation| violation.impact >= min_impact)
+        .filter_map(|violation| {
+            let known: Vec<&String> = baseline
+                .iter()
+                .filter(|old| old.rule == violation.rule)
+                .flat_map(|old| &old.targets)
+                .collect();
+            let targets: Vec<String> =
+                violation.targets.iter().filter(|target| !known.contains(target)).cloned().collect();
+            (!targets.is_empty()).then(|| AccessibilityViolation {
+                targets,
+                ..violation.clone()
+            })
+        })
+        .collect()
+}
+
+/// One line per violation, e.g.
+/// `button-name (critical): Buttons must have discernible text - button.close`.
+pub fn describe(violations: &[AccessibilityViolation]) -> String {
+    violations
+        .iter()
+        .map(|violation| {
+            format!(
+                "{} ({}): {} - {}",
+                violation.rule,
+                violation.impact,
+                violation.help,
+                violation.targets.join(", ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_existing_violations_are_not_introduced() {
+        let live = vec![AccessibilityViolation::new("button-name", Impact::Critical, "Name buttons", ["button"])];
+        assert!(introduced(&live, &live, Impact::Serious).is_empty());
+        assert_eq!(introduced(&live, &[], Impact::Serious), live);
+        assert_eq!(introduced(&live, &[], Impact::Critical).len(), 1);
+
+        let moved = vec![AccessibilityViolation::new("label", Impact::Critical, "Label inputs", ["button"])];
+        assert_eq!(introduced(&moved, &live, Impact::Serious), moved);
+
+        assert_eq!(describe(&live), "button-name (critical): Name buttons - button");
+        assert_eq!(serde_json::to_value(Impact::Serious).unwrap(), "serious");
+        assert!(Impact::Critical > Impact::Serious && Impact::Moderate < Impact::Serious);
+    }
+}
@@ -0,0 +1,394 @@
+//! Transitions and animations.
+//!
+//! Two tools, for two kinds of component:
+//!
+//! - [`transition()`] declares how an element enters and leaves, as
+//!   `data-transition-*` attributes. The host animates keyed elements
+//!   (`data-key`) that appear or disappear between renders, so components
+//!   that return HTML strings get list insertion and removal animations
+//!   without writing CSS keyframes.
+//! - [`Tween`] and [`Spring`] produce values over time, and an [`Animator`]
+//!   turns each frame's value into a message for the component, driven by
+//!   `requestAnimationFrame` in the browser.
+//!
+//! ```rust
+//! use morpheus_core::animation::{transition, Easing};
+//!
+//! let fade = transition().enter("opacity-0 -translate-y-2").leave("opacity-0").duration(200).easing(Easing::EaseOut);
+//! let row = format!(r#"<li data-key="7"{}>Buy milk</li>"#, fade.to_html_attrs());
+//! assert!(row.contains(r#"data-transition-enter="opacity-0 -translate-y-2""#));
+//! ```
+
+use crate::component::View;
+
+/// Classes an entering element starts with; removed on the next frame.
+pub const ENTER_ATTR: &str = "data-transition-enter";
+
+/// Classes a leaving element ends with before it is removed.
+pub const LEAVE_ATTR: &str = "data-transition-leave";
+
+/// Transition length in milliseconds.
+pub const DURATION_ATTR: &str = "data-transition-duration";
+
+/// CSS timing function of the transition.
+pub const EASING_ATTR: &str = "data-transition-easing";
+
+/// Default transition length in milliseconds.
+pub const DEFAULT_DURATION_MS: u32 = 150;
+
+/// Speed curve of a transition or tween.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    #[default]
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    /// Progress at time `t`, both in `0.0..=1.0`; close to the CSS
+    /// keyword of the same name.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut if t < 0.5 => 4.0 * t * t * t,
+            Easing::EaseInOut => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+        }
+    }
+
+    /// CSS `transition-timing-function` value.
+    pub fn css(self) -> &'static str {
+        match self {
+            Easing::Linear => "linear",
+            Easing::EaseIn => "ease-in",
+            Easing::EaseOut => "ease-out",
+            Easing::EaseInOut => "ease-in-out",
+        }
+    }
+}
+
+/// How an element enters and leaves; see [`transition()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    /// Classes the element starts with when it appears.
+    pub enter: String,
+    /// Classes the element ends with before it is removed.
+    pub leave: String,
+    pub duration_ms: u32,
+    pub easing: Easing,
+}
+
+/// Start declaring a transition.
+pub fn transition() -> Transition {
+    Transition {
+        enter: String::new(),
+        leave: String::new(),
+        duration_ms: DEFAULT_DURATION_MS,
+        easing: Easing::default(),
+    }
+}
+
+impl Transition {
+    /// Classes the element starts with when it appears, e.g. `opacity-0`.
+    pub fn enter(mut self, classes: impl Into<String>) -> Self {
+        self.enter = classes.into();
+        self
+    }
+
+    /// Classes the element ends with before it is removed.
+    pub fn leave(mut self, classes: impl Into<String>) -> Self {
+        self.leave = classes.into();
+        self
+    }
+
+    pub fn duration(mut self, ms: u32) -> Self {
+        self.duration_ms = ms;
+        self
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// The `data-transition-*` attributes declaring this transition.
+    pub fn attrs(&self) -> Vec<(String, String)> {
+        let mut attrs = Vec::new();
+        if !self.enter.is_empty() {
+            attrs.push((ENTER_ATTR.to_string(), self.enter.clone()));
+        }
+        if !self.leave.is_empty() {
+            attrs.push((LEAVE_ATTR.to_string(), self.leave.clone()));
+        }
+        attrs.push((DURATION_ATTR.to_string(), self.duration_ms.to_string()));
+        attrs.push((EASING_ATTR.to_string(), self.easing.css().to_string()));
+        attrs
+    }
+
+    /// The attributes as HTML, with a leading space, for components that
+    /// render strings.
+    pub fn to_html_attrs(&self) -> String {
+        self.attrs()
+            .into_iter()
+            .map(|(name, value)| format!(" {}=\"{}\"", name, value.replace('&', "&amp;").replace('"', "&quot;")))
+            .collect()
+    }
+
+    /// Add the attributes to an element view; text is returned unchanged.
+    pub fn apply(&self, view: View) -> View {
+        match view {
+            View::Element { tag, mut attrs, children } => {
+                attrs.extend(self.attrs());
+                View::Element { tag, attrs, children }
+            }
+            text => text,
+        }
+    }
+}
+
+/// A value changing over time.
+pub trait Motion {
+    /// Move `dt_ms` milliseconds forward and return the new value.
+    fn advance(&mut self, dt_ms: f64) -> f64;
+
+    /// Whether the value has reached its target.
+    fn is_done(&self) -> bool;
+}
+
+/// Interpolates between two values over a fixed time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tween {
+    pub from: f64,
+    pub to: f64,
+    pub duration_ms: f64,
+    pub easing: Easing,
+    elapsed_ms: f64,
+}
+
+impl Tween {
+    pub fn new(from: f64, to: f64, duration_ms: f64) -> Self {
+        Self {
+            from,
+            to,
+            duration_ms,
+            easing: Easing::default(),
+            elapsed_ms: 0.0,
+        }
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Value `elapsed_ms` after the start.
+    pub fn value_at(&self, elapsed_ms: f64) -> f64 {
+        let t = if self.duration_ms > 0.0 { elapsed_ms / self.duration_ms } else { 1.0 };
+        self.from + (self.to - self.from) * self.easing.apply(t)
+    }
+}
+
+impl Motion for Tween {
+    fn advance(&mut self, dt_ms: f64) -> f64 {
+        self.elapsed_ms += dt_ms.max(0.0);
+        self.value_at(self.elapsed_ms)
+    }
+
+    fn is_done(&self) -> bool {
+        self.elapsed_ms >= self.duration_ms
+    }
+}
+
+/// Moves a value towards a target like a damped spring, so changing the
+/// target mid-flight stays smooth.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spring {
+    pub position: f64,
+    pub velocity: f64,
+    pub target: f64,
+    pub stiffness: f64,
+    pub damping: f64,
+}
+
+impl Spring {
+    /// Distance and speed below which the spring is at rest.
+    const REST: f64 = 0.001;
+
+    /// Longest simulation step, in seconds; long frames are split up.
+    const MAX_STEP: f64 = 1.0 / 120.0;
+
+    /// A spring at `from` heading to `to`, with a quick, barely
+    /// overshooting response.
+    pub fn new(from: f64, to: f64) -> Self {
+        Self {
+            position: from,
+            velocity: 0.0,
+            target: to,
+            stiffness: 170.0,
+            damping: 26.0,
+        }
+    }
+
+    pub fn stiffness(mut self, stiffness: f64) -> Self {
+        self.stiffness = stiffness;
+        self
+    }
+
+    pub fn damping(mut self, damping: f64) -> Self {
+        self.damping = damping;
+        self
+    }
+}
+
+impl Motion for Spring {
+    fn advance(&mut self, dt_ms: f64) -> f64 {
+        let mut remaining = dt_ms.max(0.0) / 1000.0;
+        while remaining > 0.0 && !self.is_done() {
+            let dt = remaining.min(Self::MAX_STEP);
+            let force = self.stiffness * (self.target - self.position) - self.damping * self.velocity;
+            self.velocity += force * dt;
+            self.position += self.velocity * dt;
+            remaining -= dt;
+        }
+        if self.is_done() {
+            self.position = self.target;
+            self.velocity = 0.0;
+        }
+        self.position
+    }
+
+    fn is_done(&self) -> bool {
+        (self.target - self.position).abs() < Self::REST && self.velocity.abs() < Self::REST
+    }
+}
+
+/// Turns a [`Motion`] into a message per animation frame.
+pub struct Animator<M, Msg> {
+    motion: M,
+    to_msg: Box<dyn FnMut(f64) -> Msg>,
+    last_frame: Option<f64>,
+    finished: bool,
+}
+
+impl<M: Motion, Msg> Animator<M, Msg> {
+    pub fn new(motion: M, to_msg: impl FnMut(f64) -> Msg + 'static) -> Self {
+        Self {
+            motion,
+            to_msg: Box::new(to_msg),
+            last_frame: None,
+            finished: false,
+        }
+    }
+
+    /// The message for the frame at `now_ms` (a `requestAnimationFrame`
+    /// timestamp), or `None` once the final value has been sent.
+    pub fn frame(&mut self, now_ms: f64) -> Option<Msg> {
+        if self.finished {
+            return None;
+        }
+        let dt = self.last_frame.map_or(0.0, |last| now_ms - last);
+        self.last_frame = Some(now_ms);
+        let value = self.motion.advance(dt);
+        self.finished = self.motion.is_done();
+        Some((self.to_msg)(value))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl<M: Motion + 'static, Msg: 'static> Animator<M, Msg> {
+    /// Run the animation on `requestAnimationFrame`, dispatching each
+    /// frame's message (e.g. to [`Program::dispatch`]) until it finishes.
+    ///
+    /// [`Program::dispatch`]: crate::cmd::Program::dispatch
+    pub fn run(mut self, mut dispatch: impl FnMut(Msg) + 'static) {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        use wasm_bindgen::closure::Closure;
+
+        let callback: Rc<RefCell<Option<Closure<dyn FnMut(f64)>>>> = Rc::new(RefCell::new(None));
+        let next = callback.clone();
+        *callback.borrow_mut() = Some(Closure::new(move |now: f64| match self.frame(now) {
+            Some(msg) => {
+                dispatch(msg);
+                request_frame(&next);
+            }
+            // Drop the closure, breaking the reference cycle
+            None => drop(next.borrow_mut().take()),
+        }));
+        request_frame(&callback);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn request_frame(callback: &std::rc::Rc<std::cell::RefCell<Option<wasm_bindgen::closure::Closure<dyn FnMut(f64)>>>>) {
+    use wasm_bindgen::JsCast;
+
+    if let (Some(window), Some(callback)) = (web_sys::window(), callback.borrow().as_ref()) {
+        let _ = window.request_animation_frame(callback.as_ref().unchecked_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transition_attrs() {
+        let fade = transition().enter("opacity-0").leave("opacity-0 \"x\"").duration(300).easing(Easing::EaseInOut);
+
+        assert_eq!(
+            fade.to_html_attrs(),
+            " data-transition-enter=\"opacity-0\" data-transition-leave=\"opacity-0 &quot;x&quot;\" \
+             data-transition-duration=\"300\" data-transition-easing=\"ease-in-out\""
+        );
+        let view = fade.apply(View::Element {
+            tag: "li".to_string(),
+            attrs: vec![("data-key".to_string(), "1".to_string())],
+            children: vec![],
+        });
+        let View::Element { attrs, .. } = view else { panic!("Expected Element variant") };
+        assert_eq!(attrs.len(), 5);
+        assert_eq!(transition().attrs().len(), 2);
+    }
+
+    #[test]
+    fn test_easing_endpoints() {
+        for easing in [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+            assert_eq!(easing.apply(2.0), 1.0);
+        }
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+    }
+
+    #[test]
+    fn test_animator_sends_a_message_per_frame_until_done() {
+        let mut animator = Animator::new(Tween::new(0.0, 100.0, 32.0).easing(Easing::Linear), |v| v as i64);
+
+        let frames: Vec<i64> = [1000.0, 1016.0, 1032.0, 1048.0].into_iter().filter_map(|t| animator.frame(t)).collect();
+
+        assert_eq!(frames, [0, 50, 100]);
+    }
+
+    #[test]
+    fn test_spring_settles_on_target() {
+        let mut spring = Spring::new(0.0, 1.0);
+        let mut peak: f64 = 0.0;
+        for _ in 0..120 {
+            peak = peak.max(spring.advance(16.0));
+        }
+
+        assert!(spring.is_done());
+        assert_eq!(spring.position, 1.0);
+        assert!(peak < 1.05);
+
+        spring.target = 0.0;
+        assert!(!spring.is_done());
+        assert!(spring.advance(1000.0 / 60.0) < 1.0);
+    }
+}
@@ -0,0 +1,152 @@
+//! Build targets, and picking a component's build for where it runs.
+//!
+//! A component is always built for the browser (`wasm-pack --target web`),
+//! and can be built for other targets in the same job: a Node.js package
+//! (`--target nodejs`) or a WASI module, for server-side harnesses. Each
+//! build is an [`Artifact`]; [`select`] picks the one to use in an
+//! [`Environment`].
+//!
+//! ```rust
+//! use morpheus_core::artifact::{select, Artifact, ArtifactTarget, Environment};
+//!
+//! let artifacts = vec![
+//!     Artifact::new(ArtifactTarget::Web, b"\0asm", "export default init;"),
+//!     Artifact::new(ArtifactTarget::Nodejs, b"\0asm", "module.exports = {};"),
+//! ];
+//!
+//! assert_eq!(select(&artifacts, Environment::Browser).unwrap().target, ArtifactTarget::Web);
+//! assert_eq!(select(&artifacts, Environment::Server).unwrap().target, ArtifactTarget::Nodejs);
+//! ```
+
+use crate::errors::{MorpheusError, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// What a build is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactTarget {
+    /// ES module glue for browsers (`wasm-pack --target web`).
+    Web,
+    /// CommonJS glue for Node.js (`wasm-pack --target nodejs`).
+    Nodejs,
+    /// A plain `wasm32-wasip1` module, without glue.
+    Wasi,
+}
+
+impl ArtifactTarget {
+    pub const ALL: [ArtifactTarget; 3] = [ArtifactTarget::Web, ArtifactTarget::Nodejs, ArtifactTarget::Wasi];
+
+    /// Name used in configuration and URLs.
+    pub fn name(self) -> &'static str {
+        match self {
+            ArtifactTarget::Web => "web",
+            ArtifactTarget::Nodejs => "nodejs",
+            ArtifactTarget::Wasi => "wasi",
+        }
+    }
+}
+
+impl fmt::Display for ArtifactTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ArtifactTarget {
+    type Err = MorpheusError;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|target| target.name() == name.trim())
+            .ok_or_else(|| {
+                MorpheusError::Other(format!("Unknown build target '{}' (expected web, nodejs or wasi)", name))
+            })
+    }
+}
+
+/// Where a component is about to run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Environment {
+    #[default]
+    Browser,
+    /// A server-side harness, e.g. tests or pre-rendering.
+    Server,
+}
+
+impl Environment {
+    /// Targets that run here, best first.
+    pub fn targets(self) -> &'static [ArtifactTarget] {
+        match self {
+            Environment::Browser => &[ArtifactTarget::Web],
+            Environment::Server => &[ArtifactTarget::Nodejs, ArtifactTarget::Wasi],
+        }
+    }
+}
+
+/// One build of a component.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Artifact {
+    pub target: ArtifactTarget,
+    pub wasm_base64: String,
+    /// Glue generated with the module; empty for [`ArtifactTarget::Wasi`]
+    #[serde(default)]
+    pub js_glue: String,
+}
+
+impl Artifact {
+    pub fn new(target: ArtifactTarget, wasm: &[u8], js_glue: impl Into<String>) -> Self {
+        Self {
+            target,
+            wasm_base64: base64::engine::general_purpose::STANDARD.encode(wasm),
+            js_glue: js_glue.into(),
+        }
+    }
+
+    /// The module's bytes.
+    pub fn wasm(&self) -> Result<Vec<u8>> {
+        base64::engine::general_purpose::STANDARD
+            .decode(&self.wasm_base64)
+            .map_err(|e| MorpheusError::LoadError(format!("{} artifact is not valid base64: {}", self.target, e)))
+    }
+}
+
+/// The artifact to use in `environment`, if any was built for it.
+pub fn select(artifacts: &[Artifact], environment: Environment) -> Option<&Artifact> {
+    environment
+        .targets()
+        .iter()
+        .find_map(|target| artifacts.iter().find(|artifact| artifact.target == *target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_names_round_trip() {
+        for target in ArtifactTarget::ALL {
+            assert_eq!(target.name().parse::<ArtifactTarget>().unwrap(), target);
+            assert_eq!(serde_json::to_value(target).unwrap(), target.name());
+        }
+        assert!("wasm32".parse::<ArtifactTarget>().is_err());
+    }
+
+    #[test]
+    fn test_select_prefers_best_target() {
+        let wasi = Artifact::new(ArtifactTarget::Wasi, b"wasi", "");
+        let web = Artifact::new(ArtifactTarget::Web, b"web", "glue");
+
+        let artifacts = vec![wasi.clone(), web];
+        assert_eq!(select(&artifacts, Environment::Server), Some(&wasi));
+        assert_eq!(select(&artifacts[..1], Environment::Browser), None);
+        assert_eq!(wasi.wasm().unwrap(), b"wasi");
+    }
+}
@@ -0,0 +1,567 @@
+//! Brokered access to sensitive host APIs.
+//!
+//! Components reach the clipboard, the user's files, desktop notifications
+//! and the user's position only through host imports, and every call goes
+//! through a [`PermissionBroker`]. The broker denies an API unless the
+//! component's manifest grants the matching [`ApiPermission`]; reading the
+//! clipboard, showing notifications and locating the user additionally need
+//! the user's consent, asked once by the host. Notifications are also rate
+//! limited per component by a [`NotificationLimiter`], and positions pass
+//! through a [`LocationGate`], which coarsens them to the granted
+//! [`GeoPrecision`] and caches and rate limits them so a component can't
+//! track the user.
+//!
+//! ```rust,ignore
+//! #[wasm_bindgen]
+//! extern "C" {
+//!     #[wasm_bindgen(js_namespace = morpheus, js_name = clipboardWrite)]
+//!     fn morpheus_clipboard_write(text: &str);
+//!     #[wasm_bindgen(js_namespace = morpheus, js_name = clipboardRead)]
+//!     fn morpheus_clipboard_read();
+//!     #[wasm_bindgen(js_namespace = morpheus, js_name = pickFiles)]
+//!     fn morpheus_pick_files(accept: &str, multiple: bool) -> u32;
+//!     #[wasm_bindgen(js_namespace = morpheus, js_name = notify)]
+//!     fn morpheus_notify(title: &str, body: &str);
+//!     #[wasm_bindgen(js_namespace = morpheus, js_name = getPosition)]
+//!     fn morpheus_get_position();
+//! }
+//! ```
+//!
+//! Results come back through component exports: the clipboard text through
+//! [`CLIPBOARD_EXPORT`], the position through [`POSITION_EXPORT`], and each
+//! picked file as [`FILE_START_EXPORT`], its
+//! contents in chunks through [`FILE_CHUNK_EXPORT`], then
+//! [`FILE_END_EXPORT`]. Components see the bytes only, never a `File`
+//! handle; [`FileAssembler`] puts the chunks back together.
+
+use crate::errors::{MorpheusError, Result};
+use crate::permissions::{ApiPermission, GeoPrecision, Permissions};
+use crate::shared::SharedType;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Clipboard read import, `morpheus.clipboardRead()`.
+pub const CLIPBOARD_READ_IMPORT: &str = "clipboardRead";
+
+/// Clipboard write import, `morpheus.clipboardWrite(text)`.
+pub const CLIPBOARD_WRITE_IMPORT: &str = "clipboardWrite";
+
+/// File picker import, `morpheus.pickFiles(accept, multiple)`, returning a
+/// request ID.
+pub const PICK_FILES_IMPORT: &str = "pickFiles";
+
+/// Notification import, `morpheus.notify(title, body)`.
+pub const NOTIFY_IMPORT: &str = "notify";
+
+/// Position import, `morpheus.getPosition()`.
+pub const GET_POSITION_IMPORT: &str = "getPosition";
+
+/// Export receiving clipboard text, as `on_clipboard(text: &str)`.
+pub const CLIPBOARD_EXPORT: &str = "on_clipboard";
+
+/// Export receiving the user's position, as
+/// `on_position(latitude: f64, longitude: f64, accuracy_m: f64)`.
+pub const POSITION_EXPORT: &str = "on_position";
+
+/// Export announcing a picked file, as
+/// `on_file_start(request: u32, name: &str, mime: &str, size: f64)`.
+pub const FILE_START_EXPORT: &str = "on_file_start";
+
+/// Export receiving file contents, as
+/// `on_file_chunk(request: u32, name: &str, chunk: &[u8])`.
+pub const FILE_CHUNK_EXPORT: &str = "on_file_chunk";
+
+/// Export called after a file's last chunk, as
+/// `on_file_end(request: u32, name: &str)`.
+pub const FILE_END_EXPORT: &str = "on_file_end";
+
+/// Largest file [`FileAssembler`] accepts by default.
+pub const DEFAULT_MAX_FILE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Notifications one component may show per [`NOTIFICATION_WINDOW`] by
+/// default.
+pub const DEFAULT_NOTIFICATIONS_PER_WINDOW: usize = 5;
+
+/// Window the notification rate limit applies over.
+pub const NOTIFICATION_WINDOW: Duration = Duration::from_secs(60);
+
+/// Grid size, in degrees, of [`GeoPrecision::City`] positions (about 11 km
+/// of latitude).
+pub const CITY_GRID_DEGREES: f64 = 0.1;
+
+/// Accuracy, in metres, reported with [`GeoPrecision::City`] positions.
+pub const CITY_ACCURACY_METERS: f64 = 10_000.0;
+
+/// How long [`LocationGate`] answers with the same position.
+pub const DEFAULT_POSITION_MAX_AGE: Duration = Duration::from_secs(60);
+
+/// Fresh positions one component may get per [`POSITION_WINDOW`] by
+/// default.
+pub const DEFAULT_POSITIONS_PER_WINDOW: usize = 10;
+
+/// Window the position rate limit applies over.
+pub const POSITION_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// A sensitive host API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum HostApi {
+    ClipboardRead,
+    ClipboardWrite,
+    PickFiles,
+    Notify,
+    GetPosition,
+}
+
+impl HostApi {
+    pub const ALL: [HostApi; 5] = [
+        HostApi::ClipboardRead,
+        HostApi::ClipboardWrite,
+        HostApi::PickFiles,
+        HostApi::Notify,
+        HostApi::GetPosition,
+    ];
+
+    /// Permission the component's manifest must grant.
+    pub fn permission(self) -> ApiPermission {
+        match self {
+            HostApi::ClipboardRead | HostApi::ClipboardWrite => ApiPermission::Clipboard,
+            HostApi::PickFiles => ApiPermission::Files,
+            HostApi::Notify => ApiPermission::Notifications,
+            HostApi::GetPosition => ApiPermission::Geolocation,
+        }
+    }
+
+    /// Name of the host import.
+    pub fn import(self) -> &'static str {
+        match self {
+            HostApi::ClipboardRead => CLIPBOARD_READ_IMPORT,
+            HostApi::ClipboardWrite => CLIPBOARD_WRITE_IMPORT,
+            HostApi::PickFiles => PICK_FILES_IMPORT,
+            HostApi::Notify => NOTIFY_IMPORT,
+            HostApi::GetPosition => GET_POSITION_IMPORT,
+        }
+    }
+
+    /// Whether the user confirms each session's first use. The file picker
+    /// is its own confirmation; for notifications and positions the host
+    /// asks through the browser's own permission prompt.
+    pub fn needs_confirmation(self) -> bool {
+        matches!(self, HostApi::ClipboardRead | HostApi::Notify | HostApi::GetPosition)
+    }
+}
+
+/// Whether a host API call may go ahead.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum Decision {
+    Allow,
+    /// Ask the user first and report the answer with
+    /// [`PermissionBroker::answer`].
+    Confirm,
+    Deny { reason: String },
+}
+
+/// A host API and the broker's decision on it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ApiStatus {
+    pub api: HostApi,
+    /// Host import name
+    pub import: String,
+    #[serde(flatten)]
+    pub decision: Decision,
+}
+
+/// Decides a component's host API calls.
+#[derive(Debug, Clone)]
+pub struct PermissionBroker {
+    permissions: Permissions,
+    answers: HashMap<HostApi, bool>,
+}
+
+impl PermissionBroker {
+    /// A broker for a component granted `permissions`.
+    pub fn new(permissions: Permissions) -> Self {
+        Self {
+            permissions,
+            answers: HashMap::new(),
+        }
+    }
+
+    /// Decide a call to `api`.
+    pub fn decide(&self, api: HostApi) -> Decision {
+        let permission = api.permission();
+        if !self.permissions.apis.contains(&permission) {
+            return Decision::Deny {
+                reason: format!("{} needs the {:?} permission", api.import(), permission),
+            };
+        }
+        match self.answers.get(&api) {
+            Some(true) => Decision::Allow,
+            Some(false) => Decision::Deny {
+                reason: format!("The user declined {}", api.import()),
+            },
+            None if api.needs_confirmation() => Decision::Confirm,
+            None => Decision::Allow,
+        }
+    }
+
+    /// Record the user's answer to a [`Decision::Confirm`], for the rest of
+    /// the session.
+    pub fn answer(&mut self, api: HostApi, allowed: bool) {
+        self.answers.insert(api, allowed);
+    }
+
+    /// Decisions on every host API.
+    pub fn statuses(&self) -> Vec<ApiStatus> {
+        HostApi::ALL
+            .into_iter()
+            .map(|api| ApiStatus {
+                api,
+                import: api.import().to_string(),
+                decision: self.decide(api),
+            })
+            .collect()
+    }
+}
+
+/// Per-component rate limit on notifications: at most `max` in any
+/// `window`.
+#[derive(Debug, Clone)]
+pub struct NotificationLimiter {
+    max: usize,
+    window: Duration,
+    sent: HashMap<String, VecDeque<Instant>>,
+}
+
+impl Default for NotificationLimiter {
+    fn default() -> Self {
+        Self::new(DEFAULT_NOTIFICATIONS_PER_WINDOW, NOTIFICATION_WINDOW)
+    }
+}
+
+impl NotificationLimiter {
+    pub fn new(max: usize, window: Duration) -> Self {
+        Self {
+            max,
+            window,
+            sent: HashMap::new(),
+        }
+    }
+
+    /// Count a notification from `component` at `now`, refusing it if the
+    /// component is over its limit.
+    pub fn check(&mut self, component: &str, now: Instant) -> Result<()> {
+        let sent = self.sent.entry(component.to_string()).or_default();
+        forget_before(sent, now, self.window);
+        if sent.len() >= self.max {
+            return Err(MorpheusError::PermissionDenied(format!(
+                "'{}' has shown {} notifications in the last {}s",
+                component,
+                sent.len(),
+                self.window.as_secs()
+            )));
+        }
+        sent.push_back(now);
+        Ok(())
+    }
+}
+
+/// Drop the times in `times` more than `window` before `now`.
+fn forget_before(times: &mut VecDeque<Instant>, now: Instant, window: Duration) {
+    while times
+        .front()
+        .is_some_and(|&at| now.saturating_duration_since(at) >= window)
+    {
+        times.pop_front();
+    }
+}
+
+/// A position on Earth.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, SharedType)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Position {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Radius of uncertainty, in metres.
+    pub accuracy: f64,
+}
+
+impl Position {
+    /// The position at `precision`. City positions are snapped to the
+    /// centre of their [`CITY_GRID_DEGREES`] cell rather than jittered, so
+    /// averaging many of them reveals nothing more.
+    pub fn at_precision(self, precision: GeoPrecision) -> Position {
+        match precision {
+            GeoPrecision::Exact => self,
+            GeoPrecision::City => {
+                let snap = |degrees: f64| ((degrees / CITY_GRID_DEGREES).floor() + 0.5) * CITY_GRID_DEGREES;
+                Position {
+                    latitude: snap(self.latitude).clamp(-90.0, 90.0),
+                    longitude: snap(self.longitude).clamp(-180.0, 180.0),
+                    accuracy: self.accuracy.max(CITY_ACCURACY_METERS),
+                }
+            }
+        }
+    }
+}
+
+/// A position handed to a component.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Located {
+    pub position: Position,
+    /// Whether it is the component's previous position, repeated.
+    pub cached: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct LocationHistory {
+    last: Option<(Instant, GeoPrecision, Position)>,
+    fixes: VecDeque<Instant>,
+}
+
+/// Coarsens, caches and rate limits the positions components are given.
+///
+/// A component asking again within `max_age` gets the position it was last
+/// given, and it gets at most `max_fixes` fresh positions per `window`, so
+/// polling can't turn into a movement trace.
+#[derive(Debug, Clone)]
+pub struct LocationGate {
+    max_age: Duration,
+    max_fixes: usize,
+    window: Duration,
+    components: HashMap<String, LocationHistory>,
+}
+
+impl Default for LocationGate {
+    fn default() -> Self {
+        Self::new(DEFAULT_POSITION_MAX_AGE, DEFAULT_POSITIONS_PER_WINDOW, POSITION_WINDOW)
+    }
+}
+
+impl LocationGate {
+    pub fn new(max_age: Duration, max_fixes: usize, window: Duration) -> Self {
+        Self {
+            max_age,
+            max_fixes,
+            window,
+            components: HashMap::new(),
+        }
+    }
+
+    /// The position to give `component`, granted `precision`, when the
+    /// browser reports `position` at `now`.
+    pub fn locate(
+        &mut self,
+        component: &str,
+        precision: GeoPrecision,
+        position: Position,
+        now: Instant,
+    ) -> Result<Located> {
+        let history = self.components.entry(component.to_string()).or_default();
+        if let Some((at, last_precision, last)) = history.last {
+            if last_precision == precision && now.saturating_duration_since(at) < self.max_age {
+                return Ok(Located {
+                    position: last,
+                    cached: true,
+                });
+            }
+        }
+        forget_before(&mut history.fixes, now, self.window);
+        if history.fixes.len() >= self.max_fixes {
+            return Err(MorpheusError::PermissionDenied(format!(
+                "'{}' has been given {} positions in the last {}s",
+                component,
+                history.fixes.len(),
+                self.window.as_secs()
+            )));
+        }
+        let position = position.at_precision(precision);
+        history.fixes.push_back(now);
+        history.last = Some((now, precision, position));
+        Ok(Located {
+            position,
+            cached: false,
+        })
+    }
+}
+
+/// A picked file's contents, as the component receives them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PickedFile {
+    pub name: String,
+    pub mime: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Reassembles picked files from the host's chunks.
+#[derive(Debug, Clone)]
+pub struct FileAssembler {
+    max_bytes: usize,
+    pending: HashMap<(u32, String), PickedFile>,
+}
+
+impl Default for FileAssembler {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_FILE_BYTES)
+    }
+}
+
+impl FileAssembler {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Handle [`FILE_START_EXPORT`]; files over the limit are refused.
+    pub fn start(&mut self, request: u32, name: &str, mime: &str, size: f64) -> Result<()> {
+        if size > self.max_bytes as f64 {
+            return Err(MorpheusError::InvalidState(format!(
+                "File '{}' is {} bytes, over the {} byte limit",
+                name, size, self.max_bytes
+            )));
+        }
+        let file = PickedFile {
+            name: name.to_string(),
+            mime: mime.to_string(),
+            bytes: Vec::with_capacity(size as usize),
+        };
+        self.pending.insert((request, name.to_string()), file);
+        Ok(())
+    }
+
+    /// Handle [`FILE_CHUNK_EXPORT`].
+    pub fn chunk(&mut self, request: u32, name: &str, chunk: &[u8]) -> Result<()> {
+        let key = (request, name.to_string());
+        let file = self
+            .pending
+            .get_mut(&key)
+            .ok_or_else(|| MorpheusError::InvalidState(format!("No file '{}' in request {}", name, request)))?;
+        if file.bytes.len() + chunk.len() > self.max_bytes {
+            self.pending.remove(&key);
+            return Err(MorpheusError::InvalidState(format!(
+                "File '{}' grew past the {} byte limit",
+                name, self.max_bytes
+            )));
+        }
+        file.bytes.extend_from_slice(chunk);
+        Ok(())
+    }
+
+    /// Handle [`FILE_END_EXPORT`], returning the complete file.
+    pub fn finish(&mut self, request: u32, name: &str) -> Option<PickedFile> {
+        self.pending.remove(&(request, name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn granting(apis: &[ApiPermission]) -> Permissions {
+        let mut permissions = Permissions::default();
+        permissions.apis.extend(apis.iter().cloned());
+        permissions
+    }
+
+    #[test]
+    fn test_denied_by_default() {
+        let broker = PermissionBroker::new(Permissions::default());
+
+        for status in broker.statuses() {
+            assert!(matches!(status.decision, Decision::Deny { .. }), "{:?}", status);
+        }
+    }
+
+    #[test]
+    fn test_clipboard_read_needs_confirmation() {
+        let mut broker = PermissionBroker::new(granting(&[ApiPermission::Clipboard]));
+
+        assert_eq!(broker.decide(HostApi::ClipboardWrite), Decision::Allow);
+        assert_eq!(broker.decide(HostApi::ClipboardRead), Decision::Confirm);
+        assert!(matches!(broker.decide(HostApi::PickFiles), Decision::Deny { .. }));
+
+        broker.answer(HostApi::ClipboardRead, false);
+        assert!(matches!(broker.decide(HostApi::ClipboardRead), Decision::Deny { .. }));
+        broker.answer(HostApi::ClipboardRead, true);
+        assert_eq!(broker.decide(HostApi::ClipboardRead), Decision::Allow);
+    }
+
+    #[test]
+    fn test_status_wire_format() {
+        let broker = PermissionBroker::new(granting(&[ApiPermission::Files]));
+        let json = serde_json::to_value(broker.statuses()).unwrap();
+
+        assert_eq!(json[2], serde_json::json!({ "api": "pick_files", "import": "pickFiles", "decision": "allow" }));
+        assert_eq!(json[0]["decision"], "deny");
+        assert_eq!(json[0]["reason"], "clipboardRead needs the Clipboard permission");
+    }
+
+    #[test]
+    fn test_notifications_need_consent_and_are_rate_limited() {
+        let broker = PermissionBroker::new(granting(&[ApiPermission::Notifications]));
+        assert_eq!(broker.decide(HostApi::Notify), Decision::Confirm);
+
+        let mut limiter = NotificationLimiter::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+        limiter.check("main", start).unwrap();
+        limiter.check("main", start + Duration::from_secs(1)).unwrap();
+        assert!(limiter.check("main", start + Duration::from_secs(2)).is_err());
+        limiter.check("sidebar", start + Duration::from_secs(2)).unwrap();
+        limiter.check("main", start + Duration::from_secs(60)).unwrap();
+    }
+
+    #[test]
+    fn test_city_precision_snaps_to_the_grid() {
+        let exact = Position { latitude: 51.5072, longitude: -0.1276, accuracy: 15.0 };
+        let city = exact.at_precision(GeoPrecision::City);
+
+        assert!((city.latitude - 51.55).abs() < 1e-9);
+        assert!((city.longitude - -0.15).abs() < 1e-9);
+        assert_eq!(city.accuracy, CITY_ACCURACY_METERS);
+        // Anywhere in the same cell gives the same answer
+        let nearby = Position { latitude: 51.52, longitude: -0.11, accuracy: 5.0 };
+        assert_eq!(nearby.at_precision(GeoPrecision::City), city);
+        assert_eq!(exact.at_precision(GeoPrecision::Exact), exact);
+    }
+
+    #[test]
+    fn test_location_gate_caches_and_rate_limits() {
+        let mut gate = LocationGate::new(Duration::from_secs(60), 2, Duration::from_secs(3600));
+        let start = Instant::now();
+        let at = |latitude: f64| Position { latitude, longitude: 0.0, accuracy: 5.0 };
+
+        let first = gate.locate("map", GeoPrecision::Exact, at(1.0), start).unwrap();
+        assert!(!first.cached);
+        // Moving within max_age isn't revealed
+        let again = gate.locate("map", GeoPrecision::Exact, at(2.0), start + Duration::from_secs(30)).unwrap();
+        assert_eq!((again.cached, again.position), (true, at(1.0)));
+
+        gate.locate("map", GeoPrecision::Exact, at(2.0), start + Duration::from_secs(60)).unwrap();
+        let error = gate.locate("map", GeoPrecision::Exact, at(3.0), start + Duration::from_secs(120)).unwrap_err();
+        assert!(matches!(error, MorpheusError::PermissionDenied(_)));
+        assert!(gate.locate("weather", GeoPrecision::City, at(3.0), start + Duration::from_secs(120)).is_ok());
+    }
+
+    #[test]
+    fn test_file_assembler() {
+        let mut files = FileAssembler::new(8);
+
+        files.start(1, "a.txt", "text/plain", 6.0).unwrap();
+        files.chunk(1, "a.txt", b"abc").unwrap();
+        files.chunk(1, "a.txt", b"def").unwrap();
+        let file = files.finish(1, "a.txt").unwrap();
+        assert_eq!((file.mime.as_str(), file.bytes.as_slice()), ("text/plain", &b"abcdef"[..]));
+
+        assert!(files.start(2, "big.bin", "", 9.0).is_err());
+        files.start(3, "liar.txt", "", 1.0).unwrap();
+        assert!(files.chunk(3, "liar.txt", b"123456789").is_err());
+        assert!(files.finish(3, "liar.txt").is_none());
+    }
+}
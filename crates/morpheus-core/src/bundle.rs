@@ -0,0 +1,203 @@
+//! Version bundles: a compiled module and the JS glue built with it.
+//!
+//! wasm-bindgen glue only works with the exact module it was generated for;
+//! a page that picks up a new `.wasm` with the previous glue (or the other
+//! way round) after a hot reload fails in confusing ways, if it fails at
+//! all. So the server publishes a [`BundleManifest`] per version, naming
+//! both files with [Subresource Integrity] hashes, and loaders fetch and
+//! cache the pair by those hashes rather than by URL.
+//!
+//! Stylesheets and other files shipped with a module have the same problem
+//! with browser caches: a new module styled by the previous stylesheet. A
+//! manifest's [`assets`](BundleManifest::assets) are served under
+//! content-hashed URLs ([`asset_url`]), so a changed file always has a new
+//! URL, and an unchanged one can be cached forever.
+//!
+//! ```rust
+//! use morpheus_core::bundle::BundleManifest;
+//!
+//! let wasm = b"\0asm\x01\0\0\0";
+//! let glue = "export default async function init() {}";
+//! let manifest = BundleManifest::new(3, "counter", wasm, glue);
+//!
+//! assert_eq!(manifest.wasm.url, "/api/versions/3/component.wasm");
+//! assert!(manifest.glue.integrity.starts_with("sha384-"));
+//! assert!(manifest.verify(wasm, glue).is_ok());
+//! assert!(manifest.verify(wasm, "export default async function other() {}").is_err());
+//!
+//! let styled = manifest.clone().with_asset("styles.css", b".counter { color: teal }");
+//! assert!(styled.assets[0].url.starts_with("/api/assets/"));
+//! assert!(styled.assets[0].url.ends_with("/styles.css"));
+//! assert_ne!(styled.key(), manifest.key());
+//! ```
+//!
+//! [Subresource Integrity]: https://www.w3.org/TR/SRI/
+
+use crate::errors::{MorpheusError, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha384};
+
+/// A file of a bundle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BundleAsset {
+    /// Path on the server, e.g. `/api/versions/3/component.wasm`
+    pub url: String,
+    /// Subresource Integrity hash, e.g. `sha384-...`
+    pub integrity: String,
+    /// Size in bytes
+    pub size: usize,
+}
+
+impl BundleAsset {
+    fn new(url: String, bytes: &[u8]) -> Self {
+        Self {
+            url,
+            integrity: integrity(bytes),
+            size: bytes.len(),
+        }
+    }
+
+    fn verify(&self, what: &str, bytes: &[u8]) -> Result<()> {
+        let actual = integrity(bytes);
+        if actual != self.integrity {
+            return Err(MorpheusError::LoadError(format!(
+                "{} does not match the manifest (expected {}, got {})",
+                what, self.integrity, actual
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The module and glue of a version, which must be loaded together.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BundleManifest {
+    pub version_id: usize,
+    /// Component the version belongs to
+    pub component: String,
+    /// The compiled WASM module
+    pub wasm: BundleAsset,
+    /// The wasm-bindgen JS glue generated with it
+    pub glue: BundleAsset,
+    /// Stylesheets and other files loaded with the module, under
+    /// content-hashed URLs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assets: Vec<BundleAsset>,
+}
+
+impl BundleManifest {
+    /// The manifest of version `version_id` of `component`, compiled to
+    /// `wasm` with glue `glue`.
+    pub fn new(version_id: usize, component: impl Into<String>, wasm: &[u8], glue: &str) -> Self {
+        Self {
+            version_id,
+            component: component.into(),
+            wasm: BundleAsset::new(wasm_url(version_id), wasm),
+            glue: BundleAsset::new(glue_url(version_id), glue.as_bytes()),
+            assets: Vec::new(),
+        }
+    }
+
+    /// Add a file named `name` (e.g. `styles.css`) with contents `bytes`.
+    pub fn with_asset(mut self, name: &str, bytes: &[u8]) -> Self {
+        self.assets.push(BundleAsset::new(asset_url(name, bytes), bytes));
+        self
+    }
+
+    /// Identifies the bundle: equal keys mean identical module, glue and
+    /// assets, whichever version they were published under.
+    pub fn key(&self) -> String {
+        let mut key = format!("{} {}", self.wasm.integrity, self.glue.integrity);
+        for asset in &self.assets {
+            key.push_str(&format!(" {}", asset.url));
+        }
+        key
+    }
+
+    /// Check downloaded files against the manifest.
+    pub fn verify(&self, wasm: &[u8], glue: &str) -> Result<()> {
+        self.wasm.verify("WASM module", wasm)?;
+        self.glue.verify("JS glue", glue.as_bytes())
+    }
+}
+
+/// Path of a version's WASM module.
+pub fn wasm_url(version_id: usize) -> String {
+    format!("/api/versions/{}/component.wasm", version_id)
+}
+
+/// Path of a version's JS glue.
+pub fn glue_url(version_id: usize) -> String {
+    format!("/api/versions/{}/component.js", version_id)
+}
+
+/// Path of an asset named `name` with contents `bytes`, which changes
+/// whenever the contents do.
+pub fn asset_url(name: &str, bytes: &[u8]) -> String {
+    format!("/api/assets/{}/{}", fingerprint(bytes), name)
+}
+
+/// A short hex hash of `bytes`, for content-addressed URLs.
+pub fn fingerprint(bytes: &[u8]) -> String {
+    Sha384::digest(bytes)[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The SHA-384 Subresource Integrity hash of `bytes`.
+pub fn integrity(bytes: &[u8]) -> String {
+    format!(
+        "sha384-{}",
+        base64::engine::general_purpose::STANDARD.encode(Sha384::digest(bytes))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integrity_is_sri_sha384() {
+        // printf '' | openssl dgst -sha384 -binary | base64
+        assert_eq!(
+            integrity(b""),
+            "sha384-OLBgp1GsljhM2TJ+sbHjaiH9txEUvgdDTAzHv2P24donTt6/529l+9Ua0vFImLlb"
+        );
+    }
+
+    #[test]
+    fn test_key_identifies_the_pair() {
+        let a = BundleManifest::new(1, "main", b"wasm", "glue");
+        let rebuilt = BundleManifest::new(2, "main", b"wasm", "glue");
+        let new_glue = BundleManifest::new(3, "main", b"wasm", "glue v2");
+
+        assert_eq!(a.key(), rebuilt.key());
+        assert_ne!(a.key(), new_glue.key());
+        assert_eq!(a.glue.url, "/api/versions/1/component.js");
+        assert_eq!(a.wasm.size, 4);
+    }
+
+    #[test]
+    fn test_verify_rejects_mismatched_files() {
+        let manifest = BundleManifest::new(1, "main", b"wasm", "glue");
+
+        assert!(manifest.verify(b"wasm", "glue").is_ok());
+        let error = manifest.verify(b"wasm v2", "glue").unwrap_err();
+        assert!(error.to_string().contains("WASM module does not match"));
+        assert!(manifest.verify(b"wasm", "glue v2").is_err());
+    }
+
+    #[test]
+    fn test_asset_urls_change_with_their_contents() {
+        let manifest = BundleManifest::new(1, "main", b"wasm", "glue").with_asset("styles.css", b"p { color: red }");
+        let restyled = BundleManifest::new(2, "main", b"wasm", "glue").with_asset("styles.css", b"p { color: blue }");
+        let rebuilt = BundleManifest::new(3, "main", b"wasm", "glue").with_asset("styles.css", b"p { color: red }");
+
+        assert_eq!(manifest.assets[0].url, format!("/api/assets/{}/styles.css", fingerprint(b"p { color: red }")));
+        assert_eq!(fingerprint(b"").len(), 16);
+        assert_ne!(manifest.assets[0].url, restyled.assets[0].url);
+        assert_ne!(manifest.key(), restyled.key());
+        assert_eq!(manifest.key(), rebuilt.key());
+    }
+}
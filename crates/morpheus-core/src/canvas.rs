@@ -0,0 +1,280 @@
+//! Canvas and WebGL drawing for visualization components.
+//!
+//! Charts and diagrams outgrow HTML. A component renders a [`canvas()`]
+//! element as part of its view and draws on it through a [`CanvasHandle`],
+//! which is only issued to components granted [`ApiPermission::Graphics`]
+//! and only hands out a drawing context while the component is mounted:
+//!
+//! ```rust,ignore
+//! impl DynamicComponent for Chart {
+//!     fn on_load(&mut self) {
+//!         self.canvas.mount();
+//!         if let Ok(ctx) = self.canvas.context_2d() {
+//!             ctx.fill_rect(0.0, 0.0, 40.0, self.value);
+//!         }
+//!     }
+//!
+//!     fn on_unload(&mut self) {
+//!         self.canvas.unmount();
+//!     }
+//!     // ...
+//! }
+//! ```
+//!
+//! where `self.canvas` came from
+//! `CanvasHandle::new(&canvas("chart"), &self.permissions())?` and the view
+//! contains `canvas("chart").size(400, 300).view()`.
+
+use crate::component::View;
+use crate::errors::{MorpheusError, Result};
+use crate::permissions::{ApiPermission, Permissions};
+
+/// Default canvas width, as in HTML.
+pub const DEFAULT_WIDTH: u32 = 300;
+
+/// Default canvas height, as in HTML.
+pub const DEFAULT_HEIGHT: u32 = 150;
+
+/// Kind of drawing context a canvas is used with. A canvas supports one
+/// kind for its whole life.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContextKind {
+    #[default]
+    TwoD,
+    WebGl,
+    WebGl2,
+}
+
+impl ContextKind {
+    /// Name passed to `getContext`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContextKind::TwoD => "2d",
+            ContextKind::WebGl => "webgl",
+            ContextKind::WebGl2 => "webgl2",
+        }
+    }
+}
+
+/// A `<canvas>` element; see [`canvas()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Canvas {
+    /// Element ID, unique on the page.
+    pub id: String,
+    pub width: u32,
+    pub height: u32,
+    pub context: ContextKind,
+    pub class: String,
+}
+
+/// Start building a canvas element with ID `id`.
+pub fn canvas(id: impl Into<String>) -> Canvas {
+    Canvas {
+        id: id.into(),
+        width: DEFAULT_WIDTH,
+        height: DEFAULT_HEIGHT,
+        context: ContextKind::default(),
+        class: String::new(),
+    }
+}
+
+impl Canvas {
+    /// Drawing surface size in pixels.
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn context(mut self, context: ContextKind) -> Self {
+        self.context = context;
+        self
+    }
+
+    pub fn class(mut self, class: impl Into<String>) -> Self {
+        self.class = class.into();
+        self
+    }
+
+    /// The element as a view.
+    pub fn view(&self) -> View {
+        View::Element {
+            tag: "canvas".to_string(),
+            attrs: self.attrs(),
+            children: vec![],
+        }
+    }
+
+    /// The element as HTML, for components that render strings.
+    pub fn to_html(&self) -> String {
+        let attrs: String = self
+            .attrs()
+            .into_iter()
+            .map(|(name, value)| format!(" {}=\"{}\"", name, value.replace('&', "&amp;").replace('"', "&quot;")))
+            .collect();
+        format!("<canvas{}></canvas>", attrs)
+    }
+
+    fn attrs(&self) -> Vec<(String, String)> {
+        let mut attrs = vec![
+            ("id".to_string(), self.id.clone()),
+            ("width".to_string(), self.width.to_string()),
+            ("height".to_string(), self.height.to_string()),
+            ("data-canvas-context".to_string(), self.context.as_str().to_string()),
+        ];
+        if !self.class.is_empty() {
+            attrs.push(("class".to_string(), self.class.clone()));
+        }
+        attrs
+    }
+}
+
+/// Permissioned access to a canvas's drawing context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanvasHandle {
+    id: String,
+    context: ContextKind,
+    mounted: bool,
+}
+
+impl CanvasHandle {
+    /// Access to `canvas` for a component with `permissions`; denied
+    /// without [`ApiPermission::Graphics`].
+    pub fn new(canvas: &Canvas, permissions: &Permissions) -> Result<Self> {
+        if !permissions.apis.contains(&ApiPermission::Graphics) {
+            return Err(MorpheusError::PermissionDenied(format!(
+                "Drawing on canvas '{}' needs the Graphics permission",
+                canvas.id
+            )));
+        }
+        Ok(Self {
+            id: canvas.id.clone(),
+            context: canvas.context,
+            mounted: false,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The canvas is in the document; call from
+    /// [`DynamicComponent::on_load`](crate::component::DynamicComponent::on_load).
+    pub fn mount(&mut self) {
+        self.mounted = true;
+    }
+
+    /// The canvas is leaving the document; call from
+    /// [`DynamicComponent::on_unload`](crate::component::DynamicComponent::on_unload).
+    /// Contexts fetched earlier must not be used afterwards.
+    pub fn unmount(&mut self) {
+        self.mounted = false;
+    }
+
+    pub fn is_mounted(&self) -> bool {
+        self.mounted
+    }
+
+    /// Check a context of `kind` may be handed out now.
+    pub fn check(&self, kind: ContextKind) -> Result<()> {
+        if !self.mounted {
+            return Err(MorpheusError::InvalidState(format!(
+                "Canvas '{}' is not mounted; draw from on_load or later",
+                self.id
+            )));
+        }
+        if kind != self.context {
+            return Err(MorpheusError::InvalidState(format!(
+                "Canvas '{}' is a {} canvas, not {}",
+                self.id,
+                self.context.as_str(),
+                kind.as_str()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl CanvasHandle {
+    /// The 2D drawing context.
+    pub fn context_2d(&self) -> Result<web_sys::CanvasRenderingContext2d> {
+        self.context_as(ContextKind::TwoD)
+    }
+
+    /// The WebGL 1 context.
+    pub fn webgl(&self) -> Result<web_sys::WebGlRenderingContext> {
+        self.context_as(ContextKind::WebGl)
+    }
+
+    /// The WebGL 2 context.
+    pub fn webgl2(&self) -> Result<web_sys::WebGl2RenderingContext> {
+        self.context_as(ContextKind::WebGl2)
+    }
+
+    fn context_as<T: wasm_bindgen::JsCast>(&self, kind: ContextKind) -> Result<T> {
+        use wasm_bindgen::JsCast;
+
+        self.check(kind)?;
+        let missing = || MorpheusError::InvalidState(format!("Canvas '{}' is not in the document", self.id));
+        let element = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.get_element_by_id(&self.id))
+            .ok_or_else(missing)?;
+        let canvas = element
+            .dyn_into::<web_sys::HtmlCanvasElement>()
+            .map_err(|_| MorpheusError::InvalidState(format!("Element '{}' is not a canvas", self.id)))?;
+        canvas
+            .get_context(kind.as_str())
+            .ok()
+            .flatten()
+            .and_then(|context| context.dyn_into::<T>().ok())
+            .ok_or_else(|| {
+                MorpheusError::Other(format!("Canvas '{}' has no {} context", self.id, kind.as_str()))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graphics() -> Permissions {
+        let mut permissions = Permissions::default();
+        permissions.apis.insert(ApiPermission::Graphics);
+        permissions
+    }
+
+    #[test]
+    fn test_canvas_html() {
+        let chart = canvas("chart").size(400, 300).context(ContextKind::WebGl).class("rounded");
+
+        assert_eq!(
+            chart.to_html(),
+            "<canvas id=\"chart\" width=\"400\" height=\"300\" data-canvas-context=\"webgl\" class=\"rounded\"></canvas>"
+        );
+        let View::Element { tag, attrs, .. } = chart.view() else { panic!("Expected Element variant") };
+        assert_eq!(tag, "canvas");
+        assert_eq!(attrs.len(), 5);
+    }
+
+    #[test]
+    fn test_handle_needs_graphics_permission() {
+        let error = CanvasHandle::new(&canvas("chart"), &Permissions::default()).unwrap_err();
+        assert!(matches!(error, MorpheusError::PermissionDenied(_)));
+
+        assert!(CanvasHandle::new(&canvas("chart"), &graphics()).is_ok());
+    }
+
+    #[test]
+    fn test_context_only_while_mounted() {
+        let mut handle = CanvasHandle::new(&canvas("chart"), &graphics()).unwrap();
+
+        assert!(handle.check(ContextKind::TwoD).is_err());
+        handle.mount();
+        assert!(handle.check(ContextKind::TwoD).is_ok());
+        assert!(handle.check(ContextKind::WebGl).is_err());
+        handle.unmount();
+        assert!(handle.check(ContextKind::TwoD).is_err());
+    }
+}
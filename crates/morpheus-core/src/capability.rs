@@ -0,0 +1,141 @@
+//! What kind of job a component does.
+//!
+//! A small, fixed taxonomy, so planners and people can ask "which
+//! components visualize data?" without reading every description.
+//! Components can declare their capabilities in `__morpheus_describe()`;
+//! for those that don't, [`Capability::infer`] guesses from a description.
+
+use crate::errors::{MorpheusError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A kind of job a component does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    /// Shows records: tables, lists, cards, detail views.
+    DataDisplay,
+    /// Takes input: forms, editors, search boxes, uploads.
+    Input,
+    /// Moves between views: menus, tabs, breadcrumbs, pagination.
+    Navigation,
+    /// Draws data: charts, maps, gauges, diagrams.
+    Visualization,
+    /// Acts on its own: timers, schedules, syncing, reminders.
+    Automation,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 5] = [
+        Capability::DataDisplay,
+        Capability::Input,
+        Capability::Navigation,
+        Capability::Visualization,
+        Capability::Automation,
+    ];
+
+    /// Name used in JSON and URLs.
+    pub fn name(self) -> &'static str {
+        match self {
+            Capability::DataDisplay => "data-display",
+            Capability::Input => "input",
+            Capability::Navigation => "navigation",
+            Capability::Visualization => "visualization",
+            Capability::Automation => "automation",
+        }
+    }
+
+    /// Words that suggest the capability in a description.
+    fn keywords(self) -> &'static [&'static str] {
+        match self {
+            Capability::DataDisplay => &[
+                "table", "list", "grid", "card", "detail", "profile", "feed", "catalog", "directory", "inbox",
+            ],
+            Capability::Input => &[
+                "form", "input", "field", "editor", "checkbox", "dropdown", "upload", "search", "login", "signup",
+                "survey", "picker",
+            ],
+            Capability::Navigation => &[
+                "nav", "navbar", "navigation", "menu", "tab", "breadcrumb", "sidebar", "pagination", "router", "wizard",
+            ],
+            Capability::Visualization => &[
+                "chart", "graph", "plot", "map", "dashboard", "diagram", "gauge", "heatmap", "sparkline", "timeline",
+            ],
+            Capability::Automation => &[
+                "timer", "schedule", "scheduler", "workflow", "automation", "automate", "trigger", "reminder", "cron",
+                "countdown", "poll", "sync",
+            ],
+        }
+    }
+
+    /// Capabilities a description suggests, in taxonomy order.
+    ///
+    /// ```rust
+    /// use morpheus_core::capability::Capability;
+    ///
+    /// assert_eq!(Capability::infer("A sortable table of users"), [Capability::DataDisplay]);
+    /// assert_eq!(
+    ///     Capability::infer("Sign-up form with a countdown timer"),
+    ///     [Capability::Input, Capability::Automation]
+    /// );
+    /// ```
+    pub fn infer(description: &str) -> Vec<Capability> {
+        let lowercase = description.to_lowercase();
+        let words: Vec<&str> = lowercase
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.strip_suffix('s').filter(|stem| stem.len() > 2).unwrap_or(word))
+            .collect();
+        Self::ALL
+            .into_iter()
+            .filter(|capability| capability.keywords().iter().any(|keyword| words.contains(keyword)))
+            .collect()
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Capability {
+    type Err = MorpheusError;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|capability| capability.name() == name.trim())
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|c| c.name()).collect();
+                MorpheusError::Other(format!("Unknown capability '{}' (expected {})", name, names.join(", ")))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_round_trip() {
+        for capability in Capability::ALL {
+            assert_eq!(capability.name().parse::<Capability>().unwrap(), capability);
+            assert_eq!(serde_json::to_value(capability).unwrap(), capability.name());
+        }
+        assert!("storage".parse::<Capability>().is_err());
+    }
+
+    #[test]
+    fn test_infer() {
+        assert_eq!(
+            Capability::infer("Dashboard with tabs and charts of sales"),
+            [Capability::Navigation, Capability::Visualization]
+        );
+        assert!(Capability::infer("Hello world").is_empty());
+        // "has" is not a plural of "ha"
+        assert!(Capability::infer("It has a status").is_empty());
+    }
+}
@@ -0,0 +1,193 @@
+//! Capability introspection and the component catalog.
+//!
+//! Every generated component exports `__morpheus_describe()`, returning a
+//! JSON [`ComponentDescription`] of what it can do. The runtime aggregates
+//! these into a catalog that dashboards display and that is fed back to the
+//! AI as context for later modifications.
+
+use crate::capability::Capability;
+use crate::component::ComponentId;
+use crate::manifest::ComponentManifest;
+use crate::semver::SemVer;
+use crate::thumbnail::Thumbnail;
+use serde::{Deserialize, Serialize};
+
+/// Name of the introspection export every component should provide.
+pub const DESCRIBE_EXPORT: &str = "__morpheus_describe";
+
+/// Machine-readable description of a component's capabilities.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComponentDescription {
+    /// Messages the component handles.
+    #[serde(default)]
+    pub messages: Vec<String>,
+
+    /// Functions the module exports (excluding `__morpheus_describe`).
+    #[serde(default)]
+    pub exports: Vec<String>,
+
+    /// Events the component emits.
+    #[serde(default)]
+    pub emits: Vec<String>,
+
+    /// Events the component consumes.
+    #[serde(default)]
+    pub consumes: Vec<String>,
+
+    /// Shape of the component's state (example value or JSON schema).
+    #[serde(default)]
+    pub state: serde_json::Value,
+
+    /// What kind of job the component does, e.g. `["data-display"]`.
+    #[serde(default)]
+    pub capabilities: Vec<Capability>,
+}
+
+impl ComponentDescription {
+    /// Parse the JSON returned by a component's `__morpheus_describe()` export.
+    pub fn from_json(json: &str) -> crate::errors::Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+/// A catalog entry for one registered component.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    /// Component ID.
+    pub id: ComponentId,
+
+    /// Component version.
+    pub version: SemVer,
+
+    /// The component's manifest.
+    pub manifest: ComponentManifest,
+
+    /// Introspected capabilities, if the component has reported them.
+    pub description: Option<ComponentDescription>,
+
+    /// Preview of the component, for pickers.
+    #[serde(default)]
+    pub thumbnail: Option<Thumbnail>,
+}
+
+/// Render catalog entries as a prompt section.
+///
+/// Included in the AI context so it can compose existing components rather
+/// than regenerating them, and knows which messages and events they use.
+pub fn prompt_section(entries: &[CatalogEntry]) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+
+    let mut section = String::from(
+        "AVAILABLE COMPONENTS (embed these instead of regenerating them):\n",
+    );
+    for entry in entries {
+        section.push_str(&format!("- {}", entry.manifest.name));
+        if !entry.manifest.description.is_empty() {
+            section.push_str(&format!(": {}", entry.manifest.description));
+        }
+        section.push('\n');
+
+        if let Some(description) = &entry.description {
+            if !description.capabilities.is_empty() {
+                let names: Vec<_> = description.capabilities.iter().map(|c| c.name()).collect();
+                section.push_str(&format!("    capabilities: {}\n", names.join(", ")));
+            }
+            for (label, items) in [
+                ("exports", &description.exports),
+                ("messages", &description.messages),
+                ("emits", &description.emits),
+                ("consumes", &description.consumes),
+            ] {
+                if !items.is_empty() {
+                    section.push_str(&format!("    {}: {}\n", label, items.join(", ")));
+                }
+            }
+        }
+    }
+    section.push_str(
+        "To embed one, render an empty element with id=\"morpheus-slot-<parent>-<slot>\" \
+        and declare the slot; the runtime mounts the child there.\n",
+    );
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, description: &str) -> CatalogEntry {
+        CatalogEntry {
+            id: ComponentId(1),
+            version: SemVer::INITIAL,
+            manifest: ComponentManifest::new(name, description),
+            description: None,
+            thumbnail: None,
+        }
+    }
+
+    #[test]
+    fn test_description_from_json() {
+        let json = r#"{
+            "messages": ["Increment", "Decrement"],
+            "exports": ["render"],
+            "emits": ["count-changed"],
+            "state": { "count": 0 }
+        }"#;
+
+        let description = ComponentDescription::from_json(json).expect("Failed to parse");
+
+        assert_eq!(description.messages, vec!["Increment", "Decrement"]);
+        assert_eq!(description.exports, vec!["render"]);
+        assert_eq!(description.emits, vec!["count-changed"]);
+        assert!(description.consumes.is_empty());
+        assert_eq!(description.state["count"], 0);
+    }
+
+    #[test]
+    fn test_description_from_invalid_json() {
+        let result = ComponentDescription::from_json("not json");
+        assert!(matches!(
+            result,
+            Err(crate::errors::MorpheusError::SerializationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_prompt_section_lists_components() {
+        let entries = vec![
+            entry("chart", "Bar chart of monthly sales"),
+            entry("filters", ""),
+        ];
+
+        let section = prompt_section(&entries);
+
+        assert!(section.contains("AVAILABLE COMPONENTS"));
+        assert!(section.contains("- chart: Bar chart of monthly sales"));
+        assert!(section.contains("- filters\n"));
+    }
+
+    #[test]
+    fn test_prompt_section_includes_capabilities() {
+        let mut chart = entry("chart", "");
+        chart.description = Some(ComponentDescription {
+            exports: vec!["render".to_string()],
+            consumes: vec!["filter-changed".to_string()],
+            capabilities: vec![Capability::Visualization],
+            ..Default::default()
+        });
+
+        let section = prompt_section(&[chart]);
+
+        assert!(section.contains("    capabilities: visualization\n"));
+        assert!(section.contains("    exports: render\n"));
+        assert!(section.contains("    consumes: filter-changed\n"));
+        assert!(!section.contains("emits"));
+    }
+
+    #[test]
+    fn test_prompt_section_empty() {
+        assert!(prompt_section(&[]).is_empty());
+    }
+}
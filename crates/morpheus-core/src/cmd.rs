@@ -0,0 +1,278 @@
+//! Commands: side effects a component asks the host to run after an update.
+//!
+//! [`DynamicComponent::update_cmd`] returns a [`Cmd`] listing messages to
+//! dispatch straight away and async tasks whose results are dispatched when
+//! they finish. A [`Program`] drives the loop.
+//!
+//! [`Cmd::optimistic`] covers the common CRUD case of showing a change before
+//! the server has confirmed it:
+//!
+//! ```rust,ignore
+//! fn update_cmd(&mut self, msg: Msg) -> Cmd<Msg> {
+//!     match msg {
+//!         Msg::Delete(id) => Cmd::optimistic(Msg::Hide(id), api::delete(id), Msg::Unhide(id)),
+//!         Msg::Hide(id) => { self.hidden.insert(id); Cmd::none() }
+//!         Msg::Unhide(id) => { self.hidden.remove(&id); Cmd::none() }
+//!     }
+//! }
+//! ```
+//!
+//! The row disappears at once; if the request fails, `Unhide` brings it back.
+
+use crate::component::DynamicComponent;
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+/// An async task started by a command, producing at most one message.
+pub type Task<Msg> = Pin<Box<dyn Future<Output = Option<Msg>>>>;
+
+enum Effect<Msg> {
+    Msg(Msg),
+    Task(Task<Msg>),
+}
+
+/// Work to do after an update.
+#[must_use]
+pub struct Cmd<Msg> {
+    effects: Vec<Effect<Msg>>,
+}
+
+impl<Msg> Cmd<Msg> {
+    /// Nothing to do.
+    pub fn none() -> Self {
+        Self { effects: Vec::new() }
+    }
+
+    /// Dispatch a message straight after this update.
+    pub fn msg(msg: Msg) -> Self {
+        Self {
+            effects: vec![Effect::Msg(msg)],
+        }
+    }
+
+    /// Run several commands, in order.
+    pub fn batch(cmds: impl IntoIterator<Item = Self>) -> Self {
+        Self {
+            effects: cmds.into_iter().flat_map(|cmd| cmd.effects).collect(),
+        }
+    }
+
+    /// Whether the command does nothing.
+    pub fn is_none(&self) -> bool {
+        self.effects.is_empty()
+    }
+}
+
+impl<Msg: 'static> Cmd<Msg> {
+    /// Run `task` and dispatch the message it produces.
+    pub fn perform(task: impl Future<Output = Msg> + 'static) -> Self {
+        Self {
+            effects: vec![Effect::Task(Box::pin(async move { Some(task.await) }))],
+        }
+    }
+
+    /// Dispatch `apply` straight away, run `task`, and dispatch `revert` if
+    /// the task fails.
+    pub fn optimistic<T, E>(apply: Msg, task: impl Future<Output = Result<T, E>> + 'static, revert: Msg) -> Self {
+        let task = async move { task.await.err().map(|_| revert) };
+        Self {
+            effects: vec![Effect::Msg(apply), Effect::Task(Box::pin(task))],
+        }
+    }
+}
+
+impl<Msg> Default for Cmd<Msg> {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl<Msg> fmt::Debug for Cmd<Msg> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages = self.effects.iter().filter(|e| matches!(e, Effect::Msg(_))).count();
+        f.debug_struct("Cmd")
+            .field("messages", &messages)
+            .field("tasks", &(self.effects.len() - messages))
+            .finish()
+    }
+}
+
+/// Runs a component's updates and the commands they return.
+///
+/// [`dispatch`](Self::dispatch) applies a message and every message its
+/// commands dispatch straight away, so optimistic changes are visible before
+/// it returns. Async tasks are queued: the host either spawns them from
+/// [`take_tasks`](Self::take_tasks) and dispatches their output, or awaits
+/// [`settle`](Self::settle).
+pub struct Program<C: DynamicComponent> {
+    component: C,
+    tasks: Vec<Task<C::Message>>,
+}
+
+impl<C> Program<C>
+where
+    C: DynamicComponent,
+    C::Message: 'static,
+{
+    pub fn new(component: C) -> Self {
+        Self {
+            component,
+            tasks: Vec::new(),
+        }
+    }
+
+    /// The component, for rendering.
+    pub fn component(&self) -> &C {
+        &self.component
+    }
+
+    pub fn into_component(self) -> C {
+        self.component
+    }
+
+    /// Update the component with `msg`, then with each message its
+    /// commands dispatch, queueing their tasks.
+    pub fn dispatch(&mut self, msg: C::Message) {
+        let mut queue = VecDeque::from([msg]);
+        while let Some(msg) = queue.pop_front() {
+            for effect in self.component.update_cmd(msg).effects {
+                match effect {
+                    Effect::Msg(msg) => queue.push_back(msg),
+                    Effect::Task(task) => self.tasks.push(task),
+                }
+            }
+        }
+    }
+
+    /// Tasks queued since the last call.
+    pub fn take_tasks(&mut self) -> Vec<Task<C::Message>> {
+        std::mem::take(&mut self.tasks)
+    }
+
+    /// Number of queued tasks.
+    pub fn pending(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Run queued tasks one at a time, dispatching their results, until
+    /// none are left (including tasks those results start).
+    pub async fn settle(&mut self) {
+        while !self.tasks.is_empty() {
+            let task = self.tasks.remove(0);
+            if let Some(msg) = task.await {
+                self.dispatch(msg);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::View;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Serialize, Deserialize)]
+    enum Msg {
+        Add(String, bool),
+        Show(String),
+        Hide(String),
+        Saved(String),
+    }
+
+    #[derive(Default)]
+    struct Todos {
+        items: Vec<String>,
+        saved: Vec<String>,
+    }
+
+    impl DynamicComponent for Todos {
+        type Message = Msg;
+        type State = Vec<String>;
+
+        fn from_state(items: Vec<String>) -> Self {
+            Self { items, ..Default::default() }
+        }
+
+        fn view(&self) -> View {
+            View::Text(self.items.join(", "))
+        }
+
+        fn update(&mut self, _msg: Msg) {}
+
+        fn update_cmd(&mut self, msg: Msg) -> Cmd<Msg> {
+            match msg {
+                Msg::Add(item, succeed) => {
+                    let save = std::future::ready(if succeed { Ok(()) } else { Err("offline") });
+                    Cmd::batch([
+                        Cmd::optimistic(Msg::Show(item.clone()), save, Msg::Hide(item.clone())),
+                        Cmd::perform(async move { Msg::Saved(item) }),
+                    ])
+                }
+                Msg::Show(item) => {
+                    self.items.push(item);
+                    Cmd::none()
+                }
+                Msg::Hide(item) => {
+                    self.items.retain(|i| *i != item);
+                    Cmd::none()
+                }
+                Msg::Saved(item) => {
+                    self.saved.push(item);
+                    Cmd::none()
+                }
+            }
+        }
+
+        fn to_state(&self) -> Vec<String> {
+            self.items.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_optimistic_change_applies_before_the_task_runs() {
+        let mut program = Program::new(Todos::default());
+
+        program.dispatch(Msg::Add("milk".to_string(), true));
+        assert_eq!(program.component().items, ["milk"]);
+        assert_eq!(program.pending(), 2);
+
+        program.settle().await;
+        assert_eq!(program.component().items, ["milk"]);
+        assert_eq!(program.component().saved, ["milk"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_task_dispatches_the_revert() {
+        let mut program = Program::new(Todos::from_state(vec!["eggs".to_string()]));
+
+        program.dispatch(Msg::Add("milk".to_string(), false));
+        assert_eq!(program.component().items, ["eggs", "milk"]);
+
+        for task in program.take_tasks() {
+            if let Some(msg) = task.await {
+                program.dispatch(msg);
+            }
+        }
+        assert_eq!(program.component().items, ["eggs"]);
+        assert_eq!(program.pending(), 0);
+    }
+
+    #[test]
+    fn test_batch_keeps_order() {
+        let cmd: Cmd<u8> = Cmd::batch([Cmd::msg(1), Cmd::none(), Cmd::batch([Cmd::msg(2), Cmd::msg(3)])]);
+        let order: Vec<u8> = cmd
+            .effects
+            .into_iter()
+            .filter_map(|e| match e {
+                Effect::Msg(m) => Some(m),
+                Effect::Task(_) => None,
+            })
+            .collect();
+
+        assert_eq!(order, [1, 2, 3]);
+        assert!(Cmd::<u8>::default().is_none());
+    }
+}
@@ -0,0 +1,213 @@
+//! Serialization formats for state and API payloads.
+//!
+//! JSON is the default everywhere, but large states are slow to encode and
+//! bulky on the wire. Any serde type can also be sent as MessagePack or
+//! CBOR: pick a [`Format`] from a request's `Content-Type`, negotiate the
+//! reply from its `Accept` header, and encode with [`Format::to_vec`].
+//!
+//! ```rust
+//! use morpheus_core::codec::Format;
+//!
+//! let format = Format::negotiate("application/msgpack, application/json;q=0.5");
+//! assert_eq!(format, Format::MessagePack);
+//!
+//! let state = serde_json::json!({ "count": 3 });
+//! let bytes = format.to_vec(&state).unwrap();
+//! assert_eq!(format.from_slice::<serde_json::Value>(&bytes).unwrap(), state);
+//! ```
+
+use crate::errors::{MorpheusError, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A serialization format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// JSON (`application/json`).
+    #[default]
+    Json,
+
+    /// MessagePack (`application/msgpack`).
+    #[serde(rename = "msgpack")]
+    MessagePack,
+
+    /// CBOR (`application/cbor`).
+    Cbor,
+}
+
+impl Format {
+    /// Every supported format.
+    pub const ALL: [Format; 3] = [Format::Json, Format::MessagePack, Format::Cbor];
+
+    /// Media type sent in `Content-Type`.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::MessagePack => "application/msgpack",
+            Format::Cbor => "application/cbor",
+        }
+    }
+
+    /// Short name, as used in configuration (`json`, `msgpack`, `cbor`).
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::MessagePack => "msgpack",
+            Format::Cbor => "cbor",
+        }
+    }
+
+    /// Look up a format by short name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Format named by a `Content-Type` header, ignoring parameters.
+    ///
+    /// Also accepts the unregistered `application/x-msgpack` and
+    /// `application/vnd.msgpack` spellings.
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let media_type = content_type.split(';').next().unwrap_or_default().trim();
+        match media_type.to_ascii_lowercase().as_str() {
+            "application/json" => Some(Format::Json),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => Some(Format::MessagePack),
+            "application/cbor" => Some(Format::Cbor),
+            _ => None,
+        }
+    }
+
+    /// Pick the client's preferred format from an `Accept` header.
+    ///
+    /// Honors `q` values; falls back to JSON when nothing listed is
+    /// supported.
+    pub fn negotiate(accept: &str) -> Self {
+        let mut best: Option<(Format, f32)> = None;
+        for entry in accept.split(',') {
+            let mut params = entry.split(';');
+            let media_type = params.next().unwrap_or_default().trim();
+            let quality = params
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let format = match media_type {
+                "*/*" | "application/*" => Some(Format::Json),
+                other => Format::from_content_type(other),
+            };
+            if let Some(format) = format {
+                if quality > 0.0 && best.is_none_or(|(_, q)| quality > q) {
+                    best = Some((format, quality));
+                }
+            }
+        }
+        best.map_or(Format::Json, |(format, _)| format)
+    }
+
+    /// Whether the format is binary rather than text.
+    pub fn is_binary(self) -> bool {
+        self != Format::Json
+    }
+
+    /// Serialize `value`.
+    ///
+    /// MessagePack structs are written as maps, so fields with serde
+    /// defaults can be added later without breaking stored data.
+    pub fn to_vec<T: Serialize + ?Sized>(self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            Format::Json => Ok(serde_json::to_vec(value)?),
+            Format::MessagePack => rmp_serde::to_vec_named(value).map_err(|e| self.error(e)),
+            Format::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes).map_err(|e| self.error(e))?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Deserialize a value written by [`Format::to_vec`].
+    pub fn from_slice<T: DeserializeOwned>(self, bytes: &[u8]) -> Result<T> {
+        match self {
+            Format::Json => Ok(serde_json::from_slice(bytes)?),
+            Format::MessagePack => rmp_serde::from_slice(bytes).map_err(|e| self.error(e)),
+            Format::Cbor => ciborium::from_reader(bytes).map_err(|e| self.error(e)),
+        }
+    }
+
+    fn error(self, error: impl fmt::Display) -> MorpheusError {
+        MorpheusError::Other(format!("{} serialization error: {}", self, error))
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Json => write!(f, "JSON"),
+            Format::MessagePack => write!(f, "MessagePack"),
+            Format::Cbor => write!(f, "CBOR"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Snapshot {
+        version: u32,
+        state: serde_json::Value,
+        #[serde(default)]
+        note: Option<String>,
+    }
+
+    #[test]
+    fn test_round_trip_in_every_format() {
+        let snapshot = Snapshot {
+            version: 3,
+            state: json!({ "todos": [{ "text": "a", "done": false }], "ratio": 0.5, "owner": null }),
+            note: Some("hi".to_string()),
+        };
+
+        for format in Format::ALL {
+            let bytes = format.to_vec(&snapshot).unwrap();
+            assert_eq!(format.from_slice::<Snapshot>(&bytes).unwrap(), snapshot, "{}", format);
+        }
+    }
+
+    #[test]
+    fn test_binary_formats_are_smaller() {
+        let state = json!({ "items": (0..100).map(|i| json!({ "id": i, "done": i % 2 == 0 })).collect::<Vec<_>>() });
+        let json = Format::Json.to_vec(&state).unwrap().len();
+
+        assert!(Format::MessagePack.to_vec(&state).unwrap().len() < json);
+        assert!(Format::Cbor.to_vec(&state).unwrap().len() < json);
+    }
+
+    #[test]
+    fn test_content_types() {
+        for format in Format::ALL {
+            assert_eq!(Format::from_content_type(format.content_type()), Some(format));
+            assert_eq!(Format::from_name(format.name()), Some(format));
+        }
+        assert_eq!(Format::from_content_type("application/json; charset=utf-8"), Some(Format::Json));
+        assert_eq!(Format::from_content_type("application/x-msgpack"), Some(Format::MessagePack));
+        assert_eq!(Format::from_content_type("text/plain"), None);
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(Format::negotiate("application/cbor"), Format::Cbor);
+        assert_eq!(Format::negotiate("application/json;q=0.9, application/msgpack"), Format::MessagePack);
+        assert_eq!(Format::negotiate("application/msgpack;q=0.1, */*"), Format::Json);
+        assert_eq!(Format::negotiate("application/cbor;q=0, text/html"), Format::Json);
+        assert_eq!(Format::negotiate(""), Format::Json);
+    }
+
+    #[test]
+    fn test_malformed_input_is_an_error() {
+        assert!(Format::MessagePack.from_slice::<Snapshot>(&[0xc1]).is_err());
+        assert!(Format::Cbor.from_slice::<Snapshot>(&[0xff]).is_err());
+    }
+}
@@ -2,6 +2,14 @@
 //!
 //! Components in Morpheus can be loaded, unloaded, and hot-reloaded at runtime.
 
+use crate::capability::Capability;
+use crate::cmd::Cmd;
+use crate::interface::InterfacePin;
+use crate::lifecycle::Lifecycle;
+use crate::semver::SemVer;
+use crate::fallback::FallbackView;
+use crate::stats::ExecutionStats;
+use crate::thumbnail::Thumbnail;
 use crate::permissions::Permissions;
 use serde::{Deserialize, Serialize};
 
@@ -30,6 +38,16 @@ pub trait DynamicComponent {
     /// Update component state in response to a message.
     fn update(&mut self, msg: Self::Message);
 
+    /// Update in response to a message, returning follow-up work (see
+    /// [`crate::cmd`]).
+    ///
+    /// Components that start async work, e.g. optimistic saves, override
+    /// this instead of [`update`](Self::update).
+    fn update_cmd(&mut self, msg: Self::Message) -> Cmd<Self::Message> {
+        self.update(msg);
+        Cmd::none()
+    }
+
     /// Extract current state (for hot-reload/rollback).
     fn to_state(&self) -> Self::State;
 
@@ -63,6 +81,7 @@ pub enum View {
 
 /// Unique identifier for a component instance.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ComponentId(pub u64);
 
 impl std::fmt::Display for ComponentId {
@@ -73,6 +92,7 @@ impl std::fmt::Display for ComponentId {
 
 /// Metadata about a component.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ComponentMetadata {
     /// Unique identifier.
     pub id: ComponentId,
@@ -80,19 +100,144 @@ pub struct ComponentMetadata {
     /// Human-readable name.
     pub name: String,
 
-    /// Version (for tracking updates).
-    pub version: u32,
+    /// Semantic version, bumped by how much each reload changed the
+    /// component's interface (see [`crate::semver`]).
+    pub version: SemVer,
 
     /// When this component was loaded.
     pub loaded_at: String,  // ISO 8601 timestamp
 
     /// Whether this component was AI-generated.
     pub ai_generated: bool,
+
+    /// Where the component's code came from.
+    #[serde(default)]
+    pub provenance: Provenance,
+
+    /// What kind of job the component does.
+    #[serde(default)]
+    pub capabilities: Vec<Capability>,
+
+    /// Hash of the interface the component reported (see
+    /// [`crate::interface`]).
+    #[serde(default)]
+    pub interface_hash: Option<String>,
+
+    /// Interfaces of the components it depends on, as it was loaded against.
+    #[serde(default)]
+    pub dependencies: Vec<InterfacePin>,
+
+    /// Whether the component is active, deprecated or retired (see
+    /// [`crate::lifecycle`]).
+    #[serde(default)]
+    pub lifecycle: Lifecycle,
+
+    /// A small preview of what the component renders (see
+    /// [`crate::thumbnail`]).
+    #[serde(default)]
+    pub thumbnail: Option<Thumbnail>,
+
+    /// Calls, traps and call times of the component's exports since it was
+    /// loaded (see [`crate::stats`]).
+    #[serde(default)]
+    pub stats: ExecutionStats,
+
+    /// What clients render if the component fails to load (see
+    /// [`crate::fallback`]).
+    #[serde(default)]
+    pub fallback: Option<FallbackView>,
+}
+
+/// Who wrote a component's code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Author {
+    /// Not recorded.
+    #[default]
+    Unknown,
+
+    /// Written by a person.
+    Human,
+
+    /// Generated by an AI model.
+    Ai,
+}
+
+/// Origin of a component's code.
+///
+/// Answers "where did this code come from?": the prompt and model that
+/// produced it, the version it replaced, and the toolchain that built it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Provenance {
+    /// Who wrote the code.
+    #[serde(default)]
+    pub author: Author,
+
+    /// Prompt that produced the code, if AI-generated.
+    #[serde(default)]
+    pub prompt: Option<String>,
+
+    /// Model name and version, e.g. `anthropic/claude-3.5-sonnet`.
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Version this one was derived from.
+    #[serde(default)]
+    pub parent_version: Option<u32>,
+
+    /// Compiler toolchain that built the module, e.g. `rustc 1.82.0, wasm-pack 0.13.1`.
+    #[serde(default)]
+    pub toolchain: Option<String>,
+
+    /// Component this one was forked from.
+    #[serde(default)]
+    pub forked_from: Option<String>,
+}
+
+impl Provenance {
+    /// Provenance of code generated by `model` from `prompt`.
+    pub fn ai(prompt: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            author: Author::Ai,
+            prompt: Some(prompt.into()),
+            model: Some(model.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Provenance of hand-written code.
+    pub fn human() -> Self {
+        Self {
+            author: Author::Human,
+            ..Default::default()
+        }
+    }
+
+    /// Record the version this one was derived from.
+    pub fn with_parent(mut self, version: u32) -> Self {
+        self.parent_version = Some(version);
+        self
+    }
+
+    /// Record the toolchain that built the module.
+    pub fn with_toolchain(mut self, toolchain: impl Into<String>) -> Self {
+        self.toolchain = Some(toolchain.into());
+        self
+    }
+
+    /// Record the component this one was forked from.
+    pub fn with_forked_from(mut self, component: impl Into<String>) -> Self {
+        self.forked_from = Some(component.into());
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::semver::Bump;
 
     #[test]
     fn test_component_id_display() {
@@ -130,9 +275,19 @@ mod tests {
         let metadata = ComponentMetadata {
             id: ComponentId(999),
             name: "TestComponent".to_string(),
-            version: 3,
+            version: SemVer::new(1, 2, 0),
             loaded_at: "2025-01-01T10:30:00Z".to_string(),
             ai_generated: true,
+            provenance: Provenance::ai("Make a form", "anthropic/claude-3.5-sonnet")
+                .with_parent(2)
+                .with_toolchain("rustc 1.82.0"),
+            capabilities: vec![Capability::Input],
+            interface_hash: None,
+            dependencies: Vec::new(),
+            lifecycle: Default::default(),
+            thumbnail: None,
+            stats: Default::default(),
+            fallback: None,
         };
 
         let json = serde_json::to_string(&metadata).expect("Failed to serialize");
@@ -144,6 +299,21 @@ mod tests {
         assert_eq!(deserialized.version, metadata.version);
         assert_eq!(deserialized.loaded_at, metadata.loaded_at);
         assert_eq!(deserialized.ai_generated, metadata.ai_generated);
+        assert_eq!(deserialized.provenance, metadata.provenance);
+    }
+
+    #[test]
+    fn test_metadata_without_provenance() {
+        // Metadata serialized before provenance was tracked still loads
+        let metadata: ComponentMetadata = serde_json::from_str(
+            r#"{"id": 1, "name": "Old", "version": 1, "loaded_at": "2025-01-01T00:00:00Z", "ai_generated": true}"#,
+        )
+        .expect("Failed to deserialize");
+
+        assert_eq!(metadata.provenance, Provenance::default());
+        assert_eq!(metadata.provenance.author, Author::Unknown);
+        // So does its version counter
+        assert_eq!(metadata.version, SemVer::INITIAL);
     }
 
     #[test]
@@ -320,17 +490,25 @@ mod tests {
         let mut metadata = ComponentMetadata {
             id: ComponentId(1),
             name: "MyComponent".to_string(),
-            version: 0,
+            version: SemVer::INITIAL,
             loaded_at: "2025-01-01T00:00:00Z".to_string(),
             ai_generated: false,
+            provenance: Provenance::human(),
+            capabilities: Vec::new(),
+            interface_hash: None,
+            dependencies: Vec::new(),
+            lifecycle: Default::default(),
+            thumbnail: None,
+            stats: Default::default(),
+            fallback: None,
         };
 
-        assert_eq!(metadata.version, 0);
+        assert_eq!(metadata.version.to_string(), "1.0.0");
 
-        metadata.version += 1;
-        assert_eq!(metadata.version, 1);
+        metadata.version = metadata.version.bump(Bump::Minor);
+        assert_eq!(metadata.version.to_string(), "1.1.0");
 
-        metadata.version += 1;
-        assert_eq!(metadata.version, 2);
+        metadata.version = metadata.version.bump(Bump::Patch);
+        assert_eq!(metadata.version.to_string(), "1.1.1");
     }
 }
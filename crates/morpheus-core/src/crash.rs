@@ -0,0 +1,191 @@
+//! Crash reports that keep the user's data.
+//!
+//! A panic traps a component's WebAssembly instance: the state it held is
+//! lost and the host only sees `unreachable executed`. A component that
+//! installs the Morpheus panic hook reports the panic message, where it
+//! happened and a final state snapshot through the [`CRASH_IMPORT`] host
+//! import before the instance dies. The host can then roll back to the last
+//! good version *with that snapshot*, so users keep what they entered, and
+//! the error report shows the state that triggered the bug.
+//!
+//! Install the hook once, with a closure that snapshots the state:
+//!
+//! ```rust,ignore
+//! use morpheus_core::crash::install_panic_hook;
+//! use morpheus_core::state::MorpheusState;
+//!
+//! thread_local! {
+//!     static STATE: RefCell<Counter> = RefCell::default();
+//! }
+//!
+//! #[wasm_bindgen(start)]
+//! fn start() {
+//!     // try_borrow: the panic may have happened while the state was borrowed
+//!     install_panic_hook(|| STATE.with(|state| state.try_borrow().ok()?.snapshot().ok()));
+//! }
+//! ```
+//!
+//! The hook calls `morpheus.crash(message, location, stateJson)` (in the
+//! [`EVENT_IMPORT_NAMESPACE`](crate::events::EVENT_IMPORT_NAMESPACE)).
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::any::Any;
+use std::panic::PanicHookInfo;
+
+/// Name of the crash import, called as
+/// `morpheus.crash(message, location, stateJson)`.
+pub const CRASH_IMPORT: &str = "crash";
+
+/// Longest panic message kept, in characters.
+pub const MAX_MESSAGE_CHARS: usize = 2000;
+
+/// A component's last words: why it panicked and the state it held.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CrashReport {
+    /// The panic message.
+    pub message: String,
+
+    /// Where the panic happened, as `file:line:column`.
+    #[serde(default)]
+    pub location: Option<String>,
+
+    /// The state snapshot taken as the component panicked.
+    #[serde(default)]
+    pub state: Option<Value>,
+
+    /// Version that crashed.
+    #[serde(default)]
+    pub version: Option<u32>,
+
+    /// When the component crashed (ISO 8601).
+    #[serde(default)]
+    pub timestamp: String,
+}
+
+impl CrashReport {
+    /// Create a report; messages longer than [`MAX_MESSAGE_CHARS`] are
+    /// truncated.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into().chars().take(MAX_MESSAGE_CHARS).collect(),
+            location: None,
+            state: None,
+            version: None,
+            timestamp: String::new(),
+        }
+    }
+
+    /// Report a panic, with the state snapshot taken as it happened.
+    pub fn from_panic(info: &PanicHookInfo, state_json: Option<&str>) -> Self {
+        let mut report = Self::new(panic_message(info.payload()));
+        report.location = info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+        report.state = state_json.map(parse_state);
+        report
+    }
+
+    /// Record where the panic happened.
+    pub fn with_location(mut self, location: impl Into<String>) -> Self {
+        self.location = Some(location.into());
+        self
+    }
+
+    /// Record the state the component held, as JSON; snapshots that aren't
+    /// JSON are kept as a string.
+    pub fn with_state(mut self, state_json: &str) -> Self {
+        self.state = Some(parse_state(state_json));
+        self
+    }
+
+    /// Record which version crashed.
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Record when the component crashed.
+    pub fn with_timestamp(mut self, timestamp: impl Into<String>) -> Self {
+        self.timestamp = timestamp.into();
+        self
+    }
+}
+
+/// The message of a panic payload (`panic!` with a literal or a format string).
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+fn parse_state(json: &str) -> Value {
+    serde_json::from_str(json).unwrap_or_else(|_| Value::String(json.to_string()))
+}
+
+/// A panic hook that snapshots the state and hands the report to `report`.
+///
+/// `snapshot` runs inside the hook, so it must not panic: a second panic
+/// aborts without reporting anything. [`install_panic_hook`] reports
+/// through the host import; this is for hosts that report differently.
+pub fn panic_hook(
+    snapshot: impl Fn() -> Option<String> + Send + Sync + 'static,
+    report: impl Fn(&CrashReport) + Send + Sync + 'static,
+) -> Box<dyn Fn(&PanicHookInfo) + Send + Sync + 'static> {
+    Box::new(move |info| report(&CrashReport::from_panic(info, snapshot().as_deref())))
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen::prelude::wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = morpheus, js_name = crash)]
+    fn morpheus_crash(message: &str, location: &str, state_json: &str);
+}
+
+/// Report panics, with a final state snapshot, to the host before the
+/// instance dies.
+#[cfg(target_arch = "wasm32")]
+pub fn install_panic_hook(snapshot: impl Fn() -> Option<String> + Send + Sync + 'static) {
+    std::panic::set_hook(panic_hook(snapshot, |report| {
+        let state = report.state.as_ref().map(Value::to_string).unwrap_or_default();
+        morpheus_crash(&report.message, report.location.as_deref().unwrap_or_default(), &state);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panic_messages() {
+        let literal = std::panic::catch_unwind(|| panic!("boom")).unwrap_err();
+        assert_eq!(panic_message(literal.as_ref()), "boom");
+
+        let index = 7;
+        let formatted = std::panic::catch_unwind(|| panic!("index {} out of bounds", index)).unwrap_err();
+        assert_eq!(panic_message(formatted.as_ref()), "index 7 out of bounds");
+
+        let other = std::panic::catch_unwind(|| std::panic::panic_any(42)).unwrap_err();
+        assert_eq!(panic_message(other.as_ref()), "Box<dyn Any>");
+    }
+
+    #[test]
+    fn test_reports_keep_the_state() {
+        let report = CrashReport::new("x".repeat(MAX_MESSAGE_CHARS + 5))
+            .with_location("src/lib.rs:12:9")
+            .with_state(r#"{"version":2,"state":{"count":3}}"#)
+            .with_version(4);
+        assert_eq!(report.message.chars().count(), MAX_MESSAGE_CHARS);
+        assert_eq!(report.state.as_ref().unwrap()["state"]["count"], 3);
+
+        let report = CrashReport::new("boom").with_state("not json");
+        assert_eq!(report.state, Some(Value::String("not json".to_string())));
+
+        let json = serde_json::to_value(CrashReport::new("boom")).unwrap();
+        let parsed: CrashReport = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, CrashReport::new("boom"));
+    }
+}
@@ -0,0 +1,362 @@
+//! Binary deltas between WASM module versions.
+//!
+//! Successive AI iterations produce modules that are mostly identical, so
+//! instead of resending the whole module the server can send a patch against
+//! the version the client already has.
+//!
+//! Both modules are split with content-defined chunking (a gear rolling
+//! hash), so an insertion early in the module only changes the chunks around
+//! it rather than shifting every later block. New chunks that also appear in
+//! the old module become copy operations; the rest are sent inline.
+//!
+//! ## Patch format
+//!
+//! All integers are little-endian `u32`.
+//!
+//! ```text
+//! "MDLT" | base_len | target_len | target_fnv1a | op*
+//! op := 0x00 offset len      (copy `len` bytes from base at `offset`)
+//!     | 0x01 len bytes[len]  (insert literal bytes)
+//! ```
+//!
+//! Patches come from the network, so [`apply`] checks every length against
+//! the base, the patch and [`MAX_TARGET_LEN`] before using it.
+
+use crate::errors::{MorpheusError, Result};
+use std::collections::HashMap;
+
+const MAGIC: &[u8; 4] = b"MDLT";
+const OP_COPY: u8 = 0;
+const OP_INSERT: u8 = 1;
+
+/// Largest module a patch may produce (64 MiB).
+pub const MAX_TARGET_LEN: usize = 64 * 1024 * 1024;
+
+const MIN_CHUNK: usize = 64;
+const MAX_CHUNK: usize = 4096;
+/// Boundary when the low 8 bits of the hash are zero (~256 byte average).
+const BOUNDARY_MASK: u64 = 0xff;
+
+/// Compute a patch that turns `base` into `target`.
+pub fn diff(base: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut index: HashMap<&[u8], usize> = HashMap::new();
+    let mut offset = 0;
+    for chunk in chunks(base) {
+        index.entry(chunk).or_insert(offset);
+        offset += chunk.len();
+    }
+
+    let mut patch = Vec::new();
+    patch.extend_from_slice(MAGIC);
+    push_u32(&mut patch, base.len());
+    push_u32(&mut patch, target.len());
+    patch.extend_from_slice(&fnv1a(target).to_le_bytes());
+
+    let mut pending_copy: Option<(usize, usize)> = None;
+    let mut pending_insert: Vec<u8> = Vec::new();
+
+    for chunk in chunks(target) {
+        match index.get(chunk) {
+            Some(&base_offset) => {
+                flush_insert(&mut patch, &mut pending_insert);
+                pending_copy = match pending_copy {
+                    // Extend a copy whose source is contiguous
+                    Some((start, len)) if start + len == base_offset => Some((start, len + chunk.len())),
+                    Some((start, len)) => {
+                        push_copy(&mut patch, start, len);
+                        Some((base_offset, chunk.len()))
+                    }
+                    None => Some((base_offset, chunk.len())),
+                };
+            }
+            None => {
+                if let Some((start, len)) = pending_copy.take() {
+                    push_copy(&mut patch, start, len);
+                }
+                pending_insert.extend_from_slice(chunk);
+            }
+        }
+    }
+
+    if let Some((start, len)) = pending_copy {
+        push_copy(&mut patch, start, len);
+    }
+    flush_insert(&mut patch, &mut pending_insert);
+
+    patch
+}
+
+/// Apply a patch produced by [`diff`] to `base`.
+///
+/// Fails if the patch is malformed, was computed against a different base,
+/// or doesn't reproduce the expected target.
+pub fn apply(base: &[u8], patch: &[u8]) -> Result<Vec<u8>> {
+    let mut reader = Reader { bytes: patch, pos: 0 };
+
+    if reader.take(4)? != MAGIC {
+        return Err(invalid("missing header"));
+    }
+    let base_len = reader.u32()?;
+    let target_len = reader.u32()?;
+    let checksum = reader.u32()? as u32;
+
+    if base_len != base.len() {
+        return Err(invalid("patch was computed against a different base module"));
+    }
+    if target_len > MAX_TARGET_LEN {
+        return Err(invalid(&format!("target of {} bytes exceeds {} bytes", target_len, MAX_TARGET_LEN)));
+    }
+
+    let mut target = Vec::with_capacity(target_len);
+    while reader.pos < patch.len() {
+        match reader.take(1)?[0] {
+            OP_COPY => {
+                let offset = reader.u32()?;
+                let len = reader.u32()?;
+                let source = offset
+                    .checked_add(len)
+                    .and_then(|end| base.get(offset..end))
+                    .ok_or_else(|| invalid("copy out of range"))?;
+                target.extend_from_slice(source);
+            }
+            OP_INSERT => {
+                let len = reader.u32()?;
+                target.extend_from_slice(reader.take(len)?);
+            }
+            op => return Err(invalid(&format!("unknown op {}", op))),
+        }
+        if target.len() > target_len {
+            return Err(invalid("patch produces too many bytes"));
+        }
+    }
+
+    if target.len() != target_len || fnv1a(&target) != checksum {
+        return Err(invalid("patched module does not match the expected target"));
+    }
+
+    Ok(target)
+}
+
+/// Split bytes into content-defined chunks.
+fn chunks(bytes: &[u8]) -> Vec<&[u8]> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    let mut hash: u64 = 0;
+
+    for (i, byte) in bytes.iter().enumerate() {
+        hash = (hash << 1).wrapping_add(gear(*byte));
+        let len = i + 1 - start;
+        if (len >= MIN_CHUNK && hash & BOUNDARY_MASK == 0) || len >= MAX_CHUNK {
+            result.push(&bytes[start..=i]);
+            start = i + 1;
+            hash = 0;
+        }
+    }
+    if start < bytes.len() {
+        result.push(&bytes[start..]);
+    }
+
+    result
+}
+
+/// Per-byte random value for the gear hash (splitmix64 of the byte).
+fn gear(byte: u8) -> u64 {
+    let mut z = (byte as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// 32-bit FNV-1a checksum.
+fn fnv1a(bytes: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in bytes {
+        hash ^= *byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+fn push_u32(out: &mut Vec<u8>, value: usize) {
+    out.extend_from_slice(&(value as u32).to_le_bytes());
+}
+
+fn push_copy(out: &mut Vec<u8>, offset: usize, len: usize) {
+    out.push(OP_COPY);
+    push_u32(out, offset);
+    push_u32(out, len);
+}
+
+fn flush_insert(out: &mut Vec<u8>, pending: &mut Vec<u8>) {
+    if !pending.is_empty() {
+        out.push(OP_INSERT);
+        push_u32(out, pending.len());
+        out.append(pending);
+    }
+}
+
+fn invalid(reason: &str) -> MorpheusError {
+    MorpheusError::LoadError(format!("Invalid WASM patch: {}", reason))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.bytes.len());
+        let end = end.ok_or_else(|| invalid("truncated"))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<usize> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random bytes standing in for a compiled module.
+    fn module_bytes(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_roundtrip_identical() {
+        let base = module_bytes(50_000, 1);
+        let patch = diff(&base, &base);
+
+        assert_eq!(apply(&base, &patch).expect("Failed to apply"), base);
+        // Header plus a single copy op
+        assert!(patch.len() < 32);
+    }
+
+    #[test]
+    fn test_small_edit_produces_small_patch() {
+        let base = module_bytes(100_000, 2);
+        let mut target = base.clone();
+        // Insert a few bytes in the middle and change some near the end
+        target.splice(40_000..40_000, [1, 2, 3, 4, 5, 6, 7]);
+        target[90_000] ^= 0xff;
+
+        let patch = diff(&base, &target);
+
+        assert_eq!(apply(&base, &patch).expect("Failed to apply"), target);
+        assert!(
+            patch.len() < target.len() / 10,
+            "patch of {} bytes for {} byte module",
+            patch.len(),
+            target.len()
+        );
+    }
+
+    #[test]
+    fn test_unrelated_modules_roundtrip() {
+        let base = module_bytes(10_000, 3);
+        let target = module_bytes(12_345, 4);
+
+        let patch = diff(&base, &target);
+
+        assert_eq!(apply(&base, &patch).expect("Failed to apply"), target);
+    }
+
+    #[test]
+    fn test_empty_inputs() {
+        let target = module_bytes(1_000, 5);
+
+        assert_eq!(apply(&[], &diff(&[], &target)).unwrap(), target);
+        assert!(apply(&target, &diff(&target, &[])).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_wrong_base_rejected() {
+        let base = module_bytes(10_000, 6);
+        let target = module_bytes(10_000, 7);
+        let patch = diff(&base, &target);
+
+        let other_base = module_bytes(9_000, 8);
+        assert!(matches!(apply(&other_base, &patch), Err(MorpheusError::LoadError(_))));
+    }
+
+    #[test]
+    fn test_same_length_wrong_base_fails_checksum() {
+        let base = module_bytes(10_000, 9);
+        let mut target = base.clone();
+        target[5_000] ^= 1;
+        let patch = diff(&base, &target);
+
+        let mut other_base = base.clone();
+        other_base[100] ^= 1;
+        match apply(&other_base, &patch) {
+            Err(MorpheusError::LoadError(msg)) => assert!(msg.contains("does not match")),
+            _ => panic!("Expected checksum mismatch"),
+        }
+    }
+
+    #[test]
+    fn test_truncated_patch_rejected() {
+        let base = module_bytes(10_000, 10);
+        let target = module_bytes(10_000, 11);
+        let patch = diff(&base, &target);
+
+        assert!(apply(&base, &patch[..patch.len() / 2]).is_err());
+        assert!(apply(&base, b"MD").is_err());
+        assert!(apply(&base, b"not a patch at all").is_err());
+    }
+
+    #[test]
+    fn test_hostile_lengths_rejected() {
+        let base = module_bytes(1_000, 13);
+        let header = |target_len: u32| {
+            let mut patch = MAGIC.to_vec();
+            patch.extend_from_slice(&(base.len() as u32).to_le_bytes());
+            patch.extend_from_slice(&target_len.to_le_bytes());
+            patch.extend_from_slice(&0u32.to_le_bytes());
+            patch
+        };
+
+        match apply(&base, &header(u32::MAX)) {
+            Err(MorpheusError::LoadError(msg)) => assert!(msg.contains("exceeds")),
+            _ => panic!("Expected the target length to be refused"),
+        }
+
+        let mut copy_past_end = header(100);
+        push_copy(&mut copy_past_end, 990, 100);
+        match apply(&base, &copy_past_end) {
+            Err(MorpheusError::LoadError(msg)) => assert!(msg.contains("copy out of range")),
+            _ => panic!("Expected the copy to be refused"),
+        }
+
+        let mut overflowing_copy = header(100);
+        push_copy(&mut overflowing_copy, u32::MAX as usize, u32::MAX as usize);
+        assert!(apply(&base, &overflowing_copy).is_err());
+
+        let mut long_insert = header(100);
+        long_insert.push(OP_INSERT);
+        push_u32(&mut long_insert, 1_000_000);
+        assert!(apply(&base, &long_insert).is_err());
+    }
+
+    #[test]
+    fn test_chunks_cover_input() {
+        let bytes = module_bytes(20_000, 12);
+        let chunks = chunks(&bytes);
+
+        assert_eq!(chunks.concat(), bytes);
+        assert!(chunks.iter().all(|c| c.len() <= MAX_CHUNK));
+        assert!(chunks[..chunks.len() - 1].iter().all(|c| c.len() >= MIN_CHUNK));
+    }
+}
@@ -0,0 +1,214 @@
+//! Drag and drop for reorderable lists.
+//!
+//! A component marks a list with [`SORTABLE_ATTR`] and its items with
+//! [`KEY_ATTR`]; the host handles the browser's `dragstart`, `dragover` and
+//! `drop` events and calls the component's [`REORDER_EXPORT`] with the
+//! list name and the moved item's old and new index. Kanban boards are one
+//! sortable list per column.
+//!
+//! ```rust
+//! use morpheus_core::drag::{sortable_list, Reorder};
+//!
+//! let todo = sortable_list("todo");
+//! let html = format!(r#"<ul{}><li{}>Write</li><li{}>Ship</li></ul>"#, todo.list_attrs(), todo.item_attrs("1"), todo.item_attrs("2"));
+//! assert!(html.contains(r#"<li draggable="true" data-key="2">"#));
+//!
+//! // What the component's `on_reorder("todo", 1, 0)` does
+//! let mut items = vec!["Write", "Ship"];
+//! Reorder { list: "todo".to_string(), from: 1, to: 0 }.apply(&mut items);
+//! assert_eq!(items, ["Ship", "Write"]);
+//! ```
+//!
+//! Components built on [`DynamicComponent`](crate::component::DynamicComponent)
+//! feed [`DragEvent`]s to a [`SortableList`], which turns a completed drag
+//! into a [`Reorder`] message.
+
+use serde::{Deserialize, Serialize};
+
+/// Attribute naming a sortable list.
+pub const SORTABLE_ATTR: &str = "data-sortable";
+
+/// Attribute identifying an item, stable across renders.
+pub const KEY_ATTR: &str = "data-key";
+
+/// Component export the host calls after a drop, as
+/// `on_reorder(list: &str, from: usize, to: usize)`.
+pub const REORDER_EXPORT: &str = "on_reorder";
+
+/// MIME type of [`DragData`] in the browser's `DataTransfer`.
+pub const DRAG_MIME: &str = "application/x-morpheus-drag+json";
+
+/// What is being dragged, carried in the `DataTransfer`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DragData {
+    /// List the item came from.
+    pub list: String,
+    /// The item's key.
+    pub key: String,
+}
+
+impl DragData {
+    /// The `(format, data)` pair for `DataTransfer.setData`.
+    pub fn to_transfer(&self) -> (&'static str, String) {
+        (DRAG_MIME, serde_json::to_string(self).unwrap_or_default())
+    }
+
+    /// Read drag data from `DataTransfer.getData(DRAG_MIME)`; `None` for
+    /// anything else being dragged over the component (files, text).
+    pub fn from_transfer(data: &str) -> Option<Self> {
+        serde_json::from_str(data).ok()
+    }
+}
+
+/// An item moved within a list: remove it at `from`, insert it at `to`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reorder {
+    pub list: String,
+    pub from: usize,
+    pub to: usize,
+}
+
+impl Reorder {
+    /// Apply the move to the list's items; out-of-range moves are ignored.
+    pub fn apply<T>(&self, items: &mut Vec<T>) {
+        if self.from < items.len() && self.to < items.len() {
+            let item = items.remove(self.from);
+            items.insert(self.to, item);
+        }
+    }
+}
+
+/// Browser drag events on a sortable list's items, by item index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragEvent {
+    /// `dragstart` on an item.
+    Start(usize),
+    /// `dragover` on an item.
+    Over(usize),
+    /// `drop` on an item.
+    Drop(usize),
+    /// `dragend` without a drop on the list.
+    Cancel,
+}
+
+/// A reorderable list's attributes and drag state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortableList {
+    pub name: String,
+    dragging: Option<usize>,
+    over: Option<usize>,
+}
+
+/// A sortable list named `name`.
+pub fn sortable_list(name: impl Into<String>) -> SortableList {
+    SortableList {
+        name: name.into(),
+        dragging: None,
+        over: None,
+    }
+}
+
+impl SortableList {
+    /// Attributes for the list element, as HTML with a leading space.
+    pub fn list_attrs(&self) -> String {
+        format!(" {}=\"{}\"", SORTABLE_ATTR, escape(&self.name))
+    }
+
+    /// Attributes for an item, as HTML with a leading space.
+    pub fn item_attrs(&self, key: &str) -> String {
+        format!(" draggable=\"true\" {}=\"{}\"", KEY_ATTR, escape(key))
+    }
+
+    /// Index of the item being dragged.
+    pub fn dragging(&self) -> Option<usize> {
+        self.dragging
+    }
+
+    /// Index of the item under the pointer, for drawing a drop marker.
+    pub fn over(&self) -> Option<usize> {
+        self.over
+    }
+
+    /// Handle a drag event, returning the move once an item is dropped
+    /// somewhere new.
+    pub fn handle(&mut self, event: DragEvent) -> Option<Reorder> {
+        match event {
+            DragEvent::Start(index) => {
+                self.dragging = Some(index);
+                self.over = None;
+                None
+            }
+            DragEvent::Over(index) => {
+                if self.dragging.is_some() {
+                    self.over = Some(index);
+                }
+                None
+            }
+            DragEvent::Drop(to) => {
+                let from = self.dragging.take();
+                self.over = None;
+                from.filter(|&from| from != to).map(|from| Reorder {
+                    list: self.name.clone(),
+                    from,
+                    to,
+                })
+            }
+            DragEvent::Cancel => {
+                self.dragging = None;
+                self.over = None;
+                None
+            }
+        }
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drag_and_drop_emits_reorder() {
+        let mut list = sortable_list("doing");
+
+        assert_eq!(list.handle(DragEvent::Start(0)), None);
+        list.handle(DragEvent::Over(2));
+        assert_eq!(list.over(), Some(2));
+        let reorder = list.handle(DragEvent::Drop(2)).unwrap();
+
+        assert_eq!(reorder, Reorder { list: "doing".to_string(), from: 0, to: 2 });
+        assert_eq!(list.dragging(), None);
+        let mut items = vec!['a', 'b', 'c'];
+        reorder.apply(&mut items);
+        assert_eq!(items, ['b', 'c', 'a']);
+    }
+
+    #[test]
+    fn test_no_reorder_without_a_move() {
+        let mut list = sortable_list("todo");
+
+        assert_eq!(list.handle(DragEvent::Drop(1)), None);
+        list.handle(DragEvent::Start(1));
+        assert_eq!(list.handle(DragEvent::Drop(1)), None);
+        list.handle(DragEvent::Start(1));
+        list.handle(DragEvent::Cancel);
+        assert_eq!(list.handle(DragEvent::Drop(0)), None);
+
+        let mut items = vec![1, 2];
+        Reorder { list: "todo".to_string(), from: 5, to: 0 }.apply(&mut items);
+        assert_eq!(items, [1, 2]);
+    }
+
+    #[test]
+    fn test_drag_data_round_trip() {
+        let data = DragData { list: "todo".to_string(), key: "7".to_string() };
+        let (format, text) = data.to_transfer();
+
+        assert_eq!(format, DRAG_MIME);
+        assert_eq!(DragData::from_transfer(&text), Some(data));
+        assert_eq!(DragData::from_transfer("plain text"), None);
+    }
+}
@@ -0,0 +1,74 @@
+//! Local text embeddings, for finding similar prompts and components.
+//!
+//! Text is embedded by hashing its words and word pairs into a fixed-size
+//! vector: no network and no model, and good enough to tell "a table of
+//! users" from "a countdown timer". Servers can swap in an embeddings API
+//! for prompts (see the few-shot examples in `morpheus-complete`).
+
+/// Size of local embeddings.
+pub const DIMENSIONS: usize = 256;
+
+/// Words too common to say anything about a request.
+const STOPWORDS: &[&str] = &["the", "and", "for", "with", "that", "this", "make", "create", "add", "component"];
+
+/// Embed `text` by hashing its words and word pairs, normalized to unit length.
+pub fn embed(text: &str) -> Vec<f32> {
+    let lowercase = text.to_lowercase();
+    let words: Vec<&str> = lowercase
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 2 && !STOPWORDS.contains(w))
+        .collect();
+
+    let mut vector = vec![0.0; DIMENSIONS];
+    for word in &words {
+        vector[fnv1a(word.as_bytes()) % DIMENSIONS] += 1.0;
+    }
+    for pair in words.windows(2) {
+        vector[fnv1a(format!("{} {}", pair[0], pair[1]).as_bytes()) % DIMENSIONS] += 0.5;
+    }
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+/// FNV-1a: stable across builds, unlike `DefaultHasher`, so stored embeddings stay valid.
+fn fnv1a(bytes: &[u8]) -> usize {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash as usize
+}
+
+/// Cosine similarity; 0 for vectors of different sizes.
+pub fn similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embeddings_rank_related_text_higher() {
+        let counter = embed("Create a counter with increment and reset buttons");
+        let similar = embed("A counter with a reset button");
+        let unrelated = embed("Show a table of weather forecasts");
+
+        assert!((similarity(&counter, &counter) - 1.0).abs() < 1e-5);
+        assert!(similarity(&counter, &similar) > similarity(&counter, &unrelated));
+        assert_eq!(similarity(&counter, &[1.0]), 0.0);
+    }
+}
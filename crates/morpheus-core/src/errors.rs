@@ -21,6 +21,15 @@ pub enum MorpheusError {
     #[error("Invalid state: {0}")]
     InvalidState(String),
 
+    /// A state snapshot exceeds the configured size limit.
+    #[error("Snapshot is {size} bytes, over the {limit} byte limit")]
+    SnapshotTooLarge {
+        /// Serialized size in bytes (when decoding, as much as was read).
+        size: usize,
+        /// Configured limit in bytes.
+        limit: usize,
+    },
+
     /// Serialization/deserialization error.
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
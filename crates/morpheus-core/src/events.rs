@@ -0,0 +1,231 @@
+//! Event-sourced component state.
+//!
+//! Instead of sending its whole state after every interaction, a component
+//! can emit small domain events ("todo_added", "filter_changed") through the
+//! [`EMIT_EVENT_IMPORT`] host import. The host appends them to a per-component
+//! [`EventLog`], and state at any point is rebuilt by replaying the log
+//! through a [`Reducer`]. That gives exact time travel and keeps storage
+//! proportional to what changed rather than to the size of the state.
+//!
+//! Components declare the import with wasm-bindgen:
+//!
+//! ```rust,ignore
+//! #[wasm_bindgen]
+//! extern "C" {
+//!     #[wasm_bindgen(js_namespace = morpheus, js_name = emitEvent)]
+//!     fn emit_event(name: &str, payload_json: &str);
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// JS namespace of the event import.
+pub const EVENT_IMPORT_NAMESPACE: &str = "morpheus";
+
+/// Name of the event import, called as `morpheus.emitEvent(name, payloadJson)`.
+pub const EMIT_EVENT_IMPORT: &str = "emitEvent";
+
+/// Something that happened in a component.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DomainEvent {
+    /// Position in the component's log, starting at 1.
+    #[serde(default)]
+    pub seq: u64,
+
+    /// Event name, e.g. `todo_added`.
+    pub name: String,
+
+    /// Event data.
+    #[serde(default)]
+    pub payload: Value,
+
+    /// Version of the component that emitted the event.
+    #[serde(default)]
+    pub version: Option<u32>,
+
+    /// When the event was recorded (ISO 8601).
+    #[serde(default)]
+    pub timestamp: String,
+}
+
+impl DomainEvent {
+    /// Create an event; its sequence number is assigned by [`EventLog::append`].
+    pub fn new(name: impl Into<String>, payload: Value) -> Self {
+        Self {
+            seq: 0,
+            name: name.into(),
+            payload,
+            version: None,
+            timestamp: String::new(),
+        }
+    }
+
+    /// Record which component version emitted the event.
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Record when the event happened.
+    pub fn with_timestamp(mut self, timestamp: impl Into<String>) -> Self {
+        self.timestamp = timestamp.into();
+        self
+    }
+}
+
+/// Folds events into state.
+pub trait Reducer {
+    /// Apply `event` to `state`.
+    fn reduce(&self, state: &mut Value, event: &DomainEvent);
+}
+
+impl<F: Fn(&mut Value, &DomainEvent)> Reducer for F {
+    fn reduce(&self, state: &mut Value, event: &DomainEvent) {
+        self(state, event)
+    }
+}
+
+/// Reducer that applies each event's payload as a JSON Merge Patch
+/// (RFC 7386): object fields are merged recursively, `null` removes a
+/// field, and anything else replaces the value.
+///
+/// Works for any component whose events carry the fields they changed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MergePatchReducer;
+
+impl Reducer for MergePatchReducer {
+    fn reduce(&self, state: &mut Value, event: &DomainEvent) {
+        merge_patch(state, &event.payload);
+    }
+}
+
+/// Apply a JSON Merge Patch to `target`.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(fields) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let Value::Object(target_fields) = target else {
+        unreachable!("target was just made an object");
+    };
+    for (key, value) in fields {
+        if value.is_null() {
+            target_fields.remove(key);
+        } else {
+            merge_patch(target_fields.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// Ordered log of one component's events.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventLog {
+    events: Vec<DomainEvent>,
+}
+
+impl EventLog {
+    /// Create an empty log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an event, returning its sequence number.
+    pub fn append(&mut self, mut event: DomainEvent) -> u64 {
+        event.seq = self.last_seq() + 1;
+        self.events.push(event);
+        self.last_seq()
+    }
+
+    /// All events, oldest first.
+    pub fn events(&self) -> &[DomainEvent] {
+        &self.events
+    }
+
+    /// Sequence number of the latest event (0 if empty).
+    pub fn last_seq(&self) -> u64 {
+        self.events.last().map_or(0, |e| e.seq)
+    }
+
+    /// Events after `seq`.
+    pub fn since(&self, seq: u64) -> &[DomainEvent] {
+        let start = self.events.partition_point(|e| e.seq <= seq);
+        &self.events[start..]
+    }
+
+    /// Sequence number of the last event emitted by `version` or an
+    /// earlier one (0 if there is none).
+    pub fn last_seq_at_version(&self, version: u32) -> u64 {
+        self.events
+            .iter()
+            .rev()
+            .find(|e| e.version.is_some_and(|v| v <= version))
+            .map_or(0, |e| e.seq)
+    }
+
+    /// Rebuild state from `initial` by replaying events up to and
+    /// including `until` (or all of them).
+    pub fn replay(&self, initial: Value, reducer: &dyn Reducer, until: Option<u64>) -> Value {
+        let mut state = initial;
+        for event in self.events.iter().take_while(|e| until.is_none_or(|until| e.seq <= until)) {
+            reducer.reduce(&mut state, event);
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn log() -> EventLog {
+        let mut log = EventLog::new();
+        log.append(DomainEvent::new("renamed", json!({ "title": "Groceries" })).with_version(1));
+        log.append(DomainEvent::new("filtered", json!({ "filter": { "done": false } })).with_version(1));
+        log.append(DomainEvent::new("unfiltered", json!({ "filter": null })).with_version(2));
+        log
+    }
+
+    #[test]
+    fn test_append_assigns_sequence_numbers() {
+        let log = log();
+
+        assert_eq!(log.last_seq(), 3);
+        assert_eq!(log.since(1).iter().map(|e| e.seq).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(log.last_seq_at_version(1), 2);
+        assert_eq!(log.last_seq_at_version(9), 3);
+        assert_eq!(log.last_seq_at_version(0), 0);
+    }
+
+    #[test]
+    fn test_replay_to_any_point() {
+        let log = log();
+
+        assert_eq!(
+            log.replay(json!({}), &MergePatchReducer, Some(2)),
+            json!({ "title": "Groceries", "filter": { "done": false } })
+        );
+        assert_eq!(log.replay(json!({}), &MergePatchReducer, None), json!({ "title": "Groceries" }));
+        assert_eq!(log.replay(json!({ "n": 1 }), &MergePatchReducer, Some(0)), json!({ "n": 1 }));
+    }
+
+    #[test]
+    fn test_custom_reducer() {
+        let mut log = EventLog::new();
+        for _ in 0..3 {
+            log.append(DomainEvent::new("incremented", Value::Null));
+        }
+        let counter = |state: &mut Value, event: &DomainEvent| {
+            if event.name == "incremented" {
+                *state = json!(state.as_i64().unwrap_or(0) + 1);
+            }
+        };
+
+        assert_eq!(log.replay(json!(0), &counter, None), json!(3));
+    }
+}
@@ -0,0 +1,288 @@
+//! A/B experiments between two versions of a component.
+//!
+//! Where a feature flag pins everyone to one version, an [`Experiment`]
+//! keeps two versions live at once: sessions are split between a
+//! [`Variant::Control`] and a [`Variant::Treatment`] version, each
+//! variant's exposures, conversions and ratings are counted, and once the
+//! numbers are in the host declares a winner, which becomes the new
+//! baseline.
+//!
+//! Assignment hashes the session ID, so a session sees the same variant on
+//! every visit and across host restarts, without the host storing anything.
+//!
+//! Components report conversions through the [`CONVERT_IMPORT`] host import:
+//!
+//! ```rust,ignore
+//! #[wasm_bindgen]
+//! extern "C" {
+//!     #[wasm_bindgen(js_namespace = morpheus, js_name = convert)]
+//!     fn morpheus_convert(goal: &str);
+//! }
+//! ```
+
+use crate::errors::{MorpheusError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Name of the conversion import, called as `morpheus.convert(goal)`
+/// (in the [`EVENT_IMPORT_NAMESPACE`](crate::events::EVENT_IMPORT_NAMESPACE)).
+pub const CONVERT_IMPORT: &str = "convert";
+
+/// One side of an experiment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Variant {
+    /// The version that was live before the experiment.
+    Control,
+
+    /// The version being tried.
+    Treatment,
+}
+
+/// What was observed for one variant.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct VariantStats {
+    /// Sessions that were shown the variant.
+    pub exposures: u64,
+
+    /// Conversions reported by those sessions.
+    pub conversions: u64,
+
+    /// Ratings given to the variant's version.
+    pub ratings: u64,
+
+    /// Sum of those ratings.
+    pub rating_total: u64,
+}
+
+impl VariantStats {
+    /// Conversions per exposed session, or `None` before any exposure.
+    pub fn conversion_rate(&self) -> Option<f64> {
+        (self.exposures > 0).then(|| self.conversions as f64 / self.exposures as f64)
+    }
+
+    /// Mean rating, or `None` without ratings.
+    pub fn average_rating(&self) -> Option<f64> {
+        (self.ratings > 0).then(|| self.rating_total as f64 / self.ratings as f64)
+    }
+}
+
+/// Whether an experiment is still splitting traffic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ExperimentStatus {
+    /// Sessions are split between the variants.
+    Running,
+
+    /// A winner was declared; everyone gets its version.
+    Concluded { winner: Variant },
+}
+
+/// Two versions of a component live at once, with traffic split by session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Experiment {
+    /// Baseline version.
+    pub control: u32,
+
+    /// Version being tried.
+    pub treatment: u32,
+
+    /// Fraction of sessions shown the treatment, from 0 to 1.
+    pub treatment_share: f64,
+
+    /// Observations for the control.
+    #[serde(default)]
+    pub control_stats: VariantStats,
+
+    /// Observations for the treatment.
+    #[serde(default)]
+    pub treatment_stats: VariantStats,
+
+    /// Whether traffic is still split.
+    #[serde(flatten)]
+    pub status: ExperimentStatus,
+}
+
+impl Experiment {
+    /// Split sessions evenly between `control` and `treatment`.
+    pub fn new(control: u32, treatment: u32) -> Result<Self> {
+        if control == treatment {
+            return Err(MorpheusError::InvalidState(format!(
+                "Experiment needs two different versions, got {} twice",
+                control
+            )));
+        }
+        Ok(Self {
+            control,
+            treatment,
+            treatment_share: 0.5,
+            control_stats: VariantStats::default(),
+            treatment_stats: VariantStats::default(),
+            status: ExperimentStatus::Running,
+        })
+    }
+
+    /// Show the treatment to `share` of sessions instead of half.
+    pub fn with_treatment_share(mut self, share: f64) -> Result<Self> {
+        if !(0.0..=1.0).contains(&share) {
+            return Err(MorpheusError::InvalidState(format!(
+                "Treatment share {} is outside 0-1",
+                share
+            )));
+        }
+        self.treatment_share = share;
+        Ok(self)
+    }
+
+    /// Whether traffic is still split.
+    pub fn is_running(&self) -> bool {
+        self.status == ExperimentStatus::Running
+    }
+
+    /// The variant `session` sees. Stable for a given session and experiment.
+    pub fn assign(&self, session: &str) -> Variant {
+        if let ExperimentStatus::Concluded { winner } = self.status {
+            return winner;
+        }
+        // FNV-1a, so assignments don't change between builds or restarts
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let key = format!("{}:{}:{}", self.control, self.treatment, session);
+        for byte in key.bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        let bucket = (hash % 10_000) as f64 / 10_000.0;
+        if bucket < self.treatment_share {
+            Variant::Treatment
+        } else {
+            Variant::Control
+        }
+    }
+
+    /// Version shown for `variant`.
+    pub fn version(&self, variant: Variant) -> u32 {
+        match variant {
+            Variant::Control => self.control,
+            Variant::Treatment => self.treatment,
+        }
+    }
+
+    /// Variant showing `version`, if it is part of the experiment.
+    pub fn variant_of(&self, version: u32) -> Option<Variant> {
+        if version == self.control {
+            Some(Variant::Control)
+        } else if version == self.treatment {
+            Some(Variant::Treatment)
+        } else {
+            None
+        }
+    }
+
+    /// Observations for `variant`.
+    pub fn stats(&self, variant: Variant) -> &VariantStats {
+        match variant {
+            Variant::Control => &self.control_stats,
+            Variant::Treatment => &self.treatment_stats,
+        }
+    }
+
+    fn stats_mut(&mut self, variant: Variant) -> &mut VariantStats {
+        match variant {
+            Variant::Control => &mut self.control_stats,
+            Variant::Treatment => &mut self.treatment_stats,
+        }
+    }
+
+    /// Count a session being shown `variant`.
+    pub fn record_exposure(&mut self, variant: Variant) {
+        self.stats_mut(variant).exposures += 1;
+    }
+
+    /// Count a conversion by a session shown `variant`.
+    pub fn record_conversion(&mut self, variant: Variant) {
+        self.stats_mut(variant).conversions += 1;
+    }
+
+    /// Count a rating of `variant`'s version.
+    pub fn record_rating(&mut self, variant: Variant, rating: u8) {
+        let stats = self.stats_mut(variant);
+        stats.ratings += 1;
+        stats.rating_total += u64::from(rating);
+    }
+
+    /// Stop splitting traffic; returns the winning version.
+    pub fn conclude(&mut self, winner: Variant) -> Result<u32> {
+        if !self.is_running() {
+            return Err(MorpheusError::InvalidState("Experiment already concluded".to_string()));
+        }
+        self.status = ExperimentStatus::Concluded { winner };
+        Ok(self.version(winner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_needs_two_versions() {
+        assert!(Experiment::new(2, 2).is_err());
+        assert!(Experiment::new(1, 2).unwrap().with_treatment_share(1.5).is_err());
+    }
+
+    #[test]
+    fn test_assignment_is_stable_and_split() {
+        let experiment = Experiment::new(1, 2).unwrap();
+        assert_eq!(experiment.assign("session-a"), experiment.assign("session-a"));
+
+        let treated = (0..1000)
+            .filter(|i| experiment.assign(&format!("session-{}", i)) == Variant::Treatment)
+            .count();
+        assert!((400..600).contains(&treated), "{} of 1000 sessions treated", treated);
+
+        let none = Experiment::new(1, 2).unwrap().with_treatment_share(0.0).unwrap();
+        assert!((0..100).all(|i| none.assign(&i.to_string()) == Variant::Control));
+    }
+
+    #[test]
+    fn test_metrics_per_variant() {
+        let mut experiment = Experiment::new(1, 2).unwrap();
+        experiment.record_exposure(Variant::Control);
+        experiment.record_exposure(Variant::Control);
+        experiment.record_exposure(Variant::Treatment);
+        experiment.record_conversion(Variant::Treatment);
+        experiment.record_rating(Variant::Treatment, 4);
+        experiment.record_rating(Variant::Treatment, 5);
+
+        assert_eq!(experiment.stats(Variant::Control).conversion_rate(), Some(0.0));
+        assert_eq!(experiment.stats(Variant::Treatment).conversion_rate(), Some(1.0));
+        assert_eq!(experiment.stats(Variant::Treatment).average_rating(), Some(4.5));
+        assert_eq!(experiment.stats(Variant::Control).average_rating(), None);
+        assert_eq!(experiment.variant_of(2), Some(Variant::Treatment));
+        assert_eq!(experiment.variant_of(3), None);
+    }
+
+    #[test]
+    fn test_conclude_sends_everyone_to_winner() {
+        let mut experiment = Experiment::new(1, 2).unwrap();
+        assert_eq!(experiment.conclude(Variant::Treatment).unwrap(), 2);
+        assert!(!experiment.is_running());
+        assert!((0..100).all(|i| experiment.assign(&i.to_string()) == Variant::Treatment));
+        assert!(experiment.conclude(Variant::Control).is_err());
+    }
+
+    #[test]
+    fn test_wire_format() {
+        let mut experiment = Experiment::new(1, 2).unwrap();
+        experiment.conclude(Variant::Control).unwrap();
+        let json = serde_json::to_value(&experiment).unwrap();
+        assert_eq!(json["status"], "concluded");
+        assert_eq!(json["winner"], "control");
+
+        let parsed: Experiment = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, experiment);
+    }
+}
@@ -0,0 +1,100 @@
+//! Fallback views: what a client shows when a component fails to load.
+//!
+//! A module that fails to download, compile or instantiate leaves its
+//! mount point empty, and the rest of the page looks broken with it. Each
+//! component can declare a [`FallbackView`] in its metadata: static HTML
+//! (an error message, or a skeleton of the component) or another, simpler
+//! and stable, registered component. Clients render the fallback in the
+//! component's place and report the failure. Components that declare none
+//! get a short error message ([`DEFAULT_ERROR_HTML`]).
+//!
+//! A fallback is only shown when no working version is on screen: a failed
+//! hot reload keeps the running version. A fallback component that fails to
+//! load itself falls back to [`DEFAULT_ERROR_HTML`], never to its own
+//! fallback.
+//!
+//! ```rust
+//! use morpheus_core::fallback::{FallbackView, DEFAULT_ERROR_HTML};
+//!
+//! let view = FallbackView::component("basic-chart");
+//! assert_eq!(view.fallback_component(), Some("basic-chart"));
+//!
+//! let json = serde_json::to_value(FallbackView::html("<p>Chart unavailable</p>")).unwrap();
+//! assert_eq!(json, serde_json::json!({ "kind": "html", "html": "<p>Chart unavailable</p>" }));
+//! assert_eq!(FallbackView::default(), FallbackView::html(DEFAULT_ERROR_HTML));
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// Shown in place of a component that failed to load and declares no
+/// fallback.
+pub const DEFAULT_ERROR_HTML: &str =
+    r#"<div class="morpheus-error" role="alert">This part of the page couldn't be loaded.</div>"#;
+
+/// What to render in place of a component that failed to load.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FallbackView {
+    /// Static HTML.
+    Html { html: String },
+
+    /// Another registered component, by name.
+    Component { component: String },
+}
+
+impl FallbackView {
+    /// Show `html`.
+    pub fn html(html: impl Into<String>) -> Self {
+        FallbackView::Html { html: html.into() }
+    }
+
+    /// Render the component named `component` instead.
+    pub fn component(component: impl Into<String>) -> Self {
+        FallbackView::Component {
+            component: component.into(),
+        }
+    }
+
+    /// The component rendered instead, if the fallback is one.
+    pub fn fallback_component(&self) -> Option<&str> {
+        match self {
+            FallbackView::Component { component } => Some(component),
+            FallbackView::Html { .. } => None,
+        }
+    }
+}
+
+impl Default for FallbackView {
+    fn default() -> Self {
+        FallbackView::html(DEFAULT_ERROR_HTML)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_fallbacks_round_trip_by_kind() {
+        let view: FallbackView =
+            serde_json::from_value(json!({ "kind": "component", "component": "basic-chart" })).unwrap();
+        assert_eq!(view, FallbackView::component("basic-chart"));
+        assert_eq!(serde_json::from_value::<FallbackView>(serde_json::to_value(&view).unwrap()).unwrap(), view);
+
+        let html: FallbackView = serde_json::from_value(json!({ "kind": "html", "html": "<p>Soon</p>" })).unwrap();
+        assert_eq!(html.fallback_component(), None);
+        assert!(serde_json::from_value::<FallbackView>(json!({ "kind": "image", "src": "x.png" })).is_err());
+        assert!(serde_json::from_value::<FallbackView>(json!({ "kind": "component" })).is_err());
+    }
+
+    #[test]
+    fn test_default_fallback_is_an_alert() {
+        let FallbackView::Html { html } = FallbackView::default() else {
+            panic!("the default fallback is HTML");
+        };
+        assert!(html.contains(r#"role="alert""#));
+        assert_eq!(FallbackView::default().fallback_component(), None);
+    }
+}
@@ -0,0 +1,152 @@
+//! User feedback on component versions.
+//!
+//! Generated components can embed a feedback widget that reports a star
+//! rating and a comment through the [`FEEDBACK_IMPORT`] host import. The
+//! host stores each [`Feedback`] against the version that was live, and
+//! [`prompt_section`] folds it into the AI context for the next
+//! modification of that component, so users steer changes directly.
+//!
+//! Components declare the import with wasm-bindgen:
+//!
+//! ```rust,ignore
+//! #[wasm_bindgen]
+//! extern "C" {
+//!     #[wasm_bindgen(js_namespace = morpheus, js_name = feedback)]
+//!     fn morpheus_feedback(rating: u8, text: &str);
+//! }
+//! ```
+
+use crate::errors::{MorpheusError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Name of the feedback import, called as `morpheus.feedback(rating, text)`
+/// (in the [`EVENT_IMPORT_NAMESPACE`](crate::events::EVENT_IMPORT_NAMESPACE)).
+pub const FEEDBACK_IMPORT: &str = "feedback";
+
+/// Highest rating; ratings run from 1 to this.
+pub const MAX_RATING: u8 = 5;
+
+/// Longest comment kept, in characters.
+pub const MAX_TEXT_CHARS: usize = 2000;
+
+/// Comments included by [`prompt_section`], most recent first.
+pub const PROMPT_COMMENTS: usize = 10;
+
+/// A user's rating of a component version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Feedback {
+    /// Rating from 1 to [`MAX_RATING`].
+    pub rating: u8,
+
+    /// Free-form comment (may be empty).
+    #[serde(default)]
+    pub text: String,
+
+    /// Version the feedback is about.
+    #[serde(default)]
+    pub version: Option<u32>,
+
+    /// When the feedback was given (ISO 8601).
+    #[serde(default)]
+    pub timestamp: String,
+}
+
+impl Feedback {
+    /// Create feedback, rejecting ratings outside 1..=[`MAX_RATING`].
+    ///
+    /// Comments longer than [`MAX_TEXT_CHARS`] are truncated.
+    pub fn new(rating: u8, text: impl Into<String>) -> Result<Self> {
+        if !(1..=MAX_RATING).contains(&rating) {
+            return Err(MorpheusError::InvalidState(format!(
+                "Rating {} is outside 1-{}",
+                rating, MAX_RATING
+            )));
+        }
+        Ok(Self {
+            rating,
+            text: text.into().trim().chars().take(MAX_TEXT_CHARS).collect(),
+            version: None,
+            timestamp: String::new(),
+        })
+    }
+
+    /// Record which version the feedback is about.
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Record when the feedback was given.
+    pub fn with_timestamp(mut self, timestamp: impl Into<String>) -> Self {
+        self.timestamp = timestamp.into();
+        self
+    }
+}
+
+/// Mean rating, or `None` without feedback.
+pub fn average_rating(feedback: &[Feedback]) -> Option<f64> {
+    if feedback.is_empty() {
+        return None;
+    }
+    let total: u32 = feedback.iter().map(|f| u32::from(f.rating)).sum();
+    Some(f64::from(total) / feedback.len() as f64)
+}
+
+/// Render feedback on the live version as a prompt section.
+///
+/// Includes the average rating and the [`PROMPT_COMMENTS`] most recent
+/// comments; empty when there is no feedback.
+pub fn prompt_section(feedback: &[Feedback]) -> String {
+    let Some(average) = average_rating(feedback) else {
+        return String::new();
+    };
+
+    let mut section = format!(
+        "USER FEEDBACK ON THE CURRENT VERSION ({} ratings, average {:.1}/{}):\n",
+        feedback.len(),
+        average,
+        MAX_RATING
+    );
+    for entry in feedback.iter().rev().filter(|f| !f.text.is_empty()).take(PROMPT_COMMENTS) {
+        section.push_str(&format!("- {}/{}: {}\n", entry.rating, MAX_RATING, entry.text));
+    }
+    section.push_str("Address this feedback where it fits the request.\n");
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rating_out_of_range_is_rejected() {
+        assert!(Feedback::new(0, "").is_err());
+        assert!(Feedback::new(MAX_RATING + 1, "").is_err());
+        assert_eq!(Feedback::new(MAX_RATING, " great ").unwrap().text, "great");
+    }
+
+    #[test]
+    fn test_long_comments_are_truncated() {
+        let feedback = Feedback::new(3, "x".repeat(MAX_TEXT_CHARS + 10)).unwrap();
+        assert_eq!(feedback.text.chars().count(), MAX_TEXT_CHARS);
+    }
+
+    #[test]
+    fn test_prompt_section() {
+        assert!(prompt_section(&[]).is_empty());
+
+        let feedback = vec![
+            Feedback::new(2, "Buttons are too small").unwrap(),
+            Feedback::new(4, "").unwrap(),
+            Feedback::new(3, "Needs a dark mode").unwrap().with_version(1),
+        ];
+        assert_eq!(average_rating(&feedback), Some(3.0));
+
+        let section = prompt_section(&feedback);
+        assert!(section.contains("3 ratings, average 3.0/5"));
+        assert!(section.contains("- 3/5: Needs a dark mode"));
+        assert!(section.contains("- 2/5: Buttons are too small"));
+        assert!(section.find("dark mode") < section.find("too small"));
+    }
+}
@@ -0,0 +1,176 @@
+//! Per-component feature flags.
+//!
+//! Operators can switch a misbehaving component off, or pin it to a known
+//! good version, without redeploying. When a component is disabled the host
+//! renders its configured [`Fallback`] instead: the previous version or a
+//! static placeholder.
+
+use serde::{Deserialize, Serialize};
+
+/// Placeholder shown when no other fallback is configured or available.
+pub const DEFAULT_PLACEHOLDER: &str =
+    "<div class=\"text-gray-500\">This component is temporarily unavailable.</div>";
+
+/// Whether and which version of a component should run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum FlagState {
+    /// Run the current version.
+    #[default]
+    Enabled,
+
+    /// Don't run the component; render the fallback.
+    Disabled,
+
+    /// Run a specific version regardless of what is current.
+    Pinned {
+        /// Version number as tracked by the host.
+        version: u32,
+    },
+}
+
+/// What to render in place of a disabled component.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Fallback {
+    /// The version before the current one.
+    PreviousVersion,
+
+    /// Static HTML.
+    Placeholder { html: String },
+}
+
+impl Default for Fallback {
+    fn default() -> Self {
+        Fallback::Placeholder {
+            html: DEFAULT_PLACEHOLDER.to_string(),
+        }
+    }
+}
+
+/// Feature flag for one component.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentFlag {
+    /// Whether the component runs, and which version.
+    #[serde(flatten)]
+    pub state: FlagState,
+
+    /// What to render while disabled.
+    #[serde(default)]
+    pub fallback: Fallback,
+
+    /// Why the flag was set (e.g. an incident reference).
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// What the host should render for a component.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum RenderDecision {
+    /// Render the current version.
+    Current,
+
+    /// Render a specific version.
+    Version { version: u32 },
+
+    /// Render the fallback.
+    Fallback { fallback: Fallback },
+}
+
+impl ComponentFlag {
+    /// A flag that disables the component, rendering `fallback` instead.
+    pub fn disabled(fallback: Fallback) -> Self {
+        Self {
+            state: FlagState::Disabled,
+            fallback,
+            reason: None,
+        }
+    }
+
+    /// A flag that pins the component to `version`.
+    pub fn pinned(version: u32) -> Self {
+        Self {
+            state: FlagState::Pinned { version },
+            ..Default::default()
+        }
+    }
+
+    /// Record why the flag was set.
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// Whether the component runs at all.
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self.state, FlagState::Disabled)
+    }
+
+    /// Decide what to render.
+    pub fn decide(&self) -> RenderDecision {
+        match &self.state {
+            FlagState::Enabled => RenderDecision::Current,
+            FlagState::Pinned { version } => RenderDecision::Version { version: *version },
+            FlagState::Disabled => RenderDecision::Fallback {
+                fallback: self.fallback.clone(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_flag_renders_current() {
+        let flag = ComponentFlag::default();
+
+        assert!(flag.is_enabled());
+        assert_eq!(flag.decide(), RenderDecision::Current);
+    }
+
+    #[test]
+    fn test_disabled_renders_fallback() {
+        let flag = ComponentFlag::disabled(Fallback::PreviousVersion).with_reason("INC-42");
+
+        assert!(!flag.is_enabled());
+        assert_eq!(
+            flag.decide(),
+            RenderDecision::Fallback {
+                fallback: Fallback::PreviousVersion
+            }
+        );
+        assert_eq!(flag.reason.as_deref(), Some("INC-42"));
+    }
+
+    #[test]
+    fn test_pinned_renders_version() {
+        let flag = ComponentFlag::pinned(3);
+
+        assert!(flag.is_enabled());
+        assert_eq!(flag.decide(), RenderDecision::Version { version: 3 });
+    }
+
+    #[test]
+    fn test_flag_wire_format() {
+        let flag: ComponentFlag = serde_json::from_str(
+            r#"{"state": "disabled", "fallback": {"kind": "placeholder", "html": "<p>Back soon</p>"}}"#,
+        )
+        .expect("Failed to deserialize");
+
+        assert_eq!(flag.state, FlagState::Disabled);
+        assert_eq!(
+            flag.fallback,
+            Fallback::Placeholder {
+                html: "<p>Back soon</p>".to_string()
+            }
+        );
+
+        let pinned: ComponentFlag =
+            serde_json::from_str(r#"{"state": "pinned", "version": 2}"#).expect("Failed to deserialize");
+        assert_eq!(pinned.state, FlagState::Pinned { version: 2 });
+        assert_eq!(pinned.fallback, Fallback::default());
+    }
+}
@@ -0,0 +1,300 @@
+//! Translatable text in components.
+//!
+//! A component that hard-codes English has to be regenerated for every
+//! language. Instead, each user-visible string goes through the
+//! [`TRANSLATE_IMPORT`] host import with a key and its default text, and the
+//! host answers from the [`MessageCatalog`] of the user's locale. Switching
+//! locales is then a re-render.
+//!
+//! Messages name their placeholders in braces (`{count} items`), filled from
+//! a JSON object of arguments. A locale with no message for a key falls back
+//! to its language (`fr-CA` to `fr`), then to the default text in the
+//! source, which [`extract`] collects into the catalog translators start
+//! from.
+//!
+//! ```rust
+//! use morpheus_core::i18n::{self, Catalogs, MessageCatalog};
+//! use serde_json::json;
+//!
+//! let source = r#"t!("cart.items", "{count} items in your cart", count = items.len())"#;
+//! let defaults = i18n::extract(source);
+//!
+//! let mut catalogs = Catalogs::new();
+//! catalogs.insert(MessageCatalog::new("fr").with("cart.items", "{count} articles dans votre panier"));
+//!
+//! let french = catalogs.resolve("fr-CA", &defaults);
+//! assert_eq!(french.format("cart.items", &json!({ "count": 3 })), "3 articles dans votre panier");
+//! assert_eq!(catalogs.missing("de", &defaults), vec!["cart.items"]);
+//! ```
+//!
+//! Components that depend on this crate use the [`t!`](crate::t) macro;
+//! generated components declare the import themselves:
+//!
+//! ```rust,ignore
+//! #[wasm_bindgen]
+//! extern "C" {
+//!     #[wasm_bindgen(js_namespace = morpheus, js_name = t)]
+//!     fn t(key: &str, fallback: &str, args_json: &str) -> String;
+//! }
+//! ```
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// Name of the translation import, called as
+/// `morpheus.t(key, fallback, argsJson)`.
+pub const TRANSLATE_IMPORT: &str = "t";
+
+/// Locale of the default texts written in component source.
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Messages of one locale, by key.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MessageCatalog {
+    /// BCP 47 language tag, e.g. `fr` or `fr-CA`.
+    pub locale: String,
+
+    /// Message templates by key, e.g. `"cart.items": "{count} items"`.
+    #[serde(default)]
+    pub messages: BTreeMap<String, String>,
+}
+
+impl MessageCatalog {
+    /// Create an empty catalog.
+    pub fn new(locale: impl Into<String>) -> Self {
+        Self {
+            locale: locale.into(),
+            messages: BTreeMap::new(),
+        }
+    }
+
+    /// Add a message.
+    pub fn with(mut self, key: impl Into<String>, message: impl Into<String>) -> Self {
+        self.messages.insert(key.into(), message.into());
+        self
+    }
+
+    /// The message for `key`, if the catalog has one.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.messages.get(key).map(String::as_str)
+    }
+
+    /// The message for `key` with `args` filled in; the key itself if the
+    /// catalog has no message for it.
+    pub fn format(&self, key: &str, args: &Value) -> String {
+        format(self.get(key).unwrap_or(key), args)
+    }
+}
+
+/// Fill `{name}` placeholders in `message` from the `args` object. Strings
+/// are inserted as they are, other values as JSON; placeholders without an
+/// argument are left in place.
+pub fn format(message: &str, args: &Value) -> String {
+    let Value::Object(args) = args else {
+        return message.to_string();
+    };
+    let mut formatted = message.to_string();
+    for (name, value) in args {
+        let value = match value {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        formatted = formatted.replace(&format!("{{{}}}", name), &value);
+    }
+    formatted
+}
+
+/// The locales to look a message up in, most specific first: `fr-CA`,
+/// then `fr`. Underscores are read as hyphens.
+pub fn fallbacks(locale: &str) -> Vec<String> {
+    let locale = locale.replace('_', "-");
+    let mut chain = Vec::new();
+    let mut tag = locale.as_str();
+    while !tag.is_empty() {
+        chain.push(tag.to_string());
+        tag = tag.rsplit_once('-').map_or("", |(parent, _)| parent);
+    }
+    chain
+}
+
+/// Translations into each locale.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Catalogs {
+    catalogs: BTreeMap<String, MessageCatalog>,
+}
+
+impl Catalogs {
+    /// No translations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `catalog`'s messages to its locale's, replacing messages with
+    /// the same keys.
+    pub fn insert(&mut self, catalog: MessageCatalog) {
+        let existing = self
+            .catalogs
+            .entry(catalog.locale.clone())
+            .or_insert_with(|| MessageCatalog::new(catalog.locale.clone()));
+        existing.messages.extend(catalog.messages);
+    }
+
+    /// The translations into exactly `locale`.
+    pub fn get(&self, locale: &str) -> Option<&MessageCatalog> {
+        self.catalogs.get(locale)
+    }
+
+    /// Locales with translations.
+    pub fn locales(&self) -> Vec<&str> {
+        self.catalogs.keys().map(String::as_str).collect()
+    }
+
+    /// Every message in `locale`: each of `defaults`' keys, translated by the
+    /// most specific catalog in `locale`'s [`fallbacks`] that has it, else
+    /// left as the default text. Translations of keys `defaults` lacks are
+    /// kept too.
+    pub fn resolve(&self, locale: &str, defaults: &MessageCatalog) -> MessageCatalog {
+        let mut resolved = MessageCatalog {
+            locale: locale.to_string(),
+            messages: defaults.messages.clone(),
+        };
+        for tag in fallbacks(locale).iter().rev() {
+            if let Some(catalog) = self.catalogs.get(tag) {
+                resolved.messages.extend(catalog.messages.clone());
+            }
+        }
+        resolved
+    }
+
+    /// Keys of `defaults` that no catalog in `locale`'s [`fallbacks`]
+    /// translates.
+    pub fn missing(&self, locale: &str, defaults: &MessageCatalog) -> Vec<String> {
+        let chain = fallbacks(locale);
+        defaults
+            .messages
+            .keys()
+            .filter(|key| !chain.iter().any(|tag| self.catalogs.get(tag).is_some_and(|c| c.get(key).is_some())))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The keys and default texts `source` translates, as a [`DEFAULT_LOCALE`]
+/// catalog: every `t!("key", "text", ...)` and `t("key", "text", ...)` call
+/// with string literals. Where a key appears twice, the first text wins.
+pub fn extract(source: &str) -> MessageCatalog {
+    static CALL: OnceLock<Regex> = OnceLock::new();
+    let call = CALL.get_or_init(|| {
+        Regex::new(r#"\bt!?\(\s*"((?:[^"\\]|\\.)*)"\s*,\s*"((?:[^"\\]|\\.)*)""#).expect("valid regex")
+    });
+    let mut catalog = MessageCatalog::new(DEFAULT_LOCALE);
+    for captures in call.captures_iter(source) {
+        catalog.messages.entry(unescape(&captures[1])).or_insert_with(|| unescape(&captures[2]));
+    }
+    catalog
+}
+
+/// Undo the escapes of a Rust string literal that matter in messages.
+fn unescape(literal: &str) -> String {
+    let mut text = String::with_capacity(literal.len());
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some(escaped) => text.push(escaped),
+            None => {}
+        }
+    }
+    text
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen::prelude::wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = morpheus, js_name = t)]
+    fn morpheus_t(key: &str, fallback: &str, args_json: &str) -> String;
+}
+
+/// The host's message for `key` in the user's locale, or `fallback`, with
+/// `args` filled in. Outside the browser there is no host, so it is always
+/// `fallback`.
+pub fn t(key: &str, fallback: &str, args: &Value) -> String {
+    #[cfg(target_arch = "wasm32")]
+    {
+        morpheus_t(key, fallback, &args.to_string())
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let _ = key;
+        format(fallback, args)
+    }
+}
+
+/// Translate a message through the host: `t!("key")`, `t!("key", "Default
+/// text")` or `t!("key", "{count} items", count = n)`, each argument filling
+/// the placeholder of the same name. See [`i18n`](crate::i18n).
+#[macro_export]
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::t($key, $key, &$crate::__serde_json::Value::Null)
+    };
+    ($key:expr, $fallback:expr $(, $name:ident = $value:expr)* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut args = $crate::__serde_json::Map::new();
+        $(args.insert(stringify!($name).to_string(), $crate::__serde_json::json!($value));)*
+        $crate::i18n::t($key, $fallback, &$crate::__serde_json::Value::Object(args))
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_locales_fall_back_to_their_language_then_the_default_text() {
+        let defaults = MessageCatalog::new(DEFAULT_LOCALE).with("greeting", "Hello, {name}!").with("bye", "Bye");
+        let mut catalogs = Catalogs::new();
+        catalogs.insert(MessageCatalog::new("pt").with("greeting", "Olá, {name}!").with("bye", "Tchau"));
+        catalogs.insert(MessageCatalog::new("pt-PT").with("bye", "Adeus"));
+
+        assert_eq!(fallbacks("pt_PT"), vec!["pt-PT", "pt"]);
+        let resolved = catalogs.resolve("pt-PT", &defaults);
+        assert_eq!(resolved.format("greeting", &json!({ "name": "Ana" })), "Olá, Ana!");
+        assert_eq!(resolved.get("bye"), Some("Adeus"));
+        assert_eq!(catalogs.resolve("ja", &defaults).get("bye"), Some("Bye"));
+        assert!(catalogs.missing("pt-BR", &defaults).is_empty());
+        assert_eq!(catalogs.locales(), vec!["pt", "pt-PT"]);
+    }
+
+    #[test]
+    fn test_extract_finds_keys_and_default_texts() {
+        let source = r#"
+            let title = t("todo.title", "Things to do", "{}");
+            let empty = t!("todo.empty", "Nothing \"left\"");
+            let again = t!("todo.title", "Ignored");
+            let count = format!("{}", items.len());
+        "#;
+        let catalog = extract(source);
+        assert_eq!(catalog.locale, DEFAULT_LOCALE);
+        assert_eq!(catalog.get("todo.title"), Some("Things to do"));
+        assert_eq!(catalog.get("todo.empty"), Some("Nothing \"left\""));
+        assert_eq!(catalog.messages.len(), 2);
+    }
+
+    #[test]
+    fn test_macro_formats_the_fallback_without_a_host() {
+        assert_eq!(crate::t!("plain"), "plain");
+        assert_eq!(crate::t!("cart.items", "{count} items, {count} in stock", count = 2), "2 items, 2 in stock");
+        assert_eq!(format("{missing} and {name}", &json!({ "name": "Bo" })), "{missing} and Bo");
+    }
+}
@@ -0,0 +1,84 @@
+//! Stable element identity across versions.
+//!
+//! A hot reload replaces a component's markup with the new version's. The
+//! host carries over what the user was doing (form values, scroll offsets,
+//! focus) by finding each element's counterpart in the new markup, which
+//! works best when the element says who it is: an element marked with
+//! [`KEY_ATTR`] is matched by that key before anything else, however the
+//! markup around it changed. Keys only need to be unique within the
+//! component, and should stay the same from one version to the next.
+//!
+//! ```rust
+//! use morpheus_core::component::View;
+//! use morpheus_core::identity::{morpheus_key, MorpheusKey};
+//!
+//! // Components that render strings
+//! let input = format!(r#"<input name="title"{}>"#, morpheus_key("todo-input"));
+//! assert_eq!(input, r#"<input name="title" data-morpheus-key="todo-input">"#);
+//!
+//! // Views
+//! let list = View::Element { tag: "ul".into(), attrs: vec![], children: vec![] }.morpheus_key("todo-list");
+//! assert_eq!(list.morpheus_key_of(), Some("todo-list"));
+//! ```
+
+use crate::component::View;
+
+/// Attribute naming an element, stable across versions of a component.
+pub const KEY_ATTR: &str = "data-morpheus-key";
+
+/// The key attribute as HTML, with a leading space, for components that
+/// render strings.
+pub fn morpheus_key(key: &str) -> String {
+    format!(" {}=\"{}\"", KEY_ATTR, key.replace('&', "&amp;").replace('"', "&quot;"))
+}
+
+/// Builder support for marking elements with a stable key.
+pub trait MorpheusKey: Sized {
+    /// Mark the element with `key`, replacing any key it had.
+    fn morpheus_key(self, key: impl Into<String>) -> Self;
+
+    /// The element's key, if it has one.
+    fn morpheus_key_of(&self) -> Option<&str>;
+}
+
+impl MorpheusKey for View {
+    /// Text has no attributes, and is returned unchanged.
+    fn morpheus_key(self, key: impl Into<String>) -> Self {
+        match self {
+            View::Element { tag, mut attrs, children } => {
+                attrs.retain(|(name, _)| name != KEY_ATTR);
+                attrs.push((KEY_ATTR.to_string(), key.into()));
+                View::Element { tag, attrs, children }
+            }
+            text => text,
+        }
+    }
+
+    fn morpheus_key_of(&self) -> Option<&str> {
+        match self {
+            View::Element { attrs, .. } => attrs.iter().find(|(name, _)| name == KEY_ATTR).map(|(_, v)| v.as_str()),
+            View::Text(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_replace_earlier_keys_and_are_escaped() {
+        let input = View::Element {
+            tag: "input".to_string(),
+            attrs: vec![("name".to_string(), "title".to_string())],
+            children: Vec::new(),
+        };
+
+        let keyed = input.morpheus_key("draft").morpheus_key("title-input");
+        let View::Element { attrs, .. } = &keyed else { unreachable!() };
+        assert_eq!(attrs.len(), 2);
+        assert_eq!(keyed.morpheus_key_of(), Some("title-input"));
+        assert!(matches!(View::Text("hi".to_string()).morpheus_key("x"), View::Text(text) if text == "hi"));
+        assert_eq!(morpheus_key(r#"a"b"#), r#" data-morpheus-key="a&quot;b""#);
+    }
+}
@@ -0,0 +1,337 @@
+//! Component interfaces and compatibility between reloads.
+//!
+//! Components depend on each other: a component consuming an event depends
+//! on the components emitting it, and a parent depends on the exports and
+//! state of the children mounted into its slots. Reloading a dependency
+//! with a changed interface breaks its dependents without any error at load
+//! time.
+//!
+//! A [`ComponentInterface`] is the part of a component's
+//! [`ComponentDescription`] other components can rely on, and
+//! [`ComponentInterface::hash`] identifies it, so dependents can pin the
+//! interfaces they were loaded against. Comparing the interface a reload
+//! reports with the pinned one lists the [`InterfaceChange`]s; a removal is
+//! an [`Incompatibility`] for every dependent that used the removed item,
+//! unless that dependent is reloaded in the same transaction.
+//!
+//! ```rust
+//! use morpheus_core::catalog::ComponentDescription;
+//! use morpheus_core::interface::{ComponentInterface, InterfaceChange, InterfaceItem};
+//!
+//! let filters = ComponentInterface::from_description(&ComponentDescription {
+//!     emits: vec!["filter-changed".to_string()],
+//!     ..Default::default()
+//! });
+//! let reloaded = ComponentInterface::from_description(&ComponentDescription {
+//!     emits: vec!["filters-changed".to_string()],
+//!     ..Default::default()
+//! });
+//!
+//! let changes = reloaded.changes_from(&filters);
+//! assert!(changes.contains(&InterfaceChange::Removed(InterfaceItem::Event, "filter-changed".to_string())));
+//! assert_ne!(filters.hash(), reloaded.hash());
+//! ```
+
+use crate::catalog::ComponentDescription;
+use crate::semver::SemVer;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// What other components can rely on: exports, messages, events and the
+/// top-level fields of the state.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ComponentInterface {
+    /// Functions the module exports.
+    #[serde(default)]
+    pub exports: Vec<String>,
+
+    /// Messages the component handles.
+    #[serde(default)]
+    pub messages: Vec<String>,
+
+    /// Events the component emits.
+    #[serde(default)]
+    pub emits: Vec<String>,
+
+    /// Events the component consumes.
+    #[serde(default)]
+    pub consumes: Vec<String>,
+
+    /// Top-level fields of the component's state.
+    #[serde(default)]
+    pub state_fields: Vec<String>,
+}
+
+impl ComponentInterface {
+    /// The interface of a component, from its `__morpheus_describe()` output.
+    ///
+    /// The state shape may be an example value or a JSON schema; either way
+    /// its top-level fields are kept. Lists are sorted, so the same interface
+    /// always has the same [hash](Self::hash).
+    pub fn from_description(description: &ComponentDescription) -> Self {
+        let state = &description.state;
+        let state = match state.get("properties") {
+            Some(properties) if state.get("type").is_some_and(|t| t == "object") => properties,
+            _ => state,
+        };
+        let state_fields: Vec<String> = state
+            .as_object()
+            .map(|fields| fields.keys().cloned().collect())
+            .unwrap_or_default();
+        Self {
+            exports: sorted(&description.exports),
+            messages: sorted(&description.messages),
+            emits: sorted(&description.emits),
+            consumes: sorted(&description.consumes),
+            state_fields: sorted(&state_fields),
+        }
+    }
+
+    /// Stable hash of the interface, e.g. `"3f2a9c0d41b7e865"`.
+    pub fn hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        for (label, items) in self.lists() {
+            hasher.update(label.as_bytes());
+            for item in items {
+                hasher.update([0]);
+                hasher.update(item.as_bytes());
+            }
+            hasher.update([0xff]);
+        }
+        hasher.finalize()[..8].iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// What changed since `previous`, removals first.
+    ///
+    /// Consumed events aren't part of what others rely on, so changes to
+    /// them aren't listed.
+    pub fn changes_from(&self, previous: &ComponentInterface) -> Vec<InterfaceChange> {
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        for ((item, old), (_, new)) in previous.items().into_iter().zip(self.items()) {
+            removed.extend(
+                old.iter()
+                    .filter(|name| !new.contains(name))
+                    .map(|name| InterfaceChange::Removed(item, name.clone())),
+            );
+            added.extend(
+                new.iter()
+                    .filter(|name| !old.contains(name))
+                    .map(|name| InterfaceChange::Added(item, name.clone())),
+            );
+        }
+        removed.extend(added);
+        removed
+    }
+
+    /// Whether `self` relies on `item` of another component: an event it
+    /// consumes, or anything of a child it `embeds`.
+    pub fn relies_on(&self, item: InterfaceItem, name: &str, embeds: bool) -> bool {
+        match item {
+            InterfaceItem::Event => self.consumes.iter().any(|event| event == name),
+            InterfaceItem::Export | InterfaceItem::Message | InterfaceItem::StateField => embeds,
+        }
+    }
+
+    fn items(&self) -> [(InterfaceItem, &Vec<String>); 4] {
+        [
+            (InterfaceItem::Export, &self.exports),
+            (InterfaceItem::Message, &self.messages),
+            (InterfaceItem::Event, &self.emits),
+            (InterfaceItem::StateField, &self.state_fields),
+        ]
+    }
+
+    fn lists(&self) -> [(&'static str, &Vec<String>); 5] {
+        [
+            ("exports", &self.exports),
+            ("messages", &self.messages),
+            ("emits", &self.emits),
+            ("consumes", &self.consumes),
+            ("state", &self.state_fields),
+        ]
+    }
+}
+
+fn sorted(items: &[String]) -> Vec<String> {
+    let mut items = items.to_vec();
+    items.sort();
+    items.dedup();
+    items
+}
+
+/// Kind of item in an interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum InterfaceItem {
+    /// An exported function.
+    Export,
+    /// A handled message.
+    Message,
+    /// An emitted event.
+    Event,
+    /// A top-level state field.
+    StateField,
+}
+
+impl fmt::Display for InterfaceItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InterfaceItem::Export => "export",
+            InterfaceItem::Message => "message",
+            InterfaceItem::Event => "event",
+            InterfaceItem::StateField => "state field",
+        })
+    }
+}
+
+/// One difference between two versions of an interface.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum InterfaceChange {
+    /// Something new; dependents are unaffected.
+    Added(InterfaceItem, String),
+    /// Something dependents may rely on is gone.
+    Removed(InterfaceItem, String),
+}
+
+impl InterfaceChange {
+    /// Whether the change can break dependents.
+    pub fn is_breaking(&self) -> bool {
+        matches!(self, InterfaceChange::Removed(..))
+    }
+}
+
+impl fmt::Display for InterfaceChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterfaceChange::Added(item, name) => write!(f, "added {} `{}`", item, name),
+            InterfaceChange::Removed(item, name) => write!(f, "removed {} `{}`", item, name),
+        }
+    }
+}
+
+/// What to do with a reload that breaks dependents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Compatibility {
+    /// Refuse the reload.
+    #[default]
+    Reject,
+    /// Accept it and report what it breaks.
+    Warn,
+}
+
+/// The interface of a dependency a component was loaded against.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InterfacePin {
+    /// Name of the component depended on.
+    pub component: String,
+
+    /// Its [interface hash](ComponentInterface::hash) at the time.
+    pub interface_hash: String,
+
+    /// Its version at the time; later versions of the same major are
+    /// compatible.
+    #[serde(default)]
+    pub version: SemVer,
+}
+
+/// A reload that breaks a component depending on the reloaded one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Incompatibility {
+    /// Component that is reloaded.
+    pub component: String,
+
+    /// Version the reload makes it: always a new major version.
+    pub version: SemVer,
+
+    /// Component that relied on what changed.
+    pub dependent: String,
+
+    /// The breaking change.
+    pub change: InterfaceChange,
+
+    /// Interface hash the dependent pinned, if it recorded one.
+    #[serde(default)]
+    pub pinned_hash: Option<String>,
+
+    /// Version of the component the dependent was loaded against.
+    #[serde(default)]
+    pub pinned_version: Option<SemVer>,
+}
+
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}, which {} relies on; reload {} in the same transaction",
+            self.component, self.version, self.change, self.dependent, self.dependent
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn describe(exports: &[&str], emits: &[&str], state: serde_json::Value) -> ComponentInterface {
+        ComponentInterface::from_description(&ComponentDescription {
+            exports: exports.iter().map(|s| s.to_string()).collect(),
+            emits: emits.iter().map(|s| s.to_string()).collect(),
+            state,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_hash_ignores_order_and_state_values() {
+        let a = describe(&["render", "reset"], &["changed"], json!({ "count": 0, "step": 1 }));
+        let b = describe(&["reset", "render", "render"], &["changed"], json!({ "step": 5, "count": 3 }));
+        let schema = describe(
+            &["render", "reset"],
+            &["changed"],
+            json!({ "type": "object", "properties": { "count": {}, "step": {} } }),
+        );
+        assert_eq!(a, b);
+        assert_eq!(a.hash(), b.hash());
+        assert_eq!(a.hash(), schema.hash());
+        assert_eq!(a.hash().len(), 16);
+        assert_ne!(a.hash(), describe(&["render"], &["changed"], json!({ "count": 0, "step": 1 })).hash());
+    }
+
+    #[test]
+    fn test_changes_and_who_they_break() {
+        let old = describe(&["render", "reset"], &["changed"], json!({ "count": 0 }));
+        let new = describe(&["render", "undo"], &[], json!({ "count": 0, "history": [] }));
+
+        let changes = new.changes_from(&old);
+        assert_eq!(
+            changes,
+            vec![
+                InterfaceChange::Removed(InterfaceItem::Export, "reset".to_string()),
+                InterfaceChange::Removed(InterfaceItem::Event, "changed".to_string()),
+                InterfaceChange::Added(InterfaceItem::Export, "undo".to_string()),
+                InterfaceChange::Added(InterfaceItem::StateField, "history".to_string()),
+            ]
+        );
+        assert_eq!(changes.iter().filter(|c| c.is_breaking()).count(), 2);
+        assert_eq!(changes[1].to_string(), "removed event `changed`");
+
+        let listener = ComponentInterface {
+            consumes: vec!["changed".to_string()],
+            ..Default::default()
+        };
+        assert!(listener.relies_on(InterfaceItem::Event, "changed", false));
+        assert!(!listener.relies_on(InterfaceItem::Export, "reset", false));
+        assert!(listener.relies_on(InterfaceItem::Export, "reset", true));
+    }
+}
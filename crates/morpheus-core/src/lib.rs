@@ -38,15 +38,93 @@
 //! }
 //! ```
 
+// Lets derived code name this crate from inside it, e.g. in tests
+extern crate self as morpheus_core;
+
+pub mod accessibility;
+pub mod animation;
+pub mod artifact;
+pub mod broker;
+pub mod bundle;
+pub mod canvas;
+pub mod capability;
+pub mod catalog;
+pub mod cmd;
+pub mod codec;
 pub mod component;
+pub mod crash;
+pub mod delta;
+pub mod drag;
+pub mod embedding;
+pub mod events;
+pub mod experiment;
+pub mod fallback;
+pub mod feedback;
+pub mod flags;
+pub mod i18n;
+pub mod identity;
+pub mod interface;
+pub mod lifecycle;
+pub mod manifest;
+pub mod media;
+pub mod performance;
 pub mod permissions;
+pub mod privacy;
+pub mod profiler;
+pub mod review;
+pub mod screening;
+pub mod semver;
+pub mod shared;
 pub mod state;
+pub mod stats;
+pub mod store;
+pub mod theme;
+pub mod thumbnail;
+pub mod virtual_list;
 pub mod errors;
 
+#[doc(hidden)]
+pub use serde_json as __serde_json;
+
 pub mod prelude {
     //! Commonly used types and traits.
+    pub use crate::accessibility::{AccessibilityViolation, Impact};
+    pub use crate::animation::{transition, Animator, Easing, Motion, Spring, Transition, Tween};
+    pub use crate::artifact::{Artifact, ArtifactTarget, Environment};
+    pub use crate::broker::{Decision, FileAssembler, HostApi, LocationGate, NotificationLimiter, PermissionBroker};
+    pub use crate::bundle::{BundleAsset, BundleManifest};
+    pub use crate::canvas::{canvas, Canvas, CanvasHandle, ContextKind};
+    pub use crate::capability::Capability;
+    pub use crate::catalog::*;
+    pub use crate::cmd::{Cmd, Program};
+    pub use crate::codec::*;
+    pub use crate::drag::{sortable_list, DragData, DragEvent, Reorder, SortableList};
     pub use crate::component::*;
+    pub use crate::crash::{CrashReport, CRASH_IMPORT};
+    pub use crate::events::*;
+    pub use crate::experiment::*;
+    pub use crate::fallback::{FallbackView, DEFAULT_ERROR_HTML};
+    pub use crate::feedback::{average_rating, Feedback, FEEDBACK_IMPORT, MAX_RATING};
+    pub use crate::flags::*;
+    pub use crate::i18n::{Catalogs, MessageCatalog, DEFAULT_LOCALE, TRANSLATE_IMPORT};
+    pub use crate::identity::{morpheus_key, MorpheusKey};
+    pub use crate::interface::{Compatibility, ComponentInterface, Incompatibility, InterfaceChange, InterfaceItem, InterfacePin};
+    pub use crate::lifecycle::Lifecycle;
+    pub use crate::manifest::*;
+    pub use crate::media::{ColorScheme, MediaWatch, COLOR_SCHEME_IMPORT};
+    pub use crate::performance::{PerformanceBudget, PerformanceMeasurements};
     pub use crate::permissions::*;
+    pub use crate::privacy::*;
+    pub use crate::profiler::{Profiler, RenderProfile, RenderSample};
+    pub use crate::review::*;
+    pub use crate::screening::{Screener, Screening};
+    pub use crate::semver::{Bump, SemVer};
+    pub use crate::shared::{SharedType, SharedTypes};
     pub use crate::state::*;
+    pub use crate::stats::{CallSample, ExecutionStats, ExportStats};
+    pub use crate::store::*;
+    pub use crate::theme::{Theme, Themes, THEME_TOKEN_IMPORT};
+    pub use crate::thumbnail::{Thumbnail, MAX_THUMBNAIL_BYTES};
+    pub use crate::virtual_list::{virtual_list, VirtualList};
     pub use crate::errors::*;
 }
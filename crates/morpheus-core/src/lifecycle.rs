@@ -0,0 +1,109 @@
+//! Deprecation and retirement of components.
+//!
+//! Generated apps accumulate components that newer ones replace. Removing
+//! one outright breaks whatever still embeds it or listens to its events, so
+//! components are retired in steps: an operator marks a component
+//! [`Lifecycle::Deprecated`], naming its replacement and when it may go,
+//! mounting it keeps working but warns, and once that time has passed and no
+//! dependent still [pins](crate::interface::InterfacePin) it the host
+//! retires it.
+//!
+//! ```rust
+//! use morpheus_core::lifecycle::Lifecycle;
+//!
+//! let lifecycle = Lifecycle::Deprecated {
+//!     replacement: Some("TodoBoard".to_string()),
+//!     retire_after: Some("2025-03-01T00:00:00Z".to_string()),
+//! };
+//!
+//! assert_eq!(
+//!     lifecycle.warning("TodoList").unwrap(),
+//!     "TodoList is deprecated and retires after 2025-03-01T00:00:00Z; use TodoBoard instead"
+//! );
+//! assert!(!lifecycle.is_due("2025-02-28T12:00:00Z"));
+//! assert!(lifecycle.is_due("2025-03-01T00:00:00Z"));
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// Where a component is in its life.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Lifecycle {
+    /// In use; nothing to warn about.
+    #[default]
+    Active,
+
+    /// Still works, but should no longer be mounted.
+    Deprecated {
+        /// Name of the component to use instead.
+        #[serde(default)]
+        replacement: Option<String>,
+
+        /// UTC timestamp (RFC 3339, e.g. `2025-03-01T00:00:00Z`) from which
+        /// the component is retired once nothing depends on it. Without one
+        /// it is only retired on request.
+        #[serde(default)]
+        retire_after: Option<String>,
+    },
+
+    /// Removed from the registry.
+    Retired,
+}
+
+impl Lifecycle {
+    /// Whether the component is deprecated.
+    pub fn is_deprecated(&self) -> bool {
+        matches!(self, Lifecycle::Deprecated { .. })
+    }
+
+    /// Whether a deprecated component's retirement time has come at `now`,
+    /// a UTC timestamp in the same format as `retire_after`.
+    pub fn is_due(&self, now: &str) -> bool {
+        match self {
+            Lifecycle::Deprecated { retire_after: Some(at), .. } => at.as_str() <= now,
+            _ => false,
+        }
+    }
+
+    /// What to tell whoever mounts the component named `name`, if anything.
+    pub fn warning(&self, name: &str) -> Option<String> {
+        match self {
+            Lifecycle::Active => None,
+            Lifecycle::Deprecated { replacement, retire_after } => {
+                let mut warning = format!("{} is deprecated", name);
+                if let Some(at) = retire_after {
+                    warning.push_str(&format!(" and retires after {}", at));
+                }
+                if let Some(replacement) = replacement {
+                    warning.push_str(&format!("; use {} instead", replacement));
+                }
+                Some(warning)
+            }
+            Lifecycle::Retired => Some(format!("{} is retired", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lifecycle_serializes_with_status_tag() {
+        let deprecated = Lifecycle::Deprecated {
+            replacement: Some("TodoBoard".to_string()),
+            retire_after: None,
+        };
+        let json = serde_json::to_value(&deprecated).unwrap();
+        assert_eq!(json["status"], "deprecated");
+        assert_eq!(json["replacement"], "TodoBoard");
+        assert_eq!(serde_json::from_value::<Lifecycle>(json).unwrap(), deprecated);
+
+        let bare: Lifecycle = serde_json::from_str(r#"{"status": "deprecated"}"#).unwrap();
+        assert_eq!(bare.warning("TodoList").unwrap(), "TodoList is deprecated");
+        assert!(!bare.is_due("9999-12-31T23:59:59Z"));
+        assert_eq!(Lifecycle::default().warning("TodoList"), None);
+    }
+}
@@ -0,0 +1,102 @@
+//! Component manifests.
+//!
+//! A manifest describes a component to the runtime and to the AI: its name,
+//! what it does, and which other components it embeds. Embedding is declared
+//! through named slots so a generated dashboard can host an existing chart
+//! component instead of regenerating it.
+
+use crate::permissions::Permissions;
+use serde::{Deserialize, Serialize};
+
+/// Declarative description of a component.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComponentManifest {
+    /// Component name (unique within a registry).
+    pub name: String,
+
+    /// Short human-readable description of what the component does.
+    #[serde(default)]
+    pub description: String,
+
+    /// Child components embedded by this component.
+    #[serde(default)]
+    pub slots: Vec<SlotDecl>,
+
+    /// Permissions the component requests (none by default).
+    #[serde(default)]
+    pub permissions: Permissions,
+}
+
+/// A named slot that embeds another component.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SlotDecl {
+    /// Slot name, as referenced by the parent's markup.
+    pub name: String,
+
+    /// Name of the component mounted into this slot.
+    pub component: String,
+}
+
+impl ComponentManifest {
+    /// Create a manifest with no slots.
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            slots: Vec::new(),
+            permissions: Permissions::default(),
+        }
+    }
+
+    /// Add a slot embedding `component`.
+    pub fn with_slot(mut self, name: impl Into<String>, component: impl Into<String>) -> Self {
+        self.slots.push(SlotDecl {
+            name: name.into(),
+            component: component.into(),
+        });
+        self
+    }
+}
+
+/// DOM element ID where the child for `slot` of `parent` is mounted.
+///
+/// Parents render an element with this ID; the runtime mounts the child there.
+pub fn slot_mount_point(parent: &str, slot: &str) -> String {
+    format!("morpheus-slot-{}-{}", parent, slot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_builder() {
+        let manifest = ComponentManifest::new("dashboard", "Sales dashboard")
+            .with_slot("main-chart", "chart")
+            .with_slot("sidebar", "filters");
+
+        assert_eq!(manifest.name, "dashboard");
+        assert_eq!(manifest.slots.len(), 2);
+        assert_eq!(manifest.slots[0].name, "main-chart");
+        assert_eq!(manifest.slots[0].component, "chart");
+    }
+
+    #[test]
+    fn test_slot_mount_point() {
+        assert_eq!(
+            slot_mount_point("dashboard", "main-chart"),
+            "morpheus-slot-dashboard-main-chart"
+        );
+    }
+
+    #[test]
+    fn test_manifest_deserialize_defaults() {
+        let manifest: ComponentManifest =
+            serde_json::from_str(r#"{"name": "chart"}"#).expect("Failed to deserialize");
+
+        assert_eq!(manifest.name, "chart");
+        assert!(manifest.description.is_empty());
+        assert!(manifest.slots.is_empty());
+    }
+}
@@ -0,0 +1,207 @@
+//! Media query subscriptions.
+//!
+//! Some of what a component shows depends on the device rather than its
+//! state, most often whether the OS is in dark mode. A [`MediaWatch`] turns
+//! a media query's changes into messages, the way an
+//! [`Animator`](crate::animation::Animator) turns frames into them; in the
+//! browser, [`MediaWatch::subscribe`] runs it on `matchMedia`, and
+//! [`on_color_scheme_change`] watches `prefers-color-scheme`:
+//!
+//! ```rust,ignore
+//! let subscription = on_color_scheme_change(Msg::ColorScheme, move |msg| program.dispatch(msg));
+//! ```
+//!
+//! Styling needn't do this: the host picks the light or dark theme by the
+//! same query (see [`crate::theme::SYSTEM_THEME`]), and components styled
+//! with its CSS variables follow. The subscription is for what CSS can't
+//! reach, like choosing an image or drawing on a canvas.
+//!
+//! ```rust
+//! use morpheus_core::media::{ColorScheme, MediaWatch};
+//!
+//! let mut watch = MediaWatch::color_scheme(|scheme| format!("now {}", scheme.as_str()));
+//! assert_eq!(watch.changed(false), Some("now light".to_string()));
+//! assert_eq!(watch.changed(false), None);
+//! assert_eq!(watch.changed(true), Some("now dark".to_string()));
+//! assert_eq!(ColorScheme::parse("dark"), Some(ColorScheme::Dark));
+//! ```
+//!
+//! Generated components, which don't depend on this crate, ask the host
+//! instead; it re-renders them when the scheme changes:
+//!
+//! ```rust,ignore
+//! #[wasm_bindgen]
+//! extern "C" {
+//!     #[wasm_bindgen(js_namespace = morpheus, js_name = colorScheme)]
+//!     fn color_scheme() -> String;
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// Name of the color scheme import, called as `morpheus.colorScheme()`;
+/// returns `"light"` or `"dark"`.
+pub const COLOR_SCHEME_IMPORT: &str = "colorScheme";
+
+/// The media query that matches while the OS is in dark mode.
+pub const DARK_SCHEME_QUERY: &str = "(prefers-color-scheme: dark)";
+
+/// Whether the user wants light or dark colors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ColorScheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl ColorScheme {
+    /// The scheme [`DARK_SCHEME_QUERY`] matching or not means.
+    pub fn from_dark(dark: bool) -> Self {
+        if dark {
+            ColorScheme::Dark
+        } else {
+            ColorScheme::Light
+        }
+    }
+
+    /// `light` or `dark`, which is also the name of the built-in theme for
+    /// the scheme.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ColorScheme::Light => "light",
+            ColorScheme::Dark => "dark",
+        }
+    }
+
+    /// The scheme named `name`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "light" => Some(ColorScheme::Light),
+            "dark" => Some(ColorScheme::Dark),
+            _ => None,
+        }
+    }
+}
+
+/// Turns a media query's changes into messages.
+pub struct MediaWatch<Msg> {
+    to_msg: Box<dyn FnMut(bool) -> Msg>,
+    matches: Option<bool>,
+}
+
+impl<Msg> MediaWatch<Msg> {
+    /// Map whether the query matches to a message.
+    pub fn new(to_msg: impl FnMut(bool) -> Msg + 'static) -> Self {
+        Self {
+            to_msg: Box::new(to_msg),
+            matches: None,
+        }
+    }
+
+    /// Map the user's color scheme to a message.
+    pub fn color_scheme(mut to_msg: impl FnMut(ColorScheme) -> Msg + 'static) -> Self {
+        Self::new(move |dark| to_msg(ColorScheme::from_dark(dark)))
+    }
+
+    /// The message for the query now matching or not, or `None` if that is
+    /// what it last did. The first call always has one, for the initial
+    /// value.
+    pub fn changed(&mut self, matches: bool) -> Option<Msg> {
+        if self.matches == Some(matches) {
+            return None;
+        }
+        self.matches = Some(matches);
+        Some((self.to_msg)(matches))
+    }
+}
+
+/// A running media query subscription; dropping it unsubscribes.
+#[cfg(target_arch = "wasm32")]
+pub struct Subscription {
+    list: web_sys::MediaQueryList,
+    listener: wasm_bindgen::closure::Closure<dyn FnMut(web_sys::MediaQueryListEvent)>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        use wasm_bindgen::JsCast;
+
+        let _ = self.list.remove_event_listener_with_callback("change", self.listener.as_ref().unchecked_ref());
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl<Msg: 'static> MediaWatch<Msg> {
+    /// Watch `query` with `matchMedia`, dispatching the message for whether
+    /// it matches now and then one per change (e.g. to
+    /// [`Program::dispatch`]) until the subscription is dropped. `None`
+    /// outside a window or for a query the browser can't parse.
+    ///
+    /// [`Program::dispatch`]: crate::cmd::Program::dispatch
+    pub fn subscribe(mut self, query: &str, mut dispatch: impl FnMut(Msg) + 'static) -> Option<Subscription> {
+        use wasm_bindgen::closure::Closure;
+        use wasm_bindgen::JsCast;
+
+        let list = web_sys::window()?.match_media(query).ok().flatten()?;
+        if let Some(msg) = self.changed(list.matches()) {
+            dispatch(msg);
+        }
+        let on_change = move |event: web_sys::MediaQueryListEvent| {
+            if let Some(msg) = self.changed(event.matches()) {
+                dispatch(msg);
+            }
+        };
+        let listener: Closure<dyn FnMut(web_sys::MediaQueryListEvent)> = Closure::new(on_change);
+        list.add_event_listener_with_callback("change", listener.as_ref().unchecked_ref()).ok()?;
+        Some(Subscription { list, listener })
+    }
+}
+
+/// Dispatch `to_msg` of the user's color scheme now and each time it
+/// changes, until the subscription is dropped.
+#[cfg(target_arch = "wasm32")]
+pub fn on_color_scheme_change<Msg: 'static>(
+    to_msg: impl FnMut(ColorScheme) -> Msg + 'static,
+    dispatch: impl FnMut(Msg) + 'static,
+) -> Option<Subscription> {
+    MediaWatch::color_scheme(to_msg).subscribe(DARK_SCHEME_QUERY, dispatch)
+}
+
+/// The user's color scheme. Outside the browser there is no preference, so
+/// it is light.
+pub fn color_scheme() -> ColorScheme {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let dark = web_sys::window().and_then(|window| window.match_media(DARK_SCHEME_QUERY).ok().flatten());
+        ColorScheme::from_dark(dark.is_some_and(|list| list.matches()))
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        ColorScheme::Light
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_sends_the_initial_value_then_only_changes() {
+        let mut watch = MediaWatch::new(|matches| matches);
+        assert_eq!(watch.changed(true), Some(true));
+        assert_eq!(watch.changed(true), None);
+        assert_eq!(watch.changed(false), Some(false));
+        assert_eq!(watch.changed(false), None);
+    }
+
+    #[test]
+    fn test_color_schemes_serialize_as_their_theme_names() {
+        assert_eq!(serde_json::to_string(&ColorScheme::Dark).unwrap(), "\"dark\"");
+        assert_eq!(ColorScheme::from_dark(false).as_str(), "light");
+        assert_eq!(ColorScheme::parse("sepia"), None);
+        assert_eq!(color_scheme(), ColorScheme::Light);
+    }
+}
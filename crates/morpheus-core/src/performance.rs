@@ -0,0 +1,133 @@
+//! Performance budgets for proposed versions.
+//!
+//! A headless browser loads each proposed version before it is saved and
+//! measures what users would feel: how long the first render takes (fetching,
+//! compiling and instantiating the module, then `render()`), how many bytes
+//! the bundle is, and how many long tasks (over 50 ms) block the main thread
+//! meanwhile. A [`PerformanceBudget`] sets limits on each;
+//! [`PerformanceMeasurements::compare`] describes a proposal against the
+//! live version, so reviewers see regressions before activating it.
+//!
+//! ```rust
+//! use morpheus_core::performance::{PerformanceBudget, PerformanceMeasurements};
+//!
+//! let live = PerformanceMeasurements { first_render_ms: 12.0, bundle_bytes: 90_000, long_tasks: 0 };
+//! let proposed = PerformanceMeasurements { first_render_ms: 64.0, bundle_bytes: 120_000, long_tasks: 1 };
+//!
+//! let budget = PerformanceBudget { max_first_render_ms: Some(50.0), ..Default::default() };
+//! assert_eq!(budget.check(&proposed), vec!["first render took 64 ms, over the 50 ms budget"]);
+//! assert_eq!(
+//!     proposed.compare(Some(&live)),
+//!     "first render 64 ms (live 12 ms), bundle 120000 bytes (live 90000), 1 long task(s) (live 0)"
+//! );
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// What loading a version cost in a headless browser.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PerformanceMeasurements {
+    /// From starting to load the module to its first `render()` returning,
+    /// in milliseconds.
+    pub first_render_ms: f64,
+
+    /// Module plus glue, in bytes.
+    pub bundle_bytes: u64,
+
+    /// Main-thread tasks over 50 ms until the first render.
+    pub long_tasks: u32,
+}
+
+impl PerformanceMeasurements {
+    /// The measurements, each followed by the `live` version's if known.
+    pub fn compare(&self, live: Option<&PerformanceMeasurements>) -> String {
+        match live {
+            Some(live) => format!(
+                "first render {:.0} ms (live {:.0} ms), bundle {} bytes (live {}), {} long task(s) (live {})",
+                self.first_render_ms,
+                live.first_render_ms,
+                self.bundle_bytes,
+                live.bundle_bytes,
+                self.long_tasks,
+                live.long_tasks
+            ),
+            None => format!(
+                "first render {:.0} ms, bundle {} bytes, {} long task(s)",
+                self.first_render_ms, self.bundle_bytes, self.long_tasks
+            ),
+        }
+    }
+}
+
+/// Limits on a version's measurements.
+///
+/// Every limit is optional; the default enforces nothing.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PerformanceBudget {
+    pub max_first_render_ms: Option<f64>,
+    pub max_bundle_bytes: Option<u64>,
+    pub max_long_tasks: Option<u32>,
+}
+
+impl PerformanceBudget {
+    /// Whether no limit is set.
+    pub fn is_empty(&self) -> bool {
+        *self == PerformanceBudget::default()
+    }
+
+    /// The limits `measurements` exceed, described.
+    pub fn check(&self, measurements: &PerformanceMeasurements) -> Vec<String> {
+        let mut exceeded = Vec::new();
+        if let Some(limit) = self.max_first_render_ms.filter(|&limit| measurements.first_render_ms > limit) {
+            exceeded.push(format!(
+                "first render took {:.0} ms, over the {:.0} ms budget",
+                measurements.first_render_ms, limit
+            ));
+        }
+        if let Some(limit) = self.max_bundle_bytes.filter(|&limit| measurements.bundle_bytes > limit) {
+            exceeded.push(format!(
+                "bundle is {} bytes, over the {} byte budget",
+                measurements.bundle_bytes, limit
+            ));
+        }
+        if let Some(limit) = self.max_long_tasks.filter(|&limit| measurements.long_tasks > limit) {
+            exceeded.push(format!(
+                "{} long task(s) blocked the main thread, over the budget of {}",
+                measurements.long_tasks, limit
+            ));
+        }
+        exceeded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_checks_each_limit() {
+        let measurements = PerformanceMeasurements {
+            first_render_ms: 30.0,
+            bundle_bytes: 200_000,
+            long_tasks: 2,
+        };
+        assert!(PerformanceBudget::default().is_empty());
+        assert!(PerformanceBudget::default().check(&measurements).is_empty());
+
+        let budget = PerformanceBudget {
+            max_first_render_ms: Some(30.0),
+            max_bundle_bytes: Some(150_000),
+            max_long_tasks: Some(0),
+        };
+        assert!(!budget.is_empty());
+        assert_eq!(
+            budget.check(&measurements),
+            vec![
+                "bundle is 200000 bytes, over the 150000 byte budget",
+                "2 long task(s) blocked the main thread, over the budget of 0",
+            ]
+        );
+        assert_eq!(measurements.compare(None), "first render 30 ms, bundle 200000 bytes, 2 long task(s)");
+    }
+}
@@ -4,12 +4,13 @@
 //! malicious or buggy code from compromising the application.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 
 /// Permissions granted to a component.
 ///
 /// Components declare what they need, and the runtime enforces limits.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Permissions {
     /// Network access permissions.
     pub network: NetworkPermissions,
@@ -19,6 +20,15 @@ pub struct Permissions {
 
     /// Which JavaScript APIs can be accessed.
     pub apis: HashSet<ApiPermission>,
+
+    /// What the component may do to the DOM (enforced by the DOM proxy).
+    #[serde(default)]
+    pub dom: DomPermissions,
+
+    /// How precisely a component granted [`ApiPermission::Geolocation`] may
+    /// locate the user.
+    #[serde(default)]
+    pub geolocation: GeoPrecision,
 }
 
 impl Default for Permissions {
@@ -30,12 +40,53 @@ impl Default for Permissions {
             network: NetworkPermissions::Denied,
             storage: StoragePermissions::None,
             apis: HashSet::new(),
+            dom: DomPermissions::default(),
+            geolocation: GeoPrecision::default(),
         }
     }
 }
 
+impl Permissions {
+    /// Flat list of granted capabilities, for comparing permission sets.
+    ///
+    /// Capabilities are namespaced, e.g. `network:api.example.com`,
+    /// `network:*` (unrestricted), `storage:*`, `api:Camera`,
+    /// `dom:inline_handlers`, `geolocation:exact`.
+    pub fn capabilities(&self) -> BTreeSet<String> {
+        let mut caps = BTreeSet::new();
+        match &self.network {
+            NetworkPermissions::Denied => {}
+            NetworkPermissions::AllowList(domains) => {
+                caps.extend(domains.iter().map(|d| format!("network:{}", d)));
+            }
+            NetworkPermissions::Unrestricted => {
+                caps.insert("network:*".to_string());
+            }
+        }
+        match &self.storage {
+            StoragePermissions::None => {}
+            StoragePermissions::Limited(keys) => {
+                caps.extend(keys.iter().map(|k| format!("storage:{}", k)));
+            }
+            StoragePermissions::Full => {
+                caps.insert("storage:*".to_string());
+            }
+        }
+        caps.extend(self.apis.iter().map(|api| format!("api:{:?}", api)));
+        caps.extend(self.dom.targets.iter().map(|t| format!("dom:target:{}", t)));
+        if self.dom.inline_handlers {
+            caps.insert("dom:inline_handlers".to_string());
+        }
+        if self.apis.contains(&ApiPermission::Geolocation) && self.geolocation == GeoPrecision::Exact {
+            caps.insert("geolocation:exact".to_string());
+        }
+        caps
+    }
+}
+
 /// Network access permissions.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum NetworkPermissions {
     /// No network access allowed.
     Denied,
@@ -48,7 +99,8 @@ pub enum NetworkPermissions {
 }
 
 /// Storage access permissions.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum StoragePermissions {
     /// No storage access.
     None,
@@ -60,8 +112,39 @@ pub enum StoragePermissions {
     Full,
 }
 
+/// DOM access for components whose DOM changes go through a proxy
+/// (e.g. components running in a Web Worker).
+///
+/// A component may always modify its own mount point. Script elements and
+/// `javascript:` URLs are never allowed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DomPermissions {
+    /// Additional element IDs outside the mount point the component may modify.
+    #[serde(default)]
+    pub targets: Vec<String>,
+
+    /// Allow inline event handler attributes (`onclick="..."`).
+    #[serde(default)]
+    pub inline_handlers: bool,
+}
+
+/// Precision of the positions a component is given.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum GeoPrecision {
+    /// Snapped to a grid of about 10 km, enough for weather or local news.
+    #[default]
+    City,
+
+    /// The position as the browser reports it (use sparingly!).
+    Exact,
+}
+
 /// Specific JavaScript APIs that can be accessed.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum ApiPermission {
     /// Geolocation API.
     Geolocation,
@@ -80,6 +163,9 @@ pub enum ApiPermission {
 
     /// WebGL/Canvas rendering.
     Graphics,
+
+    /// Reading files the user picks.
+    Files,
 }
 
 #[cfg(test)]
@@ -94,6 +180,8 @@ mod tests {
         assert!(matches!(perms.network, NetworkPermissions::Denied));
         assert!(matches!(perms.storage, StoragePermissions::None));
         assert!(perms.apis.is_empty());
+        assert!(perms.dom.targets.is_empty());
+        assert!(!perms.dom.inline_handlers);
     }
 
     #[test]
@@ -157,6 +245,8 @@ mod tests {
             ]),
             storage: StoragePermissions::Limited(vec!["cache".to_string()]),
             apis: HashSet::new(),
+            dom: DomPermissions::default(),
+            geolocation: GeoPrecision::City,
         };
         perms.apis.insert(ApiPermission::Notifications);
         perms.apis.insert(ApiPermission::Graphics);
@@ -234,6 +324,8 @@ mod tests {
             network: NetworkPermissions::Unrestricted,
             storage: StoragePermissions::Full,
             apis: HashSet::new(),
+            dom: DomPermissions::default(),
+            geolocation: GeoPrecision::City,
         };
 
         // Grant all API permissions
@@ -251,4 +343,46 @@ mod tests {
         assert!(matches!(trusted_perms.storage, StoragePermissions::Full));
         assert_eq!(trusted_perms.apis.len(), 6);
     }
+
+    #[test]
+    fn test_permissions_without_dom_deserialize() {
+        let json = r#"{"network": "Denied", "storage": "None", "apis": []}"#;
+        let perms: Permissions = serde_json::from_str(json).expect("Failed to deserialize");
+
+        assert_eq!(perms.dom, DomPermissions::default());
+        assert_eq!(perms.geolocation, GeoPrecision::City);
+    }
+
+    #[test]
+    fn test_capabilities() {
+        assert!(Permissions::default().capabilities().is_empty());
+
+        let perms = Permissions {
+            network: NetworkPermissions::AllowList(vec!["api.example.com".to_string()]),
+            storage: StoragePermissions::Full,
+            apis: HashSet::from([ApiPermission::Camera]),
+            dom: DomPermissions {
+                targets: Vec::new(),
+                inline_handlers: true,
+            },
+            geolocation: GeoPrecision::Exact,
+        };
+
+        let caps: Vec<_> = perms.capabilities().into_iter().collect();
+        assert_eq!(
+            caps,
+            vec!["api:Camera", "dom:inline_handlers", "network:api.example.com", "storage:*"]
+        );
+    }
+
+    #[test]
+    fn test_exact_geolocation_is_a_capability() {
+        let mut perms = Permissions::default();
+        perms.apis.insert(ApiPermission::Geolocation);
+        assert!(perms.capabilities().contains("api:Geolocation"));
+        assert!(!perms.capabilities().contains("geolocation:exact"));
+
+        perms.geolocation = GeoPrecision::Exact;
+        assert!(perms.capabilities().contains("geolocation:exact"));
+    }
 }
@@ -0,0 +1,416 @@
+//! Scrubbing personal data out of state snapshots.
+//!
+//! Component state is sent to the host and kept in version history, so
+//! anything a user typed into a component can end up stored indefinitely.
+//! A [`ScrubPolicy`] removes or redacts personal data before that happens:
+//!
+//! - **Denied fields** are removed wherever they appear
+//! - **Allowed fields**, if any are listed, are the only fields kept
+//! - **Redactions** replace regex matches inside string values
+//! - **Hashed fields** keep their place but have their values replaced by a
+//!   keyed hash, so equal values can still be told apart from different
+//!   ones without revealing either
+//!
+//! Clients call [`ScrubPolicy::scrub`] before sending state; hosts call
+//! [`ScrubPolicy::validate`] to refuse state that wasn't scrubbed. Both sides
+//! share one policy, which serializes to JSON. Hosts also scrub what they
+//! pass on to an AI model (runtime errors, crash state, feedback), with
+//! [`ScrubPolicy::redact_text`] for free text.
+//!
+//! ```rust
+//! use morpheus_core::privacy::ScrubPolicy;
+//!
+//! let policy = ScrubPolicy::new()
+//!     .with_denied_field("password")
+//!     .with_redaction("email", r"[\w.+-]+@[\w-]+\.[\w.]+")
+//!     .unwrap();
+//!
+//! let mut state = serde_json::json!({
+//!     "password": "hunter2",
+//!     "note": "mail me at ada@example.com",
+//! });
+//! policy.scrub(&mut state);
+//!
+//! assert_eq!(state, serde_json::json!({ "note": "mail me at [REDACTED:email]" }));
+//! ```
+
+use crate::errors::{MorpheusError, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// Rules for removing personal data from state.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScrubPolicy {
+    /// If non-empty, only fields with these names are kept, at any depth.
+    /// Objects are fields too: list a container to keep what's inside it.
+    #[serde(default)]
+    pub allowed_fields: Vec<String>,
+
+    /// Fields removed wherever they appear.
+    #[serde(default)]
+    pub denied_fields: Vec<String>,
+
+    /// Patterns redacted from string values.
+    #[serde(default)]
+    pub redactions: Vec<Redaction>,
+
+    /// Fields whose values are replaced by `[HASHED:<hash>]`.
+    #[serde(default)]
+    pub hashed_fields: Vec<String>,
+
+    /// Secret mixed into hashes, so short values can't be recovered by
+    /// hashing guesses. Never serialized, so a published policy doesn't
+    /// give it away.
+    #[serde(default, skip_serializing)]
+    pub hash_key: String,
+}
+
+/// A named pattern replaced by `[REDACTED:<name>]` in string values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RedactionSpec", into = "RedactionSpec")]
+pub struct Redaction {
+    name: String,
+    pattern: Regex,
+}
+
+/// Serialized form of a [`Redaction`].
+#[derive(Serialize, Deserialize)]
+struct RedactionSpec {
+    name: String,
+    pattern: String,
+}
+
+impl TryFrom<RedactionSpec> for Redaction {
+    type Error = MorpheusError;
+
+    fn try_from(spec: RedactionSpec) -> Result<Self> {
+        Redaction::new(spec.name, &spec.pattern)
+    }
+}
+
+impl From<Redaction> for RedactionSpec {
+    fn from(redaction: Redaction) -> Self {
+        RedactionSpec {
+            name: redaction.name,
+            pattern: redaction.pattern.as_str().to_string(),
+        }
+    }
+}
+
+impl Redaction {
+    /// Compile a redaction rule.
+    pub fn new(name: impl Into<String>, pattern: &str) -> Result<Self> {
+        let name = name.into();
+        let pattern = Regex::new(pattern)
+            .map_err(|e| MorpheusError::Other(format!("Invalid redaction pattern '{}': {}", name, e)))?;
+        Ok(Self { name, pattern })
+    }
+
+    /// Name used in the replacement text.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The pattern's source.
+    pub fn pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+}
+
+/// Something a policy removed or redacted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    /// Location in the state, e.g. `$.user.email` or `$.todos[2].text`.
+    pub path: String,
+
+    /// What was done there.
+    pub kind: FindingKind,
+}
+
+/// Why a value was scrubbed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FindingKind {
+    /// The field is on the deny list.
+    DeniedField,
+
+    /// The field isn't on the allow list.
+    UnlistedField,
+
+    /// A string matched a redaction rule.
+    Redacted { rule: String },
+
+    /// The field's value was replaced by its hash.
+    Hashed,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            FindingKind::DeniedField => write!(f, "{}: denied field", self.path),
+            FindingKind::UnlistedField => write!(f, "{}: field not on allow list", self.path),
+            FindingKind::Redacted { rule } => write!(f, "{}: matches '{}'", self.path, rule),
+            FindingKind::Hashed => write!(f, "{}: hashed field", self.path),
+        }
+    }
+}
+
+impl ScrubPolicy {
+    /// Create a policy that changes nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deny common credential fields and redact emails, card numbers and
+    /// US social security numbers.
+    pub fn recommended() -> Self {
+        let mut policy = Self::new();
+        for field in ["password", "passwd", "secret", "token", "api_key", "ssn"] {
+            policy = policy.with_denied_field(field);
+        }
+        policy
+            .with_redaction("email", r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}")
+            .and_then(|p| p.with_redaction("card", r"\b(?:\d[ -]?){12,18}\d\b"))
+            .and_then(|p| p.with_redaction("ssn", r"\b\d{3}-\d{2}-\d{4}\b"))
+            .expect("built-in patterns are valid")
+    }
+
+    /// Keep only fields named `name` (and other allowed fields).
+    pub fn with_allowed_field(mut self, name: impl Into<String>) -> Self {
+        self.allowed_fields.push(name.into());
+        self
+    }
+
+    /// Remove fields named `name`.
+    pub fn with_denied_field(mut self, name: impl Into<String>) -> Self {
+        self.denied_fields.push(name.into());
+        self
+    }
+
+    /// Redact matches of `pattern` from string values.
+    pub fn with_redaction(mut self, name: impl Into<String>, pattern: &str) -> Result<Self> {
+        self.redactions.push(Redaction::new(name, pattern)?);
+        Ok(self)
+    }
+
+    /// Replace the values of fields named `name` by their hash.
+    pub fn with_hashed_field(mut self, name: impl Into<String>) -> Self {
+        self.hashed_fields.push(name.into());
+        self
+    }
+
+    /// Mix `key` into hashes.
+    pub fn with_hash_key(mut self, key: impl Into<String>) -> Self {
+        self.hash_key = key.into();
+        self
+    }
+
+    /// Whether the policy has no rules.
+    pub fn is_empty(&self) -> bool {
+        self.allowed_fields.is_empty()
+            && self.denied_fields.is_empty()
+            && self.redactions.is_empty()
+            && self.hashed_fields.is_empty()
+    }
+
+    /// Apply the redactions to free text, such as an error message.
+    ///
+    /// Field rules need structure, so scrub values before formatting them
+    /// into text.
+    pub fn redact_text(&self, text: &str) -> String {
+        let mut value = Value::String(text.to_string());
+        self.scrub_at(&mut value, "$", &mut Vec::new());
+        match value {
+            Value::String(text) => text,
+            _ => unreachable!("strings stay strings"),
+        }
+    }
+
+    /// Remove and redact personal data in place, reporting what changed.
+    ///
+    /// Field names are matched case-insensitively.
+    pub fn scrub(&self, value: &mut Value) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        self.scrub_at(value, "$", &mut findings);
+        findings
+    }
+
+    /// Check that `value` contains nothing the policy would scrub.
+    pub fn validate(&self, value: &Value) -> Result<()> {
+        let findings = self.scrub(&mut value.clone());
+        if findings.is_empty() {
+            return Ok(());
+        }
+        let details: Vec<String> = findings.iter().map(Finding::to_string).collect();
+        Err(MorpheusError::InvalidState(format!(
+            "State contains unscrubbed personal data ({})",
+            details.join("; ")
+        )))
+    }
+
+    fn scrub_at(&self, value: &mut Value, path: &str, findings: &mut Vec<Finding>) {
+        match value {
+            Value::Object(fields) => {
+                fields.retain(|name, _| {
+                    let kind = if contains(&self.denied_fields, name) {
+                        FindingKind::DeniedField
+                    } else if !self.allowed_fields.is_empty() && !contains(&self.allowed_fields, name) {
+                        FindingKind::UnlistedField
+                    } else {
+                        return true;
+                    };
+                    findings.push(Finding {
+                        path: format!("{}.{}", path, name),
+                        kind,
+                    });
+                    false
+                });
+                for (name, field) in fields.iter_mut() {
+                    let path = format!("{}.{}", path, name);
+                    if contains(&self.hashed_fields, name) {
+                        *field = Value::String(format!("[HASHED:{}]", self.hash(field)));
+                        findings.push(Finding {
+                            path,
+                            kind: FindingKind::Hashed,
+                        });
+                    } else {
+                        self.scrub_at(field, &path, findings);
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter_mut().enumerate() {
+                    self.scrub_at(item, &format!("{}[{}]", path, index), findings);
+                }
+            }
+            Value::String(text) => {
+                for redaction in &self.redactions {
+                    if redaction.pattern.is_match(text) {
+                        let replacement = format!("[REDACTED:{}]", redaction.name);
+                        *text = redaction.pattern.replace_all(text, replacement.as_str()).into_owned();
+                        findings.push(Finding {
+                            path: path.to_string(),
+                            kind: FindingKind::Redacted {
+                                rule: redaction.name.clone(),
+                            },
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// First 12 hex digits of the keyed SHA-256 of `value`'s JSON.
+    fn hash(&self, value: &Value) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.hash_key.as_bytes());
+        hasher.update([0]);
+        hasher.update(value.to_string().as_bytes());
+        hasher.finalize()[..6].iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+fn contains(names: &[String], name: &str) -> bool {
+    names.iter().any(|n| n.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_denied_fields_removed_at_any_depth() {
+        let policy = ScrubPolicy::new().with_denied_field("Password");
+        let mut state = json!({ "user": { "name": "Ada", "password": "x" }, "password": "y" });
+
+        let findings = policy.scrub(&mut state);
+
+        assert_eq!(state, json!({ "user": { "name": "Ada" } }));
+        assert_eq!(findings.len(), 2);
+        assert!(findings.iter().any(|f| f.path == "$.user.password"));
+    }
+
+    #[test]
+    fn test_allow_list_keeps_only_listed_fields() {
+        let policy = ScrubPolicy::new()
+            .with_allowed_field("todos")
+            .with_allowed_field("text");
+        let mut state = json!({ "todos": [{ "text": "a", "owner": "ada" }], "email": "e" });
+
+        let findings = policy.scrub(&mut state);
+
+        assert_eq!(state, json!({ "todos": [{ "text": "a" }] }));
+        assert!(findings.contains(&Finding {
+            path: "$.todos[0].owner".to_string(),
+            kind: FindingKind::UnlistedField,
+        }));
+    }
+
+    #[test]
+    fn test_recommended_redacts_strings() {
+        let policy = ScrubPolicy::recommended();
+        let mut state = json!({ "notes": ["call 555-12-3456", "card 4111 1111 1111 1111", "hi"] });
+
+        policy.scrub(&mut state);
+
+        assert_eq!(
+            state,
+            json!({ "notes": ["call [REDACTED:ssn]", "card [REDACTED:card]", "hi"] })
+        );
+    }
+
+    #[test]
+    fn test_validate_accepts_scrubbed_state() {
+        let policy = ScrubPolicy::recommended();
+        let mut state = json!({ "count": 3, "token": "abc", "contact": "ada@example.com" });
+
+        let error = policy.validate(&state).unwrap_err().to_string();
+        assert!(error.contains("$.token"));
+        assert!(error.contains("$.contact: matches 'email'"));
+
+        policy.scrub(&mut state);
+        assert!(policy.validate(&state).is_ok());
+    }
+
+    #[test]
+    fn test_hashed_fields_keep_equality_but_not_values() {
+        let policy = ScrubPolicy::new().with_hashed_field("user").with_hash_key("k");
+        let mut state = json!({ "rows": [{ "user": "ada" }, { "user": "ada" }, { "user": "bob" }] });
+
+        let findings = policy.scrub(&mut state);
+
+        let hashes: Vec<&str> = (0..3).map(|i| state["rows"][i]["user"].as_str().unwrap()).collect();
+        assert!(hashes[0].starts_with("[HASHED:") && !hashes[0].contains("ada"));
+        assert_eq!(hashes[0], hashes[1]);
+        assert_ne!(hashes[0], hashes[2]);
+        assert_eq!(findings[0].to_string(), "$.rows[0].user: hashed field");
+
+        let mut rekeyed = json!({ "user": "ada" });
+        policy.clone().with_hash_key("other").scrub(&mut rekeyed);
+        assert_ne!(rekeyed["user"].as_str(), Some(hashes[0]));
+        assert!(!serde_json::to_string(&policy).unwrap().contains("\"k\""));
+    }
+
+    #[test]
+    fn test_redact_text() {
+        let text = ScrubPolicy::recommended().redact_text("failed for ada@example.com");
+        assert_eq!(text, "failed for [REDACTED:email]");
+    }
+
+    #[test]
+    fn test_policy_round_trips_through_json() {
+        let policy = ScrubPolicy::recommended();
+
+        let json = serde_json::to_string(&policy).unwrap();
+        let parsed: ScrubPolicy = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed.redactions.len(), 3);
+        assert_eq!(parsed.redactions[0].pattern(), policy.redactions[0].pattern());
+        assert!(serde_json::from_str::<ScrubPolicy>(r#"{"redactions":[{"name":"x","pattern":"("}]}"#).is_err());
+    }
+}
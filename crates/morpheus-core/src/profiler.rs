@@ -0,0 +1,249 @@
+//! Render profiling.
+//!
+//! The host times every render of a component in three parts: building the
+//! view (the component's `render()`), diffing it against the page, and the
+//! number of DOM patches the update applied. Each phase is marked with the
+//! browser's Performance API under [`VIEW_MEASURE`] and [`DIFF_MEASURE`], so
+//! it also shows up in dev tools, and reported to the server as a
+//! [`RenderSample`].
+//!
+//! A [`Profiler`] keeps the latest samples of each component's live version
+//! and summarises them as [`RenderProfile`]s for the dev overlay;
+//! [`prompt_section`] turns a slow profile into context for the AI, so it
+//! can optimise the view that is actually slow.
+
+use crate::errors::{MorpheusError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Performance measure covering view construction.
+pub const VIEW_MEASURE: &str = "morpheus:view";
+
+/// Performance measure covering the diff and DOM update.
+pub const DIFF_MEASURE: &str = "morpheus:diff";
+
+/// Samples kept per component.
+pub const SAMPLES_KEPT: usize = 200;
+
+/// Render time above which a profile counts as slow: one 60 fps frame.
+pub const SLOW_RENDER_MS: f64 = 16.0;
+
+/// One render of a component, as timed by the host.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RenderSample {
+    /// Component that rendered.
+    pub component: String,
+
+    /// Version that rendered.
+    #[serde(default)]
+    pub version: Option<u32>,
+
+    /// Time spent building the view, in milliseconds.
+    pub view_ms: f64,
+
+    /// Time spent diffing and patching the DOM, in milliseconds.
+    pub diff_ms: f64,
+
+    /// DOM patches applied.
+    pub patches: u32,
+}
+
+/// Average, 95th percentile and maximum of a set of timings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Timing {
+    pub average_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+impl Timing {
+    pub(crate) fn of(mut values: Vec<f64>) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+        values.sort_by(f64::total_cmp);
+        let p95 = (values.len() * 95).div_ceil(100).max(1) - 1;
+        Self {
+            average_ms: values.iter().sum::<f64>() / values.len() as f64,
+            p95_ms: values[p95],
+            max_ms: values[values.len() - 1],
+        }
+    }
+}
+
+/// How a component's live version renders.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RenderProfile {
+    pub component: String,
+    pub version: Option<u32>,
+
+    /// Renders recorded since the version went live.
+    pub renders: u64,
+
+    /// View construction, over the kept samples.
+    pub view: Timing,
+
+    /// Diff and DOM update, over the kept samples.
+    pub diff: Timing,
+
+    /// DOM patches per render.
+    pub average_patches: f64,
+    pub max_patches: u32,
+}
+
+impl RenderProfile {
+    /// Whether renders regularly take longer than [`SLOW_RENDER_MS`].
+    pub fn is_slow(&self) -> bool {
+        self.view.p95_ms + self.diff.p95_ms > SLOW_RENDER_MS
+    }
+}
+
+/// Recent samples of one component.
+#[derive(Debug, Default)]
+struct Samples {
+    version: Option<u32>,
+    renders: u64,
+    recent: VecDeque<RenderSample>,
+}
+
+/// Recent render samples, by component.
+#[derive(Debug, Default)]
+pub struct Profiler {
+    components: BTreeMap<String, Samples>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a render.
+    ///
+    /// A sample from a different version than the component's previous
+    /// samples starts its profile over, so profiles describe one version.
+    pub fn record(&mut self, sample: RenderSample) -> Result<()> {
+        if ![sample.view_ms, sample.diff_ms].iter().all(|ms| ms.is_finite() && *ms >= 0.0) {
+            return Err(MorpheusError::InvalidState(format!(
+                "Render timings must be non-negative, got {} ms view and {} ms diff",
+                sample.view_ms, sample.diff_ms
+            )));
+        }
+        let samples = self.components.entry(sample.component.clone()).or_default();
+        if samples.version != sample.version {
+            *samples = Samples {
+                version: sample.version,
+                ..Samples::default()
+            };
+        }
+        if samples.recent.len() == SAMPLES_KEPT {
+            samples.recent.pop_front();
+        }
+        samples.renders += 1;
+        samples.recent.push_back(sample);
+        Ok(())
+    }
+
+    /// Profile of `component`, if it has rendered.
+    pub fn profile(&self, component: &str) -> Option<RenderProfile> {
+        let samples = self.components.get(component)?;
+        let recent = &samples.recent;
+        let patches: u32 = recent.iter().map(|s| s.patches).sum();
+        Some(RenderProfile {
+            component: component.to_string(),
+            version: samples.version,
+            renders: samples.renders,
+            view: Timing::of(recent.iter().map(|s| s.view_ms).collect()),
+            diff: Timing::of(recent.iter().map(|s| s.diff_ms).collect()),
+            average_patches: f64::from(patches) / recent.len().max(1) as f64,
+            max_patches: recent.iter().map(|s| s.patches).max().unwrap_or(0),
+        })
+    }
+
+    /// Profiles of every component, slowest first.
+    pub fn profiles(&self) -> Vec<RenderProfile> {
+        let mut profiles: Vec<_> = self.components.keys().filter_map(|c| self.profile(c)).collect();
+        profiles.sort_by(|a, b| (b.view.p95_ms + b.diff.p95_ms).total_cmp(&(a.view.p95_ms + a.diff.p95_ms)));
+        profiles
+    }
+
+    /// Forget `component`'s samples.
+    pub fn clear(&mut self, component: &str) {
+        self.components.remove(component);
+    }
+}
+
+/// Render a slow profile as a prompt section.
+///
+/// Empty unless the profile [is slow](RenderProfile::is_slow), so fast views
+/// aren't "optimised" for nothing.
+pub fn prompt_section(profile: &RenderProfile) -> String {
+    if !profile.is_slow() {
+        return String::new();
+    }
+    let timing = |t: &Timing| format!("{:.1} ms average, {:.1} ms p95, {:.1} ms max", t.average_ms, t.p95_ms, t.max_ms);
+    let plural = if profile.renders == 1 { "" } else { "s" };
+    let mut section = format!("RENDER PROFILE ({} render{}):\n", profile.renders, plural);
+    section.push_str(&format!("- Building the view (render()): {}\n", timing(&profile.view)));
+    section.push_str(&format!("- Diffing and updating the DOM: {}\n", timing(&profile.diff)));
+    section.push_str(&format!(
+        "- DOM patches per render: {:.1} average, {} max\n",
+        profile.average_patches, profile.max_patches
+    ));
+    let slower = if profile.view.p95_ms >= profile.diff.p95_ms {
+        "Building the view is the slower part: avoid repeated work and large string copies in render()."
+    } else {
+        "Updating the DOM is the slower part: render less markup and give list items stable data-key attributes."
+    };
+    section.push_str(&format!("Renders should take under {:.0} ms. {}\n", SLOW_RENDER_MS, slower));
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(version: u32, view_ms: f64, diff_ms: f64, patches: u32) -> RenderSample {
+        RenderSample {
+            component: "chart".to_string(),
+            version: Some(version),
+            view_ms,
+            diff_ms,
+            patches,
+        }
+    }
+
+    #[test]
+    fn test_profiles_summarise_the_live_version() {
+        let mut profiler = Profiler::new();
+        profiler.record(sample(1, 90.0, 1.0, 40)).unwrap();
+        for ms in 1..=20 {
+            profiler.record(sample(2, f64::from(ms), 0.5, 2)).unwrap();
+        }
+        assert!(profiler.record(sample(2, -1.0, 0.0, 0)).is_err());
+        assert!(profiler.record(sample(2, f64::NAN, 0.0, 0)).is_err());
+
+        let profile = profiler.profile("chart").unwrap();
+        assert_eq!((profile.version, profile.renders), (Some(2), 20));
+        assert_eq!(profile.view.average_ms, 10.5);
+        assert_eq!((profile.view.p95_ms, profile.view.max_ms), (19.0, 20.0));
+        assert_eq!((profile.average_patches, profile.max_patches), (2.0, 2));
+        assert!(profile.is_slow());
+        assert!(profiler.profile("table").is_none());
+    }
+
+    #[test]
+    fn test_prompt_section_names_the_slow_phase() {
+        let mut profiler = Profiler::new();
+        profiler.record(sample(1, 2.0, 1.0, 3)).unwrap();
+        assert!(prompt_section(&profiler.profile("chart").unwrap()).is_empty());
+
+        profiler.record(sample(1, 1.0, 45.0, 300)).unwrap();
+        let section = prompt_section(&profiler.profile("chart").unwrap());
+        assert!(section.contains("RENDER PROFILE (2 renders)"));
+        assert!(section.contains("Diffing and updating the DOM: 23.0 ms average, 45.0 ms p95, 45.0 ms max"));
+        assert!(section.contains("Updating the DOM is the slower part"));
+    }
+}
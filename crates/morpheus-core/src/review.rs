@@ -0,0 +1,136 @@
+//! Human review of AI-generated versions.
+//!
+//! Teams that review code before it ships can require a version to be
+//! approved before it is activated. Reviewers leave inline comments on line
+//! ranges of the version's source and approve or request changes.
+
+use crate::errors::{MorpheusError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Outcome of a review.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewStatus {
+    /// Not yet reviewed.
+    #[default]
+    Pending,
+
+    /// Approved for activation.
+    Approved,
+
+    /// A reviewer asked for changes.
+    ChangesRequested,
+}
+
+/// An inline comment on a range of source lines.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ReviewComment {
+    /// Who wrote the comment.
+    pub author: String,
+
+    /// First line commented on (1-based, inclusive).
+    pub start_line: usize,
+
+    /// Last line commented on (1-based, inclusive).
+    pub end_line: usize,
+
+    /// Comment text.
+    pub body: String,
+}
+
+/// Review state of one version.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Review {
+    /// Everyone who has approved or requested changes, in order.
+    #[serde(default)]
+    pub reviewers: Vec<String>,
+
+    /// Current status (the most recent verdict wins).
+    #[serde(default)]
+    pub status: ReviewStatus,
+
+    /// Inline comments.
+    #[serde(default)]
+    pub comments: Vec<ReviewComment>,
+}
+
+impl Review {
+    /// Add an inline comment, checking its line range against `source`.
+    pub fn comment(&mut self, source: &str, comment: ReviewComment) -> Result<()> {
+        let lines = source.lines().count();
+        if comment.start_line == 0 || comment.start_line > comment.end_line || comment.end_line > lines {
+            return Err(MorpheusError::InvalidState(format!(
+                "Line range {}-{} is outside the source (1-{})",
+                comment.start_line, comment.end_line, lines
+            )));
+        }
+        self.comments.push(comment);
+        Ok(())
+    }
+
+    /// Record a reviewer's verdict.
+    pub fn record_verdict(&mut self, reviewer: impl Into<String>, status: ReviewStatus) {
+        let reviewer = reviewer.into();
+        if !self.reviewers.contains(&reviewer) {
+            self.reviewers.push(reviewer);
+        }
+        self.status = status;
+    }
+
+    /// Whether the version may be activated.
+    pub fn is_approved(&self) -> bool {
+        self.status == ReviewStatus::Approved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "use wasm_bindgen::prelude::*;\n\n#[wasm_bindgen]\npub fn render() -> String {\n    String::new()\n}\n";
+
+    fn comment(start_line: usize, end_line: usize) -> ReviewComment {
+        ReviewComment {
+            author: "alice".to_string(),
+            start_line,
+            end_line,
+            body: "Looks fine".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_comment_within_source() {
+        let mut review = Review::default();
+
+        review.comment(SOURCE, comment(4, 6)).expect("Failed to comment");
+
+        assert_eq!(review.comments.len(), 1);
+        assert_eq!(review.status, ReviewStatus::Pending);
+    }
+
+    #[test]
+    fn test_comment_out_of_range() {
+        let mut review = Review::default();
+
+        assert!(review.comment(SOURCE, comment(0, 1)).is_err());
+        assert!(review.comment(SOURCE, comment(5, 4)).is_err());
+        assert!(review.comment(SOURCE, comment(6, 7)).is_err());
+        assert!(review.comments.is_empty());
+    }
+
+    #[test]
+    fn test_latest_verdict_wins() {
+        let mut review = Review::default();
+        assert!(!review.is_approved());
+
+        review.record_verdict("alice", ReviewStatus::ChangesRequested);
+        review.record_verdict("bob", ReviewStatus::Approved);
+        review.record_verdict("alice", ReviewStatus::Approved);
+
+        assert!(review.is_approved());
+        assert_eq!(review.reviewers, vec!["alice", "bob"]);
+    }
+}
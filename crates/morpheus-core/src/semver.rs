@@ -0,0 +1,183 @@
+//! Semantic versions of components.
+//!
+//! A component's version says what its dependents can expect from it:
+//! reloads that remove something from its [interface](crate::interface) are
+//! a new major version, reloads that only add to it a new minor version,
+//! and reloads that leave it unchanged a patch. Dependents pin the version
+//! they were loaded against and stay compatible with later versions of the
+//! same major.
+//!
+//! ```rust
+//! use morpheus_core::interface::{InterfaceChange, InterfaceItem};
+//! use morpheus_core::semver::{Bump, SemVer};
+//!
+//! let added = [InterfaceChange::Added(InterfaceItem::Export, "reset".to_string())];
+//! let version = SemVer::new(1, 2, 3).bump(Bump::for_changes(&added));
+//!
+//! assert_eq!(version.to_string(), "1.3.0");
+//! assert!(version.is_compatible_with(&"1.2.0".parse().unwrap()));
+//! assert!(!version.is_compatible_with(&"2.0.0".parse().unwrap()));
+//! ```
+
+use crate::errors::MorpheusError;
+use crate::interface::InterfaceChange;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A `major.minor.patch` version, serialized as a string such as `"1.3.0"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "VersionSpec", into = "String")]
+pub struct SemVer {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl SemVer {
+    /// The version of a newly loaded component.
+    pub const INITIAL: SemVer = SemVer::new(1, 0, 0);
+
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self { major, minor, patch }
+    }
+
+    /// The next version after a change of this size.
+    pub fn bump(self, bump: Bump) -> Self {
+        match bump {
+            Bump::Major => Self::new(self.major + 1, 0, 0),
+            Bump::Minor => Self::new(self.major, self.minor + 1, 0),
+            Bump::Patch => Self::new(self.major, self.minor, self.patch + 1),
+        }
+    }
+
+    /// Whether a dependent loaded against `pinned` works with this version:
+    /// the same major version, and no older than `pinned`.
+    pub fn is_compatible_with(&self, pinned: &SemVer) -> bool {
+        self.major == pinned.major && self >= pinned
+    }
+}
+
+impl Default for SemVer {
+    fn default() -> Self {
+        Self::INITIAL
+    }
+}
+
+impl fmt::Display for SemVer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for SemVer {
+    type Err = MorpheusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || MorpheusError::InvalidState(format!("Invalid version '{}', expected major.minor.patch", s));
+        let mut parts = s.trim().trim_start_matches('v').split('.');
+        let mut next = || parts.next().ok_or_else(invalid)?.parse::<u32>().map_err(|_| invalid());
+        let version = Self::new(next()?, next()?, next()?);
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(version)
+    }
+}
+
+/// Serialized form of a [`SemVer`]: a string, or the plain counter versions
+/// were before, read as patch releases of 1.0.0 (version 3 is `1.0.2`).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum VersionSpec {
+    Text(String),
+    Counter(u32),
+}
+
+impl TryFrom<VersionSpec> for SemVer {
+    type Error = MorpheusError;
+
+    fn try_from(spec: VersionSpec) -> Result<Self, Self::Error> {
+        match spec {
+            VersionSpec::Text(text) => text.parse(),
+            VersionSpec::Counter(counter) => Ok(Self::new(1, 0, counter.saturating_sub(1))),
+        }
+    }
+}
+
+impl From<SemVer> for String {
+    fn from(version: SemVer) -> Self {
+        version.to_string()
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for SemVer {
+    fn schema_name() -> String {
+        "SemVer".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(gen)
+    }
+}
+
+/// How big a change is, in semantic versioning terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Bump {
+    /// Same interface; only the behaviour changed.
+    Patch,
+    /// The interface grew.
+    Minor,
+    /// Something dependents may rely on was removed.
+    Major,
+}
+
+impl Bump {
+    /// The bump a set of interface changes calls for.
+    pub fn for_changes(changes: &[InterfaceChange]) -> Self {
+        if changes.iter().any(InterfaceChange::is_breaking) {
+            Bump::Major
+        } else if changes.is_empty() {
+            Bump::Patch
+        } else {
+            Bump::Minor
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::InterfaceItem;
+
+    #[test]
+    fn test_bumps_follow_interface_changes() {
+        let removed = InterfaceChange::Removed(InterfaceItem::Event, "changed".to_string());
+        let added = InterfaceChange::Added(InterfaceItem::StateField, "history".to_string());
+        assert_eq!(Bump::for_changes(&[]), Bump::Patch);
+        assert_eq!(Bump::for_changes(std::slice::from_ref(&added)), Bump::Minor);
+        assert_eq!(Bump::for_changes(&[added, removed]), Bump::Major);
+
+        let version = SemVer::new(1, 4, 2);
+        assert_eq!(version.bump(Bump::Patch), SemVer::new(1, 4, 3));
+        assert_eq!(version.bump(Bump::Minor), SemVer::new(1, 5, 0));
+        assert_eq!(version.bump(Bump::Major), SemVer::new(2, 0, 0));
+        assert!(SemVer::new(1, 5, 0).is_compatible_with(&version));
+        assert!(!SemVer::new(1, 4, 1).is_compatible_with(&version));
+    }
+
+    #[test]
+    fn test_versions_parse_and_read_old_counters() {
+        assert_eq!("v2.10.0".parse::<SemVer>().unwrap(), SemVer::new(2, 10, 0));
+        assert!("1.2".parse::<SemVer>().is_err());
+        assert!("1.2.3.4".parse::<SemVer>().is_err());
+
+        assert_eq!(serde_json::to_string(&SemVer::new(1, 3, 0)).unwrap(), r#""1.3.0""#);
+        assert_eq!(serde_json::from_str::<SemVer>(r#""1.3.0""#).unwrap(), SemVer::new(1, 3, 0));
+        assert_eq!(serde_json::from_str::<SemVer>("3").unwrap(), SemVer::new(1, 0, 2));
+        assert!(serde_json::from_str::<SemVer>(r#""next""#).is_err());
+    }
+}
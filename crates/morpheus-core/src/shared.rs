@@ -0,0 +1,138 @@
+//! Host types shared with generated components.
+//!
+//! Data crossing between the host and a component (positions, events,
+//! records from the app's API) travels as JSON, and each side used to
+//! declare its own struct for it; a renamed field on the host broke every
+//! component silently. A [`SharedType`] (derived with
+//! `#[derive(SharedType)]`) carries its own definition, and [`SharedTypes`]
+//! collects the definitions into the source of a `morpheus_types` crate
+//! every component builds against, versioned by a hash of its contents.
+//!
+//! ```rust
+//! use morpheus_core::shared::{SharedType, SharedTypes};
+//! use serde::{Deserialize, Serialize};
+//!
+//! /// An item in the shop's catalogue.
+//! #[derive(Serialize, Deserialize, SharedType)]
+//! struct Product {
+//!     sku: String,
+//!     #[serde(rename = "priceCents")]
+//!     price_cents: u64,
+//! }
+//!
+//! let types = SharedTypes::new().with::<Product>();
+//!
+//! assert_eq!(types.names(), ["Product"]);
+//! assert!(types.source().contains("pub struct Product {\n    pub sku: String,"));
+//! assert!(types.source().contains(r#"#[serde(rename = "priceCents")]"#));
+//! assert_eq!(types.version().len(), 16);
+//! ```
+
+pub use morpheus_macros::SharedType;
+
+use sha2::{Digest, Sha256};
+
+/// Name of the crate components import shared types from.
+pub const CRATE_NAME: &str = "morpheus_types";
+
+/// A host type components can use as-is.
+///
+/// Derive it with `#[derive(SharedType)]`. The definition keeps the type's
+/// doc comments and `serde` attributes, makes the type and its fields
+/// public, and derives `Debug`, `Clone`, `PartialEq`, `Serialize` and
+/// `Deserialize`; its fields may only use std types, `serde_json::Value`
+/// and other shared types.
+pub trait SharedType {
+    /// Name of the type.
+    const NAME: &'static str;
+
+    /// Rust definition of the type, as components see it.
+    const SOURCE: &'static str;
+}
+
+/// The host types shared with components, in the order they were added.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SharedTypes {
+    types: Vec<(&'static str, &'static str)>,
+}
+
+impl SharedTypes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Share `T`; sharing a type twice keeps the first.
+    pub fn with<T: SharedType>(mut self) -> Self {
+        if !self.types.iter().any(|(name, _)| *name == T::NAME) {
+            self.types.push((T::NAME, T::SOURCE));
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    /// Names of the shared types.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.types.iter().map(|(name, _)| *name).collect()
+    }
+
+    /// Definitions of the shared types, one after another.
+    pub fn source(&self) -> String {
+        self.types.iter().map(|(_, source)| *source).collect::<Vec<_>>().join("\n")
+    }
+
+    /// Hash of the definitions, e.g. `"3f2a9c0d41b7e865"`; it changes
+    /// whenever any shared type does.
+    pub fn version(&self) -> String {
+        Sha256::digest(self.source().as_bytes())[..8].iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Source of the `morpheus_types` crate's `lib.rs`.
+    pub fn crate_source(&self) -> String {
+        format!(
+            "//! Types shared with the host application (generated; do not edit).\n\n\
+             /// Version of the shared types, for checking against the host's.\n\
+             pub const VERSION: &str = \"{}\";\n\n{}",
+            self.version(),
+            self.source()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    /// How an order is doing.
+    #[derive(Debug, Serialize, Deserialize, SharedType)]
+    #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+    #[serde(rename_all = "snake_case")]
+    enum OrderStatus {
+        Open,
+        /// Sent to the customer.
+        Shipped { tracking: String },
+    }
+
+    #[test]
+    fn test_shared_definitions_keep_only_the_data_contract() {
+        assert_eq!(
+            OrderStatus::SOURCE,
+            "/// How an order is doing.\n\
+             #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]\n\
+             #[serde(rename_all = \"snake_case\")]\n\
+             pub enum OrderStatus {\n    \
+                 Open,\n    \
+                 /// Sent to the customer.\n    \
+                 Shipped { tracking: String },\n\
+             }\n"
+        );
+
+        let types = SharedTypes::new().with::<OrderStatus>().with::<OrderStatus>();
+        assert_eq!(types.names(), ["OrderStatus"]);
+        assert!(types.crate_source().contains(&format!("pub const VERSION: &str = \"{}\";", types.version())));
+        assert_ne!(types.version(), SharedTypes::new().version());
+    }
+}
@@ -1,6 +1,17 @@
 //! Versioned state management with rollback support.
 //!
 //! All state changes are tracked so modifications can be rolled back atomically.
+//! State shared by concurrent clients can instead be kept in a [`CrdtDoc`],
+//! which merges their edits rather than letting the last write win.
+//! A component's own state implements [`MorpheusState`] to survive hot
+//! reloads between versions whose state differs.
+
+mod crdt;
+mod preserve;
+
+pub use crdt::{Action, Clock, CrdtDoc, Key, ObjId, Op, OpId, OpValue, SyncMessage};
+pub use morpheus_macros::MorpheusState;
+pub use preserve::{MorpheusState, StateSchema};
 
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
@@ -107,6 +118,86 @@ impl<T: Clone> VersionedState<T> {
     pub fn clear_history(&mut self) {
         self.history.clear();
     }
+
+    /// Step through history without changing anything, starting at the
+    /// current state.
+    pub fn time_travel(&self) -> TimeTravel<'_, T> {
+        TimeTravel {
+            state: self,
+            position: self.history.len(),
+        }
+    }
+}
+
+/// Read-only cursor over a [`VersionedState`]'s history, for debugging how
+/// state evolved.
+///
+/// Positions run from 0 (the oldest snapshot) to `positions() - 1` (the
+/// current state).
+#[derive(Debug)]
+pub struct TimeTravel<'a, T> {
+    state: &'a VersionedState<T>,
+    position: usize,
+}
+
+impl<'a, T> TimeTravel<'a, T> {
+    /// Number of positions: every snapshot plus the current state.
+    pub fn positions(&self) -> usize {
+        self.state.history.len() + 1
+    }
+
+    /// Where the cursor is.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Whether the cursor is at the current state.
+    pub fn is_live(&self) -> bool {
+        self.position == self.state.history.len()
+    }
+
+    /// State at the cursor.
+    pub fn state(&self) -> &'a T {
+        match self.state.history.get(self.position) {
+            Some(snapshot) => &snapshot.state,
+            None => &self.state.current,
+        }
+    }
+
+    /// Version number at the cursor.
+    pub fn version(&self) -> u64 {
+        match self.state.history.get(self.position) {
+            Some(snapshot) => snapshot.version,
+            None => self.state.version,
+        }
+    }
+
+    /// Step one change back. Returns `None` at the oldest snapshot.
+    pub fn back(&mut self) -> Option<&'a T> {
+        self.position = self.position.checked_sub(1)?;
+        Some(self.state())
+    }
+
+    /// Step one change forward. Returns `None` at the current state.
+    pub fn forward(&mut self) -> Option<&'a T> {
+        if self.is_live() {
+            return None;
+        }
+        self.position += 1;
+        Some(self.state())
+    }
+
+    /// Move `steps` changes (negative is back), stopping at either end.
+    pub fn step(&mut self, steps: i64) -> &'a T {
+        let target = self.position as i64 + steps;
+        self.seek(target.max(0) as usize)
+    }
+
+    /// Move to `position`, or the current state if it is past the end.
+    pub fn seek(&mut self, position: usize) -> &'a T {
+        self.position = position.min(self.state.history.len());
+        self.state()
+    }
 }
 
 // Temporary workaround: use strings for timestamps instead of chrono
@@ -317,4 +408,28 @@ mod tests {
         assert_eq!(snapshot1.state, vec![1, 2, 3]);
         assert_eq!(snapshot2.state, vec![4, 5, 6]);
     }
+
+    #[test]
+    fn test_time_travel_steps_without_changing_state() {
+        let mut state = VersionedState::new(1);
+        state.update(2);
+        state.update(3);
+
+        let mut cursor = state.time_travel();
+        assert_eq!(cursor.positions(), 3);
+        assert!(cursor.is_live());
+        assert_eq!(cursor.back(), Some(&2));
+        assert_eq!(cursor.back(), Some(&1));
+        assert_eq!(cursor.back(), None);
+        assert_eq!(cursor.version(), 0);
+        assert_eq!(cursor.forward(), Some(&2));
+
+        assert_eq!(*cursor.step(10), 3);
+        assert!(cursor.forward().is_none());
+        assert_eq!(*cursor.step(-10), 1);
+        assert_eq!(cursor.position(), 0);
+
+        assert_eq!(*state.get(), 3);
+        assert_eq!(state.history().len(), 2);
+    }
 }
@@ -0,0 +1,589 @@
+//! JSON CRDT for merging concurrent state updates.
+//!
+//! With plain snapshots, two clients editing the same state (say, two tabs
+//! that both hot-reloaded) race, and the last write silently discards the
+//! other's changes. A [`CrdtDoc`] records every change as an operation
+//! instead, and replicas that exchange operations converge on the same
+//! state no matter what order they arrive in.
+//!
+//! The design follows Automerge:
+//!
+//! - Every operation has a unique [`OpId`]: a Lamport counter plus the
+//!   actor (replica) that made it. Higher IDs win conflicts.
+//! - Objects map keys to registers. Setting a key overwrites only the
+//!   values the writer had seen, so a concurrent set beats a delete.
+//! - Lists are RGA sequences: each element is inserted after another
+//!   element, and concurrent inserts at the same spot are ordered by ID.
+//!
+//! Replicas sync by sending a [`SyncMessage`]: their [`Clock`] and any
+//! operations the other side may lack.
+//!
+//! ```rust
+//! use morpheus_core::state::CrdtDoc;
+//! use serde_json::json;
+//!
+//! let mut server = CrdtDoc::from_value("server", &json!({ "todos": ["a"] })).unwrap();
+//! let mut tab1 = CrdtDoc::new("tab1");
+//! let mut tab2 = CrdtDoc::new("tab2");
+//! tab1.apply(server.changes_since(tab1.clock())).unwrap();
+//! tab2.apply(server.changes_since(tab2.clock())).unwrap();
+//!
+//! let ops1 = tab1.update(&json!({ "todos": ["a", "b"] })).unwrap();
+//! let ops2 = tab2.update(&json!({ "todos": ["a", "c"] })).unwrap();
+//! server.apply(ops1).unwrap();
+//! server.apply(ops2).unwrap();
+//!
+//! assert_eq!(server.value()["todos"].as_array().unwrap().len(), 3);
+//! ```
+
+use crate::errors::{MorpheusError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Highest counter seen from each actor.
+pub type Clock = BTreeMap<String, u64>;
+
+/// Unique operation ID, ordered by counter then actor.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct OpId {
+    /// Lamport counter.
+    pub counter: u64,
+
+    /// Replica that made the operation.
+    pub actor: String,
+}
+
+/// Object an operation applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjId {
+    /// The top-level object.
+    Root,
+
+    /// An object or list created by the operation with this ID.
+    Op(OpId),
+}
+
+/// Slot within an object: a key of a map or an element of a list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Key {
+    /// Map key.
+    Map(String),
+
+    /// List element, identified by the ID of the insert that created it.
+    Elem(OpId),
+}
+
+/// Value written by an operation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum OpValue {
+    /// A JSON scalar (null, bool, number or string).
+    Scalar(Value),
+
+    /// A new empty object.
+    Map,
+
+    /// A new empty list.
+    List,
+}
+
+/// What an operation does.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// Write a value to a key or element.
+    Set { key: Key, value: OpValue },
+
+    /// Insert a list element after `after` (or at the start).
+    Insert { after: Option<OpId>, value: OpValue },
+
+    /// Remove a key or element.
+    Delete { key: Key },
+}
+
+/// A single change to a document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Op {
+    /// This operation's ID.
+    pub id: OpId,
+
+    /// Object being changed.
+    pub obj: ObjId,
+
+    /// Operations this one overwrites.
+    #[serde(default)]
+    pub pred: Vec<OpId>,
+
+    /// The change itself.
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+/// Message exchanged between replicas.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncMessage {
+    /// What the sender has seen.
+    pub clock: Clock,
+
+    /// Operations the receiver may not have seen.
+    #[serde(default)]
+    pub ops: Vec<Op>,
+}
+
+/// Concurrent values of one key or element; the highest ID is visible.
+type Register = BTreeMap<OpId, OpValue>;
+
+#[derive(Debug, Clone)]
+struct Elem {
+    id: OpId,
+    value: Register,
+}
+
+#[derive(Debug, Clone)]
+enum Obj {
+    Map(BTreeMap<String, Register>),
+    List(Vec<Elem>),
+}
+
+/// A JSON document that merges concurrent edits.
+///
+/// The top level is always an object.
+#[derive(Debug, Clone)]
+pub struct CrdtDoc {
+    actor: String,
+    counter: u64,
+    clock: Clock,
+    objects: HashMap<ObjId, Obj>,
+    log: Vec<Op>,
+    /// Operations overwritten before they arrived.
+    overwritten: HashSet<OpId>,
+}
+
+fn missing(obj: &ObjId) -> MorpheusError {
+    MorpheusError::InvalidState(format!("CRDT operation refers to unknown object {:?}", obj))
+}
+
+impl CrdtDoc {
+    /// Create an empty document edited by `actor`.
+    ///
+    /// Actor names must be unique among replicas that sync with each other.
+    pub fn new(actor: impl Into<String>) -> Self {
+        let mut objects = HashMap::new();
+        objects.insert(ObjId::Root, Obj::Map(BTreeMap::new()));
+        Self {
+            actor: actor.into(),
+            counter: 0,
+            clock: Clock::new(),
+            objects,
+            log: Vec::new(),
+            overwritten: HashSet::new(),
+        }
+    }
+
+    /// Create a document holding `value`, which must be a JSON object.
+    pub fn from_value(actor: impl Into<String>, value: &Value) -> Result<Self> {
+        let mut doc = Self::new(actor);
+        doc.update(value)?;
+        Ok(doc)
+    }
+
+    /// This replica's actor name.
+    pub fn actor(&self) -> &str {
+        &self.actor
+    }
+
+    /// Highest counter seen from each actor.
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// The document's current JSON value.
+    pub fn value(&self) -> Value {
+        self.materialize(&ObjId::Root)
+    }
+
+    /// Operations not covered by `clock`, in the order they were applied.
+    pub fn changes_since(&self, clock: &Clock) -> Vec<Op> {
+        self.log
+            .iter()
+            .filter(|op| op.id.counter > clock.get(&op.id.actor).copied().unwrap_or(0))
+            .cloned()
+            .collect()
+    }
+
+    /// Reply to a peer's sync message: merge its operations and return
+    /// everything it hasn't seen.
+    pub fn sync(&mut self, message: SyncMessage) -> Result<SyncMessage> {
+        let mut their_clock = message.clock;
+        for op in &message.ops {
+            observe(&mut their_clock, &op.id);
+        }
+        self.apply(message.ops)?;
+        Ok(SyncMessage {
+            clock: self.clock.clone(),
+            ops: self.changes_since(&their_clock),
+        })
+    }
+
+    /// Merge operations from another replica, returning those that were new.
+    ///
+    /// Operations must arrive in the order their replica applied them;
+    /// ones already seen are skipped.
+    pub fn apply(&mut self, ops: Vec<Op>) -> Result<Vec<Op>> {
+        let mut applied = Vec::new();
+        for op in ops {
+            if self.apply_op(&op)? {
+                applied.push(op);
+            }
+        }
+        Ok(applied)
+    }
+
+    /// Change the document to `value`, returning the operations to send to
+    /// other replicas.
+    pub fn update(&mut self, value: &Value) -> Result<Vec<Op>> {
+        let Value::Object(fields) = value else {
+            return Err(MorpheusError::InvalidState("CRDT state must be a JSON object".to_string()));
+        };
+        let mut ops = Vec::new();
+        self.diff_map(ObjId::Root, fields, &mut ops)?;
+        Ok(ops)
+    }
+
+    fn apply_op(&mut self, op: &Op) -> Result<bool> {
+        if self.clock.get(&op.id.actor).is_some_and(|&seen| seen >= op.id.counter) {
+            return Ok(false);
+        }
+        let obj = self.objects.get_mut(&op.obj).ok_or_else(|| missing(&op.obj))?;
+        let created = match &op.action {
+            Action::Set { key, value } => {
+                let register = register(obj, key, true)?;
+                overwrite(register, &op.pred, &self.clock, &mut self.overwritten);
+                if !self.overwritten.contains(&op.id) {
+                    register.insert(op.id.clone(), value.clone());
+                }
+                Some(value)
+            }
+            Action::Delete { key } => {
+                match register(obj, key, false) {
+                    Ok(register) => overwrite(register, &op.pred, &self.clock, &mut self.overwritten),
+                    Err(_) => overwrite(&mut Register::new(), &op.pred, &self.clock, &mut self.overwritten),
+                }
+                None
+            }
+            Action::Insert { after, value } => {
+                let Obj::List(elems) = obj else {
+                    return Err(MorpheusError::InvalidState("CRDT insert into a map".to_string()));
+                };
+                let mut index = match after {
+                    Some(after) => elems
+                        .iter()
+                        .position(|e| &e.id == after)
+                        .ok_or_else(|| MorpheusError::InvalidState(format!("CRDT insert after unknown element {:?}", after)))?
+                        + 1,
+                    None => 0,
+                };
+                // Concurrent inserts at the same spot go in descending ID order
+                while index < elems.len() && elems[index].id > op.id {
+                    index += 1;
+                }
+                let mut value_register = Register::new();
+                value_register.insert(op.id.clone(), value.clone());
+                elems.insert(
+                    index,
+                    Elem {
+                        id: op.id.clone(),
+                        value: value_register,
+                    },
+                );
+                Some(value)
+            }
+        };
+        match created {
+            Some(OpValue::Map) => {
+                self.objects.insert(ObjId::Op(op.id.clone()), Obj::Map(BTreeMap::new()));
+            }
+            Some(OpValue::List) => {
+                self.objects.insert(ObjId::Op(op.id.clone()), Obj::List(Vec::new()));
+            }
+            _ => {}
+        }
+        self.counter = self.counter.max(op.id.counter);
+        observe(&mut self.clock, &op.id);
+        self.log.push(op.clone());
+        Ok(true)
+    }
+
+    /// Make and apply a local operation.
+    fn local(&mut self, obj: ObjId, pred: Vec<OpId>, action: Action, ops: &mut Vec<Op>) -> Result<OpId> {
+        let op = Op {
+            id: OpId {
+                counter: self.counter + 1,
+                actor: self.actor.clone(),
+            },
+            obj,
+            pred,
+            action,
+        };
+        self.apply_op(&op)?;
+        let id = op.id.clone();
+        ops.push(op);
+        Ok(id)
+    }
+
+    fn materialize(&self, obj: &ObjId) -> Value {
+        match self.objects.get(obj) {
+            Some(Obj::Map(keys)) => Value::Object(
+                keys.iter()
+                    .filter_map(|(key, register)| Some((key.clone(), self.resolve(register)?)))
+                    .collect(),
+            ),
+            Some(Obj::List(elems)) => Value::Array(elems.iter().filter_map(|e| self.resolve(&e.value)).collect()),
+            None => Value::Null,
+        }
+    }
+
+    fn resolve(&self, register: &Register) -> Option<Value> {
+        let (id, value) = register.last_key_value()?;
+        Some(match value {
+            OpValue::Scalar(value) => value.clone(),
+            OpValue::Map | OpValue::List => self.materialize(&ObjId::Op(id.clone())),
+        })
+    }
+
+    fn diff_map(&mut self, obj: ObjId, fields: &Map<String, Value>, ops: &mut Vec<Op>) -> Result<()> {
+        let Some(Obj::Map(keys)) = self.objects.get(&obj) else {
+            return Err(missing(&obj));
+        };
+        let current: Vec<(String, Register)> = keys
+            .iter()
+            .filter(|(_, register)| !register.is_empty())
+            .map(|(key, register)| (key.clone(), register.clone()))
+            .collect();
+
+        for (key, register) in &current {
+            if !fields.contains_key(key) {
+                let pred = register.keys().cloned().collect();
+                self.local(obj.clone(), pred, Action::Delete { key: Key::Map(key.clone()) }, ops)?;
+            }
+        }
+        for (key, value) in fields {
+            let register = current.iter().find(|(k, _)| k == key).map(|(_, r)| r.clone()).unwrap_or_default();
+            self.diff_slot(&obj, Key::Map(key.clone()), &register, value, ops)?;
+        }
+        Ok(())
+    }
+
+    fn diff_list(&mut self, obj: ObjId, items: &[Value], ops: &mut Vec<Op>) -> Result<()> {
+        let Some(Obj::List(elems)) = self.objects.get(&obj) else {
+            return Err(missing(&obj));
+        };
+        let current: Vec<(OpId, Register)> = elems
+            .iter()
+            .filter(|e| !e.value.is_empty())
+            .map(|e| (e.id.clone(), e.value.clone()))
+            .collect();
+        let values: Vec<Value> = current.iter().filter_map(|(_, r)| self.resolve(r)).collect();
+
+        let prefix = values.iter().zip(items).take_while(|(a, b)| a == b).count();
+        let suffix = values[prefix..]
+            .iter()
+            .rev()
+            .zip(items[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let old = &current[prefix..current.len() - suffix];
+        let new = &items[prefix..items.len() - suffix];
+
+        // Change elements in place, then delete or insert the rest
+        let mut last = prefix.checked_sub(1).map(|i| current[i].0.clone());
+        for ((id, register), value) in old.iter().zip(new) {
+            self.diff_slot(&obj, Key::Elem(id.clone()), register, value, ops)?;
+            last = Some(id.clone());
+        }
+        for (id, register) in old.iter().skip(new.len()) {
+            let pred = register.keys().cloned().collect();
+            self.local(obj.clone(), pred, Action::Delete { key: Key::Elem(id.clone()) }, ops)?;
+        }
+        for value in new.iter().skip(old.len()) {
+            let id = self.local(
+                obj.clone(),
+                Vec::new(),
+                Action::Insert {
+                    after: last,
+                    value: op_value(value),
+                },
+                ops,
+            )?;
+            self.fill(&id, value, ops)?;
+            last = Some(id);
+        }
+        Ok(())
+    }
+
+    /// Bring one key or element from `register` to `value`.
+    fn diff_slot(&mut self, obj: &ObjId, key: Key, register: &Register, value: &Value, ops: &mut Vec<Op>) -> Result<()> {
+        match (register.last_key_value(), value) {
+            (Some((id, OpValue::Map)), Value::Object(fields)) => self.diff_map(ObjId::Op(id.clone()), fields, ops),
+            (Some((id, OpValue::List)), Value::Array(items)) => self.diff_list(ObjId::Op(id.clone()), items, ops),
+            (Some((_, OpValue::Scalar(current))), _) if current == value && register.len() == 1 => Ok(()),
+            _ => {
+                let pred = register.keys().cloned().collect();
+                let action = Action::Set {
+                    key,
+                    value: op_value(value),
+                };
+                let id = self.local(obj.clone(), pred, action, ops)?;
+                self.fill(&id, value, ops)
+            }
+        }
+    }
+
+    /// Populate a container just created by operation `id`.
+    fn fill(&mut self, id: &OpId, value: &Value, ops: &mut Vec<Op>) -> Result<()> {
+        match value {
+            Value::Object(fields) => self.diff_map(ObjId::Op(id.clone()), fields, ops),
+            Value::Array(items) => self.diff_list(ObjId::Op(id.clone()), items, ops),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn op_value(value: &Value) -> OpValue {
+    match value {
+        Value::Object(_) => OpValue::Map,
+        Value::Array(_) => OpValue::List,
+        scalar => OpValue::Scalar(scalar.clone()),
+    }
+}
+
+fn observe(clock: &mut Clock, id: &OpId) {
+    let seen = clock.entry(id.actor.clone()).or_default();
+    *seen = (*seen).max(id.counter);
+}
+
+/// Remove `pred` from a register, remembering any that haven't arrived yet.
+fn overwrite(register: &mut Register, pred: &[OpId], clock: &Clock, overwritten: &mut HashSet<OpId>) {
+    for id in pred {
+        let seen = clock.get(&id.actor).is_some_and(|&counter| counter >= id.counter);
+        if register.remove(id).is_none() && !seen {
+            overwritten.insert(id.clone());
+        }
+    }
+}
+
+fn register<'a>(obj: &'a mut Obj, key: &Key, create: bool) -> Result<&'a mut Register> {
+    match (obj, key) {
+        (Obj::Map(keys), Key::Map(name)) => {
+            if create {
+                Ok(keys.entry(name.clone()).or_default())
+            } else {
+                keys.get_mut(name)
+                    .ok_or_else(|| MorpheusError::InvalidState(format!("CRDT key '{}' not found", name)))
+            }
+        }
+        (Obj::List(elems), Key::Elem(id)) => elems
+            .iter_mut()
+            .find(|e| &e.id == id)
+            .map(|e| &mut e.value)
+            .ok_or_else(|| MorpheusError::InvalidState(format!("CRDT element {:?} not found", id))),
+        _ => Err(MorpheusError::InvalidState("CRDT key doesn't match object type".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Two replicas that start from `initial` via `server`.
+    fn replicas(initial: Value) -> (CrdtDoc, CrdtDoc, CrdtDoc) {
+        let server = CrdtDoc::from_value("server", &initial).unwrap();
+        let mut a = CrdtDoc::new("a");
+        let mut b = CrdtDoc::new("b");
+        a.apply(server.changes_since(&Clock::new())).unwrap();
+        b.apply(server.changes_since(&Clock::new())).unwrap();
+        (server, a, b)
+    }
+
+    #[test]
+    fn test_round_trips_json() {
+        let value = json!({ "n": 1, "user": { "name": "Ada", "tags": ["x", { "y": null }] } });
+        let doc = CrdtDoc::from_value("a", &value).unwrap();
+
+        assert_eq!(doc.value(), value);
+        assert!(CrdtDoc::from_value("a", &json!([1])).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_edits_to_different_keys_both_survive() {
+        let (mut server, mut a, mut b) = replicas(json!({ "count": 0, "theme": "light" }));
+
+        let ops_a = a.update(&json!({ "count": 1, "theme": "light" })).unwrap();
+        let ops_b = b.update(&json!({ "count": 0, "theme": "dark" })).unwrap();
+        server.apply(ops_a).unwrap();
+        server.apply(ops_b).unwrap();
+
+        assert_eq!(server.value(), json!({ "count": 1, "theme": "dark" }));
+    }
+
+    #[test]
+    fn test_concurrent_list_appends_both_survive_and_converge() {
+        let (mut server, mut a, mut b) = replicas(json!({ "todos": ["milk"] }));
+
+        let ops_a = a.update(&json!({ "todos": ["milk", "eggs"] })).unwrap();
+        let ops_b = b.update(&json!({ "todos": ["milk", "bread"] })).unwrap();
+        a.apply(ops_b.clone()).unwrap();
+        b.apply(ops_a.clone()).unwrap();
+        server.apply(ops_b).unwrap();
+        server.apply(ops_a).unwrap();
+
+        let merged = server.value();
+        assert_eq!(merged["todos"].as_array().unwrap().len(), 3);
+        assert_eq!(a.value(), merged);
+        assert_eq!(b.value(), merged);
+    }
+
+    #[test]
+    fn test_concurrent_set_beats_delete() {
+        let (mut server, mut a, mut b) = replicas(json!({ "draft": "hi" }));
+
+        let ops_a = a.update(&json!({})).unwrap();
+        let ops_b = b.update(&json!({ "draft": "hello" })).unwrap();
+        server.apply(ops_a).unwrap();
+        server.apply(ops_b).unwrap();
+
+        assert_eq!(server.value(), json!({ "draft": "hello" }));
+    }
+
+    #[test]
+    fn test_sync_sends_only_missing_ops_and_is_idempotent() {
+        let (mut server, mut a, _) = replicas(json!({ "n": 0 }));
+        let ops = a.update(&json!({ "n": 1 })).unwrap();
+
+        let reply = server
+            .sync(SyncMessage {
+                clock: a.clock().clone(),
+                ops: ops.clone(),
+            })
+            .unwrap();
+
+        assert!(reply.ops.is_empty());
+        assert!(server.apply(ops).unwrap().is_empty());
+        assert_eq!(server.value(), json!({ "n": 1 }));
+    }
+
+    #[test]
+    fn test_ops_serialize() {
+        let mut doc = CrdtDoc::new("a");
+        let ops = doc.update(&json!({ "list": [1] })).unwrap();
+
+        let json = serde_json::to_string(&ops).unwrap();
+        let parsed: Vec<Op> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed, ops);
+    }
+}
@@ -0,0 +1,249 @@
+//! The state-preservation contract, derived rather than hand-written.
+//!
+//! A component keeps its state across hot reloads and rollbacks by handing
+//! the host a snapshot and restoring from it. The code on each side of that
+//! hand-off may be different versions of the component: fields get added,
+//! removed or retyped between them. [`MorpheusState`] (usually derived with
+//! `#[derive(MorpheusState)]`) snapshots state with its version, and
+//! restores any snapshot it is given:
+//!
+//! - snapshots from an earlier version go through
+//!   [`migrate`](MorpheusState::migrate) first
+//! - fields the snapshot lacks, or whose value no longer fits, get their
+//!   default
+//! - fields the state no longer has are dropped
+//!
+//! ```rust
+//! use morpheus_core::state::MorpheusState;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Default, Serialize, Deserialize, MorpheusState)]
+//! #[morpheus(version = 2)]
+//! struct Counter {
+//!     count: i64,
+//!     step: i64,
+//! }
+//!
+//! // Written by a version that had no `step` and a `label`
+//! let counter = Counter::restore(r#"{"version": 1, "state": {"count": 5, "label": "Clicks"}}"#).unwrap();
+//! assert_eq!((counter.count, counter.step), (5, 0));
+//!
+//! let snapshot = counter.snapshot().unwrap();
+//! assert_eq!(snapshot, r#"{"version":2,"state":{"count":5,"step":0}}"#);
+//! ```
+
+use crate::errors::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// State that snapshots with its version and restores from any snapshot.
+///
+/// Derive it with `#[derive(MorpheusState)]`; see the
+/// [module docs](self) for what restoring does.
+pub trait MorpheusState: Serialize + DeserializeOwned + Default {
+    /// Version of the state's shape.
+    const VERSION: u32;
+
+    /// Serialized field names and their Rust types, in declaration order.
+    const FIELDS: &'static [(&'static str, &'static str)];
+
+    /// Upgrade `state`, written by version `from`, to [`VERSION`](Self::VERSION).
+    ///
+    /// Called only for earlier versions; snapshots without a version (from
+    /// before the component used this trait) are version 0. Fields that
+    /// still don't fit afterwards get their default.
+    fn migrate(from: u32, state: Value) -> Result<Value> {
+        let _ = from;
+        Ok(state)
+    }
+
+    /// Snapshot the state, with its version, as JSON.
+    fn snapshot(&self) -> Result<String> {
+        Ok(serde_json::to_string(&Snapshot {
+            version: Self::VERSION,
+            state: serde_json::to_value(self)?,
+        })?)
+    }
+
+    /// Restore state from a snapshot taken by any version of the component.
+    ///
+    /// Fails only on malformed JSON or a failed migration.
+    fn restore(json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json)?;
+        let (version, state) = match serde_json::from_value::<Snapshot>(value.clone()) {
+            Ok(snapshot) => (snapshot.version, snapshot.state),
+            Err(_) => (0, value),
+        };
+        let state = if version < Self::VERSION {
+            Self::migrate(version, state)?
+        } else {
+            state
+        };
+
+        let mut fields = match serde_json::to_value(Self::default())? {
+            Value::Object(fields) => fields,
+            _ => Map::new(),
+        };
+        if let Value::Object(stored) = state {
+            for (name, value) in stored {
+                let Some(default) = fields.insert(name.clone(), value) else {
+                    fields.remove(&name);
+                    continue;
+                };
+                if serde_json::from_value::<Self>(Value::Object(fields.clone())).is_err() {
+                    fields.insert(name, default);
+                }
+            }
+        }
+        Ok(serde_json::from_value(Value::Object(fields))?)
+    }
+
+    /// The state's shape, e.g. for a component's `__morpheus_describe`.
+    fn schema() -> Result<StateSchema> {
+        Ok(StateSchema {
+            version: Self::VERSION,
+            fields: Self::FIELDS
+                .iter()
+                .map(|(name, ty)| (name.to_string(), ty.to_string()))
+                .collect(),
+            default: serde_json::to_value(Self::default())?,
+        })
+    }
+}
+
+/// A snapshot as [`MorpheusState::snapshot`] writes it.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Snapshot {
+    version: u32,
+    state: Value,
+}
+
+/// Shape of a [`MorpheusState`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateSchema {
+    pub version: u32,
+    /// Field names and their Rust types, in declaration order
+    pub fields: Vec<(String, String)>,
+    /// The default state
+    pub default: Value,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::MorpheusError;
+    use crate::state::MorpheusState;
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize, MorpheusState)]
+    #[morpheus(version = 3, migrate = upgrade)]
+    struct Todos {
+        items: Vec<String>,
+        #[serde(rename = "showDone")]
+        show_done: bool,
+        #[serde(skip)]
+        dragging: Option<usize>,
+    }
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize, MorpheusState)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
+    struct Settings {
+        dark_mode: bool,
+        font_size_px: u8,
+        #[serde(rename = "lang")]
+        locale: String,
+        r#type: String,
+    }
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize, MorpheusState)]
+    #[serde(rename_all(serialize = "SCREAMING-KEBAB-CASE", deserialize = "snake_case"))]
+    struct Filters {
+        #[serde(rename(serialize = "q", deserialize = "query"))]
+        search_query: String,
+        #[serde(rename(deserialize = "done"))]
+        show_done: bool,
+    }
+
+    /// The derived field names, and the keys serde writes
+    fn field_names<T: MorpheusState>() -> (Vec<&'static str>, Vec<String>) {
+        let names = T::FIELDS.iter().map(|(name, _)| *name).collect();
+        let written = serde_json::to_value(T::default()).unwrap();
+        (names, written.as_object().unwrap().keys().cloned().collect())
+    }
+
+    /// Version 1 kept a comma-separated string; version 2 a list
+    fn upgrade(from: u32, mut state: Value) -> Result<Value> {
+        if from == 1 {
+            let items = state["items"].as_str().unwrap_or_default();
+            state["items"] = items.split(',').collect();
+        }
+        if from == 0 {
+            return Err(MorpheusError::InvalidState("too old".to_string()));
+        }
+        Ok(state)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let todos = Todos {
+            items: vec!["milk".to_string()],
+            show_done: true,
+            dragging: Some(0),
+        };
+        let restored = Todos::restore(&todos.snapshot().unwrap()).unwrap();
+        assert_eq!(restored, Todos { dragging: None, ..todos });
+    }
+
+    #[test]
+    fn test_migrates_earlier_versions() {
+        let todos = Todos::restore(r#"{"version": 1, "state": {"items": "milk,eggs"}}"#).unwrap();
+        assert_eq!(todos.items, ["milk", "eggs"]);
+
+        // No version: written before the component adopted the trait
+        assert!(Todos::restore(r#"{"items": []}"#).is_err());
+    }
+
+    #[test]
+    fn test_fields_that_no_longer_fit_get_defaults() {
+        let json = r#"{"version": 3, "state": {"items": ["milk"], "showDone": "yes", "filter": "all"}}"#;
+        let todos = Todos::restore(json).unwrap();
+
+        assert_eq!(todos.items, ["milk"]);
+        assert!(!todos.show_done);
+        assert!(Todos::restore("not json").is_err());
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema = Todos::schema().unwrap();
+
+        assert_eq!(schema.version, 3);
+        assert_eq!(
+            schema.fields,
+            [
+                ("items".to_string(), "Vec<String>".to_string()),
+                ("showDone".to_string(), "bool".to_string()),
+            ]
+        );
+        assert_eq!(schema.default, serde_json::json!({"items": [], "showDone": false}));
+    }
+
+    #[test]
+    fn test_fields_follow_the_containers_rename_all() {
+        let (names, mut written) = field_names::<Settings>();
+
+        assert_eq!(names, ["darkMode", "fontSizePx", "lang", "type"]);
+        written.sort();
+        assert_eq!(written, ["darkMode", "fontSizePx", "lang", "type"]);
+    }
+
+    #[test]
+    fn test_fields_use_the_serialize_side_of_renames() {
+        let (names, mut written) = field_names::<Filters>();
+
+        assert_eq!(names, ["q", "SHOW-DONE"]);
+        written.sort();
+        assert_eq!(written, ["SHOW-DONE", "q"]);
+    }
+}
@@ -0,0 +1,154 @@
+//! Execution statistics: how much each component is used, and how fast.
+//!
+//! Generated components pile up, and without numbers nobody knows which
+//! ones are still called or which export makes a page slow. The host times
+//! every call into a component's exports and reports it as a
+//! [`CallSample`]; the registry folds the samples into the component's
+//! [`ExecutionStats`], which are part of its metadata: calls and traps per
+//! export, call time (average, 95th percentile and maximum over the latest
+//! [`SAMPLES_KEPT`] calls) and when the component was last used.
+//!
+//! ```rust
+//! use morpheus_core::stats::ExecutionStats;
+//!
+//! let mut stats = ExecutionStats::default();
+//! stats.record("render", 4.0, false, "2026-01-01T10:00:00Z").unwrap();
+//! stats.record("on_click", 1.0, true, "2026-01-01T10:00:05Z").unwrap();
+//!
+//! assert_eq!((stats.calls, stats.traps), (2, 1));
+//! assert_eq!(stats.exports["render"].timing.average_ms, 4.0);
+//! assert!(!stats.is_unused_since("2026-01-01T09:00:00Z"));
+//! assert_eq!(stats.slowest_export(), Some("render"));
+//! ```
+
+use crate::errors::{MorpheusError, Result};
+use crate::profiler::Timing;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Call times kept per export.
+pub const SAMPLES_KEPT: usize = 200;
+
+/// One call into a component's export, as timed by the host.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CallSample {
+    /// Component that was called.
+    pub component: String,
+
+    /// Export that was called, e.g. `render` or `on_click`.
+    pub export: String,
+
+    /// Time the call took, in milliseconds.
+    pub duration_ms: f64,
+
+    /// Whether the call trapped (panicked or hit `unreachable`).
+    #[serde(default)]
+    pub trapped: bool,
+}
+
+/// How a component has been used since it was loaded.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ExecutionStats {
+    /// Calls into any export.
+    pub calls: u64,
+
+    /// Calls that trapped.
+    pub traps: u64,
+
+    /// When an export was last called (UTC timestamp).
+    #[serde(default)]
+    pub last_used: Option<String>,
+
+    /// Statistics per export, by name.
+    #[serde(default)]
+    pub exports: BTreeMap<String, ExportStats>,
+}
+
+/// How one export has been called.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ExportStats {
+    pub calls: u64,
+    pub traps: u64,
+
+    /// Call time, over the kept samples.
+    pub timing: Timing,
+
+    /// Latest call times, oldest first.
+    #[serde(skip)]
+    #[cfg_attr(feature = "schema", schemars(skip))]
+    recent: VecDeque<f64>,
+}
+
+impl ExecutionStats {
+    /// Record a call of `export` that took `duration_ms` and ended at `at`,
+    /// a UTC timestamp.
+    pub fn record(&mut self, export: &str, duration_ms: f64, trapped: bool, at: impl Into<String>) -> Result<()> {
+        if !duration_ms.is_finite() || duration_ms < 0.0 {
+            return Err(MorpheusError::InvalidState(format!(
+                "Call time must be non-negative, got {} ms",
+                duration_ms
+            )));
+        }
+        let export = self.exports.entry(export.to_string()).or_default();
+        if export.recent.len() == SAMPLES_KEPT {
+            export.recent.pop_front();
+        }
+        export.recent.push_back(duration_ms);
+        export.timing = Timing::of(export.recent.iter().copied().collect());
+        export.calls += 1;
+        self.calls += 1;
+        if trapped {
+            export.traps += 1;
+            self.traps += 1;
+        }
+        self.last_used = Some(at.into());
+        Ok(())
+    }
+
+    /// Whether nothing has been called since `since`, a UTC timestamp in
+    /// the same format as `last_used`.
+    pub fn is_unused_since(&self, since: &str) -> bool {
+        self.last_used.as_deref().is_none_or(|last| last < since)
+    }
+
+    /// The export with the highest 95th percentile call time.
+    pub fn slowest_export(&self) -> Option<&str> {
+        self.exports
+            .iter()
+            .max_by(|a, b| a.1.timing.p95_ms.total_cmp(&b.1.timing.p95_ms))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_keep_recent_timings_and_every_count() {
+        let mut stats = ExecutionStats::default();
+        assert!(stats.is_unused_since("2026-01-01T00:00:00Z"));
+        assert_eq!(stats.slowest_export(), None);
+
+        for ms in 0..SAMPLES_KEPT + 10 {
+            stats.record("render", ms as f64, false, "2026-01-02T00:00:00Z").unwrap();
+        }
+        stats.record("on_click", 500.0, true, "2026-01-03T00:00:00Z").unwrap();
+        assert!(stats.record("on_click", f64::NAN, false, "2026-01-03T00:00:00Z").is_err());
+
+        let render = &stats.exports["render"];
+        assert_eq!(render.calls, SAMPLES_KEPT as u64 + 10);
+        assert_eq!(render.timing.max_ms, (SAMPLES_KEPT + 9) as f64);
+        assert_eq!(render.timing.average_ms, 109.5);
+        assert_eq!((stats.calls, stats.traps), (SAMPLES_KEPT as u64 + 11, 1));
+        assert_eq!(stats.slowest_export(), Some("on_click"));
+        assert!(!stats.is_unused_since("2026-01-03T00:00:00Z"));
+        assert!(stats.is_unused_since("2026-01-04T00:00:00Z"));
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert!(json["exports"]["render"].get("recent").is_none());
+    }
+}
@@ -0,0 +1,438 @@
+//! Pluggable storage for state snapshots and WASM blobs.
+//!
+//! State history and component versions live in memory by default and are
+//! lost when the process exits. A [`SnapshotStore`] persists them somewhere
+//! durable: the local filesystem, S3-compatible object storage, or the
+//! browser's IndexedDB (backends live in `morpheus-runtime`).
+//!
+//! Stores are flat key/value blob stores, like object storage. Keys are
+//! `/`-separated paths, conventionally prefixed by kind:
+//! [`STATE_PREFIX`] for state snapshots and [`WASM_PREFIX`] for modules.
+//!
+//! State snapshots pass through a [`SnapshotCodec`], which serializes them
+//! (JSON by default, or a binary [`Format`]), enforces a size limit and
+//! zstd-compresses them on the way into the store.
+
+use crate::codec::Format;
+use crate::errors::{MorpheusError, Result};
+use crate::state::VersionedState;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+
+/// Key prefix for state snapshots.
+pub const STATE_PREFIX: &str = "state/";
+
+/// Key prefix for WASM modules.
+pub const WASM_PREFIX: &str = "wasm/";
+
+/// Default limit on a snapshot's serialized size (1 MiB).
+pub const DEFAULT_MAX_SNAPSHOT_SIZE: usize = 1024 * 1024;
+
+/// Leading bytes of a zstd frame.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Leading byte of a snapshot in a binary format, followed by a byte naming
+/// the format. No JSON text starts with it, so JSON snapshots need no header.
+const FORMAT_TAG: u8 = 0;
+
+/// Stores must be shareable across threads, except in the browser where
+/// everything runs on one thread and JS handles aren't `Send`.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send + Sync {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + Sync> MaybeSend for T {}
+
+/// Stores must be shareable across threads, except in the browser where
+/// everything runs on one thread and JS handles aren't `Send`.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSend for T {}
+
+/// Durable storage for snapshots and modules.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait SnapshotStore: MaybeSend {
+    /// Store `bytes` under `key`, replacing any existing value.
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<()>;
+
+    /// Fetch the value under `key`, or `None` if there is none.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// List keys starting with `prefix`, in lexicographic order.
+    async fn list(&self, prefix: &str) -> Result<Vec<String>>;
+
+    /// Delete the value under `key`. Returns whether it existed.
+    async fn delete(&self, key: &str) -> Result<bool>;
+}
+
+/// Shared stores are stores, so wrappers can take `Arc<dyn SnapshotStore>`.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<S: SnapshotStore + ?Sized> SnapshotStore for Arc<S> {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+        (**self).put(key, bytes).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        (**self).get(key).await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        (**self).list(prefix).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool> {
+        (**self).delete(key).await
+    }
+}
+
+/// Key of the state snapshot named `name`.
+pub fn state_key(name: &str) -> String {
+    format!("{}{}.json", STATE_PREFIX, name)
+}
+
+/// Key of the WASM module named `name`.
+pub fn wasm_key(name: &str) -> String {
+    format!("{}{}.wasm", WASM_PREFIX, name)
+}
+
+/// Reject keys that could escape a store's namespace.
+///
+/// Backends map keys onto paths and URLs, so empty, absolute and `..`
+/// segments are refused everywhere.
+pub fn validate_key(key: &str) -> Result<()> {
+    if key.is_empty()
+        || key.starts_with('/')
+        || key.split('/').any(|segment| segment.is_empty() || segment == "." || segment == "..")
+    {
+        return Err(MorpheusError::InvalidState(format!("Invalid store key '{}'", key)));
+    }
+    Ok(())
+}
+
+/// Format, size limit and compression applied to stored snapshots.
+///
+/// Decoding accepts snapshots in any format, compressed or not, so both
+/// settings can be changed for an existing store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotCodec {
+    /// Largest serialized snapshot accepted, in bytes (`None` for no limit).
+    pub max_size: Option<usize>,
+
+    /// Whether snapshots are zstd-compressed when stored.
+    pub compress: bool,
+
+    /// Format new snapshots are serialized in.
+    pub format: Format,
+}
+
+impl Default for SnapshotCodec {
+    fn default() -> Self {
+        Self {
+            max_size: Some(DEFAULT_MAX_SNAPSHOT_SIZE),
+            compress: true,
+            format: Format::Json,
+        }
+    }
+}
+
+impl SnapshotCodec {
+    /// Compress, with the default size limit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the size limit.
+    pub fn with_max_size(mut self, max_size: Option<usize>) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Enable or disable compression.
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Set the format new snapshots are serialized in.
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Fail with [`MorpheusError::SnapshotTooLarge`] if `size` is over the limit.
+    pub fn check_size(&self, size: usize) -> Result<()> {
+        match self.max_size {
+            Some(limit) if size > limit => Err(MorpheusError::SnapshotTooLarge { size, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check a serialized snapshot against the limit and compress it.
+    pub fn encode(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        self.check_size(bytes.len())?;
+        if !self.compress {
+            return Ok(bytes.to_vec());
+        }
+        Ok(ruzstd::encoding::compress_to_vec(
+            bytes,
+            ruzstd::encoding::CompressionLevel::Fastest,
+        ))
+    }
+
+    /// Reverse [`SnapshotCodec::encode`].
+    ///
+    /// Decompression stops at the size limit, so a small compressed blob
+    /// can't expand into an unbounded allocation.
+    pub fn decode(&self, bytes: Vec<u8>) -> Result<Vec<u8>> {
+        if !bytes.starts_with(&ZSTD_MAGIC) {
+            self.check_size(bytes.len())?;
+            return Ok(bytes);
+        }
+        let decoder = ruzstd::decoding::StreamingDecoder::new(bytes.as_slice())
+            .map_err(|e| MorpheusError::Other(format!("Invalid compressed snapshot: {}", e)))?;
+        let mut decoded = Vec::new();
+        let cap = self.max_size.map_or(u64::MAX, |limit| limit as u64 + 1);
+        decoder
+            .take(cap)
+            .read_to_end(&mut decoded)
+            .map_err(|e| MorpheusError::Other(format!("Invalid compressed snapshot: {}", e)))?;
+        self.check_size(decoded.len())?;
+        Ok(decoded)
+    }
+
+    /// Serialize `value` in the codec's format, then [`encode`](Self::encode) it.
+    pub fn serialize<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        if self.format.is_binary() {
+            bytes.extend([FORMAT_TAG, format_id(self.format)]);
+        }
+        bytes.extend(self.format.to_vec(value)?);
+        self.encode(&bytes)
+    }
+
+    /// Reverse [`SnapshotCodec::serialize`], whatever format the snapshot
+    /// was written in.
+    pub fn deserialize<T: DeserializeOwned>(&self, bytes: Vec<u8>) -> Result<T> {
+        let bytes = self.decode(bytes)?;
+        match bytes.as_slice() {
+            [FORMAT_TAG, id, body @ ..] => Format::ALL
+                .into_iter()
+                .find(|&f| f.is_binary() && format_id(f) == *id)
+                .ok_or_else(|| MorpheusError::Other(format!("Unknown snapshot format {}", id)))?
+                .from_slice(body),
+            _ => Format::Json.from_slice(&bytes),
+        }
+    }
+}
+
+/// Byte identifying a binary format in a snapshot header.
+fn format_id(format: Format) -> u8 {
+    match format {
+        Format::Json => 0,
+        Format::MessagePack => 1,
+        Format::Cbor => 2,
+    }
+}
+
+/// Store that keeps everything in memory.
+///
+/// Useful for tests and as a stand-in when no durable store is configured.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    blobs: Mutex<BTreeMap<String, Vec<u8>>>,
+}
+
+impl MemoryStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl SnapshotStore for MemoryStore {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+        validate_key(key)?;
+        self.blobs.lock().unwrap().insert(key.to_string(), bytes);
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        validate_key(key)?;
+        Ok(self.blobs.lock().unwrap().get(key).cloned())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        Ok(self
+            .blobs
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool> {
+        validate_key(key)?;
+        Ok(self.blobs.lock().unwrap().remove(key).is_some())
+    }
+}
+
+impl<T: Clone + Serialize + DeserializeOwned> VersionedState<T> {
+    /// Persist the state and its history under [`state_key`]`(name)`,
+    /// with the default [`SnapshotCodec`].
+    pub async fn save(&self, store: &dyn SnapshotStore, name: &str) -> Result<()> {
+        self.save_with(store, name, &SnapshotCodec::default()).await
+    }
+
+    /// Persist the state and its history, encoded with `codec`.
+    pub async fn save_with(&self, store: &dyn SnapshotStore, name: &str, codec: &SnapshotCodec) -> Result<()> {
+        store.put(&state_key(name), codec.serialize(self)?).await
+    }
+
+    /// Load state previously saved with [`VersionedState::save`].
+    pub async fn load(store: &dyn SnapshotStore, name: &str) -> Result<Option<Self>> {
+        Self::load_with(store, name, &SnapshotCodec::default()).await
+    }
+
+    /// Load state previously saved with [`VersionedState::save_with`].
+    pub async fn load_with(store: &dyn SnapshotStore, name: &str, codec: &SnapshotCodec) -> Result<Option<Self>> {
+        match store.get(&state_key(name)).await? {
+            Some(bytes) => Ok(Some(codec.deserialize(bytes)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        // MemoryStore never awaits anything, so a single poll completes it
+        let waker = std::task::Waker::noop();
+        let mut context = std::task::Context::from_waker(waker);
+        let mut future = std::pin::pin!(future);
+        match future.as_mut().poll(&mut context) {
+            std::task::Poll::Ready(output) => output,
+            std::task::Poll::Pending => panic!("MemoryStore future was pending"),
+        }
+    }
+
+    #[test]
+    fn test_put_get_delete() {
+        let store = MemoryStore::new();
+
+        block_on(store.put(&wasm_key("v1"), vec![0, 97, 115, 109])).unwrap();
+
+        assert_eq!(block_on(store.get("wasm/v1.wasm")).unwrap(), Some(vec![0, 97, 115, 109]));
+        assert!(block_on(store.delete("wasm/v1.wasm")).unwrap());
+        assert!(!block_on(store.delete("wasm/v1.wasm")).unwrap());
+        assert_eq!(block_on(store.get("wasm/v1.wasm")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_list_by_prefix() {
+        let store = MemoryStore::new();
+        for key in [state_key("b"), wasm_key("a"), state_key("a")] {
+            block_on(store.put(&key, Vec::new())).unwrap();
+        }
+
+        assert_eq!(
+            block_on(store.list(STATE_PREFIX)).unwrap(),
+            vec!["state/a.json", "state/b.json"]
+        );
+    }
+
+    #[test]
+    fn test_rejects_escaping_keys() {
+        let store = MemoryStore::new();
+
+        for key in ["", "/abs", "state/../secret", "state//x", "./x"] {
+            assert!(block_on(store.put(key, Vec::new())).is_err(), "accepted {:?}", key);
+        }
+    }
+
+    #[test]
+    fn test_versioned_state_round_trip() {
+        let store = MemoryStore::new();
+        let mut state = VersionedState::new(1);
+        state.update(2);
+
+        block_on(state.save(&store, "counter")).unwrap();
+        let loaded = block_on(VersionedState::<i32>::load(&store, "counter")).unwrap().unwrap();
+
+        assert_eq!(*loaded.get(), 2);
+        assert_eq!(loaded.version(), 1);
+        assert_eq!(loaded.history().len(), 1);
+        assert!(block_on(VersionedState::<i32>::load(&store, "missing")).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_snapshots_are_compressed() {
+        let store = MemoryStore::new();
+        let state = VersionedState::new(vec!["the same todo item".to_string(); 200]);
+
+        block_on(state.save(&store, "todos")).unwrap();
+
+        let stored = block_on(store.get(&state_key("todos"))).unwrap().unwrap();
+        assert!(stored.starts_with(&ZSTD_MAGIC));
+        assert!(stored.len() < serde_json::to_vec(&state).unwrap().len() / 10);
+        let loaded = block_on(VersionedState::<Vec<String>>::load(&store, "todos")).unwrap().unwrap();
+        assert_eq!(loaded.get().len(), 200);
+    }
+
+    #[test]
+    fn test_uncompressed_snapshots_still_load() {
+        let store = MemoryStore::new();
+        let state = VersionedState::new(5);
+
+        block_on(state.save_with(&store, "n", &SnapshotCodec::new().with_compression(false))).unwrap();
+
+        assert_eq!(*block_on(VersionedState::<i32>::load(&store, "n")).unwrap().unwrap().get(), 5);
+    }
+
+    #[test]
+    fn test_binary_formats() {
+        let store = MemoryStore::new();
+        let mut state = VersionedState::new(serde_json::json!({ "todos": ["a"] }));
+        state.update(serde_json::json!({ "todos": ["a", "b"] }));
+
+        for format in [Format::MessagePack, Format::Cbor] {
+            let codec = SnapshotCodec::new().with_compression(false).with_format(format);
+            block_on(state.save_with(&store, "todos", &codec)).unwrap();
+
+            let stored = block_on(store.get(&state_key("todos"))).unwrap().unwrap();
+            assert_eq!(stored[0], FORMAT_TAG);
+            // Loading doesn't depend on the configured format
+            let loaded = block_on(VersionedState::<serde_json::Value>::load(&store, "todos")).unwrap().unwrap();
+            assert_eq!(loaded.get(), state.get());
+            assert_eq!(loaded.history().len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let codec = SnapshotCodec::new().with_max_size(Some(100));
+
+        match codec.encode(&[b'x'; 101]) {
+            Err(MorpheusError::SnapshotTooLarge { size, limit }) => assert_eq!((size, limit), (101, 100)),
+            other => panic!("expected SnapshotTooLarge, got {:?}", other),
+        }
+
+        // A blob compressed without a limit can't be inflated past one
+        let compressed = SnapshotCodec::new().with_max_size(None).encode(&[b'x'; 10_000]).unwrap();
+        assert!(matches!(
+            codec.decode(compressed),
+            Err(MorpheusError::SnapshotTooLarge { size: 101, limit: 100 })
+        ));
+    }
+}
@@ -0,0 +1,409 @@
+//! Design tokens shared by the host and its components.
+//!
+//! A component that hard-codes `#1f2937` has to be regenerated to change
+//! it, so "add dark mode" would mean regenerating every component. Instead,
+//! components style themselves with the tokens of a [`Theme`] (colors,
+//! spacing and typography), as CSS variables the host sets on the page
+//! (`var(--morpheus-color-background)`, see [`css_variable`]) or, where CSS
+//! can't reach, through the [`THEME_TOKEN_IMPORT`] host import. Switching
+//! themes with [`Themes::set_theme`] changes the variables, and every
+//! mounted component follows without re-rendering.
+//!
+//! Until a theme is chosen, hosts follow the OS: the [`SYSTEM_THEME`] is
+//! the `light` or `dark` theme, whichever matches the user's
+//! [`ColorScheme`] (see [`crate::media`]).
+//!
+//! ```rust
+//! use morpheus_core::media::ColorScheme;
+//! use morpheus_core::theme::{self, Theme, Themes};
+//!
+//! let mut themes = Themes::new();
+//! assert_eq!(themes.active_name(), "system");
+//! assert_eq!(themes.for_scheme(ColorScheme::Dark).name, "dark");
+//! assert_eq!(theme::css_variable("color.background"), "--morpheus-color-background");
+//!
+//! themes.set_theme("dark").unwrap();
+//! assert_eq!(themes.for_scheme(ColorScheme::Light).name, "dark");
+//! assert_eq!(themes.active().token("color.background"), Some("#0f172a"));
+//! assert!(themes.active().css(":root").starts_with(":root {\n  --morpheus-color-accent: "));
+//!
+//! // Every theme defines the tokens components rely on
+//! assert!(themes.register(Theme::new("sepia").with_color("background", "#f4ecd8")).is_err());
+//! ```
+//!
+//! Generated components declare the import themselves:
+//!
+//! ```rust,ignore
+//! #[wasm_bindgen]
+//! extern "C" {
+//!     #[wasm_bindgen(js_namespace = morpheus, js_name = themeToken)]
+//!     fn theme_token(name: &str) -> String;
+//! }
+//! ```
+
+use crate::errors::{MorpheusError, Result};
+use crate::media::ColorScheme;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Name of the theme token import, called as `morpheus.themeToken(name)`;
+/// returns the token's value in the active theme, or `""`.
+pub const THEME_TOKEN_IMPORT: &str = "themeToken";
+
+/// Prefix of the CSS variables tokens are set as.
+pub const CSS_VARIABLE_PREFIX: &str = "--morpheus-";
+
+/// The theme used where there is no color scheme preference.
+pub const DEFAULT_THEME: &str = "light";
+
+/// Name to set to follow the user's color scheme: the theme named after it
+/// (`light` or `dark`), where there is one.
+pub const SYSTEM_THEME: &str = "system";
+
+/// A named set of design tokens.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Theme {
+    /// Theme name, e.g. `dark`.
+    pub name: String,
+
+    /// CSS colors by role, e.g. `"primary": "#4f46e5"`.
+    #[serde(default)]
+    pub colors: BTreeMap<String, String>,
+
+    /// CSS lengths by size, e.g. `"md": "1rem"`.
+    #[serde(default)]
+    pub spacing: BTreeMap<String, String>,
+
+    /// CSS `font` shorthands by use, e.g. `"body": "400 1rem/1.5 system-ui, sans-serif"`.
+    #[serde(default)]
+    pub typography: BTreeMap<String, String>,
+}
+
+impl Theme {
+    /// Create a theme with no tokens.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Set a color token.
+    pub fn with_color(mut self, role: impl Into<String>, value: impl Into<String>) -> Self {
+        self.colors.insert(role.into(), value.into());
+        self
+    }
+
+    /// Set a spacing token.
+    pub fn with_spacing(mut self, size: impl Into<String>, value: impl Into<String>) -> Self {
+        self.spacing.insert(size.into(), value.into());
+        self
+    }
+
+    /// Set a typography token.
+    pub fn with_typography(mut self, usage: impl Into<String>, value: impl Into<String>) -> Self {
+        self.typography.insert(usage.into(), value.into());
+        self
+    }
+
+    /// The built-in light theme.
+    pub fn light() -> Self {
+        Self::new("light")
+            .with_color("background", "#ffffff")
+            .with_color("surface", "#f8fafc")
+            .with_color("text", "#111827")
+            .with_color("muted", "#6b7280")
+            .with_color("border", "#e5e7eb")
+            .with_color("primary", "#4f46e5")
+            .with_color("on-primary", "#ffffff")
+            .with_color("accent", "#7c3aed")
+            .with_color("danger", "#dc2626")
+            .with_color("success", "#16a34a")
+            .with_base_tokens()
+    }
+
+    /// The built-in dark theme.
+    pub fn dark() -> Self {
+        Self::new("dark")
+            .with_color("background", "#0f172a")
+            .with_color("surface", "#1e293b")
+            .with_color("text", "#f1f5f9")
+            .with_color("muted", "#94a3b8")
+            .with_color("border", "#334155")
+            .with_color("primary", "#818cf8")
+            .with_color("on-primary", "#0f172a")
+            .with_color("accent", "#a78bfa")
+            .with_color("danger", "#f87171")
+            .with_color("success", "#4ade80")
+            .with_base_tokens()
+    }
+
+    /// Spacing and typography the built-in themes share.
+    fn with_base_tokens(self) -> Self {
+        self.with_spacing("xs", "0.25rem")
+            .with_spacing("sm", "0.5rem")
+            .with_spacing("md", "1rem")
+            .with_spacing("lg", "1.5rem")
+            .with_spacing("xl", "2rem")
+            .with_typography("body", "400 1rem/1.5 system-ui, sans-serif")
+            .with_typography("heading", "700 1.5rem/1.25 system-ui, sans-serif")
+            .with_typography("small", "400 0.875rem/1.4 system-ui, sans-serif")
+            .with_typography("mono", "400 0.875rem/1.4 ui-monospace, monospace")
+    }
+
+    /// Every token by its name: `color.primary`, `spacing.md`,
+    /// `typography.body`.
+    pub fn tokens(&self) -> BTreeMap<String, String> {
+        let groups = [("color", &self.colors), ("spacing", &self.spacing), ("typography", &self.typography)];
+        let mut all = BTreeMap::new();
+        for (group, tokens) in groups {
+            for (name, value) in tokens {
+                all.insert(format!("{}.{}", group, name), value.clone());
+            }
+        }
+        all
+    }
+
+    /// The value of a token, by its name.
+    pub fn token(&self, name: &str) -> Option<&str> {
+        let (group, name) = name.split_once('.')?;
+        let tokens = match group {
+            "color" => &self.colors,
+            "spacing" => &self.spacing,
+            "typography" => &self.typography,
+            _ => return None,
+        };
+        tokens.get(name).map(String::as_str)
+    }
+
+    /// The tokens as CSS variables declared on `selector`.
+    pub fn css(&self, selector: &str) -> String {
+        let mut css = format!("{} {{\n", selector);
+        for (name, value) in self.tokens() {
+            css.push_str(&format!("  {}: {};\n", css_variable(&name), value));
+        }
+        css.push('}');
+        css
+    }
+}
+
+/// The CSS variable a token is set as: `color.primary` is
+/// `--morpheus-color-primary`.
+pub fn css_variable(token: &str) -> String {
+    format!("{}{}", CSS_VARIABLE_PREFIX, token.replace('.', "-"))
+}
+
+/// The themes a host offers, and which one is active.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Themes {
+    themes: BTreeMap<String, Theme>,
+    active: String,
+    /// Whether the [`SYSTEM_THEME`] is set, rather than `active`.
+    #[serde(default)]
+    follows_system: bool,
+}
+
+impl Default for Themes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Themes {
+    /// The built-in light and dark themes, following the user's color
+    /// scheme.
+    pub fn new() -> Self {
+        let themes = [Theme::light(), Theme::dark()].into_iter().map(|theme| (theme.name.clone(), theme)).collect();
+        Self {
+            themes,
+            active: DEFAULT_THEME.to_string(),
+            follows_system: true,
+        }
+    }
+
+    /// Add a theme, or replace the one with its name. It must define every
+    /// token of the default theme, which components may use, and its values
+    /// must be plain CSS values (they end up in stylesheets).
+    pub fn register(&mut self, theme: Theme) -> Result<()> {
+        let tokens = theme.tokens();
+        if let Some((name, _)) = tokens.iter().find(|(_, value)| value.contains([';', '{', '}', '<', '>'])) {
+            return Err(MorpheusError::InvalidState(format!(
+                "Theme '{}' token {} is not a plain CSS value",
+                theme.name, name
+            )));
+        }
+        let missing: Vec<_> =
+            Theme::light().tokens().into_keys().filter(|name| theme.token(name).is_none()).collect();
+        if !missing.is_empty() {
+            return Err(MorpheusError::InvalidState(format!(
+                "Theme '{}' is missing tokens: {}",
+                theme.name,
+                missing.join(", ")
+            )));
+        }
+        self.themes.insert(theme.name.clone(), theme);
+        Ok(())
+    }
+
+    /// A theme by name.
+    pub fn get(&self, name: &str) -> Option<&Theme> {
+        self.themes.get(name)
+    }
+
+    /// Every theme, by name.
+    pub fn list(&self) -> Vec<&Theme> {
+        self.themes.values().collect()
+    }
+
+    /// The chosen theme; while following the system, the one for users
+    /// without a color scheme preference.
+    pub fn active(&self) -> &Theme {
+        &self.themes[&self.active]
+    }
+
+    /// Name of the chosen theme, or [`SYSTEM_THEME`].
+    pub fn active_name(&self) -> &str {
+        if self.follows_system {
+            SYSTEM_THEME
+        } else {
+            &self.active
+        }
+    }
+
+    /// Whether the [`SYSTEM_THEME`] is set.
+    pub fn follows_system(&self) -> bool {
+        self.follows_system
+    }
+
+    /// The theme to style components with for a user preferring `scheme`.
+    pub fn for_scheme(&self, scheme: ColorScheme) -> &Theme {
+        let chosen = self.follows_system.then(|| self.themes.get(scheme.as_str())).flatten();
+        chosen.unwrap_or_else(|| self.active())
+    }
+
+    /// The themes for either color scheme, as hosts apply them.
+    pub fn current(&self) -> ActiveTheme {
+        ActiveTheme {
+            name: self.active_name().to_string(),
+            light: self.for_scheme(ColorScheme::Light).clone(),
+            dark: self.for_scheme(ColorScheme::Dark).clone(),
+        }
+    }
+
+    /// Make `name` the active theme, or follow the user's color scheme
+    /// with [`SYSTEM_THEME`].
+    pub fn set_theme(&mut self, name: &str) -> Result<ActiveTheme> {
+        if name == SYSTEM_THEME {
+            self.follows_system = true;
+            return Ok(self.current());
+        }
+        if !self.themes.contains_key(name) {
+            return Err(MorpheusError::InvalidState(format!("No theme named '{}'", name)));
+        }
+        self.active = name.to_string();
+        self.follows_system = false;
+        Ok(self.current())
+    }
+}
+
+/// The active theme as hosts apply it: the same theme for both color
+/// schemes, unless it is the [`SYSTEM_THEME`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ActiveTheme {
+    /// Name of the chosen theme, or `system`.
+    pub name: String,
+
+    /// Theme for users preferring light colors, or without a preference.
+    pub light: Theme,
+
+    /// Theme for users preferring dark colors.
+    pub dark: Theme,
+}
+
+impl ActiveTheme {
+    /// The theme for a user preferring `scheme`.
+    pub fn for_scheme(&self, scheme: ColorScheme) -> &Theme {
+        match scheme {
+            ColorScheme::Light => &self.light,
+            ColorScheme::Dark => &self.dark,
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen::prelude::wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = morpheus, js_name = themeToken)]
+    fn morpheus_theme_token(name: &str) -> String;
+}
+
+/// The value of a token in the host's active theme. Outside the browser
+/// there is no host, so it is the default theme's.
+pub fn token(name: &str) -> String {
+    #[cfg(target_arch = "wasm32")]
+    {
+        morpheus_theme_token(name)
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        Theme::light().token(name).unwrap_or_default().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_built_in_themes_define_the_same_tokens() {
+        let light = Theme::light().tokens();
+        let dark = Theme::dark().tokens();
+        assert_eq!(light.keys().collect::<Vec<_>>(), dark.keys().collect::<Vec<_>>());
+        assert_eq!(light["spacing.md"], "1rem");
+        assert_eq!(Theme::light().token("typography.mono"), Some("400 0.875rem/1.4 ui-monospace, monospace"));
+        assert_eq!(Theme::light().token("colour.text"), None);
+        assert_eq!(token("color.primary"), "#4f46e5");
+    }
+
+    #[test]
+    fn test_themes_switch_only_to_complete_registered_themes() {
+        let mut themes = Themes::new();
+        assert!(themes.set_theme("contrast").is_err());
+        assert_eq!(themes.active_name(), SYSTEM_THEME);
+
+        let mut contrast = Theme::dark();
+        contrast.name = "contrast".to_string();
+        contrast.colors.insert("text".to_string(), "#ffffff".to_string());
+        themes.register(contrast).unwrap();
+        let active = themes.set_theme("contrast").unwrap();
+        assert_eq!(active.for_scheme(ColorScheme::Light).token("color.text"), Some("#ffffff"));
+        assert_eq!(active.light, active.dark);
+        assert_eq!(themes.list().len(), 3);
+
+        let error = themes.register(Theme::new("empty").with_color("text", "#000")).unwrap_err();
+        assert!(error.to_string().contains("missing tokens: color.accent, color.background"));
+        let injected = Theme::light().with_color("text", "red; } body { display: none");
+        assert!(themes.register(injected).unwrap_err().to_string().contains("color.text is not a plain CSS value"));
+    }
+
+    #[test]
+    fn test_system_theme_follows_the_color_scheme() {
+        let mut themes = Themes::new();
+        themes.set_theme("dark").unwrap();
+        assert!(!themes.follows_system());
+
+        let system = themes.set_theme(SYSTEM_THEME).unwrap();
+        assert_eq!(system.name, "system");
+        assert_eq!(system.for_scheme(ColorScheme::Light).name, "light");
+        assert_eq!(system.for_scheme(ColorScheme::Dark).name, "dark");
+        assert_eq!(themes.active().name, "dark");
+    }
+
+    #[test]
+    fn test_css_declares_every_token() {
+        let css = Theme::new("tiny").with_color("text", "#000").with_spacing("md", "1rem").css(".app");
+        assert_eq!(css, ".app {\n  --morpheus-color-text: #000;\n  --morpheus-spacing-md: 1rem;\n}");
+    }
+}
@@ -0,0 +1,106 @@
+//! Thumbnails: small previews of what a component renders.
+//!
+//! Component pickers and history views that only show names make people
+//! open each component to find the one they mean. A [`Thumbnail`] is a
+//! small PNG, JPEG or WebP image of a version as rendered, captured by a
+//! client (e.g. with `canvas.toDataURL()`) or by a visual test, and kept
+//! inline in the component's metadata and catalog entry so a picker can
+//! show it without another request. Images over [`MAX_THUMBNAIL_BYTES`]
+//! are refused.
+//!
+//! ```rust
+//! use morpheus_core::thumbnail::Thumbnail;
+//!
+//! let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+//! let thumbnail = Thumbnail::from_bytes(png).unwrap();
+//!
+//! assert_eq!(thumbnail.content_type, "image/png");
+//! assert_eq!(Thumbnail::from_data_url(&thumbnail.data_url()).unwrap(), thumbnail);
+//! assert!(Thumbnail::from_bytes(b"<svg></svg>").is_err());
+//! ```
+
+use crate::errors::{MorpheusError, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// Largest image accepted as a thumbnail, in bytes.
+pub const MAX_THUMBNAIL_BYTES: usize = 64 * 1024;
+
+/// A small rendered preview of a component.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Thumbnail {
+    /// `image/png`, `image/jpeg` or `image/webp`
+    pub content_type: String,
+    /// The image, base64-encoded
+    pub data_base64: String,
+}
+
+impl Thumbnail {
+    /// A thumbnail of the image `bytes`, whose type is read from its
+    /// header.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() > MAX_THUMBNAIL_BYTES {
+            return Err(MorpheusError::InvalidState(format!(
+                "Thumbnail is {} bytes, more than the {} allowed",
+                bytes.len(),
+                MAX_THUMBNAIL_BYTES
+            )));
+        }
+        let content_type = image_type(bytes)
+            .ok_or_else(|| MorpheusError::InvalidState("Thumbnail must be a PNG, JPEG or WebP image".to_string()))?;
+        Ok(Self {
+            content_type: content_type.to_string(),
+            data_base64: base64::engine::general_purpose::STANDARD.encode(bytes),
+        })
+    }
+
+    /// A thumbnail of the image in a `data:` URL, as `canvas.toDataURL()`
+    /// returns.
+    pub fn from_data_url(url: &str) -> Result<Self> {
+        let data = url
+            .strip_prefix("data:")
+            .and_then(|rest| rest.split_once(";base64,"))
+            .map(|(_, data)| data)
+            .ok_or_else(|| MorpheusError::InvalidState("Thumbnail must be a base64 data: URL".to_string()))?;
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(|e| MorpheusError::InvalidState(format!("Thumbnail is not valid base64: {}", e)))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// The image as a `data:` URL, for an `<img src>`.
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.content_type, self.data_base64)
+    }
+}
+
+/// The MIME type of a PNG, JPEG or WebP image, from its magic bytes.
+fn image_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_small_images_are_accepted() {
+        assert_eq!(Thumbnail::from_bytes(b"\xff\xd8\xff\xe0").unwrap().content_type, "image/jpeg");
+        assert_eq!(Thumbnail::from_bytes(b"RIFF\0\0\0\0WEBPVP8 ").unwrap().content_type, "image/webp");
+        assert!(Thumbnail::from_bytes(b"GIF89a").is_err());
+
+        let mut large = b"\x89PNG\r\n\x1a\n".to_vec();
+        large.resize(MAX_THUMBNAIL_BYTES + 1, 0);
+        assert!(Thumbnail::from_bytes(&large).unwrap_err().to_string().contains("more than"));
+        assert!(Thumbnail::from_data_url("https://example.com/shot.png").is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! Virtualized lists: render only the rows in view.
+//!
+//! A [`VirtualList`] knows the row count, row height and scroll position,
+//! and renders the visible rows (plus a few either side, the overscan)
+//! between two spacer elements that give the scrollbar its full length. The
+//! host reports scrolling through the component's [`SCROLL_EXPORT`] and
+//! re-renders, keeping the list's scroll position, so a table of 50,000
+//! rows renders a few dozen.
+//!
+//! ```rust
+//! use morpheus_core::virtual_list::virtual_list;
+//!
+//! let mut rows = virtual_list("orders", 50_000, 32.0).viewport(480.0);
+//! rows.scroll_to(32_000.0);
+//! assert_eq!(rows.window(), 995..1021);
+//!
+//! let html = rows.render(|i| format!(r#"<div class="h-8">Order {}</div>"#, i));
+//! assert!(html.contains("Order 1000") && !html.contains("Order 994<"));
+//! ```
+
+use std::ops::Range;
+
+/// Attribute naming the scrolling element of a virtual list.
+pub const VIRTUAL_LIST_ATTR: &str = "data-virtual-list";
+
+/// Component export the host calls when a virtual list scrolls, as
+/// `on_scroll(list: &str, scroll_top: f64, viewport_height: f64)`.
+pub const SCROLL_EXPORT: &str = "on_scroll";
+
+/// Rows rendered beyond each edge of the viewport by default.
+pub const DEFAULT_OVERSCAN: usize = 5;
+
+/// Viewport height used until the host reports the real one.
+pub const DEFAULT_VIEWPORT_HEIGHT: f64 = 600.0;
+
+/// The visible window of a long list of fixed-height rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VirtualList {
+    pub name: String,
+    pub item_count: usize,
+    /// Height of every row, in pixels.
+    pub row_height: f64,
+    pub viewport_height: f64,
+    pub overscan: usize,
+    scroll_top: f64,
+}
+
+/// A virtual list of `item_count` rows, each `row_height` pixels tall.
+pub fn virtual_list(name: impl Into<String>, item_count: usize, row_height: f64) -> VirtualList {
+    VirtualList {
+        name: name.into(),
+        item_count,
+        row_height: row_height.max(1.0),
+        viewport_height: DEFAULT_VIEWPORT_HEIGHT,
+        overscan: DEFAULT_OVERSCAN,
+        scroll_top: 0.0,
+    }
+}
+
+impl VirtualList {
+    /// Height of the scrolling area, in pixels.
+    pub fn viewport(mut self, height: f64) -> Self {
+        self.viewport_height = height.max(0.0);
+        self
+    }
+
+    /// Rows to render beyond each edge of the viewport, so fast scrolling
+    /// doesn't show blank space before the next render.
+    pub fn overscan(mut self, rows: usize) -> Self {
+        self.overscan = rows;
+        self
+    }
+
+    /// Record a scroll position, as reported to the component's
+    /// [`SCROLL_EXPORT`].
+    pub fn scroll_to(&mut self, scroll_top: f64) {
+        let max = (self.total_height() - self.viewport_height).max(0.0);
+        self.scroll_top = scroll_top.clamp(0.0, max);
+    }
+
+    pub fn scroll_top(&self) -> f64 {
+        self.scroll_top
+    }
+
+    /// Height of all rows together.
+    pub fn total_height(&self) -> f64 {
+        self.item_count as f64 * self.row_height
+    }
+
+    /// Indices of the rows to render.
+    pub fn window(&self) -> Range<usize> {
+        let first = (self.scroll_top / self.row_height).floor() as usize;
+        let visible = (self.viewport_height / self.row_height).ceil() as usize + 1;
+        let start = first.saturating_sub(self.overscan).min(self.item_count);
+        let end = (first + visible + self.overscan).min(self.item_count);
+        start..end
+    }
+
+    /// Heights of the spacers above and below the rendered rows.
+    pub fn spacers(&self) -> (f64, f64) {
+        let window = self.window();
+        let above = window.start as f64 * self.row_height;
+        let below = (self.item_count - window.end) as f64 * self.row_height;
+        (above, below)
+    }
+
+    /// The list as a scrolling `<div>`, with `row(i)` rendering row `i`.
+    pub fn render(&self, row: impl FnMut(usize) -> String) -> String {
+        let (above, below) = self.spacers();
+        format!(
+            "{}<div style=\"height:{}px\"></div>{}<div style=\"height:{}px\"></div></div>",
+            self.scroller(),
+            above,
+            self.window().map(row).collect::<String>(),
+            below
+        )
+    }
+
+    /// The list as a scrolling table: `head` is the `<thead>` (may be
+    /// empty) and `row(i)` renders the `<tr>` of row `i`.
+    pub fn render_table(&self, head: &str, row: impl FnMut(usize) -> String) -> String {
+        let (above, below) = self.spacers();
+        format!(
+            "{}<table class=\"w-full\">{}<tbody><tr style=\"height:{}px\"></tr>{}<tr style=\"height:{}px\"></tr></tbody></table></div>",
+            self.scroller(),
+            head,
+            above,
+            self.window().map(row).collect::<String>(),
+            below
+        )
+    }
+
+    /// Opening tag of the scrolling element.
+    fn scroller(&self) -> String {
+        format!(
+            "<div {}=\"{}\" style=\"height:{}px;overflow-y:auto\">",
+            VIRTUAL_LIST_ATTR,
+            self.name.replace('&', "&amp;").replace('"', "&quot;"),
+            self.viewport_height
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_follows_scroll() {
+        let mut list = virtual_list("rows", 10_000, 20.0).viewport(200.0).overscan(2);
+
+        assert_eq!(list.window(), 0..13);
+        list.scroll_to(1_000.0);
+        assert_eq!(list.window(), 48..63);
+
+        // Clamped to the last screenful
+        list.scroll_to(1e9);
+        assert_eq!(list.scroll_top(), 199_800.0);
+        assert_eq!(list.window(), 9988..10_000);
+    }
+
+    #[test]
+    fn test_spacers_keep_the_full_height() {
+        let mut list = virtual_list("rows", 1_000, 30.0).viewport(300.0);
+        list.scroll_to(9_000.0);
+
+        let (above, below) = list.spacers();
+        let window = list.window();
+        assert_eq!(above + window.len() as f64 * 30.0 + below, list.total_height());
+
+        let short = virtual_list("few", 3, 30.0);
+        assert_eq!(short.window(), 0..3);
+        assert_eq!(short.spacers(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_render_only_the_window() {
+        let mut list = virtual_list("big \"table\"", 50_000, 32.0).viewport(320.0).overscan(0);
+        list.scroll_to(3_200.0);
+
+        let html = list.render_table("<thead></thead>", |i| format!("<tr><td>{}</td></tr>", i));
+
+        assert!(html.starts_with("<div data-virtual-list=\"big &quot;table&quot;\" style=\"height:320px;overflow-y:auto\">"));
+        assert_eq!(html.matches("<td>").count(), 11);
+        assert!(html.contains("<tr style=\"height:3200px\"></tr><tr><td>100</td></tr>"));
+    }
+}
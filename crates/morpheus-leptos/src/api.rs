@@ -0,0 +1,265 @@
+//! The server endpoints the chat uses, over the browser's `fetch` and
+//! `EventSource`.
+
+use futures_util::{Stream, StreamExt};
+use gloo_net::eventsource::futures::EventSource;
+use gloo_net::http::{Request, Response};
+use morpheus_core::profiler::RenderProfile;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Name the chat records review verdicts under.
+const REVIEWER: &str = "chat";
+
+/// A queued generation, as `GET /api/jobs/{id}/events` sends it.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Job {
+    pub id: String,
+    pub status: JobStatus,
+    /// Current compile attempt, from 1 (0 while queued).
+    pub iteration: u32,
+    /// Jobs ahead of this one while queued.
+    pub position: Option<usize>,
+    /// Outcome once [`JobStatus::Done`].
+    pub result: Option<JobResult>,
+    /// Why the job failed.
+    pub error: Option<String>,
+}
+
+/// Where a job is in the pipeline.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    #[default]
+    Queued,
+    AwaitingAi,
+    Compiling,
+    Saving,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job will change no further.
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Done | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+/// What a finished job produced.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct JobResult {
+    pub success: bool,
+    pub version_id: Option<usize>,
+    pub error: Option<String>,
+    pub iterations: u32,
+    pub logs: Vec<String>,
+}
+
+/// What the diagnostics overlay shows, from `GET /api/overview`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Diagnostics {
+    /// Loaded components and the versions serving them.
+    pub components: Vec<ComponentVersion>,
+    /// When a version last went live (RFC 3339).
+    pub last_reload: Option<String>,
+    pub last_failed_generation: Option<FailedGeneration>,
+}
+
+/// A loaded component.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ComponentVersion {
+    pub name: String,
+    pub version_id: Option<usize>,
+}
+
+/// A generation that produced no version.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct FailedGeneration {
+    pub prompt: String,
+    /// When it gave up (RFC 3339).
+    pub at: String,
+    pub error: String,
+    /// Compiler output of each failed attempt.
+    pub compile_errors: Vec<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct History {
+    versions: Vec<VersionSummary>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct VersionSummary {
+    id: usize,
+    is_current: bool,
+}
+
+#[derive(Serialize)]
+struct JobRequest<'a> {
+    prompt: &'a str,
+    component: &'a str,
+    base_version_id: Option<usize>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct RollbackResponse {
+    success: bool,
+    error: Option<String>,
+}
+
+/// The Morpheus server the chat talks to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerApi {
+    base_url: String,
+}
+
+impl ServerApi {
+    /// Server at `base_url`; empty for the page's own origin.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// The version currently live, if any.
+    pub async fn current_version(&self) -> Result<Option<usize>, String> {
+        let history: History = read(Request::get(&self.url("/api/history")).send().await).await?;
+        Ok(history.versions.iter().find(|v| v.is_current).map(|v| v.id))
+    }
+
+    /// Component versions, the last reload and the last failed generation.
+    pub async fn diagnostics(&self) -> Result<Diagnostics, String> {
+        read(Request::get(&self.url("/api/overview")).send().await).await
+    }
+
+    /// How each component renders, slowest first.
+    pub async fn render_profiles(&self) -> Result<Vec<RenderProfile>, String> {
+        read(Request::get(&self.url("/api/profiler")).send().await).await
+    }
+
+    /// Whether `version_id` is live.
+    pub async fn is_live(&self, version_id: usize) -> Result<bool, String> {
+        Ok(self.current_version().await? == Some(version_id))
+    }
+
+    /// Queue a change to `component`, based on `base_version_id`.
+    pub async fn submit(&self, prompt: &str, component: &str, base_version_id: Option<usize>) -> Result<Job, String> {
+        let body = JobRequest {
+            prompt,
+            component,
+            base_version_id,
+        };
+        let request = Request::post(&self.url("/api/jobs")).json(&body).map_err(|e| e.to_string())?;
+        read(request.send().await).await
+    }
+
+    /// A job's status changes, as the server streams them.
+    pub fn watch_job(&self, id: &str) -> Result<impl Stream<Item = Job>, String> {
+        let mut events = EventSource::new(&self.url(&format!("/api/jobs/{}/events", id))).map_err(|e| e.to_string())?;
+        let jobs = events.subscribe("job").map_err(|e| e.to_string())?;
+        Ok(jobs.filter_map(move |event| {
+            // Keep the source open as long as the stream
+            let _ = &events;
+            let job = event
+                .ok()
+                .and_then(|(_, message)| message.data().as_string())
+                .and_then(|data| serde_json::from_str(&data).ok());
+            async move { job }
+        }))
+    }
+
+    /// Make `version_id` live, restoring its state.
+    pub async fn rollback(&self, version_id: usize) -> Result<(), String> {
+        let request = Request::post(&self.url("/api/rollback"))
+            .json(&json!({ "version_id": version_id }))
+            .map_err(|e| e.to_string())?;
+        let response: RollbackResponse = read(request.send().await).await?;
+        match response.success {
+            true => Ok(()),
+            false => Err(response.error.unwrap_or_else(|| format!("Version {} can't be activated", version_id))),
+        }
+    }
+
+    /// Record a review verdict: `approved` or `changes_requested`.
+    pub async fn review(&self, version_id: usize, status: &str) -> Result<(), String> {
+        let request = Request::post(&self.url(&format!("/api/versions/{}/review", version_id)))
+            .json(&json!({ "reviewer": REVIEWER, "status": status }))
+            .map_err(|e| e.to_string())?;
+        let _: Value = read(request.send().await).await?;
+        Ok(())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+}
+
+/// The response body, or the server's `error` message.
+async fn read<T: DeserializeOwned>(response: Result<Response, gloo_net::Error>) -> Result<T, String> {
+    let response = response.map_err(|e| e.to_string())?;
+    if !response.ok() {
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        return Err(body["error"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{} {}", response.status(), response.status_text())));
+    }
+    response.json().await.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls_are_relative_to_the_server() {
+        assert_eq!(ServerApi::new("").url("/api/jobs"), "/api/jobs");
+        assert_eq!(ServerApi::new("http://localhost:3002/").url("/api/jobs"), "http://localhost:3002/api/jobs");
+    }
+
+    #[test]
+    fn test_job_requests_name_their_base_version() {
+        let request = JobRequest {
+            prompt: "Bigger buttons",
+            component: "main",
+            base_version_id: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({ "prompt": "Bigger buttons", "component": "main", "base_version_id": null })
+        );
+    }
+
+    #[test]
+    fn test_server_bodies_tolerate_missing_and_unknown_fields() {
+        let job: Job = serde_json::from_value(json!({
+            "id": "j1",
+            "status": "done",
+            "result": { "success": true, "version_id": 4 },
+            "prompt": "ignored",
+        }))
+        .unwrap();
+        assert!(job.status.is_finished());
+        assert_eq!(job.result.and_then(|r| r.version_id), Some(4));
+        assert!(!JobStatus::Saving.is_finished());
+
+        let diagnostics: Diagnostics = serde_json::from_value(json!({ "components": [{ "name": "main" }] })).unwrap();
+        assert_eq!(diagnostics.components[0].version_id, None);
+        assert_eq!(diagnostics.last_failed_generation, None);
+
+        let history: History =
+            serde_json::from_value(json!({ "versions": [{ "id": 2, "is_current": true }] })).unwrap();
+        assert_eq!(history.versions.iter().find(|v| v.is_current).map(|v| v.id), Some(2));
+    }
+}
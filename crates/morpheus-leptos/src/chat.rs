@@ -0,0 +1,217 @@
+//! The chat for asking the AI to change the app.
+
+use crate::api::{JobStatus, ServerApi};
+use crate::transcript::{Proposal, Role, Transcript};
+use futures_util::StreamExt;
+use leptos::*;
+
+/// Chat for asking the AI to change a component of the app.
+///
+/// Each message becomes a generation job; its progress (queued, writing,
+/// compiling, attempt number) streams into the chat until a new version
+/// is ready. The user then approves or rejects it:
+///
+/// - a version that went live right away is kept, or rolled back to the
+///   version live before it
+/// - a version waiting for review (the server requires approval) is
+///   approved and activated, or sent back with changes requested
+///
+/// `on_change` is called with the live version whenever it changes.
+#[component]
+pub fn ModificationChat(
+    /// Morpheus server URL; empty for the page's own origin.
+    #[prop(into, default = String::new())]
+    server: String,
+    /// Component the chat changes.
+    #[prop(into, default = "main".to_string())]
+    component: String,
+    /// Placeholder for the message box.
+    #[prop(into, default = "Ask for a change…".to_string())]
+    placeholder: String,
+    /// Called with the live version after it changes.
+    #[prop(optional, into)]
+    on_change: Option<Callback<usize>>,
+) -> impl IntoView {
+    let api = store_value(ServerApi::new(server));
+    let component = store_value(component);
+    let transcript = create_rw_signal(Transcript::default());
+    let draft = create_rw_signal(String::new());
+
+    let notify = move |version_id: Option<usize>| {
+        if let (Some(on_change), Some(version_id)) = (on_change, version_id) {
+            on_change.call(version_id);
+        }
+    };
+
+    let send = move || {
+        let prompt = draft.get_untracked().trim().to_string();
+        if prompt.is_empty() || !transcript.with_untracked(Transcript::can_ask) {
+            return;
+        }
+        draft.set(String::new());
+        transcript.update(|t| t.ask(&prompt));
+        spawn_local(async move {
+            let api = api.get_value();
+            let previous = api.current_version().await.ok().flatten();
+            let job = match api.submit(&prompt, &component.get_value(), previous).await {
+                Ok(job) => job,
+                Err(error) => return transcript.update(|t| t.fail(error)),
+            };
+            transcript.update(|t| t.progress(&job));
+            let mut last = job.clone();
+            if !job.status.is_finished() {
+                let mut updates = match api.watch_job(&job.id) {
+                    Ok(updates) => Box::pin(updates),
+                    Err(error) => return transcript.update(|t| t.fail(error)),
+                };
+                while let Some(job) = updates.next().await {
+                    let finished = job.status.is_finished();
+                    transcript.update(|t| t.progress(&job));
+                    last = job;
+                    if finished {
+                        break;
+                    }
+                }
+            }
+            let version_id = last.result.as_ref().and_then(|r| r.version_id);
+            let live = match (last.status, version_id) {
+                (JobStatus::Done, Some(version_id)) => api.is_live(version_id).await.unwrap_or(false),
+                _ => false,
+            };
+            let proposal = transcript.try_update(|t| t.finish(&last, previous, live)).flatten();
+            if proposal.is_some_and(|p| p.live) {
+                notify(version_id);
+            }
+        });
+    };
+
+    let decide = move |approve: bool| {
+        let Some(proposal) = transcript.with_untracked(|t| t.proposal) else {
+            return;
+        };
+        spawn_local(async move {
+            let outcome = decide_proposal(&api.get_value(), proposal, approve).await;
+            if outcome.is_ok() {
+                let live = match (approve, proposal.live) {
+                    (true, _) => Some(proposal.version_id),
+                    (false, true) => proposal.previous,
+                    (false, false) => None,
+                };
+                notify(live);
+            }
+            transcript.update(|t| t.resolve(outcome));
+        });
+    };
+
+    view! {
+        <div class="flex flex-col h-full border border-slate-200 rounded-lg bg-white text-slate-900">
+            <div class="flex-1 overflow-y-auto p-4 space-y-3">
+                <For
+                    each=move || transcript.with(|t| t.turns.clone())
+                    key=|turn| (turn.id, turn.text.clone())
+                    children=move |turn| {
+                        let class = match turn.role {
+                            Role::User => "ml-auto max-w-[80%] rounded-lg px-3 py-2 bg-blue-600 text-white",
+                            Role::Assistant => "max-w-[80%] rounded-lg px-3 py-2 bg-slate-100",
+                            Role::Progress => "max-w-[80%] px-3 py-2 text-sm text-slate-500 animate-pulse",
+                            Role::Error => "max-w-[80%] rounded-lg px-3 py-2 bg-red-50 text-red-700",
+                        };
+                        view! { <div class=class>{turn.text}</div> }
+                    }
+                />
+            </div>
+            <Show when=move || transcript.with(|t| t.proposal.is_some())>
+                <div class="flex gap-2 px-4 pb-3">
+                    <button
+                        on:click=move |_| decide(true)
+                        class="px-4 py-2 rounded-lg bg-green-600 text-white hover:bg-green-700">
+                        "Approve"
+                    </button>
+                    <button
+                        on:click=move |_| decide(false)
+                        class="px-4 py-2 rounded-lg bg-slate-200 hover:bg-slate-300">
+                        "Reject"
+                    </button>
+                </div>
+            </Show>
+            <form
+                class="flex gap-2 p-3 border-t border-slate-200"
+                on:submit=move |ev| {
+                    ev.prevent_default();
+                    send();
+                }>
+                <input
+                    class="flex-1 px-3 py-2 rounded-lg border border-slate-300"
+                    placeholder=placeholder
+                    prop:value=move || draft.get()
+                    on:input=move |ev| draft.set(event_target_value(&ev))
+                    disabled=move || !transcript.with(Transcript::can_ask)
+                />
+                <button
+                    type="submit"
+                    class="px-4 py-2 rounded-lg bg-blue-600 text-white disabled:opacity-50"
+                    disabled=move || !transcript.with(Transcript::can_ask)>
+                    "Send"
+                </button>
+            </form>
+        </div>
+    }
+}
+
+/// Carry out the user's decision on a proposed version.
+async fn decide_proposal(api: &ServerApi, proposal: Proposal, approve: bool) -> Result<String, String> {
+    let version_id = proposal.version_id;
+    match (approve, proposal.live) {
+        (true, true) => Ok(format!("Kept version {}.", version_id)),
+        (true, false) => {
+            api.review(version_id, "approved").await?;
+            api.rollback(version_id).await?;
+            Ok(format!("Approved version {}; it's live.", version_id))
+        }
+        (false, true) => match proposal.previous {
+            Some(previous) => {
+                api.rollback(previous).await?;
+                Ok(format!("Rejected version {}; version {} is live again.", version_id, previous))
+            }
+            None => Err(format!("Version {} is the first version, so there's nothing to go back to.", version_id)),
+        },
+        (false, false) => {
+            api.review(version_id, "changes_requested").await?;
+            Ok(format!("Rejected version {}.", version_id))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::FutureExt;
+
+    /// Decide on a proposal whose outcome needs no request to the server
+    fn decide_offline(proposal: Proposal, approve: bool) -> Result<String, String> {
+        decide_proposal(&ServerApi::new(""), proposal, approve)
+            .now_or_never()
+            .expect("decided without a request")
+    }
+
+    #[test]
+    fn test_keeping_a_live_version_changes_nothing() {
+        let proposal = Proposal {
+            version_id: 4,
+            previous: Some(3),
+            live: true,
+        };
+        assert_eq!(decide_offline(proposal, true), Ok("Kept version 4.".to_string()));
+    }
+
+    #[test]
+    fn test_a_live_first_version_cannot_be_rejected() {
+        let proposal = Proposal {
+            version_id: 0,
+            previous: None,
+            live: true,
+        };
+        let refused = decide_offline(proposal, false).unwrap_err();
+        assert_eq!(refused, "Version 0 is the first version, so there's nothing to go back to.");
+    }
+}
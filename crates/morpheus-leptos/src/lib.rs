@@ -0,0 +1,46 @@
+//! # Morpheus Leptos
+//!
+//! Leptos components for apps that embed Morpheus, so a host app gets the
+//! "ask the AI to change this app" experience with one component instead
+//! of copying the example server's HTML.
+//!
+//! [`ModificationChat`] talks to a running Morpheus server from the
+//! browser: it queues each request as a generation job, streams the job's
+//! progress into the chat, and offers to approve or reject the version it
+//! produces. [`DiagnosticsOverlay`] is a dev-mode panel with the live
+//! component versions, the last reload, the last failed generation's
+//! compile errors, frame timings and render profiles; it renders nothing in
+//! release builds.
+//!
+//! ```rust,ignore
+//! use leptos::*;
+//! use morpheus_leptos::{DiagnosticsOverlay, ModificationChat};
+//!
+//! #[component]
+//! fn App() -> impl IntoView {
+//!     let (live, set_live) = create_signal(None::<usize>);
+//!     view! {
+//!         <p>"Live version: " {move || live.get()}</p>
+//!         <ModificationChat
+//!             server="http://127.0.0.1:3002"
+//!             component="main"
+//!             on_change=move |version_id| set_live.set(Some(version_id))
+//!         />
+//!         <DiagnosticsOverlay server="http://127.0.0.1:3002"/>
+//!     }
+//! }
+//! ```
+//!
+//! The server must allow the host page's origin (the example server allows
+//! any) and, with `MORPHEUS_OWNER_TOKEN` set, the page must be able to send
+//! it; the chat itself sends no credentials.
+
+pub mod api;
+pub mod chat;
+pub mod overlay;
+pub mod transcript;
+
+pub use api::{ComponentVersion, Diagnostics, FailedGeneration, Job, JobResult, JobStatus, ServerApi};
+pub use chat::ModificationChat;
+pub use overlay::{DiagnosticsOverlay, FrameStats};
+pub use transcript::{Proposal, Role, Transcript, Turn};
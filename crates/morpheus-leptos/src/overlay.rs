@@ -0,0 +1,253 @@
+//! Dev-mode diagnostics overlay.
+
+#[cfg(debug_assertions)]
+use crate::api::{Diagnostics, ServerApi};
+use leptos::*;
+#[cfg(debug_assertions)]
+use morpheus_core::profiler::RenderProfile;
+use std::collections::VecDeque;
+#[cfg(debug_assertions)]
+use std::time::Duration;
+
+/// Frames the timings are taken over.
+const FRAMES_KEPT: usize = 120;
+
+/// How often the overlay refreshes while open.
+#[cfg(debug_assertions)]
+const REFRESH: Duration = Duration::from_secs(2);
+
+/// Durations of the most recent frames.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameStats {
+    frames: VecDeque<f64>,
+}
+
+impl FrameStats {
+    /// Record a frame that took `ms` milliseconds.
+    pub fn record(&mut self, ms: f64) {
+        if self.frames.len() == FRAMES_KEPT {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(ms);
+    }
+
+    /// Frames per second over the recorded frames.
+    pub fn fps(&self) -> f64 {
+        match self.average_ms() {
+            ms if ms > 0.0 => 1000.0 / ms,
+            _ => 0.0,
+        }
+    }
+
+    /// Average frame time in milliseconds.
+    pub fn average_ms(&self) -> f64 {
+        match self.frames.len() {
+            0 => 0.0,
+            n => self.frames.iter().sum::<f64>() / n as f64,
+        }
+    }
+
+    /// Slowest recorded frame in milliseconds.
+    pub fn worst_ms(&self) -> f64 {
+        self.frames.iter().copied().fold(0.0, f64::max)
+    }
+}
+
+/// Dev-mode panel showing what the page is running: the live component
+/// versions, when a version last went live, the compile errors of the most
+/// recent failed generation, frame rate and frame times, and how each
+/// component renders (view, diff and DOM patches, from
+/// [`morpheus_core::profiler`]).
+///
+/// Ctrl+Shift+`shortcut` shows and hides it. Release builds compile it to
+/// nothing, so it can stay in the host app's view.
+#[component]
+pub fn DiagnosticsOverlay(
+    /// Morpheus server URL; empty for the page's own origin.
+    #[prop(into, default = String::new())]
+    server: String,
+    /// Key that toggles the overlay with Ctrl+Shift.
+    #[prop(default = 'd')]
+    shortcut: char,
+) -> impl IntoView {
+    #[cfg(debug_assertions)]
+    let view = overlay(server, shortcut).into_view();
+    #[cfg(not(debug_assertions))]
+    let view = {
+        let _ = (server, shortcut);
+        ().into_view()
+    };
+    view
+}
+
+#[cfg(debug_assertions)]
+fn overlay(server: String, shortcut: char) -> impl IntoView {
+    let api = store_value(ServerApi::new(server));
+    let open = create_rw_signal(false);
+    let diagnostics = create_rw_signal(None::<Result<Diagnostics, String>>);
+    let profiles = create_rw_signal(Vec::<RenderProfile>::new());
+    let frames = store_value(FrameStats::default());
+    let stats = create_rw_signal(FrameStats::default());
+
+    let toggle = window_event_listener(ev::keydown, move |ev| {
+        if ev.ctrl_key() && ev.shift_key() && ev.key().eq_ignore_ascii_case(&shortcut.to_string()) {
+            ev.prevent_default();
+            open.update(|open| *open = !*open);
+        }
+    });
+    on_cleanup(move || toggle.remove());
+
+    let refresh = move || {
+        stats.set(frames.get_value());
+        spawn_local(async move {
+            let api = api.get_value();
+            let latest = api.diagnostics().await;
+            diagnostics.set(Some(latest));
+            if let Ok(latest) = api.render_profiles().await {
+                profiles.set(latest);
+            }
+        });
+    };
+    // Measure frames and poll the server only while the overlay is open
+    create_effect(move |_| {
+        if open.get() {
+            frames.set_value(FrameStats::default());
+            measure_frames(open, frames, now());
+            refresh();
+        }
+    });
+    if let Ok(interval) = set_interval_with_handle(
+        move || {
+            if open.get_untracked() {
+                refresh();
+            }
+        },
+        REFRESH,
+    ) {
+        on_cleanup(move || interval.clear());
+    }
+
+    view! {
+        <Show when=move || open.get()>
+            <div class="fixed bottom-4 right-4 z-50 w-96 max-h-[80vh] overflow-y-auto rounded-lg bg-slate-900/95 p-4 font-mono text-xs text-slate-100 shadow-xl">
+                <div class="mb-2 flex justify-between font-semibold">
+                    <span>"Morpheus diagnostics"</span>
+                    <span class="text-slate-400">{format!("Ctrl+Shift+{}", shortcut.to_ascii_uppercase())}</span>
+                </div>
+                <div class="mb-2">
+                    {move || stats.with(|s| format!(
+                        "{:.0} fps · {:.1} ms avg · {:.1} ms worst",
+                        s.fps(),
+                        s.average_ms(),
+                        s.worst_ms()
+                    ))}
+                </div>
+                {move || profiles_view(profiles.get())}
+                {move || match diagnostics.get() {
+                    None => view! { <div class="text-slate-400">"Loading…"</div> }.into_view(),
+                    Some(Err(error)) => view! { <div class="text-red-400">{error}</div> }.into_view(),
+                    Some(Ok(diagnostics)) => server_view(diagnostics).into_view(),
+                }}
+            </div>
+        </Show>
+    }
+}
+
+#[cfg(debug_assertions)]
+fn server_view(diagnostics: Diagnostics) -> impl IntoView {
+    let components = diagnostics
+        .components
+        .into_iter()
+        .map(|c| {
+            let version = c.version_id.map_or("-".to_string(), |id| format!("v{}", id));
+            view! { <li>{c.name}" "<span class="text-slate-400">{version}</span></li> }
+        })
+        .collect_view();
+    let last_reload = diagnostics.last_reload.unwrap_or_else(|| "never".to_string());
+    let failure = diagnostics.last_failed_generation.map(|failure| {
+        let errors = failure
+            .compile_errors
+            .into_iter()
+            .map(|error| view! { <pre class="mt-1 whitespace-pre-wrap text-red-300">{error}</pre> })
+            .collect_view();
+        view! {
+            <div class="mt-2">
+                <div class="font-semibold text-red-400">"Last failed generation"</div>
+                <div>{failure.prompt}</div>
+                <div class="text-slate-400">{failure.at}" · "{failure.error}</div>
+                {errors}
+            </div>
+        }
+    });
+    view! {
+        <div class="font-semibold">"Components"</div>
+        <ul class="mb-2">{components}</ul>
+        <div>"Last reload: "{last_reload}</div>
+        {failure}
+    }
+}
+
+#[cfg(debug_assertions)]
+fn profiles_view(profiles: Vec<RenderProfile>) -> impl IntoView {
+    let rows = profiles
+        .into_iter()
+        .map(|p| {
+            let version = p.version.map_or(String::new(), |v| format!(" v{}", v));
+            let class = if p.is_slow() { "text-amber-300" } else { "" };
+            let timings = format!(
+                "view {:.1} ms · diff {:.1} ms · {:.0} patches (p95 {:.1} ms)",
+                p.view.average_ms,
+                p.diff.average_ms,
+                p.average_patches,
+                p.view.p95_ms + p.diff.p95_ms
+            );
+            view! { <li class=class>{p.component}{version}": "{timings}</li> }
+        })
+        .collect_view();
+    view! {
+        <div class="font-semibold">"Renders"</div>
+        <ul class="mb-2">{rows}</ul>
+    }
+}
+
+/// Record each frame's duration until the overlay closes.
+#[cfg(debug_assertions)]
+fn measure_frames(open: RwSignal<bool>, frames: StoredValue<FrameStats>, last: f64) {
+    request_animation_frame(move || {
+        if open.try_get_untracked() != Some(true) {
+            return;
+        }
+        let now = now();
+        frames.update_value(|frames| frames.record(now - last));
+        measure_frames(open, frames, now);
+    });
+}
+
+/// Milliseconds since the page loaded.
+#[cfg(debug_assertions)]
+fn now() -> f64 {
+    window().performance().map_or(0.0, |performance| performance.now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_stats_keep_the_latest_frames() {
+        let mut stats = FrameStats::default();
+        assert_eq!(stats.fps(), 0.0);
+
+        for _ in 0..FRAMES_KEPT {
+            stats.record(40.0);
+        }
+        stats.record(10.0);
+        for _ in 1..FRAMES_KEPT {
+            stats.record(20.0);
+        }
+
+        assert!((stats.average_ms() - (20.0 - 10.0 / FRAMES_KEPT as f64)).abs() < 1e-9);
+        assert_eq!(stats.worst_ms(), 20.0);
+        assert!(stats.fps() > 50.0);
+    }
+}
@@ -0,0 +1,213 @@
+//! What the chat shows, kept apart from the view so it can be tested
+//! without a browser.
+
+use crate::api::{Job, JobStatus};
+
+/// Who a message is from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// The person asking for a change.
+    User,
+    /// Answers about the change.
+    Assistant,
+    /// The running job's progress; replaced as it moves on.
+    Progress,
+    Error,
+}
+
+/// One message in the chat.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turn {
+    /// Position in the transcript, for keyed lists.
+    pub id: usize,
+    pub role: Role,
+    pub text: String,
+}
+
+/// A generated version waiting to be approved or rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Proposal {
+    pub version_id: usize,
+    /// Version live before the change, which rejecting restores.
+    pub previous: Option<usize>,
+    /// Whether the version went live right away (no review required).
+    pub live: bool,
+}
+
+/// The chat's messages and what it's waiting for.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Transcript {
+    pub turns: Vec<Turn>,
+    /// Whether a change is being generated.
+    pub busy: bool,
+    pub proposal: Option<Proposal>,
+}
+
+impl Transcript {
+    /// Whether a new request can be sent.
+    pub fn can_ask(&self) -> bool {
+        !self.busy && self.proposal.is_none()
+    }
+
+    /// Start a request.
+    pub fn ask(&mut self, prompt: &str) {
+        self.push(Role::User, prompt.to_string());
+        self.busy = true;
+    }
+
+    /// Show how far the job has got, replacing the previous progress line.
+    pub fn progress(&mut self, job: &Job) {
+        if let Some(last) = self.turns.last_mut().filter(|turn| turn.role == Role::Progress) {
+            last.text = progress_label(job);
+        } else {
+            self.push(Role::Progress, progress_label(job));
+        }
+    }
+
+    /// Show what a finished job produced; returns the version to decide on.
+    pub fn finish(&mut self, job: &Job, previous: Option<usize>, live: bool) -> Option<Proposal> {
+        self.busy = false;
+        if self.turns.last().is_some_and(|turn| turn.role == Role::Progress) {
+            self.turns.pop();
+        }
+        let result = job.result.as_ref();
+        match (job.status, result.and_then(|r| r.version_id)) {
+            (JobStatus::Done, Some(version_id)) if result.is_some_and(|r| r.success) => {
+                let attempts = result.map_or(1, |r| r.iterations.max(1));
+                let text = match live {
+                    true => format!("Version {} is live ({}). Keep it?", version_id, plural(attempts, "attempt")),
+                    false => format!(
+                        "Version {} is ready ({}) and waits for approval.",
+                        version_id,
+                        plural(attempts, "attempt")
+                    ),
+                };
+                self.push(Role::Assistant, text);
+                self.proposal = Some(Proposal {
+                    version_id,
+                    previous,
+                    live,
+                });
+                self.proposal
+            }
+            (JobStatus::Cancelled, _) => {
+                self.push(Role::Error, "The change was cancelled.".to_string());
+                None
+            }
+            _ => {
+                let error = job.error.clone().or_else(|| result.and_then(|r| r.error.clone()));
+                self.push(Role::Error, error.unwrap_or_else(|| "The change couldn't be made.".to_string()));
+                None
+            }
+        }
+    }
+
+    /// Record the outcome of approving or rejecting the proposal.
+    pub fn resolve(&mut self, outcome: Result<String, String>) {
+        self.proposal = None;
+        match outcome {
+            Ok(text) => self.push(Role::Assistant, text),
+            Err(error) => self.push(Role::Error, error),
+        }
+    }
+
+    /// Record an error that ended a request.
+    pub fn fail(&mut self, error: String) {
+        self.busy = false;
+        self.push(Role::Error, error);
+    }
+
+    fn push(&mut self, role: Role, text: String) {
+        let id = self.turns.len();
+        self.turns.push(Turn { id, role, text });
+    }
+}
+
+/// A job's progress in words.
+pub fn progress_label(job: &Job) -> String {
+    let attempt = if job.iteration > 1 {
+        format!(" (attempt {})", job.iteration)
+    } else {
+        String::new()
+    };
+    match job.status {
+        JobStatus::Queued => match job.position {
+            Some(ahead) if ahead > 0 => format!("Queued behind {}…", plural(ahead as u32, "change")),
+            _ => "Queued…".to_string(),
+        },
+        JobStatus::AwaitingAi => format!("Writing the change{}…", attempt),
+        JobStatus::Compiling => format!("Compiling{}…", attempt),
+        JobStatus::Saving => "Saving the new version…".to_string(),
+        JobStatus::Done => "Done".to_string(),
+        JobStatus::Failed => "Failed".to_string(),
+        JobStatus::Cancelled => "Cancelled".to_string(),
+    }
+}
+
+fn plural(count: u32, noun: &str) -> String {
+    match count {
+        1 => format!("1 {}", noun),
+        _ => format!("{} {}s", count, noun),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::JobResult;
+
+    fn job(status: JobStatus, iteration: u32) -> Job {
+        Job {
+            id: "j1".to_string(),
+            status,
+            iteration,
+            ..Job::default()
+        }
+    }
+
+    #[test]
+    fn test_progress_replaces_itself() {
+        let mut transcript = Transcript::default();
+        transcript.ask("Make the buttons bigger");
+        transcript.progress(&job(JobStatus::Queued, 0));
+        transcript.progress(&job(JobStatus::AwaitingAi, 1));
+        transcript.progress(&job(JobStatus::Compiling, 2));
+
+        assert_eq!(transcript.turns.len(), 2);
+        assert_eq!(transcript.turns[1].text, "Compiling (attempt 2)…");
+        assert!(!transcript.can_ask());
+    }
+
+    #[test]
+    fn test_finished_jobs_propose_a_version() {
+        let mut transcript = Transcript::default();
+        transcript.ask("Make the buttons bigger");
+        transcript.progress(&job(JobStatus::Saving, 1));
+        let done = Job {
+            result: Some(JobResult {
+                success: true,
+                version_id: Some(4),
+                iterations: 2,
+                ..JobResult::default()
+            }),
+            ..job(JobStatus::Done, 2)
+        };
+
+        let proposal = transcript.finish(&done, Some(3), true).unwrap();
+        assert_eq!((proposal.version_id, proposal.previous), (4, Some(3)));
+        assert_eq!(transcript.turns[1].text, "Version 4 is live (2 attempts). Keep it?");
+        assert!(!transcript.can_ask());
+
+        transcript.resolve(Ok("Kept version 4.".to_string()));
+        assert!(transcript.can_ask());
+
+        transcript.ask("Now make them red");
+        let failed = Job {
+            error: Some("Compilation failed after 3 attempts".to_string()),
+            ..job(JobStatus::Failed, 3)
+        };
+        assert!(transcript.finish(&failed, Some(4), false).is_none());
+        assert_eq!(transcript.turns.last().unwrap().role, Role::Error);
+        assert!(transcript.can_ask());
+    }
+}
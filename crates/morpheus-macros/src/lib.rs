@@ -0,0 +1,281 @@
+//! Derive macros for Morpheus components.
+//!
+//! `#[derive(MorpheusState)]` implements
+//! `morpheus_core::state::MorpheusState` for a component's state struct, so
+//! its snapshots carry a version and restore across hot reloads, rollbacks
+//! and schema changes without hand-written plumbing:
+//!
+//! ```rust,ignore
+//! use morpheus_core::state::MorpheusState;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Default, Serialize, Deserialize, MorpheusState)]
+//! #[morpheus(version = 2, migrate = upgrade)]
+//! struct Counter {
+//!     count: i64,
+//!     step: i64,
+//! }
+//!
+//! fn upgrade(from: u32, state: serde_json::Value) -> morpheus_core::errors::Result<serde_json::Value> {
+//!     // Version 1 stored the count as a string
+//!     ...
+//! }
+//! ```
+//!
+//! The struct must have named fields and implement `Default`, `Serialize`
+//! and `Deserialize`. Attributes, all optional:
+//!
+//! - `version = N`: version of the state's shape (default 1); bump it when
+//!   a field changes meaning or type
+//! - `migrate = path`: `fn(u32, Value) -> Result<Value>` that upgrades a
+//!   snapshot written by an earlier version
+//!
+//! The field names it reports are the ones serde writes: `skip`, `rename`
+//! and the container's `rename_all` are honoured, including their
+//! `(serialize = "..")` forms.
+//!
+//! `#[derive(SharedType)]` implements `morpheus_core::shared::SharedType`
+//! for a host type generated components should use too: the type's
+//! definition, with its doc comments and `serde` attributes but nothing
+//! host-specific, becomes source code for the `morpheus_types` crate they
+//! build against.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::meta::ParseNestedMeta;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Fields, LitInt, LitStr, Path, Visibility};
+
+#[proc_macro_derive(MorpheusState, attributes(morpheus))]
+pub fn derive_morpheus_state(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(input, "MorpheusState can only be derived for structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            input,
+            "MorpheusState needs named fields, so snapshots can be restored field by field",
+        ));
+    };
+
+    let mut version = 1u32;
+    let mut migrate = None;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("morpheus")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("version") {
+                version = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+                Ok(())
+            } else if meta.path.is_ident("migrate") {
+                migrate = Some(meta.value()?.parse::<Path>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `version` or `migrate`"))
+            }
+        })?;
+    }
+
+    let rename_all = rename_all(&input.attrs)?;
+    let mut names = Vec::new();
+    let mut types = Vec::new();
+    for field in &fields.named {
+        let Some(name) = serialized_name(field, rename_all.as_deref())? else {
+            continue;
+        };
+        let ty = &field.ty;
+        names.push(name);
+        types.push(quote!(#ty).to_string().replace(' ', ""));
+    }
+
+    let migrate = migrate.map(|path| {
+        quote! {
+            fn migrate(
+                from: u32,
+                state: ::morpheus_core::__serde_json::Value,
+            ) -> ::morpheus_core::errors::Result<::morpheus_core::__serde_json::Value> {
+                #path(from, state)
+            }
+        }
+    });
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::morpheus_core::state::MorpheusState for #name #ty_generics #where_clause {
+            const VERSION: u32 = #version;
+            const FIELDS: &'static [(&'static str, &'static str)] = &[#((#names, #types)),*];
+            #migrate
+        }
+    })
+}
+
+#[proc_macro_derive(SharedType)]
+pub fn derive_shared_type(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_shared(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand_shared(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let source = shared_source(input.clone())?;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::morpheus_core::shared::SharedType for #name #ty_generics #where_clause {
+            const NAME: &'static str = #name_str;
+            const SOURCE: &'static str = #source;
+        }
+    })
+}
+
+/// The type's definition as components see it: public, deriving what the
+/// data contract needs, and keeping only doc comments and `serde`
+/// attributes, since other attributes may name the host's crates.
+fn shared_source(mut input: DeriveInput) -> syn::Result<String> {
+    fn keep_contract(attrs: &mut Vec<Attribute>) {
+        attrs.retain(|attr| attr.path().is_ident("doc") || attr.path().is_ident("serde"));
+    }
+
+    match &mut input.data {
+        Data::Struct(data) => {
+            for field in data.fields.iter_mut() {
+                keep_contract(&mut field.attrs);
+                field.vis = Visibility::Public(Default::default());
+            }
+        }
+        Data::Enum(data) => {
+            for variant in &mut data.variants {
+                keep_contract(&mut variant.attrs);
+                for field in variant.fields.iter_mut() {
+                    keep_contract(&mut field.attrs);
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(&input, "SharedType can only be derived for structs and enums"));
+        }
+    }
+    keep_contract(&mut input.attrs);
+    let docs = input.attrs.iter().take_while(|attr| attr.path().is_ident("doc")).count();
+    input.attrs.insert(
+        docs,
+        syn::parse_quote!(#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]),
+    );
+    input.vis = Visibility::Public(Default::default());
+
+    Ok(prettyplease::unparse(&syn::File {
+        shebang: None,
+        attrs: Vec::new(),
+        items: vec![input.into()],
+    }))
+}
+
+/// The name serde gives `field` under the container's `rename_all` rule,
+/// or `None` if serde skips it.
+fn serialized_name(field: &syn::Field, rename_all: Option<&str>) -> syn::Result<Option<String>> {
+    let ident = field.ident.as_ref().map(|ident| ident.unraw().to_string()).unwrap_or_default();
+    let mut name = None;
+    let mut skipped = false;
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                name = serialize_value(&meta)?.map(|lit| lit.value()).or(name.take());
+            } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_serializing") {
+                skipped = true;
+            } else {
+                skip_value(&meta)?;
+            }
+            Ok(())
+        })?;
+    }
+    let name = name.unwrap_or_else(|| match rename_all {
+        Some(rule) => rename_field(rule, &ident).unwrap_or(ident),
+        None => ident,
+    });
+    Ok((!skipped).then_some(name))
+}
+
+/// The container's `rename_all` rule for serializing, if it has one.
+fn rename_all(attrs: &[Attribute]) -> syn::Result<Option<String>> {
+    let mut rule = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("rename_all") {
+                return skip_value(&meta);
+            }
+            if let Some(lit) = serialize_value(&meta)? {
+                if rename_field(&lit.value(), "").is_none() {
+                    return Err(syn::Error::new_spanned(&lit, format!("unknown rename_all rule `{}`", lit.value())));
+                }
+                rule = Some(lit.value());
+            }
+            Ok(())
+        })?;
+    }
+    Ok(rule)
+}
+
+/// The serializing side of `name = ".."` or `name(serialize = "..", deserialize = "..")`.
+fn serialize_value(meta: &ParseNestedMeta) -> syn::Result<Option<LitStr>> {
+    if meta.input.peek(syn::Token![=]) {
+        return Ok(Some(meta.value()?.parse()?));
+    }
+    let mut value = None;
+    meta.parse_nested_meta(|inner| {
+        let lit: LitStr = inner.value()?.parse()?;
+        if inner.path.is_ident("serialize") {
+            value = Some(lit);
+        }
+        Ok(())
+    })?;
+    Ok(value)
+}
+
+/// Step over a `serde` attribute this macro doesn't need.
+fn skip_value(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        let content;
+        syn::parenthesized!(content in meta.input);
+        content.parse::<proc_macro2::TokenStream>()?;
+    }
+    Ok(())
+}
+
+/// `field`, a snake_case Rust name, as serde's `rename_all = rule` writes
+/// it, or `None` if serde has no such rule.
+fn rename_field(rule: &str, field: &str) -> Option<String> {
+    let pascal = || {
+        let mut capitalize = true;
+        let mut pascal = String::new();
+        for c in field.chars() {
+            if c == '_' {
+                capitalize = true;
+            } else if capitalize {
+                pascal.push(c.to_ascii_uppercase());
+                capitalize = false;
+            } else {
+                pascal.push(c);
+            }
+        }
+        pascal
+    };
+    Some(match rule {
+        "lowercase" | "snake_case" => field.to_string(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => field.to_ascii_uppercase(),
+        "PascalCase" => pascal(),
+        "camelCase" => {
+            let pascal = pascal();
+            let mut chars = pascal.chars();
+            chars.next().map(|first| first.to_ascii_lowercase().to_string() + chars.as_str()).unwrap_or_default()
+        }
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.to_ascii_uppercase().replace('_', "-"),
+        _ => return None,
+    })
+}
@@ -0,0 +1,908 @@
+//! Embeddable application facade.
+//!
+//! [`MorpheusApp`] owns the pieces a host application would otherwise wire
+//! up by hand: the [`ComponentRegistry`], a [`Compiler`], a [`Generator`]
+//! for AI code, the [`AppPolicy`] changes are held to, and an [`EventBus`]
+//! announcing what happened. Give it a [`SnapshotStore`] and each
+//! component's version history is saved under [`HISTORY_PREFIX`] and can be
+//! reloaded on the next start. [`ReloadHooks`] run around each hot reload
+//! of a component that is already on screen. Components translate their
+//! text through the host (see [`morpheus_core::i18n`]): give the app
+//! [`Catalogs`] and [`MorpheusApp::messages`] has what they should show in
+//! the current locale, which [`MorpheusApp::set_locale`] switches without
+//! regenerating anything. Their styles use the tokens of the active
+//! [`Theme`] likewise (see [`morpheus_core::theme`]), switched with
+//! [`MorpheusApp::set_theme`]; by default it is the light or dark theme,
+//! following the [`ColorScheme`] the host reports with
+//! [`MorpheusApp::set_color_scheme`] (see [`morpheus_core::media`]).
+//!
+//! ```rust,ignore
+//! use morpheus_compiler::SubprocessCompiler;
+//! use morpheus_runtime::app::MorpheusApp;
+//!
+//! let mut app = MorpheusApp::new(SubprocessCompiler::new().await?, MyLlm::new());
+//! let events = app.events().subscribe();
+//!
+//! let counter = app.request_modification("A counter with + and - buttons").await?;
+//! app.modify(counter.component, "Add a reset button").await?;
+//!
+//! // "Actually, undo that"
+//! app.rollback(counter.component).await?;
+//! ```
+
+use crate::wasm_loader::WasmComponent;
+use crate::ComponentRegistry;
+use async_trait::async_trait;
+use morpheus_compiler::guardrails::Change;
+use morpheus_compiler::{Compiler, Guardrails, Violation};
+use morpheus_core::codec::Format;
+use morpheus_core::component::{ComponentId, ComponentMetadata, Provenance};
+use morpheus_core::errors::{MorpheusError, Result};
+use morpheus_core::i18n::{self, Catalogs, MessageCatalog, DEFAULT_LOCALE};
+use morpheus_core::permissions::Permissions;
+use morpheus_core::semver::{Bump, SemVer};
+use morpheus_core::store::SnapshotStore;
+use morpheus_core::media::ColorScheme;
+use morpheus_core::theme::{Theme, Themes};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+
+/// Key prefix for saved component histories.
+pub const HISTORY_PREFIX: &str = "history/";
+
+/// Writes component source code from a prompt, usually by calling an LLM.
+#[async_trait]
+pub trait Generator: Send + Sync {
+    /// Generate the complete Rust source of a component.
+    async fn generate(&self, request: &GenerationRequest) -> Result<String>;
+
+    /// Model name recorded in component provenance.
+    fn model(&self) -> &str {
+        "unknown"
+    }
+}
+
+/// What a [`Generator`] is asked to produce.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationRequest {
+    /// The user's request.
+    pub prompt: String,
+
+    /// Source of the version being modified (`None` for a new component).
+    pub current_source: Option<String>,
+
+    /// Compiler errors from the previous attempt, to be fixed.
+    pub errors: Vec<String>,
+
+    /// Attempt number, starting at 1.
+    pub attempt: u32,
+}
+
+/// Limits AI changes are held to.
+#[derive(Debug, Clone)]
+pub struct AppPolicy {
+    /// Permissions granted to generated components.
+    pub permissions: Permissions,
+
+    /// Budgets a change must stay within to be activated.
+    pub guardrails: Guardrails,
+
+    /// Generate-and-compile attempts before giving up.
+    pub max_attempts: u32,
+}
+
+impl Default for AppPolicy {
+    fn default() -> Self {
+        Self {
+            permissions: Permissions::default(),
+            guardrails: Guardrails::default(),
+            max_attempts: 3,
+        }
+    }
+}
+
+/// Something that happened to the app's components.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppEvent {
+    /// An attempt failed to compile and will be retried if attempts remain.
+    CompileFailed { attempt: u32, error: String },
+
+    /// A compiled change exceeded the guardrails and was not activated.
+    Rejected {
+        component: Option<ComponentId>,
+        violations: Vec<Violation>,
+    },
+
+    /// A new or modified component went live.
+    Reloaded { component: ComponentId, version: SemVer },
+
+    /// A component went back to its previous source.
+    RolledBack { component: ComponentId, version: SemVer },
+
+    /// The app switched locales; components should re-render with its
+    /// messages.
+    LocaleChanged { locale: String },
+
+    /// The theme components are styled with changed; hosts should apply
+    /// its tokens.
+    ThemeChanged { theme: String },
+
+    /// The user's OS switched between light and dark mode.
+    ColorSchemeChanged { scheme: ColorScheme },
+}
+
+/// Callbacks around hot reloads of components already on screen.
+///
+/// A reload replaces the component under whoever is using it; hooks let the
+/// host pause input or start a transition first, persist what the user was
+/// typing, and resume afterwards. They run in the order they were added
+/// with [`MorpheusApp::with_hooks`], synchronously, in the middle of the
+/// change.
+pub trait ReloadHooks: Send + Sync {
+    /// Before `component`'s module is replaced. An error cancels the reload:
+    /// the component keeps running its current version.
+    fn before_reload(&self, _component: ComponentId) -> Result<()> {
+        Ok(())
+    }
+
+    /// After `component` has been reloaded, now at `version`.
+    fn after_reload(&self, _component: ComponentId, _version: SemVer) {}
+
+    /// When reloading `component` failed or was cancelled; it still runs the
+    /// version it ran before.
+    fn reload_error(&self, _component: ComponentId, _error: &MorpheusError) {}
+}
+
+/// Fan-out of [`AppEvent`]s to any number of subscribers.
+///
+/// Subscribers that have dropped their receiver are forgotten on the next
+/// publish.
+#[derive(Debug, Default)]
+pub struct EventBus {
+    subscribers: Mutex<Vec<Sender<AppEvent>>>,
+}
+
+impl EventBus {
+    /// Receive every event published from now on.
+    pub fn subscribe(&self) -> Receiver<AppEvent> {
+        let (sender, receiver) = channel();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// Send an event to every live subscriber.
+    pub fn publish(&self, event: AppEvent) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
+
+/// Result of an activated modification.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Modification {
+    /// The component that was created or changed.
+    pub component: ComponentId,
+
+    /// Its version after the change.
+    pub version: SemVer,
+
+    /// Generate-and-compile attempts it took.
+    pub attempts: u32,
+}
+
+/// One activated version of a component.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Revision {
+    /// Rust source the module was compiled from.
+    pub source: String,
+
+    /// Compiled WASM module.
+    pub wasm: Vec<u8>,
+
+    /// JavaScript glue generated by wasm-bindgen.
+    #[serde(default)]
+    pub js_glue: String,
+
+    /// Permissions the version was granted.
+    pub permissions: Permissions,
+}
+
+/// A component's saved history.
+#[derive(Serialize, Deserialize)]
+struct StoredHistory {
+    metadata: ComponentMetadata,
+    revisions: Vec<Revision>,
+}
+
+/// A self-modifying application.
+pub struct MorpheusApp {
+    registry: ComponentRegistry,
+    compiler: Box<dyn Compiler + Send + Sync>,
+    generator: Box<dyn Generator>,
+    policy: AppPolicy,
+    events: EventBus,
+    store: Option<Box<dyn SnapshotStore>>,
+    hooks: Vec<Box<dyn ReloadHooks>>,
+    catalogs: Catalogs,
+    locale: String,
+    themes: Themes,
+    color_scheme: ColorScheme,
+
+    /// Activated revisions per component, oldest first.
+    revisions: HashMap<ComponentId, Vec<Revision>>,
+
+    next_id: u64,
+}
+
+impl MorpheusApp {
+    /// Create an app with the default policy.
+    pub fn new(compiler: impl Compiler + Send + Sync + 'static, generator: impl Generator + 'static) -> Self {
+        Self {
+            registry: ComponentRegistry::new(),
+            compiler: Box::new(compiler),
+            generator: Box::new(generator),
+            policy: AppPolicy::default(),
+            events: EventBus::default(),
+            store: None,
+            hooks: Vec::new(),
+            catalogs: Catalogs::new(),
+            locale: DEFAULT_LOCALE.to_string(),
+            themes: Themes::new(),
+            color_scheme: ColorScheme::default(),
+            revisions: HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Set the policy changes are held to.
+    pub fn with_policy(mut self, policy: AppPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Save component histories to `store`.
+    pub fn with_store(mut self, store: impl SnapshotStore + 'static) -> Self {
+        self.store = Some(Box::new(store));
+        self
+    }
+
+    /// Run `hooks` around every hot reload.
+    pub fn with_hooks(mut self, hooks: impl ReloadHooks + 'static) -> Self {
+        self.hooks.push(Box::new(hooks));
+        self
+    }
+
+    /// Translate components' text with `catalogs`.
+    pub fn with_catalogs(mut self, catalogs: Catalogs) -> Self {
+        self.catalogs = catalogs;
+        self
+    }
+
+    /// The locale components' text is shown in.
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Show components' text in `locale` from now on.
+    pub fn set_locale(&mut self, locale: &str) {
+        if self.locale == locale {
+            return;
+        }
+        self.locale = locale.to_string();
+        self.events.publish(AppEvent::LocaleChanged {
+            locale: locale.to_string(),
+        });
+    }
+
+    /// Every message the live components use, in the current locale: the
+    /// default texts in their source, translated where the catalogs can.
+    pub fn messages(&self) -> MessageCatalog {
+        let mut defaults = MessageCatalog::new(DEFAULT_LOCALE);
+        for revisions in self.revisions.values() {
+            if let Some(current) = revisions.last() {
+                for (key, text) in i18n::extract(&current.source).messages {
+                    defaults.messages.entry(key).or_insert(text);
+                }
+            }
+        }
+        self.catalogs.resolve(&self.locale, &defaults)
+    }
+
+    /// Offer `themes` to components instead of the built-in light and dark.
+    pub fn with_themes(mut self, themes: Themes) -> Self {
+        self.themes = themes;
+        self
+    }
+
+    /// The theme whose tokens components are styled with, for the user's
+    /// color scheme.
+    pub fn theme(&self) -> &Theme {
+        self.themes.for_scheme(self.color_scheme)
+    }
+
+    /// Style components with the theme named `name` from now on, or with
+    /// the one for the user's color scheme with
+    /// [`SYSTEM_THEME`](morpheus_core::theme::SYSTEM_THEME).
+    pub fn set_theme(&mut self, name: &str) -> Result<()> {
+        if self.themes.active_name() == name {
+            return Ok(());
+        }
+        let before = self.theme().name.clone();
+        self.themes.set_theme(name)?;
+        self.announce_theme(before);
+        Ok(())
+    }
+
+    /// The user's color scheme, as last reported by the host.
+    pub fn color_scheme(&self) -> ColorScheme {
+        self.color_scheme
+    }
+
+    /// Record that the user's OS switched to `scheme` (e.g. from a
+    /// [`MediaWatch`](morpheus_core::media::MediaWatch)), changing theme if
+    /// the app follows it.
+    pub fn set_color_scheme(&mut self, scheme: ColorScheme) {
+        if self.color_scheme == scheme {
+            return;
+        }
+        let before = self.theme().name.clone();
+        self.color_scheme = scheme;
+        self.events.publish(AppEvent::ColorSchemeChanged { scheme });
+        self.announce_theme(before);
+    }
+
+    /// Publish the theme if it is no longer the one named `before`.
+    fn announce_theme(&self, before: String) {
+        let theme = &self.theme().name;
+        if *theme != before {
+            self.events.publish(AppEvent::ThemeChanged { theme: theme.clone() });
+        }
+    }
+
+    /// The policy changes are held to.
+    pub fn policy(&self) -> &AppPolicy {
+        &self.policy
+    }
+
+    /// Events published by the app.
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// The underlying registry, for manifests, flags and slot resolution.
+    pub fn registry(&self) -> &ComponentRegistry {
+        &self.registry
+    }
+
+    /// Mutable access to the underlying registry.
+    pub fn registry_mut(&mut self) -> &mut ComponentRegistry {
+        &mut self.registry
+    }
+
+    /// Metadata of every loaded component.
+    pub fn components(&self) -> Vec<ComponentMetadata> {
+        let mut components: Vec<_> = self.registry.list().cloned().collect();
+        components.sort_by_key(|metadata| metadata.id.0);
+        components
+    }
+
+    /// A component's live version.
+    pub fn current(&self, id: &ComponentId) -> Option<&Revision> {
+        self.revisions.get(id)?.last()
+    }
+
+    /// Source of a component's live version.
+    pub fn source(&self, id: &ComponentId) -> Option<&str> {
+        self.current(id).map(|r| r.source.as_str())
+    }
+
+    /// Load every component history saved in the store, returning how many
+    /// components were restored.
+    ///
+    /// Components already loaded are left alone.
+    pub async fn load_history(&mut self) -> Result<usize> {
+        let Some(store) = &self.store else {
+            return Ok(0);
+        };
+        let mut histories = Vec::new();
+        for key in store.list(HISTORY_PREFIX).await? {
+            if let Some(bytes) = store.get(&key).await? {
+                histories.push(Format::MessagePack.from_slice::<StoredHistory>(&bytes)?);
+            }
+        }
+
+        let mut restored = 0;
+        for history in histories {
+            let id = history.metadata.id;
+            let Some(current) = history.revisions.last() else {
+                continue;
+            };
+            if self.revisions.contains_key(&id) {
+                continue;
+            }
+            let component = WasmComponent::load(&current.wasm, current.permissions.clone()).await?;
+            self.registry.register(id, component, history.metadata);
+            self.revisions.insert(id, history.revisions);
+            self.next_id = self.next_id.max(id.0 + 1);
+            restored += 1;
+        }
+        Ok(restored)
+    }
+
+    /// Generate, compile and load a new component.
+    pub async fn request_modification(&mut self, prompt: &str) -> Result<Modification> {
+        self.apply(None, prompt).await
+    }
+
+    /// Generate, compile and hot-reload a change to an existing component.
+    pub async fn modify(&mut self, id: ComponentId, prompt: &str) -> Result<Modification> {
+        if !self.revisions.contains_key(&id) {
+            return Err(MorpheusError::InvalidState(format!("Unknown component {}", id)));
+        }
+        self.apply(Some(id), prompt).await
+    }
+
+    /// Reload a component's previous version.
+    pub async fn rollback(&mut self, id: ComponentId) -> Result<Modification> {
+        let revisions = self
+            .revisions
+            .get(&id)
+            .ok_or_else(|| MorpheusError::InvalidState(format!("Unknown component {}", id)))?;
+        if revisions.len() < 2 {
+            return Err(MorpheusError::InvalidState(format!(
+                "Component {} has no earlier version",
+                id
+            )));
+        }
+        let wasm = revisions[revisions.len() - 2].wasm.clone();
+
+        let version = self.reload(id, &wasm, None).await?;
+        if let Some(revisions) = self.revisions.get_mut(&id) {
+            revisions.pop();
+        }
+        self.save_history(id).await?;
+        self.events.publish(AppEvent::RolledBack { component: id, version });
+        Ok(Modification {
+            component: id,
+            version,
+            attempts: 0,
+        })
+    }
+
+    async fn apply(&mut self, target: Option<ComponentId>, prompt: &str) -> Result<Modification> {
+        let current = target.and_then(|id| self.revisions.get(&id)?.last());
+        let mut request = GenerationRequest {
+            prompt: prompt.to_string(),
+            current_source: current.map(|r| r.source.clone()),
+            errors: Vec::new(),
+            attempt: 0,
+        };
+
+        let max_attempts = self.policy.max_attempts.max(1);
+        let (source, compiled) = loop {
+            request.attempt += 1;
+            let source = self.generator.generate(&request).await?;
+            match self.compiler.compile(&source).await {
+                Ok(compiled) => break (compiled.fixed_source.clone().unwrap_or(source), compiled),
+                Err(e) => {
+                    let error = e.to_string();
+                    self.events.publish(AppEvent::CompileFailed {
+                        attempt: request.attempt,
+                        error: error.clone(),
+                    });
+                    if request.attempt >= max_attempts {
+                        return Err(MorpheusError::CompilationError(format!(
+                            "Gave up after {} attempts: {}",
+                            request.attempt, error
+                        )));
+                    }
+                    request.errors = vec![error];
+                }
+            }
+        };
+
+        let current = target.and_then(|id| self.revisions.get(&id)?.last());
+        let violations = self.policy.guardrails.evaluate(&Change {
+            old_source: current.map(|r| r.source.as_str()),
+            new_source: &source,
+            old_wasm_size: current.map(|r| r.wasm.len()),
+            new_wasm_size: compiled.wasm_bytes.len(),
+            old_permissions: current.map(|r| &r.permissions),
+            new_permissions: &self.policy.permissions,
+        });
+        if !violations.is_empty() {
+            let reasons: Vec<_> = violations.iter().map(Violation::to_string).collect();
+            self.events.publish(AppEvent::Rejected {
+                component: target,
+                violations,
+            });
+            return Err(MorpheusError::PermissionDenied(format!(
+                "Change exceeds guardrails: {}",
+                reasons.join("; ")
+            )));
+        }
+
+        let provenance = Provenance::ai(prompt, self.generator.model());
+        let (id, version) = match target {
+            Some(id) => (id, self.reload(id, &compiled.wasm_bytes, Some(provenance)).await?),
+            None => {
+                let id = ComponentId(self.next_id);
+                self.next_id += 1;
+                let component = WasmComponent::load(&compiled.wasm_bytes, self.policy.permissions.clone()).await?;
+                let metadata = ComponentMetadata {
+                    id,
+                    name: format!("component-{}", id.0),
+                    ai_generated: true,
+                    provenance,
+                    ..component.metadata().clone()
+                };
+                let version = metadata.version;
+                self.registry.register(id, component, metadata);
+                (id, version)
+            }
+        };
+
+        self.revisions.entry(id).or_default().push(Revision {
+            source,
+            wasm: compiled.wasm_bytes,
+            js_glue: compiled.js_glue,
+            permissions: self.policy.permissions.clone(),
+        });
+        self.save_history(id).await?;
+        self.events.publish(AppEvent::Reloaded { component: id, version });
+        Ok(Modification {
+            component: id,
+            version,
+            attempts: request.attempt,
+        })
+    }
+
+    /// Write a component's history to the store, if there is one.
+    async fn save_history(&self, id: ComponentId) -> Result<()> {
+        let (Some(store), Some(metadata), Some(revisions)) =
+            (&self.store, self.registry.metadata(&id), self.revisions.get(&id))
+        else {
+            return Ok(());
+        };
+        let history = StoredHistory {
+            metadata: metadata.clone(),
+            revisions: revisions.clone(),
+        };
+        let key = format!("{}{}", HISTORY_PREFIX, id);
+        store.put(&key, Format::MessagePack.to_vec(&history)?).await
+    }
+
+    /// Hot-reload a registered component, bumping its version, with the
+    /// hooks around it.
+    async fn reload(&mut self, id: ComponentId, wasm: &[u8], provenance: Option<Provenance>) -> Result<SemVer> {
+        let reloaded = match self.hooks.iter().try_for_each(|hooks| hooks.before_reload(id)) {
+            Ok(()) => self.replace_module(id, wasm, provenance).await,
+            Err(e) => Err(e),
+        };
+        match &reloaded {
+            Ok(version) => self.hooks.iter().for_each(|hooks| hooks.after_reload(id, *version)),
+            Err(e) => self.hooks.iter().for_each(|hooks| hooks.reload_error(id, e)),
+        }
+        reloaded
+    }
+
+    /// Swap a registered component's module, bumping its version.
+    async fn replace_module(&mut self, id: ComponentId, wasm: &[u8], provenance: Option<Provenance>) -> Result<SemVer> {
+        let mut metadata = self
+            .registry
+            .metadata(&id)
+            .cloned()
+            .ok_or_else(|| MorpheusError::InvalidState(format!("Unknown component {}", id)))?;
+        let mut component = self
+            .registry
+            .remove(&id)
+            .ok_or_else(|| MorpheusError::InvalidState(format!("Unknown component {}", id)))?;
+
+        let reloaded = component.reload(wasm).await;
+        if reloaded.is_ok() {
+            metadata.version = metadata.version.bump(Bump::Patch);
+            if let Some(provenance) = provenance {
+                // Parents are numbered by revision, as versions were before
+                let revision = self.revisions.get(&id).map_or(0, Vec::len);
+                metadata.provenance = provenance.with_parent(revision as u32);
+            }
+        }
+        let version = metadata.version;
+        self.registry.register(id, component, metadata);
+        reloaded.map(|_| version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morpheus_compiler::CompilationResult;
+    use morpheus_core::store::MemoryStore;
+    use std::sync::Arc;
+
+    /// Compiles anything without "broken" in it; the "WASM" is the source.
+    struct FakeCompiler;
+
+    #[async_trait]
+    impl Compiler for FakeCompiler {
+        async fn compile(&self, source: &str) -> Result<CompilationResult> {
+            self.check(source).await?;
+            Ok(CompilationResult {
+                wasm_bytes: source.as_bytes().to_vec(),
+                js_glue: String::new(),
+                snapshot: None,
+                sbom: None,
+                diagnostics: Vec::new(),
+                fixed_source: None,
+                variants: Vec::new(),
+                verification: None,
+            })
+        }
+
+        async fn check(&self, source: &str) -> Result<()> {
+            if source.contains("broken") {
+                return Err(MorpheusError::CompilationError("expected `;`".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    /// Replays scripted responses and records the requests it saw.
+    #[derive(Clone, Default)]
+    struct ScriptedGenerator {
+        responses: Arc<Mutex<Vec<String>>>,
+        requests: Arc<Mutex<Vec<GenerationRequest>>>,
+    }
+
+    impl ScriptedGenerator {
+        fn new(responses: &[&str]) -> Self {
+            let generator = Self::default();
+            generator
+                .responses
+                .lock()
+                .unwrap()
+                .extend(responses.iter().rev().map(|s| s.to_string()));
+            generator
+        }
+    }
+
+    #[async_trait]
+    impl Generator for ScriptedGenerator {
+        async fn generate(&self, request: &GenerationRequest) -> Result<String> {
+            self.requests.lock().unwrap().push(request.clone());
+            self.responses
+                .lock()
+                .unwrap()
+                .pop()
+                .ok_or_else(|| MorpheusError::Other("script exhausted".to_string()))
+        }
+
+        fn model(&self) -> &str {
+            "scripted"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_modify_and_rollback() {
+        let mut app = MorpheusApp::new(FakeCompiler, ScriptedGenerator::new(&["fn v1() {}", "fn v2() {}"]));
+        let events = app.events().subscribe();
+
+        let created = app.request_modification("a counter").await.unwrap();
+        assert_eq!((created.version, created.attempts), (SemVer::INITIAL, 1));
+
+        // Without a described interface, a reload is a patch
+        let modified = app.modify(created.component, "add reset").await.unwrap();
+        assert_eq!(modified.version, SemVer::new(1, 0, 1));
+        assert_eq!(app.source(&created.component), Some("fn v2() {}"));
+
+        let components = app.components();
+        assert_eq!(components.len(), 1);
+        assert!(components[0].ai_generated);
+        assert_eq!(components[0].provenance.prompt.as_deref(), Some("add reset"));
+
+        let rolled_back = app.rollback(created.component).await.unwrap();
+        assert_eq!(rolled_back.version, SemVer::new(1, 0, 2));
+        assert_eq!(app.source(&created.component), Some("fn v1() {}"));
+        assert!(app.rollback(created.component).await.is_err());
+
+        let events: Vec<_> = events.try_iter().collect();
+        assert_eq!(
+            events,
+            vec![
+                AppEvent::Reloaded { component: created.component, version: SemVer::new(1, 0, 0) },
+                AppEvent::Reloaded { component: created.component, version: SemVer::new(1, 0, 1) },
+                AppEvent::RolledBack { component: created.component, version: SemVer::new(1, 0, 2) },
+            ]
+        );
+    }
+
+    /// Records the hooks it saw; refuses reloads while `busy` is set.
+    #[derive(Clone, Default)]
+    struct RecordingHooks {
+        calls: Arc<Mutex<Vec<String>>>,
+        busy: Arc<Mutex<bool>>,
+    }
+
+    impl ReloadHooks for RecordingHooks {
+        fn before_reload(&self, component: ComponentId) -> Result<()> {
+            self.calls.lock().unwrap().push(format!("before {}", component.0));
+            match *self.busy.lock().unwrap() {
+                true => Err(MorpheusError::InvalidState("user is dragging".to_string())),
+                false => Ok(()),
+            }
+        }
+
+        fn after_reload(&self, component: ComponentId, version: SemVer) {
+            self.calls.lock().unwrap().push(format!("after {} {}", component.0, version));
+        }
+
+        fn reload_error(&self, component: ComponentId, error: &MorpheusError) {
+            self.calls.lock().unwrap().push(format!("error {} {}", component.0, error));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks_run_around_reloads_and_can_cancel_them() {
+        let hooks = RecordingHooks::default();
+        let generator = ScriptedGenerator::new(&["fn v1() {}", "fn v2() {}", "fn v3() {}"]);
+        let mut app = MorpheusApp::new(FakeCompiler, generator).with_hooks(hooks.clone());
+
+        // Creating a component isn't a reload
+        let created = app.request_modification("a counter").await.unwrap();
+        assert!(hooks.calls.lock().unwrap().is_empty());
+
+        app.modify(created.component, "add reset").await.unwrap();
+        *hooks.busy.lock().unwrap() = true;
+        assert!(app.modify(created.component, "add undo").await.is_err());
+        assert!(app.rollback(created.component).await.is_err());
+        assert_eq!(app.source(&created.component), Some("fn v2() {}"));
+
+        let id = created.component.0;
+        assert_eq!(
+            *hooks.calls.lock().unwrap(),
+            [
+                format!("before {}", id),
+                format!("after {} 1.0.1", id),
+                format!("before {}", id),
+                format!("error {} Invalid state: user is dragging", id),
+                format!("before {}", id),
+                format!("error {} Invalid state: user is dragging", id),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compile_errors_are_fed_back() {
+        let generator = ScriptedGenerator::new(&["fn broken(", "fn fixed() {}"]);
+        let mut app = MorpheusApp::new(FakeCompiler, generator.clone());
+
+        let created = app.request_modification("a counter").await.unwrap();
+
+        assert_eq!(created.attempts, 2);
+        let requests = generator.requests.lock().unwrap();
+        assert!(requests[0].errors.is_empty());
+        assert_eq!(requests[1].attempt, 2);
+        assert!(requests[1].errors[0].contains("expected `;`"));
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let policy = AppPolicy {
+            max_attempts: 2,
+            ..AppPolicy::default()
+        };
+        let mut app = MorpheusApp::new(FakeCompiler, ScriptedGenerator::new(&["broken", "still broken", "ok"]))
+            .with_policy(policy);
+
+        let error = app.request_modification("a counter").await.unwrap_err();
+
+        assert!(matches!(error, MorpheusError::CompilationError(msg) if msg.contains("2 attempts")));
+        assert!(app.components().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_guardrail_violations_are_not_activated() {
+        let policy = AppPolicy {
+            guardrails: Guardrails::default().with_max_lines_changed(1),
+            ..AppPolicy::default()
+        };
+        let mut app = MorpheusApp::new(FakeCompiler, ScriptedGenerator::new(&["fn a() {}", "fn b() {}\nfn c() {}"]))
+            .with_policy(policy);
+        let events = app.events().subscribe();
+        let created = app.request_modification("a counter").await.unwrap();
+
+        let error = app.modify(created.component, "rewrite it").await.unwrap_err();
+
+        assert!(matches!(error, MorpheusError::PermissionDenied(_)));
+        assert_eq!(app.source(&created.component), Some("fn a() {}"));
+        assert!(events
+            .try_iter()
+            .any(|e| matches!(e, AppEvent::Rejected { violations, .. } if !violations.is_empty())));
+    }
+
+    #[tokio::test]
+    async fn test_switching_locales_translates_without_regenerating() {
+        let source = r#"fn render() -> String { t("greeting", "Hello", "{}") }"#;
+        let mut catalogs = Catalogs::new();
+        catalogs.insert(MessageCatalog::new("es").with("greeting", "Hola"));
+        let mut app = MorpheusApp::new(FakeCompiler, ScriptedGenerator::new(&[source])).with_catalogs(catalogs);
+        let events = app.events().subscribe();
+        app.request_modification("a greeting").await.unwrap();
+        assert_eq!(app.messages().get("greeting"), Some("Hello"));
+
+        app.set_locale("es-MX");
+        app.set_locale("es-MX");
+
+        assert_eq!(app.locale(), "es-MX");
+        assert_eq!(app.messages().get("greeting"), Some("Hola"));
+        let switches: Vec<_> = events.try_iter().filter(|e| matches!(e, AppEvent::LocaleChanged { .. })).collect();
+        assert_eq!(switches, [AppEvent::LocaleChanged { locale: "es-MX".to_string() }]);
+    }
+
+    #[tokio::test]
+    async fn test_theme_switches_are_announced() {
+        let mut app = MorpheusApp::new(FakeCompiler, ScriptedGenerator::new(&[]));
+        let events = app.events().subscribe();
+
+        assert!(app.set_theme("neon").is_err());
+        app.set_theme("dark").unwrap();
+        app.set_theme("dark").unwrap();
+
+        assert_eq!(app.theme().token("color.background"), Some("#0f172a"));
+        assert_eq!(events.try_iter().collect::<Vec<_>>(), [AppEvent::ThemeChanged { theme: "dark".to_string() }]);
+    }
+
+    #[tokio::test]
+    async fn test_system_theme_follows_the_color_scheme() {
+        let mut app = MorpheusApp::new(FakeCompiler, ScriptedGenerator::new(&[]));
+        let events = app.events().subscribe();
+
+        assert_eq!(app.theme().name, "light");
+        app.set_color_scheme(ColorScheme::Dark);
+        app.set_color_scheme(ColorScheme::Dark);
+        assert_eq!(app.theme().name, "dark");
+
+        // A chosen theme stays whatever the OS does
+        app.set_theme("light").unwrap();
+        app.set_color_scheme(ColorScheme::Light);
+        app.set_color_scheme(ColorScheme::Dark);
+        assert_eq!(app.color_scheme(), ColorScheme::Dark);
+        assert_eq!(app.theme().name, "light");
+
+        let dark = ColorScheme::Dark;
+        let theme = |name: &str| AppEvent::ThemeChanged { theme: name.to_string() };
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            [
+                AppEvent::ColorSchemeChanged { scheme: dark },
+                theme("dark"),
+                theme("light"),
+                AppEvent::ColorSchemeChanged { scheme: ColorScheme::Light },
+                AppEvent::ColorSchemeChanged { scheme: dark },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_history_survives_restart() {
+        let store = Arc::new(MemoryStore::new());
+        let mut app = MorpheusApp::new(FakeCompiler, ScriptedGenerator::new(&["fn v1() {}", "fn v2() {}"]))
+            .with_store(store.clone());
+        let created = app.request_modification("a counter").await.unwrap();
+        app.modify(created.component, "add reset").await.unwrap();
+
+        let mut restarted = MorpheusApp::new(FakeCompiler, ScriptedGenerator::new(&["fn v3() {}"])).with_store(store);
+        assert_eq!(restarted.load_history().await.unwrap(), 1);
+
+        assert_eq!(restarted.components()[0].version, SemVer::new(1, 0, 1));
+        assert_eq!(restarted.source(&created.component), Some("fn v2() {}"));
+        restarted.rollback(created.component).await.unwrap();
+        assert_eq!(restarted.source(&created.component), Some("fn v1() {}"));
+        let another = restarted.request_modification("a clock").await.unwrap();
+        assert_ne!(another.component, created.component);
+    }
+}
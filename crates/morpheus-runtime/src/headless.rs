@@ -0,0 +1,519 @@
+//! Headless components: backend logic executed under wasmtime.
+//!
+//! Not every component renders UI. HTTP handlers and data transforms can be
+//! generated, compiled, hot-reloaded and rolled back like any other
+//! component, but run on the server instead of in a browser.
+//!
+//! Headless modules are built for [`Target::Headless`] and import nothing,
+//! so a module can only compute: it has no clock, filesystem, network or
+//! randomness. Each call runs in a fresh instance with bounded fuel and
+//! memory, so a handler keeps no state between requests and a runaway loop
+//! can't take the server down.
+//!
+//! Identical modules are compiled and pre-instantiated once and shared (see
+//! [`crate::modules`]), and instances map their initial memory
+//! copy-on-write, so per-call instances are cheap. A [`CompiledCache`]
+//! keeps the machine code on disk too, so restarts and reloads of known
+//! modules skip compiling altogether.
+//!
+//! ## ABI
+//!
+//! Requests and responses cross the boundary as JSON. A module exports:
+//!
+//! - `memory`
+//! - `morpheus_alloc(len: i32) -> i32`, returning a buffer the host writes
+//!   the input into
+//! - `morpheus_handle(ptr: i32, len: i32) -> i64`, taking an
+//!   [`HttpRequest`] and returning an [`HttpResponse`], and/or
+//!   `morpheus_transform(ptr: i32, len: i32) -> i64`, taking and returning
+//!   any JSON value
+//!
+//! The `i64` result packs the output's pointer into the high 32 bits and its
+//! length into the low 32 bits.
+//!
+//! [`Target::Headless`]: morpheus_compiler::Target::Headless
+
+use crate::modules::ModuleCache;
+use morpheus_core::errors::{MorpheusError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use wasmtime::{
+    Config, Engine, InstancePre, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, Trap,
+};
+
+/// Export allocating input buffers.
+pub const ALLOC_EXPORT: &str = "morpheus_alloc";
+
+/// Export handling HTTP requests.
+pub const HANDLE_EXPORT: &str = "morpheus_handle";
+
+/// Export transforming JSON values.
+pub const TRANSFORM_EXPORT: &str = "morpheus_transform";
+
+/// An HTTP request dispatched to a component.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HttpRequest {
+    /// Method, e.g. `GET`.
+    pub method: String,
+
+    /// Path below the component's mount point, starting with `/`.
+    pub path: String,
+
+    /// Query string, without the `?`.
+    #[serde(default)]
+    pub query: String,
+
+    /// Headers, with lowercase names.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// Body, as text.
+    #[serde(default)]
+    pub body: String,
+}
+
+/// A component's response to an [`HttpRequest`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpResponse {
+    /// Status code.
+    #[serde(default = "default_status")]
+    pub status: u16,
+
+    /// Headers to send.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// Body, as text.
+    #[serde(default)]
+    pub body: String,
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+/// Resources a single call may use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeadlessLimits {
+    /// Fuel per call; roughly one unit per WASM instruction.
+    pub fuel: u64,
+
+    /// Maximum linear memory, in bytes.
+    pub memory_bytes: usize,
+}
+
+impl Default for HeadlessLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 100_000_000,
+            memory_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// Engine shared by all headless components.
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        // Instances share the module's initial memory until they write to it
+        config.memory_init_cow(true);
+        Engine::new(&config).expect("default wasmtime configuration is valid")
+    })
+}
+
+/// Compiled modules shared by all headless components.
+fn modules() -> &'static ModuleCache<Compiled> {
+    static MODULES: OnceLock<ModuleCache<Compiled>> = OnceLock::new();
+    MODULES.get_or_init(ModuleCache::new)
+}
+
+/// Compiled machine code of headless modules, in a directory, by the
+/// SHA-256 of their WASM bytes.
+///
+/// Files wasmtime can't use (written by another wasmtime version or engine
+/// configuration, or damaged) are compiled again and replaced. Loading a
+/// file runs its machine code as is, so the directory must only be
+/// writable by the server.
+#[derive(Debug, Clone)]
+pub struct CompiledCache {
+    dir: PathBuf,
+}
+
+impl CompiledCache {
+    /// Cache compiled modules in `dir`, creating it if needed.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| MorpheusError::Other(format!("Can't create {}: {}", dir.display(), e)))?;
+        Ok(Self { dir })
+    }
+
+    /// Directory the compiled modules are kept in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, wasm_bytes: &[u8]) -> PathBuf {
+        self.dir.join(format!("{}.cwasm", hex::encode(Sha256::digest(wasm_bytes))))
+    }
+
+    /// The cached module compiled from `wasm_bytes`, if there is a usable one.
+    fn get(&self, wasm_bytes: &[u8]) -> Option<Module> {
+        let path = self.path(wasm_bytes);
+        if !path.exists() {
+            return None;
+        }
+        // SAFETY: files in the cache directory are only written by `put`,
+        // from `Module::serialize`, and wasmtime rejects files produced by
+        // an incompatible version or configuration.
+        unsafe { Module::deserialize_file(engine(), &path) }.ok()
+    }
+
+    /// Cache `module`, compiled from `wasm_bytes`. Best effort: a module that
+    /// can't be written is compiled again next time.
+    fn put(&self, wasm_bytes: &[u8], module: &Module) {
+        let Ok(compiled) = module.serialize() else {
+            return;
+        };
+        // Write to a temporary file first, so readers never see a partial one
+        let path = self.path(wasm_bytes);
+        let partial = path.with_extension(format!("{}.partial", std::process::id()));
+        if std::fs::write(&partial, compiled).and_then(|_| std::fs::rename(&partial, &path)).is_err() {
+            let _ = std::fs::remove_file(&partial);
+        }
+    }
+}
+
+/// A module compiled, checked against the ABI and ready to instantiate.
+struct Compiled {
+    instance_pre: InstancePre<StoreLimits>,
+    handles_http: bool,
+    transforms: bool,
+}
+
+/// A compiled headless module, ready to be called.
+///
+/// Cheap to call from several threads at once; calls are synchronous, so
+/// async servers should run them on a blocking thread.
+#[derive(Clone)]
+pub struct HeadlessComponent {
+    compiled: Arc<Compiled>,
+    limits: HeadlessLimits,
+}
+
+impl std::fmt::Debug for HeadlessComponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HeadlessComponent")
+            .field("limits", &self.limits)
+            .field("handles_http", &self.compiled.handles_http)
+            .field("transforms", &self.compiled.transforms)
+            .finish()
+    }
+}
+
+impl HeadlessComponent {
+    /// Compile a headless module, checking it follows the ABI, or share the
+    /// module already compiled from the same bytes.
+    pub fn load(wasm_bytes: &[u8]) -> Result<Self> {
+        let compiled = modules().get_or_compile(wasm_bytes, |bytes| Compiled::new(bytes, None))?;
+        Ok(Self {
+            compiled,
+            limits: HeadlessLimits::default(),
+        })
+    }
+
+    /// Like [`Self::load`], but use machine code compiled earlier from the
+    /// same bytes from `cache`, and cache what has to be compiled.
+    pub fn load_cached(wasm_bytes: &[u8], cache: &CompiledCache) -> Result<Self> {
+        let compiled = modules().get_or_compile(wasm_bytes, |bytes| Compiled::new(bytes, Some(cache)))?;
+        Ok(Self {
+            compiled,
+            limits: HeadlessLimits::default(),
+        })
+    }
+
+    /// Set the resources each call may use.
+    pub fn with_limits(mut self, limits: HeadlessLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Resources each call may use.
+    pub fn limits(&self) -> HeadlessLimits {
+        self.limits
+    }
+
+    /// Whether the module handles HTTP requests.
+    pub fn handles_http(&self) -> bool {
+        self.compiled.handles_http
+    }
+
+    /// Whether the module transforms JSON values.
+    pub fn transforms(&self) -> bool {
+        self.compiled.transforms
+    }
+
+    /// Whether this component runs the same compiled module as `other`.
+    pub fn shares_module_with(&self, other: &HeadlessComponent) -> bool {
+        Arc::ptr_eq(&self.compiled, &other.compiled)
+    }
+
+    /// Handle an HTTP request.
+    pub fn handle(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        if !self.compiled.handles_http {
+            return Err(MorpheusError::InvalidState("Component doesn't handle HTTP requests".to_string()));
+        }
+        let output = self.call(HANDLE_EXPORT, &serde_json::to_vec(request)?)?;
+        serde_json::from_slice(&output)
+            .map_err(|e| MorpheusError::Other(format!("Component returned an invalid response: {}", e)))
+    }
+
+    /// Transform a JSON value.
+    pub fn transform(&self, input: &Value) -> Result<Value> {
+        if !self.compiled.transforms {
+            return Err(MorpheusError::InvalidState("Component doesn't transform data".to_string()));
+        }
+        let output = self.call(TRANSFORM_EXPORT, &serde_json::to_vec(input)?)?;
+        serde_json::from_slice(&output)
+            .map_err(|e| MorpheusError::Other(format!("Component returned invalid JSON: {}", e)))
+    }
+
+    /// Call `export` in a fresh instance with `input`, returning its output.
+    fn call(&self, export: &str, input: &[u8]) -> Result<Vec<u8>> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.limits.memory_bytes)
+            .instances(1)
+            .build();
+        let mut store = Store::new(engine(), limits);
+        store.limiter(|limits: &mut StoreLimits| limits);
+        store.set_fuel(self.limits.fuel).map_err(trap_error)?;
+
+        let instance = self.compiled.instance_pre.instantiate(&mut store).map_err(trap_error)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| MorpheusError::LoadError("Component doesn't export `memory`".to_string()))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, ALLOC_EXPORT)
+            .map_err(trap_error)?;
+        let entry = instance
+            .get_typed_func::<(i32, i32), i64>(&mut store, export)
+            .map_err(trap_error)?;
+
+        let len = i32::try_from(input.len())
+            .map_err(|_| MorpheusError::InvalidState(format!("Input of {} bytes is too large", input.len())))?;
+        let ptr = alloc.call(&mut store, len).map_err(trap_error)?;
+        memory
+            .write(&mut store, ptr as u32 as usize, input)
+            .map_err(|e| MorpheusError::Other(format!("Component allocated an invalid buffer: {}", e)))?;
+
+        let packed = entry.call(&mut store, (ptr, len)).map_err(trap_error)? as u64;
+        read_output(&memory, &store, (packed >> 32) as usize, (packed & 0xffff_ffff) as usize)
+    }
+}
+
+impl Compiled {
+    fn new(wasm_bytes: &[u8], cache: Option<&CompiledCache>) -> Result<Self> {
+        let cached = cache.and_then(|cache| cache.get(wasm_bytes));
+        let fresh = cached.is_none();
+        let module = match cached {
+            Some(module) => module,
+            None => Module::new(engine(), wasm_bytes)
+                .map_err(|e| MorpheusError::LoadError(format!("Invalid WASM module: {}", e)))?,
+        };
+
+        if let Some(import) = module.imports().next() {
+            return Err(MorpheusError::LoadError(format!(
+                "Headless components can't import host functions, but this one imports {}::{}",
+                import.module(),
+                import.name()
+            )));
+        }
+        let exports: Vec<_> = module.exports().map(|e| e.name().to_string()).collect();
+        for required in ["memory", ALLOC_EXPORT] {
+            if !exports.iter().any(|e| e == required) {
+                return Err(MorpheusError::LoadError(format!("Headless component doesn't export `{}`", required)));
+            }
+        }
+        let handles_http = exports.iter().any(|e| e == HANDLE_EXPORT);
+        let transforms = exports.iter().any(|e| e == TRANSFORM_EXPORT);
+        if !handles_http && !transforms {
+            return Err(MorpheusError::LoadError(format!(
+                "Headless component exports neither `{}` nor `{}`",
+                HANDLE_EXPORT, TRANSFORM_EXPORT
+            )));
+        }
+
+        let instance_pre = Linker::new(engine()).instantiate_pre(&module).map_err(trap_error)?;
+        if let Some(cache) = cache.filter(|_| fresh) {
+            cache.put(wasm_bytes, &module);
+        }
+        Ok(Self {
+            instance_pre,
+            handles_http,
+            transforms,
+        })
+    }
+}
+
+fn read_output(memory: &Memory, store: &Store<StoreLimits>, ptr: usize, len: usize) -> Result<Vec<u8>> {
+    let data = memory.data(store);
+    ptr.checked_add(len)
+        .and_then(|end| data.get(ptr..end))
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| MorpheusError::Other(format!("Component returned an out-of-bounds buffer ({} bytes at {})", len, ptr)))
+}
+
+fn trap_error(error: wasmtime::Error) -> MorpheusError {
+    match error.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => MorpheusError::Other("Component exceeded its fuel limit".to_string()),
+        Some(trap) => MorpheusError::Other(format!("Component trapped: {}", trap)),
+        None => MorpheusError::Other(format!("Component failed: {:#}", error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use serde_json::json;
+
+    /// Bump allocator from offset 1024, plus exports that echo their input
+    /// (`morpheus_transform`), return a fixed response (`morpheus_handle`)
+    /// or loop forever (`spin`).
+    const ECHO: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (data (i32.const 16) "{\"status\":201,\"body\":\"created\"}")
+          (func (export "morpheus_alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+          (func (export "morpheus_transform") (param $ptr i32) (param $len i32) (result i64)
+            (i64.or
+              (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+              (i64.extend_i32_u (local.get $len))))
+          (func (export "morpheus_handle") (param $ptr i32) (param $len i32) (result i64)
+            (i64.or (i64.shl (i64.const 16) (i64.const 32)) (i64.const 31)))
+          (func (export "spin") (param i32 i32) (result i64)
+            (loop $forever (br $forever))
+            (i64.const 0)))
+    "#;
+
+    fn echo() -> HeadlessComponent {
+        HeadlessComponent::load(&wat::parse_str(ECHO).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_transform_and_handle() {
+        let component = echo();
+        assert!(component.handles_http() && component.transforms());
+
+        let input = json!({ "items": [1, 2, 3] });
+        assert_eq!(component.transform(&input).unwrap(), input);
+
+        let response = component.handle(&HttpRequest {
+            method: "POST".to_string(),
+            path: "/todos".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(response.unwrap(), HttpResponse {
+            status: 201,
+            headers: BTreeMap::new(),
+            body: "created".to_string(),
+        });
+    }
+
+    #[test]
+    fn test_identical_modules_are_compiled_once() {
+        let first = echo();
+        let second = echo().with_limits(HeadlessLimits { fuel: 1_000_000, ..HeadlessLimits::default() });
+        assert!(first.shares_module_with(&second));
+        assert_eq!(second.transform(&json!({"n": 1})).unwrap(), json!({"n": 1}));
+
+        let other = HeadlessComponent::load(&wat::parse_str(ECHO.replace("created", "updated")).unwrap()).unwrap();
+        assert!(!other.shares_module_with(&first));
+    }
+
+    #[test]
+    fn test_compiled_modules_are_cached_on_disk() {
+        let dir = std::env::temp_dir().join(format!("morpheus-compiled-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = CompiledCache::new(&dir).unwrap();
+        let wasm = wat::parse_str(ECHO.replace("created", "fetched")).unwrap();
+        let path = cache.path(&wasm);
+
+        let component = HeadlessComponent::load_cached(&wasm, &cache).unwrap();
+        assert!(path.exists());
+        drop(component);
+        let component = HeadlessComponent::load_cached(&wasm, &cache).unwrap();
+        assert_eq!(component.transform(&json!([1])).unwrap(), json!([1]));
+        drop(component);
+
+        // Damaged files are compiled again and replaced
+        std::fs::write(&path, b"not machine code").unwrap();
+        let component = HeadlessComponent::load_cached(&wasm, &cache).unwrap();
+        assert_eq!(component.handle(&HttpRequest::default()).unwrap().body, "fetched");
+        assert!(std::fs::metadata(&path).unwrap().len() > 16);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fuel_limit_stops_runaway_loops() {
+        let component = echo().with_limits(HeadlessLimits {
+            fuel: 10_000,
+            ..HeadlessLimits::default()
+        });
+
+        let error = component.call("spin", b"{}").unwrap_err();
+
+        assert!(error.to_string().contains("fuel"));
+    }
+
+    #[test]
+    fn test_rejects_modules_outside_the_abi() {
+        let imports = wat::parse_str(r#"(module (import "env" "now" (func)) (memory (export "memory") 1))"#).unwrap();
+        let error = HeadlessComponent::load(&imports).unwrap_err();
+        assert!(error.to_string().contains("env::now"));
+
+        let no_entry = wat::parse_str(
+            r#"(module (memory (export "memory") 1) (func (export "morpheus_alloc") (param i32) (result i32) i32.const 0))"#,
+        )
+        .unwrap();
+        let error = HeadlessComponent::load(&no_entry).unwrap_err();
+        assert!(error.to_string().contains("neither"));
+    }
+
+    proptest! {
+        // Each case compiles a module, so keep the count down
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn prop_hostile_modules_are_rejected_not_panicked_on(
+            edits in prop::collection::vec((any::<prop::sample::Index>(), any::<u8>()), 1..8),
+            cut in any::<prop::sample::Index>(),
+        ) {
+            let mut wasm = wat::parse_str(ECHO).unwrap();
+            for (index, byte) in edits {
+                let i = index.index(wasm.len());
+                wasm[i] = byte;
+            }
+            wasm.truncate(cut.index(wasm.len()) + 1);
+
+            if let Ok(component) = HeadlessComponent::load(&wasm) {
+                let component = component.with_limits(HeadlessLimits {
+                    fuel: 100_000,
+                    ..HeadlessLimits::default()
+                });
+                let _ = component.transform(&json!({ "items": [1, 2, 3] }));
+            }
+        }
+    }
+}
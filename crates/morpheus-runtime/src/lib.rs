@@ -8,6 +8,7 @@
 //! 2. **Hot-reload** - Replace running components without breaking the app
 //! 3. **Sandbox** - Enforce permission restrictions
 //! 4. **Rollback** - Atomically undo bad modifications
+//! 5. **Headless** - Run backend components under wasmtime (see [`headless`])
 //!
 //! ## Architecture
 //!
@@ -35,12 +36,41 @@
 //! └─────────────────────────────────────┘
 //! ```
 
+pub mod app;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod headless;
+pub mod modules;
+pub mod sandbox;
+pub mod store;
 pub mod wasm_loader;
+pub mod worker;
 
+pub use app::{AppEvent, AppPolicy, Generator, GenerationRequest, MorpheusApp, ReloadHooks};
+#[cfg(not(target_arch = "wasm32"))]
+pub use headless::HeadlessComponent;
+pub use sandbox::SandboxBridge;
 pub use wasm_loader::WasmComponent;
+pub use worker::{DomProxy, ExecutionMode};
 
+use morpheus_core::artifact::{self, Artifact, ArtifactTarget, Environment};
+use morpheus_core::capability::Capability;
+use morpheus_core::catalog::{CatalogEntry, ComponentDescription};
 use morpheus_core::component::{ComponentId, ComponentMetadata};
+use morpheus_core::embedding::{self, similarity};
+use morpheus_core::fallback::FallbackView;
+use morpheus_core::errors::{MorpheusError, Result};
+use morpheus_core::flags::{ComponentFlag, RenderDecision};
+use morpheus_core::interface::{Compatibility, ComponentInterface, Incompatibility, InterfaceChange, InterfacePin};
+use morpheus_core::lifecycle::Lifecycle;
+use morpheus_core::manifest::{slot_mount_point, ComponentManifest};
+use morpheus_core::semver::{Bump, SemVer};
+use morpheus_core::thumbnail::Thumbnail;
+use serde::Serialize;
 use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Components less similar than this to a query are not worth suggesting.
+const MIN_SIMILARITY: f32 = 0.2;
 
 /// Registry of dynamically loaded components.
 pub struct ComponentRegistry {
@@ -49,6 +79,76 @@ pub struct ComponentRegistry {
 
     /// Component metadata.
     metadata: HashMap<ComponentId, ComponentMetadata>,
+
+    /// Component manifests (for components that declare one).
+    manifests: HashMap<ComponentId, ComponentManifest>,
+
+    /// Capabilities reported by each component's `__morpheus_describe()` export.
+    descriptions: HashMap<ComponentId, ComponentDescription>,
+
+    /// Operator feature flags (components without one are enabled).
+    flags: HashMap<ComponentId, ComponentFlag>,
+
+    /// Builds of each component, per target (see [`morpheus_core::artifact`]).
+    artifacts: HashMap<ComponentId, Vec<Artifact>>,
+
+    /// Embedding of each component's name, description and capabilities.
+    embeddings: HashMap<ComponentId, Vec<f32>>,
+
+    /// Last accepted interface of each component, by name, so it outlives
+    /// reloads that replace the component.
+    interfaces: HashMap<String, Published>,
+
+    /// What to do with reloads that break dependents.
+    compatibility: Compatibility,
+
+    /// Idle sweeps after which an unused instance is unloaded (none: never).
+    idle_sweeps: Option<u32>,
+
+    /// Sweeps each instantiated component has gone unused for.
+    idle: HashMap<ComponentId, u32>,
+}
+
+/// The interface a component last reported, and the load that reported it.
+struct Published {
+    id: ComponentId,
+    version: SemVer,
+    interface: ComponentInterface,
+}
+
+/// A child component resolved into one of its parent's slots.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SlotMount {
+    /// Slot name declared by the parent.
+    pub slot: String,
+
+    /// Component mounted into the slot.
+    pub component: ComponentId,
+
+    /// DOM element ID the child is mounted into.
+    pub mount_point: String,
+
+    /// The child's own slots, resolved recursively.
+    pub children: Vec<SlotMount>,
+}
+
+/// A registered component that resembles a query.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SimilarComponent {
+    pub id: ComponentId,
+
+    /// Manifest name (or metadata name, without a manifest).
+    pub name: String,
+
+    /// Manifest description.
+    pub description: String,
+
+    pub capabilities: Vec<Capability>,
+
+    /// Cosine similarity to the query, up to 1.
+    pub score: f32,
 }
 
 impl ComponentRegistry {
@@ -57,13 +157,38 @@ impl ComponentRegistry {
         Self {
             components: HashMap::new(),
             metadata: HashMap::new(),
+            manifests: HashMap::new(),
+            descriptions: HashMap::new(),
+            flags: HashMap::new(),
+            artifacts: HashMap::new(),
+            embeddings: HashMap::new(),
+            interfaces: HashMap::new(),
+            compatibility: Compatibility::default(),
+            idle_sweeps: None,
+            idle: HashMap::new(),
         }
     }
 
+    /// Warn about reloads that break dependents instead of rejecting them.
+    pub fn with_compatibility(mut self, compatibility: Compatibility) -> Self {
+        self.compatibility = compatibility;
+        self
+    }
+
+    /// Unload instances that go unused for `sweeps` calls of
+    /// [`Self::unload_idle`]; with sweeps on a fixed interval, the idle
+    /// threshold is `sweeps` times the interval.
+    pub fn with_idle_unloading(mut self, sweeps: u32) -> Self {
+        self.idle_sweeps = Some(sweeps.max(1));
+        self
+    }
+
     /// Register a loaded component.
     pub fn register(&mut self, id: ComponentId, component: WasmComponent, metadata: ComponentMetadata) {
+        self.idle.insert(id, 0);
         self.components.insert(id, component);
         self.metadata.insert(id, metadata);
+        self.index(id);
     }
 
     /// Get a component by ID.
@@ -76,6 +201,45 @@ impl ComponentRegistry {
         self.components.get_mut(id)
     }
 
+    /// Get a component to run, re-instantiating it if it was unloaded for
+    /// being idle. Counts as a use.
+    pub fn instance(&mut self, id: &ComponentId) -> Result<&mut WasmComponent> {
+        let component = self
+            .components
+            .get_mut(id)
+            .ok_or_else(|| MorpheusError::LoadError(format!("Component {} not registered", id)))?;
+        component.instantiate()?;
+        self.idle.insert(*id, 0);
+        Ok(component)
+    }
+
+    /// Unload the instances of components unused since the last
+    /// [`with_idle_unloading`](Self::with_idle_unloading) sweeps, keeping
+    /// their modules and metadata; [`Self::instance`] brings them back.
+    /// Returns the unloaded components, sorted by name. Call it on a fixed
+    /// interval; without idle unloading configured it does nothing.
+    pub fn unload_idle(&mut self) -> Vec<ComponentId> {
+        let Some(sweeps) = self.idle_sweeps else {
+            return Vec::new();
+        };
+        let mut unloaded = Vec::new();
+        for (id, component) in self.components.iter_mut().filter(|(_, c)| c.is_instantiated()) {
+            let idle = self.idle.entry(*id).or_default();
+            *idle += 1;
+            if *idle >= sweeps {
+                component.unload();
+                unloaded.push(*id);
+            }
+        }
+        unloaded.sort_by_cached_key(|id| self.name_of(id));
+        unloaded
+    }
+
+    /// Registered components whose instances are loaded.
+    pub fn instantiated(&self) -> usize {
+        self.components.values().filter(|c| c.is_instantiated()).count()
+    }
+
     /// Get component metadata.
     pub fn metadata(&self, id: &ComponentId) -> Option<&ComponentMetadata> {
         self.metadata.get(id)
@@ -89,8 +253,659 @@ impl ComponentRegistry {
     /// Remove a component.
     pub fn remove(&mut self, id: &ComponentId) -> Option<WasmComponent> {
         self.metadata.remove(id);
+        self.manifests.remove(id);
+        self.descriptions.remove(id);
+        self.flags.remove(id);
+        self.artifacts.remove(id);
+        self.embeddings.remove(id);
+        self.idle.remove(id);
         self.components.remove(id)
     }
+
+    /// Register a copy of a component under a new name, to take it in
+    /// another direction without touching the original.
+    ///
+    /// The fork gets its own ID and starts at version 0.1.0. It copies the
+    /// original's module and permissions, its manifest (renamed), its
+    /// reported description and interface (including its state shape), its
+    /// capabilities and its builds, and its provenance records what it was
+    /// forked from. Flags and deprecation stay with the original. Slots keep
+    /// their children, but mount at the fork's own mount points, which the
+    /// copied module doesn't render until it is rebuilt under its new name.
+    pub fn fork(&mut self, id: &ComponentId, name: impl Into<String>) -> Result<ComponentId> {
+        let name = name.into();
+        let original = self
+            .components
+            .get(id)
+            .ok_or_else(|| MorpheusError::LoadError(format!("Component {} not registered", id)))?;
+        if self.find_by_name(&name).is_some() {
+            return Err(MorpheusError::InvalidState(format!("Component '{}' already exists", name)));
+        }
+        let original_name = self.name_of(id);
+        let mut hasher = DefaultHasher::new();
+        (id.0, &name).hash(&mut hasher);
+        let fork_id = ComponentId(hasher.finish());
+
+        let component = original.fork(fork_id, name.clone());
+        let mut metadata = self.metadata.get(id).cloned().unwrap_or_else(|| component.metadata().clone());
+        metadata.id = fork_id;
+        metadata.name = name.clone();
+        metadata.version = SemVer::INITIAL;
+        metadata.loaded_at = component.metadata().loaded_at.clone();
+        metadata.provenance = metadata.provenance.with_forked_from(original_name.as_str());
+        metadata.lifecycle = Lifecycle::default();
+        self.components.insert(fork_id, component);
+        self.metadata.insert(fork_id, metadata);
+        self.idle.insert(fork_id, 0);
+
+        if let Some(manifest) = self.manifests.get(id) {
+            let manifest = ComponentManifest { name: name.clone(), ..manifest.clone() };
+            self.manifests.insert(fork_id, manifest);
+        }
+        if let Some(description) = self.descriptions.get(id) {
+            self.descriptions.insert(fork_id, description.clone());
+        }
+        if let Some(published) = self.interfaces.get(&original_name) {
+            let interface = published.interface.clone();
+            self.interfaces.insert(name, Published { id: fork_id, version: SemVer::INITIAL, interface });
+        }
+        if let Some(artifacts) = self.artifacts.get(id) {
+            self.artifacts.insert(fork_id, artifacts.clone());
+        }
+        self.index(fork_id);
+        Ok(fork_id)
+    }
+
+    /// Attach a manifest to a registered component.
+    pub fn set_manifest(&mut self, id: ComponentId, manifest: ComponentManifest) -> Result<()> {
+        if !self.components.contains_key(&id) {
+            return Err(MorpheusError::LoadError(format!("Component {} not registered", id)));
+        }
+        self.manifests.insert(id, manifest);
+        self.index(id);
+        Ok(())
+    }
+
+    /// Get a component's manifest.
+    pub fn manifest(&self, id: &ComponentId) -> Option<&ComponentManifest> {
+        self.manifests.get(id)
+    }
+
+    /// Manifests of all components available for composition.
+    ///
+    /// Deprecated components are left out, so new components don't start
+    /// depending on them.
+    pub fn available_manifests(&self) -> Vec<ComponentManifest> {
+        let mut manifests: Vec<_> = self
+            .manifests
+            .iter()
+            .filter(|(id, _)| !self.metadata.get(id).is_some_and(|m| m.lifecycle.is_deprecated()))
+            .map(|(_, manifest)| manifest.clone())
+            .collect();
+        manifests.sort_by(|a, b| a.name.cmp(&b.name));
+        manifests
+    }
+
+    /// Record the capabilities a component reported via `__morpheus_describe()`.
+    ///
+    /// Capabilities it declares replace those in its metadata. The component
+    /// is checked as a reload on its own; see [`Self::set_descriptions`].
+    pub fn set_description(
+        &mut self,
+        id: ComponentId,
+        description: ComponentDescription,
+    ) -> Result<Vec<Incompatibility>> {
+        self.set_descriptions(vec![(id, description)])
+    }
+
+    /// Record the descriptions of components reloaded together.
+    ///
+    /// Each component's interface is compared with the one last recorded
+    /// under its name. Changes that break a component outside the
+    /// transaction are rejected, or with [`Compatibility::Warn`] recorded
+    /// and returned. Accepted interfaces are hashed into the metadata, each
+    /// reload's version is bumped by how much its interface changed, and the
+    /// components pin the interfaces of the components they depend on.
+    pub fn set_descriptions(
+        &mut self,
+        descriptions: Vec<(ComponentId, ComponentDescription)>,
+    ) -> Result<Vec<Incompatibility>> {
+        let incompatibilities = self.check_reload(&descriptions)?;
+        if self.compatibility == Compatibility::Reject && !incompatibilities.is_empty() {
+            let reasons: Vec<_> = incompatibilities.iter().map(ToString::to_string).collect();
+            return Err(MorpheusError::InvalidState(format!(
+                "Reload breaks dependent components: {}",
+                reasons.join("; ")
+            )));
+        }
+
+        for (id, description) in descriptions {
+            if !description.capabilities.is_empty() {
+                self.set_capabilities(id, description.capabilities.clone())?;
+            }
+            let interface = ComponentInterface::from_description(&description);
+            let name = self.name_of(&id);
+            let published = self.interfaces.get(&name);
+            let Some(metadata) = self.metadata.get_mut(&id) else {
+                continue;
+            };
+            // Describing the same load again isn't a reload
+            let reloaded = |p: &&Published| p.id != id || p.version != metadata.version || p.interface != interface;
+            if let Some(published) = published.filter(reloaded) {
+                let bump = Bump::for_changes(&interface.changes_from(&published.interface));
+                metadata.version = metadata.version.max(published.version.bump(bump));
+            }
+            metadata.interface_hash = Some(interface.hash());
+            let version = metadata.version;
+            self.interfaces.insert(name, Published { id, version, interface });
+            self.descriptions.insert(id, description);
+        }
+        // Components left broken keep the pins they were loaded against
+        let broken: Vec<_> = incompatibilities.iter().map(|i| i.dependent.clone()).collect();
+        let ids: Vec<_> = self.metadata.keys().copied().collect();
+        for id in ids {
+            if !broken.contains(&self.name_of(&id)) {
+                let dependencies = self.pins(&id);
+                if let Some(metadata) = self.metadata.get_mut(&id) {
+                    metadata.dependencies = dependencies;
+                }
+            }
+        }
+        Ok(incompatibilities)
+    }
+
+    /// What reloading components with these descriptions would break,
+    /// without recording anything.
+    ///
+    /// Only components outside the transaction count as broken: dependents
+    /// reloaded alongside are expected to have been updated.
+    pub fn check_reload(&self, descriptions: &[(ComponentId, ComponentDescription)]) -> Result<Vec<Incompatibility>> {
+        let mut reloaded = Vec::with_capacity(descriptions.len());
+        for (id, _) in descriptions {
+            if !self.components.contains_key(id) {
+                return Err(MorpheusError::LoadError(format!("Component {} not registered", id)));
+            }
+            reloaded.push(self.name_of(id));
+        }
+
+        let mut incompatibilities = Vec::new();
+        for ((_, description), component) in descriptions.iter().zip(&reloaded) {
+            let Some(previous) = self.interfaces.get(component) else {
+                continue;
+            };
+            let changes = ComponentInterface::from_description(description).changes_from(&previous.interface);
+            // Only a new major version can break anything
+            let bump = Bump::for_changes(&changes);
+            if bump != Bump::Major {
+                continue;
+            }
+            for id in self.metadata.keys() {
+                let dependent = self.name_of(id);
+                let Some(interface) = self.interfaces.get(&dependent).map(|p| &p.interface) else {
+                    continue;
+                };
+                if reloaded.contains(&dependent) {
+                    continue;
+                }
+                let embeds = self.embeds(id, component);
+                for change in &changes {
+                    let InterfaceChange::Removed(item, name) = change else {
+                        continue;
+                    };
+                    if interface.relies_on(*item, name, embeds) {
+                        let pin = self.metadata[id].dependencies.iter().find(|pin| pin.component == *component);
+                        incompatibilities.push(Incompatibility {
+                            component: component.clone(),
+                            version: previous.version.bump(bump),
+                            dependent: dependent.clone(),
+                            change: change.clone(),
+                            pinned_hash: pin.map(|pin| pin.interface_hash.clone()),
+                            pinned_version: pin.map(|pin| pin.version),
+                        });
+                    }
+                }
+            }
+        }
+        incompatibilities.sort_by(|a, b| (&a.component, &a.dependent).cmp(&(&b.component, &b.dependent)));
+        Ok(incompatibilities)
+    }
+
+    /// Last accepted interface of the component named `name`.
+    pub fn interface(&self, name: &str) -> Option<&ComponentInterface> {
+        self.interfaces.get(name).map(|p| &p.interface)
+    }
+
+    /// Manifest name of a component, falling back to its metadata name.
+    fn name_of(&self, id: &ComponentId) -> String {
+        self.manifests
+            .get(id)
+            .map(|m| m.name.clone())
+            .or_else(|| self.metadata.get(id).map(|m| m.name.clone()))
+            .unwrap_or_default()
+    }
+
+    /// Whether `id` mounts the component named `child` into a slot.
+    fn embeds(&self, id: &ComponentId, child: &str) -> bool {
+        self.manifests
+            .get(id)
+            .is_some_and(|m| m.slots.iter().any(|slot| slot.component == child))
+    }
+
+    /// Current interfaces of the components `id` depends on, by name.
+    fn pins(&self, id: &ComponentId) -> Vec<InterfacePin> {
+        let name = self.name_of(id);
+        let consumes = self.interfaces.get(&name).map(|p| p.interface.consumes.as_slice()).unwrap_or_default();
+        let mut pins: Vec<_> = self
+            .interfaces
+            .iter()
+            .filter(|(component, published)| {
+                **component != name
+                    && (self.embeds(id, component)
+                        || published.interface.emits.iter().any(|event| consumes.contains(event)))
+            })
+            .map(|(component, published)| InterfacePin {
+                component: component.clone(),
+                interface_hash: published.interface.hash(),
+                version: published.version,
+            })
+            .collect();
+        pins.sort_by(|a, b| a.component.cmp(&b.component));
+        pins
+    }
+
+    /// Set what kind of job a component does.
+    pub fn set_capabilities(&mut self, id: ComponentId, mut capabilities: Vec<Capability>) -> Result<()> {
+        let metadata = self
+            .metadata
+            .get_mut(&id)
+            .ok_or_else(|| MorpheusError::LoadError(format!("Component {} not registered", id)))?;
+        capabilities.sort();
+        capabilities.dedup();
+        metadata.capabilities = capabilities;
+        self.index(id);
+        Ok(())
+    }
+
+    /// Components with `capability`, sorted by name.
+    pub fn find_by_capability(&self, capability: Capability) -> Vec<ComponentId> {
+        let mut found: Vec<_> = self
+            .metadata
+            .values()
+            .filter(|metadata| metadata.capabilities.contains(&capability))
+            .map(|metadata| (self.name(&metadata.id), metadata.id.0))
+            .collect();
+        found.sort();
+        found.into_iter().map(|(_, id)| ComponentId(id)).collect()
+    }
+
+    /// Up to `limit` components whose name, description and capabilities
+    /// resemble `query`, e.g. `"a table of users"`, most similar first.
+    ///
+    /// Lets a planner reuse a component instead of generating another one.
+    pub fn find_similar(&self, query: &str, limit: usize) -> Vec<SimilarComponent> {
+        let query = embedding::embed(query);
+        let mut found: Vec<_> = self
+            .embeddings
+            .iter()
+            .map(|(id, embedding)| (id, similarity(&query, embedding)))
+            .filter(|(_, score)| *score >= MIN_SIMILARITY)
+            .map(|(id, score)| SimilarComponent {
+                id: *id,
+                name: self.name(id),
+                description: self.manifests.get(id).map(|m| m.description.clone()).unwrap_or_default(),
+                capabilities: self.metadata.get(id).map(|m| m.capabilities.clone()).unwrap_or_default(),
+                score,
+            })
+            .collect();
+        found.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+        found.truncate(limit);
+        found
+    }
+
+    /// Manifest name, or metadata name without a manifest.
+    fn name(&self, id: &ComponentId) -> String {
+        self.manifests
+            .get(id)
+            .map(|manifest| manifest.name.clone())
+            .or_else(|| self.metadata.get(id).map(|metadata| metadata.name.clone()))
+            .unwrap_or_default()
+    }
+
+    /// Re-embed a component after its name, description or capabilities change.
+    fn index(&mut self, id: ComponentId) {
+        let mut text = self.name(&id);
+        if let Some(manifest) = self.manifests.get(&id) {
+            text.push(' ');
+            text.push_str(&manifest.description);
+        }
+        for capability in self.metadata.get(&id).map(|m| m.capabilities.as_slice()).unwrap_or_default() {
+            text.push(' ');
+            text.push_str(&capability.name().replace('-', " "));
+        }
+        self.embeddings.insert(id, embedding::embed(&text));
+    }
+
+    /// Get a component's reported capabilities.
+    pub fn description(&self, id: &ComponentId) -> Option<&ComponentDescription> {
+        self.descriptions.get(id)
+    }
+
+    /// Catalog of all components that declare a manifest, sorted by name.
+    pub fn catalog(&self) -> Vec<CatalogEntry> {
+        let mut entries: Vec<_> = self
+            .manifests
+            .iter()
+            .map(|(id, manifest)| CatalogEntry {
+                id: *id,
+                version: self.metadata.get(id).map(|m| m.version).unwrap_or_default(),
+                manifest: manifest.clone(),
+                description: self.descriptions.get(id).cloned(),
+                thumbnail: self.metadata.get(id).and_then(|m| m.thumbnail.clone()),
+            })
+            .collect();
+        entries.sort_by(|a, b| a.manifest.name.cmp(&b.manifest.name));
+        entries
+    }
+
+    /// Set a component's feature flag.
+    pub fn set_flag(&mut self, id: ComponentId, flag: ComponentFlag) -> Result<()> {
+        if !self.components.contains_key(&id) {
+            return Err(MorpheusError::LoadError(format!("Component {} not registered", id)));
+        }
+        self.flags.insert(id, flag);
+        Ok(())
+    }
+
+    /// Get a component's feature flag, if one is set.
+    pub fn flag(&self, id: &ComponentId) -> Option<&ComponentFlag> {
+        self.flags.get(id)
+    }
+
+    /// Remove a component's feature flag, re-enabling its current version.
+    pub fn clear_flag(&mut self, id: &ComponentId) -> Option<ComponentFlag> {
+        self.flags.remove(id)
+    }
+
+    /// Deprecate a component, naming its replacement and when it may be
+    /// retired (see [`morpheus_core::lifecycle`]).
+    pub fn deprecate(
+        &mut self,
+        id: ComponentId,
+        replacement: Option<String>,
+        retire_after: Option<String>,
+    ) -> Result<()> {
+        let metadata = self
+            .metadata
+            .get_mut(&id)
+            .ok_or_else(|| MorpheusError::LoadError(format!("Component {} not registered", id)))?;
+        metadata.lifecycle = Lifecycle::Deprecated { replacement, retire_after };
+        Ok(())
+    }
+
+    /// Set or clear a component's preview (see [`morpheus_core::thumbnail`]).
+    pub fn set_thumbnail(&mut self, id: ComponentId, thumbnail: Option<Thumbnail>) -> Result<()> {
+        let metadata = self
+            .metadata
+            .get_mut(&id)
+            .ok_or_else(|| MorpheusError::LoadError(format!("Component {} not registered", id)))?;
+        metadata.thumbnail = thumbnail;
+        Ok(())
+    }
+
+    /// Set or clear what clients render if a component fails to load (see
+    /// [`morpheus_core::fallback`]). A fallback component must be another
+    /// registered component.
+    pub fn set_fallback(&mut self, id: ComponentId, fallback: Option<FallbackView>) -> Result<()> {
+        if !self.metadata.contains_key(&id) {
+            return Err(MorpheusError::LoadError(format!("Component {} not registered", id)));
+        }
+        if let Some(component) = fallback.as_ref().and_then(FallbackView::fallback_component) {
+            match self.find_by_name(component) {
+                None => {
+                    return Err(MorpheusError::LoadError(format!(
+                        "Fallback component '{}' not registered",
+                        component
+                    )))
+                }
+                Some(other) if other == id => {
+                    return Err(MorpheusError::InvalidState("A component can't be its own fallback".to_string()))
+                }
+                Some(_) => {}
+            }
+        }
+        if let Some(metadata) = self.metadata.get_mut(&id) {
+            metadata.fallback = fallback;
+        }
+        Ok(())
+    }
+
+    /// Record a call into one of a component's exports (see
+    /// [`morpheus_core::stats`]); `at` is a UTC timestamp.
+    pub fn record_call(
+        &mut self,
+        id: ComponentId,
+        export: &str,
+        duration_ms: f64,
+        trapped: bool,
+        at: &str,
+    ) -> Result<()> {
+        let metadata = self
+            .metadata
+            .get_mut(&id)
+            .ok_or_else(|| MorpheusError::LoadError(format!("Component {} not registered", id)))?;
+        metadata.stats.record(export, duration_ms, trapped, at)?;
+        self.idle.insert(id, 0);
+        Ok(())
+    }
+
+    /// Components nothing has called since `since`, a UTC timestamp, sorted
+    /// by name: candidates for retiring.
+    pub fn unused_since(&self, since: &str) -> Vec<ComponentId> {
+        let mut unused: Vec<&ComponentMetadata> =
+            self.metadata.values().filter(|m| m.stats.is_unused_since(since)).collect();
+        unused.sort_by(|a, b| a.name.cmp(&b.name));
+        unused.into_iter().map(|m| m.id).collect()
+    }
+
+    /// A component's lifecycle status.
+    pub fn lifecycle(&self, id: &ComponentId) -> Option<&Lifecycle> {
+        self.metadata.get(id).map(|m| &m.lifecycle)
+    }
+
+    /// Deprecated components, sorted by name.
+    pub fn deprecated(&self) -> Vec<ComponentId> {
+        let mut ids: Vec<_> = self
+            .metadata
+            .iter()
+            .filter(|(_, m)| m.lifecycle.is_deprecated())
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort_by_key(|id| self.name_of(id));
+        ids
+    }
+
+    /// Names of the components still depending on `id`: those mounting it
+    /// into a slot, pinning its interface or falling back to it.
+    pub fn dependents(&self, id: &ComponentId) -> Vec<String> {
+        let name = self.name_of(id);
+        let mut dependents: Vec<_> = self
+            .metadata
+            .iter()
+            .filter(|(other, metadata)| {
+                *other != id
+                    && (self.embeds(other, &name)
+                        || metadata.dependencies.iter().any(|pin| pin.component == name)
+                        || metadata.fallback.as_ref().and_then(FallbackView::fallback_component) == Some(&name))
+            })
+            .map(|(other, _)| self.name_of(other))
+            .collect();
+        dependents.sort();
+        dependents.dedup();
+        dependents
+    }
+
+    /// Warnings for mounting a component: whether it, or anything mounted
+    /// into its slots, is deprecated.
+    pub fn mount_warnings(&self, id: &ComponentId) -> Vec<String> {
+        let mut ids = vec![*id];
+        let mut mounts = self.resolve_slots(id).unwrap_or_default();
+        while !mounts.is_empty() {
+            ids.extend(mounts.iter().map(|mount| mount.component));
+            mounts = mounts.into_iter().flat_map(|mount| mount.children).collect();
+        }
+        let mut warnings = Vec::new();
+        for id in ids {
+            let warning = self.metadata.get(&id).and_then(|m| m.lifecycle.warning(&self.name_of(&id)));
+            if let Some(warning) = warning.filter(|w| !warnings.contains(w)) {
+                warnings.push(warning);
+            }
+        }
+        warnings
+    }
+
+    /// Retire a component, removing it from the registry.
+    ///
+    /// Refused while other components depend on it. Returns its metadata,
+    /// marked [`Lifecycle::Retired`].
+    pub fn retire(&mut self, id: &ComponentId) -> Result<ComponentMetadata> {
+        let name = self.name_of(id);
+        let Some(metadata) = self.metadata.get(id) else {
+            return Err(MorpheusError::LoadError(format!("Component {} not registered", id)));
+        };
+        let mut metadata = metadata.clone();
+        let dependents = self.dependents(id);
+        if !dependents.is_empty() {
+            return Err(MorpheusError::InvalidState(format!(
+                "Component {} is still used by {}",
+                name,
+                dependents.join(", ")
+            )));
+        }
+        self.remove(id);
+        // Nothing can pin a retired interface
+        self.interfaces.remove(&name);
+        metadata.lifecycle = Lifecycle::Retired;
+        Ok(metadata)
+    }
+
+    /// Retire the deprecated components whose `retire_after` has passed by
+    /// `now` and whose dependents have all migrated away.
+    pub fn retire_due(&mut self, now: &str) -> Vec<ComponentMetadata> {
+        let mut retired = Vec::new();
+        // Retiring a component can free the ones it depended on
+        loop {
+            let due: Vec<_> = self
+                .metadata
+                .iter()
+                .filter(|(_, m)| m.lifecycle.is_due(now))
+                .map(|(id, _)| *id)
+                .collect();
+            let before = retired.len();
+            retired.extend(due.iter().filter_map(|id| self.retire(id).ok()));
+            if retired.len() == before {
+                return retired;
+            }
+        }
+    }
+
+    /// Record the builds of a registered component, replacing earlier ones.
+    pub fn set_artifacts(&mut self, id: ComponentId, artifacts: Vec<Artifact>) -> Result<()> {
+        if !self.components.contains_key(&id) {
+            return Err(MorpheusError::LoadError(format!("Component {} not registered", id)));
+        }
+        self.artifacts.insert(id, artifacts);
+        Ok(())
+    }
+
+    /// Targets a component was built for.
+    pub fn targets(&self, id: &ComponentId) -> Vec<ArtifactTarget> {
+        self.artifacts
+            .get(id)
+            .map(|artifacts| artifacts.iter().map(|artifact| artifact.target).collect())
+            .unwrap_or_default()
+    }
+
+    /// The build of a component to run in `environment`.
+    pub fn artifact(&self, id: &ComponentId, environment: Environment) -> Result<&Artifact> {
+        let artifacts = self.artifacts.get(id).map(Vec::as_slice).unwrap_or_default();
+        artifact::select(artifacts, environment).ok_or_else(|| {
+            MorpheusError::LoadError(format!(
+                "Component {} has no build for {:?} (built for: {})",
+                id,
+                environment,
+                artifacts.iter().map(|a| a.target.name()).collect::<Vec<_>>().join(", ")
+            ))
+        })
+    }
+
+    /// Decide what the host should render for a component.
+    pub fn render_decision(&self, id: &ComponentId) -> Result<RenderDecision> {
+        if !self.components.contains_key(id) {
+            return Err(MorpheusError::LoadError(format!("Component {} not registered", id)));
+        }
+        Ok(self.flags.get(id).map(ComponentFlag::decide).unwrap_or(RenderDecision::Current))
+    }
+
+    /// Find a component by manifest name, falling back to metadata name.
+    pub fn find_by_name(&self, name: &str) -> Option<ComponentId> {
+        self.manifests
+            .iter()
+            .find(|(_, manifest)| manifest.name == name)
+            .map(|(id, _)| *id)
+            .or_else(|| {
+                self.metadata
+                    .iter()
+                    .find(|(_, meta)| meta.name == name)
+                    .map(|(id, _)| *id)
+            })
+    }
+
+    /// Resolve the slots declared by a component's manifest.
+    ///
+    /// Each slot is bound to the registered component of that name, with a
+    /// mount point the parent renders and the runtime mounts the child into.
+    /// Children's slots are resolved recursively; cycles are rejected.
+    pub fn resolve_slots(&self, id: &ComponentId) -> Result<Vec<SlotMount>> {
+        self.resolve_slots_inner(id, &mut Vec::new())
+    }
+
+    fn resolve_slots_inner(
+        &self,
+        id: &ComponentId,
+        path: &mut Vec<ComponentId>,
+    ) -> Result<Vec<SlotMount>> {
+        if path.contains(id) {
+            return Err(MorpheusError::InvalidState(format!(
+                "Component {} embeds itself through its slots",
+                id
+            )));
+        }
+
+        let Some(manifest) = self.manifests.get(id) else {
+            return Ok(Vec::new());
+        };
+
+        path.push(*id);
+        let mut mounts = Vec::with_capacity(manifest.slots.len());
+        for slot in &manifest.slots {
+            let child = self.find_by_name(&slot.component).ok_or_else(|| {
+                MorpheusError::LoadError(format!(
+                    "Slot '{}' of '{}' references unknown component '{}'",
+                    slot.name, manifest.name, slot.component
+                ))
+            })?;
+
+            mounts.push(SlotMount {
+                slot: slot.name.clone(),
+                component: child,
+                mount_point: slot_mount_point(&manifest.name, &slot.name),
+                children: self.resolve_slots_inner(&child, path)?,
+            });
+        }
+        path.pop();
+
+        Ok(mounts)
+    }
 }
 
 impl Default for ComponentRegistry {
@@ -104,17 +919,37 @@ mod tests {
     use super::*;
     use morpheus_core::permissions::Permissions;
     use morpheus_core::component::ComponentMetadata;
+    use morpheus_core::flags::Fallback;
 
-    fn create_test_metadata(id: u64, name: &str, version: u32) -> ComponentMetadata {
+    fn create_test_metadata(id: u64, name: &str, major: u32) -> ComponentMetadata {
         ComponentMetadata {
             id: ComponentId(id),
             name: name.to_string(),
-            version,
+            version: SemVer::new(major, 0, 0),
             loaded_at: "2025-01-01T00:00:00Z".to_string(),
             ai_generated: false,
+            provenance: Default::default(),
+            capabilities: Vec::new(),
+            interface_hash: None,
+            dependencies: Vec::new(),
+            lifecycle: Default::default(),
+            thumbnail: None,
+            stats: Default::default(),
+            fallback: None,
         }
     }
 
+    async fn register_named(registry: &mut ComponentRegistry, bytes: &[u8], manifest: ComponentManifest) -> ComponentId {
+        let component = WasmComponent::load(bytes, Permissions::default())
+            .await
+            .expect("Failed to load component");
+        let id = component.id();
+        let metadata = create_test_metadata(id.0, &manifest.name, 1);
+        registry.register(id, component, metadata);
+        registry.set_manifest(id, manifest).expect("Failed to set manifest");
+        id
+    }
+
     #[tokio::test]
     async fn test_registry_new() {
         let registry = ComponentRegistry::new();
@@ -203,7 +1038,7 @@ mod tests {
         let retrieved_metadata = registry.metadata(&id);
         assert!(retrieved_metadata.is_some());
         assert_eq!(retrieved_metadata.unwrap().name, "test-component");
-        assert_eq!(retrieved_metadata.unwrap().version, 1);
+        assert_eq!(retrieved_metadata.unwrap().version, SemVer::INITIAL);
     }
 
     #[tokio::test]
@@ -312,6 +1147,448 @@ mod tests {
         // Should have overwritten
         assert_eq!(registry.components.len(), 1);
         assert_eq!(registry.metadata(&id).unwrap().name, "version-2");
-        assert_eq!(registry.metadata(&id).unwrap().version, 2);
+        assert_eq!(registry.metadata(&id).unwrap().version, SemVer::new(2, 0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_set_manifest_requires_registered_component() {
+        let mut registry = ComponentRegistry::new();
+        let result = registry.set_manifest(ComponentId(1), ComponentManifest::new("orphan", ""));
+
+        assert!(matches!(result, Err(MorpheusError::LoadError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_find_by_name() {
+        let mut registry = ComponentRegistry::new();
+        let id = register_named(&mut registry, &[1, 2, 3, 4], ComponentManifest::new("chart", "")).await;
+
+        assert_eq!(registry.find_by_name("chart"), Some(id));
+        assert_eq!(registry.find_by_name("missing"), None);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_nested_slots() {
+        let mut registry = ComponentRegistry::new();
+        let legend = register_named(&mut registry, &[1, 1, 1, 1], ComponentManifest::new("legend", "")).await;
+        let chart = register_named(
+            &mut registry,
+            &[2, 2, 2, 2],
+            ComponentManifest::new("chart", "").with_slot("key", "legend"),
+        )
+        .await;
+        let dashboard = register_named(
+            &mut registry,
+            &[3, 3, 3, 3],
+            ComponentManifest::new("dashboard", "").with_slot("main", "chart"),
+        )
+        .await;
+
+        let mounts = registry.resolve_slots(&dashboard).expect("Failed to resolve slots");
+
+        assert_eq!(mounts.len(), 1);
+        assert_eq!(mounts[0].slot, "main");
+        assert_eq!(mounts[0].component, chart);
+        assert_eq!(mounts[0].mount_point, "morpheus-slot-dashboard-main");
+        assert_eq!(mounts[0].children.len(), 1);
+        assert_eq!(mounts[0].children[0].component, legend);
+        assert_eq!(mounts[0].children[0].mount_point, "morpheus-slot-chart-key");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_slots_unknown_component() {
+        let mut registry = ComponentRegistry::new();
+        let id = register_named(
+            &mut registry,
+            &[1, 2, 3, 4],
+            ComponentManifest::new("dashboard", "").with_slot("main", "missing-chart"),
+        )
+        .await;
+
+        let result = registry.resolve_slots(&id);
+        match result {
+            Err(MorpheusError::LoadError(msg)) => assert!(msg.contains("missing-chart")),
+            _ => panic!("Expected LoadError"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_slots_rejects_cycles() {
+        let mut registry = ComponentRegistry::new();
+        register_named(
+            &mut registry,
+            &[1, 1, 1, 1],
+            ComponentManifest::new("a", "").with_slot("child", "b"),
+        )
+        .await;
+        let b = register_named(
+            &mut registry,
+            &[2, 2, 2, 2],
+            ComponentManifest::new("b", "").with_slot("child", "a"),
+        )
+        .await;
+
+        assert!(matches!(registry.resolve_slots(&b), Err(MorpheusError::InvalidState(_))));
+    }
+
+    #[tokio::test]
+    async fn test_remove_drops_manifest() {
+        let mut registry = ComponentRegistry::new();
+        let id = register_named(&mut registry, &[1, 2, 3, 4], ComponentManifest::new("chart", "")).await;
+
+        registry.remove(&id);
+
+        assert!(registry.manifest(&id).is_none());
+        assert!(registry.available_manifests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_catalog_includes_descriptions() {
+        let mut registry = ComponentRegistry::new();
+        let chart = register_named(&mut registry, &[1, 2, 3, 4], ComponentManifest::new("chart", "Sales chart")).await;
+        register_named(&mut registry, &[5, 6, 7, 8], ComponentManifest::new("buttons", "")).await;
+
+        let description = ComponentDescription {
+            exports: vec!["render".to_string()],
+            ..Default::default()
+        };
+        registry.set_description(chart, description.clone()).expect("Failed to set description");
+
+        let catalog = registry.catalog();
+
+        assert_eq!(catalog.len(), 2);
+        assert_eq!(catalog[0].manifest.name, "buttons");
+        assert!(catalog[0].description.is_none());
+        assert_eq!(catalog[1].id, chart);
+        assert_eq!(catalog[1].version, SemVer::INITIAL);
+        assert_eq!(catalog[1].description, Some(description));
+    }
+
+    #[tokio::test]
+    async fn test_thumbnails_show_in_the_catalog() {
+        let mut registry = ComponentRegistry::new();
+        let chart = register_named(&mut registry, &[1, 2, 3, 4], ComponentManifest::new("chart", "Sales chart")).await;
+        let thumbnail = Thumbnail::from_bytes(b"\x89PNG\r\n\x1a\n").unwrap();
+
+        registry.set_thumbnail(chart, Some(thumbnail.clone())).unwrap();
+        assert_eq!(registry.catalog()[0].thumbnail, Some(thumbnail));
+        assert!(registry.metadata(&chart).unwrap().thumbnail.is_some());
+
+        registry.set_thumbnail(chart, None).unwrap();
+        assert_eq!(registry.catalog()[0].thumbnail, None);
+        assert!(registry.set_thumbnail(ComponentId(7), None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fallbacks_must_be_other_registered_components() {
+        let mut registry = ComponentRegistry::new();
+        let chart = register_named(&mut registry, &[1, 2, 3, 4], ComponentManifest::new("chart", "Sales chart")).await;
+        let table = register_named(&mut registry, &[5, 6, 7, 8], ComponentManifest::new("table", "Sales table")).await;
+
+        assert!(registry.set_fallback(chart, Some(FallbackView::component("chart"))).is_err());
+        assert!(registry.set_fallback(chart, Some(FallbackView::component("legend"))).is_err());
+        assert!(registry.set_fallback(ComponentId(7), None).is_err());
+
+        registry.set_fallback(chart, Some(FallbackView::component("table"))).unwrap();
+        assert_eq!(registry.metadata(&chart).unwrap().fallback, Some(FallbackView::component("table")));
+        assert_eq!(registry.dependents(&table), vec!["chart"]);
+        assert!(registry.retire(&table).is_err());
+
+        registry.set_fallback(chart, Some(FallbackView::html("<p>No chart</p>"))).unwrap();
+        assert!(registry.dependents(&table).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_calls_are_counted_and_idle_components_found() {
+        let mut registry = ComponentRegistry::new();
+        let chart = register_named(&mut registry, &[1, 2, 3, 4], ComponentManifest::new("chart", "Sales chart")).await;
+        let table = register_named(&mut registry, &[5, 6, 7, 8], ComponentManifest::new("table", "Sales table")).await;
+
+        registry.record_call(chart, "render", 3.0, false, "2026-01-02T00:00:00Z").unwrap();
+        registry.record_call(chart, "on_click", 1.0, true, "2026-01-02T00:00:01Z").unwrap();
+        assert!(registry.record_call(ComponentId(7), "render", 1.0, false, "2026-01-02T00:00:00Z").is_err());
+
+        let stats = &registry.metadata(&chart).unwrap().stats;
+        assert_eq!((stats.calls, stats.traps), (2, 1));
+        assert_eq!(stats.last_used.as_deref(), Some("2026-01-02T00:00:01Z"));
+        assert_eq!(registry.unused_since("2026-01-01T00:00:00Z"), vec![table]);
+        assert_eq!(registry.unused_since("2026-01-03T00:00:00Z"), vec![chart, table]);
+    }
+
+    #[tokio::test]
+    async fn test_idle_instances_unload_and_come_back_on_use() {
+        let mut registry = ComponentRegistry::new().with_idle_unloading(2);
+        let chart = register_named(&mut registry, &[1, 2, 3, 4], ComponentManifest::new("chart", "Sales chart")).await;
+        let table = register_named(&mut registry, &[5, 6, 7, 8], ComponentManifest::new("table", "Sales table")).await;
+
+        assert!(registry.unload_idle().is_empty());
+        registry.record_call(chart, "render", 3.0, false, "2026-01-02T00:00:00Z").unwrap();
+        assert_eq!(registry.unload_idle(), vec![table]);
+        assert_eq!(registry.instantiated(), 1);
+        assert!(registry.metadata(&table).is_some());
+
+        assert_eq!(registry.unload_idle(), vec![chart]);
+        assert!(registry.unload_idle().is_empty());
+
+        assert!(registry.instance(&table).unwrap().is_instantiated());
+        assert_eq!(registry.instantiated(), 1);
+        assert!(registry.instance(&ComponentId(7)).is_err());
+        assert!(ComponentRegistry::new().unload_idle().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_set_description_requires_registered_component() {
+        let mut registry = ComponentRegistry::new();
+        let result = registry.set_description(ComponentId(7), ComponentDescription::default());
+
+        assert!(matches!(result, Err(MorpheusError::LoadError(_))));
+    }
+
+    fn describe(exports: &[&str], emits: &[&str], consumes: &[&str]) -> ComponentDescription {
+        let names = |items: &[&str]| items.iter().map(|s| s.to_string()).collect();
+        ComponentDescription {
+            exports: names(exports),
+            emits: names(emits),
+            consumes: names(consumes),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_breaking_reloads_need_their_dependents() {
+        let mut registry = ComponentRegistry::new();
+        let filters = register_named(&mut registry, &[1, 1, 1, 1], ComponentManifest::new("filters", "")).await;
+        let chart = register_named(&mut registry, &[2, 2, 2, 2], ComponentManifest::new("chart", "")).await;
+        registry.set_description(filters, describe(&["render"], &["filter-changed"], &[])).unwrap();
+        registry.set_description(chart, describe(&["render"], &[], &["filter-changed"])).unwrap();
+        // Describing the same load again is no reload
+        registry.set_description(chart, describe(&["render"], &[], &["filter-changed"])).unwrap();
+        assert_eq!(registry.metadata(&chart).unwrap().version, SemVer::INITIAL);
+        let pinned = registry.metadata(&filters).unwrap().interface_hash.clone().unwrap();
+        assert_eq!(registry.metadata(&chart).unwrap().dependencies[0].interface_hash, pinned);
+
+        // Renaming the event breaks the chart unless it is reloaded too
+        let renamed = describe(&["render"], &["filters-changed"], &[]);
+        let broken = registry.check_reload(&[(filters, renamed.clone())]).unwrap();
+        assert_eq!(broken.len(), 1);
+        assert_eq!((broken[0].dependent.as_str(), broken[0].pinned_hash.as_ref()), ("chart", Some(&pinned)));
+        assert_eq!((broken[0].version, broken[0].pinned_version), (SemVer::new(2, 0, 0), Some(SemVer::INITIAL)));
+        assert!(matches!(
+            registry.set_description(filters, renamed.clone()),
+            Err(MorpheusError::InvalidState(_))
+        ));
+        assert_eq!(registry.metadata(&filters).unwrap().interface_hash, Some(pinned.clone()));
+
+        let updated = describe(&["render"], &[], &["filters-changed"]);
+        let accepted = registry.set_descriptions(vec![(filters, renamed), (chart, updated)]).unwrap();
+        assert!(accepted.is_empty());
+        let hash = registry.metadata(&filters).unwrap().interface_hash.clone().unwrap();
+        assert_ne!(hash, pinned);
+        assert_eq!(registry.metadata(&filters).unwrap().version, SemVer::new(2, 0, 0));
+        assert_eq!(registry.metadata(&chart).unwrap().dependencies[0].interface_hash, hash);
+        // Additions break nothing and are a minor version
+        assert!(registry.set_description(filters, describe(&["render", "reset"], &["filters-changed"], &[])).is_ok());
+        assert_eq!(registry.metadata(&filters).unwrap().version, SemVer::new(2, 1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_warned_reloads_leave_dependents_pinned() {
+        let mut registry = ComponentRegistry::new().with_compatibility(Compatibility::Warn);
+        let chart = register_named(&mut registry, &[3, 3, 3, 3], ComponentManifest::new("chart", "")).await;
+        let page = register_named(
+            &mut registry,
+            &[4, 4, 4, 4],
+            ComponentManifest::new("page", "").with_slot("main", "chart"),
+        )
+        .await;
+        registry.set_description(chart, describe(&["render", "select"], &[], &[])).unwrap();
+        registry.set_description(page, describe(&["render"], &[], &[])).unwrap();
+        let pins = registry.metadata(&page).unwrap().dependencies.clone();
+        assert_eq!(pins[0].component, "chart");
+
+        let warnings = registry.set_description(chart, describe(&["render"], &[], &[])).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].to_string(),
+            "chart 2.0.0 removed export `select`, which page relies on; reload page in the same transaction"
+        );
+        assert_eq!(registry.interface("chart").unwrap().exports, ["render"]);
+        assert_eq!(registry.metadata(&page).unwrap().dependencies, pins);
+    }
+
+    #[tokio::test]
+    async fn test_deprecated_components_retire_once_dependents_migrate() {
+        let mut registry = ComponentRegistry::new();
+        let legend = register_named(&mut registry, &[1, 1, 1, 1], ComponentManifest::new("legend", "")).await;
+        register_named(&mut registry, &[2, 2, 2, 2], ComponentManifest::new("key", "")).await;
+        let page = register_named(
+            &mut registry,
+            &[3, 3, 3, 3],
+            ComponentManifest::new("page", "").with_slot("side", "legend"),
+        )
+        .await;
+        registry
+            .deprecate(legend, Some("key".to_string()), Some("2025-03-01T00:00:00Z".to_string()))
+            .unwrap();
+
+        assert_eq!(registry.deprecated(), [legend]);
+        assert_eq!(
+            registry.mount_warnings(&page),
+            ["legend is deprecated and retires after 2025-03-01T00:00:00Z; use key instead"]
+        );
+        assert!(registry.available_manifests().iter().all(|m| m.name != "legend"));
+        assert_eq!(registry.dependents(&legend), ["page"]);
+        assert!(registry.retire_due("2025-04-01T00:00:00Z").is_empty());
+        assert!(matches!(registry.retire(&legend), Err(MorpheusError::InvalidState(_))));
+
+        registry
+            .set_manifest(page, ComponentManifest::new("page", "").with_slot("side", "key"))
+            .unwrap();
+        assert!(registry.mount_warnings(&page).is_empty());
+        assert!(registry.retire_due("2025-02-01T00:00:00Z").is_empty());
+        let retired = registry.retire_due("2025-04-01T00:00:00Z");
+        assert_eq!(retired.len(), 1);
+        assert_eq!((retired[0].id, &retired[0].lifecycle), (legend, &Lifecycle::Retired));
+        assert!(registry.get(&legend).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_forks_copy_the_component_under_a_new_name() {
+        let mut registry = ComponentRegistry::new();
+        let manifest = ComponentManifest::new("counter", "A counter");
+        let counter = register_named(&mut registry, &[5, 5, 5, 5], manifest).await;
+        let state = serde_json::json!({ "count": 0 });
+        let description = ComponentDescription { state: state.clone(), ..describe(&["render"], &[], &[]) };
+        registry.set_description(counter, description).unwrap();
+        registry.deprecate(counter, None, None).unwrap();
+
+        let dark = registry.fork(&counter, "counter-dark").unwrap();
+
+        assert_ne!(dark, counter);
+        assert_eq!(registry.find_by_name("counter-dark"), Some(dark));
+        assert_eq!(registry.manifest(&dark).unwrap().description, "A counter");
+        assert_eq!(registry.description(&dark).unwrap().state, state);
+        assert_eq!(registry.interface("counter-dark").unwrap().exports, ["render"]);
+        let metadata = registry.metadata(&dark).unwrap();
+        assert_eq!(metadata.provenance.forked_from.as_deref(), Some("counter"));
+        assert_eq!(metadata.lifecycle, Lifecycle::Active);
+        assert!(registry.lifecycle(&counter).unwrap().is_deprecated(), "the original is untouched");
+        assert_eq!(registry.manifest(&counter).unwrap().name, "counter");
+
+        assert!(matches!(registry.fork(&counter, "counter-dark"), Err(MorpheusError::InvalidState(_))));
+        assert!(matches!(registry.fork(&ComponentId(7), "other"), Err(MorpheusError::LoadError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_feature_flags() {
+        let mut registry = ComponentRegistry::new();
+        let id = register_named(&mut registry, &[1, 2, 3, 4], ComponentManifest::new("chart", "")).await;
+
+        assert_eq!(registry.render_decision(&id).unwrap(), RenderDecision::Current);
+
+        registry
+            .set_flag(id, ComponentFlag::disabled(Fallback::PreviousVersion))
+            .expect("Failed to set flag");
+        assert_eq!(
+            registry.render_decision(&id).unwrap(),
+            RenderDecision::Fallback {
+                fallback: Fallback::PreviousVersion
+            }
+        );
+
+        registry.set_flag(id, ComponentFlag::pinned(2)).expect("Failed to set flag");
+        assert_eq!(registry.render_decision(&id).unwrap(), RenderDecision::Version { version: 2 });
+
+        registry.clear_flag(&id);
+        assert!(registry.flag(&id).is_none());
+        assert_eq!(registry.render_decision(&id).unwrap(), RenderDecision::Current);
+    }
+
+    #[tokio::test]
+    async fn test_feature_flags_require_registered_component() {
+        let mut registry = ComponentRegistry::new();
+
+        assert!(registry.set_flag(ComponentId(7), ComponentFlag::default()).is_err());
+        assert!(registry.render_decision(&ComponentId(7)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_artifact_per_environment() {
+        let mut registry = ComponentRegistry::new();
+        let id = register_named(&mut registry, &[1, 2, 3, 4], ComponentManifest::new("chart", "")).await;
+
+        registry
+            .set_artifacts(id, vec![Artifact::new(ArtifactTarget::Web, &[1, 2, 3, 4], "glue")])
+            .expect("Failed to set artifacts");
+        assert_eq!(registry.artifact(&id, Environment::Browser).unwrap().js_glue, "glue");
+        let error = registry.artifact(&id, Environment::Server).unwrap_err();
+        assert!(error.to_string().contains("built for: web"));
+
+        registry
+            .set_artifacts(
+                id,
+                vec![
+                    Artifact::new(ArtifactTarget::Web, &[1, 2, 3, 4], "glue"),
+                    Artifact::new(ArtifactTarget::Wasi, &[5, 6], ""),
+                ],
+            )
+            .expect("Failed to set artifacts");
+        assert_eq!(registry.targets(&id), [ArtifactTarget::Web, ArtifactTarget::Wasi]);
+        assert_eq!(registry.artifact(&id, Environment::Server).unwrap().wasm().unwrap(), [5, 6]);
+
+        registry.remove(&id);
+        assert!(registry.set_artifacts(id, Vec::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_find_similar() {
+        let mut registry = ComponentRegistry::new();
+        let users = register_named(
+            &mut registry,
+            &[0x00, 0x61, 0x73, 0x6d, 1],
+            ComponentManifest::new("user-table", "Sortable table of users with their email and role"),
+        )
+        .await;
+        register_named(
+            &mut registry,
+            &[0x00, 0x61, 0x73, 0x6d, 2],
+            ComponentManifest::new("countdown", "Countdown timer with start and pause buttons"),
+        )
+        .await;
+
+        let found = registry.find_similar("a table of users", 5);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].id, found[0].name.as_str()), (users, "user-table"));
+        assert!(registry.find_similar("weather forecast", 5).is_empty());
+        assert!(registry.find_similar("a table of users", 0).is_empty());
+
+        registry.remove(&users);
+        assert!(registry.find_similar("a table of users", 5).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_capabilities() {
+        let mut registry = ComponentRegistry::new();
+        let chart = register_named(
+            &mut registry,
+            &[0x00, 0x61, 0x73, 0x6d, 3],
+            ComponentManifest::new("sales", "Monthly sales"),
+        )
+        .await;
+        assert!(registry.find_by_capability(Capability::Visualization).is_empty());
+
+        let description = ComponentDescription {
+            capabilities: vec![Capability::Visualization, Capability::DataDisplay, Capability::Visualization],
+            ..Default::default()
+        };
+        registry.set_description(chart, description).expect("Failed to set description");
+
+        assert_eq!(
+            registry.metadata(&chart).unwrap().capabilities,
+            [Capability::DataDisplay, Capability::Visualization]
+        );
+        assert_eq!(registry.find_by_capability(Capability::Visualization), [chart]);
+        // Capabilities are part of what similarity compares
+        assert_eq!(registry.find_similar("visualization of revenue", 5)[0].id, chart);
     }
 }
@@ -0,0 +1,100 @@
+//! Content-addressed module sharing.
+//!
+//! Components often hold byte-identical modules: forks, reloads whose
+//! source didn't change, and several components built from the same code.
+//! Compiling each copy costs time and memory for nothing, so compiled
+//! modules live in a [`ModuleCache`] keyed by the SHA-256 of their bytes.
+//! Loading bytes whose module is still held somewhere returns that module
+//! instead of compiling it again.
+//!
+//! The cache holds modules weakly: a module is freed once the last
+//! component using it is dropped, and compiled again if it is loaded later.
+//!
+//! ```rust
+//! use morpheus_runtime::modules::ModuleCache;
+//!
+//! let cache = ModuleCache::new();
+//! let mut compiled = 0;
+//! let a = cache.get_or_compile(b"\0asm", |bytes| { compiled += 1; Ok(bytes.len()) }).unwrap();
+//! let b = cache.get_or_compile(b"\0asm", |bytes| { compiled += 1; Ok(bytes.len()) }).unwrap();
+//!
+//! assert!(std::sync::Arc::ptr_eq(&a, &b));
+//! assert_eq!((compiled, cache.len()), (1, 1));
+//! drop((a, b));
+//! assert!(cache.is_empty());
+//! ```
+
+use morpheus_core::errors::Result;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+
+/// Compiled modules by the SHA-256 of their bytes.
+pub struct ModuleCache<T> {
+    modules: Mutex<HashMap<[u8; 32], Weak<T>>>,
+}
+
+impl<T> ModuleCache<T> {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self { modules: Mutex::new(HashMap::new()) }
+    }
+
+    /// The module compiled from `bytes`, compiling it with `compile` unless
+    /// a live one is cached.
+    ///
+    /// Compiling holds the cache's lock, so concurrent loads of the same
+    /// bytes compile once.
+    pub fn get_or_compile(&self, bytes: &[u8], compile: impl FnOnce(&[u8]) -> Result<T>) -> Result<Arc<T>> {
+        let key: [u8; 32] = Sha256::digest(bytes).into();
+        let mut modules = self.modules.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(module) = modules.get(&key).and_then(Weak::upgrade) {
+            return Ok(module);
+        }
+        let module = Arc::new(compile(bytes)?);
+        modules.retain(|_, module| module.strong_count() > 0);
+        modules.insert(key, Arc::downgrade(&module));
+        Ok(module)
+    }
+
+    /// Modules some component still holds.
+    pub fn len(&self) -> usize {
+        let modules = self.modules.lock().unwrap_or_else(|e| e.into_inner());
+        modules.values().filter(|module| module.strong_count() > 0).count()
+    }
+
+    /// Whether no component holds a cached module.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Default for ModuleCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morpheus_core::errors::MorpheusError;
+
+    #[test]
+    fn test_failed_compiles_are_not_cached() {
+        let cache: ModuleCache<Vec<u8>> = ModuleCache::new();
+        let error = cache.get_or_compile(b"bad", |_| Err(MorpheusError::LoadError("invalid".to_string())));
+        assert!(error.is_err());
+        assert!(cache.is_empty());
+
+        let good = cache.get_or_compile(b"good", |bytes| Ok(bytes.to_vec())).unwrap();
+        let other = cache.get_or_compile(b"other", |bytes| Ok(bytes.to_vec())).unwrap();
+        assert!(!Arc::ptr_eq(&good, &other));
+        assert_eq!(cache.len(), 2);
+
+        drop(other);
+        let again = cache.get_or_compile(b"good", |_| panic!("compiled twice")).unwrap();
+        assert!(Arc::ptr_eq(&good, &again));
+        assert_eq!(cache.len(), 1);
+    }
+}
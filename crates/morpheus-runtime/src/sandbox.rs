@@ -0,0 +1,494 @@
+//! Sandboxed iframe execution mode.
+//!
+//! Worker mode keeps a component off the page's DOM, but still runs it on
+//! the page's origin. For components that shouldn't be trusted with even
+//! that, sandbox mode serves the component into an
+//! `<iframe sandbox="allow-scripts">`: without `allow-same-origin` the frame
+//! gets an opaque origin of its own, so it can't read the page's DOM,
+//! cookies or storage, and its document is served with a Content Security
+//! Policy built from the component's [`Permissions`] by [`frame_csp`]. The
+//! component renders into the frame's own DOM; everything else goes
+//! through `postMessage`, and the host checks each message from the frame
+//! with a [`SandboxBridge`].
+//!
+//! ## Protocol
+//!
+//! ```text
+//! host page                                 frame (opaque origin)
+//!     │ ── src=frame_html(nonce), CSP ────────→ │
+//!     │ ←─────────────────────── iframe `load` ─ │
+//!     │ ── FrameRequest::Load{state} ─────────→ │  import glue, instantiate, render
+//!     │ ←────────── FrameResponse::Ready ──────── │
+//!     │ ←────────── FrameResponse::Resize{height} ─ │  (whenever its content resizes)
+//!     │ ←─── FrameResponse::Event/Feedback/Conversion ─ │
+//!     │ ←────────── FrameResponse::SetState{state} ─ │  bridge keeps it for reloads
+//!     │ ←─── FrameResponse::HostApi{api,args} ─── │  bridge checks the broker
+//!     │ ── FrameRequest::Call{name,args} ────────→ │  (e.g. on_clipboard results)
+//!     │ ── FrameRequest::State{state} ───────────→ │  (shared state changes)
+//!     │ ── FrameRequest::Locale{messages} ───────→ │  (locale switches)
+//!     │ ── FrameRequest::Theme{tokens,scheme} ───→ │  (theme or color scheme switches)
+//!     │ ── FrameRequest::Unload ─────────────────→ │  then the frame is replaced
+//! ```
+//!
+//! A reload replaces the frame rather than reusing it, so nothing the old
+//! version left behind survives; the bridge hands the last state the
+//! component shared to the new frame's [`FrameRequest::Load`].
+
+use morpheus_core::broker::{Decision, HostApi, PermissionBroker};
+use morpheus_core::errors::{MorpheusError, Result};
+use morpheus_core::media::ColorScheme;
+use morpheus_core::permissions::{NetworkPermissions, Permissions};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// `sandbox` attribute of the frame: scripts, and nothing else. In
+/// particular no `allow-same-origin`, which would give the frame the page's
+/// origin back.
+pub const FRAME_SANDBOX: &str = "allow-scripts";
+
+/// Tallest the host lets a frame grow, in CSS pixels.
+pub const MAX_FRAME_HEIGHT: u32 = 4000;
+
+/// Messages from the host page to the frame.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FrameRequest {
+    /// Load and render a component, starting from `state` if given.
+    Load {
+        wasm_base64: String,
+        js_glue: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        state: Option<serde_json::Value>,
+    },
+
+    /// Restore a state snapshot and re-render.
+    State { state: serde_json::Value },
+
+    /// Answer `morpheus.t` from `messages` from now on (see
+    /// [`morpheus_core::i18n`]), and re-render.
+    Locale { messages: BTreeMap<String, String> },
+
+    /// Set `tokens` as the frame's CSS variables and answer
+    /// `morpheus.themeToken` from them (see [`morpheus_core::theme`]); if
+    /// `scheme` changed, re-render for `morpheus.colorScheme`.
+    Theme {
+        tokens: BTreeMap<String, String>,
+        #[serde(default)]
+        scheme: ColorScheme,
+    },
+
+    /// Call one of the component's exports, then re-render; used to hand
+    /// host API results back (`on_clipboard`, `on_position`, ...).
+    Call {
+        name: String,
+        #[serde(default)]
+        args: Vec<serde_json::Value>,
+    },
+
+    /// Unload the component; the frame is discarded next.
+    Unload,
+}
+
+/// Messages from the frame to the host page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FrameResponse {
+    /// The component loaded and rendered; lists its exports.
+    Ready { exports: Vec<String> },
+
+    /// The frame's content is now `height` pixels tall.
+    Resize { height: u32 },
+
+    /// The component emitted a domain event through `morpheus.emitEvent`.
+    Event { name: String, payload: serde_json::Value },
+
+    /// A user rated the component through `morpheus.feedback`.
+    Feedback { rating: u8, text: String },
+
+    /// The user reached a goal, reported through `morpheus.convert`.
+    Conversion { goal: String },
+
+    /// The component shared a state change through `morpheus.setState`.
+    SetState { state: serde_json::Value },
+
+    /// The component called a sensitive host import; the host performs it
+    /// if the bridge lets it through.
+    HostApi {
+        api: HostApi,
+        #[serde(default)]
+        args: Vec<serde_json::Value>,
+    },
+
+    /// The component failed.
+    Error { message: String },
+}
+
+/// Content Security Policy of a frame whose inline bootstrap script carries
+/// `nonce`, for a component granted `permissions`.
+///
+/// Nothing loads unless listed: the bootstrap script, the component's glue
+/// (imported from a `blob:` URL) and WASM, inline styles, and images from
+/// `data:`/`blob:` URLs. The component may connect to the hosts its
+/// [`NetworkPermissions`] allow and nowhere else. The policy repeats the
+/// frame's sandbox, so the frame stays on an opaque origin even if it is
+/// opened directly.
+pub fn frame_csp(permissions: &Permissions, nonce: &str) -> String {
+    let network = match &permissions.network {
+        NetworkPermissions::Denied => String::new(),
+        NetworkPermissions::Unrestricted => "https:".to_string(),
+        NetworkPermissions::AllowList(domains) => domains
+            .iter()
+            .filter_map(|domain| csp_source(domain))
+            .collect::<Vec<_>>()
+            .join(" "),
+    };
+    let connect = if network.is_empty() { "'none'".to_string() } else { network.clone() };
+    let images = if network.is_empty() {
+        "data: blob:".to_string()
+    } else {
+        format!("data: blob: {}", network)
+    };
+    format!(
+        "default-src 'none'; script-src 'nonce-{}' 'wasm-unsafe-eval' blob:; style-src 'unsafe-inline'; \
+         img-src {}; font-src data:; connect-src {}; base-uri 'none'; form-action 'none'; sandbox {}",
+        nonce, images, connect, FRAME_SANDBOX
+    )
+}
+
+/// An allow-listed domain as a CSP source (`https://` unless it names its
+/// scheme), or `None` if it would break out of the directive.
+fn csp_source(domain: &str) -> Option<String> {
+    let domain = domain.trim();
+    if domain.is_empty() || domain.contains(|c: char| c.is_whitespace() || matches!(c, ';' | ',' | '\'')) {
+        return None;
+    }
+    if domain.contains("://") {
+        Some(domain.to_string())
+    } else {
+        Some(format!("https://{}", domain))
+    }
+}
+
+/// The frame's document, with its bootstrap script tagged with `nonce`
+/// (which must match the [`frame_csp`] it is served with, and be base64).
+pub fn frame_html(nonce: &str) -> String {
+    FRAME_PAGE.replace("__NONCE__", nonce)
+}
+
+/// Host-side checks on messages from a sandboxed frame, and the state that
+/// carries over when the frame is reloaded.
+pub struct SandboxBridge {
+    broker: PermissionBroker,
+    /// Last state the component shared.
+    state: Option<serde_json::Value>,
+    /// Frames loaded so far.
+    generation: u64,
+}
+
+impl SandboxBridge {
+    /// A bridge for a component granted `permissions`.
+    pub fn new(permissions: Permissions) -> Self {
+        Self {
+            broker: PermissionBroker::new(permissions),
+            state: None,
+            generation: 0,
+        }
+    }
+
+    /// The broker deciding host API calls, e.g. to record the user's answer
+    /// to a confirmation.
+    pub fn broker_mut(&mut self) -> &mut PermissionBroker {
+        &mut self.broker
+    }
+
+    /// Number of frames loaded through this bridge.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Last state the component shared, if any.
+    pub fn state(&self) -> Option<&serde_json::Value> {
+        self.state.as_ref()
+    }
+
+    /// The request loading a component into a fresh frame, carrying over
+    /// the state the previous frame shared.
+    pub fn load(&mut self, wasm_base64: impl Into<String>, js_glue: impl Into<String>) -> FrameRequest {
+        self.generation += 1;
+        FrameRequest::Load {
+            wasm_base64: wasm_base64.into(),
+            js_glue: js_glue.into(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// The request handing shared state to the frame.
+    pub fn share_state(&mut self, state: serde_json::Value) -> FrameRequest {
+        self.state = Some(state.clone());
+        FrameRequest::State { state }
+    }
+
+    /// Check a message from the frame, returning it (with its height
+    /// clamped, for [`FrameResponse::Resize`]) if the host should act on it.
+    ///
+    /// Host API calls the broker denies are refused; calls it wants
+    /// confirmed are let through for the host to ask the user.
+    pub fn handle(&mut self, response: FrameResponse) -> Result<FrameResponse> {
+        match response {
+            FrameResponse::HostApi { api, .. } => match self.broker.decide(api) {
+                Decision::Deny { reason } => Err(MorpheusError::PermissionDenied(reason)),
+                Decision::Allow | Decision::Confirm => Ok(response),
+            },
+            FrameResponse::SetState { state } => {
+                self.state = Some(state.clone());
+                Ok(FrameResponse::SetState { state })
+            }
+            FrameResponse::Resize { height } => Ok(FrameResponse::Resize {
+                height: height.min(MAX_FRAME_HEIGHT),
+            }),
+            FrameResponse::Error { message } => Err(MorpheusError::LoadError(format!(
+                "Sandboxed component failed: {}",
+                message
+            ))),
+            FrameResponse::Ready { .. }
+            | FrameResponse::Event { .. }
+            | FrameResponse::Feedback { .. }
+            | FrameResponse::Conversion { .. } => Ok(response),
+        }
+    }
+}
+
+/// The frame's document; `__NONCE__` is filled in by [`frame_html`].
+const FRAME_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="referrer" content="no-referrer">
+<style>html, body { margin: 0; padding: 0; background: transparent; }</style>
+</head>
+<body>
+<div id="componentMount"></div>
+<script nonce="__NONCE__">
+(() => {
+    // Messages from the page are trusted; the page's origin is unknown here
+    // (and ours is opaque), so the only check is that they come from the parent
+    const post = (message) => parent.postMessage(message, '*');
+    const mount = document.getElementById('componentMount');
+    let component = null;
+
+    const hostApi = (api, ...args) => post({ type: 'host_api', api, args });
+    let fileRequests = 0;
+    let messages = {};
+    let tokens = {};
+    let scheme = 'light';
+    const applyTheme = (theme, newScheme) => {
+        tokens = theme || {};
+        for (const [name, value] of Object.entries(tokens)) {
+            document.documentElement.style.setProperty(`--morpheus-${name.replaceAll('.', '-')}`, value);
+        }
+        const changed = (newScheme || 'light') !== scheme;
+        scheme = newScheme || 'light';
+        if (changed) render();
+    };
+    const format = (message, args) => {
+        const values = JSON.parse(args || 'null') || {};
+        return message.replace(/\{([^{}]+)\}/g, (placeholder, name) => name in values
+            ? (typeof values[name] === 'string' ? values[name] : JSON.stringify(values[name]))
+            : placeholder);
+    };
+    window.morpheus = {
+        t(key, fallback, args) {
+            return format(messages[key] ?? fallback ?? key, args);
+        },
+        themeToken(name) {
+            return tokens[name] ?? '';
+        },
+        colorScheme() {
+            return scheme;
+        },
+        emitEvent(name, payload) {
+            post({ type: 'event', name, payload: JSON.parse(payload || 'null') });
+        },
+        feedback(rating, text) {
+            post({ type: 'feedback', rating, text: text || '' });
+        },
+        convert(goal) {
+            post({ type: 'conversion', goal: goal || '' });
+        },
+        setState(state) {
+            post({ type: 'set_state', state: JSON.parse(state || 'null') });
+        },
+        clipboardWrite(text) {
+            hostApi('clipboard_write', text || '');
+        },
+        clipboardRead() {
+            hostApi('clipboard_read');
+        },
+        // The request ID is ours; the page answers with it
+        pickFiles(accept, multiple) {
+            const request = ++fileRequests;
+            hostApi('pick_files', accept || '', !!multiple, request);
+            return request;
+        },
+        notify(title, body) {
+            hostApi('notify', title || '', body || '');
+        },
+        getPosition() {
+            hostApi('get_position');
+        }
+    };
+
+    function render() {
+        if (typeof component?.render === 'function') mount.innerHTML = component.render();
+    }
+
+    function restore(state) {
+        if (state !== undefined && state !== null && typeof component?.restore_state === 'function') {
+            component.restore_state(JSON.stringify(state));
+        }
+    }
+
+    async function load({ wasm_base64, js_glue, state }) {
+        const url = URL.createObjectURL(new Blob([js_glue], { type: 'application/javascript' }));
+        try {
+            component = await import(url);
+            await component.default(await WebAssembly.compile(Uint8Array.from(atob(wasm_base64), c => c.charCodeAt(0))));
+        } finally {
+            URL.revokeObjectURL(url);
+        }
+        restore(state);
+        render();
+        post({ type: 'ready', exports: Object.keys(component).filter(name => typeof component[name] === 'function') });
+    }
+
+    function call({ name, args }) {
+        const fn = component?.[name];
+        if (typeof fn !== 'function') return;
+        fn(...(args || []));
+        render();
+    }
+
+    new ResizeObserver(() => post({ type: 'resize', height: Math.ceil(document.documentElement.scrollHeight) }))
+        .observe(document.documentElement);
+
+    window.addEventListener('message', async (event) => {
+        if (event.source !== parent) return;
+        const request = event.data;
+        try {
+            if (request.type === 'load') await load(request);
+            else if (request.type === 'state') { restore(request.state); render(); }
+            else if (request.type === 'locale') { messages = request.messages || {}; render(); }
+            else if (request.type === 'theme') applyTheme(request.tokens, request.scheme);
+            else if (request.type === 'call') call(request);
+            else if (request.type === 'unload') { component = null; mount.innerHTML = ''; }
+        } catch (error) {
+            post({ type: 'error', message: error.message || String(error) });
+        }
+    });
+})();
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morpheus_core::permissions::ApiPermission;
+
+    fn permissions(network: NetworkPermissions, apis: &[ApiPermission]) -> Permissions {
+        Permissions {
+            network,
+            apis: apis.iter().cloned().collect(),
+            ..Permissions::default()
+        }
+    }
+
+    #[test]
+    fn test_wire_format() {
+        let json = serde_json::to_value(FrameRequest::Call {
+            name: "on_clipboard".to_string(),
+            args: vec![serde_json::json!("copied")],
+        })
+        .unwrap();
+        assert_eq!(json, serde_json::json!({ "type": "call", "name": "on_clipboard", "args": ["copied"] }));
+
+        let json = serde_json::json!({ "type": "locale", "messages": { "todo.add": "Ajouter" } });
+        let locale: FrameRequest = serde_json::from_value(json).unwrap();
+        assert_eq!(locale, FrameRequest::Locale { messages: BTreeMap::from([("todo.add".into(), "Ajouter".into())]) });
+
+        let response: FrameResponse =
+            serde_json::from_value(serde_json::json!({ "type": "host_api", "api": "clipboard_read" })).unwrap();
+        assert_eq!(response, FrameResponse::HostApi { api: HostApi::ClipboardRead, args: vec![] });
+    }
+
+    #[test]
+    fn test_csp_follows_network_permissions() {
+        let denied = frame_csp(&permissions(NetworkPermissions::Denied, &[]), "abc");
+        assert!(denied.starts_with("default-src 'none'; script-src 'nonce-abc' 'wasm-unsafe-eval' blob:;"));
+        assert!(denied.contains("connect-src 'none';"));
+        assert!(denied.ends_with("sandbox allow-scripts"));
+
+        let allowed = frame_csp(
+            &permissions(
+                NetworkPermissions::AllowList(vec![
+                    "api.example.com".to_string(),
+                    "http://localhost:8080".to_string(),
+                    "evil.com; script-src *".to_string(),
+                ]),
+                &[],
+            ),
+            "abc",
+        );
+        assert!(allowed.contains("connect-src https://api.example.com http://localhost:8080;"));
+        assert!(!allowed.contains("evil.com"));
+
+        let open = frame_csp(&permissions(NetworkPermissions::Unrestricted, &[]), "abc");
+        assert!(open.contains("connect-src https:;"));
+    }
+
+    #[test]
+    fn test_bridge_checks_host_apis() {
+        let mut bridge = SandboxBridge::new(permissions(NetworkPermissions::Denied, &[ApiPermission::Clipboard]));
+
+        let write = FrameResponse::HostApi { api: HostApi::ClipboardWrite, args: vec![serde_json::json!("hi")] };
+        assert_eq!(bridge.handle(write.clone()).unwrap(), write);
+        // Confirmed by the host
+        assert!(bridge.handle(FrameResponse::HostApi { api: HostApi::ClipboardRead, args: vec![] }).is_ok());
+        bridge.broker_mut().answer(HostApi::ClipboardRead, false);
+        assert!(bridge.handle(FrameResponse::HostApi { api: HostApi::ClipboardRead, args: vec![] }).is_err());
+        assert!(matches!(
+            bridge.handle(FrameResponse::HostApi { api: HostApi::PickFiles, args: vec![] }),
+            Err(MorpheusError::PermissionDenied(_))
+        ));
+
+        assert_eq!(
+            bridge.handle(FrameResponse::Resize { height: 1_000_000 }).unwrap(),
+            FrameResponse::Resize { height: MAX_FRAME_HEIGHT }
+        );
+    }
+
+    #[test]
+    fn test_reload_carries_state() {
+        let mut bridge = SandboxBridge::new(Permissions::default());
+        assert_eq!(
+            bridge.load("AA==", "glue"),
+            FrameRequest::Load { wasm_base64: "AA==".to_string(), js_glue: "glue".to_string(), state: None }
+        );
+
+        bridge.handle(FrameResponse::SetState { state: serde_json::json!({ "count": 3 }) }).unwrap();
+        let FrameRequest::Load { state, .. } = bridge.load("AA==", "glue") else {
+            panic!("expected a load request");
+        };
+        assert_eq!(state, Some(serde_json::json!({ "count": 3 })));
+        assert_eq!(bridge.generation(), 2);
+    }
+
+    #[test]
+    fn test_frame_html_uses_nonce() {
+        let html = frame_html("n0nce");
+        assert!(html.contains(r#"<script nonce="n0nce">"#));
+        assert!(!html.contains("__NONCE__"));
+        assert!(html.contains(r#"<div id="componentMount"></div>"#));
+    }
+}
@@ -0,0 +1,27 @@
+//! [`SnapshotStore`] backends.
+//!
+//! - [`FsStore`] - a directory on the local filesystem
+//! - [`S3Store`] - S3-compatible object storage (AWS, MinIO, R2, ...)
+//! - [`IndexedDbStore`] - the browser's IndexedDB (wasm32 only)
+//!
+//! Any of them can be wrapped in an [`EncryptedStore`] for encryption at rest.
+//!
+//! [`SnapshotStore`]: morpheus_core::store::SnapshotStore
+
+mod encrypted;
+#[cfg(not(target_arch = "wasm32"))]
+mod fs;
+#[cfg(target_arch = "wasm32")]
+mod indexeddb;
+#[cfg(any(target_arch = "wasm32", test))]
+mod key_range;
+#[cfg(not(target_arch = "wasm32"))]
+mod s3;
+
+pub use encrypted::{EncryptedStore, KeyProvider, LocalKey};
+#[cfg(not(target_arch = "wasm32"))]
+pub use fs::FsStore;
+#[cfg(target_arch = "wasm32")]
+pub use indexeddb::IndexedDbStore;
+#[cfg(not(target_arch = "wasm32"))]
+pub use s3::{S3Config, S3Store};
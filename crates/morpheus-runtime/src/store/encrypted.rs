@@ -0,0 +1,256 @@
+//! Envelope encryption for any store.
+//!
+//! Generated source and user state may contain secrets. [`EncryptedStore`]
+//! wraps another [`SnapshotStore`] and encrypts every blob before it leaves
+//! the process, so nothing is stored in plaintext.
+//!
+//! Each blob is encrypted with a fresh AES-256-GCM data key, and the data
+//! key is wrapped by a [`KeyProvider`]: a local key-encryption key
+//! ([`LocalKey`]) or a KMS. The blob's store key is bound in as associated
+//! data, so ciphertext can't be moved to another key undetected.
+//!
+//! Blob layout:
+//!
+//! ```text
+//! "MPHE" | version (1) | key id length (1) | key id
+//!        | wrapped key length (2, BE) | wrapped key | nonce (12) | ciphertext
+//! ```
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use async_trait::async_trait;
+use morpheus_core::errors::{MorpheusError, Result};
+use morpheus_core::store::{MaybeSend, SnapshotStore};
+
+const MAGIC: &[u8; 4] = b"MPHE";
+const FORMAT_VERSION: u8 = 1;
+const NONCE_LEN: usize = 12;
+
+/// Wraps and unwraps data keys; the hook for KMS integration.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait KeyProvider: MaybeSend {
+    /// Identifies the key-encryption key, recorded with each blob.
+    fn key_id(&self) -> &str;
+
+    /// Encrypt a data key.
+    async fn wrap(&self, data_key: &[u8]) -> Result<Vec<u8>>;
+
+    /// Decrypt a data key wrapped by the key named `key_id`.
+    async fn unwrap(&self, key_id: &str, wrapped: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Key-encryption key held in process memory.
+pub struct LocalKey {
+    id: String,
+    cipher: Aes256Gcm,
+}
+
+fn crypto_error(what: &str) -> MorpheusError {
+    MorpheusError::Other(format!("Encryption error: {}", what))
+}
+
+/// AES-256-GCM encrypt with a random nonce, returning nonce || ciphertext.
+fn seal(cipher: &Aes256Gcm, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad })
+        .map_err(|_| crypto_error("encryption failed"))?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+/// Reverse [`seal`].
+fn open(cipher: &Aes256Gcm, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return Err(crypto_error("ciphertext too short"));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| crypto_error("decryption failed (wrong key or tampered data)"))
+}
+
+impl LocalKey {
+    /// Use a 256-bit key, identified as `id`.
+    pub fn new(id: impl Into<String>, key: [u8; 32]) -> Self {
+        Self {
+            id: id.into(),
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl KeyProvider for LocalKey {
+    fn key_id(&self) -> &str {
+        &self.id
+    }
+
+    async fn wrap(&self, data_key: &[u8]) -> Result<Vec<u8>> {
+        seal(&self.cipher, data_key, self.id.as_bytes())
+    }
+
+    async fn unwrap(&self, key_id: &str, wrapped: &[u8]) -> Result<Vec<u8>> {
+        if key_id != self.id {
+            return Err(crypto_error(&format!("blob was encrypted with unknown key '{}'", key_id)));
+        }
+        open(&self.cipher, wrapped, key_id.as_bytes())
+    }
+}
+
+/// Store that encrypts blobs before handing them to an inner store.
+///
+/// Blobs that aren't encrypted are refused, since anyone who can write to
+/// the inner store could otherwise plant them. To switch an existing store
+/// over in place, enable [`with_plaintext_migration`](Self::with_plaintext_migration)
+/// until its blobs have been rewritten.
+pub struct EncryptedStore<S, K> {
+    inner: S,
+    keys: K,
+    plaintext_migration: bool,
+}
+
+impl<S: SnapshotStore, K: KeyProvider> EncryptedStore<S, K> {
+    /// Encrypt everything written to `inner` with data keys wrapped by `keys`.
+    pub fn new(inner: S, keys: K) -> Self {
+        Self {
+            inner,
+            keys,
+            plaintext_migration: false,
+        }
+    }
+
+    /// Read blobs written before encryption was enabled back unchanged
+    /// (off by default).
+    pub fn with_plaintext_migration(mut self, enabled: bool) -> Self {
+        self.plaintext_migration = enabled;
+        self
+    }
+
+    async fn encrypt(&self, key: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let data_key = Aes256Gcm::generate_key(&mut OsRng);
+        let wrapped = self.keys.wrap(&data_key).await?;
+        let key_id = self.keys.key_id().as_bytes();
+        let key_id_len = u8::try_from(key_id.len()).map_err(|_| crypto_error("key id too long"))?;
+        let wrapped_len = u16::try_from(wrapped.len()).map_err(|_| crypto_error("wrapped key too long"))?;
+
+        let mut blob = MAGIC.to_vec();
+        blob.push(FORMAT_VERSION);
+        blob.push(key_id_len);
+        blob.extend_from_slice(key_id);
+        blob.extend_from_slice(&wrapped_len.to_be_bytes());
+        blob.extend(wrapped);
+        blob.extend(seal(&Aes256Gcm::new(&data_key), plaintext, key.as_bytes())?);
+        Ok(blob)
+    }
+
+    async fn decrypt(&self, key: &str, blob: Vec<u8>) -> Result<Vec<u8>> {
+        let Some(rest) = blob.strip_prefix(MAGIC.as_slice()) else {
+            if self.plaintext_migration {
+                return Ok(blob);
+            }
+            return Err(crypto_error(&format!("'{}' is not encrypted", key)));
+        };
+        let truncated = || crypto_error("truncated blob");
+
+        let (&version, rest) = rest.split_first().ok_or_else(truncated)?;
+        if version != FORMAT_VERSION {
+            return Err(crypto_error(&format!("unsupported format version {}", version)));
+        }
+        let (&key_id_len, rest) = rest.split_first().ok_or_else(truncated)?;
+        let (key_id, rest) = rest.split_at_checked(key_id_len as usize).ok_or_else(truncated)?;
+        let (wrapped_len, rest) = rest.split_at_checked(2).ok_or_else(truncated)?;
+        let wrapped_len = u16::from_be_bytes([wrapped_len[0], wrapped_len[1]]) as usize;
+        let (wrapped, sealed) = rest.split_at_checked(wrapped_len).ok_or_else(truncated)?;
+
+        let key_id = std::str::from_utf8(key_id).map_err(|_| crypto_error("invalid key id"))?;
+        let data_key = self.keys.unwrap(key_id, wrapped).await?;
+        if data_key.len() != 32 {
+            return Err(crypto_error("invalid data key"));
+        }
+        open(&Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&data_key)), sealed, key.as_bytes())
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<S: SnapshotStore, K: KeyProvider> SnapshotStore for EncryptedStore<S, K> {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+        let blob = self.encrypt(key, &bytes).await?;
+        self.inner.put(key, blob).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match self.inner.get(key).await? {
+            Some(blob) => Ok(Some(self.decrypt(key, blob).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        self.inner.list(prefix).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool> {
+        self.inner.delete(key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morpheus_core::store::MemoryStore;
+
+    fn store() -> EncryptedStore<MemoryStore, LocalKey> {
+        EncryptedStore::new(MemoryStore::new(), LocalKey::new("test-key", [7; 32]))
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_is_encrypted_at_rest() {
+        let store = store();
+        let secret = br#"{"api_token": "sk-live-1234"}"#.to_vec();
+
+        store.put("state/1.json", secret.clone()).await.unwrap();
+
+        let raw = store.inner.get("state/1.json").await.unwrap().unwrap();
+        assert!(raw.starts_with(MAGIC));
+        assert!(!raw.windows(7).any(|w| w == b"sk-live"));
+        assert_eq!(store.get("state/1.json").await.unwrap(), Some(secret));
+    }
+
+    #[tokio::test]
+    async fn test_plaintext_only_read_while_migrating() {
+        let store = store();
+        store.inner.put("state/old.json", b"{}".to_vec()).await.unwrap();
+        assert!(store.get("state/old.json").await.is_err());
+
+        let store = store.with_plaintext_migration(true);
+        assert_eq!(store.get("state/old.json").await.unwrap(), Some(b"{}".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn test_wrong_key_fails() {
+        let store = store();
+        store.put("wasm/1.wasm", vec![0, 97, 115, 109]).await.unwrap();
+        let blob = store.inner.get("wasm/1.wasm").await.unwrap().unwrap();
+
+        let other = EncryptedStore::new(MemoryStore::new(), LocalKey::new("test-key", [8; 32]));
+        other.inner.put("wasm/1.wasm", blob).await.unwrap();
+
+        assert!(other.get("wasm/1.wasm").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_blob_bound_to_its_key() {
+        let store = store();
+        store.put("state/a.json", b"a".to_vec()).await.unwrap();
+        let blob = store.inner.get("state/a.json").await.unwrap().unwrap();
+
+        store.inner.put("state/b.json", blob).await.unwrap();
+
+        assert!(store.get("state/b.json").await.is_err());
+    }
+}
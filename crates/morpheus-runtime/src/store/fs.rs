@@ -0,0 +1,164 @@
+//! Filesystem-backed store.
+
+use async_trait::async_trait;
+use morpheus_core::errors::{MorpheusError, Result};
+use morpheus_core::store::{validate_key, SnapshotStore};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Store that keeps each key as a file under a root directory.
+///
+/// Key segments become directories, so `wasm/v1.wasm` is stored at
+/// `<root>/wasm/v1.wasm`.
+#[derive(Debug, Clone)]
+pub struct FsStore {
+    root: PathBuf,
+}
+
+fn io_error(action: &str, path: &Path, e: std::io::Error) -> MorpheusError {
+    MorpheusError::Other(format!("Failed to {} {}: {}", action, path.display(), e))
+}
+
+impl FsStore {
+    /// Use `root` as the store directory, creating it if needed.
+    pub async fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root)
+            .await
+            .map_err(|e| io_error("create", &root, e))?;
+        Ok(Self { root })
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        validate_key(key)?;
+        Ok(key.split('/').fold(self.root.clone(), |path, segment| path.join(segment)))
+    }
+
+    /// Collect keys of all files under `dir`, relative to the root.
+    async fn walk(&self, dir: PathBuf, keys: &mut Vec<String>) -> Result<()> {
+        let mut pending = vec![dir];
+        while let Some(dir) = pending.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(io_error("read", &dir, e)),
+            };
+            while let Some(entry) = entries.next_entry().await.map_err(|e| io_error("read", &dir, e))? {
+                let path = entry.path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else if path.extension().is_none_or(|ext| ext != "tmp") {
+                    let relative = path.strip_prefix(&self.root).unwrap_or(&path);
+                    let key = relative
+                        .components()
+                        .map(|c| c.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/");
+                    keys.push(key);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SnapshotStore for FsStore {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| io_error("create", parent, e))?;
+        }
+
+        // Write then rename so readers never see a partial file
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        fs::write(&tmp, bytes).await.map_err(|e| io_error("write", &tmp, e))?;
+        fs::rename(&tmp, &path).await.map_err(|e| io_error("write", &path, e))
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.path(key)?;
+        match fs::read(&path).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error("read", &path, e)),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        self.walk(self.root.clone(), &mut keys).await?;
+        keys.retain(|key| key.starts_with(prefix));
+        keys.sort();
+        Ok(keys)
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool> {
+        let path = self.path(key)?;
+        match fs::remove_file(&path).await {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(io_error("delete", &path, e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morpheus_core::store::{state_key, wasm_key, STATE_PREFIX};
+
+    fn temp_root(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("morpheus-fs-store-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let root = temp_root("round-trip");
+        let store = FsStore::open(&root).await.expect("Failed to open store");
+
+        store.put(&wasm_key("v1"), vec![1, 2, 3]).await.unwrap();
+
+        assert!(root.join("wasm").join("v1.wasm").exists());
+        assert_eq!(store.get("wasm/v1.wasm").await.unwrap(), Some(vec![1, 2, 3]));
+        assert_eq!(store.get("wasm/v2.wasm").await.unwrap(), None);
+        assert!(store.delete("wasm/v1.wasm").await.unwrap());
+        assert!(!store.delete("wasm/v1.wasm").await.unwrap());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_list_by_prefix() {
+        let root = temp_root("list");
+        let store = FsStore::open(&root).await.unwrap();
+        for key in [state_key("b"), state_key("a"), wasm_key("a")] {
+            store.put(&key, Vec::new()).await.unwrap();
+        }
+
+        assert_eq!(
+            store.list(STATE_PREFIX).await.unwrap(),
+            vec!["state/a.json", "state/b.json"]
+        );
+        assert_eq!(store.list("").await.unwrap().len(), 3);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_escaping_keys() {
+        let root = temp_root("escape");
+        let store = FsStore::open(&root).await.unwrap();
+
+        assert!(store.put("../outside", Vec::new()).await.is_err());
+        assert!(store.get("/etc/passwd").await.is_err());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
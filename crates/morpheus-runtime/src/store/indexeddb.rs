@@ -0,0 +1,132 @@
+//! Browser IndexedDB store.
+
+use async_trait::async_trait;
+use super::key_range::prefix_upper_bound;
+use js_sys::{Array, Function, JsString, Promise, Uint8Array};
+use morpheus_core::errors::{MorpheusError, Result};
+use morpheus_core::store::{validate_key, SnapshotStore};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbKeyRange, IdbObjectStore, IdbRequest, IdbTransactionMode};
+
+/// Object store holding all blobs.
+const OBJECT_STORE: &str = "blobs";
+
+/// Store backed by an IndexedDB database in the browser.
+///
+/// Blobs are kept as `Uint8Array` values in a single object store, keyed
+/// by their store key.
+#[derive(Debug, Clone)]
+pub struct IndexedDbStore {
+    db: IdbDatabase,
+}
+
+fn js_error(e: JsValue) -> MorpheusError {
+    MorpheusError::Other(format!("IndexedDB error: {:?}", e))
+}
+
+/// Wait for an IndexedDB request to complete.
+async fn complete(request: &IdbRequest) -> Result<JsValue> {
+    let promise = Promise::new(&mut |resolve: Function, reject: Function| {
+        let success = request.clone();
+        let on_success = Closure::once_into_js(move || {
+            let _ = resolve.call1(&JsValue::NULL, &success.result().unwrap_or(JsValue::UNDEFINED));
+        });
+        let failure = request.clone();
+        let on_error = Closure::once_into_js(move || {
+            let error = failure.error().ok().flatten().map(JsValue::from).unwrap_or(JsValue::UNDEFINED);
+            let _ = reject.call1(&JsValue::NULL, &error);
+        });
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+    JsFuture::from(promise).await.map_err(js_error)
+}
+
+impl IndexedDbStore {
+    /// Open (or create) the database `name`.
+    pub async fn open(name: &str) -> Result<Self> {
+        let factory = web_sys::window()
+            .ok_or_else(|| MorpheusError::InvalidState("No window".to_string()))?
+            .indexed_db()
+            .map_err(js_error)?
+            .ok_or_else(|| MorpheusError::InvalidState("IndexedDB is unavailable".to_string()))?;
+        let request = factory.open_with_u32(name, 1).map_err(js_error)?;
+
+        let upgrading = request.clone();
+        let on_upgrade = Closure::once_into_js(move || {
+            if let Ok(db) = upgrading.result().and_then(|db| db.dyn_into::<IdbDatabase>()) {
+                if !db.object_store_names().contains(OBJECT_STORE) {
+                    let _ = db.create_object_store(OBJECT_STORE);
+                }
+            }
+        });
+        request.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+
+        let db = complete(&request).await?.dyn_into::<IdbDatabase>().map_err(js_error)?;
+        Ok(Self { db })
+    }
+
+    fn object_store(&self, mode: IdbTransactionMode) -> Result<IdbObjectStore> {
+        self.db
+            .transaction_with_str_and_mode(OBJECT_STORE, mode)
+            .and_then(|tx| tx.object_store(OBJECT_STORE))
+            .map_err(js_error)
+    }
+}
+
+#[async_trait(?Send)]
+impl SnapshotStore for IndexedDbStore {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+        validate_key(key)?;
+        let value = Uint8Array::from(bytes.as_slice());
+        let request = self
+            .object_store(IdbTransactionMode::Readwrite)?
+            .put_with_key(&value, &JsValue::from_str(key))
+            .map_err(js_error)?;
+        complete(&request).await.map(|_| ())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        validate_key(key)?;
+        let request = self
+            .object_store(IdbTransactionMode::Readonly)?
+            .get(&JsValue::from_str(key))
+            .map_err(js_error)?;
+        let value = complete(&request).await?;
+        if value.is_undefined() {
+            return Ok(None);
+        }
+        Ok(Some(Uint8Array::new(&value).to_vec()))
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let lower = JsValue::from_str(prefix);
+        let range = match prefix_upper_bound(prefix) {
+            Some(upper) => {
+                let upper: JsValue = JsString::from_char_code(&upper).into();
+                IdbKeyRange::bound_with_lower_open_and_upper_open(&lower, &upper, false, true)
+            }
+            None => IdbKeyRange::lower_bound(&lower),
+        }
+        .map_err(js_error)?;
+        let request = self
+            .object_store(IdbTransactionMode::Readonly)?
+            .get_all_keys_with_key(&range)
+            .map_err(js_error)?;
+        let keys: Array = complete(&request).await?.unchecked_into();
+        Ok(keys.iter().filter_map(|key| key.as_string()).collect())
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool> {
+        validate_key(key)?;
+        let existed = self.get(key).await?.is_some();
+        let request = self
+            .object_store(IdbTransactionMode::Readwrite)?
+            .delete(&JsValue::from_str(key))
+            .map_err(js_error)?;
+        complete(&request).await?;
+        Ok(existed)
+    }
+}
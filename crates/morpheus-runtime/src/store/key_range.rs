@@ -0,0 +1,51 @@
+//! Prefix listing for stores that list keys by range
+//! ([`IndexedDbStore`](super::IndexedDbStore)).
+//!
+//! IndexedDB sorts string keys by UTF-16 code unit, so every key starting
+//! with a prefix lies between the prefix and the first string after all of
+//! them, which this works out.
+
+/// UTF-16 code units of the first string, in IndexedDB key order, after
+/// every string that starts with `prefix`; `None` if there is none (for an
+/// empty prefix, or one of only U+FFFF)
+pub(super) fn prefix_upper_bound(prefix: &str) -> Option<Vec<u16>> {
+    let mut units: Vec<u16> = prefix.encode_utf16().collect();
+    while let Some(last) = units.pop() {
+        if last < u16::MAX {
+            units.push(last + 1);
+            return Some(units);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether IndexedDB lists `key` for `prefix`
+    fn listed(key: &str, prefix: &str) -> bool {
+        let key: Vec<u16> = key.encode_utf16().collect();
+        key >= prefix.encode_utf16().collect::<Vec<_>>()
+            && prefix_upper_bound(prefix).is_none_or(|upper| key < upper)
+    }
+
+    #[test]
+    fn test_range_holds_exactly_the_prefixed_keys() {
+        for key in ["events/", "events/main.json", "events/😀.json", "events/\u{ffff}x"] {
+            assert!(listed(key, "events/"), "{}", key);
+        }
+        for key in ["events", "eventt/main.json", "events0", "history.json"] {
+            assert!(!listed(key, "events/"), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_bound_carries_past_maximal_units() {
+        assert_eq!(prefix_upper_bound("a/"), Some(vec![u16::from(b'a'), u16::from(b'0')]));
+        assert_eq!(prefix_upper_bound("a\u{ffff}"), Some(vec![u16::from(b'b')]));
+        assert_eq!(prefix_upper_bound("\u{ffff}"), None);
+        assert_eq!(prefix_upper_bound(""), None);
+        assert!(listed("anything", ""));
+    }
+}
@@ -6,9 +6,13 @@
 //! browser/WASM environments. The code is here to document the intended
 //! API, but won't compile for native targets.
 
+use crate::modules::ModuleCache;
 use morpheus_core::errors::Result;
 use morpheus_core::permissions::Permissions;
 use morpheus_core::component::{ComponentId, ComponentMetadata};
+use morpheus_core::semver::{Bump, SemVer};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, OnceLock};
 
 /// A loaded WASM component instance.
 ///
@@ -21,10 +25,42 @@ pub struct WasmComponent {
     /// Component metadata.
     metadata: ComponentMetadata,
 
-    /// WASM bytes (stored for reload).
-    wasm_bytes: Vec<u8>,
+    /// Compiled module, shared with every component loaded from the same
+    /// bytes (see [`crate::modules`]).
+    module: Arc<Module>,
+
+    /// The running instance, until the component is unloaded.
+    instance: Option<Instance>,
 }
 
+/// A compiled module.
+///
+/// Note: A placeholder. In a browser this would hold the
+/// WebAssembly::Module, compiled once from the bytes.
+struct Module {
+    /// WASM bytes.
+    bytes: Vec<u8>,
+}
+
+/// Modules shared by all components.
+fn modules() -> &'static ModuleCache<Module> {
+    static MODULES: OnceLock<ModuleCache<Module>> = OnceLock::new();
+    MODULES.get_or_init(ModuleCache::new)
+}
+
+/// Compile `wasm_bytes`, or share the module already compiled from them.
+fn compile(wasm_bytes: &[u8]) -> Result<Arc<Module>> {
+    // In a real implementation: WebAssembly::Module::new(&wasm_bytes)
+    modules().get_or_compile(wasm_bytes, |bytes| Ok(Module { bytes: bytes.to_vec() }))
+}
+
+/// An instantiated module.
+///
+/// Note: A placeholder, like the rest of this module. In a browser this
+/// would hold the WebAssembly::Instance, whose memory is what unloading
+/// frees.
+struct Instance;
+
 impl WasmComponent {
     /// Load a WASM module from bytes.
     ///
@@ -32,25 +68,35 @@ impl WasmComponent {
     /// this would use WebAssembly::Module and WebAssembly::Instance from web-sys.
     pub async fn load(wasm_bytes: &[u8], permissions: Permissions) -> Result<Self> {
         // In a real implementation:
-        // 1. Compile: WebAssembly::Module::new(&wasm_bytes)
+        // 1. Compile, or share the module compiled from the same bytes
         // 2. Create imports based on permissions
         // 3. Instantiate: WebAssembly::Instance::new(&module, &imports)
         // 4. Store module and instance for hot-reload
 
-        let component_id = ComponentId(simple_hash(wasm_bytes));
+        let module = compile(wasm_bytes)?;
+        let component_id = ComponentId(content_hash(wasm_bytes));
 
         let metadata = ComponentMetadata {
             id: component_id,
             name: format!("component-{:016x}", component_id.0),
-            version: 1,
+            version: SemVer::INITIAL,
             loaded_at: get_timestamp(),
             ai_generated: false,
+            provenance: Default::default(),
+            capabilities: Vec::new(),
+            interface_hash: None,
+            dependencies: Vec::new(),
+            lifecycle: Default::default(),
+            thumbnail: None,
+            stats: Default::default(),
+            fallback: None,
         };
 
         Ok(Self {
             permissions,
             metadata,
-            wasm_bytes: wasm_bytes.to_vec(),
+            module,
+            instance: Some(Instance),
         })
     }
 
@@ -69,31 +115,79 @@ impl WasmComponent {
         &self.metadata
     }
 
+    /// The module's WASM bytes.
+    pub fn wasm_bytes(&self) -> &[u8] {
+        &self.module.bytes
+    }
+
+    /// A copy of this component with its own ID and name, as if freshly
+    /// loaded from the same module with the same permissions.
+    pub fn fork(&self, id: ComponentId, name: impl Into<String>) -> Self {
+        Self {
+            permissions: self.permissions.clone(),
+            metadata: ComponentMetadata {
+                id,
+                name: name.into(),
+                version: SemVer::INITIAL,
+                loaded_at: get_timestamp(),
+                ..self.metadata.clone()
+            },
+            module: Arc::clone(&self.module),
+            instance: Some(Instance),
+        }
+    }
+
+    /// Whether the module is instantiated (see [`Self::unload`]).
+    pub fn is_instantiated(&self) -> bool {
+        self.instance.is_some()
+    }
+
+    /// Drop the instance to free its memory, keeping the module,
+    /// permissions and metadata so it can be instantiated again.
+    ///
+    /// State kept only in the instance's memory goes with it; components
+    /// hand theirs to the host (see [`morpheus_core::state`]) to survive.
+    pub fn unload(&mut self) {
+        self.instance = None;
+    }
+
+    /// Instantiate the module again after [`Self::unload`]. Does nothing if
+    /// it is instantiated.
+    pub fn instantiate(&mut self) -> Result<()> {
+        // In a real implementation:
+        // WebAssembly::Instance::new(&self.module, &imports), with the
+        // imports the permissions allow
+        if self.instance.is_none() {
+            self.instance = Some(Instance);
+        }
+        Ok(())
+    }
+
     /// Hot-reload with a new WASM module.
     ///
     /// Creates a new instance from the new WASM bytes while preserving
-    /// the component ID and incrementing the version.
+    /// the component ID. The version gets a patch bump; the registry raises
+    /// it further once the component describes a changed interface.
     pub async fn reload(&mut self, wasm_bytes: &[u8]) -> Result<()> {
         // In a real implementation:
         // 1. Compile new module
         // 2. Instantiate with same imports
         // 3. Replace old instance
-        // 4. Increment version
+        // 4. Bump the version
 
-        self.wasm_bytes = wasm_bytes.to_vec();
-        self.metadata.version += 1;
+        self.module = compile(wasm_bytes)?;
+        self.instance = Some(Instance);
+        self.metadata.version = self.metadata.version.bump(Bump::Patch);
 
         Ok(())
     }
 }
 
-// Simple hash function for generating component IDs
-fn simple_hash(bytes: &[u8]) -> u64 {
-    let mut hash: u64 = 0;
-    for byte in bytes.iter().take(64) {
-        hash = hash.wrapping_mul(31).wrapping_add(*byte as u64);
-    }
-    hash
+// Component IDs: the first 8 bytes of the SHA-256 of the whole module, so
+// modules that differ anywhere get different IDs
+fn content_hash(bytes: &[u8]) -> u64 {
+    let digest = Sha256::digest(bytes);
+    u64::from_be_bytes(digest[..8].try_into().expect("SHA-256 is 32 bytes"))
 }
 
 // Simple timestamp (placeholder)
@@ -119,8 +213,36 @@ mod tests {
             .await
             .expect("Failed to load component");
 
-        assert_eq!(component.metadata().version, 1);
-        assert_eq!(component.wasm_bytes.len(), 8);
+        assert_eq!(component.metadata().version, SemVer::INITIAL);
+        assert_eq!(component.module.bytes.len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_unloaded_components_keep_their_module() {
+        let mut component = WasmComponent::load(&[1, 2, 3, 4], Permissions::default()).await.unwrap();
+        assert!(component.is_instantiated());
+
+        component.unload();
+        assert!(!component.is_instantiated());
+        assert_eq!(component.module.bytes, [1, 2, 3, 4]);
+
+        component.instantiate().unwrap();
+        assert!(component.is_instantiated());
+        component.unload();
+        component.reload(&[5, 6, 7, 8]).await.unwrap();
+        assert!(component.is_instantiated());
+    }
+
+    #[tokio::test]
+    async fn test_identical_modules_are_shared() {
+        let original = WasmComponent::load(&[9, 9, 9, 9], Permissions::default()).await.unwrap();
+        let mut copy = WasmComponent::load(&[9, 9, 9, 9], Permissions::default()).await.unwrap();
+        let fork = original.fork(ComponentId(1), "fork");
+        assert!(Arc::ptr_eq(&original.module, &copy.module));
+        assert!(Arc::ptr_eq(&original.module, &fork.module));
+
+        copy.reload(&[9, 9, 9, 8]).await.unwrap();
+        assert!(!Arc::ptr_eq(&original.module, &copy.module));
     }
 
     #[tokio::test]
@@ -186,7 +308,7 @@ mod tests {
             .unwrap();
 
         let metadata = component.metadata();
-        assert_eq!(metadata.version, 1);
+        assert_eq!(metadata.version, SemVer::INITIAL);
         assert!(!metadata.ai_generated);
         assert!(metadata.name.starts_with("component-"));
         assert!(!metadata.loaded_at.is_empty());
@@ -210,11 +332,11 @@ mod tests {
         // ID should remain the same
         assert_eq!(component.id(), original_id);
 
-        // Version should increment
-        assert_eq!(component.metadata().version, original_version + 1);
+        // Version should get a patch bump
+        assert_eq!(component.metadata().version, original_version.bump(Bump::Patch));
 
         // Bytes should be updated
-        assert_eq!(component.wasm_bytes, new_bytes);
+        assert_eq!(component.module.bytes, new_bytes);
     }
 
     #[tokio::test]
@@ -224,60 +346,61 @@ mod tests {
             .await
             .unwrap();
 
-        assert_eq!(component.metadata().version, 1);
+        assert_eq!(component.metadata().version.to_string(), "1.0.0");
 
         component.reload(&vec![5, 6, 7, 8]).await.unwrap();
-        assert_eq!(component.metadata().version, 2);
+        assert_eq!(component.metadata().version.to_string(), "1.0.1");
 
         component.reload(&vec![9, 10, 11, 12]).await.unwrap();
-        assert_eq!(component.metadata().version, 3);
+        assert_eq!(component.metadata().version.to_string(), "1.0.2");
 
         component.reload(&vec![13, 14, 15, 16]).await.unwrap();
-        assert_eq!(component.metadata().version, 4);
+        assert_eq!(component.metadata().version.to_string(), "1.0.3");
     }
 
     #[test]
-    fn test_simple_hash_consistency() {
+    fn test_content_hash_consistency() {
         let bytes = vec![1, 2, 3, 4, 5];
-        let hash1 = simple_hash(&bytes);
-        let hash2 = simple_hash(&bytes);
+        let hash1 = content_hash(&bytes);
+        let hash2 = content_hash(&bytes);
 
         // Same input should produce same hash
         assert_eq!(hash1, hash2);
     }
 
     #[test]
-    fn test_simple_hash_different_inputs() {
+    fn test_content_hash_different_inputs() {
         let bytes1 = vec![1, 2, 3, 4];
         let bytes2 = vec![5, 6, 7, 8];
 
-        let hash1 = simple_hash(&bytes1);
-        let hash2 = simple_hash(&bytes2);
+        let hash1 = content_hash(&bytes1);
+        let hash2 = content_hash(&bytes2);
 
         // Different inputs should (usually) produce different hashes
         assert_ne!(hash1, hash2);
     }
 
     #[test]
-    fn test_simple_hash_empty() {
+    fn test_content_hash_empty() {
         let bytes = vec![];
-        let hash = simple_hash(&bytes);
+        let hash = content_hash(&bytes);
 
-        // Empty input should produce a deterministic hash (0 in this case)
-        assert_eq!(hash, 0);
+        // Empty input should produce a deterministic hash (SHA-256 of nothing)
+        assert_eq!(hash, 0xe3b0_c442_98fc_1c14);
     }
 
     #[test]
-    fn test_simple_hash_truncates_long_input() {
-        // Hash only uses first 64 bytes
+    fn test_content_hash_covers_whole_input() {
+        // Modules sharing a long prefix still differ
         let bytes1 = vec![1u8; 100];
-        let bytes2 = vec![1u8; 64];
+        let mut bytes2 = vec![1u8; 100];
+        bytes2[99] = 2;
 
-        let hash1 = simple_hash(&bytes1);
-        let hash2 = simple_hash(&bytes2);
+        let hash1 = content_hash(&bytes1);
+        let hash2 = content_hash(&bytes2);
 
-        // Should be the same because only first 64 bytes are used
-        assert_eq!(hash1, hash2);
+        assert_ne!(hash1, hash2);
+        assert_ne!(content_hash(&bytes1), content_hash(&bytes1[..64]));
     }
 
     #[test]
@@ -317,7 +440,7 @@ mod tests {
             .unwrap();
 
         // Component should store a copy of the WASM bytes
-        assert_eq!(component.wasm_bytes, wasm_bytes);
+        assert_eq!(component.module.bytes, wasm_bytes);
     }
 
     #[tokio::test]
@@ -0,0 +1,446 @@
+//! Web Worker execution mode.
+//!
+//! Running untrusted generated code on the main thread risks jank and gives
+//! it ambient DOM access. In worker mode the component runs inside a Web
+//! Worker with no DOM at all; every DOM change it wants is sent to the main
+//! thread as a [`DomOp`], where a [`DomProxy`] checks it against the
+//! component's [`DomPermissions`] before applying it.
+//!
+//! ## Protocol
+//!
+//! ```text
+//! main thread                              worker
+//!     │ ── WorkerRequest::Load ───────────────→ │  import glue, instantiate
+//!     │ ←────────────── WorkerResponse::Ready ─ │
+//!     │ ── WorkerRequest::Render ─────────────→ │
+//!     │ ←──────────── WorkerResponse::Dom{ops} ─ │  proxy validates, applies
+//!     │ ── WorkerRequest::Dispatch{message} ──→ │  (user events)
+//!     │ ←──────────── WorkerResponse::Dom{ops} ─ │
+//!     │ ── WorkerRequest::Locale{messages} ───→ │  (locale switches, then Render)
+//!     │ ── WorkerRequest::Theme{tokens,scheme} → │  (theme or color scheme switches)
+//!     │ ←─── WorkerResponse::Event{name,payload} ─ │  (domain events)
+//!     │ ←─── WorkerResponse::Feedback{rating,text} ─ │  (user feedback)
+//!     │ ←──────── WorkerResponse::Conversion{goal} ─ │  (experiment goals)
+//!     │ ── WorkerRequest::Unload ─────────────→ │
+//! ```
+//!
+//! Messages are JSON objects tagged by `type` so the worker script
+//! (`public/morpheus-worker.js` in the examples) can handle them without
+//! Rust.
+
+use morpheus_core::errors::{MorpheusError, Result};
+use morpheus_core::media::ColorScheme;
+use morpheus_core::permissions::DomPermissions;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Where a component's code executes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionMode {
+    /// On the main thread with direct DOM access.
+    #[default]
+    MainThread,
+
+    /// In a dedicated Web Worker, with DOM access through the proxy.
+    Worker,
+
+    /// In a sandboxed iframe on an opaque origin, talking to the page
+    /// through a [`SandboxBridge`](crate::sandbox::SandboxBridge).
+    Sandbox,
+}
+
+/// Messages from the main thread to the worker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerRequest {
+    /// Load a component module.
+    Load {
+        wasm_base64: String,
+        js_glue: String,
+    },
+
+    /// Render the component.
+    Render,
+
+    /// Deliver a user event or message to the component.
+    Dispatch { message: serde_json::Value },
+
+    /// Answer `morpheus.t` from `messages` from now on (see
+    /// [`morpheus_core::i18n`]).
+    Locale { messages: BTreeMap<String, String> },
+
+    /// Answer `morpheus.themeToken` from `tokens` and `morpheus.colorScheme`
+    /// with `scheme` from now on (see [`morpheus_core::theme`] and
+    /// [`morpheus_core::media`]); the page sets the tokens as CSS variables.
+    Theme {
+        tokens: BTreeMap<String, String>,
+        #[serde(default)]
+        scheme: ColorScheme,
+    },
+
+    /// Unload the component and shut the worker down.
+    Unload,
+}
+
+/// Messages from the worker to the main thread.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerResponse {
+    /// The component loaded; lists its exports.
+    Ready { exports: Vec<String> },
+
+    /// DOM changes requested by the component.
+    Dom { ops: Vec<DomOp> },
+
+    /// The component emitted a domain event through `morpheus.emitEvent`.
+    Event { name: String, payload: serde_json::Value },
+
+    /// A user rated the component through `morpheus.feedback`.
+    Feedback { rating: u8, text: String },
+
+    /// The user reached a goal, reported through `morpheus.convert`.
+    Conversion { goal: String },
+
+    /// The component failed.
+    Error { message: String },
+}
+
+/// A DOM change requested by a worker-hosted component.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum DomOp {
+    /// Replace an element's inner HTML.
+    SetHtml { target: String, html: String },
+
+    /// Replace an element's text content.
+    SetText { target: String, text: String },
+
+    /// Set an attribute on an element.
+    SetAttribute {
+        target: String,
+        name: String,
+        value: String,
+    },
+}
+
+impl DomOp {
+    /// ID of the element this operation modifies.
+    pub fn target(&self) -> &str {
+        match self {
+            DomOp::SetHtml { target, .. }
+            | DomOp::SetText { target, .. }
+            | DomOp::SetAttribute { target, .. } => target,
+        }
+    }
+}
+
+/// Main-thread gatekeeper for DOM operations from a worker.
+pub struct DomProxy {
+    /// Element ID the component is mounted into.
+    mount_point: String,
+
+    /// What the component may do.
+    permissions: DomPermissions,
+}
+
+impl DomProxy {
+    /// Create a proxy for a component mounted at `mount_point`.
+    pub fn new(mount_point: impl Into<String>, permissions: DomPermissions) -> Self {
+        Self {
+            mount_point: mount_point.into(),
+            permissions,
+        }
+    }
+
+    /// Check that an operation is allowed.
+    pub fn check(&self, op: &DomOp) -> Result<()> {
+        let target = op.target();
+        if target != self.mount_point && !self.permissions.targets.iter().any(|t| t == target) {
+            return Err(MorpheusError::PermissionDenied(format!(
+                "DOM target '{}' is outside the component's mount point",
+                target
+            )));
+        }
+
+        match op {
+            DomOp::SetHtml { html, .. } => self.check_html(html),
+            DomOp::SetText { .. } => Ok(()),
+            DomOp::SetAttribute { name, value, .. } => self.check_attribute(name, value),
+        }
+    }
+
+    /// Validate a batch of operations, rejecting the whole batch if any fails.
+    pub fn validate(&self, ops: Vec<DomOp>) -> Result<Vec<DomOp>> {
+        for op in &ops {
+            self.check(op)?;
+        }
+        Ok(ops)
+    }
+
+    /// Handle a worker response, returning the DOM operations to apply.
+    pub fn handle(&self, response: WorkerResponse) -> Result<Vec<DomOp>> {
+        match response {
+            WorkerResponse::Ready { .. }
+            | WorkerResponse::Event { .. }
+            | WorkerResponse::Feedback { .. }
+            | WorkerResponse::Conversion { .. } => Ok(Vec::new()),
+            WorkerResponse::Dom { ops } => self.validate(ops),
+            WorkerResponse::Error { message } => Err(MorpheusError::LoadError(format!(
+                "Worker component failed: {}",
+                message
+            ))),
+        }
+    }
+
+    fn check_html(&self, html: &str) -> Result<()> {
+        let lower = html.to_ascii_lowercase();
+        if lower.contains("<script") {
+            return Err(MorpheusError::PermissionDenied(
+                "Script elements are not allowed".to_string(),
+            ));
+        }
+        if lower.contains("javascript:") {
+            return Err(MorpheusError::PermissionDenied(
+                "javascript: URLs are not allowed".to_string(),
+            ));
+        }
+        if !self.permissions.inline_handlers && has_inline_handler(&lower) {
+            return Err(MorpheusError::PermissionDenied(
+                "Inline event handlers are not allowed".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_attribute(&self, name: &str, value: &str) -> Result<()> {
+        let name = name.to_ascii_lowercase();
+        if name.starts_with("on") && !self.permissions.inline_handlers {
+            return Err(MorpheusError::PermissionDenied(format!(
+                "Inline event handler '{}' is not allowed",
+                name
+            )));
+        }
+        if value
+            .to_ascii_lowercase()
+            .trim_start()
+            .starts_with("javascript:")
+        {
+            return Err(MorpheusError::PermissionDenied(
+                "javascript: URLs are not allowed".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Whether lowercase HTML contains an `on*=` attribute inside a tag.
+fn has_inline_handler(html: &str) -> bool {
+    let mut in_tag = false;
+    let bytes = html.as_bytes();
+    for (i, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'<' => in_tag = true,
+            b'>' => in_tag = false,
+            b'o' if in_tag && i > 0 && bytes[i - 1].is_ascii_whitespace() => {
+                let rest = &html[i..];
+                let name_len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+                    .unwrap_or(rest.len());
+                if name_len > 2
+                    && rest.starts_with("on")
+                    && rest[name_len..].trim_start().starts_with('=')
+                {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxy() -> DomProxy {
+        DomProxy::new("componentMount", DomPermissions::default())
+    }
+
+    fn set_html(target: &str, html: &str) -> DomOp {
+        DomOp::SetHtml {
+            target: target.to_string(),
+            html: html.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_request_wire_format() {
+        let json = serde_json::to_value(WorkerRequest::Dispatch {
+            message: serde_json::json!({ "Increment": null }),
+        })
+        .unwrap();
+
+        assert_eq!(json["type"], "dispatch");
+        assert!(json["message"].is_object());
+
+        let render: WorkerRequest = serde_json::from_str(r#"{"type": "render"}"#).unwrap();
+        assert_eq!(render, WorkerRequest::Render);
+
+        let json = r#"{"type": "theme", "tokens": {}, "scheme": "dark"}"#;
+        let theme: WorkerRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(theme, WorkerRequest::Theme { tokens: BTreeMap::new(), scheme: ColorScheme::Dark });
+    }
+
+    #[test]
+    fn test_response_wire_format() {
+        let json = r#"{"type": "dom", "ops": [
+            {"op": "set_html", "target": "componentMount", "html": "<p>Hi</p>"},
+            {"op": "set_attribute", "target": "componentMount", "name": "class", "value": "p-4"}
+        ]}"#;
+
+        let response: WorkerResponse = serde_json::from_str(json).unwrap();
+        match response {
+            WorkerResponse::Dom { ops } => {
+                assert_eq!(ops.len(), 2);
+                assert_eq!(ops[1].target(), "componentMount");
+            }
+            _ => panic!("Expected Dom response"),
+        }
+    }
+
+    #[test]
+    fn test_feedback_wire_format() {
+        let json = r#"{"type": "feedback", "rating": 4, "text": "Nice"}"#;
+        let response: WorkerResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            response,
+            WorkerResponse::Feedback {
+                rating: 4,
+                text: "Nice".to_string()
+            }
+        );
+        assert!(proxy().handle(response).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_mount_point_allowed() {
+        assert!(proxy()
+            .check(&set_html("componentMount", "<div class=\"p-4\">Hi</div>"))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_other_targets_denied_unless_granted() {
+        let op = set_html("header", "<h1>Hacked</h1>");
+        assert!(matches!(
+            proxy().check(&op),
+            Err(MorpheusError::PermissionDenied(_))
+        ));
+
+        let permissions = DomPermissions {
+            targets: vec!["header".to_string()],
+            ..Default::default()
+        };
+        let proxy = DomProxy::new("componentMount", permissions);
+        assert!(proxy.check(&op).is_ok());
+    }
+
+    #[test]
+    fn test_scripts_always_denied() {
+        let permissions = DomPermissions {
+            inline_handlers: true,
+            ..Default::default()
+        };
+        let proxy = DomProxy::new("componentMount", permissions);
+
+        assert!(proxy
+            .check(&set_html("componentMount", "<SCRIPT>alert(1)</SCRIPT>"))
+            .is_err());
+        assert!(proxy
+            .check(&set_html(
+                "componentMount",
+                "<a href=\"JavaScript:alert(1)\">x</a>"
+            ))
+            .is_err());
+    }
+
+    #[test]
+    fn test_inline_handlers_require_permission() {
+        let html = "<button onclick=\"alert('hi')\">Click</button>";
+        assert!(proxy().check(&set_html("componentMount", html)).is_err());
+
+        let permissions = DomPermissions {
+            inline_handlers: true,
+            ..Default::default()
+        };
+        let proxy = DomProxy::new("componentMount", permissions);
+        assert!(proxy.check(&set_html("componentMount", html)).is_ok());
+    }
+
+    #[test]
+    fn test_text_mentioning_on_is_not_a_handler() {
+        let html = "<p class=\"note\">Turn online mode on = enabled</p>";
+        assert!(proxy().check(&set_html("componentMount", html)).is_ok());
+    }
+
+    #[test]
+    fn test_attribute_checks() {
+        let handler = DomOp::SetAttribute {
+            target: "componentMount".to_string(),
+            name: "onMouseOver".to_string(),
+            value: "steal()".to_string(),
+        };
+        assert!(proxy().check(&handler).is_err());
+
+        let class = DomOp::SetAttribute {
+            target: "componentMount".to_string(),
+            name: "class".to_string(),
+            value: "p-4".to_string(),
+        };
+        assert!(proxy().check(&class).is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_whole_batch() {
+        let ops = vec![
+            set_html("componentMount", "<p>ok</p>"),
+            set_html("elsewhere", "<p>not ok</p>"),
+        ];
+
+        assert!(proxy().validate(ops).is_err());
+    }
+
+    #[test]
+    fn test_handle_responses() {
+        let proxy = proxy();
+
+        let ready = WorkerResponse::Ready {
+            exports: vec!["render".to_string()],
+        };
+        assert!(proxy.handle(ready).unwrap().is_empty());
+
+        let dom = WorkerResponse::Dom {
+            ops: vec![set_html("componentMount", "<p>Hi</p>")],
+        };
+        assert_eq!(proxy.handle(dom).unwrap().len(), 1);
+
+        let error = WorkerResponse::Error {
+            message: "unreachable executed".to_string(),
+        };
+        assert!(matches!(
+            proxy.handle(error),
+            Err(MorpheusError::LoadError(_))
+        ));
+    }
+
+    #[test]
+    fn test_execution_mode_default() {
+        assert_eq!(ExecutionMode::default(), ExecutionMode::MainThread);
+        assert_eq!(
+            serde_json::to_string(&ExecutionMode::Worker).unwrap(),
+            "\"worker\""
+        );
+    }
+}
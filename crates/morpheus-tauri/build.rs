@@ -0,0 +1,5 @@
+const COMMANDS: &[&str] = &["generate", "modify", "rollback", "components", "module"];
+
+fn main() {
+    tauri_plugin::Builder::new(COMMANDS).build();
+}
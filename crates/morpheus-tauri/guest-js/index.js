@@ -0,0 +1,64 @@
+// Webview side of the Morpheus Tauri plugin.
+//
+//   import { generate, mountOnReload } from 'morpheus-tauri';
+//
+//   mountOnReload(document.getElementById('app'));
+//   await generate('A counter with + and - buttons');
+
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+
+export const RELOAD_EVENT = 'morpheus://reload';
+export const APP_EVENT = 'morpheus://event';
+
+/** Generate a new component. Resolves to `{ component, version, attempts }`. */
+export function generate(prompt) {
+    return invoke('plugin:morpheus|generate', { prompt });
+}
+
+/** Change an existing component. */
+export function modify(component, prompt) {
+    return invoke('plugin:morpheus|modify', { component, prompt });
+}
+
+/** Go back to a component's previous version. */
+export function rollback(component) {
+    return invoke('plugin:morpheus|rollback', { component });
+}
+
+/** Metadata of every loaded component. */
+export function components() {
+    return invoke('plugin:morpheus|components');
+}
+
+/** Load a component's live module and render it into `container`. */
+export async function mount(component, container) {
+    const { wasm, jsGlue } = await invoke('plugin:morpheus|module', { component });
+
+    const url = URL.createObjectURL(new Blob([jsGlue], { type: 'application/javascript' }));
+    try {
+        const module = await import(url);
+        await module.default(await WebAssembly.compile(new Uint8Array(wasm)));
+        if (typeof module.render === 'function') {
+            container.innerHTML = module.render();
+        }
+        return module;
+    } finally {
+        URL.revokeObjectURL(url);
+    }
+}
+
+/**
+ * Re-render into `container` whenever a version goes live, from generation
+ * or rollback. Resolves to a function that stops listening.
+ */
+export function mountOnReload(container, onError = console.error) {
+    return listen(RELOAD_EVENT, ({ payload }) => {
+        mount(payload.component, container).catch(onError);
+    });
+}
+
+/** Follow compile failures, guardrail rejections, reloads and rollbacks. */
+export function onEvent(callback) {
+    return listen(APP_EVENT, ({ payload }) => callback(payload));
+}
@@ -0,0 +1,67 @@
+//! Commands invoked from the webview as `plugin:morpheus|<name>`.
+//!
+//! Errors reach JavaScript as the rejected promise's message.
+
+use crate::Morpheus;
+use morpheus_core::component::{ComponentId, ComponentMetadata};
+use morpheus_core::semver::SemVer;
+use morpheus_runtime::app::Modification;
+use serde::Serialize;
+use tauri::State;
+
+type Result<T> = std::result::Result<T, String>;
+
+/// A component's live module, for loading into the webview.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModulePayload {
+    /// Version of the module.
+    pub version: SemVer,
+
+    /// Compiled WASM module.
+    pub wasm: Vec<u8>,
+
+    /// JavaScript glue generated by wasm-bindgen.
+    pub js_glue: String,
+}
+
+/// Generate a new component from a prompt.
+#[tauri::command]
+pub(crate) async fn generate(state: State<'_, Morpheus>, prompt: String) -> Result<Modification> {
+    let mut app = state.0.lock().await;
+    app.request_modification(&prompt).await.map_err(|e| e.to_string())
+}
+
+/// Change an existing component.
+#[tauri::command]
+pub(crate) async fn modify(state: State<'_, Morpheus>, component: ComponentId, prompt: String) -> Result<Modification> {
+    let mut app = state.0.lock().await;
+    app.modify(component, &prompt).await.map_err(|e| e.to_string())
+}
+
+/// Go back to a component's previous version.
+#[tauri::command]
+pub(crate) async fn rollback(state: State<'_, Morpheus>, component: ComponentId) -> Result<Modification> {
+    let mut app = state.0.lock().await;
+    app.rollback(component).await.map_err(|e| e.to_string())
+}
+
+/// Every loaded component.
+#[tauri::command]
+pub(crate) async fn components(state: State<'_, Morpheus>) -> Result<Vec<ComponentMetadata>> {
+    Ok(state.0.lock().await.components())
+}
+
+/// A component's live module.
+#[tauri::command]
+pub(crate) async fn module(state: State<'_, Morpheus>, component: ComponentId) -> Result<ModulePayload> {
+    let app = state.0.lock().await;
+    let revision = app
+        .current(&component)
+        .ok_or_else(|| format!("Unknown component {}", component))?;
+    Ok(ModulePayload {
+        version: app.registry().metadata(&component).map_or(SemVer::INITIAL, |m| m.version),
+        wasm: revision.wasm.clone(),
+        js_glue: revision.js_glue.clone(),
+    })
+}
@@ -0,0 +1,172 @@
+//! # Morpheus Tauri
+//!
+//! Tauri plugin that embeds the Morpheus runtime in a desktop app. AI
+//! changes compile on the user's machine with the local Rust toolchain,
+//! new versions are hot-reloaded into the webview through Tauri commands and
+//! events, and component history is kept in the app data directory so it
+//! survives restarts.
+//!
+//! ```rust,ignore
+//! use morpheus_tauri::MorpheusPlugin;
+//!
+//! fn main() {
+//!     tauri::Builder::default()
+//!         .plugin(MorpheusPlugin::new(MyLlm::new()).build())
+//!         .run(tauri::generate_context!())
+//!         .expect("error while running tauri application");
+//! }
+//! ```
+//!
+//! Grant the webview the `morpheus:default` permission in a capability file,
+//! then drive it from JavaScript with `guest-js/index.js`.
+
+mod commands;
+
+pub use commands::ModulePayload;
+
+use morpheus_compiler::SubprocessCompiler;
+use morpheus_core::component::ComponentId;
+use morpheus_core::errors::MorpheusError;
+use morpheus_core::semver::SemVer;
+use morpheus_runtime::app::{AppEvent, AppPolicy, Generator, MorpheusApp};
+use morpheus_runtime::store::FsStore;
+use serde::Serialize;
+use std::sync::mpsc::Receiver;
+use tauri::async_runtime::{self, Mutex};
+use tauri::plugin::{Builder, TauriPlugin};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+/// Event emitted to the webview when a component version goes live.
+pub const RELOAD_EVENT: &str = "morpheus://reload";
+
+/// Event emitted to the webview for everything on the app's event bus.
+pub const APP_EVENT: &str = "morpheus://event";
+
+/// Directory under the app data directory holding component history.
+pub const HISTORY_DIR: &str = "morpheus";
+
+/// Payload of [`RELOAD_EVENT`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadPayload {
+    /// Component to reload.
+    pub component: ComponentId,
+
+    /// Version now live.
+    pub version: SemVer,
+}
+
+/// Payload of [`APP_EVENT`], mirroring [`AppEvent`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventPayload {
+    CompileFailed { attempt: u32, error: String },
+    Rejected {
+        component: Option<ComponentId>,
+        violations: Vec<String>,
+    },
+    Reloaded { component: ComponentId, version: SemVer },
+    RolledBack { component: ComponentId, version: SemVer },
+}
+
+impl From<&AppEvent> for EventPayload {
+    fn from(event: &AppEvent) -> Self {
+        match event {
+            AppEvent::CompileFailed { attempt, error } => EventPayload::CompileFailed {
+                attempt: *attempt,
+                error: error.clone(),
+            },
+            AppEvent::Rejected { component, violations } => EventPayload::Rejected {
+                component: *component,
+                violations: violations.iter().map(ToString::to_string).collect(),
+            },
+            AppEvent::Reloaded { component, version } => EventPayload::Reloaded {
+                component: *component,
+                version: *version,
+            },
+            AppEvent::RolledBack { component, version } => EventPayload::RolledBack {
+                component: *component,
+                version: *version,
+            },
+        }
+    }
+}
+
+/// Managed state holding the app.
+pub(crate) struct Morpheus(pub(crate) Mutex<MorpheusApp>);
+
+/// Access the embedded [`MorpheusApp`] from Rust, e.g. in your own commands.
+pub trait MorpheusExt<R: Runtime> {
+    /// The embedded app.
+    fn morpheus(&self) -> &Mutex<MorpheusApp>;
+}
+
+impl<R: Runtime, T: Manager<R>> MorpheusExt<R> for T {
+    fn morpheus(&self) -> &Mutex<MorpheusApp> {
+        &self.state::<Morpheus>().inner().0
+    }
+}
+
+/// Configures the plugin.
+pub struct MorpheusPlugin<G> {
+    generator: G,
+    policy: AppPolicy,
+}
+
+impl<G: Generator + 'static> MorpheusPlugin<G> {
+    /// Plugin generating code with `generator`, under the default policy.
+    pub fn new(generator: G) -> Self {
+        Self {
+            generator,
+            policy: AppPolicy::default(),
+        }
+    }
+
+    /// Set the policy changes are held to.
+    pub fn with_policy(mut self, policy: AppPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Build the plugin.
+    ///
+    /// Startup fails if the Rust toolchain or wasm-pack is missing, since
+    /// nothing could be compiled.
+    pub fn build<R: Runtime>(self) -> TauriPlugin<R> {
+        Builder::new("morpheus")
+            .invoke_handler(tauri::generate_handler![
+                commands::generate,
+                commands::modify,
+                commands::rollback,
+                commands::components,
+                commands::module,
+            ])
+            .setup(move |app, _api| {
+                let dir = app.path().app_data_dir()?.join(HISTORY_DIR);
+                let morpheus = async_runtime::block_on(async move {
+                    let compiler = SubprocessCompiler::new().await?;
+                    let store = FsStore::open(dir).await?;
+                    let mut morpheus = MorpheusApp::new(compiler, self.generator)
+                        .with_policy(self.policy)
+                        .with_store(store);
+                    morpheus.load_history().await?;
+                    Ok::<_, MorpheusError>(morpheus)
+                })?;
+                forward_events(app.clone(), morpheus.events().subscribe());
+                app.manage(Morpheus(Mutex::new(morpheus)));
+                Ok(())
+            })
+            .build()
+    }
+}
+
+/// Re-emit the app's events to the webview.
+fn forward_events<R: Runtime>(app: AppHandle<R>, events: Receiver<AppEvent>) {
+    std::thread::spawn(move || {
+        for event in events {
+            if let AppEvent::Reloaded { component, version } | AppEvent::RolledBack { component, version } = event {
+                let _ = app.emit(RELOAD_EVENT, ReloadPayload { component, version });
+            }
+            let _ = app.emit(APP_EVENT, EventPayload::from(&event));
+        }
+    });
+}
@@ -0,0 +1,721 @@
+// Framework-neutral core of the Morpheus host adapters, served by the
+// Morpheus server at /api/adapters/morpheus-host.js.
+//
+//   import { MorpheusHost } from 'morpheus-host';
+//
+//   await preload(['main']);   // optional: load every component in parallel first
+//   const host = new MorpheusHost('main', { container: document.getElementById('app') });
+//   await host.start();   // render, then follow hot reloads and shared state
+//   await setLocale('fr');   // show every component in French, no regeneration
+//   await setTheme('dark');   // restyle every component, no re-render
+//   onColorSchemeChange((scheme) => console.log(scheme));   // 'light' or 'dark', now and on OS switches
+//   host.stop();
+//
+// The React hook and Vue plugin (react.js, vue.js) are thin wrappers around
+// MorpheusHost.
+
+// Server the package was generated by; pass `server` to use another
+export const SERVER = __MORPHEUS_SERVER__;
+
+const SESSION_KEY = 'morpheusSession';
+
+// Per-browser session, so A/B experiments show the same variant on every visit
+function session() {
+    try {
+        const existing = localStorage.getItem(SESSION_KEY);
+        if (existing) return existing;
+        const created = crypto.randomUUID();
+        localStorage.setItem(SESSION_KEY, created);
+        return created;
+    } catch {
+        return undefined;
+    }
+}
+
+// Bundles by their integrity hashes and asset URLs, so a module is only ever
+// paired with the glue and styles it was built with, however reloads
+// interleave
+const bundles = new Map();
+
+/**
+ * Fetch the module, glue and stylesheets a bundle manifest describes,
+ * checked against its integrity hashes. Resolves to `{ wasm, glue, styles }`:
+ * the compiled `WebAssembly.Module`, the glue source and the text of each
+ * `.css` asset. Bundles are cached by content. `priority` is passed to
+ * `fetch()` as a hint: `high`, `low` or `auto`.
+ */
+export function loadBundle(manifest, { server = SERVER, priority = 'auto' } = {}) {
+    const assets = manifest.assets || [];
+    const key = [manifest.wasm.integrity, manifest.glue.integrity, ...assets.map((asset) => asset.url)].join(' ');
+    if (!bundles.has(key)) {
+        const file = async ({ url, integrity }) => {
+            const response = await fetch(`${server}${url}`, { integrity, priority });
+            if (!response.ok) throw new Error(`Fetching ${url} failed (${response.status})`);
+            return response;
+        };
+        const bundle = Promise.all([
+            file(manifest.wasm).then((response) => WebAssembly.compileStreaming(response)),
+            file(manifest.glue).then((response) => response.text()),
+            Promise.all(assets
+                .filter(({ url }) => url.endsWith('.css'))
+                .map((asset) => file(asset).then((response) => response.text())))
+        ]).then(([wasm, glue, styles]) => ({ wasm, glue, styles }));
+        bundle.catch(() => bundles.delete(key));
+        bundles.set(key, bundle);
+    }
+    return bundles.get(key);
+}
+
+// Components instantiated by `preload`, by server and name, until a host
+// takes them or they get too old to trust (a new version may have gone live)
+const preloaded = new Map();
+const PRELOAD_TTL_MS = 30_000;
+
+/**
+ * Fetch and instantiate what the server says to render for `component`.
+ * Resolves to `{ module, versionId, mode, placeholderHtml, styles }`;
+ * `module` is null when the component is disabled and a placeholder is
+ * shown instead. A component `preload` instantiated is used once, without
+ * asking the server again.
+ */
+export async function loadComponent(component, { server = SERVER } = {}) {
+    const ready = preloaded.get(`${server} ${component}`);
+    preloaded.delete(`${server} ${component}`);
+    if (ready && Date.now() - ready.at < PRELOAD_TTL_MS) return ready.loading;
+    const query = new URLSearchParams({ manifest: 'true' });
+    const id = session();
+    if (id) query.set('session', id);
+    const response = await fetch(`${server}/api/components/${encodeURIComponent(component)}/render?${query}`);
+    const data = await response.json();
+    if (!response.ok) throw new Error(data.error || `Rendering ${component} failed (${response.status})`);
+    return instantiate(data, { server });
+}
+
+/**
+ * Load every active component in parallel before the first render, most
+ * urgent first: `roots`, the components the page mounts, and those in
+ * their slots are fetched with high priority, the rest with low priority.
+ * Hosts started within 30 seconds render the preloaded instances. Resolves
+ * to the names of the components that loaded; one that fails is left for
+ * its host to load (and report) itself.
+ */
+export async function preload(roots = [], { server = SERVER } = {}) {
+    const query = new URLSearchParams({ roots: roots.join(',') });
+    const id = session();
+    if (id) query.set('session', id);
+    const response = await fetch(`${server}/api/preload?${query}`);
+    const data = await response.json();
+    if (!response.ok) throw new Error(data.error || `Preloading failed (${response.status})`);
+
+    const loads = data.components.map((entry) => {
+        const key = `${server} ${entry.component}`;
+        const loading = instantiate(entry, { server, priority: entry.priority });
+        preloaded.set(key, { loading, at: Date.now() });
+        return loading.then(
+            () => entry.component,
+            () => {
+                if (preloaded.get(key)?.loading === loading) preloaded.delete(key);
+                return null;
+            }
+        );
+    });
+    return (await Promise.all(loads)).filter((component) => component !== null);
+}
+
+// Instantiate a render response (or preload entry)
+// Instantiate a render response (or preload entry). Errors carry the
+// version's `fallback` and `versionId`, for `loadFallback`
+async function instantiate(data, { server, priority = 'auto' }) {
+    const fallback = data.fallback;
+    if (data.mode === 'placeholder') {
+        return {
+            module: null, versionId: null, mode: data.mode, placeholderHtml: data.placeholder_html, styles: [], fallback
+        };
+    }
+
+    // Each load imports its own copy of the glue, and so gets its own instance
+    let url = null;
+    try {
+        const { wasm, glue, styles } = await loadBundle(data.bundle, { server, priority });
+        url = URL.createObjectURL(new Blob([glue], { type: 'application/javascript' }));
+        const module = await import(url);
+        await module.default(wasm);
+        return { module, versionId: data.version_id, mode: data.mode, placeholderHtml: null, styles, fallback };
+    } catch (error) {
+        throw Object.assign(error, { fallback, versionId: data.version_id });
+    } finally {
+        if (url) URL.revokeObjectURL(url);
+    }
+}
+
+// Shown for a component that failed to load and declares no fallback
+const DEFAULT_ERROR_HTML =
+    '<div class="morpheus-error" role="alert">This part of the page couldn\'t be loaded.</div>';
+
+/**
+ * Report that `component` failed to load with `error`, and load what to
+ * show instead: its declared fallback (`error.fallback`, or `fallback` when
+ * the error doesn't say, e.g. because the server couldn't be reached),
+ * static HTML or another component, or a short error message. A fallback
+ * component that fails too gets the error message. Resolves like
+ * `loadComponent`, in mode `fallback`.
+ */
+export async function loadFallback(component, error, { server = SERVER, fallback = null } = {}) {
+    fetch(`${server}/api/telemetry`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({
+            kind: 'error',
+            version_id: error.versionId ?? null,
+            message: `${component} failed to load, showing its fallback: ${error.message}`
+        })
+    }).catch(() => {});
+
+    const view = error.fallback || fallback || { kind: 'html', html: DEFAULT_ERROR_HTML };
+    if (view.kind === 'component') {
+        try {
+            const loaded = await loadComponent(view.component, { server });
+            return { ...loaded, mode: 'fallback', fallback: view };
+        } catch {
+            return { module: null, versionId: null, mode: 'fallback', placeholderHtml: DEFAULT_ERROR_HTML, styles: [] };
+        }
+    }
+    return { module: null, versionId: null, mode: 'fallback', placeholderHtml: view.html, styles: [], fallback: view };
+}
+
+/**
+ * Call `onReload(event)` whenever a version of `component` goes live. The
+ * event's bundle starts downloading straight away, so the reload that
+ * follows finds it cached. Returns a function that stops listening.
+ */
+export function subscribeReloads(component, onReload, { server = SERVER } = {}) {
+    const source = new EventSource(`${server}/api/reloads`);
+    source.addEventListener('reload', (message) => {
+        const event = JSON.parse(message.data);
+        if (event.name !== component) return;
+        if (event.bundle) loadBundle(event.bundle, { server }).catch(() => {});
+        onReload(event);
+    });
+    return () => source.close();
+}
+
+// Form controls whose values survive a reload
+const INPUTS = ['input', 'textarea', 'select'];
+
+// Whether a form control's value is carried over: not files, nor secrets
+// the page asked browsers not to remember (passwords, hidden fields, and
+// autocomplete=off on the control or its form)
+function carriesValue(element) {
+    if (!INPUTS.includes(element.localName)) return false;
+    if (['file', 'password', 'hidden'].includes(element.type)) return false;
+    const autocomplete = element.getAttribute('autocomplete') ?? element.form?.getAttribute('autocomplete');
+    return autocomplete?.trim().toLowerCase() !== 'off';
+}
+
+// A name for an element that finds its counterpart in the next version's
+// DOM: its data-morpheus-key (see morpheus_core::identity), else its id,
+// else its name (and value, for radios and checkboxes sharing one), else
+// its data-key, else its tag and position among that tag
+function elementKey(element, position) {
+    if (element.dataset.morpheusKey) return `[data-morpheus-key=${element.dataset.morpheusKey}]`;
+    if (element.id) return `#${element.id}`;
+    const name = element.getAttribute('name');
+    if (name) {
+        const choice = element.type === 'radio' || element.type === 'checkbox';
+        const named = `${element.localName}[name=${name}]`;
+        return choice ? `${named}[value=${element.value}]` : named;
+    }
+    if (element.dataset.key) return `[data-key=${element.dataset.key}]`;
+    return `${element.localName}:${position}`;
+}
+
+// The elements of `container` by key; the container itself is ''
+function keyedElements(container) {
+    const elements = new Map([['', container]]);
+    const positions = new Map();
+    for (const element of container.querySelectorAll('*')) {
+        const position = positions.get(element.localName) ?? 0;
+        positions.set(element.localName, position + 1);
+        const key = elementKey(element, position);
+        if (!elements.has(key)) elements.set(key, element);
+    }
+    return elements;
+}
+
+/**
+ * What the user is in the middle of inside `container`: `{ values, scroll,
+ * focus }`, with form values, scroll offsets and the focused element (and
+ * its text selection) by stable keys. Password, hidden and
+ * autocomplete=off fields are left out, but the rest is still what the user
+ * typed: keep it in memory across the reload rather than storing or
+ * sending it anywhere. Put it back with `restoreInputs` once the container
+ * has new content.
+ */
+export function captureInputs(container) {
+    const values = {};
+    const scroll = {};
+    let focus = null;
+    for (const [key, element] of keyedElements(container)) {
+        if (carriesValue(element)) {
+            values[key] = element.type === 'radio' || element.type === 'checkbox' ? element.checked : element.value;
+        }
+        if (element.scrollTop || element.scrollLeft) scroll[key] = [element.scrollTop, element.scrollLeft];
+        if (element === document.activeElement && element !== container) {
+            focus = { key };
+            if (typeof element.selectionStart === 'number') {
+                focus.selection = [element.selectionStart, element.selectionEnd];
+            }
+        }
+    }
+    return { values, scroll, focus };
+}
+
+/**
+ * Put back what `captureInputs` saw, on the elements of `container` with
+ * the same keys. Elements the new content no longer has are skipped. Each
+ * control whose value changes gets `input` and `change` events, so
+ * components listening for them see the restored value.
+ */
+export function restoreInputs(container, { values = {}, scroll = {}, focus = null } = {}) {
+    const elements = keyedElements(container);
+    for (const [key, value] of Object.entries(values)) {
+        const element = elements.get(key);
+        if (!element || !carriesValue(element)) continue;
+        if (typeof value === 'boolean') {
+            if (element.checked === value) continue;
+            element.checked = value;
+        } else {
+            if (element.value === value) continue;
+            element.value = value;
+        }
+        element.dispatchEvent(new Event('input', { bubbles: true }));
+        element.dispatchEvent(new Event('change', { bubbles: true }));
+    }
+    for (const [key, [top, left]] of Object.entries(scroll)) {
+        const element = elements.get(key);
+        if (element) element.scrollTo(left, top);
+    }
+    const focused = focus && elements.get(focus.key);
+    if (focused && focused !== container) {
+        focused.focus({ preventScroll: true });
+        if (focus.selection && typeof focused.setSelectionRange === 'function') {
+            try {
+                focused.setSelectionRange(...focus.selection);
+            } catch {
+                // The new element doesn't take a selection (e.g. changed to type=number)
+            }
+        }
+    }
+}
+
+// One state sync socket per server, shared by every host on the page
+const stateBridges = new Map();
+
+/**
+ * Follow the shared component state. `onState(state)` is called with the
+ * latest snapshot, first on (re)connecting. Returns `{ send(state), close() }`.
+ */
+export function connectState(onState, { server = SERVER } = {}) {
+    let bridge = stateBridges.get(server);
+    if (!bridge) {
+        bridge = { listeners: new Set(), socket: null, revision: -1, state: undefined };
+        stateBridges.set(server, bridge);
+        const connect = () => {
+            const socket = new WebSocket(server.replace(/^http/, 'ws') + '/api/state/sync');
+            let first = true;
+            socket.onmessage = (message) => {
+                const data = JSON.parse(message.data);
+                if (data.revision === undefined) return; // errors and CRDT mode
+                // The first snapshot after (re)connecting is authoritative
+                if (!first && data.revision <= bridge.revision) return;
+                first = false;
+                bridge.revision = data.revision;
+                bridge.state = data.state;
+                bridge.listeners.forEach((listener) => listener(data.state));
+            };
+            socket.onclose = () => {
+                if (bridge.listeners.size > 0) setTimeout(connect, 2000);
+            };
+            bridge.socket = socket;
+        };
+        connect();
+    }
+    bridge.listeners.add(onState);
+    if (bridge.state !== undefined) onState(bridge.state);
+    return {
+        send(state) {
+            if (bridge.socket?.readyState === WebSocket.OPEN) bridge.socket.send(JSON.stringify({ state }));
+        },
+        close() {
+            bridge.listeners.delete(onState);
+            if (bridge.listeners.size === 0) {
+                stateBridges.delete(server);
+                bridge.socket?.close();
+            }
+        }
+    };
+}
+
+// Host imports are global, so calls into a component record which host is
+// active; the page's own `morpheus` object, if it has one, is left alone.
+// Every host on the page shows the same locale's messages and theme
+const hosts = (globalThis.__morpheusHosts ??= {
+    active: null,
+    started: new Set(),
+    locale: null,
+    messages: {},
+    tokens: {},
+    theme: null,
+    themeSource: null,
+    scheme: 'light',
+    schemeWatch: null
+});
+globalThis.morpheus ??= {
+    t(key, fallback, args) {
+        return formatMessage(hosts.messages[key] ?? fallback ?? key, args);
+    },
+    themeToken(name) {
+        return hosts.tokens[name] ?? '';
+    },
+    colorScheme() {
+        return hosts.scheme;
+    },
+    emitEvent(name, payload) {
+        hosts.active?.emitEvent(name, JSON.parse(payload || 'null'));
+    },
+    setState(state) {
+        hosts.active?.setState(JSON.parse(state || 'null'));
+    }
+};
+
+/**
+ * Fill `{name}` placeholders in `message` from the JSON object `argsJson`,
+ * as morpheus_core::i18n::format does.
+ */
+export function formatMessage(message, argsJson) {
+    const args = JSON.parse(argsJson || 'null');
+    if (!args || typeof args !== 'object') return message;
+    return message.replace(/\{([^{}]+)\}/g, (placeholder, name) => {
+        if (!(name in args)) return placeholder;
+        return typeof args[name] === 'string' ? args[name] : JSON.stringify(args[name]);
+    });
+}
+
+// Every message in `locale`, untranslated ones in English
+async function fetchMessages(locale, server) {
+    const response = await fetch(`${server}/api/i18n/${encodeURIComponent(locale)}`);
+    if (!response.ok) throw new Error(`Fetching ${locale} messages failed (${response.status})`);
+    return (await response.json()).messages;
+}
+
+/**
+ * Show every component on the page in `locale` (e.g. `fr-CA`): fetch its
+ * messages for `morpheus.t`, then re-render each started host. Until a
+ * locale is set, components show the English text in their source.
+ */
+export async function setLocale(locale, { server = SERVER } = {}) {
+    hosts.messages = await fetchMessages(locale, server);
+    hosts.locale = locale;
+    for (const host of hosts.started) host.render(host.mode ?? undefined);
+}
+
+// Matches while the OS is in dark mode
+const DARK_SCHEME_QUERY = '(prefers-color-scheme: dark)';
+
+/**
+ * Call `callback(matches)` with whether the media query `query` matches,
+ * now and each time that changes, until the returned function is called.
+ */
+export function onMediaChange(query, callback) {
+    if (typeof matchMedia !== 'function') return () => {};
+    const list = matchMedia(query);
+    const listener = (event) => callback(event.matches);
+    list.addEventListener('change', listener);
+    callback(list.matches);
+    return () => list.removeEventListener('change', listener);
+}
+
+/**
+ * Call `callback(scheme)` with the user's color scheme, `'light'` or
+ * `'dark'`, now and each time the OS switches, until the returned function
+ * is called.
+ */
+export function onColorSchemeChange(callback) {
+    return onMediaChange(DARK_SCHEME_QUERY, (dark) => callback(dark ? 'dark' : 'light'));
+}
+
+// Components ask for the scheme with morpheus.colorScheme, and the system
+// theme follows it
+hosts.schemeWatch ??= onColorSchemeChange((scheme) => {
+    const changed = scheme !== hosts.scheme;
+    hosts.scheme = scheme;
+    if (hosts.theme) applyTheme(hosts.theme[scheme]);
+    if (changed) for (const host of hosts.started) host.render(host.mode ?? undefined);
+});
+
+/**
+ * A theme's tokens by name (`color.primary`, `spacing.md`,
+ * `typography.body`), as morpheus_core::theme::Theme::tokens names them.
+ */
+export function themeTokens(theme) {
+    const groups = [['color', theme.colors], ['spacing', theme.spacing], ['typography', theme.typography]];
+    const tokens = {};
+    for (const [group, values] of groups) {
+        for (const [name, value] of Object.entries(values ?? {})) tokens[`${group}.${name}`] = value;
+    }
+    return tokens;
+}
+
+/**
+ * Style every component on the page with `theme` (as `GET /api/themes`
+ * lists them): set its tokens as `--morpheus-*` CSS variables on `root`
+ * and answer `morpheus.themeToken` from them. Components style themselves
+ * with the variables, so nothing re-renders.
+ */
+export function applyTheme(theme, root = document.documentElement) {
+    hosts.tokens = themeTokens(theme);
+    for (const [name, value] of Object.entries(hosts.tokens)) {
+        root.style.setProperty(`--morpheus-${name.replaceAll('.', '-')}`, value);
+    }
+    root.dataset.morpheusTheme = theme.name;
+}
+
+/**
+ * Style the page with the server's active theme as its events and
+ * `POST /api/themes/active` describe it (`{ name, light, dark }`): the
+ * theme for the user's color scheme, switching along with the OS.
+ */
+export function applyActiveTheme(active, root = document.documentElement) {
+    hosts.theme = active;
+    applyTheme(active[hosts.scheme], root);
+}
+
+/**
+ * Switch the server's active theme to `name` (e.g. `dark`, or `system` to
+ * follow each user's OS), restyling every page following it, this one
+ * included.
+ */
+export async function setTheme(name, { server = SERVER } = {}) {
+    const response = await fetch(`${server}/api/themes/active`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ name })
+    });
+    if (!response.ok) throw new Error(`Switching to theme ${name} failed (${response.status})`);
+    applyActiveTheme(await response.json());
+}
+
+/**
+ * Apply the server's active theme, and each switch to another, until the
+ * returned function is called. Started hosts do this for the page.
+ */
+export function followTheme({ server = SERVER, root = document.documentElement } = {}) {
+    const source = new EventSource(`${server}/api/themes/events`);
+    source.addEventListener('theme', (message) => applyActiveTheme(JSON.parse(message.data), root));
+    return () => source.close();
+}
+
+/** A component rendered into a container, kept current. */
+export class MorpheusHost {
+    /**
+     * Options: `container` (element to render into), `server`, `onRender({
+     * versionId, mode })` after each render, `onEvent(name, payload)` for
+     * events the component emits, and `onError(error)`.
+     *
+     * Hot reloads replace the component's DOM. Form values, scroll offsets
+     * and focus are carried over by stable keys (see `captureInputs`) unless
+     * `preserveInputs` is false, and hosts can prepare for reloads too:
+     * `onBeforeReload({ from, to, mode, inputs })` runs once the new version
+     * has downloaded and before anything on screen changes (the swap waits
+     * for it if it returns a promise, e.g. to pause input or start a
+     * transition; `inputs` is what will be restored, and can be changed),
+     * `onAfterReload({ from, to, mode, inputs })` once the new version has
+     * rendered, and `onReloadError(error)` when a reload fails, so whatever
+     * `onBeforeReload` paused can resume (defaults to `onError`). The first
+     * load isn't a reload and runs none of them.
+     *
+     * With `locale`, `start()` switches the page to it first (see
+     * `setLocale`). The page follows the server's theme while any host is
+     * started (see `followTheme`), unless `followTheme` is false.
+     */
+    constructor(component, {
+        container = null,
+        server = SERVER,
+        onRender = () => {},
+        onEvent = () => {},
+        onError = console.error,
+        onBeforeReload = () => {},
+        onAfterReload = () => {},
+        onReloadError = onError,
+        preserveInputs = true,
+        locale = null,
+        followTheme: followsTheme = true
+    } = {}) {
+        this.component = component;
+        this.container = container;
+        this.server = server;
+        this.onRender = onRender;
+        this.onEvent = onEvent;
+        this.onError = onError;
+        this.onBeforeReload = onBeforeReload;
+        this.onAfterReload = onAfterReload;
+        this.onReloadError = onReloadError;
+        this.preserveInputs = preserveInputs;
+        this.locale = locale;
+        this.followsTheme = followsTheme;
+        this.loaded = false;
+        this.module = null;
+        this.versionId = null;
+        this.mode = null;
+        this.fallback = null;
+        this.state = undefined;
+        this.stopReloads = null;
+        this.stateBridge = null;
+        this.styleElement = null;
+    }
+
+    /** Render the component, then follow hot reloads and shared state. */
+    async start() {
+        hosts.started.add(this);
+        if (this.locale && this.locale !== hosts.locale) {
+            await setLocale(this.locale, { server: this.server }).catch(this.onError);
+        }
+        if (this.followsTheme && !hosts.themeSource) hosts.themeSource = followTheme({ server: this.server });
+        this.stateBridge = connectState((state) => {
+            this.state = state;
+            if (this.module && typeof this.module.restore_state === 'function') {
+                this.run(() => this.module.restore_state(JSON.stringify(state)));
+                this.render();
+            }
+        }, { server: this.server });
+        this.stopReloads = subscribeReloads(
+            this.component,
+            () => this.reload().catch((error) => this.onReloadError(error)),
+            { server: this.server }
+        );
+        await this.reload();
+    }
+
+    /** Stop following reloads and state. */
+    stop() {
+        hosts.started.delete(this);
+        if (hosts.started.size === 0) {
+            hosts.themeSource?.();
+            hosts.themeSource = null;
+        }
+        this.stopReloads?.();
+        this.stateBridge?.close();
+        this.stopReloads = null;
+        this.stateBridge = null;
+        this.module = null;
+        this.loaded = false;
+        this.styleElement?.remove();
+        this.styleElement = null;
+    }
+
+    /**
+     * Load the version the server says to render, keeping the state. If it
+     * fails to load while no working version is on screen, render the
+     * component's fallback instead (see `loadFallback`) and report the
+     * error to `onError`.
+     */
+    async reload() {
+        // The new version may use keys the page has no messages for yet
+        const messages = hosts.locale ? fetchMessages(hosts.locale, this.server).catch(() => null) : null;
+        let loaded;
+        let failure = null;
+        try {
+            loaded = await loadComponent(this.component, { server: this.server });
+        } catch (error) {
+            if (this.module && this.mode !== 'fallback') throw error;
+            loaded = await loadFallback(this.component, error, { server: this.server, fallback: this.fallback });
+            failure = error;
+        }
+        if (loaded.mode !== 'fallback') this.fallback = loaded.fallback ?? null;
+        const reloading = this.loaded;
+        const preserve = reloading && this.preserveInputs && this.container;
+        const change = {
+            from: this.versionId,
+            to: loaded.versionId,
+            mode: loaded.mode,
+            inputs: preserve ? captureInputs(this.container) : null
+        };
+        if (reloading) await this.onBeforeReload(change);
+        hosts.messages = (await messages) ?? hosts.messages;
+        this.swap(loaded);
+        if (preserve && change.inputs) restoreInputs(this.container, change.inputs);
+        this.loaded = true;
+        if (reloading) this.onAfterReload(change);
+        if (failure) this.onError(failure);
+    }
+
+    /** Put a loaded version on screen. */
+    swap(loaded) {
+        this.module = loaded.module;
+        this.versionId = loaded.versionId;
+        this.mode = loaded.mode;
+        this.applyStyles(loaded.styles);
+        if (!this.module) {
+            if (this.container) this.container.innerHTML = loaded.placeholderHtml || '';
+            this.onRender({ versionId: null, mode: loaded.mode });
+            return;
+        }
+        if (this.state !== undefined && typeof this.module.restore_state === 'function') {
+            this.run(() => this.module.restore_state(JSON.stringify(this.state)));
+        }
+        this.render(loaded.mode);
+    }
+
+    /** Replace the component's stylesheets, in step with its module. */
+    applyStyles(styles) {
+        if (styles.length === 0 && !this.styleElement) return;
+        if (!this.styleElement) {
+            this.styleElement = document.createElement('style');
+            this.styleElement.dataset.morpheusComponent = this.component;
+            document.head.appendChild(this.styleElement);
+        }
+        this.styleElement.textContent = styles.join('\n');
+    }
+
+    /** Render into the container. */
+    render(mode = 'current') {
+        if (!this.module || typeof this.module.render !== 'function') return;
+        const html = this.run(() => this.module.render());
+        if (this.container) this.container.innerHTML = html;
+        this.onRender({ versionId: this.versionId, mode });
+    }
+
+    /** Call one of the component's exports, then re-render. */
+    call(name, ...args) {
+        const fn = this.module?.[name];
+        if (typeof fn !== 'function') throw new Error(`${this.component} has no export ${name}`);
+        const result = this.run(() => fn(...args));
+        this.render();
+        return result;
+    }
+
+    /** Share a state change with every client, as `morpheus.setState` does. */
+    setState(state) {
+        this.state = state;
+        this.stateBridge?.send(state);
+    }
+
+    emitEvent(name, payload) {
+        this.onEvent(name, payload);
+        fetch(`${this.server}/api/events`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ component: this.component, name, payload })
+        }).catch(this.onError);
+    }
+
+    run(fn) {
+        const previous = hosts.active;
+        hosts.active = this;
+        try {
+            return fn();
+        } finally {
+            hosts.active = previous;
+        }
+    }
+}
@@ -0,0 +1,68 @@
+// React bindings for Morpheus components.
+//
+//   import { useMorpheusComponent } from 'morpheus-host/react';
+//
+//   function Dashboard() {
+//       const { ref, loading, error } = useMorpheusComponent('main');
+//       return <div ref={ref}>{loading && 'Loading…'}</div>;
+//   }
+//
+// The hook renders the component into the element `ref` is attached to and
+// re-renders it on hot reloads and shared state changes.
+
+import { createElement, useCallback, useEffect, useRef, useState } from 'react';
+import { MorpheusHost } from './morpheus-host.js';
+
+/**
+ * Mount component `id` into the element `ref` is attached to. Options are
+ * MorpheusHost's: `server`, `onEvent(name, payload)`, `onError(error)`,
+ * `onBeforeReload(change)`, `onAfterReload(change)` and
+ * `onReloadError(error)`. Returns `{ ref, loading, error, versionId, mode,
+ * call }`.
+ */
+export function useMorpheusComponent(id, options = {}) {
+    const ref = useRef(null);
+    const hostRef = useRef(null);
+    const optionsRef = useRef(options);
+    optionsRef.current = options;
+    const [status, setStatus] = useState({ loading: true, error: null, versionId: null, mode: null });
+
+    useEffect(() => {
+        const host = new MorpheusHost(id, {
+            container: ref.current,
+            server: optionsRef.current.server,
+            onRender: ({ versionId, mode }) => setStatus({ loading: false, error: null, versionId, mode }),
+            onEvent: (name, payload) => optionsRef.current.onEvent?.(name, payload),
+            onError: (error) => {
+                setStatus((status) => ({ ...status, loading: false, error }));
+                optionsRef.current.onError?.(error);
+            },
+            onBeforeReload: (change) => optionsRef.current.onBeforeReload?.(change),
+            onAfterReload: (change) => optionsRef.current.onAfterReload?.(change),
+            onReloadError: (error) => {
+                setStatus((status) => ({ ...status, error }));
+                (optionsRef.current.onReloadError ?? optionsRef.current.onError)?.(error);
+            }
+        });
+        hostRef.current = host;
+        setStatus({ loading: true, error: null, versionId: null, mode: null });
+        host.start().catch(host.onError);
+        return () => {
+            host.stop();
+            hostRef.current = null;
+        };
+    }, [id, options.server]);
+
+    const call = useCallback((name, ...args) => hostRef.current?.call(name, ...args), []);
+    return { ref, call, ...status };
+}
+
+/** `<MorpheusComponent id="main" />`: the hook as a component. */
+export function MorpheusComponent({
+    id, server, onEvent, onError, onBeforeReload, onAfterReload, onReloadError, ...props
+}) {
+    const { ref } = useMorpheusComponent(id, {
+        server, onEvent, onError, onBeforeReload, onAfterReload, onReloadError
+    });
+    return createElement('div', { ...props, ref });
+}
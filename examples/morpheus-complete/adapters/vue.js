@@ -0,0 +1,64 @@
+// Vue bindings for Morpheus components.
+//
+//   import { createApp } from 'vue';
+//   import { MorpheusPlugin } from 'morpheus-host/vue';
+//
+//   createApp(App).use(MorpheusPlugin, { server: 'http://127.0.0.1:3002' }).mount('#app');
+//
+//   <MorpheusComponent id="main" @morpheus-event="onEvent" />
+//
+// The component renders a Morpheus component into its root element and
+// re-renders it on hot reloads and shared state changes, emitting
+// `before-reload`, `after-reload` and `reload-error` around hot reloads.
+
+import { defineComponent, h, inject, onBeforeUnmount, onMounted, ref, watch } from 'vue';
+import { MorpheusHost } from './morpheus-host.js';
+
+const OPTIONS_KEY = Symbol('morpheus');
+
+export const MorpheusComponent = defineComponent({
+    name: 'MorpheusComponent',
+    props: {
+        id: { type: String, required: true },
+        server: { type: String, default: undefined }
+    },
+    emits: ['morpheus-event', 'render', 'error', 'before-reload', 'after-reload', 'reload-error'],
+    setup(props, { emit, expose }) {
+        const options = inject(OPTIONS_KEY, {});
+        const root = ref(null);
+        let host = null;
+
+        const start = () => {
+            host?.stop();
+            host = new MorpheusHost(props.id, {
+                container: root.value,
+                server: props.server ?? options.server,
+                onRender: (status) => emit('render', status),
+                onEvent: (name, payload) => emit('morpheus-event', { name, payload }),
+                onError: (error) => emit('error', error),
+                onBeforeReload: (change) => emit('before-reload', change),
+                onAfterReload: (change) => emit('after-reload', change),
+                onReloadError: (error) => {
+                    emit('reload-error', error);
+                    emit('error', error);
+                }
+            });
+            host.start().catch(host.onError);
+        };
+
+        onMounted(start);
+        watch(() => [props.id, props.server], start);
+        onBeforeUnmount(() => host?.stop());
+        expose({ call: (name, ...args) => host?.call(name, ...args) });
+
+        return () => h('div', { ref: root });
+    }
+});
+
+/** Registers `<MorpheusComponent>`; options: `server`. */
+export const MorpheusPlugin = {
+    install(app, options = {}) {
+        app.provide(OPTIONS_KEY, options);
+        app.component('MorpheusComponent', MorpheusComponent);
+    }
+};
@@ -0,0 +1,1788 @@
+// Generated from the Morpheus OpenAPI spec (GET /api/openapi.json). Do not edit.
+// Regenerate with `MORPHEUS_UPDATE_CLIENT=1 cargo test -p morpheus-complete`.
+
+/** An axe-core rule the rendered component fails. */
+export interface AccessibilityViolation {
+  /** What the rule asks for, e.g. "Buttons must have discernible text". */
+  help: string;
+  /** Rule documentation. */
+  help_url?: string | null;
+  impact: Impact;
+  /** Rule ID, e.g. `button-name`. */
+  rule: string;
+  /** CSS selectors of the failing elements. */
+  targets: string[];
+}
+
+/** The active theme as hosts apply it: the same theme for both color schemes, unless it is the [`SYSTEM_THEME`]. */
+export interface ActiveTheme {
+  /** Theme for users preferring dark colors. */
+  dark: Theme;
+  /** Theme for users preferring light colors, or without a preference. */
+  light: Theme;
+  /** Name of the chosen theme, or `system`. */
+  name: string;
+}
+
+/** Specific JavaScript APIs that can be accessed. */
+export type ApiPermission = "Geolocation" | "Notifications" | "Camera" | "Microphone" | "Clipboard" | "Graphics" | "Files";
+
+/** A host API and the broker's decision on it. */
+export interface ApiStatus {
+  api: HostApi;
+  /** Host import name */
+  import: string;
+}
+
+/** One build of a component. */
+export interface Artifact {
+  /** Glue generated with the module; empty for [`ArtifactTarget::Wasi`] */
+  js_glue?: string;
+  target: ArtifactTarget;
+  wasm_base64: string;
+}
+
+/** Query for a component's build */
+export interface ArtifactQuery {
+  /** Where the component will run (default: browser) */
+  environment?: Environment;
+}
+
+/** What a build is for. */
+export type ArtifactTarget = "web" | "nodejs" | "wasi";
+
+/** Who wrote a component's code. */
+export type Author = "unknown" | "human" | "ai";
+
+/** One pass of the self-improvement loop */
+export interface AutonomousRun {
+  /** Version the telemetry was about */
+  base_version_id?: number | null;
+  detail: string;
+  events: number;
+  outcome: RunOutcome;
+  started_at: string;
+  /** Proposal added to the history */
+  version_id?: number | null;
+}
+
+/** Autonomous mode's configuration and recent runs */
+export interface AutonomousStatus {
+  auto_activate: boolean;
+  enabled: boolean;
+  interval_secs?: number | null;
+  min_events?: number | null;
+  objective?: string | null;
+  /** Telemetry about the live version not yet handed to the AI */
+  pending_events: number;
+  /** Most recent first */
+  runs: AutonomousRun[];
+}
+
+/** A file of a bundle. */
+export interface BundleAsset {
+  /** Subresource Integrity hash, e.g. `sha384-...` */
+  integrity: string;
+  /** Size in bytes */
+  size: number;
+  /** Path on the server, e.g. `/api/versions/3/component.wasm` */
+  url: string;
+}
+
+/** The module and glue of a version, which must be loaded together. */
+export interface BundleManifest {
+  /** Stylesheets and other files loaded with the module, under content-hashed URLs */
+  assets?: BundleAsset[];
+  /** Component the version belongs to */
+  component: string;
+  /** The wasm-bindgen JS glue generated with it */
+  glue: BundleAsset;
+  version_id: number;
+  /** The compiled WASM module */
+  wasm: BundleAsset;
+}
+
+/** One call into a component's export, as timed by the host. */
+export interface CallSample {
+  /** Component that was called. */
+  component: string;
+  /** Time the call took, in milliseconds. */
+  duration_ms: number;
+  /** Export that was called, e.g. `render` or `on_click`. */
+  export: string;
+  /** Whether the call trapped (panicked or hit `unreachable`). */
+  trapped?: boolean;
+}
+
+/** A kind of job a component does. */
+export type Capability = "data-display" | "input" | "navigation" | "visualization" | "automation";
+
+/** Human-readable summary of a version's changes */
+export interface ChangelogEntry {
+  base_version_id: number;
+  generated_at: string;
+  summary: string;
+}
+
+/** Unique identifier for a component instance. */
+export type ComponentId = number;
+
+/** Metadata about a component. */
+export interface ComponentMetadata {
+  /** Whether this component was AI-generated. */
+  ai_generated: boolean;
+  /** What kind of job the component does. */
+  capabilities?: Capability[];
+  /** Interfaces of the components it depends on, as it was loaded against. */
+  dependencies?: InterfacePin[];
+  /** What clients render if the component fails to load (see [`crate::fallback`]). */
+  fallback?: FallbackView | null;
+  /** Unique identifier. */
+  id: ComponentId;
+  /** Hash of the interface the component reported (see [`crate::interface`]). */
+  interface_hash?: string | null;
+  /** Whether the component is active, deprecated or retired (see [`crate::lifecycle`]). */
+  lifecycle?: Lifecycle;
+  /** When this component was loaded. */
+  loaded_at: string;
+  /** Human-readable name. */
+  name: string;
+  /** Where the component's code came from. */
+  provenance?: Provenance;
+  /** Calls, traps and call times of the component's exports since it was loaded (see [`crate::stats`]). */
+  stats?: ExecutionStats;
+  /** A small preview of what the component renders (see [`crate::thumbnail`]). */
+  thumbnail?: Thumbnail | null;
+  /** Semantic version, bumped by how much each reload changed the component's interface (see [`crate::semver`]). */
+  version: SemVer;
+}
+
+/** A loaded component */
+export interface ComponentStatus {
+  ai_generated: boolean;
+  /** Feature flag, when one is set: `disabled` or `pinned to version N` */
+  flag?: string | null;
+  loaded_at: string;
+  name: string;
+  /** Calls, traps and call times since it was loaded */
+  stats: ExecutionStats;
+  /** Version serving it */
+  version_id?: number | null;
+}
+
+/** How to resolve a concurrent modification */
+export type ConflictStrategy = "reject" | "rebase";
+
+/** Conversation entry for display */
+export interface ConversationEntry {
+  content: string;
+  role: string;
+  timestamp: string;
+}
+
+/** A conversion by a session */
+export interface ConversionRequest {
+  /** What the session achieved, e.g. "signed_up" (for the logs) */
+  goal?: string | null;
+  session: string;
+}
+
+/** The variant a conversion was counted for */
+export interface ConversionResponse {
+  variant: Variant;
+  version_id: number;
+}
+
+/** A recorded crash */
+export interface Crash {
+  component?: string | null;
+  id: number;
+  /** Where the panic happened, as `file:line:column`. */
+  location?: string | null;
+  /** The panic message. */
+  message: string;
+  received_at: string;
+  /** Version the server rolled back to */
+  rolled_back_to?: number | null;
+  /** The state snapshot taken as the component panicked. */
+  state?: unknown;
+  /** When the component crashed (ISO 8601). */
+  timestamp?: string;
+  /** Version that crashed. */
+  version?: number | null;
+  /** Version that crashed (the reported one, or the live one) */
+  version_id?: number | null;
+}
+
+/** A component's last words: why it panicked and the state it held. */
+export interface CrashReport {
+  /** Where the panic happened, as `file:line:column`. */
+  location?: string | null;
+  /** The panic message. */
+  message: string;
+  /** The state snapshot taken as the component panicked. */
+  state?: unknown;
+  /** When the component crashed (ISO 8601). */
+  timestamp?: string;
+  /** Version that crashed. */
+  version?: number | null;
+}
+
+/** What the client should load after a crash */
+export interface CrashResponse {
+  crash_id: number;
+  js_glue?: string | null;
+  /** Version now live, when the server rolled back */
+  rolled_back_to?: number | null;
+  /** State to restore: the crashed component's snapshot when it was kept */
+  state?: unknown;
+  wasm_base64?: string | null;
+}
+
+/** Move the time-travel cursor: by `steps` (negative is back), from `position` if given or else from where it was */
+export interface DebugStepRequest {
+  position?: number | null;
+  steps?: number;
+}
+
+/** A point on the state timeline, with what is needed to re-render it */
+export interface DebugStepResponse {
+  js_glue?: string | null;
+  live: boolean;
+  position: number;
+  positions: number;
+  state: unknown;
+  state_version: number;
+  version_id?: number | null;
+  wasm_base64?: string | null;
+}
+
+/** Deprecate a component */
+export interface DeprecateRequest {
+  /** Component to use instead */
+  replacement?: string | null;
+  /** Retire the component from then on, once nothing depends on it */
+  retire_after?: string | null;
+}
+
+/** Request to commit the current design */
+export interface DesignCommitRequest {
+  /** Commit even if another version was committed since the session started */
+  force?: boolean;
+  message?: string | null;
+}
+
+/** Response to design commit */
+export interface DesignCommitResponse {
+  error?: string | null;
+  success: boolean;
+  version_id: number;
+  wasm_base64: string;
+}
+
+/** Get current design preview */
+export interface DesignPreviewResponse {
+  active: boolean;
+  conversation: ConversationEntry[];
+  draft?: DraftInfo | null;
+  session_id?: string | null;
+}
+
+/** Request to refine the current draft */
+export interface DesignRefineRequest {
+  /** Draft iteration the client currently has loaded; when set, the new WASM may be sent as a patch against it */
+  base_iteration?: number | null;
+  feedback: string;
+}
+
+/** Response to design refinement */
+export interface DesignRefineResponse {
+  draft: DraftInfo;
+  error?: string | null;
+  logs: string[];
+  success: boolean;
+}
+
+/** Request to start a new design session */
+export interface DesignStartRequest {
+  /** Version the design builds on (defaults to the current version) */
+  base_version_id?: number | null;
+  prompt: string;
+}
+
+/** Response to design session start */
+export interface DesignStartResponse {
+  draft: DraftInfo;
+  logs: string[];
+  session_id: string;
+}
+
+/** DOM access for components whose DOM changes go through a proxy (e.g. components running in a Web Worker). A component may always modify its own mount point. Script elements and `javascript:` URLs are never allowed. */
+export interface DomPermissions {
+  /** Allow inline event handler attributes (`onclick="..."`). */
+  inline_handlers?: boolean;
+  /** Additional element IDs outside the mount point the component may modify. */
+  targets?: string[];
+}
+
+/** Something that happened in a component. */
+export interface DomainEvent {
+  /** Event name, e.g. `todo_added`. */
+  name: string;
+  /** Event data. */
+  payload?: unknown;
+  /** Position in the component's log, starting at 1. */
+  seq?: number;
+  /** When the event was recorded (ISO 8601). */
+  timestamp?: string;
+  /** Version of the component that emitted the event. */
+  version?: number | null;
+}
+
+/** Information about a draft */
+export interface DraftInfo {
+  compilation_error?: string | null;
+  has_runtime_error: boolean;
+  iteration: number;
+  js_glue?: string | null;
+  patch_base?: number | null;
+  prompt: string;
+  wasm_base64?: string | null;
+  /** Patch against draft `patch_base` (sent instead of `wasm_base64` when smaller) */
+  wasm_patch?: string | null;
+}
+
+/** Hand-edited source for a version */
+export interface EditRequest {
+  /** Replace the version's assets, by file name (e.g. `styles.css`), with these text contents; they go live together with the new module */
+  assets?: Record<string, string> | null;
+  /** Save even if another version was committed since the edited one */
+  force?: boolean;
+  /** What the edit changes */
+  message?: string | null;
+  /** The whole edited source */
+  source: string;
+}
+
+/** A domain event emitted by a component */
+export interface EmitEventRequest {
+  component?: string;
+  name: string;
+  payload?: unknown;
+}
+
+/** Response to an emitted event */
+export interface EmitEventResponse {
+  seq: number;
+}
+
+/** Where a component is about to run. */
+export type Environment = "browser" | "server";
+
+/** Body of every error response */
+export interface ErrorResponse {
+  error: string;
+}
+
+/** Query for events after a sequence number */
+export interface EventsQuery {
+  since?: number;
+}
+
+/** Stored examples and how generations fared with them */
+export interface ExamplesStatus {
+  embedder: string;
+  examples: number;
+  /** Examples retrieved per request; 0 disables retrieval */
+  top_k: number;
+  with_examples: OutcomeStats;
+  without_examples: OutcomeStats;
+}
+
+/** How a component has been used since it was loaded. */
+export interface ExecutionStats {
+  /** Calls into any export. */
+  calls: number;
+  /** Statistics per export, by name. */
+  exports?: Record<string, ExportStats>;
+  /** When an export was last called (UTC timestamp). */
+  last_used?: string | null;
+  /** Calls that trapped. */
+  traps: number;
+}
+
+/** Whether an experiment is still splitting traffic. */
+export type ExperimentStatus = {
+  status: "running";
+} | {
+  status: "concluded";
+  winner: Variant;
+};
+
+/** An experiment and its metrics */
+export interface ExperimentSummary {
+  component: string;
+  concluded_at?: string | null;
+  control: VariantSummary;
+  started_at: string;
+  status: ExperimentStatus;
+  treatment: VariantSummary;
+  treatment_share: number;
+}
+
+/** How one export has been called. */
+export interface ExportStats {
+  calls: number;
+  /** Call time, over the kept samples. */
+  timing: Timing;
+  traps: number;
+}
+
+/** A generation that produced no version, and why */
+export interface FailedGeneration {
+  at: string;
+  /** Compiler output of each failed attempt, in order */
+  compile_errors: string[];
+  error: string;
+  job_id: string;
+  prompt: string;
+}
+
+/** Something that went wrong */
+export interface Failure {
+  at: string;
+  message: string;
+  /** `job`, `runtime_error`, or the audited action (`activate`, `autonomous_run`, `state_update`, ...) */
+  source: string;
+  version_id?: number | null;
+}
+
+/** What clients render if a component fails to load */
+export interface FallbackResponse {
+  component: string;
+  /** The declared fallback; without one, clients show a short error message */
+  fallback?: FallbackView | null;
+}
+
+/** What to render in place of a component that failed to load. */
+export type FallbackView = {
+  html: string;
+  kind: "html";
+} | {
+  component: string;
+  kind: "component";
+};
+
+/** A user's rating of a component version. */
+export interface Feedback {
+  /** Rating from 1 to [`MAX_RATING`]. */
+  rating: number;
+  /** Free-form comment (may be empty). */
+  text?: string;
+  /** When the feedback was given (ISO 8601). */
+  timestamp?: string;
+  /** Version the feedback is about. */
+  version?: number | null;
+}
+
+/** A user's rating, sent by a component's feedback widget */
+export interface FeedbackRequest {
+  /** 1 to 5 */
+  rating: number;
+  text?: string;
+  /** Version being rated (defaults to the live version) */
+  version_id?: number | null;
+}
+
+/** Feedback recorded on a version */
+export interface FeedbackResponse {
+  average_rating: number;
+  feedback_count: number;
+  version_id: number;
+}
+
+/** How soon a client should fetch a component, as `fetch()`'s `priority` */
+export type FetchPriority = "high" | "auto" | "low";
+
+/** Request to fix a runtime error */
+export interface FixErrorRequest {
+  error_message: string;
+  version_id?: number | null;
+}
+
+/** Fork a component */
+export interface ForkRequest {
+  /** Name of the new component */
+  name: string;
+}
+
+/** A component forked from another */
+export interface ForkResponse {
+  component: string;
+  forked_from: string;
+  slots: SlotMount[];
+  /** First version of the fork */
+  version_id: number;
+}
+
+/** Request to generate component with AI */
+export interface GenerateRequest {
+  /** Version the client based this request on; a newer version committed in the meantime is a conflict */
+  base_version_id?: number | null;
+  /** Component name (defaults to "main") */
+  component?: string | null;
+  /** What to do on conflict */
+  on_conflict?: ConflictStrategy;
+  /** Permissions the component needs (none by default) */
+  permissions?: Permissions;
+  prompt: string;
+  /** Existing components to embed into named slots */
+  slots?: SlotDecl[];
+}
+
+/** Response to generation request */
+export interface GenerateResponse {
+  error?: string | null;
+  iterations: number;
+  logs: string[];
+  restored_state?: unknown;
+  slots: SlotMount[];
+  success: boolean;
+  version_id?: number | null;
+  wasm_base64?: string | null;
+}
+
+/** Precision of the positions a component is given. */
+export type GeoPrecision = "city" | "exact";
+
+/** Response to rolling back a group */
+export interface GroupRollbackResponse {
+  components: RestoredComponent[];
+  error?: string | null;
+  group_id: number;
+  success: boolean;
+  /** Version current again */
+  version_id?: number | null;
+}
+
+/** Sign-off to activate a version that exceeds guardrails */
+export interface GuardrailOverride {
+  at: string;
+  by: string;
+  reason: string;
+}
+
+export interface HeadlessGenerateRequest {
+  kind?: HeadlessKind;
+  /** Component name; generating under an existing name modifies it */
+  name: string;
+  prompt: string;
+}
+
+export interface HeadlessGenerateResponse {
+  error?: string | null;
+  iterations: number;
+  logs: string[];
+  name: string;
+  success: boolean;
+  version_id?: number | null;
+}
+
+/** What a headless component does */
+export type HeadlessKind = "http" | "transform";
+
+export interface HeadlessRollbackRequest {
+  /** Version to make live (defaults to the one before the live version) */
+  version_id?: number | null;
+}
+
+export interface HeadlessSummary {
+  kind: HeadlessKind;
+  name: string;
+  /** Where the component is reached */
+  route: string;
+  /** Live version */
+  version_id: number;
+  versions: number;
+}
+
+/** One compiled version of a headless component */
+export interface HeadlessVersion {
+  created_at: string;
+  id: number;
+  prompt: string;
+  provenance: Provenance;
+  /** The AI's code, without the prelude */
+  rust_code: string;
+  wasm_size: number;
+}
+
+/** Get version history */
+export interface HistoryResponse {
+  current_state?: unknown;
+  versions: VersionSummary[];
+}
+
+/** A sensitive host API. */
+export type HostApi = "clipboard_read" | "clipboard_write" | "pick_files" | "notify" | "get_position";
+
+/** A host API call the browser allowed or refused */
+export interface HostApiRequest {
+  allowed: boolean;
+  api: HostApi;
+  /** Why it was refused, e.g. the user declined */
+  reason?: string | null;
+  version_id?: number | null;
+}
+
+/** What a version may do */
+export interface HostPermissions {
+  /** Decision on each sensitive host import */
+  apis: ApiStatus[];
+  /** Permissions granted by the version's manifest */
+  permissions: Permissions;
+  version_id?: number | null;
+}
+
+/** How much a violation gets in users' way, as axe-core rates it. */
+export type Impact = "minor" | "moderate" | "serious" | "critical";
+
+/** The interface of a dependency a component was loaded against. */
+export interface InterfacePin {
+  /** Name of the component depended on. */
+  component: string;
+  /** Its [interface hash](ComponentInterface::hash) at the time. */
+  interface_hash: string;
+  /** Its version at the time; later versions of the same major are compatible. */
+  version?: SemVer;
+}
+
+/** A queued or finished generation */
+export interface Job {
+  created_at: string;
+  /** Why the job failed */
+  error?: string | null;
+  finished_at?: string | null;
+  id: string;
+  /** Current attempt, from 1 (0 while queued) */
+  iteration: number;
+  /** Jobs ahead of this one while queued */
+  position?: number | null;
+  prompt: string;
+  /** Outcome once `done` */
+  result?: GenerateResponse | null;
+  started_at?: string | null;
+  status: JobStatus;
+}
+
+/** Where a job is in the pipeline */
+export type JobStatus = "cancelled" | "queued" | "awaiting_ai" | "compiling" | "saving" | "done" | "failed";
+
+/** Where a component is in its life. */
+export type Lifecycle = {
+  status: "active";
+} | {
+  /** Name of the component to use instead. */
+  replacement?: string | null;
+  /** UTC timestamp (RFC 3339, e.g. `2025-03-01T00:00:00Z`) from which the component is retired once nothing depends on it. Without one it is only retired on request. */
+  retire_after?: string | null;
+  status: "deprecated";
+} | {
+  status: "retired";
+};
+
+/** A component's lifecycle status and what still depends on it */
+export interface LifecycleResponse {
+  component: string;
+  /** Components that embed it, pin its interface or fall back to it */
+  dependents: string[];
+  lifecycle: Lifecycle;
+}
+
+/** Configured limits and today's usage */
+export interface LimitsStatus {
+  /** Estimated AI spend today, in USD */
+  cost_usd: number;
+  daily_cost_budget_usd?: number | null;
+  daily_token_budget?: number | null;
+  /** UTC day the usage is for */
+  day: string;
+  max_concurrent_generations: number;
+  per_ip_per_minute: number;
+  per_key_per_minute: number;
+  running_generations: number;
+  tokens_used: number;
+}
+
+/** How far a locale's translation has got */
+export interface LocaleStatus {
+  locale: string;
+  /** Keys in use that it doesn't, shown in English */
+  missing: string[];
+  /** Keys in use that the locale (or its language) translates */
+  translated: number;
+}
+
+/** A position the browser reported for a component */
+export interface LocateRequest {
+  /** Radius of uncertainty, in metres. */
+  accuracy: number;
+  latitude: number;
+  longitude: number;
+  /** Version asking (defaults to the live one) */
+  version_id?: number | null;
+}
+
+/** The position to hand the component, if any */
+export interface LocateResult {
+  position?: Position | null;
+  /** Why not, e.g. the component is over its rate limit */
+  reason?: string | null;
+}
+
+/** One log event with the fields we index by */
+export interface LogRecord {
+  component_id?: string | null;
+  /** Any other fields on the event or its spans */
+  fields: Record<string, string>;
+  job_id?: string | null;
+  level: string;
+  message: string;
+  /** Order across all components */
+  seq: number;
+  target: string;
+  timestamp: string;
+  version?: number | null;
+}
+
+/** Query for `GET /api/logs` */
+export interface LogsQuery {
+  /** Only this component's logs */
+  component?: string | null;
+  /** Only this generation job's logs */
+  job?: string | null;
+  /** Minimum level: `error`, `warn`, `info`, `debug` or `trace` */
+  level?: string | null;
+  /** Most recent records to return (default 200) */
+  limit?: number | null;
+}
+
+/** Messages of one locale, by key. */
+export interface MessageCatalog {
+  /** BCP 47 language tag, e.g. `fr` or `fr-CA`. */
+  locale: string;
+  /** Message templates by key, e.g. `"cart.items": "{count} items"`. */
+  messages?: Record<string, string>;
+}
+
+/** Network access permissions. */
+export type NetworkPermissions = "Denied" | {
+  AllowList: string[];
+} | "Unrestricted";
+
+/** A notification a component asked to show */
+export interface NotifyRequest {
+  /** Title, for the audit log; the body stays in the browser */
+  title: string;
+  /** Version showing it (defaults to the live one) */
+  version_id?: number | null;
+}
+
+/** Whether the browser may show a notification */
+export interface NotifyResult {
+  /** Why not, e.g. the component is over its rate limit */
+  reason?: string | null;
+  shown: boolean;
+}
+
+/** Compile outcomes of generations, with or without examples */
+export interface OutcomeStats {
+  /** Compile attempts across all generations */
+  attempts: number;
+  /** Generations that never compiled */
+  failed: number;
+  /** Generations whose first attempt compiled */
+  first_try: number;
+  generations: number;
+}
+
+/** The system at a glance */
+export interface Overview {
+  /** Rate limits and today's AI usage */
+  ai: LimitsStatus;
+  /** Loaded components, by name */
+  components: ComponentStatus[];
+  /** The live version */
+  current_version?: VersionSummary2 | null;
+  generated_at: string;
+  /** The most recent generation that produced no version */
+  last_failed_generation?: FailedGeneration | null;
+  /** When a version last went live */
+  last_reload?: string | null;
+  /** Versions that have never been live, newest first */
+  pending_proposals: PendingProposal[];
+  /** Generation jobs waiting and running */
+  queue: QueueDepth;
+  /** Failed jobs, runtime errors and refused or failed operations, newest first */
+  recent_failures: Failure[];
+}
+
+/** Query for `GET /api/overview` */
+export interface OverviewQuery {
+  /** `json` (default) or `markdown` */
+  format?: string | null;
+}
+
+/** Query for a version patch */
+export interface PatchQuery {
+  from: number;
+}
+
+/** A version's WASM encoded as a patch against another version */
+export interface PatchResponse {
+  base_version_id: number;
+  patch_base64: string;
+  patch_size: number;
+  version_id: number;
+  wasm_size: number;
+}
+
+/** A version that has not gone live, and what it waits for */
+export interface PendingProposal {
+  activated_at?: string | null;
+  author: Author;
+  component: string;
+  created_at: string;
+  description: string;
+  id: number;
+  /** e.g. `review`, `guardrail override`, `scheduled for ...`; empty if nothing stops activating it */
+  waiting_for: string[];
+}
+
+/** What loading a version cost in a headless browser. */
+export interface PerformanceMeasurements {
+  /** Module plus glue, in bytes. */
+  bundle_bytes: number;
+  /** From starting to load the module to its first `render()` returning, in milliseconds. */
+  first_render_ms: number;
+  /** Main-thread tasks over 50 ms until the first render. */
+  long_tasks: number;
+}
+
+/** Permissions granted to a component. Components declare what they need, and the runtime enforces limits. */
+export interface Permissions {
+  /** Which JavaScript APIs can be accessed. */
+  apis: ApiPermission[];
+  /** What the component may do to the DOM (enforced by the DOM proxy). */
+  dom?: DomPermissions;
+  /** How precisely a component granted [`ApiPermission::Geolocation`] may locate the user. */
+  geolocation?: GeoPrecision;
+  /** Network access permissions. */
+  network: NetworkPermissions;
+  /** Local storage access. */
+  storage: StoragePermissions;
+}
+
+/** Query for `GET /api/permissions` */
+export interface PermissionsQuery {
+  /** Version to check (defaults to the live one) */
+  version_id?: number | null;
+}
+
+/** Component changes that together fulfil a request */
+export interface Plan {
+  steps: PlanStep[];
+}
+
+/** Request to plan, and optionally execute, a multi-component change */
+export interface PlanRequest {
+  /** Only return the plan */
+  dry_run?: boolean;
+  /** Execute this plan instead of asking the AI for one */
+  plan?: Plan | null;
+  prompt: string;
+}
+
+/** Outcome of a plan */
+export interface PlanResponse {
+  /** Version current before the plan */
+  base_version_id?: number | null;
+  error?: string | null;
+  /** Rollback group of the plan's versions, which undoes the whole plan */
+  group_id?: number | null;
+  logs: string[];
+  plan?: Plan | null;
+  /** Steps in the order they ran */
+  steps: StepResult[];
+  /** Whether every step was committed (or, for a dry run, the plan is valid) */
+  success: boolean;
+}
+
+/** One component change of a plan */
+export interface PlanStep {
+  action: StepAction;
+  /** Component to create or modify */
+  component: string;
+  /** Components (of the plan or existing) that must be built first */
+  depends_on?: string[];
+  /** What to generate, as for `POST /api/generate` */
+  prompt: string;
+  /** Components to embed, which are dependencies too */
+  slots?: SlotDecl[];
+}
+
+/** A position on Earth. */
+export interface Position {
+  /** Radius of uncertainty, in metres. */
+  accuracy: number;
+  latitude: number;
+  longitude: number;
+}
+
+/** What to render for one component, and how soon to fetch it */
+export interface PreloadEntry {
+  /** The version's module and glue as a matched pair, for loaders that fetch and cache them separately */
+  bundle?: BundleManifest | null;
+  component: string;
+  /** What to render instead if the version fails to load */
+  fallback: FallbackView;
+  js_glue?: string | null;
+  /** "current", "experiment", "pinned", "previous_version" or "placeholder" */
+  mode: string;
+  placeholder_html?: string | null;
+  priority: FetchPriority;
+  reason?: string | null;
+  /** Variant the session was assigned, in "experiment" mode */
+  variant?: Variant | null;
+  version_id?: number | null;
+  /** Deprecated components among it and its slots */
+  warnings: string[];
+  wasm_base64?: string | null;
+}
+
+/** What to render for every active component, highest priority first */
+export interface PreloadManifest {
+  components: PreloadEntry[];
+}
+
+/** Query for the preload manifest */
+export interface PreloadQuery {
+  /** Comma-separated components the page mounts, e.g. `main,sidebar` */
+  roots?: string;
+  /** Client session, for A/B experiment assignment */
+  session?: string | null;
+}
+
+/** Origin of a component's code. Answers "where did this code come from?": the prompt and model that produced it, the version it replaced, and the toolchain that built it. */
+export interface Provenance {
+  /** Who wrote the code. */
+  author?: Author;
+  /** Component this one was forked from. */
+  forked_from?: string | null;
+  /** Model name and version, e.g. `anthropic/claude-3.5-sonnet`. */
+  model?: string | null;
+  /** Version this one was derived from. */
+  parent_version?: number | null;
+  /** Prompt that produced the code, if AI-generated. */
+  prompt?: string | null;
+  /** Compiler toolchain that built the module, e.g. `rustc 1.82.0, wasm-pack 0.13.1`. */
+  toolchain?: string | null;
+}
+
+/** How busy the queue is */
+export interface QueueDepth {
+  /** Queued jobs accepted before new ones are refused */
+  capacity: number;
+  /** Jobs waiting to run */
+  queued: number;
+  /** Jobs started and not yet finished */
+  running: number;
+}
+
+/** How a component's live version renders. */
+export interface RenderProfile {
+  /** DOM patches per render. */
+  average_patches: number;
+  component: string;
+  /** Diff and DOM update, over the kept samples. */
+  diff: Timing;
+  max_patches: number;
+  /** Renders recorded since the version went live. */
+  renders: number;
+  version?: number | null;
+  /** View construction, over the kept samples. */
+  view: Timing;
+}
+
+/** Query for what to render */
+export interface RenderQuery {
+  /** Leave out `wasm_base64` and `js_glue`; the caller loads the bundle */
+  manifest?: boolean;
+  /** Client session, for A/B experiment assignment */
+  session?: string | null;
+}
+
+/** What the host should render for a component */
+export interface RenderResponse {
+  /** The version's module and glue as a matched pair, for loaders that fetch and cache them separately */
+  bundle?: BundleManifest | null;
+  component: string;
+  /** What to render instead if the version fails to load */
+  fallback: FallbackView;
+  js_glue?: string | null;
+  /** "current", "experiment", "pinned", "previous_version" or "placeholder" */
+  mode: string;
+  placeholder_html?: string | null;
+  reason?: string | null;
+  /** Variant the session was assigned, in "experiment" mode */
+  variant?: Variant | null;
+  version_id?: number | null;
+  /** Deprecated components among it and its slots */
+  warnings: string[];
+  wasm_base64?: string | null;
+}
+
+/** One render of a component, as timed by the host. */
+export interface RenderSample {
+  /** Component that rendered. */
+  component: string;
+  /** Time spent diffing and patching the DOM, in milliseconds. */
+  diff_ms: number;
+  /** DOM patches applied. */
+  patches: number;
+  /** Version that rendered. */
+  version?: number | null;
+  /** Time spent building the view, in milliseconds. */
+  view_ms: number;
+}
+
+/** Point to replay a component's events to: a sequence number, or the last event emitted by a version */
+export interface ReplayQuery {
+  seq?: number | null;
+  version?: number | null;
+}
+
+/** State rebuilt from events */
+export interface ReplayResponse {
+  seq: number;
+  state: unknown;
+}
+
+/** A component after its rollback group was undone */
+export interface RestoredComponent {
+  component: string;
+  /** Version it runs again (none if the group created it, so it was removed) */
+  version_id?: number | null;
+}
+
+/** Review state of one version. */
+export interface Review {
+  /** Inline comments. */
+  comments?: ReviewComment[];
+  /** Everyone who has approved or requested changes, in order. */
+  reviewers?: string[];
+  /** Current status (the most recent verdict wins). */
+  status?: ReviewStatus;
+}
+
+/** An inline comment on a range of source lines. */
+export interface ReviewComment {
+  /** Who wrote the comment. */
+  author: string;
+  /** Comment text. */
+  body: string;
+  /** Last line commented on (1-based, inclusive). */
+  end_line: number;
+  /** First line commented on (1-based, inclusive). */
+  start_line: number;
+}
+
+/** Outcome of a review. */
+export type ReviewStatus = "pending" | "approved" | "changes_requested";
+
+/** Versions committed together, which roll back together */
+export interface RollbackGroup {
+  /** Version current before the group was committed */
+  base_version_id?: number | null;
+  created_at: string;
+  id: number;
+  /** What the versions were committed for */
+  label: string;
+  rolled_back_at?: string | null;
+  version_ids: number[];
+}
+
+/** Request to rollback to a version */
+export interface RollbackRequest {
+  version_id: number;
+}
+
+/** Response to rollback */
+export interface RollbackResponse {
+  error?: string | null;
+  restored_state?: unknown;
+  success: boolean;
+  version_id: number;
+  wasm_base64: string;
+}
+
+/** How a run ended */
+export type RunOutcome = "skipped" | "proposed" | "activated" | "failed";
+
+export type SemVer = string;
+
+/** Request for `POST /api/themes/active` */
+export interface SetThemeRequest {
+  /** A theme's name, or `system` to follow each user's OS */
+  name: string;
+}
+
+/** A link granting read-only access to the live component */
+export interface ShareLink {
+  created_at: string;
+  /** When the link stops working (never if unset) */
+  expires_at?: string | null;
+  label?: string | null;
+  token: string;
+  /** Page spectators open */
+  url: string;
+}
+
+/** Request to create a share link */
+export interface ShareRequest {
+  label?: string | null;
+  /** Seconds until the link expires (never if unset) */
+  ttl_secs?: number | null;
+}
+
+/** A named slot that embeds another component. */
+export interface SlotDecl {
+  /** Name of the component mounted into this slot. */
+  component: string;
+  /** Slot name, as referenced by the parent's markup. */
+  name: string;
+}
+
+/** A child component resolved into one of its parent's slots. */
+export interface SlotMount {
+  /** The child's own slots, resolved recursively. */
+  children: SlotMount[];
+  /** Component mounted into the slot. */
+  component: ComponentId;
+  /** DOM element ID the child is mounted into. */
+  mount_point: string;
+  /** Slot name declared by the parent. */
+  slot: string;
+}
+
+/** State snapshot sizes seen for one component */
+export interface SnapshotSizeStats {
+  last_bytes: number;
+  last_stored_bytes: number;
+  max_bytes: number;
+  rejected: number;
+  snapshots: number;
+  total_bytes: number;
+}
+
+/** Query naming the share link */
+export interface SpectateQuery {
+  share?: string | null;
+}
+
+/** What a spectator sees: the live version and its state */
+export interface SpectatorView {
+  js_glue?: string | null;
+  /** The share link's label */
+  label?: string | null;
+  name?: string | null;
+  state?: unknown;
+  /** Revision of `state`, as in state sync snapshots */
+  state_revision: number;
+  version_id?: number | null;
+  wasm_base64?: string | null;
+}
+
+/** Request to start an experiment */
+export interface StartExperimentRequest {
+  /** Component name (defaults to "main") */
+  component?: string | null;
+  /** Baseline version (defaults to the live version) */
+  control_version_id?: number | null;
+  /** Fraction of sessions shown the treatment (defaults to 0.5) */
+  treatment_share?: number | null;
+  treatment_version_id: number;
+}
+
+/** Whether a step creates a component or changes an existing one */
+export type StepAction = "create" | "modify";
+
+/** What became of a step */
+export interface StepResult {
+  action: StepAction;
+  component: string;
+  error?: string | null;
+  /** Version saved for the step (none unless the whole plan was committed) */
+  version_id?: number | null;
+}
+
+/** Storage access permissions. */
+export type StoragePermissions = "None" | {
+  Limited: string[];
+} | "Full";
+
+/** A recorded telemetry event */
+export interface TelemetryEvent {
+  duration_ms?: number | null;
+  kind: TelemetryKind;
+  message: string;
+  received_at: string;
+  seq: number;
+  version_id?: number | null;
+}
+
+/** What a telemetry event reports */
+export type TelemetryKind = "error" | "slow_render" | "feedback";
+
+/** Telemetry sent by a client */
+export interface TelemetryReport {
+  /** Render time, for slow renders */
+  duration_ms?: number | null;
+  kind: TelemetryKind;
+  message: string;
+  /** Version the event was observed on (defaults to the live version) */
+  version_id?: number | null;
+}
+
+/** A named set of design tokens. */
+export interface Theme {
+  /** CSS colors by role, e.g. `"primary": "#4f46e5"`. */
+  colors?: Record<string, string>;
+  /** Theme name, e.g. `dark`. */
+  name: string;
+  /** CSS lengths by size, e.g. `"md": "1rem"`. */
+  spacing?: Record<string, string>;
+  /** CSS `font` shorthands by use, e.g. `"body": "400 1rem/1.5 system-ui, sans-serif"`. */
+  typography?: Record<string, string>;
+}
+
+/** Response for `GET /api/themes` */
+export interface ThemesResponse {
+  /** Name of the active theme, or `system` */
+  active: string;
+  themes: Theme[];
+}
+
+/** A small rendered preview of a component. */
+export interface Thumbnail {
+  /** `image/png`, `image/jpeg` or `image/webp` */
+  content_type: string;
+  /** The image, base64-encoded */
+  data_base64: string;
+}
+
+/** A preview of a version, as rendered */
+export interface ThumbnailRequest {
+  /** A PNG, JPEG or WebP `data:` URL of at most 64 KiB, as `canvas.toDataURL()` returns */
+  data_url: string;
+}
+
+/** Average, 95th percentile and maximum of a set of timings. */
+export interface Timing {
+  average_ms: number;
+  max_ms: number;
+  p95_ms: number;
+}
+
+/** Response for `GET /api/i18n` */
+export interface TranslationsOverview {
+  /** The keys the live components use, with their English text */
+  defaults: MessageCatalog;
+  /** Locales with translations */
+  locales: LocaleStatus[];
+}
+
+/** Request to update component state */
+export interface UpdateStateRequest {
+  state: unknown;
+}
+
+/** Response to state update */
+export interface UpdateStateResponse {
+  /** Revision the new state was stored as */
+  revision: number;
+  success: boolean;
+}
+
+/** Request for `POST /api/i18n/{locale}` */
+export interface UpdateTranslationsRequest {
+  /** Messages by key, replacing any the locale already has */
+  messages: Record<string, string>;
+}
+
+/** One side of an experiment. */
+export type Variant = "control" | "treatment";
+
+/** Metrics for one variant */
+export interface VariantSummary {
+  average_rating?: number | null;
+  conversion_rate?: number | null;
+  conversions: number;
+  exposures: number;
+  ratings: number;
+  version_id: number;
+}
+
+/** Version summary for history display */
+export interface VersionSummary {
+  /** axe-core violations of the rendered version, if it was audited */
+  accessibility?: AccessibilityViolation[] | null;
+  ai_generated: boolean;
+  average_rating?: number | null;
+  changelog?: ChangelogEntry | null;
+  created_at: string;
+  description: string;
+  feedback_count: number;
+  guardrail_override?: GuardrailOverride | null;
+  guardrail_violations: string[];
+  id: number;
+  is_current: boolean;
+  name: string;
+  /** First-render time, bundle size and long tasks, if measured */
+  performance?: PerformanceMeasurements | null;
+  provenance: Provenance;
+  review: Review;
+  /** Where to get the version's preview, if it has one */
+  thumbnail_url?: string | null;
+}
+
+/** A version in brief */
+export interface VersionSummary2 {
+  activated_at?: string | null;
+  author: Author;
+  component: string;
+  created_at: string;
+  description: string;
+  id: number;
+}
+
+/** Request to end an experiment */
+export interface WinnerRequest {
+  winner: Variant;
+}
+
+/** A non-2xx response from the server */
+export class MorpheusApiError extends Error {
+  constructor(
+    readonly status: number,
+    message: string,
+    readonly body: unknown,
+  ) {
+    super(message);
+  }
+}
+
+/** Typed client for the Morpheus HTTP API */
+export class MorpheusClient {
+  constructor(
+    private readonly baseUrl = "",
+    private readonly fetchImpl: typeof fetch = (input, init) => fetch(input, init),
+  ) {}
+
+  private async request<T>(method: string, path: string, query?: object, body?: unknown): Promise<T> {
+    let url = this.baseUrl + path;
+    if (query) {
+      const params = new URLSearchParams();
+      for (const [key, value] of Object.entries(query)) {
+        if (value !== undefined && value !== null) params.set(key, String(value));
+      }
+      const search = params.toString();
+      if (search) url += `?${search}`;
+    }
+    const response = await this.fetchImpl(url, {
+      method,
+      headers: body === undefined ? undefined : { "Content-Type": "application/json" },
+      body: body === undefined ? undefined : JSON.stringify(body),
+    });
+    const data = await response.json().catch(() => null);
+    if (!response.ok) {
+      throw new MorpheusApiError(response.status, data?.error ?? response.statusText, data);
+    }
+    return data as T;
+  }
+
+  /** Autonomous mode's policy and recent runs */
+  getAutonomousStatus(): Promise<AutonomousStatus> {
+    return this.request("GET", `/api/autonomous`);
+  }
+
+  /** Turn pending telemetry into a proposal now */
+  runAutonomous(): Promise<AutonomousRun> {
+    return this.request("POST", `/api/autonomous/run`);
+  }
+
+  /** List loaded components */
+  listComponents(): Promise<ComponentMetadata[]> {
+    return this.request("GET", `/api/components`);
+  }
+
+  /** Record export calls timed by a client */
+  reportCalls(body: CallSample[]): Promise<ComponentMetadata[]> {
+    return this.request("POST", `/api/components/stats`, undefined, body);
+  }
+
+  /** A component's build for the browser or a server-side harness */
+  getComponentArtifact(name: string, query?: ArtifactQuery): Promise<Artifact> {
+    return this.request("GET", `/api/components/${encodeURIComponent(String(name))}/artifact`, query);
+  }
+
+  /** Declare the HTML or component clients render if a component fails to load */
+  setComponentFallback(name: string, body: FallbackView): Promise<FallbackResponse> {
+    return this.request("POST", `/api/components/${encodeURIComponent(String(name))}/fallback`, undefined, body);
+  }
+
+  /** Remove a component's fallback, so clients show a default error message */
+  clearComponentFallback(name: string): Promise<FallbackResponse> {
+    return this.request("DELETE", `/api/components/${encodeURIComponent(String(name))}/fallback`);
+  }
+
+  /** Copy a component under a new name, with its source, permissions and state shape */
+  forkComponent(name: string, body: ForkRequest): Promise<ForkResponse> {
+    return this.request("POST", `/api/components/${encodeURIComponent(String(name))}/fork`, undefined, body);
+  }
+
+  /** Whether a component is deprecated, and what still depends on it */
+  getComponentLifecycle(name: string): Promise<LifecycleResponse> {
+    return this.request("GET", `/api/components/${encodeURIComponent(String(name))}/lifecycle`);
+  }
+
+  /** Deprecate a component, optionally scheduling its retirement */
+  deprecateComponent(name: string, body: DeprecateRequest): Promise<LifecycleResponse> {
+    return this.request("POST", `/api/components/${encodeURIComponent(String(name))}/lifecycle`, undefined, body);
+  }
+
+  /** What to render for a component, honoring its feature flag */
+  renderComponent(name: string, query?: RenderQuery): Promise<RenderResponse> {
+    return this.request("GET", `/api/components/${encodeURIComponent(String(name))}/render`, query);
+  }
+
+  /** Retire a component nothing depends on any more */
+  retireComponent(name: string): Promise<LifecycleResponse> {
+    return this.request("POST", `/api/components/${encodeURIComponent(String(name))}/retire`);
+  }
+
+  /** Report a component panic and roll back with its last state */
+  reportCrash(body: CrashReport): Promise<CrashResponse> {
+    return this.request("POST", `/api/crash`, undefined, body);
+  }
+
+  /** Recent component panics, newest first */
+  listCrashes(): Promise<Crash[]> {
+    return this.request("GET", `/api/crashes`);
+  }
+
+  /** Where the time-travel cursor is */
+  getDebugPosition(): Promise<DebugStepResponse> {
+    return this.request("GET", `/api/debug/step`);
+  }
+
+  /** Move the time-travel cursor through state history */
+  debugStep(body: DebugStepRequest): Promise<DebugStepResponse> {
+    return this.request("POST", `/api/debug/step`, undefined, body);
+  }
+
+  /** Discard the design session */
+  cancelDesign(): Promise<unknown> {
+    return this.request("POST", `/api/design/cancel`);
+  }
+
+  /** Commit the current draft as a new version */
+  commitDesign(body: DesignCommitRequest): Promise<DesignCommitResponse> {
+    return this.request("POST", `/api/design/commit`, undefined, body);
+  }
+
+  /** The design session's current draft */
+  getDesignPreview(): Promise<DesignPreviewResponse> {
+    return this.request("GET", `/api/design/preview`);
+  }
+
+  /** Refine the current draft with feedback */
+  refineDesign(body: DesignRefineRequest): Promise<DesignRefineResponse> {
+    return this.request("POST", `/api/design/refine`, undefined, body);
+  }
+
+  /** Start an interactive design session */
+  startDesign(body: DesignStartRequest): Promise<DesignStartResponse> {
+    return this.request("POST", `/api/design/start`, undefined, body);
+  }
+
+  /** Record a domain event (event-sourced state mode) */
+  emitEvent(body: EmitEventRequest): Promise<EmitEventResponse> {
+    return this.request("POST", `/api/events`, undefined, body);
+  }
+
+  /** A component's events after a sequence number */
+  listEvents(component: string, query?: EventsQuery): Promise<DomainEvent[]> {
+    return this.request("GET", `/api/events/${encodeURIComponent(String(component))}`, query);
+  }
+
+  /** Rebuild a component's state by replaying its events */
+  replayEvents(component: string, query?: ReplayQuery): Promise<ReplayResponse> {
+    return this.request("GET", `/api/events/${encodeURIComponent(String(component))}/replay`, query);
+  }
+
+  /** Few-shot examples and compile outcomes with and without them */
+  getExamples(): Promise<ExamplesStatus> {
+    return this.request("GET", `/api/examples`);
+  }
+
+  /** A/B experiments and their metrics */
+  listExperiments(): Promise<ExperimentSummary[]> {
+    return this.request("GET", `/api/experiments`);
+  }
+
+  /** Split a component's sessions between two versions */
+  startExperiment(body: StartExperimentRequest): Promise<ExperimentSummary> {
+    return this.request("POST", `/api/experiments`, undefined, body);
+  }
+
+  /** Count a conversion for a session's variant */
+  recordConversion(component: string, body: ConversionRequest): Promise<ConversionResponse> {
+    return this.request("POST", `/api/experiments/${encodeURIComponent(String(component))}/conversion`, undefined, body);
+  }
+
+  /** End an experiment; the winner becomes the live version */
+  declareWinner(component: string, body: WinnerRequest): Promise<ExperimentSummary> {
+    return this.request("POST", `/api/experiments/${encodeURIComponent(String(component))}/winner`, undefined, body);
+  }
+
+  /** Rate a version (defaults to the live one) */
+  submitFeedback(body: FeedbackRequest): Promise<FeedbackResponse> {
+    return this.request("POST", `/api/feedback`, undefined, body);
+  }
+
+  /** Regenerate a component that failed at runtime */
+  fixRuntimeError(body: FixErrorRequest): Promise<GenerateResponse> {
+    return this.request("POST", `/api/fix`, undefined, body);
+  }
+
+  /** Generate a component from a prompt, waiting for the result */
+  generate(body: GenerateRequest): Promise<GenerateResponse> {
+    return this.request("POST", `/api/generate`, undefined, body);
+  }
+
+  /** Coarsen a reported position to the component's granted precision, with caching and rate limits */
+  locate(body: LocateRequest): Promise<LocateResult> {
+    return this.request("POST", `/api/geolocation`, undefined, body);
+  }
+
+  /** Versions committed together, newest first */
+  listGroups(): Promise<RollbackGroup[]> {
+    return this.request("GET", `/api/groups`);
+  }
+
+  /** Roll back every version of a group at once */
+  rollbackGroup(id: number): Promise<GroupRollbackResponse> {
+    return this.request("POST", `/api/groups/${encodeURIComponent(String(id))}/rollback`);
+  }
+
+  /** List headless (server-side) components */
+  listHeadless(): Promise<HeadlessSummary[]> {
+    return this.request("GET", `/api/headless`);
+  }
+
+  /** Generate or modify a headless component */
+  generateHeadless(body: HeadlessGenerateRequest): Promise<HeadlessGenerateResponse> {
+    return this.request("POST", `/api/headless/generate`, undefined, body);
+  }
+
+  /** A headless component's versions */
+  getHeadlessHistory(name: string): Promise<HeadlessVersion[]> {
+    return this.request("GET", `/api/headless/${encodeURIComponent(String(name))}/history`);
+  }
+
+  /** Make an earlier version of a headless component live */
+  rollbackHeadless(name: string, body: HeadlessRollbackRequest): Promise<HeadlessSummary> {
+    return this.request("POST", `/api/headless/${encodeURIComponent(String(name))}/rollback`, undefined, body);
+  }
+
+  /** Run a transform component */
+  transformHeadless(name: string, body: unknown): Promise<unknown> {
+    return this.request("POST", `/api/headless/${encodeURIComponent(String(name))}/transform`, undefined, body);
+  }
+
+  /** Health check */
+  health(): Promise<unknown> {
+    return this.request("GET", `/api/health`);
+  }
+
+  /** Version history and the current state */
+  getHistory(): Promise<HistoryResponse> {
+    return this.request("GET", `/api/history`);
+  }
+
+  /** Translation keys in use, and what each locale lacks */
+  getTranslations(): Promise<TranslationsOverview> {
+    return this.request("GET", `/api/i18n`);
+  }
+
+  /** Every message in a locale, with fallbacks applied */
+  getLocale(locale: string): Promise<MessageCatalog> {
+    return this.request("GET", `/api/i18n/${encodeURIComponent(String(locale))}`);
+  }
+
+  /** Add or change a locale's translations */
+  updateLocale(locale: string, body: UpdateTranslationsRequest): Promise<MessageCatalog> {
+    return this.request("POST", `/api/i18n/${encodeURIComponent(String(locale))}`, undefined, body);
+  }
+
+  /** Queue a generation and return its job right away */
+  createJob(body: GenerateRequest): Promise<Job> {
+    return this.request("POST", `/api/jobs`, undefined, body);
+  }
+
+  /** A generation job's status, and its outcome once finished */
+  getJob(id: string): Promise<Job> {
+    return this.request("GET", `/api/jobs/${encodeURIComponent(String(id))}`);
+  }
+
+  /** Cancel a job that hasn't started saving */
+  cancelJob(id: string): Promise<Job> {
+    return this.request("DELETE", `/api/jobs/${encodeURIComponent(String(id))}`);
+  }
+
+  /** Rate limits and today's AI usage against the budget */
+  getLimits(): Promise<LimitsStatus> {
+    return this.request("GET", `/api/limits`);
+  }
+
+  /** Recent server logs, optionally for one component or job */
+  getLogs(query?: LogsQuery): Promise<LogRecord[]> {
+    return this.request("GET", `/api/logs`, query);
+  }
+
+  /** Check a component's notification against its permission and rate limit */
+  notify(body: NotifyRequest): Promise<NotifyResult> {
+    return this.request("POST", `/api/notifications`, undefined, body);
+  }
+
+  /** Components, live versions, pending proposals, recent failures, AI spend and queue depth */
+  getOverview(query?: OverviewQuery): Promise<Overview> {
+    return this.request("GET", `/api/overview`, query);
+  }
+
+  /** Which clipboard, file, notification and location imports a version may call */
+  getPermissions(query?: PermissionsQuery): Promise<HostPermissions> {
+    return this.request("GET", `/api/permissions`, query);
+  }
+
+  /** Record a host API call the browser allowed or refused */
+  recordHostApiRequest(body: HostApiRequest): Promise<unknown> {
+    return this.request("POST", `/api/permissions/requests`, undefined, body);
+  }
+
+  /** Split a request into several component changes and commit them together */
+  plan(body: PlanRequest): Promise<PlanResponse> {
+    return this.request("POST", `/api/plan`, undefined, body);
+  }
+
+  /** What to render for every active component, with fetch priorities, to load before the first render */
+  getPreload(query?: PreloadQuery): Promise<PreloadManifest> {
+    return this.request("GET", `/api/preload`, query);
+  }
+
+  /** How each component renders, slowest first */
+  listRenderProfiles(): Promise<RenderProfile[]> {
+    return this.request("GET", `/api/profiler`);
+  }
+
+  /** Record render timings from a client */
+  reportRenders(body: RenderSample[]): Promise<RenderProfile[]> {
+    return this.request("POST", `/api/profiler`, undefined, body);
+  }
+
+  /** Make an earlier version current and restore its state */
+  rollback(body: RollbackRequest): Promise<RollbackResponse> {
+    return this.request("POST", `/api/rollback`, undefined, body);
+  }
+
+  /** List share links */
+  listShares(): Promise<ShareLink[]> {
+    return this.request("GET", `/api/share`);
+  }
+
+  /** Create a read-only link to the live component */
+  createShare(body: ShareRequest): Promise<ShareLink> {
+    return this.request("POST", `/api/share`, undefined, body);
+  }
+
+  /** Revoke a share link */
+  revokeShare(token: string): Promise<unknown> {
+    return this.request("DELETE", `/api/share/${encodeURIComponent(String(token))}`);
+  }
+
+  /** The live version and state, as a spectator sees them */
+  spectate(query?: SpectateQuery): Promise<SpectatorView> {
+    return this.request("GET", `/api/spectate`, query);
+  }
+
+  /** Replace the current component state */
+  updateState(body: UpdateStateRequest): Promise<UpdateStateResponse> {
+    return this.request("POST", `/api/state`, undefined, body);
+  }
+
+  /** State snapshot sizes by component */
+  getSnapshotMetrics(): Promise<Record<string, SnapshotSizeStats>> {
+    return this.request("GET", `/api/state/metrics`);
+  }
+
+  /** Recent telemetry events */
+  listTelemetry(): Promise<TelemetryEvent[]> {
+    return this.request("GET", `/api/telemetry`);
+  }
+
+  /** Report a runtime error, slow render or feedback */
+  reportTelemetry(body: TelemetryReport): Promise<TelemetryEvent> {
+    return this.request("POST", `/api/telemetry`, undefined, body);
+  }
+
+  /** Themes on offer, and which one is active */
+  listThemes(): Promise<ThemesResponse> {
+    return this.request("GET", `/api/themes`);
+  }
+
+  /** Add a theme, or replace the one with its name */
+  registerTheme(body: Theme): Promise<ThemesResponse> {
+    return this.request("POST", `/api/themes`, undefined, body);
+  }
+
+  /** Switch themes, restyling mounted components */
+  setActiveTheme(body: SetThemeRequest): Promise<ActiveTheme> {
+    return this.request("POST", `/api/themes/active`, undefined, body);
+  }
+
+  /** A version's WASM module, JS glue and assets, with integrity hashes */
+  getVersionBundle(id: number): Promise<BundleManifest> {
+    return this.request("GET", `/api/versions/${encodeURIComponent(String(id))}/bundle`);
+  }
+
+  /** Compile hand-edited source of a version and save it as a new version */
+  editVersion(id: number, body: EditRequest): Promise<GenerateResponse> {
+    return this.request("POST", `/api/versions/${encodeURIComponent(String(id))}/edit`, undefined, body);
+  }
+
+  /** Ratings recorded on a version */
+  getVersionFeedback(id: number): Promise<Feedback[]> {
+    return this.request("GET", `/api/versions/${encodeURIComponent(String(id))}/feedback`);
+  }
+
+  /** A version's WASM as a patch against another version */
+  getVersionPatch(id: number, query: PatchQuery): Promise<PatchResponse> {
+    return this.request("GET", `/api/versions/${encodeURIComponent(String(id))}/patch`, query);
+  }
+
+  /** Store a preview of a version, as rendered */
+  setVersionThumbnail(id: number, body: ThumbnailRequest): Promise<Thumbnail> {
+    return this.request("POST", `/api/versions/${encodeURIComponent(String(id))}/thumbnail`, undefined, body);
+  }
+}
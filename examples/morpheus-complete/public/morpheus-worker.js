@@ -0,0 +1,121 @@
+// Morpheus worker host
+//
+// Runs a generated component off the main thread. The component never sees
+// the DOM: its rendered HTML is posted back as DOM operations, which the main
+// thread validates against the component's DOM permissions before applying.
+//
+// Protocol (JSON, tagged by `type`) mirrors morpheus_runtime::worker.
+
+let component = null;
+let mountPoint = 'componentMount';
+// Messages of the page's locale, for morpheus.t
+let messages = {};
+// Tokens of the page's theme and the OS color scheme, for
+// morpheus.themeToken and morpheus.colorScheme
+let tokens = {};
+let scheme = 'light';
+
+function post(message) {
+    self.postMessage(message);
+}
+
+function formatMessage(message, args) {
+    const values = JSON.parse(args || 'null') || {};
+    return message.replace(/\{([^{}]+)\}/g, (placeholder, name) => name in values
+        ? (typeof values[name] === 'string' ? values[name] : JSON.stringify(values[name]))
+        : placeholder);
+}
+
+// Host imports for translations, theme tokens, domain events, feedback and
+// conversions; the main thread forwards events to the server
+self.morpheus = {
+    t(key, fallback, args) {
+        return formatMessage(messages[key] ?? fallback ?? key, args);
+    },
+    themeToken(name) {
+        return tokens[name] ?? '';
+    },
+    colorScheme() {
+        return scheme;
+    },
+    emitEvent(name, payload) {
+        post({ type: 'event', name, payload: JSON.parse(payload || 'null') });
+    },
+    feedback(rating, text) {
+        post({ type: 'feedback', rating, text: text || '' });
+    },
+    convert(goal) {
+        post({ type: 'conversion', goal: goal || '' });
+    },
+    // Clipboard, files, notifications and location need the page; not available
+    // in worker mode
+    clipboardWrite() {
+        throw new Error('morpheus.clipboardWrite is not available in worker mode');
+    },
+    clipboardRead() {
+        throw new Error('morpheus.clipboardRead is not available in worker mode');
+    },
+    pickFiles() {
+        throw new Error('morpheus.pickFiles is not available in worker mode');
+    },
+    notify() {
+        throw new Error('morpheus.notify is not available in worker mode');
+    },
+    getPosition() {
+        throw new Error('morpheus.getPosition is not available in worker mode');
+    }
+};
+
+function renderOps(html) {
+    return [{ op: 'set_html', target: mountPoint, html }];
+}
+
+async function load({ wasm_base64, js_glue, mount_point }) {
+    if (mount_point) mountPoint = mount_point;
+
+    const wasmBinary = Uint8Array.from(atob(wasm_base64), c => c.charCodeAt(0));
+    const jsUrl = URL.createObjectURL(new Blob([js_glue], { type: 'application/javascript' }));
+    try {
+        component = await import(jsUrl);
+        await component.default(await WebAssembly.compile(wasmBinary));
+    } finally {
+        URL.revokeObjectURL(jsUrl);
+    }
+
+    const exports = Object.keys(component).filter(name => typeof component[name] === 'function');
+    post({ type: 'ready', exports });
+}
+
+function render() {
+    if (typeof component.render !== 'function') {
+        throw new Error('No render() function found in component');
+    }
+    post({ type: 'dom', ops: renderOps(component.render()) });
+}
+
+function dispatch({ message }) {
+    if (typeof component.dispatch !== 'function') {
+        throw new Error('Component does not handle messages');
+    }
+    const html = component.dispatch(JSON.stringify(message));
+    if (typeof html === 'string') {
+        post({ type: 'dom', ops: renderOps(html) });
+    }
+}
+
+self.onmessage = async (event) => {
+    const request = event.data;
+    try {
+        switch (request.type) {
+            case 'load': await load(request); break;
+            case 'render': render(); break;
+            case 'dispatch': dispatch(request); break;
+            case 'locale': messages = request.messages || {}; break;
+            case 'theme': tokens = request.tokens || {}; scheme = request.scheme || 'light'; break;
+            case 'unload': component = null; self.close(); break;
+            default: throw new Error(`Unknown request: ${request.type}`);
+        }
+    } catch (error) {
+        post({ type: 'error', message: error.message || String(error) });
+    }
+};
@@ -0,0 +1,134 @@
+//! Host adapters for running components inside existing single-page apps.
+//!
+//! `GET /api/adapters/{file}` serves a small JS package, `morpheus-host`:
+//! a framework-neutral `MorpheusHost` that mounts a component, follows
+//! `GET /api/reloads` to hot-reload it and bridges the shared state over
+//! `/api/state/sync`, plus `useMorpheusComponent(id)` for React and a Vue
+//! plugin on top. The package is generated with the address of the server
+//! that served it, so apps can import it straight from the server or
+//! download it into their own build.
+
+use crate::AppError;
+use axum::extract::Path;
+use axum::http::{header, HeaderMap};
+use axum::response::IntoResponse;
+
+/// Name of the generated package
+pub const PACKAGE_NAME: &str = "morpheus-host";
+
+/// Files in the package
+pub const FILES: [&str; 4] = ["package.json", "morpheus-host.js", "react.js", "vue.js"];
+
+/// Address baked in when the request has no `Host` header
+const DEFAULT_SERVER: &str = "http://127.0.0.1:3002";
+
+/// Placeholder for the server address in `morpheus-host.js`
+const SERVER_PLACEHOLDER: &str = "__MORPHEUS_SERVER__";
+
+const HOST_JS: &str = include_str!("../adapters/morpheus-host.js");
+const REACT_JS: &str = include_str!("../adapters/react.js");
+const VUE_JS: &str = include_str!("../adapters/vue.js");
+
+/// A file of the package, for a server at `server`, with its content type
+pub fn package_file(file: &str, server: &str) -> Option<(&'static str, String)> {
+    let js = "text/javascript; charset=utf-8";
+    match file {
+        "package.json" => Some(("application/json", package_json())),
+        "morpheus-host.js" => Some((
+            js,
+            HOST_JS.replace(SERVER_PLACEHOLDER, &serde_json::Value::from(server).to_string()),
+        )),
+        "react.js" => Some((js, REACT_JS.to_string())),
+        "vue.js" => Some((js, VUE_JS.to_string())),
+        _ => None,
+    }
+}
+
+fn package_json() -> String {
+    let package = serde_json::json!({
+        "name": PACKAGE_NAME,
+        "version": env!("CARGO_PKG_VERSION"),
+        "description": "Mount hot-reloading Morpheus components in React, Vue or plain JavaScript apps",
+        "type": "module",
+        "main": "./morpheus-host.js",
+        "exports": {
+            ".": "./morpheus-host.js",
+            "./react": "./react.js",
+            "./vue": "./vue.js"
+        },
+        "peerDependencies": { "react": ">=16.8", "vue": ">=3.0" },
+        "peerDependenciesMeta": {
+            "react": { "optional": true },
+            "vue": { "optional": true }
+        }
+    });
+    serde_json::to_string_pretty(&package).unwrap_or_default()
+}
+
+/// Serve a file of the host adapter package
+pub async fn get_adapter(headers: HeaderMap, Path(file): Path<String>) -> Result<impl IntoResponse, AppError> {
+    let server = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .map(|host| format!("http://{}", host))
+        .unwrap_or_else(|| DEFAULT_SERVER.to_string());
+    let (content_type, body) = package_file(&file, &server).ok_or_else(|| {
+        AppError::ApiError(format!("No adapter file '{}' (available: {})", file, FILES.join(", ")))
+    })?;
+    Ok(([(header::CONTENT_TYPE, content_type)], body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_files() {
+        for file in FILES {
+            assert!(package_file(file, DEFAULT_SERVER).is_some(), "{}", file);
+        }
+        assert!(package_file("../main.rs", DEFAULT_SERVER).is_none());
+
+        let (_, host) = package_file("morpheus-host.js", "http://localhost:8080").unwrap();
+        assert!(host.contains(r#"export const SERVER = "http://localhost:8080";"#));
+        assert!(!host.contains(SERVER_PLACEHOLDER));
+        assert!(host.contains("export function captureInputs(container)"));
+        assert!(host.contains("export function restoreInputs(container"));
+        let (_, react) = package_file("react.js", DEFAULT_SERVER).unwrap();
+        assert!(react.contains("export function useMorpheusComponent(id"));
+        let (_, vue) = package_file("vue.js", DEFAULT_SERVER).unwrap();
+        assert!(vue.contains("export const MorpheusPlugin"));
+    }
+
+    #[test]
+    fn test_host_leaves_secrets_behind_and_announces_restored_values() {
+        let (_, host) = package_file("morpheus-host.js", DEFAULT_SERVER).unwrap();
+        let carries = &host[host.find("function carriesValue(element)").unwrap()..];
+        let carries = &carries[..carries.find("\n}\n").unwrap()];
+        assert!(carries.contains("['file', 'password', 'hidden'].includes(element.type)"));
+        assert!(carries.contains("element.form?.getAttribute('autocomplete')"));
+        assert!(carries.contains("!== 'off'"));
+
+        let capture = &host[host.find("export function captureInputs(container)").unwrap()..];
+        assert!(capture[..capture.find("\n}\n").unwrap()].contains("if (carriesValue(element))"));
+        let restore = &host[host.find("export function restoreInputs(container").unwrap()..];
+        let restore = &restore[..restore.find("\n}\n").unwrap()];
+        assert!(restore.contains("!carriesValue(element)"));
+        assert!(restore.contains("dispatchEvent(new Event('input', { bubbles: true }))"));
+        assert!(restore.contains("dispatchEvent(new Event('change', { bubbles: true }))"));
+        assert!(!host.contains("so hosts can keep it"));
+    }
+
+    #[test]
+    fn test_package_json_exports_every_file() {
+        let (content_type, json) = package_file("package.json", DEFAULT_SERVER).unwrap();
+        let package: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(content_type, "application/json");
+        assert_eq!(package["name"], PACKAGE_NAME);
+        let exported: Vec<_> = package["exports"].as_object().unwrap().values().collect();
+        for file in &FILES[1..] {
+            assert!(exported.iter().any(|path| path.as_str() == Some(&format!("./{}", file))), "{}", file);
+        }
+    }
+}
@@ -0,0 +1,618 @@
+//! Autonomous mode: the component improves itself from runtime telemetry.
+//!
+//! Clients report errors, slow renders and user feedback to
+//! `POST /api/telemetry`. When `MORPHEUS_AUTONOMOUS_INTERVAL_SECS` is set, a
+//! background task periodically hands the telemetry about the live version
+//! to the AI together with a constrained objective, compiles the result and
+//! adds it to the version history as a proposal. Telemetry passes through
+//! the telemetry scrub policy on its way to the AI, so user data reported
+//! with an error stays on the server.
+//!
+//! Clients also time every render (view construction, diff and DOM patches)
+//! and report the samples to `POST /api/profiler`. When the live version
+//! renders slowly, its render profile goes into the prompt as well.
+//!
+//! Proposals go through the same pipeline as any other version: review and
+//! guardrails apply, and a proposal is only activated when
+//! `MORPHEUS_AUTONOMOUS_ACTIVATE` is set *and* neither review nor a
+//! guardrail override is required. Otherwise it waits for a person to
+//! approve and activate it.
+
+use crate::{
+    compile_verified, complete, extract_rust_code, record_audit, register_component, screen_request, truncate,
+    AppError, AppState, Message, PromptContext,
+};
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use morpheus_compiler::source;
+use morpheus_core::component::Provenance;
+use morpheus_core::privacy::ScrubPolicy;
+use morpheus_core::profiler::{self, RenderProfile, RenderSample};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// Telemetry events kept in memory
+const MAX_TELEMETRY_EVENTS: usize = 500;
+
+/// Longest telemetry message kept
+const MAX_MESSAGE_CHARS: usize = 2000;
+
+/// Runs kept for `GET /api/autonomous`
+const MAX_RUNS: usize = 50;
+
+/// Generate-and-compile attempts per run
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Objective used when `MORPHEUS_AUTONOMOUS_OBJECTIVE` is not set
+const DEFAULT_OBJECTIVE: &str =
+    "Fix the reported errors and slow renders and address user feedback without removing any existing features.";
+
+/// What a telemetry event reports
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TelemetryKind {
+    /// The component failed at runtime
+    Error,
+    /// A render took longer than expected
+    SlowRender,
+    /// A user commented on the component
+    Feedback,
+}
+
+/// Telemetry sent by a client
+#[derive(Deserialize, JsonSchema)]
+pub struct TelemetryReport {
+    pub kind: TelemetryKind,
+    /// Version the event was observed on (defaults to the live version)
+    #[serde(default)]
+    pub version_id: Option<usize>,
+    pub message: String,
+    /// Render time, for slow renders
+    #[serde(default)]
+    pub duration_ms: Option<f64>,
+}
+
+/// A recorded telemetry event
+#[derive(Clone, Serialize, JsonSchema)]
+pub struct TelemetryEvent {
+    pub seq: u64,
+    pub kind: TelemetryKind,
+    pub version_id: Option<usize>,
+    pub message: String,
+    pub duration_ms: Option<f64>,
+    pub received_at: DateTime<Utc>,
+}
+
+impl TelemetryEvent {
+    /// The event with `policy`'s redactions applied to its message, for a
+    /// prompt
+    pub fn redacted(mut self, policy: &ScrubPolicy) -> Self {
+        self.message = policy.redact_text(&self.message);
+        self
+    }
+}
+
+/// Recent telemetry, oldest first
+#[derive(Default)]
+pub struct Telemetry {
+    events: VecDeque<TelemetryEvent>,
+    next_seq: u64,
+    /// Events before this sequence number have been handed to the AI
+    consumed: u64,
+}
+
+impl Telemetry {
+    /// Record a report, dropping the oldest event when full
+    pub fn record(&mut self, report: TelemetryReport, current_version: Option<usize>) -> TelemetryEvent {
+        let event = TelemetryEvent {
+            seq: self.next_seq,
+            kind: report.kind,
+            version_id: report.version_id.or(current_version),
+            message: report.message.chars().take(MAX_MESSAGE_CHARS).collect(),
+            duration_ms: report.duration_ms,
+            received_at: Utc::now(),
+        };
+        self.next_seq += 1;
+        self.events.push_back(event.clone());
+        while self.events.len() > MAX_TELEMETRY_EVENTS {
+            self.events.pop_front();
+        }
+        event
+    }
+
+    /// Events about `version_id` not yet handed to the AI
+    pub fn pending(&self, version_id: usize) -> Vec<TelemetryEvent> {
+        self.events
+            .iter()
+            .filter(|e| e.seq >= self.consumed && e.version_id == Some(version_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Mark every event recorded so far as handled
+    pub fn consume_all(&mut self) {
+        self.consumed = self.next_seq;
+    }
+
+    /// All events kept, oldest first
+    pub fn recent(&self) -> Vec<TelemetryEvent> {
+        self.events.iter().cloned().collect()
+    }
+}
+
+/// What autonomous mode may do
+#[derive(Clone, Debug)]
+pub struct AutonomousPolicy {
+    /// Time between runs
+    pub interval: Duration,
+    /// What the AI is asked to achieve
+    pub objective: String,
+    /// Whether proposals may go live without a person activating them
+    pub activate: bool,
+    /// Pending events needed before a run asks the AI for a proposal
+    pub min_events: usize,
+}
+
+impl AutonomousPolicy {
+    /// Read the policy from the environment; `None` unless
+    /// `MORPHEUS_AUTONOMOUS_INTERVAL_SECS` is set. Proposals only go live on
+    /// their own when `MORPHEUS_AUTONOMOUS_ACTIVATE` is true.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let Ok(interval) = std::env::var("MORPHEUS_AUTONOMOUS_INTERVAL_SECS") else {
+            return Ok(None);
+        };
+        let min_events = match std::env::var("MORPHEUS_AUTONOMOUS_MIN_EVENTS") {
+            Ok(value) => value.parse()?,
+            Err(_) => 1,
+        };
+        Ok(Some(Self {
+            interval: Duration::from_secs(interval.parse()?),
+            objective: std::env::var("MORPHEUS_AUTONOMOUS_OBJECTIVE").unwrap_or_else(|_| DEFAULT_OBJECTIVE.to_string()),
+            activate: crate::env_flag("MORPHEUS_AUTONOMOUS_ACTIVATE")?,
+            min_events: min_events.max(1),
+        }))
+    }
+}
+
+/// Autonomous mode's configuration and run log
+pub struct Autonomous {
+    pub policy: Option<AutonomousPolicy>,
+    runs: Mutex<VecDeque<AutonomousRun>>,
+    /// Held while a run is in progress so runs never overlap
+    running: Mutex<()>,
+}
+
+impl Autonomous {
+    pub fn new(policy: Option<AutonomousPolicy>) -> Self {
+        Self {
+            policy,
+            runs: Mutex::new(VecDeque::new()),
+            running: Mutex::new(()),
+        }
+    }
+}
+
+/// How a run ended
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RunOutcome {
+    /// Not enough new telemetry to act on
+    Skipped,
+    /// A proposal was added and awaits activation
+    Proposed,
+    /// A proposal was added and activated under the policy
+    Activated,
+    /// No proposal could be produced
+    Failed,
+}
+
+/// One pass of the self-improvement loop
+#[derive(Clone, Serialize, JsonSchema)]
+pub struct AutonomousRun {
+    pub started_at: DateTime<Utc>,
+    pub outcome: RunOutcome,
+    /// Version the telemetry was about
+    pub base_version_id: Option<usize>,
+    /// Proposal added to the history
+    pub version_id: Option<usize>,
+    pub events: usize,
+    pub detail: String,
+}
+
+/// Autonomous mode's configuration and recent runs
+#[derive(Serialize, JsonSchema)]
+pub struct AutonomousStatus {
+    pub enabled: bool,
+    pub interval_secs: Option<u64>,
+    pub objective: Option<String>,
+    pub auto_activate: bool,
+    pub min_events: Option<usize>,
+    /// Telemetry about the live version not yet handed to the AI
+    pub pending_events: usize,
+    /// Most recent first
+    pub runs: Vec<AutonomousRun>,
+}
+
+/// Record a telemetry event
+pub async fn report_telemetry(
+    State(state): State<AppState>,
+    Json(report): Json<TelemetryReport>,
+) -> Result<Json<TelemetryEvent>, AppError> {
+    // Telemetry goes to the AI in autonomous runs
+    screen_request(&state, "telemetry", &report.message).await?;
+    let current = state.versions.lock().await.get_current().map(|v| v.id);
+    let event = state.telemetry.lock().await.record(report, current);
+    Ok(Json(event))
+}
+
+/// Recent telemetry events
+pub async fn list_telemetry(State(state): State<AppState>) -> Result<Json<Vec<TelemetryEvent>>, AppError> {
+    Ok(Json(state.telemetry.lock().await.recent()))
+}
+
+/// Record render samples timed by a client
+pub async fn report_renders(
+    State(state): State<AppState>,
+    Json(samples): Json<Vec<RenderSample>>,
+) -> Result<Json<Vec<RenderProfile>>, AppError> {
+    let mut profiler = state.profiler.lock().await;
+    for sample in samples {
+        profiler.record(sample)?;
+    }
+    Ok(Json(profiler.profiles()))
+}
+
+/// How each component's live version renders, slowest first
+pub async fn list_render_profiles(State(state): State<AppState>) -> Result<Json<Vec<RenderProfile>>, AppError> {
+    Ok(Json(state.profiler.lock().await.profiles()))
+}
+
+/// Autonomous mode's policy and recent runs
+pub async fn get_status(State(state): State<AppState>) -> Result<Json<AutonomousStatus>, AppError> {
+    let current = state.versions.lock().await.get_current().map(|v| v.id);
+    let pending_events = match current {
+        Some(id) => state.telemetry.lock().await.pending(id).len(),
+        None => 0,
+    };
+    let policy = state.autonomous.policy.as_ref();
+    Ok(Json(AutonomousStatus {
+        enabled: policy.is_some(),
+        interval_secs: policy.map(|p| p.interval.as_secs()),
+        objective: policy.map(|p| p.objective.clone()),
+        auto_activate: policy.is_some_and(|p| p.activate),
+        min_events: policy.map(|p| p.min_events),
+        pending_events,
+        runs: state.autonomous.runs.lock().await.iter().rev().cloned().collect(),
+    }))
+}
+
+/// Run the self-improvement loop once, now
+pub async fn run_now(State(state): State<AppState>) -> Result<Json<AutonomousRun>, AppError> {
+    let Some(policy) = state.autonomous.policy.clone() else {
+        return Err(AppError::ApiError(
+            "Autonomous mode is disabled; set MORPHEUS_AUTONOMOUS_INTERVAL_SECS".to_string(),
+        ));
+    };
+    Ok(Json(run_once(&state, &policy).await))
+}
+
+/// Run the self-improvement loop every `policy.interval`
+pub async fn run_autonomous(state: AppState, policy: AutonomousPolicy) {
+    let mut interval = tokio::time::interval(policy.interval);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        run_once(&state, &policy).await;
+    }
+}
+
+/// Turn pending telemetry into a proposal
+async fn run_once(state: &AppState, policy: &AutonomousPolicy) -> AutonomousRun {
+    let _running = state.autonomous.running.lock().await;
+    let started_at = Utc::now();
+
+    let history = state.versions.lock().await;
+    let Some(current) = history.get_current().cloned() else {
+        return skipped(started_at, None, 0, "No live version to improve");
+    };
+    drop(history);
+
+    let events: Vec<TelemetryEvent> = state
+        .telemetry
+        .lock()
+        .await
+        .pending(current.id)
+        .into_iter()
+        .map(|e| e.redacted(&state.telemetry_policy))
+        .collect();
+    if events.len() < policy.min_events {
+        return skipped(
+            started_at,
+            Some(current.id),
+            events.len(),
+            &format!("{} of {} telemetry events needed", events.len(), policy.min_events),
+        );
+    }
+    if !state.has_ai() {
+        return skipped(started_at, Some(current.id), events.len(), "OPENROUTER_API_KEY not configured");
+    }
+    // Each batch of telemetry gets one proposal, whatever the outcome
+    state.telemetry.lock().await.consume_all();
+    info!(
+        component_id = %current.manifest.name,
+        version = current.id,
+        "🔁 Autonomous run with {} telemetry events",
+        events.len()
+    );
+
+    let profile = state
+        .profiler
+        .lock()
+        .await
+        .profile(&current.manifest.name)
+        .filter(|profile| profile.version.and_then(|v| usize::try_from(v).ok()) == Some(current.id));
+    let prompt = improvement_prompt(&policy.objective, &current.rust_code, &events, profile.as_ref());
+    let mut run = AutonomousRun {
+        started_at,
+        outcome: RunOutcome::Failed,
+        base_version_id: Some(current.id),
+        version_id: None,
+        events: events.len(),
+        detail: String::new(),
+    };
+    match propose(state, policy, &current.manifest, &prompt, summarize(&events)).await {
+        Ok((version_id, activated)) => {
+            run.version_id = Some(version_id);
+            run.outcome = if activated { RunOutcome::Activated } else { RunOutcome::Proposed };
+            run.detail = if activated {
+                format!("Version {} activated", version_id)
+            } else {
+                format!("Version {} awaits approval and activation", version_id)
+            };
+            info!(component_id = %current.manifest.name, version = version_id, "🔁 {}", run.detail);
+        }
+        Err(e) => {
+            run.detail = e.to_string();
+            warn!(component_id = %current.manifest.name, "🔁 Autonomous run failed: {}", e);
+        }
+    }
+
+    let outcome = format!("{:?}", run.outcome).to_lowercase();
+    record_audit(state, "autonomous_run", run.version_id, &outcome, run.detail.clone()).await;
+    let mut runs = state.autonomous.runs.lock().await;
+    runs.push_back(run.clone());
+    while runs.len() > MAX_RUNS {
+        runs.pop_front();
+    }
+    run
+}
+
+/// Generate, compile and add a proposal; returns its version and whether it went live
+async fn propose(
+    state: &AppState,
+    policy: &AutonomousPolicy,
+    manifest: &morpheus_core::manifest::ComponentManifest,
+    prompt: &str,
+    telemetry_summary: String,
+) -> Result<(usize, bool), AppError> {
+    let mut messages = vec![
+        Message {
+            role: "user".to_string(),
+            content: PromptContext::gather(state, None).await.system_prompt(),
+        },
+        Message {
+            role: "user".to_string(),
+            content: prompt.to_string(),
+        },
+    ];
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let rust_code = extract_rust_code(&complete(state, messages.clone()).await?)?;
+        match compile_verified(state, &rust_code, &manifest.name).await {
+            Ok(result) => {
+                let rust_code = source::tidy(result.fixed_source.as_deref().unwrap_or(&rust_code)).await;
+                let mut history = state.versions.lock().await;
+                let version_id = history.add_version(
+                    format!("Autonomous: {}", truncate(&policy.objective, 40)),
+                    format!("{}\n\nTelemetry:\n{}", policy.objective, telemetry_summary),
+                    rust_code,
+                    result.wasm_bytes.clone(),
+                    result.js_glue.clone(),
+                    true, // AI generated
+                    manifest.clone(),
+                    Provenance::ai(policy.objective.clone(), state.generation.frontier_model.as_str()).with_toolchain(state.compiler.toolchain()),
+                    policy.activate,
+                );
+                history.versions[version_id].sbom = result.sbom.clone();
+                history.versions[version_id].variants = result.variants.clone();
+                history.versions[version_id].record_verification(result.verification);
+                let activated = history.current_index == version_id;
+                let provenance = history.versions[version_id].provenance.clone();
+                let artifacts = history.versions[version_id].artifacts();
+                drop(history);
+
+                if activated {
+                    register_component(state, manifest.clone(), &result.wasm_bytes, provenance, artifacts).await?;
+                }
+                return Ok((version_id, activated));
+            }
+            Err(e) => {
+                warn!(component_id = %manifest.name, attempt, "🔁 Autonomous proposal failed to compile: {}", e);
+                messages.push(Message {
+                    role: "assistant".to_string(),
+                    content: rust_code,
+                });
+                messages.push(Message {
+                    role: "user".to_string(),
+                    content: format!("That code failed to compile with this error:\n\n{}\n\nFix it.", e),
+                });
+            }
+        }
+    }
+    Err(AppError::ApiError(format!("No proposal compiled after {} attempts", MAX_ATTEMPTS)))
+}
+
+/// A run that didn't ask the AI for anything
+fn skipped(started_at: DateTime<Utc>, base_version_id: Option<usize>, events: usize, detail: &str) -> AutonomousRun {
+    AutonomousRun {
+        started_at,
+        outcome: RunOutcome::Skipped,
+        base_version_id,
+        version_id: None,
+        events,
+        detail: detail.to_string(),
+    }
+}
+
+/// One line per telemetry event
+fn summarize(events: &[TelemetryEvent]) -> String {
+    events
+        .iter()
+        .map(|e| match (e.kind, e.duration_ms) {
+            (TelemetryKind::SlowRender, Some(ms)) => format!("- slow render ({:.0} ms): {}", ms, e.message),
+            (TelemetryKind::SlowRender, None) => format!("- slow render: {}", e.message),
+            (TelemetryKind::Error, _) => format!("- runtime error: {}", e.message),
+            (TelemetryKind::Feedback, _) => format!("- user feedback: {}", e.message),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Ask for a targeted improvement of `current_code`, with its render
+/// profile when it renders slowly
+fn improvement_prompt(
+    objective: &str,
+    current_code: &str,
+    events: &[TelemetryEvent],
+    profile: Option<&RenderProfile>,
+) -> String {
+    format!(
+        "This component is live:\n\n```rust\n{}\n```\n\n\
+         Telemetry collected from it since it went live:\n{}\n\n\
+         {}\
+         Objective: {}\n\n\
+         Constraints:\n\
+         - Make the smallest change that meets the objective\n\
+         - Keep every existing feature and exported function\n\
+         - Keep the shape of the component's state so it can be restored\n\
+         - Don't add dependencies\n\n\
+         Return the complete updated component.",
+        current_code,
+        summarize(events),
+        profile
+            .map(profiler::prompt_section)
+            .filter(|section| !section.is_empty())
+            .map(|section| section + "\n")
+            .unwrap_or_default(),
+        objective
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(kind: TelemetryKind, version_id: Option<usize>) -> TelemetryReport {
+        TelemetryReport {
+            kind,
+            version_id,
+            message: "boom".to_string(),
+            duration_ms: None,
+        }
+    }
+
+    #[test]
+    fn test_pending_filters_by_version_and_consumption() {
+        let mut telemetry = Telemetry::default();
+        telemetry.record(report(TelemetryKind::Error, None), Some(1));
+        telemetry.record(report(TelemetryKind::Feedback, Some(0)), Some(1));
+        assert_eq!(telemetry.pending(1).len(), 1);
+        assert_eq!(telemetry.pending(0).len(), 1);
+
+        telemetry.consume_all();
+        telemetry.record(report(TelemetryKind::SlowRender, None), Some(1));
+        let pending = telemetry.pending(1);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].kind, TelemetryKind::SlowRender);
+        assert_eq!(telemetry.recent().len(), 3);
+    }
+
+    #[test]
+    fn test_telemetry_is_bounded() {
+        let mut telemetry = Telemetry::default();
+        for _ in 0..MAX_TELEMETRY_EVENTS + 10 {
+            telemetry.record(report(TelemetryKind::Error, None), Some(0));
+        }
+        let recent = telemetry.recent();
+        assert_eq!(recent.len(), MAX_TELEMETRY_EVENTS);
+        assert_eq!(recent[0].seq, 10);
+    }
+
+    #[test]
+    fn test_prompts_get_redacted_messages() {
+        let mut telemetry = Telemetry::default();
+        let mut error = report(TelemetryKind::Error, None);
+        error.message = "No account for ada@example.com".to_string();
+        telemetry.record(error, Some(0));
+
+        let events: Vec<_> = telemetry
+            .pending(0)
+            .into_iter()
+            .map(|e| e.redacted(&ScrubPolicy::recommended()))
+            .collect();
+        let prompt = improvement_prompt("Fix it", "fn render() {}", &events, None);
+        assert!(prompt.contains("No account for [REDACTED:email]"));
+        assert!(!prompt.contains("ada@"));
+        assert!(telemetry.recent()[0].message.contains("ada@example.com"));
+    }
+
+    #[test]
+    fn test_prompt_includes_objective_and_telemetry() {
+        let mut telemetry = Telemetry::default();
+        let mut slow = report(TelemetryKind::SlowRender, None);
+        slow.duration_ms = Some(250.0);
+        telemetry.record(slow, Some(0));
+        let prompt = improvement_prompt("Render faster", "fn render() {}", &telemetry.pending(0), None);
+        assert!(prompt.contains("Objective: Render faster"));
+        assert!(prompt.contains("slow render (250 ms): boom"));
+        assert!(prompt.contains("fn render() {}"));
+
+        let mut profiler = profiler::Profiler::new();
+        let sample = RenderSample {
+            component: "main".to_string(),
+            version: Some(0),
+            view_ms: 40.0,
+            diff_ms: 2.0,
+            patches: 12,
+        };
+        profiler.record(sample).unwrap();
+        let profile = profiler.profile("main");
+        let prompt = improvement_prompt("Render faster", "fn render() {}", &telemetry.pending(0), profile.as_ref());
+        assert!(prompt.contains("RENDER PROFILE (1 render)"));
+        assert!(prompt.contains("Building the view is the slower part"));
+    }
+
+    #[test]
+    fn test_activation_stays_off_unless_enabled() {
+        std::env::set_var("MORPHEUS_AUTONOMOUS_INTERVAL_SECS", "60");
+        let activates = |value: Option<&str>| {
+            match value {
+                Some(value) => std::env::set_var("MORPHEUS_AUTONOMOUS_ACTIVATE", value),
+                None => std::env::remove_var("MORPHEUS_AUTONOMOUS_ACTIVATE"),
+            }
+            AutonomousPolicy::from_env().map(|policy| policy.expect("a policy").activate)
+        };
+
+        for off in [None, Some("false"), Some("0"), Some("")] {
+            assert!(!activates(off).unwrap(), "{:?}", off);
+        }
+        assert!(activates(Some("true")).unwrap());
+        assert!(activates(Some("maybe")).is_err());
+        std::env::remove_var("MORPHEUS_AUTONOMOUS_ACTIVATE");
+        std::env::remove_var("MORPHEUS_AUTONOMOUS_INTERVAL_SECS");
+    }
+}
@@ -0,0 +1,475 @@
+//! Project facts for the AI's system prompt.
+//!
+//! The static system prompt describes how to write a component, not what
+//! this project actually provides, so generations kept reaching for crates,
+//! web-sys features and host functions the sandbox doesn't have. A
+//! [`PromptContext`] adds the facts to the prompt: the component's current
+//! source, the crates generated code can use (including the host app's own,
+//! with what they offer), the host types shared with components (see
+//! [`shared_types`]), the host imports the page
+//! provides, the translation keys already in use (see [`crate::i18n`]), the
+//! active theme's design tokens (see [`crate::theme`]), the
+//! components available to embed (and which of them most
+//! resemble the request, so it can reuse them), working examples for
+//! similar requests (see [`crate::fewshot`]), other components' code using
+//! names the request mentions (see [`crate::search`]) and recent runtime
+//! errors, with user data redacted by the telemetry scrub policy.
+
+use crate::fewshot::{self, Example};
+use crate::{create_system_prompt, AppState, TelemetryKind};
+use morpheus_compiler::search::LineMatch;
+use morpheus_compiler::Dependency;
+use morpheus_core::broker::Position;
+use morpheus_core::catalog::{self, CatalogEntry};
+use morpheus_core::i18n::MessageCatalog;
+use morpheus_core::shared::{self, SharedTypes};
+use morpheus_core::theme::{self, Theme};
+use morpheus_runtime::SimilarComponent;
+
+/// Runtime errors included in the context
+const MAX_RUNTIME_ERRORS: usize = 5;
+
+/// Translation keys included in the context
+const MAX_MESSAGES: usize = 50;
+
+/// A function the host page provides to components
+pub struct HostImport {
+    /// Rust declaration inside a `#[wasm_bindgen] extern "C"` block
+    pub declaration: &'static str,
+    /// When to call it
+    pub description: &'static str,
+}
+
+/// Host imports on `window.morpheus` (see `public/index.html` and
+/// `public/morpheus-worker.js`)
+pub const HOST_IMPORTS: &[HostImport] = &[
+    HostImport {
+        declaration: "#[wasm_bindgen(js_namespace = morpheus, js_name = emitEvent)]\n    fn emit_event(name: &str, payload_json: &str);",
+        description: "record a state change as a domain event, with a JSON object of the fields that changed, e.g. emit_event(\"todo_added\", r#\"{\"last_added\": \"Buy milk\"}\"#)",
+    },
+    HostImport {
+        declaration: "#[wasm_bindgen(js_namespace = morpheus, js_name = feedback)]\n    fn morpheus_feedback(rating: u8, text: &str);",
+        description: "report a user's rating (1-5) and comment from a feedback widget",
+    },
+    HostImport {
+        declaration: "#[wasm_bindgen(js_namespace = morpheus, js_name = convert)]\n    fn morpheus_convert(goal: &str);",
+        description: "count a conversion towards an A/B experiment goal, e.g. morpheus_convert(\"signup\")",
+    },
+    HostImport {
+        declaration: "#[wasm_bindgen(js_namespace = morpheus, js_name = themeToken)]\n    fn theme_token(name: &str) -> String;",
+        description: "the active theme's value of a design token, for code that can't use its CSS variable, e.g. theme_token(\"color.primary\")",
+    },
+    HostImport {
+        declaration: "#[wasm_bindgen(js_namespace = morpheus, js_name = colorScheme)]\n    fn color_scheme() -> String;",
+        description: "\"light\" or \"dark\", whichever the user's OS prefers; the host re-renders when it changes",
+    },
+];
+
+/// Host types components build against, in the `morpheus_types` crate:
+/// what `morpheus.getPosition` resolves to
+pub fn shared_types() -> SharedTypes {
+    SharedTypes::new().with::<Position>()
+}
+
+/// Facts about the project to put in front of the AI
+#[derive(Clone, Default)]
+pub struct PromptContext {
+    component: Option<String>,
+    source: Option<String>,
+    dependencies: Vec<Dependency>,
+    shared_types: SharedTypes,
+    host_imports: &'static [HostImport],
+    messages: MessageCatalog,
+    theme: Option<Theme>,
+    catalog: Vec<CatalogEntry>,
+    similar: Vec<SimilarComponent>,
+    examples: Vec<Example>,
+    usages: Vec<LineMatch>,
+    runtime_errors: Vec<String>,
+}
+
+impl PromptContext {
+    /// A context with the standard host imports and nothing else
+    pub fn new() -> Self {
+        Self {
+            host_imports: HOST_IMPORTS,
+            ..Self::default()
+        }
+    }
+
+    /// Gather the project's facts from the server state. With a component,
+    /// also include its live source and the runtime errors reported on it.
+    pub async fn gather(state: &AppState, component: Option<&str>) -> Self {
+        let mut context = Self::new()
+            .with_dependencies(state.compiler.dependencies())
+            .with_shared_types(shared_types())
+            .with_messages(crate::i18n::defaults(state).await)
+            .with_theme(state.themes.current().await.light)
+            .with_catalog(state.registry.lock().await.catalog());
+
+        let Some(component) = component else {
+            return context;
+        };
+        let current = state
+            .versions
+            .lock()
+            .await
+            .get_current()
+            .filter(|v| v.manifest.name == component)
+            .map(|v| (v.id, v.rust_code.clone()));
+        if let Some((version_id, source)) = current {
+            let errors = state
+                .telemetry
+                .lock()
+                .await
+                .recent()
+                .into_iter()
+                .filter(|e| e.kind == TelemetryKind::Error && e.version_id == Some(version_id))
+                .map(|e| e.redacted(&state.telemetry_policy).message)
+                .collect();
+            context = context.with_source(component, source).with_runtime_errors(errors);
+        }
+        context
+    }
+
+    /// The current source of `component`, to modify rather than start over
+    pub fn with_source(mut self, component: impl Into<String>, source: impl Into<String>) -> Self {
+        self.component = Some(component.into());
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Whether the context includes a component's current source
+    pub fn has_source(&self) -> bool {
+        self.source.is_some()
+    }
+
+    /// Crates generated code can use
+    pub fn with_dependencies(mut self, dependencies: Vec<Dependency>) -> Self {
+        self.dependencies = dependencies;
+        self
+    }
+
+    /// Host types in the `morpheus_types` crate
+    pub fn with_shared_types(mut self, shared_types: SharedTypes) -> Self {
+        self.shared_types = shared_types;
+        self
+    }
+
+    /// Functions the host provides
+    pub fn with_host_imports(mut self, host_imports: &'static [HostImport]) -> Self {
+        self.host_imports = host_imports;
+        self
+    }
+
+    /// Translation keys the live components use, with their English text
+    pub fn with_messages(mut self, messages: MessageCatalog) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    /// The active theme, whose tokens components style themselves with
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = Some(theme);
+        self
+    }
+
+    /// Components available to embed
+    pub fn with_catalog(mut self, catalog: Vec<CatalogEntry>) -> Self {
+        self.catalog = catalog;
+        self
+    }
+
+    /// Existing components that resemble the request, most similar first
+    pub fn with_similar(mut self, similar: Vec<SimilarComponent>) -> Self {
+        self.similar = similar;
+        self
+    }
+
+    /// Known-good generations for similar requests
+    pub fn with_examples(mut self, examples: Vec<Example>) -> Self {
+        self.examples = examples;
+        self
+    }
+
+    /// Lines of other components using names the request mentions (see
+    /// [`crate::search::usages`])
+    pub fn with_usages(mut self, usages: Vec<LineMatch>) -> Self {
+        self.usages = usages;
+        self
+    }
+
+    /// Runtime errors, oldest first; only the most recent are kept
+    pub fn with_runtime_errors(mut self, mut errors: Vec<String>) -> Self {
+        errors.drain(..errors.len().saturating_sub(MAX_RUNTIME_ERRORS));
+        self.runtime_errors = errors;
+        self
+    }
+
+    /// The context as prompt sections
+    pub fn render(&self) -> String {
+        let mut sections = Vec::new();
+
+        if let (Some(component), Some(source)) = (&self.component, &self.source) {
+            sections.push(format!(
+                "CURRENT SOURCE of the '{}' component (modify it rather than starting over):\n```rust\n{}\n```",
+                component,
+                source.trim_end()
+            ));
+        }
+
+        if !self.dependencies.is_empty() {
+            let mut section =
+                String::from("AVAILABLE CRATES (no others can be used; web-sys APIs only for the features listed):\n");
+            for dependency in &self.dependencies {
+                section.push_str(&format!("- {}", dependency.name));
+                if dependency.version != "*" {
+                    section.push_str(&format!(" {}", dependency.version));
+                }
+                if !dependency.features.is_empty() {
+                    section.push_str(&format!(" (features: {})", dependency.features.join(", ")));
+                }
+                if let Some(description) = &dependency.description {
+                    section.push_str(&format!(": {} (the app's own crate; use its types)", description));
+                }
+                section.push('\n');
+            }
+            sections.push(section.trim_end().to_string());
+        }
+
+        if !self.shared_types.is_empty() {
+            sections.push(format!(
+                "SHARED TYPES (version {} of the {} crate; use these instead of declaring your own):\n```rust\n{}\n```",
+                self.shared_types.version(),
+                shared::CRATE_NAME,
+                self.shared_types.source().trim_end()
+            ));
+        }
+
+        if !self.host_imports.is_empty() {
+            let mut section = String::from(
+                "HOST IMPORTS (optional; the only functions the host provides, declare the ones you use):\n\n#[wasm_bindgen]\nextern \"C\" {\n",
+            );
+            for import in self.host_imports {
+                section.push_str(&format!("    {}\n", import.declaration));
+            }
+            section.push_str("}\n");
+            for import in self.host_imports {
+                let name = import.declaration.split("fn ").nth(1).and_then(|rest| rest.split('(').next());
+                section.push_str(&format!("- {}: {}\n", name.unwrap_or_default(), import.description));
+            }
+            sections.push(section.trim_end().to_string());
+        }
+
+        if !self.messages.messages.is_empty() {
+            let mut section = String::from(
+                "TRANSLATION KEYS already in use (reuse a key for the same text, so its translations apply):\n",
+            );
+            for (key, text) in self.messages.messages.iter().take(MAX_MESSAGES) {
+                section.push_str(&format!("- {}: {:?}\n", key, text));
+            }
+            sections.push(section.trim_end().to_string());
+        }
+
+        if let Some(active) = &self.theme {
+            let mut section = format!(
+                "THEME TOKENS, as CSS variables (values of the '{}' theme; every theme sets the same ones):\n",
+                active.name
+            );
+            for (name, value) in active.tokens() {
+                section.push_str(&format!("- var({}): {}\n", theme::css_variable(&name), value));
+            }
+            sections.push(section.trim_end().to_string());
+        }
+
+        let catalog = catalog::prompt_section(&self.catalog);
+        if !catalog.is_empty() {
+            sections.push(catalog.trim_end().to_string());
+        }
+
+        if !self.similar.is_empty() {
+            let mut section = String::from(
+                "SIMILAR EXISTING COMPONENTS (if one already does part of what is asked, embed it in a slot instead of regenerating it):\n",
+            );
+            for component in &self.similar {
+                section.push_str(&format!("- {}", component.name));
+                if !component.capabilities.is_empty() {
+                    let names: Vec<_> = component.capabilities.iter().map(|c| c.name()).collect();
+                    section.push_str(&format!(" [{}]", names.join(", ")));
+                }
+                if !component.description.is_empty() {
+                    section.push_str(&format!(": {}", component.description));
+                }
+                section.push('\n');
+            }
+            sections.push(section.trim_end().to_string());
+        }
+
+        let examples = fewshot::prompt_section(&self.examples);
+        if !examples.is_empty() {
+            sections.push(examples);
+        }
+
+        if !self.usages.is_empty() {
+            let mut section = String::from(
+                "USAGES in other components of names the request mentions (keep your change consistent with them):\n",
+            );
+            for usage in &self.usages {
+                section.push_str(&format!("- {} line {}: {}\n", usage.component, usage.line, usage.text));
+            }
+            sections.push(section.trim_end().to_string());
+        }
+
+        if !self.runtime_errors.is_empty() {
+            let mut section =
+                String::from("RECENT RUNTIME ERRORS in the current version (make sure your code avoids them):\n");
+            for error in &self.runtime_errors {
+                section.push_str(&format!("- {}\n", error));
+            }
+            sections.push(section.trim_end().to_string());
+        }
+
+        sections.join("\n\n")
+    }
+
+    /// The system prompt with the context appended
+    pub fn system_prompt(&self) -> String {
+        let context = self.render();
+        if context.is_empty() {
+            create_system_prompt()
+        } else {
+            format!("{}\n\n{}", create_system_prompt(), context)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_context_lists_host_imports_only() {
+        let context = PromptContext::new().render();
+
+        assert!(context.starts_with("HOST IMPORTS"));
+        assert!(context.contains("js_name = emitEvent"));
+        assert!(context.contains("- morpheus_convert: count a conversion"));
+        assert!(!context.contains("AVAILABLE CRATES"));
+        assert!(!context.contains("CURRENT SOURCE"));
+    }
+
+    #[test]
+    fn test_full_context() {
+        let dependency = Dependency {
+            name: "web-sys".to_string(),
+            version: "0.3".to_string(),
+            features: vec!["Window".to_string(), "Document".to_string()],
+            description: None,
+        };
+        let host_crate = Dependency {
+            name: "shop-types".to_string(),
+            version: "*".to_string(),
+            features: Vec::new(),
+            description: Some("Order and Customer as the shop's API returns them".to_string()),
+        };
+        let context = PromptContext::new()
+            .with_host_imports(&[])
+            .with_source("main", "pub fn render() -> String { String::new() }\n")
+            .with_dependencies(vec![dependency, host_crate])
+            .with_runtime_errors(vec!["unreachable executed".to_string()])
+            .render();
+
+        assert_eq!(
+            context,
+            "CURRENT SOURCE of the 'main' component (modify it rather than starting over):\n\
+             ```rust\npub fn render() -> String { String::new() }\n```\n\n\
+             AVAILABLE CRATES (no others can be used; web-sys APIs only for the features listed):\n\
+             - web-sys 0.3 (features: Window, Document)\n\
+             - shop-types: Order and Customer as the shop's API returns them (the app's own crate; use its types)\n\n\
+             RECENT RUNTIME ERRORS in the current version (make sure your code avoids them):\n\
+             - unreachable executed"
+        );
+    }
+
+    #[test]
+    fn test_similar_components() {
+        let similar = SimilarComponent {
+            id: morpheus_core::component::ComponentId(1),
+            name: "user-table".to_string(),
+            description: "Table of users".to_string(),
+            capabilities: vec![morpheus_core::capability::Capability::DataDisplay],
+            score: 0.6,
+        };
+        let context = PromptContext::new().with_host_imports(&[]).with_similar(vec![similar]).render();
+
+        assert!(context.starts_with("SIMILAR EXISTING COMPONENTS"));
+        assert!(context.ends_with("\n- user-table [data-display]: Table of users"));
+    }
+
+    #[test]
+    fn test_shared_types_are_listed_with_their_version() {
+        let types = shared_types();
+        let context = PromptContext::new().with_host_imports(&[]).with_shared_types(types.clone()).render();
+
+        assert!(context.starts_with(&format!("SHARED TYPES (version {} of the morpheus_types crate", types.version())));
+        assert!(context.contains("pub struct Position {\n    pub latitude: f64,"));
+    }
+
+    #[test]
+    fn test_usages_name_their_component_and_line() {
+        let usage = LineMatch {
+            version_id: 3,
+            component: "header".to_string(),
+            line: 12,
+            column: 20,
+            text: "class=\"bg-slate-900\"".to_string(),
+        };
+        let context = PromptContext::new().with_host_imports(&[]).with_usages(vec![usage]).render();
+        assert!(context.starts_with("USAGES in other components"));
+        assert!(context.ends_with("\n- header line 12: class=\"bg-slate-900\""));
+    }
+
+    #[test]
+    fn test_translation_keys_in_use_are_listed() {
+        let messages = MessageCatalog::new("en").with("todo.add", "Add").with("todo.remaining", "{count} left");
+        let context = PromptContext::new().with_host_imports(&[]).with_messages(messages).render();
+
+        assert_eq!(
+            context,
+            "TRANSLATION KEYS already in use (reuse a key for the same text, so its translations apply):\n\
+             - todo.add: \"Add\"\n\
+             - todo.remaining: \"{count} left\""
+        );
+    }
+
+    #[test]
+    fn test_theme_tokens_are_listed_as_css_variables() {
+        let theme = Theme::new("dark").with_color("primary", "#818cf8").with_spacing("md", "1rem");
+        let context = PromptContext::new().with_host_imports(&[]).with_theme(theme).render();
+
+        assert_eq!(
+            context,
+            "THEME TOKENS, as CSS variables (values of the 'dark' theme; every theme sets the same ones):\n\
+             - var(--morpheus-color-primary): #818cf8\n\
+             - var(--morpheus-spacing-md): 1rem"
+        );
+    }
+
+    #[test]
+    fn test_only_recent_runtime_errors_are_kept() {
+        let errors = (0..8).map(|i| format!("error {}", i)).collect();
+        let context = PromptContext::new().with_runtime_errors(errors).render();
+
+        assert!(!context.contains("error 2"));
+        assert!(context.contains("error 3"));
+        assert!(context.contains("error 7"));
+    }
+
+    #[test]
+    fn test_system_prompt_appends_context() {
+        let prompt = PromptContext::new().system_prompt();
+
+        assert!(prompt.starts_with(&create_system_prompt()));
+        assert!(prompt.ends_with(PromptContext::new().render().as_str()));
+    }
+}
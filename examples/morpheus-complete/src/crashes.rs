@@ -0,0 +1,259 @@
+//! Component crash reports.
+//!
+//! Components that install `morpheus_core::crash::install_panic_hook` send
+//! the panic message, its location and a final state snapshot to
+//! `POST /api/crash` before their instance dies. The state is scrubbed by
+//! the state scrub policy on arrival; the report, state included, is kept
+//! for `GET /api/crashes` and handed to autonomous mode as a runtime
+//! error, with the state scrubbed by the telemetry scrub policy first. If
+//! the crashed version is live, the server rolls back to the component's
+//! last good version and restores the reported state rather than that
+//! version's older snapshot, so users keep their data.
+
+use crate::autonomous::{TelemetryKind, TelemetryReport};
+use crate::{
+    apply_state_update, record_audit, screen_request, scrub_incoming, truncate, AppError, AppState, VersionHistory,
+};
+use axum::extract::State;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use morpheus_core::crash::CrashReport;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use tracing::{error, warn};
+
+/// Crash reports kept in memory
+const MAX_CRASHES: usize = 100;
+
+/// Most characters of the crash state passed on as telemetry
+const TELEMETRY_STATE_CHARS: usize = 500;
+
+/// A recorded crash
+#[derive(Clone, Serialize, JsonSchema)]
+pub struct Crash {
+    pub id: u64,
+    #[serde(flatten)]
+    pub report: CrashReport,
+    /// Version that crashed (the reported one, or the live one)
+    pub version_id: Option<usize>,
+    pub component: Option<String>,
+    pub received_at: DateTime<Utc>,
+    /// Version the server rolled back to
+    pub rolled_back_to: Option<usize>,
+}
+
+/// What the client should load after a crash
+#[derive(Serialize, JsonSchema)]
+pub struct CrashResponse {
+    pub crash_id: u64,
+    /// Version now live, when the server rolled back
+    pub rolled_back_to: Option<usize>,
+    pub wasm_base64: Option<String>,
+    pub js_glue: Option<String>,
+    /// State to restore: the crashed component's snapshot when it was kept
+    pub state: Option<Value>,
+}
+
+/// Recent crashes, oldest first
+#[derive(Default)]
+pub struct Crashes {
+    recent: VecDeque<Crash>,
+    next_id: u64,
+}
+
+impl Crashes {
+    /// Keep a crash, dropping the oldest when full; returns its id
+    fn record(&mut self, mut crash: Crash) -> u64 {
+        crash.id = self.next_id;
+        self.next_id += 1;
+        if self.recent.len() == MAX_CRASHES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(crash);
+        self.next_id - 1
+    }
+
+    /// Crashes, newest first
+    pub fn recent(&self) -> Vec<Crash> {
+        self.recent.iter().rev().cloned().collect()
+    }
+}
+
+/// The version of the same component that was live last before `version_id`
+fn last_good_version(history: &VersionHistory, version_id: usize) -> Option<usize> {
+    let component = &history.versions.get(version_id)?.manifest.name;
+    history.versions[..version_id]
+        .iter()
+        .rev()
+        .find(|v| v.manifest.name == *component && v.activated_at.is_some() && !history.is_reverted(v.id))
+        .map(|v| v.id)
+}
+
+/// Record a crash and, if the live version crashed, roll back to the last
+/// good version with the crashed component's state
+pub async fn report_crash(
+    State(state): State<AppState>,
+    Json(mut report): Json<CrashReport>,
+) -> Result<Json<CrashResponse>, AppError> {
+    // The message goes to the AI in autonomous runs
+    screen_request(&state, "crash", &report.message).await?;
+    // Kept, restored and listed below, so scrubbed like any state update
+    if let Some(crash_state) = report.state.as_mut() {
+        scrub_incoming(&state, "crash state", crash_state);
+    }
+
+    let mut history = state.versions.lock().await;
+    let current = history.get_current().map(|v| v.id);
+    let version_id = report.version.and_then(|v| usize::try_from(v).ok()).or(current);
+    let component = version_id.and_then(|id| history.versions.get(id)).map(|v| v.manifest.name.clone());
+    let target = version_id.filter(|id| Some(*id) == current).and_then(|id| last_good_version(&history, id));
+    let mut response = CrashResponse {
+        crash_id: 0,
+        rolled_back_to: None,
+        wasm_base64: None,
+        js_glue: None,
+        state: None,
+    };
+    if let Some(version) = target.and_then(|target| history.rollback_to(target)) {
+        response.rolled_back_to = Some(version.id);
+        response.wasm_base64 = Some(version.wasm_base64.clone());
+        response.js_glue = Some(version.js_glue.clone());
+        response.state = version.state_snapshot.clone();
+    }
+    drop(history);
+
+    let location = report.location.as_deref().map(|l| format!(" at {}", l)).unwrap_or_default();
+    error!(
+        version = ?version_id,
+        component_id = component.as_deref().unwrap_or("-"),
+        "💥 Component panicked{}: {}",
+        location,
+        report.message
+    );
+    // Keep the user's data: the crash snapshot replaces the restored one
+    if let (Some(_), Some(crash_state)) = (response.rolled_back_to, &report.state) {
+        match apply_state_update(&state, crash_state.clone()).await {
+            Ok(_) => response.state = Some(crash_state.clone()),
+            Err(e) => warn!("Kept the restored version's state; the crash state was rejected: {}", e),
+        }
+    } else if response.rolled_back_to.is_some() {
+        let _ = state.state_sync.send(());
+    }
+
+    // Field rules can't be applied once the state is text
+    let state_summary = report
+        .state
+        .clone()
+        .map(|mut s| {
+            state.telemetry_policy.scrub(&mut s);
+            format!(" (state: {})", truncate(&s.to_string(), TELEMETRY_STATE_CHARS))
+        })
+        .unwrap_or_default();
+    state.telemetry.lock().await.record(
+        TelemetryReport {
+            kind: TelemetryKind::Error,
+            version_id,
+            message: format!("Panicked{}: {}{}", location, report.message, state_summary),
+            duration_ms: None,
+        },
+        current,
+    );
+    let (outcome, detail) = match response.rolled_back_to {
+        Some(previous) => ("rolled_back", format!("Panicked{}, restored version {}: {}", location, previous, report.message)),
+        None => ("failed", format!("Panicked{}: {}", location, report.message)),
+    };
+    record_audit(&state, "crash", version_id, outcome, detail).await;
+
+    response.crash_id = state.crashes.lock().await.record(Crash {
+        id: 0,
+        report,
+        version_id,
+        component,
+        received_at: Utc::now(),
+        rolled_back_to: response.rolled_back_to,
+    });
+    Ok(Json(response))
+}
+
+/// Recent crash reports, newest first, with the state each component held
+pub async fn list_crashes(State(state): State<AppState>) -> Json<Vec<Crash>> {
+    Json(state.crashes.lock().await.recent())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morpheus_core::component::Provenance;
+    use morpheus_core::manifest::ComponentManifest;
+    use morpheus_core::privacy::ScrubPolicy;
+    use std::sync::Arc;
+
+    fn add(history: &mut VersionHistory, component: &str) -> usize {
+        history.add_version(
+            component.to_string(),
+            component.to_string(),
+            "pub fn render() -> String { String::new() }".to_string(),
+            vec![0; 8],
+            String::new(),
+            true,
+            ComponentManifest {
+                name: component.to_string(),
+                ..Default::default()
+            },
+            Provenance::ai(component, "test-model"),
+            true,
+        )
+    }
+
+    #[test]
+    fn test_crashes_roll_back_to_the_last_live_version_of_the_component() {
+        let mut history = VersionHistory::new();
+        let good = add(&mut history, "chart");
+        add(&mut history, "table");
+        history.require_review = true;
+        add(&mut history, "chart");
+        history.require_review = false;
+        let crashed = add(&mut history, "chart");
+
+        assert_eq!(last_good_version(&history, crashed), Some(good));
+        assert_eq!(last_good_version(&history, good), None);
+
+        let mut crashes = Crashes::default();
+        for _ in 0..MAX_CRASHES + 1 {
+            crashes.record(Crash {
+                id: 0,
+                report: CrashReport::new("boom"),
+                version_id: Some(crashed),
+                component: Some("chart".to_string()),
+                received_at: Utc::now(),
+                rolled_back_to: Some(good),
+            });
+        }
+        let recent = crashes.recent();
+        assert_eq!(recent.len(), MAX_CRASHES);
+        assert_eq!((recent[0].id, recent[MAX_CRASHES - 1].id), (MAX_CRASHES as u64, 1));
+    }
+
+    #[tokio::test]
+    async fn test_crash_state_is_scrubbed_rather_than_dropped() {
+        let mut state = AppState::for_tests().await;
+        state.scrub_policy = Arc::new(ScrubPolicy::recommended());
+        let crashed = {
+            let mut history = state.versions.lock().await;
+            add(&mut history, "chart");
+            add(&mut history, "chart")
+        };
+        let report = CrashReport::new("boom")
+            .with_state(r#"{"count": 3, "password": "hunter2"}"#)
+            .with_version(crashed as u32);
+
+        let Json(response) = report_crash(State(state.clone()), Json(report)).await.unwrap();
+
+        let kept = serde_json::json!({ "count": 3 });
+        assert_eq!(response.state, Some(kept.clone()));
+        assert_eq!(state.versions.lock().await.current_state, Some(kept.clone()));
+        assert_eq!(state.crashes.lock().await.recent()[0].report.state, Some(kept));
+    }
+}
@@ -0,0 +1,344 @@
+//! A/B experiments: two live versions of a component, split by session.
+//!
+//! `POST /api/experiments` puts a treatment version live next to the
+//! control (the live version by default). `GET /api/components/{name}/render`
+//! with a `session` query parameter then serves each session its variant,
+//! conversions come in through `POST /api/experiments/{component}/conversion`
+//! and ratings through the feedback API. Declaring a winner makes its version
+//! the baseline for everyone.
+//!
+//! Operator feature flags still win: a disabled or pinned component ignores
+//! its experiment.
+
+use crate::{base64_decode, record_audit, register_component, AppError, AppState};
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use morpheus_core::experiment::{Experiment, ExperimentStatus, Variant, VariantStats};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use tracing::info;
+
+/// Experiments by component name
+pub type Experiments = BTreeMap<String, ExperimentEntry>;
+
+/// An experiment and the sessions it has been shown to
+pub struct ExperimentEntry {
+    experiment: Experiment,
+    started_at: DateTime<Utc>,
+    concluded_at: Option<DateTime<Utc>>,
+    /// Sessions counted as exposures, so reloads don't inflate them
+    sessions: HashSet<String>,
+}
+
+impl ExperimentEntry {
+    fn summary(&self, component: &str) -> ExperimentSummary {
+        let variant = |variant| {
+            let stats: &VariantStats = self.experiment.stats(variant);
+            VariantSummary {
+                version_id: self.experiment.version(variant) as usize,
+                exposures: stats.exposures,
+                conversions: stats.conversions,
+                conversion_rate: stats.conversion_rate(),
+                ratings: stats.ratings,
+                average_rating: stats.average_rating(),
+            }
+        };
+        ExperimentSummary {
+            component: component.to_string(),
+            status: self.experiment.status,
+            treatment_share: self.experiment.treatment_share,
+            control: variant(Variant::Control),
+            treatment: variant(Variant::Treatment),
+            started_at: self.started_at,
+            concluded_at: self.concluded_at,
+        }
+    }
+}
+
+/// Request to start an experiment
+#[derive(Deserialize, JsonSchema)]
+pub struct StartExperimentRequest {
+    /// Component name (defaults to "main")
+    #[serde(default)]
+    pub component: Option<String>,
+    /// Baseline version (defaults to the live version)
+    #[serde(default)]
+    pub control_version_id: Option<usize>,
+    pub treatment_version_id: usize,
+    /// Fraction of sessions shown the treatment (defaults to 0.5)
+    #[serde(default)]
+    pub treatment_share: Option<f64>,
+}
+
+/// An experiment and its metrics
+#[derive(Serialize, JsonSchema)]
+pub struct ExperimentSummary {
+    pub component: String,
+    pub status: ExperimentStatus,
+    pub treatment_share: f64,
+    pub control: VariantSummary,
+    pub treatment: VariantSummary,
+    pub started_at: DateTime<Utc>,
+    pub concluded_at: Option<DateTime<Utc>>,
+}
+
+/// Metrics for one variant
+#[derive(Serialize, JsonSchema)]
+pub struct VariantSummary {
+    pub version_id: usize,
+    pub exposures: u64,
+    pub conversions: u64,
+    pub conversion_rate: Option<f64>,
+    pub ratings: u64,
+    pub average_rating: Option<f64>,
+}
+
+/// A conversion by a session
+#[derive(Deserialize, JsonSchema)]
+pub struct ConversionRequest {
+    pub session: String,
+    /// What the session achieved, e.g. "signed_up" (for the logs)
+    #[serde(default)]
+    pub goal: Option<String>,
+}
+
+/// The variant a conversion was counted for
+#[derive(Serialize, JsonSchema)]
+pub struct ConversionResponse {
+    pub variant: Variant,
+    pub version_id: usize,
+}
+
+/// Request to end an experiment
+#[derive(Deserialize, JsonSchema)]
+pub struct WinnerRequest {
+    pub winner: Variant,
+}
+
+/// Pick the version a session sees, counting its first exposure
+///
+/// `None` unless the component has a running experiment.
+pub fn choose(experiments: &mut Experiments, component: &str, session: &str) -> Option<(Variant, usize)> {
+    let entry = experiments.get_mut(component).filter(|e| e.experiment.is_running())?;
+    let variant = entry.experiment.assign(session);
+    if entry.sessions.insert(session.to_string()) {
+        entry.experiment.record_exposure(variant);
+    }
+    Some((variant, entry.experiment.version(variant) as usize))
+}
+
+/// Count a rating against the variant showing `version_id`
+pub fn record_rating(experiments: &mut Experiments, version_id: usize, rating: u8) {
+    for entry in experiments.values_mut().filter(|e| e.experiment.is_running()) {
+        if let Some(variant) = entry.experiment.variant_of(version_id as u32) {
+            entry.experiment.record_rating(variant, rating);
+        }
+    }
+}
+
+/// Start splitting a component's traffic between two versions
+pub async fn start_experiment(
+    State(state): State<AppState>,
+    Json(req): Json<StartExperimentRequest>,
+) -> Result<Json<ExperimentSummary>, AppError> {
+    let component = req.component.unwrap_or_else(|| "main".to_string());
+    let history = state.versions.lock().await;
+    let control = match req.control_version_id {
+        Some(id) => id,
+        None => history
+            .get_current()
+            .map(|v| v.id)
+            .ok_or_else(|| AppError::ApiError("No live version to use as control".to_string()))?,
+    };
+    for id in [control, req.treatment_version_id] {
+        let version = history
+            .versions
+            .get(id)
+            .ok_or_else(|| AppError::ApiError(format!("Version {} not found", id)))?;
+        if version.manifest.name != component {
+            return Err(AppError::ApiError(format!(
+                "Version {} is a version of '{}', not '{}'",
+                id, version.manifest.name, component
+            )));
+        }
+    }
+    // The treatment goes live for real users, so it must be allowed to
+    history
+        .check_activation(req.treatment_version_id)
+        .map_err(AppError::ApiError)?;
+    drop(history);
+
+    let mut experiment = Experiment::new(control as u32, req.treatment_version_id as u32)?;
+    if let Some(share) = req.treatment_share {
+        experiment = experiment.with_treatment_share(share)?;
+    }
+
+    let mut experiments = state.experiments.lock().await;
+    if experiments.get(&component).is_some_and(|e| e.experiment.is_running()) {
+        return Err(AppError::ApiError(format!(
+            "An experiment on '{}' is already running",
+            component
+        )));
+    }
+    let entry = ExperimentEntry {
+        experiment,
+        started_at: Utc::now(),
+        concluded_at: None,
+        sessions: HashSet::new(),
+    };
+    let summary = entry.summary(&component);
+    experiments.insert(component.clone(), entry);
+    drop(experiments);
+
+    info!(
+        component_id = %component,
+        "🧪 Experiment started: version {} vs {} ({:.0}% treatment)",
+        control,
+        req.treatment_version_id,
+        summary.treatment_share * 100.0
+    );
+    record_audit(
+        &state,
+        "experiment_start",
+        Some(req.treatment_version_id),
+        "running",
+        format!("{}: control version {}", component, control),
+    )
+    .await;
+    Ok(Json(summary))
+}
+
+/// Experiments and their metrics
+pub async fn list_experiments(State(state): State<AppState>) -> Result<Json<Vec<ExperimentSummary>>, AppError> {
+    let experiments = state.experiments.lock().await;
+    Ok(Json(experiments.iter().map(|(name, e)| e.summary(name)).collect()))
+}
+
+/// Count a conversion for the variant a session was shown
+pub async fn record_conversion(
+    State(state): State<AppState>,
+    Path(component): Path<String>,
+    Json(req): Json<ConversionRequest>,
+) -> Result<Json<ConversionResponse>, AppError> {
+    let mut experiments = state.experiments.lock().await;
+    let entry = experiments
+        .get_mut(&component)
+        .filter(|e| e.experiment.is_running())
+        .ok_or_else(|| AppError::ApiError(format!("No experiment running on '{}'", component)))?;
+    if !entry.sessions.contains(&req.session) {
+        return Err(AppError::ApiError(format!(
+            "Session '{}' hasn't been shown the experiment",
+            req.session
+        )));
+    }
+    let variant = entry.experiment.assign(&req.session);
+    entry.experiment.record_conversion(variant);
+    let version_id = entry.experiment.version(variant) as usize;
+    drop(experiments);
+
+    info!(
+        component_id = %component,
+        version = version_id,
+        "🧪 Conversion ({:?}){}",
+        variant,
+        req.goal.map(|goal| format!(": {}", goal)).unwrap_or_default()
+    );
+    Ok(Json(ConversionResponse { variant, version_id }))
+}
+
+/// End an experiment, making the winner's version the baseline
+pub async fn declare_winner(
+    State(state): State<AppState>,
+    Path(component): Path<String>,
+    Json(req): Json<WinnerRequest>,
+) -> Result<Json<ExperimentSummary>, AppError> {
+    let mut experiments = state.experiments.lock().await;
+    let entry = experiments
+        .get_mut(&component)
+        .ok_or_else(|| AppError::ApiError(format!("No experiment on '{}'", component)))?;
+    let winner = entry.experiment.version(req.winner) as usize;
+
+    let mut history = state.versions.lock().await;
+    history.check_activation(winner).map_err(AppError::ApiError)?;
+    entry.experiment.conclude(req.winner)?;
+    entry.concluded_at = Some(Utc::now());
+    let summary = entry.summary(&component);
+    drop(experiments);
+
+    let previous = history.get_current().map(|v| v.id);
+    let version = history.activate(winner).cloned();
+    drop(history);
+    if let Some(version) = version.filter(|_| previous != Some(winner)) {
+        let wasm_bytes = base64_decode(&version.wasm_base64)?;
+        register_component(
+            &state,
+            version.manifest.clone(),
+            &wasm_bytes,
+            version.provenance.clone(),
+            version.artifacts(),
+        )
+        .await?;
+    }
+
+    info!(component_id = %component, version = winner, "🧪 Experiment concluded: version {} wins", winner);
+    record_audit(
+        &state,
+        "experiment_winner",
+        Some(winner),
+        "concluded",
+        format!(
+            "{}: {:?} won (control {:?}, treatment {:?} conversion)",
+            component, req.winner, summary.control.conversion_rate, summary.treatment.conversion_rate
+        ),
+    )
+    .await;
+    Ok(Json(summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(experiment: Experiment) -> ExperimentEntry {
+        ExperimentEntry {
+            experiment,
+            started_at: Utc::now(),
+            concluded_at: None,
+            sessions: HashSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_choose_counts_each_session_once() {
+        let mut experiments = Experiments::new();
+        assert!(choose(&mut experiments, "main", "s1").is_none());
+
+        let experiment = Experiment::new(1, 2).unwrap().with_treatment_share(1.0).unwrap();
+        experiments.insert("main".to_string(), entry(experiment));
+        assert_eq!(choose(&mut experiments, "main", "s1"), Some((Variant::Treatment, 2)));
+        assert_eq!(choose(&mut experiments, "main", "s1"), Some((Variant::Treatment, 2)));
+        choose(&mut experiments, "main", "s2");
+
+        let summary = experiments["main"].summary("main");
+        assert_eq!(summary.treatment.exposures, 2);
+        assert_eq!(summary.control.exposures, 0);
+    }
+
+    #[test]
+    fn test_ratings_count_only_for_running_experiments() {
+        let mut experiments = Experiments::new();
+        experiments.insert("main".to_string(), entry(Experiment::new(1, 2).unwrap()));
+        let mut done = Experiment::new(3, 4).unwrap();
+        done.conclude(Variant::Control).unwrap();
+        experiments.insert("other".to_string(), entry(done));
+
+        record_rating(&mut experiments, 1, 4);
+        record_rating(&mut experiments, 3, 5);
+        assert_eq!(experiments["main"].summary("main").control.average_rating, Some(4.0));
+        assert_eq!(experiments["other"].summary("other").control.ratings, 0);
+    }
+}
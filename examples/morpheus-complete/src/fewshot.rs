@@ -0,0 +1,355 @@
+//! Few-shot examples retrieved from past successful generations.
+//!
+//! Every generation that compiles is stored as a known-good (prompt →
+//! source) pair with an embedding of its prompt. New requests are embedded
+//! too, and the most similar stored examples go into the prompt, so the AI
+//! starts from code that is known to build in this sandbox instead of
+//! rediscovering the same patterns (and the same compile errors) each time.
+//!
+//! Prompts are embedded locally by default (see
+//! [`morpheus_core::embedding`]). Set `MORPHEUS_EMBEDDINGS_URL` to use an
+//! OpenAI-compatible embeddings API instead; it's requested with the model
+//! gateway's client certificate, and must be on the gateway when external
+//! providers are blocked (see [`crate::gateway`]). `GET /api/examples`
+//! compares first-try compile success with and without examples.
+
+use crate::gateway::ModelEndpoint;
+use crate::AppState;
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use morpheus_core::embedding::{self, similarity};
+use morpheus_core::store::SnapshotStore;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Store key of the examples
+pub const EXAMPLES_KEY: &str = "examples.json";
+
+/// Examples kept; the oldest are dropped first
+const MAX_EXAMPLES: usize = 200;
+
+/// Examples retrieved per request unless `MORPHEUS_FEW_SHOT_K` is set
+const DEFAULT_TOP_K: usize = 2;
+
+/// Examples less similar than this are not worth their tokens
+const MIN_SIMILARITY: f32 = 0.2;
+
+/// Model used with an embeddings API unless `MORPHEUS_EMBEDDINGS_MODEL` is set
+const DEFAULT_EMBEDDINGS_MODEL: &str = "text-embedding-3-small";
+
+/// A known-good generation
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Example {
+    pub prompt: String,
+    pub source: String,
+    pub version_id: Option<usize>,
+    pub created_at: DateTime<Utc>,
+    /// Embedder that produced `embedding`
+    embedder: String,
+    embedding: Vec<f32>,
+}
+
+/// Turns prompts into vectors
+#[derive(Clone, Debug)]
+pub enum Embedder {
+    /// Hashed words and word pairs; no network, no model
+    Local,
+    /// An OpenAI-compatible `/embeddings` endpoint
+    Api {
+        url: String,
+        model: String,
+        api_key: String,
+        client: reqwest::Client,
+    },
+}
+
+impl Embedder {
+    /// `Api` if `MORPHEUS_EMBEDDINGS_URL` is set, otherwise `Local`; an
+    /// API `endpoint` doesn't allow is an error
+    pub fn from_env(endpoint: &ModelEndpoint) -> anyhow::Result<Self> {
+        match std::env::var("MORPHEUS_EMBEDDINGS_URL") {
+            Ok(url) if !url.is_empty() => {
+                endpoint.check_url(&url)?;
+                Ok(Embedder::Api {
+                    url,
+                    model: std::env::var("MORPHEUS_EMBEDDINGS_MODEL")
+                        .unwrap_or_else(|_| DEFAULT_EMBEDDINGS_MODEL.to_string()),
+                    api_key: std::env::var("MORPHEUS_EMBEDDINGS_API_KEY").unwrap_or_default(),
+                    client: endpoint.client(),
+                })
+            }
+            _ => Ok(Embedder::Local),
+        }
+    }
+
+    /// Identifies the embedding space; vectors from different embedders don't compare
+    pub fn name(&self) -> String {
+        match self {
+            Embedder::Local => format!("local-{}", embedding::DIMENSIONS),
+            Embedder::Api { model, .. } => model.clone(),
+        }
+    }
+
+    pub async fn embed(&self, text: &str) -> anyhow::Result<Vec<f32>> {
+        match self {
+            Embedder::Local => Ok(embedding::embed(text)),
+            Embedder::Api {
+                url,
+                model,
+                api_key,
+                client,
+            } => {
+                #[derive(Deserialize)]
+                struct Response {
+                    data: Vec<Embedding>,
+                }
+                #[derive(Deserialize)]
+                struct Embedding {
+                    embedding: Vec<f32>,
+                }
+
+                let response: Response = client
+                    .post(url)
+                    .bearer_auth(api_key)
+                    .json(&serde_json::json!({ "model": model, "input": text }))
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                response
+                    .data
+                    .into_iter()
+                    .next()
+                    .map(|e| e.embedding)
+                    .ok_or_else(|| anyhow::anyhow!("Embeddings API returned no embedding"))
+            }
+        }
+    }
+}
+
+/// Compile outcomes of generations, with or without examples
+#[derive(Clone, Copy, Debug, Default, Serialize, JsonSchema)]
+pub struct OutcomeStats {
+    pub generations: u64,
+    /// Generations whose first attempt compiled
+    pub first_try: u64,
+    /// Generations that never compiled
+    pub failed: u64,
+    /// Compile attempts across all generations
+    pub attempts: u64,
+}
+
+impl OutcomeStats {
+    fn record(&mut self, iterations: u32, success: bool) {
+        self.generations += 1;
+        self.attempts += iterations as u64;
+        if success && iterations == 1 {
+            self.first_try += 1;
+        }
+        if !success {
+            self.failed += 1;
+        }
+    }
+}
+
+/// Stored examples and how generations fared with them
+#[derive(Serialize, JsonSchema)]
+pub struct ExamplesStatus {
+    pub examples: usize,
+    pub embedder: String,
+    /// Examples retrieved per request; 0 disables retrieval
+    pub top_k: usize,
+    pub with_examples: OutcomeStats,
+    pub without_examples: OutcomeStats,
+}
+
+/// Known-good examples, retrieved by prompt similarity
+pub struct ExampleStore {
+    examples: Mutex<Vec<Example>>,
+    embedder: Embedder,
+    top_k: usize,
+    with_examples: Mutex<OutcomeStats>,
+    without_examples: Mutex<OutcomeStats>,
+}
+
+impl ExampleStore {
+    pub fn new(embedder: Embedder, top_k: usize) -> Self {
+        Self {
+            examples: Mutex::new(Vec::new()),
+            embedder,
+            top_k,
+            with_examples: Mutex::new(OutcomeStats::default()),
+            without_examples: Mutex::new(OutcomeStats::default()),
+        }
+    }
+
+    /// Configure from `MORPHEUS_FEW_SHOT_K` and the embeddings variables
+    pub fn from_env(endpoint: &ModelEndpoint) -> anyhow::Result<Self> {
+        let top_k = match std::env::var("MORPHEUS_FEW_SHOT_K") {
+            Ok(k) => k.parse()?,
+            Err(_) => DEFAULT_TOP_K,
+        };
+        Ok(Self::new(Embedder::from_env(endpoint)?, top_k))
+    }
+
+    pub fn embedder(&self) -> &Embedder {
+        &self.embedder
+    }
+
+    /// Load stored examples, re-embedding any from a different embedder
+    pub async fn restore(&self, store: &dyn SnapshotStore) -> anyhow::Result<usize> {
+        let Some(bytes) = store.get(EXAMPLES_KEY).await? else {
+            return Ok(0);
+        };
+        let mut examples: Vec<Example> = serde_json::from_slice(&bytes)?;
+        let name = self.embedder.name();
+        for example in examples.iter_mut().filter(|e| e.embedder != name) {
+            example.embedding = self.embedder.embed(&example.prompt).await?;
+            example.embedder = name.clone();
+        }
+        let count = examples.len();
+        *self.examples.lock().unwrap() = examples;
+        Ok(count)
+    }
+
+    /// Store a generation that compiled, replacing any example with the same prompt
+    pub async fn add(&self, prompt: &str, source: &str, version_id: Option<usize>, store: Option<&dyn SnapshotStore>) {
+        let embedding = match self.embedder.embed(prompt).await {
+            Ok(embedding) => embedding,
+            Err(e) => {
+                warn!("📚 Failed to embed example: {}", e);
+                return;
+            }
+        };
+        let bytes = {
+            let mut examples = self.examples.lock().unwrap();
+            examples.retain(|e| e.prompt != prompt);
+            examples.push(Example {
+                prompt: prompt.to_string(),
+                source: source.to_string(),
+                version_id,
+                created_at: Utc::now(),
+                embedder: self.embedder.name(),
+                embedding,
+            });
+            let excess = examples.len().saturating_sub(MAX_EXAMPLES);
+            examples.drain(..excess);
+            store.map(|_| serde_json::to_vec(&*examples))
+        };
+        if let (Some(store), Some(Ok(bytes))) = (store, bytes) {
+            if let Err(e) = store.put(EXAMPLES_KEY, bytes).await {
+                warn!("📚 Failed to store examples: {}", e);
+            }
+        }
+    }
+
+    /// The stored examples most similar to `prompt`, most similar first
+    pub async fn similar(&self, prompt: &str) -> Vec<Example> {
+        if self.top_k == 0 || self.examples.lock().unwrap().is_empty() {
+            return Vec::new();
+        }
+        let query = match self.embedder.embed(prompt).await {
+            Ok(query) => query,
+            Err(e) => {
+                warn!("📚 Failed to embed request: {}", e);
+                return Vec::new();
+            }
+        };
+        let examples = self.examples.lock().unwrap();
+        let mut scored: Vec<(f32, &Example)> = examples
+            .iter()
+            .map(|e| (similarity(&query, &e.embedding), e))
+            .filter(|(score, _)| *score >= MIN_SIMILARITY)
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().take(self.top_k).map(|(_, e)| e.clone()).collect()
+    }
+
+    /// Record how a generation went, to measure what examples are worth
+    pub fn record_outcome(&self, used_examples: bool, iterations: u32, success: bool) {
+        let stats = if used_examples { &self.with_examples } else { &self.without_examples };
+        stats.lock().unwrap().record(iterations, success);
+    }
+
+    pub fn status(&self) -> ExamplesStatus {
+        ExamplesStatus {
+            examples: self.examples.lock().unwrap().len(),
+            embedder: self.embedder.name(),
+            top_k: self.top_k,
+            with_examples: *self.with_examples.lock().unwrap(),
+            without_examples: *self.without_examples.lock().unwrap(),
+        }
+    }
+}
+
+/// Render examples as a prompt section
+pub fn prompt_section(examples: &[Example]) -> String {
+    if examples.is_empty() {
+        return String::new();
+    }
+    let mut section = String::from("WORKING EXAMPLES for similar requests (these compiled in this sandbox):");
+    for example in examples {
+        section.push_str(&format!(
+            "\n\nRequest: {}\n```rust\n{}\n```",
+            example.prompt,
+            example.source.trim_end()
+        ));
+    }
+    section
+}
+
+/// Stored examples and compile outcomes with and without them
+pub async fn get_examples(State(state): State<AppState>) -> Json<ExamplesStatus> {
+    Json(state.examples.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morpheus_core::store::MemoryStore;
+
+    #[tokio::test]
+    async fn test_retrieval() {
+        let examples = ExampleStore::new(Embedder::Local, 1);
+        assert!(examples.similar("counter").await.is_empty());
+
+        examples.add("A counter with a reset button", "// counter", Some(0), None).await;
+        examples.add("A todo list with checkboxes", "// todo", Some(1), None).await;
+        examples.add("A todo list with checkboxes", "// todo v2", Some(2), None).await;
+
+        let found = examples.similar("Build a todo list app").await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].source, "// todo v2");
+        assert!(examples.similar("weather forecast table").await.is_empty());
+        assert_eq!(examples.status().examples, 2);
+        assert!(prompt_section(&found).contains("Request: A todo list with checkboxes\n```rust\n// todo v2\n```"));
+    }
+
+    #[tokio::test]
+    async fn test_examples_survive_restarts() {
+        let store = MemoryStore::new();
+        let examples = ExampleStore::new(Embedder::Local, DEFAULT_TOP_K);
+        examples.add("A counter", "// counter", Some(0), Some(&store)).await;
+
+        let restored = ExampleStore::new(Embedder::Local, DEFAULT_TOP_K);
+        assert_eq!(restored.restore(&store).await.unwrap(), 1);
+        assert_eq!(restored.similar("counter").await.len(), 1);
+    }
+
+    #[test]
+    fn test_outcomes() {
+        let examples = ExampleStore::new(Embedder::Local, DEFAULT_TOP_K);
+        examples.record_outcome(true, 1, true);
+        examples.record_outcome(true, 3, true);
+        examples.record_outcome(false, 5, false);
+
+        let status = examples.status();
+        assert_eq!(status.with_examples.generations, 2);
+        assert_eq!(status.with_examples.first_try, 1);
+        assert_eq!(status.with_examples.attempts, 4);
+        assert_eq!(status.without_examples.failed, 1);
+    }
+}
@@ -0,0 +1,311 @@
+//! Model gateway: AI requests through an internal endpoint.
+//!
+//! Completions go to OpenRouter by default. Organisations that can't send
+//! code to an external API set `MORPHEUS_MODEL_GATEWAY_URL` to their own
+//! OpenAI-compatible chat completions endpoint, and every completion goes
+//! there instead:
+//!
+//! - `MORPHEUS_MODEL_GATEWAY_CLIENT_CERT` and `MORPHEUS_MODEL_GATEWAY_CLIENT_KEY`
+//!   (PEM files) authenticate the server with a client certificate (mTLS),
+//!   and `MORPHEUS_MODEL_GATEWAY_CA` trusts an internal certificate authority
+//! - `MORPHEUS_MODEL_GATEWAY_SIGNING_KEY` signs every request, and every
+//!   response must be signed over the request's signature, so a response
+//!   can't be forged or replayed from another request
+//!
+//! `MORPHEUS_BLOCK_EXTERNAL_AI` turns the gateway into a hard rule: the
+//! server refuses to start without one, or with an embeddings API anywhere
+//! else, and refuses to send AI requests to any other origin.
+
+use crate::AppError;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Certificate, Identity, Url};
+use serde::Serialize;
+use sha2::Sha256;
+
+/// Header with the Unix time a request was signed at
+pub const TIMESTAMP_HEADER: &str = "x-morpheus-timestamp";
+
+/// Header with the hex HMAC-SHA256 of a request or response
+pub const SIGNATURE_HEADER: &str = "x-morpheus-signature";
+
+/// Chat completions endpoint used without a gateway
+const OPENROUTER_URL: &str = "https://openrouter.ai/api/v1/chat/completions";
+
+/// Where completions are sent, and how
+pub struct ModelEndpoint {
+    url: Url,
+    client: reqwest::Client,
+    signing_key: Option<Vec<u8>>,
+    gateway: bool,
+    block_external: bool,
+}
+
+impl ModelEndpoint {
+    /// OpenRouter, with no signing and no policy
+    pub fn openrouter() -> Self {
+        Self {
+            url: Url::parse(OPENROUTER_URL).expect("OpenRouter URL is valid"),
+            client: reqwest::Client::new(),
+            signing_key: None,
+            gateway: false,
+            block_external: false,
+        }
+    }
+
+    /// An internal gateway at `url`, using `client` (which carries any
+    /// client certificate and trusted CA)
+    pub fn gateway(url: Url, client: reqwest::Client) -> Self {
+        Self {
+            url,
+            client,
+            signing_key: None,
+            gateway: true,
+            block_external: false,
+        }
+    }
+
+    /// Sign requests with `key` and require signed responses
+    pub fn with_signing_key(mut self, key: impl Into<Vec<u8>>) -> Self {
+        self.signing_key = Some(key.into());
+        self
+    }
+
+    /// Refuse AI requests to anywhere but the gateway
+    pub fn with_external_blocked(mut self) -> Self {
+        self.block_external = true;
+        self
+    }
+
+    /// Configure from the `MORPHEUS_MODEL_GATEWAY_*` variables and
+    /// `MORPHEUS_BLOCK_EXTERNAL_AI`
+    pub fn from_env() -> anyhow::Result<Self> {
+        let block_external = crate::env_flag("MORPHEUS_BLOCK_EXTERNAL_AI")?;
+        let Some(url) = std::env::var("MORPHEUS_MODEL_GATEWAY_URL").ok().filter(|url| !url.is_empty()) else {
+            if block_external {
+                anyhow::bail!("MORPHEUS_BLOCK_EXTERNAL_AI is set but MORPHEUS_MODEL_GATEWAY_URL is not");
+            }
+            return Ok(Self::openrouter());
+        };
+
+        let mut client = reqwest::Client::builder();
+        match (
+            std::env::var("MORPHEUS_MODEL_GATEWAY_CLIENT_CERT"),
+            std::env::var("MORPHEUS_MODEL_GATEWAY_CLIENT_KEY"),
+        ) {
+            (Ok(cert), Ok(key)) => {
+                client = client.identity(Identity::from_pkcs8_pem(&std::fs::read(cert)?, &std::fs::read(key)?)?);
+            }
+            (Err(_), Err(_)) => {}
+            _ => anyhow::bail!(
+                "MORPHEUS_MODEL_GATEWAY_CLIENT_CERT and MORPHEUS_MODEL_GATEWAY_CLIENT_KEY must be set together"
+            ),
+        }
+        if let Ok(ca) = std::env::var("MORPHEUS_MODEL_GATEWAY_CA") {
+            client = client.add_root_certificate(Certificate::from_pem(&std::fs::read(ca)?)?);
+        }
+
+        let mut endpoint = Self::gateway(Url::parse(&url)?, client.build()?);
+        if let Ok(key) = std::env::var("MORPHEUS_MODEL_GATEWAY_SIGNING_KEY") {
+            endpoint = endpoint.with_signing_key(key);
+        }
+        if block_external {
+            endpoint = endpoint.with_external_blocked();
+        }
+        Ok(endpoint)
+    }
+
+    /// Whether completions go to an internal gateway
+    pub fn is_gateway(&self) -> bool {
+        self.gateway
+    }
+
+    /// Whether requests are signed
+    pub fn is_signed(&self) -> bool {
+        self.signing_key.is_some()
+    }
+
+    /// Whether AI requests elsewhere are refused
+    pub fn blocks_external(&self) -> bool {
+        self.block_external
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Client for other AI requests (embeddings), with the gateway's
+    /// certificates
+    pub fn client(&self) -> reqwest::Client {
+        self.client.clone()
+    }
+
+    /// Refuse `url` when external providers are blocked and it isn't on
+    /// the gateway's origin
+    pub fn check_url(&self, url: &str) -> anyhow::Result<()> {
+        if !self.block_external {
+            return Ok(());
+        }
+        let parsed = Url::parse(url)?;
+        if parsed.origin() != self.url.origin() {
+            anyhow::bail!(
+                "{} is outside the model gateway ({}) and MORPHEUS_BLOCK_EXTERNAL_AI is set",
+                url,
+                self.url.origin().ascii_serialization()
+            );
+        }
+        Ok(())
+    }
+
+    /// POST `body` as JSON and return the response body, once its
+    /// signature checks out
+    pub async fn post(&self, body: &impl Serialize, api_key: &str) -> Result<String, AppError> {
+        self.check_url(self.url.as_str())?;
+        let body = serde_json::to_vec(body).map_err(anyhow::Error::from)?;
+        let mut request = self.client.post(self.url.clone()).header(CONTENT_TYPE, "application/json");
+        if !api_key.is_empty() {
+            request = request.bearer_auth(api_key);
+        }
+        if !self.gateway {
+            request = request
+                .header("HTTP-Referer", "https://github.com/morpheus-project")
+                .header("X-Title", "Morpheus");
+        }
+        let mut request_signature = None;
+        if let Some(key) = &self.signing_key {
+            let timestamp = Utc::now().timestamp().to_string();
+            let signature = sign(key, &[timestamp.as_bytes(), b"\n", &body]);
+            request = request
+                .header(TIMESTAMP_HEADER, timestamp)
+                .header(SIGNATURE_HEADER, signature.clone());
+            request_signature = Some(signature);
+        }
+
+        let response = request.body(body).send().await?;
+        let status = response.status();
+        let response_signature = response
+            .headers()
+            .get(SIGNATURE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let text = response.text().await?;
+        if !status.is_success() {
+            return Err(AppError::ApiError(format!("{} returned {}: {}", self.url, status, text)));
+        }
+        if let (Some(key), Some(request_signature)) = (&self.signing_key, request_signature) {
+            let signed_parts: &[&[u8]] = &[request_signature.as_bytes(), b"\n", text.as_bytes()];
+            if !response_signature.is_some_and(|signature| verify(key, signed_parts, &signature)) {
+                return Err(AppError::ApiError(
+                    "Model gateway response is not signed with the gateway key".to_string(),
+                ));
+            }
+        }
+        Ok(text)
+    }
+}
+
+/// Hex HMAC-SHA256 of `parts`, concatenated
+///
+/// Requests are signed over `<timestamp>\n<body>`; responses over
+/// `<request signature>\n<body>`.
+pub fn sign(key: &[u8], parts: &[&[u8]]) -> String {
+    mac(key, parts).finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Whether `signature` is the hex HMAC-SHA256 of `parts`, compared in
+/// constant time
+fn verify(key: &[u8], parts: &[&[u8]], signature: &str) -> bool {
+    let Some(bytes) = decode_hex(signature) else {
+        return false;
+    };
+    mac(key, parts).verify_slice(&bytes).is_ok()
+}
+
+fn mac(key: &[u8], parts: &[&[u8]]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Bytes;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+
+    const KEY: &[u8] = b"gateway-secret";
+
+    /// A gateway that checks request signatures and signs its responses
+    /// with `response_key`
+    async fn serve(response_key: &'static [u8]) -> Url {
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move |headers: HeaderMap, body: Bytes| async move {
+                let header = |name| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+                let signature = header(SIGNATURE_HEADER);
+                if !verify(KEY, &[header(TIMESTAMP_HEADER).as_bytes(), b"\n", &body], &signature) {
+                    return (StatusCode::UNAUTHORIZED, HeaderMap::new(), "bad signature".to_string());
+                }
+                let text = r#"{"choices":[]}"#.to_string();
+                let mut response_headers = HeaderMap::new();
+                let response_signature = sign(response_key, &[signature.as_bytes(), b"\n", text.as_bytes()]);
+                response_headers.insert(SIGNATURE_HEADER, response_signature.parse().unwrap());
+                (StatusCode::OK, response_headers, text)
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        Url::parse(&format!("http://{}/v1/chat/completions", addr)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_signed_requests_need_signed_responses() {
+        let url = serve(KEY).await;
+        let endpoint = ModelEndpoint::gateway(url.clone(), reqwest::Client::new()).with_signing_key(KEY);
+        assert_eq!(endpoint.post(&"hi", "").await.unwrap(), r#"{"choices":[]}"#);
+
+        let unsigned = ModelEndpoint::gateway(url.clone(), reqwest::Client::new());
+        let refused = unsigned.post(&"hi", "").await.unwrap_err().to_string();
+        assert!(refused.starts_with(&format!("{} returned 401", url)), "{}", refused);
+
+        let forged = ModelEndpoint::gateway(serve(b"not-the-key").await, reqwest::Client::new()).with_signing_key(KEY);
+        assert!(forged.post(&"hi", "").await.unwrap_err().to_string().contains("not signed"));
+    }
+
+    #[test]
+    fn test_blocking_allows_only_the_gateway_origin() {
+        let url = Url::parse("https://models.internal:8443/v1/chat/completions").unwrap();
+        let endpoint = ModelEndpoint::gateway(url, reqwest::Client::new());
+        assert!(endpoint.check_url("https://api.openai.com/v1/embeddings").is_ok());
+
+        let endpoint = endpoint.with_external_blocked();
+        assert!(endpoint.check_url("https://models.internal:8443/v1/embeddings").is_ok());
+        assert!(endpoint.check_url("https://models.internal/v1/embeddings").is_err());
+        assert!(endpoint.check_url(OPENROUTER_URL).is_err());
+    }
+
+    #[test]
+    fn test_signatures_cover_every_part() {
+        let signature = sign(KEY, &[b"1700000000", b"\n", b"{}"]);
+        assert_eq!(signature.len(), 64);
+        assert!(verify(KEY, &[b"1700000000", b"\n", b"{}"], &signature));
+        assert!(!verify(KEY, &[b"1700000001", b"\n", b"{}"], &signature));
+        assert!(!verify(KEY, &[b"1700000000", b"\n", b"{}"], "zz"));
+    }
+}
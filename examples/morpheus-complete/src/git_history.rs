@@ -0,0 +1,230 @@
+//! Git-backed version history
+//!
+//! Mirrors every version into a real git repository so the AI's work can be
+//! inspected with ordinary tools: `git log`, `git diff v2 v3`, `git blame
+//! component.rs`, branches, and pushing to GitHub for human review.
+//!
+//! Each version is one commit on `main` containing:
+//! - `component.rs` - the Rust source
+//! - `manifest.json` - the component manifest
+//! - `version.json` - version metadata
+//!
+//! Activated versions are tagged `v{id}`, and the `active` branch always
+//! points at the version currently running.
+
+use crate::ComponentVersion;
+use git2::{Oid, Repository, Signature};
+use std::collections::HashMap;
+use std::path::Path;
+
+const BRANCH: &str = "refs/heads/main";
+const ACTIVE_BRANCH: &str = "refs/heads/active";
+const VERSION_TRAILER: &str = "Morpheus-Version: ";
+
+/// A git repository holding one commit per component version
+pub struct GitHistory {
+    repo: Repository,
+    /// Commit for each version ID
+    commits: HashMap<usize, Oid>,
+}
+
+impl GitHistory {
+    /// Open the repository at `path`, creating it if needed
+    pub fn open_or_init(path: &Path) -> Result<Self, git2::Error> {
+        let repo = match Repository::open(path) {
+            Ok(repo) => repo,
+            Err(_) => {
+                std::fs::create_dir_all(path).map_err(|e| git2::Error::from_str(&e.to_string()))?;
+                Repository::init(path)?
+            }
+        };
+
+        let mut history = Self {
+            repo,
+            commits: HashMap::new(),
+        };
+        history.index_commits()?;
+        Ok(history)
+    }
+
+    /// Record a version as a commit on `main`
+    pub fn commit_version(&mut self, version: &ComponentVersion) -> Result<Oid, git2::Error> {
+        let metadata = serde_json::json!({
+            "id": version.id,
+            "name": version.name,
+            "description": version.description,
+            "created_at": version.created_at.to_rfc3339(),
+            "ai_generated": version.ai_generated,
+            "provenance": version.provenance,
+        });
+        let manifest = serde_json::to_string_pretty(&version.manifest)
+            .map_err(|e| git2::Error::from_str(&e.to_string()))?;
+
+        let mut tree = self.repo.treebuilder(None)?;
+        for (name, contents) in [
+            ("component.rs", format!("{}\n", version.rust_code.trim_end())),
+            ("manifest.json", format!("{}\n", manifest)),
+            ("version.json", format!("{:#}\n", metadata)),
+        ] {
+            let blob = self.repo.blob(contents.as_bytes())?;
+            tree.insert(name, blob, 0o100644)?;
+        }
+        let tree = self.repo.find_tree(tree.write()?)?;
+
+        let parent = self
+            .repo
+            .find_reference(BRANCH)
+            .ok()
+            .and_then(|r| r.peel_to_commit().ok());
+        let parents: Vec<_> = parent.iter().collect();
+
+        let author = if version.ai_generated { "Morpheus AI" } else { "Morpheus" };
+        let signature = Signature::now(author, "morpheus@localhost")?;
+        let message = format!(
+            "{}\n\n{}\n\n{}{}\n",
+            version.name, version.description, VERSION_TRAILER, version.id
+        );
+
+        let oid = self
+            .repo
+            .commit(Some(BRANCH), &signature, &signature, &message, &tree, &parents)?;
+        if parent.is_none() {
+            self.repo.set_head(BRANCH)?;
+        }
+        self.commits.insert(version.id, oid);
+        Ok(oid)
+    }
+
+    /// Tag a version as activated and point `active` at it
+    pub fn mark_active(&self, version_id: usize) -> Result<(), git2::Error> {
+        let oid = self
+            .commits
+            .get(&version_id)
+            .ok_or_else(|| git2::Error::from_str(&format!("Version {} has no commit", version_id)))?;
+        let commit = self.repo.find_commit(*oid)?;
+
+        let tag = format!("v{}", version_id);
+        if self.repo.refname_to_id(&format!("refs/tags/{}", tag)).is_err() {
+            self.repo.tag_lightweight(&tag, commit.as_object(), false)?;
+        }
+        self.repo
+            .reference(ACTIVE_BRANCH, *oid, true, &format!("activate version {}", version_id))?;
+        Ok(())
+    }
+
+    /// Rebuild the version → commit index from `main`'s history
+    fn index_commits(&mut self) -> Result<(), git2::Error> {
+        let Ok(head) = self.repo.refname_to_id(BRANCH) else {
+            return Ok(());
+        };
+        let mut walk = self.repo.revwalk()?;
+        walk.push(head)?;
+        for oid in walk {
+            let oid = oid?;
+            let commit = self.repo.find_commit(oid)?;
+            let version_id = commit
+                .message()
+                .unwrap_or_default()
+                .lines()
+                .find_map(|line| line.strip_prefix(VERSION_TRAILER))
+                .and_then(|id| id.trim().parse().ok());
+            if let Some(id) = version_id {
+                self.commits.entry(id).or_insert(oid);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use morpheus_core::manifest::ComponentManifest;
+
+    fn version(id: usize, rust_code: &str) -> ComponentVersion {
+        ComponentVersion {
+            id,
+            name: format!("Version {}", id),
+            description: "Test version".to_string(),
+            rust_code: rust_code.to_string(),
+            wasm_base64: String::new(),
+            js_glue: String::new(),
+            created_at: Utc::now(),
+            state_snapshot: None,
+            ai_generated: true,
+            manifest: ComponentManifest::new("main", "Test version"),
+            review: Default::default(),
+            activated_at: None,
+            changelog: None,
+            guardrail_violations: Vec::new(),
+            guardrail_override: None,
+            provenance: Default::default(),
+            sbom: None,
+            feedback: Vec::new(),
+            variants: Vec::new(),
+            assets: Default::default(),
+            thumbnail: None,
+            accessibility: None,
+            performance: None,
+        }
+    }
+
+    fn temp_repo(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("morpheus-git-history-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        path
+    }
+
+    #[test]
+    fn test_commits_versions_in_order() {
+        let path = temp_repo("commits");
+        let mut history = GitHistory::open_or_init(&path).expect("Failed to init");
+
+        let first = history.commit_version(&version(0, "fn a() {}")).unwrap();
+        let second = history.commit_version(&version(1, "fn b() {}")).unwrap();
+
+        let repo = Repository::open(&path).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.id(), second);
+        assert_eq!(head.parent_id(0).unwrap(), first);
+
+        let tree = head.tree().unwrap();
+        let blob = tree.get_name("component.rs").unwrap().to_object(&repo).unwrap();
+        assert_eq!(blob.as_blob().unwrap().content(), b"fn b() {}\n");
+        assert!(tree.get_name("manifest.json").is_some());
+        assert!(tree.get_name("version.json").is_some());
+
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn test_mark_active_tags_and_moves_branch() {
+        let path = temp_repo("active");
+        let mut history = GitHistory::open_or_init(&path).expect("Failed to init");
+        let first = history.commit_version(&version(0, "fn a() {}")).unwrap();
+        history.commit_version(&version(1, "fn b() {}")).unwrap();
+
+        history.mark_active(0).expect("Failed to mark active");
+
+        let repo = Repository::open(&path).unwrap();
+        assert_eq!(repo.refname_to_id("refs/tags/v0").unwrap(), first);
+        assert_eq!(repo.refname_to_id(ACTIVE_BRANCH).unwrap(), first);
+        assert!(history.mark_active(7).is_err());
+
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn test_reopen_rebuilds_index() {
+        let path = temp_repo("reopen");
+        let mut history = GitHistory::open_or_init(&path).expect("Failed to init");
+        let oid = history.commit_version(&version(0, "fn a() {}")).unwrap();
+        drop(history);
+
+        let history = GitHistory::open_or_init(&path).expect("Failed to reopen");
+        assert_eq!(history.commits.get(&0), Some(&oid));
+
+        let _ = std::fs::remove_dir_all(&path);
+    }
+}
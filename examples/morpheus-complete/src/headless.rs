@@ -0,0 +1,621 @@
+//! Headless components: self-modifying backend logic.
+//!
+//! A headless component is generated, compiled, hot-reloaded and rolled
+//! back like a UI component, but runs on the server under wasmtime. HTTP
+//! handlers are mounted at `/x/{component}/{path}`; data transforms are
+//! called through `/api/headless/{component}/transform`.
+//!
+//! The AI only writes a `handle` or `transform` function. The request and
+//! response types and the exports the runtime calls (see
+//! [`morpheus_runtime::headless`]) come from a prelude prepended before
+//! compilation.
+
+use crate::{
+    complete, extract_rust_code, record_audit, screen_request, truncate, AppError, AppState, Message, PromptContext,
+};
+use axum::{
+    body::Bytes,
+    extract::{Path, Request, State},
+    http::{HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use morpheus_compiler::{source, Compiler};
+use morpheus_core::component::Provenance;
+use morpheus_runtime::headless::{HeadlessComponent, HttpRequest, HttpResponse};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tracing::{error, info};
+
+/// Generate-and-compile attempts before giving up
+const MAX_ITERATIONS: u32 = 5;
+
+/// Largest request body dispatched to a component
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// What a headless component does
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HeadlessKind {
+    /// Handles HTTP requests at `/x/{component}/...`
+    #[default]
+    Http,
+    /// Transforms JSON values
+    Transform,
+}
+
+impl HeadlessKind {
+    /// Types and exports prepended to the AI's code
+    fn prelude(self) -> String {
+        let exports = match self {
+            HeadlessKind::Http => HTTP_EXPORTS,
+            HeadlessKind::Transform => TRANSFORM_EXPORTS,
+        };
+        format!("{}{}", ABI_PRELUDE, exports)
+    }
+}
+
+/// One compiled version of a headless component
+#[derive(Clone, Serialize, JsonSchema)]
+pub struct HeadlessVersion {
+    pub id: usize,
+    pub prompt: String,
+    /// The AI's code, without the prelude
+    pub rust_code: String,
+    pub wasm_size: usize,
+    pub created_at: DateTime<Utc>,
+    pub provenance: Provenance,
+    #[serde(skip)]
+    wasm_bytes: Vec<u8>,
+}
+
+struct HeadlessEntry {
+    kind: HeadlessKind,
+    versions: Vec<HeadlessVersion>,
+    current: usize,
+    module: HeadlessComponent,
+}
+
+/// Headless components by name
+#[derive(Default)]
+pub struct HeadlessRegistry {
+    components: BTreeMap<String, HeadlessEntry>,
+}
+
+impl HeadlessRegistry {
+    /// Add a version and make it live
+    fn activate_new(&mut self, name: &str, kind: HeadlessKind, mut version: HeadlessVersion, module: HeadlessComponent) -> usize {
+        match self.components.get_mut(name) {
+            Some(entry) => {
+                version.id = entry.versions.len();
+                entry.kind = kind;
+                entry.current = version.id;
+                entry.module = module;
+                entry.versions.push(version);
+                entry.current
+            }
+            None => {
+                version.id = 0;
+                self.components.insert(
+                    name.to_string(),
+                    HeadlessEntry {
+                        kind,
+                        versions: vec![version],
+                        current: 0,
+                        module,
+                    },
+                );
+                0
+            }
+        }
+    }
+
+    /// The live module of a component
+    fn module(&self, name: &str) -> Result<HeadlessComponent, AppError> {
+        self.components
+            .get(name)
+            .map(|entry| entry.module.clone())
+            .ok_or_else(|| unknown(name))
+    }
+
+    fn summary(&self, name: &str) -> Option<HeadlessSummary> {
+        let entry = self.components.get(name)?;
+        Some(HeadlessSummary {
+            name: name.to_string(),
+            kind: entry.kind,
+            version_id: entry.current,
+            versions: entry.versions.len(),
+            route: match entry.kind {
+                HeadlessKind::Http => format!("/x/{}/", name),
+                HeadlessKind::Transform => format!("/api/headless/{}/transform", name),
+            },
+        })
+    }
+}
+
+fn unknown(name: &str) -> AppError {
+    AppError::NotFound(format!("Headless component '{}' not found", name))
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct HeadlessGenerateRequest {
+    /// Component name; generating under an existing name modifies it
+    pub name: String,
+    pub prompt: String,
+    #[serde(default)]
+    pub kind: HeadlessKind,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct HeadlessGenerateResponse {
+    pub success: bool,
+    pub name: String,
+    pub version_id: Option<usize>,
+    pub error: Option<String>,
+    pub iterations: u32,
+    pub logs: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct HeadlessSummary {
+    pub name: String,
+    pub kind: HeadlessKind,
+    /// Live version
+    pub version_id: usize,
+    pub versions: usize,
+    /// Where the component is reached
+    pub route: String,
+}
+
+#[derive(Deserialize, Default, JsonSchema)]
+pub struct HeadlessRollbackRequest {
+    /// Version to make live (defaults to the one before the live version)
+    #[serde(default)]
+    pub version_id: Option<usize>,
+}
+
+/// Generate or modify a headless component
+pub async fn generate_headless(
+    State(state): State<AppState>,
+    Json(req): Json<HeadlessGenerateRequest>,
+) -> Result<Json<HeadlessGenerateResponse>, AppError> {
+    info!(component_id = %req.name, "Headless generation request: {}", req.prompt);
+
+    if !state.has_ai() {
+        return Err(AppError::ApiError("OPENROUTER_API_KEY not configured".to_string()));
+    }
+
+    screen_request(&state, "headless_generate", &req.prompt).await?;
+
+    let mut logs = vec![format!("🎯 User request: {}", req.prompt)];
+    let current_code = {
+        let registry = state.headless.lock().await;
+        registry
+            .components
+            .get(&req.name)
+            .map(|entry| entry.versions[entry.current].rust_code.clone())
+    };
+    let mut messages = vec![
+        Message {
+            role: "user".to_string(),
+            // Headless modules have no host imports, only their crates
+            content: format!(
+                "{}\n\n{}",
+                create_headless_system_prompt(req.kind),
+                PromptContext::new()
+                    .with_host_imports(&[])
+                    .with_dependencies(state.headless_compiler.dependencies())
+                    .with_shared_types(crate::context::shared_types())
+                    .render()
+            ),
+        },
+        Message {
+            role: "user".to_string(),
+            content: match &current_code {
+                Some(code) => format!("Current code:\n\n```rust\n{}\n```\n\nChange it: {}", code, req.prompt),
+                None => req.prompt.clone(),
+            },
+        },
+    ];
+
+    let mut iteration = 0;
+    let (rust_code, wasm_bytes, module) = loop {
+        iteration += 1;
+        logs.push(format!("\n━━━ Iteration {} ━━━", iteration));
+        if iteration > MAX_ITERATIONS {
+            logs.push("❌ Max iterations reached".to_string());
+            record_audit(&state, "headless_generate", None, "failed", format!("{}: gave up", req.name)).await;
+            return Ok(Json(HeadlessGenerateResponse {
+                success: false,
+                name: req.name,
+                version_id: None,
+                error: Some(format!("Failed after {} attempts", MAX_ITERATIONS)),
+                iterations: MAX_ITERATIONS,
+                logs,
+            }));
+        }
+
+        logs.push("🤖 Asking AI to generate Rust code...".to_string());
+        let rust_code = match complete(&state, messages.clone()).await.and_then(|text| extract_rust_code(&text)) {
+            Ok(code) => code,
+            Err(e) => {
+                error!("Claude API error: {}", e);
+                return Ok(Json(HeadlessGenerateResponse {
+                    success: false,
+                    name: req.name,
+                    version_id: None,
+                    error: Some(format!("AI API error: {}", e)),
+                    iterations: iteration,
+                    logs,
+                }));
+            }
+        };
+
+        logs.push("⚙️  Compiling Rust → WASM (headless)...".to_string());
+        let prelude = format!("{}\n", req.kind.prelude());
+        let source = format!("{}{}", prelude, rust_code);
+        let failure = match state.headless_compiler.compile(&source).await {
+            Ok(result) => match load_module(&state, &result.wasm_bytes) {
+                Ok(module) => {
+                    // Keep pre-compile fixes, unless they touched the prelude
+                    let fixed = result.fixed_source.as_deref().and_then(|fixed| fixed.strip_prefix(&prelude));
+                    break (fixed.map(str::to_string).unwrap_or(rust_code), result.wasm_bytes, module);
+                }
+                Err(e) => e.to_string(),
+            },
+            Err(e) => e.to_string(),
+        };
+        logs.push(format!("❌ Build failed:\n{}", failure));
+        logs.push("🔄 Feeding error back to AI for retry...".to_string());
+        messages.push(Message {
+            role: "assistant".to_string(),
+            content: rust_code,
+        });
+        messages.push(Message {
+            role: "user".to_string(),
+            content: format!("That code failed to build with this error:\n\n{}\n\nFix it.", failure),
+        });
+    };
+
+    logs.push(format!("✅ Compiled {} bytes of WASM", wasm_bytes.len()));
+    let provenance = Provenance::ai(req.prompt.clone(), state.generation.frontier_model.as_str()).with_toolchain(state.headless_compiler.toolchain());
+    let version = HeadlessVersion {
+        id: 0,
+        prompt: req.prompt.clone(),
+        rust_code: source::with_header(&source::tidy(&rust_code).await, &req.name, &provenance),
+        wasm_size: wasm_bytes.len(),
+        created_at: Utc::now(),
+        provenance,
+        wasm_bytes,
+    };
+    let version_id = state.headless.lock().await.activate_new(&req.name, req.kind, version, module);
+    logs.push(format!("🔥 Hot-reloaded '{}' as version {}", req.name, version_id));
+    record_audit(
+        &state,
+        "headless_generate",
+        None,
+        "success",
+        format!("{} v{}: {}", req.name, version_id, truncate(&req.prompt, 80)),
+    )
+    .await;
+
+    Ok(Json(HeadlessGenerateResponse {
+        success: true,
+        name: req.name,
+        version_id: Some(version_id),
+        error: None,
+        iterations: iteration,
+        logs,
+    }))
+}
+
+/// List headless components
+pub async fn list_headless(State(state): State<AppState>) -> Json<Vec<HeadlessSummary>> {
+    let registry = state.headless.lock().await;
+    Json(registry.components.keys().filter_map(|name| registry.summary(name)).collect())
+}
+
+/// A headless component's versions
+pub async fn headless_history(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<HeadlessVersion>>, AppError> {
+    let registry = state.headless.lock().await;
+    let entry = registry.components.get(&name).ok_or_else(|| unknown(&name))?;
+    Ok(Json(entry.versions.clone()))
+}
+
+/// Compile a headless module, reusing machine code cached by an earlier
+/// run if `MORPHEUS_COMPILED_CACHE_DIR` is set
+fn load_module(state: &AppState, wasm_bytes: &[u8]) -> morpheus_core::errors::Result<HeadlessComponent> {
+    match &state.compiled_cache {
+        Some(cache) => HeadlessComponent::load_cached(wasm_bytes, cache),
+        None => HeadlessComponent::load(wasm_bytes),
+    }
+}
+
+/// Make an earlier version of a headless component live
+pub async fn rollback_headless(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(req): Json<HeadlessRollbackRequest>,
+) -> Result<Json<HeadlessSummary>, AppError> {
+    let mut registry = state.headless.lock().await;
+    let entry = registry.components.get_mut(&name).ok_or_else(|| unknown(&name))?;
+    let target = match req.version_id {
+        Some(id) => id,
+        None => entry
+            .current
+            .checked_sub(1)
+            .ok_or_else(|| AppError::ApiError(format!("'{}' has no earlier version", name)))?,
+    };
+    let version = entry
+        .versions
+        .get(target)
+        .ok_or_else(|| AppError::NotFound(format!("Version {} of '{}' not found", target, name)))?;
+
+    entry.module = load_module(&state, &version.wasm_bytes)?;
+    entry.current = target;
+    info!(component_id = %name, version = target, "⏪ Rolled back headless component");
+    let summary = registry.summary(&name);
+    drop(registry);
+
+    record_audit(&state, "headless_rollback", None, "success", format!("{} v{}", name, target)).await;
+    summary.map(Json).ok_or_else(|| unknown(&name))
+}
+
+/// Run a transform component on a JSON value
+pub async fn transform(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(input): Json<Value>,
+) -> Result<Json<Value>, AppError> {
+    let module = state.headless.lock().await.module(&name)?;
+    let output = tokio::task::spawn_blocking(move || module.transform(&input))
+        .await
+        .map_err(|e| AppError::Anyhow(e.into()))?
+        .map_err(|e| AppError::ApiError(format!("'{}' failed: {}", name, e)))?;
+    Ok(Json(output))
+}
+
+/// Dispatch `/x/{component}` to the component
+pub async fn dispatch_root(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    request: Request,
+) -> Result<Response, AppError> {
+    dispatch(&state, name, String::new(), request).await
+}
+
+/// Dispatch `/x/{component}/{path}` to the component
+pub async fn dispatch_path(
+    State(state): State<AppState>,
+    Path((name, path)): Path<(String, String)>,
+    request: Request,
+) -> Result<Response, AppError> {
+    dispatch(&state, name, path, request).await
+}
+
+async fn dispatch(state: &AppState, name: String, path: String, request: Request) -> Result<Response, AppError> {
+    let module = state.headless.lock().await.module(&name)?;
+    let (parts, body) = request.into_parts();
+    let body: Bytes = axum::body::to_bytes(body, MAX_BODY_BYTES)
+        .await
+        .map_err(|e| AppError::ApiError(format!("Failed to read request body: {}", e)))?;
+
+    let request = HttpRequest {
+        method: parts.method.to_string(),
+        path: format!("/{}", path),
+        query: parts.uri.query().unwrap_or_default().to_string(),
+        headers: parts
+            .headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+        body: String::from_utf8_lossy(&body).into_owned(),
+    };
+    let response = tokio::task::spawn_blocking(move || module.handle(&request))
+        .await
+        .map_err(|e| AppError::Anyhow(e.into()))?
+        .map_err(|e| AppError::ApiError(format!("'{}' failed: {}", name, e)))?;
+    Ok(into_response(response))
+}
+
+fn into_response(response: HttpResponse) -> Response {
+    let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let mut reply = (status, response.body).into_response();
+    for (name, value) in response.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            reply.headers_mut().insert(name, value);
+        }
+    }
+    reply
+}
+
+fn create_headless_system_prompt(kind: HeadlessKind) -> String {
+    let task = match kind {
+        HeadlessKind::Http => {
+            "Write `fn handle(request: Request) -> Response`. `Request` has `method`, `path` (below the \
+             component's mount point, starting with `/`), `query` (without `?`), `headers` (lowercase \
+             names) and `body`, all Strings except `headers: BTreeMap<String, String>`. `Response` has \
+             `status: u16`, `headers: BTreeMap<String, String>` and `body: String`; `Response::json(status, \
+             &value)` and `Response::text(status, body)` build one."
+        }
+        HeadlessKind::Transform => {
+            "Write `fn transform(input: Value) -> Value`, taking and returning a `serde_json::Value`."
+        }
+    };
+    format!(
+        "You write backend logic for Morpheus that runs on the server inside a WASM sandbox.\n\n\
+         {}\n\n\
+         Rules:\n\
+         - `use serde::{{Deserialize, Serialize}};`, `use serde_json::{{json, Value}};` and \
+         `use std::collections::BTreeMap;` are already in scope, along with `Request` and `Response`. \
+         Don't redefine them or write `#[no_mangle]` exports.\n\
+         - Only serde and serde_json are available. There is no clock, filesystem, network, randomness \
+         or state kept between calls.\n\
+         - Never panic: return an error status instead.\n\n\
+         Reply with the Rust code in a single ```rust block.",
+        task
+    )
+}
+
+/// Imports and ABI helpers shared by every headless component
+const ABI_PRELUDE: &str = r#"#![allow(dead_code, unused_imports)]
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+#[no_mangle]
+pub extern "C" fn morpheus_alloc(len: i32) -> i32 {
+    let mut buffer = Vec::<u8>::with_capacity(len.max(0) as usize);
+    let ptr = buffer.as_mut_ptr();
+    std::mem::forget(buffer);
+    ptr as i32
+}
+
+unsafe fn morpheus_input<'a>(ptr: i32, len: i32) -> &'a [u8] {
+    std::slice::from_raw_parts(ptr as *const u8, len as usize)
+}
+
+fn morpheus_output(bytes: Vec<u8>) -> i64 {
+    let bytes = bytes.into_boxed_slice();
+    let packed = ((bytes.as_ptr() as u32 as i64) << 32) | bytes.len() as i64;
+    std::mem::forget(bytes);
+    packed
+}
+"#;
+
+/// Request and response types and the export of an HTTP handler
+const HTTP_EXPORTS: &str = r#"
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    #[serde(default)]
+    pub query: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Response {
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn text(status: u16, body: impl Into<String>) -> Self {
+        let headers = BTreeMap::from([("content-type".to_string(), "text/plain; charset=utf-8".to_string())]);
+        Self { status, headers, body: body.into() }
+    }
+
+    pub fn json<T: Serialize>(status: u16, value: &T) -> Self {
+        let headers = BTreeMap::from([("content-type".to_string(), "application/json".to_string())]);
+        Self { status, headers, body: serde_json::to_string(value).unwrap_or_default() }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn morpheus_handle(ptr: i32, len: i32) -> i64 {
+    let response = match serde_json::from_slice(unsafe { morpheus_input(ptr, len) }) {
+        Ok(request) => handle(request),
+        Err(e) => Response::text(400, e.to_string()),
+    };
+    morpheus_output(serde_json::to_vec(&response).unwrap_or_default())
+}
+"#;
+
+/// Export of a data transform
+const TRANSFORM_EXPORTS: &str = r#"
+#[no_mangle]
+pub extern "C" fn morpheus_transform(ptr: i32, len: i32) -> i64 {
+    let output = match serde_json::from_slice(unsafe { morpheus_input(ptr, len) }) {
+        Ok(input) => transform(input),
+        Err(e) => json!({ "error": e.to_string() }),
+    };
+    morpheus_output(serde_json::to_vec(&output).unwrap_or_default())
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Transform that returns a constant, `{"v":N}`
+    fn constant(n: u8) -> HeadlessComponent {
+        let wat = format!(
+            r#"(module
+                 (memory (export "memory") 1)
+                 (data (i32.const 16) "{{\"v\":{}}}")
+                 (func (export "morpheus_alloc") (param i32) (result i32) i32.const 1024)
+                 (func (export "morpheus_transform") (param i32 i32) (result i64)
+                   (i64.or (i64.shl (i64.const 16) (i64.const 32)) (i64.const 7))))"#,
+            n
+        );
+        HeadlessComponent::load(&wat::parse_str(wat).unwrap()).unwrap()
+    }
+
+    fn version(prompt: &str) -> HeadlessVersion {
+        HeadlessVersion {
+            id: 0,
+            prompt: prompt.to_string(),
+            rust_code: String::new(),
+            wasm_size: 0,
+            created_at: Utc::now(),
+            provenance: Provenance::default(),
+            wasm_bytes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_new_versions_are_hot_reloaded() {
+        let mut registry = HeadlessRegistry::default();
+
+        assert_eq!(registry.activate_new("score", HeadlessKind::Transform, version("a"), constant(1)), 0);
+        assert_eq!(registry.activate_new("score", HeadlessKind::Transform, version("b"), constant(2)), 1);
+
+        let module = registry.module("score").unwrap();
+        assert_eq!(module.transform(&Value::Null).unwrap(), serde_json::json!({ "v": 2 }));
+        let summary = registry.summary("score").unwrap();
+        assert_eq!((summary.version_id, summary.versions), (1, 2));
+        assert_eq!(summary.route, "/api/headless/score/transform");
+        assert!(registry.module("missing").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_components_and_versions_are_not_found() {
+        let state = AppState::for_tests().await;
+        state.headless.lock().await.activate_new("score", HeadlessKind::Transform, version("a"), constant(1));
+        let rollback = |name: &str, version_id| {
+            let request = HeadlessRollbackRequest { version_id };
+            rollback_headless(State(state.clone()), Path(name.to_string()), Json(request))
+        };
+
+        assert!(matches!(rollback("missing", Some(0)).await, Err(AppError::NotFound(_))));
+        let response = rollback("score", Some(7)).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_component_response_headers_are_forwarded() {
+        let response = into_response(HttpResponse {
+            status: 201,
+            headers: BTreeMap::from([
+                ("content-type".to_string(), "application/json".to_string()),
+                ("bad header".to_string(), "dropped".to_string()),
+            ]),
+            body: "{}".to_string(),
+        });
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        assert_eq!(response.headers().len(), 1);
+    }
+}
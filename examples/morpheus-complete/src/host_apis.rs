@@ -0,0 +1,420 @@
+//! Clipboard, file, notification and location access for components,
+//! through the permission broker.
+//!
+//! `GET /api/permissions` tells the browser which sensitive host imports a
+//! version may call (`morpheus.clipboardRead`, `clipboardWrite`,
+//! `pickFiles`, `notify`, `getPosition`), as decided by a
+//! [`PermissionBroker`] over the version's manifest permissions. Everything
+//! is denied unless the manifest grants `Clipboard`, `Files`,
+//! `Notifications` or `Geolocation`. The browser enforces the
+//! decisions, asks the user before the first clipboard read, and reports
+//! each call to `POST /api/permissions/requests`, which lands in the audit
+//! log.
+//!
+//! Notifications go through the server first: `POST /api/notifications`
+//! checks the permission and the component's rate limit, records the
+//! notification in the audit log, and tells the browser whether to show it.
+//! Positions do too: the browser sends what `navigator.geolocation` reported
+//! to `POST /api/geolocation`, and hands the component the position that
+//! comes back, coarsened, cached and rate limited by a
+//! [`LocationGate`](morpheus_core::broker::LocationGate).
+//!
+//! `GET /api/sandbox/frame` serves the document untrusted components run in
+//! when the page is in sandbox mode (`?sandbox=1`): a frame on an opaque
+//! origin whose Content Security Policy only lets the component connect to
+//! the hosts its network permissions allow (see [`morpheus_runtime::sandbox`]).
+
+use crate::{record_audit, AppError, AppState};
+use axum::extract::{Query, State};
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::Json;
+use morpheus_core::broker::{ApiStatus, Decision, HostApi, PermissionBroker, Position};
+use morpheus_core::permissions::Permissions;
+use morpheus_runtime::sandbox::{frame_csp, frame_html};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// Component name used when no version is live
+const DEFAULT_COMPONENT: &str = "main";
+
+/// Query for `GET /api/permissions`
+#[derive(Deserialize, JsonSchema)]
+pub struct PermissionsQuery {
+    /// Version to check (defaults to the live one)
+    pub version_id: Option<usize>,
+}
+
+/// What a version may do
+#[derive(Serialize, JsonSchema)]
+pub struct HostPermissions {
+    pub version_id: Option<usize>,
+    /// Permissions granted by the version's manifest
+    pub permissions: Permissions,
+    /// Decision on each sensitive host import
+    pub apis: Vec<ApiStatus>,
+}
+
+/// A host API call the browser allowed or refused
+#[derive(Deserialize, JsonSchema)]
+pub struct HostApiRequest {
+    pub api: HostApi,
+    pub version_id: Option<usize>,
+    pub allowed: bool,
+    /// Why it was refused, e.g. the user declined
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// A notification a component asked to show
+#[derive(Deserialize, JsonSchema)]
+pub struct NotifyRequest {
+    /// Version showing it (defaults to the live one)
+    pub version_id: Option<usize>,
+    /// Title, for the audit log; the body stays in the browser
+    pub title: String,
+}
+
+/// Whether the browser may show a notification
+#[derive(Serialize, JsonSchema)]
+pub struct NotifyResult {
+    pub shown: bool,
+    /// Why not, e.g. the component is over its rate limit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// A position the browser reported for a component
+#[derive(Deserialize, JsonSchema)]
+pub struct LocateRequest {
+    /// Version asking (defaults to the live one)
+    pub version_id: Option<usize>,
+    #[serde(flatten)]
+    pub position: Position,
+}
+
+/// The position to hand the component, if any
+#[derive(Serialize, JsonSchema)]
+pub struct LocateResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<Position>,
+    /// Why not, e.g. the component is over its rate limit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// The version's ID, component name and manifest permissions
+async fn version_permissions(
+    state: &AppState,
+    version_id: Option<usize>,
+) -> Result<(Option<usize>, String, Permissions), AppError> {
+    let history = state.versions.lock().await;
+    let version = match version_id {
+        Some(id) => Some(
+            history
+                .versions
+                .get(id)
+                .ok_or_else(|| AppError::ApiError(format!("Version {} not found", id)))?,
+        ),
+        None => history.get_current(),
+    };
+    Ok(match version {
+        Some(v) => (Some(v.id), v.name.clone(), v.manifest.permissions.clone()),
+        None => (None, DEFAULT_COMPONENT.to_string(), Permissions::default()),
+    })
+}
+
+/// Which sensitive host imports a version may call
+pub async fn get_permissions(
+    State(state): State<AppState>,
+    Query(query): Query<PermissionsQuery>,
+) -> Result<Json<HostPermissions>, AppError> {
+    let (version_id, _, permissions) = version_permissions(&state, query.version_id).await?;
+    Ok(Json(HostPermissions {
+        version_id,
+        apis: PermissionBroker::new(permissions.clone()).statuses(),
+        permissions,
+    }))
+}
+
+/// Check and record a notification; the browser shows it only if allowed
+pub async fn notify(
+    State(state): State<AppState>,
+    Json(req): Json<NotifyRequest>,
+) -> Result<Json<NotifyResult>, AppError> {
+    let (version_id, component, permissions) = version_permissions(&state, req.version_id).await?;
+    let reason = match PermissionBroker::new(permissions).decide(HostApi::Notify) {
+        Decision::Deny { reason } => Some(reason),
+        // The browser asked for the user's consent before calling
+        Decision::Allow | Decision::Confirm => state
+            .notifications
+            .lock()
+            .await
+            .check(&component, Instant::now())
+            .err()
+            .map(|e| e.to_string()),
+    };
+    let (outcome, detail) = match &reason {
+        None => ("shown", format!("{}: {}", component, req.title)),
+        Some(reason) => ("denied", format!("{}: {} ({})", component, req.title, reason)),
+    };
+    record_audit(&state, "notification", version_id, outcome, detail).await;
+    Ok(Json(NotifyResult {
+        shown: reason.is_none(),
+        reason,
+    }))
+}
+
+/// Coarsen a reported position to the version's granted precision, subject
+/// to the location gate's cache and rate limit
+pub async fn locate(
+    State(state): State<AppState>,
+    Json(req): Json<LocateRequest>,
+) -> Result<Json<LocateResult>, AppError> {
+    let (version_id, component, permissions) = version_permissions(&state, req.version_id).await?;
+    let precision = permissions.geolocation;
+    let located = match PermissionBroker::new(permissions).decide(HostApi::GetPosition) {
+        Decision::Deny { reason } => Err(reason),
+        // The browser asked for the user's consent before calling
+        Decision::Allow | Decision::Confirm => state
+            .locations
+            .lock()
+            .await
+            .locate(&component, precision, req.position, Instant::now())
+            .map_err(|e| e.to_string()),
+    };
+    // Coordinates stay out of the audit log
+    let (outcome, detail) = match &located {
+        Ok(located) if located.cached => ("cached", format!("{} ({:?})", component, precision)),
+        Ok(_) => ("located", format!("{} ({:?})", component, precision)),
+        Err(reason) => ("denied", format!("{}: {}", component, reason)),
+    };
+    record_audit(&state, "geolocation", version_id, outcome, detail).await;
+    Ok(Json(match located {
+        Ok(located) => LocateResult {
+            position: Some(located.position),
+            reason: None,
+        },
+        Err(reason) => LocateResult {
+            position: None,
+            reason: Some(reason),
+        },
+    }))
+}
+
+/// The sandbox frame for a version, with a fresh script nonce and the CSP
+/// its permissions allow
+pub async fn get_sandbox_frame(
+    State(state): State<AppState>,
+    Query(query): Query<PermissionsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let (_, _, permissions) = version_permissions(&state, query.version_id).await?;
+    let nonce = uuid::Uuid::new_v4().simple().to_string();
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/html; charset=utf-8".to_string()),
+            (header::CONTENT_SECURITY_POLICY, frame_csp(&permissions, &nonce)),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+            (header::REFERRER_POLICY, "no-referrer".to_string()),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        frame_html(&nonce),
+    ))
+}
+
+/// Record a host API call in the audit log
+pub async fn record_request(
+    State(state): State<AppState>,
+    Json(req): Json<HostApiRequest>,
+) -> Json<serde_json::Value> {
+    let outcome = if req.allowed { "allowed" } else { "denied" };
+    let mut detail = req.api.import().to_string();
+    if let Some(reason) = req.reason {
+        detail = format!("{}: {}", detail, reason);
+    }
+    record_audit(&state, "host_api", req.version_id, outcome, detail).await;
+    Json(serde_json::json!({ "success": true }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morpheus_core::broker::{CITY_ACCURACY_METERS, DEFAULT_NOTIFICATIONS_PER_WINDOW};
+    use morpheus_core::manifest::ComponentManifest;
+    use morpheus_core::permissions::ApiPermission;
+    use morpheus_core::component::Provenance;
+
+    /// Add a live version of `name` granted `apis`
+    async fn add_version(state: &AppState, name: &str, apis: &[ApiPermission]) -> usize {
+        let mut manifest = ComponentManifest::new(name, "A component");
+        manifest.permissions.apis = apis.iter().cloned().collect();
+        state.versions.lock().await.add_version(
+            name.to_string(),
+            "A component".to_string(),
+            "pub fn render() {}".to_string(),
+            wat::parse_str(r#"(module (func (export "render")))"#).unwrap(),
+            String::new(),
+            false,
+            manifest,
+            Provenance::default(),
+            true,
+        )
+    }
+
+    async fn last_audit(state: &AppState) -> (String, Option<usize>, String, String) {
+        let log = state.audit_log.lock().await;
+        let entry = log.last().expect("an audit entry");
+        (entry.action.clone(), entry.version_id, entry.outcome.clone(), entry.detail.clone())
+    }
+
+    #[tokio::test]
+    async fn test_everything_is_denied_without_a_version() {
+        let state = AppState::for_tests().await;
+
+        let Json(granted) = get_permissions(State(state.clone()), Query(PermissionsQuery { version_id: None }))
+            .await
+            .unwrap();
+
+        assert_eq!(granted.version_id, None);
+        assert_eq!(granted.apis.len(), HostApi::ALL.len());
+        assert!(granted.apis.iter().all(|status| matches!(status.decision, Decision::Deny { .. })));
+        let missing = get_permissions(State(state), Query(PermissionsQuery { version_id: Some(3) })).await;
+        assert!(missing.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_only_granted_apis_are_allowed() {
+        let state = AppState::for_tests().await;
+        let id = add_version(&state, "alerts", &[ApiPermission::Notifications]).await;
+
+        let Json(granted) = get_permissions(State(state), Query(PermissionsQuery { version_id: Some(id) }))
+            .await
+            .unwrap();
+
+        for status in granted.apis {
+            let denied = matches!(status.decision, Decision::Deny { .. });
+            assert_eq!(denied, status.api != HostApi::Notify, "{:?}", status.api);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notifications_need_the_permission() {
+        let state = AppState::for_tests().await;
+        let id = add_version(&state, "quiet", &[]).await;
+        let request = NotifyRequest {
+            version_id: Some(id),
+            title: "Hello".to_string(),
+        };
+
+        let Json(result) = notify(State(state.clone()), Json(request)).await.unwrap();
+
+        assert!(!result.shown);
+        assert!(result.reason.is_some());
+        let (action, version_id, outcome, detail) = last_audit(&state).await;
+        assert_eq!((action.as_str(), version_id, outcome.as_str()), ("notification", Some(id), "denied"));
+        assert!(detail.starts_with("quiet: Hello ("), "{}", detail);
+    }
+
+    #[tokio::test]
+    async fn test_notifications_are_rate_limited_per_component() {
+        let state = AppState::for_tests().await;
+        let id = add_version(&state, "chatty", &[ApiPermission::Notifications]).await;
+        let send = || {
+            let request = NotifyRequest {
+                version_id: Some(id),
+                title: "Ping".to_string(),
+            };
+            notify(State(state.clone()), Json(request))
+        };
+
+        for _ in 0..DEFAULT_NOTIFICATIONS_PER_WINDOW {
+            let Json(result) = send().await.unwrap();
+            assert!(result.shown);
+            assert_eq!(last_audit(&state).await.2, "shown");
+        }
+        let Json(result) = send().await.unwrap();
+
+        assert!(!result.shown);
+        assert_eq!(last_audit(&state).await.2, "denied");
+    }
+
+    #[tokio::test]
+    async fn test_positions_are_coarsened_and_kept_out_of_the_audit_log() {
+        let state = AppState::for_tests().await;
+        let id = add_version(&state, "weather", &[ApiPermission::Geolocation]).await;
+        let reported = Position {
+            latitude: 51.50735,
+            longitude: -0.12776,
+            accuracy: 12.0,
+        };
+        let request = LocateRequest {
+            version_id: Some(id),
+            position: reported,
+        };
+
+        let Json(result) = locate(State(state.clone()), Json(request)).await.unwrap();
+
+        let position = result.position.expect("a position");
+        assert_ne!(position, reported);
+        assert_eq!(position.accuracy, CITY_ACCURACY_METERS);
+        let (action, _, outcome, detail) = last_audit(&state).await;
+        assert_eq!((action.as_str(), outcome.as_str()), ("geolocation", "located"));
+        assert!(!detail.contains("51.5"), "{}", detail);
+    }
+
+    #[tokio::test]
+    async fn test_positions_need_the_permission() {
+        let state = AppState::for_tests().await;
+        let id = add_version(&state, "nosy", &[]).await;
+        let request = LocateRequest {
+            version_id: Some(id),
+            position: Position {
+                latitude: 0.0,
+                longitude: 0.0,
+                accuracy: 1.0,
+            },
+        };
+
+        let Json(result) = locate(State(state.clone()), Json(request)).await.unwrap();
+
+        assert!(result.position.is_none());
+        assert!(result.reason.is_some());
+        assert_eq!(last_audit(&state).await.2, "denied");
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_frame_carries_its_policy() {
+        let state = AppState::for_tests().await;
+
+        let response = get_sandbox_frame(State(state), Query(PermissionsQuery { version_id: None }))
+            .await
+            .unwrap()
+            .into_response();
+
+        let headers = response.headers();
+        let csp = headers[header::CONTENT_SECURITY_POLICY].to_str().unwrap();
+        assert!(csp.contains("'nonce-"), "{}", csp);
+        assert_eq!(headers[header::CACHE_CONTROL], "no-store");
+        assert_eq!(headers[header::REFERRER_POLICY], "no-referrer");
+    }
+
+    #[tokio::test]
+    async fn test_browser_decisions_are_audited_with_their_reason() {
+        let state = AppState::for_tests().await;
+        let request = HostApiRequest {
+            api: HostApi::ClipboardRead,
+            version_id: Some(2),
+            allowed: false,
+            reason: Some("user declined".to_string()),
+        };
+
+        let Json(response) = record_request(State(state.clone()), Json(request)).await;
+
+        assert_eq!(response["success"], true);
+
+        let (action, version_id, outcome, detail) = last_audit(&state).await;
+        assert_eq!((action.as_str(), version_id, outcome.as_str()), ("host_api", Some(2), "denied"));
+        assert_eq!(detail, format!("{}: user declined", HostApi::ClipboardRead.import()));
+    }
+}
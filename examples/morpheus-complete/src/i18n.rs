@@ -0,0 +1,219 @@
+//! Translations of components' text
+//!
+//! The system prompt has the AI pass every user-visible string through the
+//! `morpheus.t(key, fallback, argsJson)` host import (see
+//! [`morpheus_core::i18n`]) instead of hard-coding English, so a component
+//! can be shown in another language without regenerating it. The English
+//! defaults are read from the live components' source; translators add the
+//! rest:
+//!
+//! - `GET /api/i18n`: the keys in use, and for each locale which of them
+//!   are still untranslated
+//! - `GET /api/i18n/{locale}`: every message in a locale, falling back to
+//!   its language and then to English; what hosts answer `morpheus.t` from
+//! - `POST /api/i18n/{locale}`: add or change translations
+//!
+//! With `MORPHEUS_LOCALES_DIR` set, catalogs are read from the
+//! `<locale>.json` files there (an object of messages by key) at startup,
+//! and written back when they change.
+
+use crate::{AppError, AppState};
+use axum::extract::{Path, State};
+use axum::Json;
+use morpheus_core::i18n::{self, Catalogs, MessageCatalog, DEFAULT_LOCALE};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Longest locale tag accepted
+const MAX_LOCALE_LEN: usize = 35;
+
+/// Translations by locale, and where they are kept
+#[derive(Default)]
+pub struct Translations {
+    catalogs: Catalogs,
+    dir: Option<PathBuf>,
+}
+
+impl Translations {
+    /// Read the catalogs in `MORPHEUS_LOCALES_DIR`, if set
+    pub fn from_env() -> anyhow::Result<Self> {
+        match std::env::var("MORPHEUS_LOCALES_DIR") {
+            Ok(dir) => Self::load(dir.into()),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Read every `<locale>.json` catalog in `dir`, which is created if
+    /// needed
+    pub fn load(dir: PathBuf) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        let mut catalogs = Catalogs::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let Some(locale) = path.file_stem().and_then(|stem| stem.to_str()).filter(|l| valid_locale(l)) else {
+                continue;
+            };
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let messages: BTreeMap<String, String> = serde_json::from_slice(&std::fs::read(&path)?)
+                .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+            catalogs.insert(MessageCatalog {
+                locale: locale.to_string(),
+                messages,
+            });
+        }
+        Ok(Self {
+            catalogs,
+            dir: Some(dir),
+        })
+    }
+
+    /// The translations
+    pub fn catalogs(&self) -> &Catalogs {
+        &self.catalogs
+    }
+
+    /// Add `messages` to `locale`'s catalog and save it
+    async fn update(&mut self, locale: &str, messages: BTreeMap<String, String>) -> std::io::Result<MessageCatalog> {
+        self.catalogs.insert(MessageCatalog {
+            locale: locale.to_string(),
+            messages,
+        });
+        let catalog = self.catalogs.get(locale).cloned().unwrap_or_else(|| MessageCatalog::new(locale));
+        if let Some(dir) = &self.dir {
+            let json = serde_json::to_vec_pretty(&catalog.messages).map_err(std::io::Error::other)?;
+            tokio::fs::write(dir.join(format!("{}.json", locale)), json).await?;
+        }
+        Ok(catalog)
+    }
+}
+
+/// Whether `locale` looks like a language tag (`en`, `pt-BR`, `zh_Hant`);
+/// it names a file, so nothing else is allowed
+pub fn valid_locale(locale: &str) -> bool {
+    !locale.is_empty()
+        && locale.len() <= MAX_LOCALE_LEN
+        && locale.starts_with(|c: char| c.is_ascii_alphabetic())
+        && locale.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// English texts of every key the live components use
+pub async fn defaults(state: &AppState) -> MessageCatalog {
+    let history = state.versions.lock().await;
+    let mut defaults = MessageCatalog::new(DEFAULT_LOCALE);
+    for version in history.active_versions() {
+        for (key, text) in i18n::extract(&version.rust_code).messages {
+            defaults.messages.entry(key).or_insert(text);
+        }
+    }
+    defaults
+}
+
+/// How far a locale's translation has got
+#[derive(Serialize, JsonSchema)]
+pub struct LocaleStatus {
+    pub locale: String,
+    /// Keys in use that the locale (or its language) translates
+    pub translated: usize,
+    /// Keys in use that it doesn't, shown in English
+    pub missing: Vec<String>,
+}
+
+/// Response for `GET /api/i18n`
+#[derive(Serialize, JsonSchema)]
+pub struct TranslationsOverview {
+    /// The keys the live components use, with their English text
+    pub defaults: MessageCatalog,
+    /// Locales with translations
+    pub locales: Vec<LocaleStatus>,
+}
+
+/// Request for `POST /api/i18n/{locale}`
+#[derive(Deserialize, JsonSchema)]
+pub struct UpdateTranslationsRequest {
+    /// Messages by key, replacing any the locale already has
+    pub messages: BTreeMap<String, String>,
+}
+
+/// The keys in use and how far each locale has got
+pub async fn get_translations(State(state): State<AppState>) -> Json<TranslationsOverview> {
+    let defaults = defaults(&state).await;
+    let translations = state.translations.lock().await;
+    let catalogs = translations.catalogs();
+    let locales = catalogs
+        .locales()
+        .into_iter()
+        .map(|locale| {
+            let missing = catalogs.missing(locale, &defaults);
+            LocaleStatus {
+                locale: locale.to_string(),
+                translated: defaults.messages.len() - missing.len(),
+                missing,
+            }
+        })
+        .collect();
+    Json(TranslationsOverview { defaults, locales })
+}
+
+/// Every message in a locale, for hosts to answer `morpheus.t` from
+pub async fn get_locale(
+    State(state): State<AppState>,
+    Path(locale): Path<String>,
+) -> Result<Json<MessageCatalog>, AppError> {
+    if !valid_locale(&locale) {
+        return Err(AppError::ApiError(format!("Invalid locale: {}", locale)));
+    }
+    let defaults = defaults(&state).await;
+    Ok(Json(state.translations.lock().await.catalogs().resolve(&locale, &defaults)))
+}
+
+/// Add or change a locale's translations, returning all of them
+pub async fn update_locale(
+    State(state): State<AppState>,
+    Path(locale): Path<String>,
+    Json(req): Json<UpdateTranslationsRequest>,
+) -> Result<Json<MessageCatalog>, AppError> {
+    if !valid_locale(&locale) {
+        return Err(AppError::ApiError(format!("Invalid locale: {}", locale)));
+    }
+    let catalog = state.translations.lock().await.update(&locale, req.messages).await.map_err(|e| {
+        warn!(locale = %locale, "Failed to save translations: {}", e);
+        AppError::ApiError(format!("Failed to save translations: {}", e))
+    })?;
+    Ok(Json(catalog))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locales_must_be_language_tags() {
+        assert!(valid_locale("en"));
+        assert!(valid_locale("pt-BR"));
+        assert!(valid_locale("zh_Hant"));
+        assert!(!valid_locale(""));
+        assert!(!valid_locale("../secrets"));
+        assert!(!valid_locale("-en"));
+        assert!(!valid_locale(&"a".repeat(MAX_LOCALE_LEN + 1)));
+    }
+
+    #[tokio::test]
+    async fn test_catalogs_are_saved_and_loaded_by_locale() {
+        let dir = std::env::temp_dir().join(format!("morpheus-locales-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut translations = Translations::load(dir.clone()).unwrap();
+        let messages = BTreeMap::from([("todo.add".to_string(), "Ajouter".to_string())]);
+        translations.update("fr", messages).await.unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a catalog").unwrap();
+
+        let loaded = Translations::load(dir.clone()).unwrap();
+        assert_eq!(loaded.catalogs().locales(), vec!["fr"]);
+        assert_eq!(loaded.catalogs().get("fr").unwrap().get("todo.add"), Some("Ajouter"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
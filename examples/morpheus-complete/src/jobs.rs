@@ -0,0 +1,501 @@
+//! Generation jobs.
+//!
+//! A generation takes as long as the AI and the compiler need, often
+//! minutes over several attempts. `POST /api/jobs` queues one and answers
+//! right away with a job ID; clients poll `GET /api/jobs/{id}` or follow
+//! `GET /api/jobs/{id}/events` as it moves through `queued`, `awaiting_ai`,
+//! `compiling` and `saving` (once per attempt) to `done` or `failed`.
+//! `DELETE /api/jobs/{id}` cancels it until it starts saving.
+//!
+//! Jobs run one at a time, in order, since they share the AI conversation.
+//! `POST /api/generate` still blocks: it queues a job and waits for it.
+
+use crate::limits::LimitError;
+use crate::{generate, screen_request, AppError, AppState, GenerateRequest, GenerateResponse};
+use axum::{
+    extract::{Path, State},
+    response::sse::{Event, KeepAlive, Sse},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, Stream};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::{broadcast, oneshot, Mutex, Notify};
+use tokio::task::AbortHandle;
+use tracing::{info, info_span, warn, Instrument};
+
+/// Jobs waiting to run before new ones are refused
+pub const MAX_QUEUED_JOBS: usize = 32;
+
+/// Finished jobs kept for polling
+const FINISHED_JOBS_KEPT: usize = 100;
+
+/// Where a job is in the pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for earlier jobs
+    Queued,
+    /// Waiting for the AI to write code
+    AwaitingAi,
+    /// Compiling what the AI wrote
+    Compiling,
+    /// Saving and loading the new version; can no longer be cancelled
+    Saving,
+    /// Finished; `result` says whether a version was produced
+    Done,
+    /// Stopped by an error, see `error`
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job will change no further
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Done | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+/// A queued or finished generation
+#[derive(Clone, Serialize, JsonSchema)]
+pub struct Job {
+    pub id: String,
+    pub status: JobStatus,
+    /// Current attempt, from 1 (0 while queued)
+    pub iteration: u32,
+    /// Jobs ahead of this one while queued
+    pub position: Option<usize>,
+    pub prompt: String,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Outcome once `done`
+    pub result: Option<GenerateResponse>,
+    /// Why the job failed
+    pub error: Option<String>,
+}
+
+/// How busy the queue is
+#[derive(Clone, Serialize, JsonSchema)]
+pub struct QueueDepth {
+    /// Jobs waiting to run
+    pub queued: usize,
+    /// Jobs started and not yet finished
+    pub running: usize,
+    /// Queued jobs accepted before new ones are refused
+    pub capacity: usize,
+}
+
+/// A job and what's needed to run, cancel and answer it
+struct JobEntry {
+    job: Job,
+    /// Taken when the job starts
+    request: Option<GenerateRequest>,
+    /// Where a blocking `POST /api/generate` waits for the outcome
+    reply: Option<oneshot::Sender<Result<GenerateResponse, AppError>>>,
+    abort: Option<AbortHandle>,
+}
+
+#[derive(Default)]
+struct Queue {
+    entries: BTreeMap<String, JobEntry>,
+    /// IDs of queued jobs, next first
+    pending: VecDeque<String>,
+    /// IDs of finished jobs, oldest first
+    finished: VecDeque<String>,
+}
+
+impl Queue {
+    fn snapshot(&self, id: &str) -> Option<Job> {
+        let mut job = self.entries.get(id)?.job.clone();
+        job.position = self.pending.iter().position(|pending| pending == id);
+        Some(job)
+    }
+
+    fn finish(&mut self, id: &str) {
+        self.finished.push_back(id.to_string());
+        while self.finished.len() > FINISHED_JOBS_KEPT {
+            if let Some(old) = self.finished.pop_front() {
+                self.entries.remove(&old);
+            }
+        }
+    }
+}
+
+/// The job queue
+pub struct Jobs {
+    queue: Mutex<Queue>,
+    /// Wakes the worker when a job is queued
+    wake: Notify,
+    /// Every change to a job
+    updates: broadcast::Sender<Job>,
+}
+
+impl Default for Jobs {
+    fn default() -> Self {
+        Self {
+            queue: Mutex::new(Queue::default()),
+            wake: Notify::new(),
+            updates: broadcast::channel(64).0,
+        }
+    }
+}
+
+impl Jobs {
+    /// Queue a generation; with `wait`, also return where its outcome arrives
+    pub async fn enqueue(
+        &self,
+        request: GenerateRequest,
+        wait: bool,
+    ) -> Result<(Job, Option<oneshot::Receiver<Result<GenerateResponse, AppError>>>), AppError> {
+        let mut queue = self.queue.lock().await;
+        if queue.pending.len() >= MAX_QUEUED_JOBS {
+            return Err(LimitError::QueueFull { limit: MAX_QUEUED_JOBS }.into());
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        let (reply, receiver) = match wait {
+            true => {
+                let (reply, receiver) = oneshot::channel();
+                (Some(reply), Some(receiver))
+            }
+            false => (None, None),
+        };
+        let job = Job {
+            id: id.clone(),
+            status: JobStatus::Queued,
+            iteration: 0,
+            position: None,
+            prompt: request.prompt.clone(),
+            created_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+            result: None,
+            error: None,
+        };
+        queue.entries.insert(
+            id.clone(),
+            JobEntry {
+                job,
+                request: Some(request),
+                reply,
+                abort: None,
+            },
+        );
+        queue.pending.push_back(id.clone());
+        let job = queue.snapshot(&id).expect("job was just queued");
+        drop(queue);
+
+        let _ = self.updates.send(job.clone());
+        self.wake.notify_one();
+        Ok((job, receiver))
+    }
+
+    /// A job's current state
+    pub async fn get(&self, id: &str) -> Option<Job> {
+        self.queue.lock().await.snapshot(id)
+    }
+
+    /// Jobs queued and running
+    pub async fn depth(&self) -> QueueDepth {
+        let queue = self.queue.lock().await;
+        let running = queue
+            .entries
+            .values()
+            .filter(|e| e.job.started_at.is_some() && !e.job.status.is_finished())
+            .count();
+        QueueDepth {
+            queued: queue.pending.len(),
+            running,
+            capacity: MAX_QUEUED_JOBS,
+        }
+    }
+
+    /// Finished jobs still kept, newest first
+    pub async fn recent(&self) -> Vec<Job> {
+        let queue = self.queue.lock().await;
+        queue.finished.iter().rev().filter_map(|id| queue.snapshot(id)).collect()
+    }
+
+    /// Move a running job to `status`
+    pub async fn update(&self, id: &str, status: JobStatus, iteration: u32) {
+        let mut queue = self.queue.lock().await;
+        let Some(entry) = queue.entries.get_mut(id).filter(|e| !e.job.status.is_finished()) else { return };
+        entry.job.status = status;
+        entry.job.iteration = iteration;
+        let job = entry.job.clone();
+        drop(queue);
+        let _ = self.updates.send(job);
+    }
+
+    /// Cancel a job that hasn't started saving
+    pub async fn cancel(&self, id: &str) -> Result<Job, AppError> {
+        let mut queue = self.queue.lock().await;
+        let entry = queue
+            .entries
+            .get_mut(id)
+            .ok_or_else(|| AppError::NotFound(format!("Job {} not found", id)))?;
+        if entry.job.status.is_finished() || entry.job.status == JobStatus::Saving {
+            return Err(AppError::ApiError(format!("Job {} is already saving or finished", id)));
+        }
+        if let Some(abort) = entry.abort.take() {
+            abort.abort();
+        }
+        entry.job.status = JobStatus::Cancelled;
+        entry.job.finished_at = Some(Utc::now());
+        // Dropping the reply tells a waiting `POST /api/generate`
+        entry.reply = None;
+        entry.request = None;
+        queue.pending.retain(|pending| pending != id);
+        queue.finish(id);
+        let job = queue.snapshot(id).expect("cancelled job is kept");
+        drop(queue);
+
+        let _ = self.updates.send(job.clone());
+        Ok(job)
+    }
+
+    /// Wait for the next queued job
+    async fn next(&self) -> (String, GenerateRequest) {
+        loop {
+            let mut queue = self.queue.lock().await;
+            while let Some(id) = queue.pending.pop_front() {
+                let request = queue.entries.get_mut(&id).and_then(|entry| {
+                    entry.job.started_at = Some(Utc::now());
+                    entry.request.take()
+                });
+                if let Some(request) = request {
+                    return (id, request);
+                }
+            }
+            drop(queue);
+            self.wake.notified().await;
+        }
+    }
+
+    /// Remember how to stop a started job, stopping it at once if it was
+    /// cancelled in the meantime
+    async fn started(&self, id: &str, abort: AbortHandle) {
+        let mut queue = self.queue.lock().await;
+        match queue.entries.get_mut(id) {
+            Some(entry) if entry.job.status != JobStatus::Cancelled => entry.abort = Some(abort),
+            _ => abort.abort(),
+        }
+    }
+
+    /// Record a job's outcome and answer anyone waiting for it
+    async fn finished(&self, id: &str, outcome: Result<GenerateResponse, AppError>) {
+        let mut queue = self.queue.lock().await;
+        let Some(entry) = queue.entries.get_mut(id) else { return };
+        if entry.job.status == JobStatus::Cancelled {
+            return;
+        }
+        entry.abort = None;
+        entry.job.finished_at = Some(Utc::now());
+        match &outcome {
+            Ok(response) => {
+                entry.job.status = JobStatus::Done;
+                entry.job.result = Some(response.clone());
+            }
+            Err(e) => {
+                entry.job.status = JobStatus::Failed;
+                entry.job.error = Some(e.to_string());
+            }
+        }
+        let job = entry.job.clone();
+        if let Some(reply) = entry.reply.take() {
+            let _ = reply.send(outcome);
+        }
+        queue.finish(id);
+        drop(queue);
+        let _ = self.updates.send(job);
+    }
+}
+
+/// Reports a running job's progress
+pub struct Progress {
+    jobs: Arc<Jobs>,
+    id: String,
+}
+
+impl Progress {
+    pub async fn set(&self, status: JobStatus, iteration: u32) {
+        self.jobs.update(&self.id, status, iteration).await;
+    }
+}
+
+/// Run queued jobs one at a time
+pub async fn run_worker(state: AppState) {
+    loop {
+        let (id, request) = state.jobs.next().await;
+        // Everything logged while the job runs carries its ID and component
+        let span = info_span!(
+            "job",
+            job_id = %id,
+            component_id = request.component.as_deref().unwrap_or("main")
+        );
+        span.in_scope(|| info!("📋 Starting job"));
+
+        let progress = Progress {
+            jobs: state.jobs.clone(),
+            id: id.clone(),
+        };
+        let task = tokio::spawn({
+            let state = state.clone();
+            async move { generate(&state, request, &progress).await }.instrument(span.clone())
+        });
+        state.jobs.started(&id, task.abort_handle()).await;
+
+        match task.await {
+            Ok(outcome) => state.jobs.finished(&id, outcome).await,
+            Err(e) if e.is_cancelled() => span.in_scope(|| info!("📋 Job cancelled")),
+            Err(e) => {
+                span.in_scope(|| warn!("Job panicked: {}", e));
+                let outcome = Err(AppError::ApiError("Generation failed unexpectedly".to_string()));
+                state.jobs.finished(&id, outcome).await;
+            }
+        }
+    }
+}
+
+/// Queue a generation
+pub async fn create_job(State(state): State<AppState>, Json(req): Json<GenerateRequest>) -> Result<Json<Job>, AppError> {
+    screen_request(&state, "generate", &req.prompt).await?;
+    let (job, _) = state.jobs.enqueue(req, false).await?;
+    info!(job_id = %job.id, "📋 Queued job: {}", job.prompt);
+    Ok(Json(job))
+}
+
+/// A job's status, and its outcome once finished
+pub async fn get_job(State(state): State<AppState>, Path(id): Path<String>) -> Result<Json<Job>, AppError> {
+    state
+        .jobs
+        .get(&id)
+        .await
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Job {} not found", id)))
+}
+
+/// Cancel a queued or running job
+pub async fn cancel_job(State(state): State<AppState>, Path(id): Path<String>) -> Result<Json<Job>, AppError> {
+    let job = state.jobs.cancel(&id).await?;
+    info!(job_id = %id, "📋 Cancelled job");
+    Ok(Json(job))
+}
+
+/// Stream a job's status changes as server-sent `job` events, ending once
+/// it finishes
+pub async fn job_events(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    // Subscribe first so no change between the snapshot and the stream is lost
+    let updates = state.jobs.updates.subscribe();
+    let current = state
+        .jobs
+        .get(&id)
+        .await
+        .ok_or_else(|| AppError::NotFound(format!("Job {} not found", id)))?;
+
+    let events = stream::unfold((Some(current), updates, false), move |(next, mut updates, done)| {
+        let id = id.clone();
+        async move {
+            if done {
+                return None;
+            }
+            let job = match next {
+                Some(job) => job,
+                None => loop {
+                    match updates.recv().await {
+                        Ok(job) if job.id == id => break job,
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                },
+            };
+            let finished = job.status.is_finished();
+            let event = Event::default()
+                .event("job")
+                .json_data(&job)
+                .unwrap_or_else(|_| Event::default().event("job"));
+            Some((Ok(event), (None, updates, finished)))
+        }
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+
+    fn request(prompt: &str) -> GenerateRequest {
+        serde_json::from_value(serde_json::json!({ "prompt": prompt })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_jobs_run_in_order() {
+        let jobs = Jobs::default();
+        let (first, _) = jobs.enqueue(request("first"), false).await.unwrap();
+        let (second, _) = jobs.enqueue(request("second"), false).await.unwrap();
+        assert_eq!(first.position, Some(0));
+        assert_eq!(second.position, Some(1));
+
+        let (id, request) = jobs.next().await;
+        assert_eq!(id, first.id);
+        assert_eq!(request.prompt, "first");
+        assert_eq!(jobs.get(&second.id).await.unwrap().position, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_job_is_skipped_and_waiter_told() {
+        let jobs = Jobs::default();
+        let (first, reply) = jobs.enqueue(request("first"), true).await.unwrap();
+        let (second, _) = jobs.enqueue(request("second"), false).await.unwrap();
+
+        assert_eq!(jobs.cancel(&first.id).await.unwrap().status, JobStatus::Cancelled);
+        assert!(reply.unwrap().await.is_err());
+        assert!(jobs.cancel(&first.id).await.is_err());
+        assert_eq!(jobs.next().await.0, second.id);
+    }
+
+    #[tokio::test]
+    async fn test_saving_job_cannot_be_cancelled() {
+        let jobs = Jobs::default();
+        let (job, _) = jobs.enqueue(request("first"), false).await.unwrap();
+        jobs.next().await;
+        jobs.update(&job.id, JobStatus::Saving, 1).await;
+        assert!(jobs.cancel(&job.id).await.is_err());
+
+        jobs.finished(&job.id, Err(AppError::ApiError("no".to_string()))).await;
+        let job = jobs.get(&job.id).await.unwrap();
+        assert_eq!(job.status, JobStatus::Failed);
+        assert_eq!(job.error.as_deref(), Some("no"));
+    }
+
+    #[tokio::test]
+    async fn test_queue_is_bounded() {
+        let jobs = Jobs::default();
+        for i in 0..MAX_QUEUED_JOBS {
+            jobs.enqueue(request(&i.to_string()), false).await.unwrap();
+        }
+        assert!(matches!(
+            jobs.enqueue(request("one more"), false).await,
+            Err(AppError::Limited(LimitError::QueueFull { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_jobs_are_not_found() {
+        let state = AppState::for_tests().await;
+
+        let missing = get_job(State(state.clone()), Path("missing".to_string())).await;
+        assert_eq!(missing.into_response().status(), StatusCode::NOT_FOUND);
+        assert!(matches!(state.jobs.cancel("missing").await, Err(AppError::NotFound(_))));
+        assert!(job_events(State(state), Path("missing".to_string())).await.is_err());
+    }
+}
@@ -0,0 +1,117 @@
+//! Language intelligence for the code review pane
+//!
+//! Someone reviewing AI-generated source wants what their editor would give
+//! them: errors and warnings where they occur, an outline of what the code
+//! defines, and jump-to-definition. `POST /api/language` answers all three
+//! for a source text in one request. Symbols and definitions come from
+//! parsing alone ([`morpheus_compiler::symbols`]) and are instant;
+//! diagnostics need a type check ([`Compiler::diagnostics`]), which takes
+//! seconds, so they are only included when asked for.
+//!
+//! The version page (`GET /pages/history/{id}`) uses it to underline
+//! diagnostics and to jump to a definition on double-click.
+
+use crate::{AppError, AppState};
+use axum::extract::State;
+use axum::Json;
+use morpheus_compiler::symbols::{self, Symbol};
+use morpheus_compiler::{CompilationError, Compiler};
+use serde::{Deserialize, Serialize};
+
+/// Request for `POST /api/language`
+#[derive(Deserialize)]
+pub struct LanguageRequest {
+    pub source: String,
+    /// Also type-check the source, for diagnostics
+    #[serde(default)]
+    pub check: bool,
+    /// Check it as a headless component rather than a browser one
+    #[serde(default)]
+    pub headless: bool,
+    /// Cursor line (1-indexed), to find the definition of the name under it
+    pub line: Option<usize>,
+    /// Cursor column (1-indexed)
+    pub column: Option<usize>,
+}
+
+/// What the source defines, what's wrong with it, and where the name
+/// under the cursor is defined
+#[derive(Serialize)]
+pub struct LanguageResponse {
+    /// Items in file order, members nested under them; empty if the source
+    /// doesn't parse
+    pub symbols: Vec<Symbol>,
+    /// Every error and warning, when `check` was set
+    pub diagnostics: Vec<CompilationError>,
+    /// Definition of the name at `line` and `column`, if it is one of the
+    /// source's own items
+    pub definition: Option<Symbol>,
+}
+
+/// Symbols, diagnostics and definitions for a source text
+pub async fn analyze(
+    State(state): State<AppState>,
+    Json(req): Json<LanguageRequest>,
+) -> Result<Json<LanguageResponse>, AppError> {
+    let diagnostics = match (req.check, req.headless) {
+        (false, _) => Vec::new(),
+        (true, false) => state.compiler.diagnostics(&req.source).await?,
+        (true, true) => state.headless_compiler.diagnostics(&req.source).await?,
+    };
+    let definition = match (req.line, req.column) {
+        (Some(line), Some(column)) => symbols::definition(&req.source, line, column),
+        _ => None,
+    };
+    Ok(Json(LanguageResponse {
+        symbols: symbols::outline(&req.source).unwrap_or_default(),
+        diagnostics,
+        definition,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "struct Counter {
+    count: u32,
+}
+
+fn render() -> Counter {
+    Counter { count: 0 }
+}
+";
+
+    fn request(source: &str, cursor: Option<(usize, usize)>) -> LanguageRequest {
+        LanguageRequest {
+            source: source.to_string(),
+            check: false,
+            headless: false,
+            line: cursor.map(|(line, _)| line),
+            column: cursor.map(|(_, column)| column),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_outline_and_definition_come_without_a_type_check() {
+        let state = AppState::for_tests().await;
+
+        let Json(response) = analyze(State(state), Json(request(SOURCE, Some((6, 5))))).await.unwrap();
+
+        let outline: Vec<_> = response.symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(outline, [("Counter", "struct"), ("render", "fn")]);
+        assert!(response.diagnostics.is_empty());
+        let definition = response.definition.expect("a definition");
+        assert_eq!((definition.name.as_str(), definition.line, definition.column), ("Counter", 1, 8));
+    }
+
+    #[tokio::test]
+    async fn test_unparsable_source_has_no_symbols() {
+        let state = AppState::for_tests().await;
+
+        let Json(response) = analyze(State(state), Json(request("fn render( {", None))).await.unwrap();
+
+        assert!(response.symbols.is_empty());
+        assert!(response.definition.is_none());
+    }
+}
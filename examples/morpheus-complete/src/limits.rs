@@ -0,0 +1,399 @@
+//! Rate limits, concurrency caps and a daily AI budget.
+//!
+//! Every generation request spends money on the AI provider, so the
+//! endpoints that call the AI sit behind [`limit_generation`]:
+//!
+//! - Clients sending one of the API keys in `MORPHEUS_API_KEYS` as an
+//!   `X-Morpheus-Key` header are limited per key, others per IP address;
+//!   other keys are ignored, so making them up doesn't get a fresh quota
+//! - Only so many generations run at once
+//! - A daily token and cost budget is checked before each AI call (in
+//!   `complete()`, so background work like autonomous mode counts too)
+//!
+//! Refusals are JSON errors with a machine-readable `code`: `rate_limited`,
+//! `too_many_generations` and `queue_full` (429, with `Retry-After`) or
+//! `budget_exhausted` (402).
+
+use crate::{AppError, AppState};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use governor::clock::{Clock, DefaultClock};
+use governor::{DefaultKeyedRateLimiter, Quota, RateLimiter};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tracing::warn;
+
+/// Header identifying an API client for per-key limits
+pub const KEY_HEADER: &str = "x-morpheus-key";
+
+/// OpenRouter price of the generation model, USD per million input tokens
+const INPUT_USD_PER_MTOK: f64 = 3.0;
+
+/// OpenRouter price of the generation model, USD per million output tokens
+const OUTPUT_USD_PER_MTOK: f64 = 15.0;
+
+/// Rate limiter entries kept before idle ones are dropped
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Configured limits
+#[derive(Clone, Debug)]
+pub struct LimitsConfig {
+    /// Generation requests per minute from one IP address
+    pub per_ip_per_minute: NonZeroU32,
+    /// Generation requests per minute with one `X-Morpheus-Key`
+    pub per_key_per_minute: NonZeroU32,
+    /// Keys limited per key rather than per IP address
+    pub api_keys: Vec<String>,
+    /// Generations running at once
+    pub max_concurrent: usize,
+    /// AI tokens per UTC day
+    pub daily_tokens: Option<u64>,
+    /// AI spend per UTC day, in USD
+    pub daily_cost_usd: Option<f64>,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            per_ip_per_minute: NonZeroU32::new(10).unwrap(),
+            per_key_per_minute: NonZeroU32::new(30).unwrap(),
+            api_keys: Vec::new(),
+            max_concurrent: 4,
+            daily_tokens: None,
+            daily_cost_usd: None,
+        }
+    }
+}
+
+impl LimitsConfig {
+    /// Read limits from `MORPHEUS_RATE_LIMIT_PER_IP`,
+    /// `MORPHEUS_RATE_LIMIT_PER_KEY`, `MORPHEUS_API_KEYS` (comma-separated),
+    /// `MORPHEUS_MAX_CONCURRENT_GENERATIONS`, `MORPHEUS_DAILY_TOKEN_BUDGET`
+    /// and `MORPHEUS_DAILY_COST_BUDGET_USD`
+    pub fn from_env() -> anyhow::Result<Self> {
+        fn var<T: std::str::FromStr>(name: &str) -> anyhow::Result<Option<T>>
+        where
+            T::Err: std::error::Error + Send + Sync + 'static,
+        {
+            match std::env::var(name) {
+                Ok(value) => Ok(Some(value.parse()?)),
+                Err(_) => Ok(None),
+            }
+        }
+
+        let defaults = Self::default();
+        Ok(Self {
+            per_ip_per_minute: var("MORPHEUS_RATE_LIMIT_PER_IP")?.unwrap_or(defaults.per_ip_per_minute),
+            per_key_per_minute: var("MORPHEUS_RATE_LIMIT_PER_KEY")?.unwrap_or(defaults.per_key_per_minute),
+            api_keys: std::env::var("MORPHEUS_API_KEYS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(str::to_string)
+                .collect(),
+            max_concurrent: var("MORPHEUS_MAX_CONCURRENT_GENERATIONS")?.unwrap_or(defaults.max_concurrent),
+            daily_tokens: var("MORPHEUS_DAILY_TOKEN_BUDGET")?,
+            daily_cost_usd: var("MORPHEUS_DAILY_COST_BUDGET_USD")?,
+        })
+    }
+}
+
+/// Why a request was refused
+#[derive(Debug, thiserror::Error)]
+pub enum LimitError {
+    #[error("Rate limit exceeded for this {scope}; retry in {}s", retry_after.as_secs().max(1))]
+    RateLimited { scope: &'static str, retry_after: Duration },
+    #[error("{limit} generations are already running; retry shortly")]
+    TooManyGenerations { limit: usize },
+    #[error("{limit} generation jobs are already queued; retry shortly")]
+    QueueFull { limit: usize },
+    #[error("Daily AI budget exhausted ({0}); it resets at midnight UTC")]
+    BudgetExhausted(String),
+}
+
+impl LimitError {
+    /// Machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            LimitError::RateLimited { .. } => "rate_limited",
+            LimitError::TooManyGenerations { .. } => "too_many_generations",
+            LimitError::QueueFull { .. } => "queue_full",
+            LimitError::BudgetExhausted(_) => "budget_exhausted",
+        }
+    }
+
+    /// When the client may retry, if waiting helps
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            LimitError::RateLimited { retry_after, .. } => Some(*retry_after),
+            LimitError::TooManyGenerations { .. } => Some(Duration::from_secs(5)),
+            LimitError::QueueFull { .. } => Some(Duration::from_secs(30)),
+            LimitError::BudgetExhausted(_) => None,
+        }
+    }
+}
+
+/// AI usage so far today
+#[derive(Debug, Default)]
+struct Budget {
+    day: Option<NaiveDate>,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+impl Budget {
+    /// Start a new day's count if the date changed
+    fn roll_over(&mut self, today: NaiveDate) {
+        if self.day != Some(today) {
+            *self = Budget {
+                day: Some(today),
+                ..Budget::default()
+            };
+        }
+    }
+
+    fn tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    fn cost_usd(&self) -> f64 {
+        (self.prompt_tokens as f64 * INPUT_USD_PER_MTOK + self.completion_tokens as f64 * OUTPUT_USD_PER_MTOK)
+            / 1_000_000.0
+    }
+}
+
+/// Limit state shared by all requests
+pub struct Limits {
+    config: LimitsConfig,
+    by_ip: DefaultKeyedRateLimiter<IpAddr>,
+    by_key: DefaultKeyedRateLimiter<String>,
+    generations: Arc<Semaphore>,
+    budget: std::sync::Mutex<Budget>,
+}
+
+impl Limits {
+    pub fn new(config: LimitsConfig) -> Self {
+        Self {
+            by_ip: RateLimiter::keyed(Quota::per_minute(config.per_ip_per_minute)),
+            by_key: RateLimiter::keyed(Quota::per_minute(config.per_key_per_minute)),
+            generations: Arc::new(Semaphore::new(config.max_concurrent)),
+            budget: std::sync::Mutex::new(Budget::default()),
+            config,
+        }
+    }
+
+    /// Count a request against its key if it is a configured one, or its IP
+    /// address otherwise
+    pub fn check_rate(&self, ip: Option<IpAddr>, key: Option<&str>) -> Result<(), LimitError> {
+        let clock = DefaultClock::default();
+        let key = key.filter(|key| self.config.api_keys.iter().any(|known| known == key));
+        let (scope, result) = match (key, ip) {
+            (Some(key), _) => {
+                if self.by_key.len() > MAX_TRACKED_CLIENTS {
+                    self.by_key.retain_recent();
+                }
+                ("key", self.by_key.check_key(&key.to_string()))
+            }
+            (None, Some(ip)) => {
+                if self.by_ip.len() > MAX_TRACKED_CLIENTS {
+                    self.by_ip.retain_recent();
+                }
+                ("IP address", self.by_ip.check_key(&ip))
+            }
+            (None, None) => return Ok(()),
+        };
+        result.map_err(|not_until| LimitError::RateLimited {
+            scope,
+            retry_after: not_until.wait_time_from(clock.now()),
+        })
+    }
+
+    /// Fail once today's token or cost budget is spent
+    pub fn check_budget(&self) -> Result<(), LimitError> {
+        let mut budget = self.budget.lock().unwrap();
+        budget.roll_over(Utc::now().date_naive());
+        if let Some(limit) = self.config.daily_tokens.filter(|&limit| budget.tokens() >= limit) {
+            return Err(LimitError::BudgetExhausted(format!("{} of {} tokens used", budget.tokens(), limit)));
+        }
+        if let Some(limit) = self.config.daily_cost_usd.filter(|&limit| budget.cost_usd() >= limit) {
+            return Err(LimitError::BudgetExhausted(format!(
+                "${:.2} of ${:.2} spent",
+                budget.cost_usd(),
+                limit
+            )));
+        }
+        Ok(())
+    }
+
+    /// Add the tokens an AI call used to today's budget
+    pub fn record_usage(&self, prompt_tokens: u64, completion_tokens: u64) {
+        let mut budget = self.budget.lock().unwrap();
+        budget.roll_over(Utc::now().date_naive());
+        budget.prompt_tokens += prompt_tokens;
+        budget.completion_tokens += completion_tokens;
+    }
+
+    /// Configured limits and today's usage
+    pub fn status(&self) -> LimitsStatus {
+        let mut budget = self.budget.lock().unwrap();
+        budget.roll_over(Utc::now().date_naive());
+        LimitsStatus {
+            per_ip_per_minute: self.config.per_ip_per_minute.get(),
+            per_key_per_minute: self.config.per_key_per_minute.get(),
+            max_concurrent_generations: self.config.max_concurrent,
+            running_generations: self.config.max_concurrent - self.generations.available_permits(),
+            day: budget.day.map(|day| day.to_string()).unwrap_or_default(),
+            tokens_used: budget.tokens(),
+            daily_token_budget: self.config.daily_tokens,
+            cost_usd: budget.cost_usd(),
+            daily_cost_budget_usd: self.config.daily_cost_usd,
+        }
+    }
+}
+
+/// Configured limits and today's usage
+#[derive(Serialize, JsonSchema)]
+pub struct LimitsStatus {
+    pub per_ip_per_minute: u32,
+    pub per_key_per_minute: u32,
+    pub max_concurrent_generations: usize,
+    pub running_generations: usize,
+    /// UTC day the usage is for
+    pub day: String,
+    pub tokens_used: u64,
+    pub daily_token_budget: Option<u64>,
+    /// Estimated AI spend today, in USD
+    pub cost_usd: f64,
+    pub daily_cost_budget_usd: Option<f64>,
+}
+
+/// Refuse generation requests over the rate, concurrency or budget limits
+pub async fn limit_generation(
+    State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let limits = &state.limits;
+    let key = request.headers().get(KEY_HEADER).and_then(|value| value.to_str().ok());
+    let ip = connect_info.map(|ConnectInfo(addr)| addr.ip());
+
+    let checked = limits
+        .check_budget()
+        .and_then(|()| limits.check_rate(ip, key))
+        .and_then(|()| {
+            limits.generations.clone().try_acquire_owned().map_err(|_| LimitError::TooManyGenerations {
+                limit: limits.config.max_concurrent,
+            })
+        });
+    let _permit = match checked {
+        Ok(permit) => permit,
+        Err(e) => {
+            warn!("🚦 Refused {} from {:?}: {}", request.uri().path(), ip, e);
+            return Err(e.into());
+        }
+    };
+    Ok(next.run(request).await)
+}
+
+/// Configured limits and today's AI usage
+pub async fn get_limits(State(state): State<AppState>) -> Json<LimitsStatus> {
+    Json(state.limits.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(config: LimitsConfig) -> Limits {
+        Limits::new(config)
+    }
+
+    #[test]
+    fn test_rate_limit_per_ip_and_key() {
+        let limits = limits(LimitsConfig {
+            per_ip_per_minute: NonZeroU32::new(2).unwrap(),
+            per_key_per_minute: NonZeroU32::new(3).unwrap(),
+            api_keys: vec!["ci".to_string()],
+            ..LimitsConfig::default()
+        });
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+        assert!(limits.check_rate(Some(ip), None).is_ok());
+        assert!(limits.check_rate(Some(ip), None).is_ok());
+        let refused = limits.check_rate(Some(ip), None).unwrap_err();
+        assert_eq!(refused.code(), "rate_limited");
+        assert!(refused.retry_after().unwrap() > Duration::ZERO);
+
+        // Another address, and a keyed client on the same address, have their own quota
+        assert!(limits.check_rate(Some("10.0.0.2".parse().unwrap()), None).is_ok());
+        for _ in 0..3 {
+            assert!(limits.check_rate(Some(ip), Some("ci")).is_ok());
+        }
+        assert!(limits.check_rate(Some(ip), Some("ci")).is_err());
+    }
+
+    #[test]
+    fn test_made_up_keys_are_limited_per_ip() {
+        let limits = limits(LimitsConfig {
+            per_ip_per_minute: NonZeroU32::new(2).unwrap(),
+            api_keys: vec!["ci".to_string()],
+            ..LimitsConfig::default()
+        });
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+        assert!(limits.check_rate(Some(ip), Some("rotated-1")).is_ok());
+        assert!(limits.check_rate(Some(ip), Some("rotated-2")).is_ok());
+        let refused = limits.check_rate(Some(ip), Some("rotated-3")).unwrap_err();
+        assert_eq!(refused.code(), "rate_limited");
+        assert!(refused.to_string().contains("IP address"));
+    }
+
+    #[test]
+    fn test_token_budget() {
+        let limits = limits(LimitsConfig {
+            daily_tokens: Some(1000),
+            ..LimitsConfig::default()
+        });
+        assert!(limits.check_budget().is_ok());
+        limits.record_usage(600, 400);
+        let refused = limits.check_budget().unwrap_err();
+        assert_eq!(refused.code(), "budget_exhausted");
+        assert!(refused.retry_after().is_none());
+        assert_eq!(limits.status().tokens_used, 1000);
+    }
+
+    #[test]
+    fn test_cost_budget() {
+        let limits = limits(LimitsConfig {
+            daily_cost_usd: Some(1.0),
+            ..LimitsConfig::default()
+        });
+        // $0.30 input + $0.75 output
+        limits.record_usage(100_000, 50_000);
+        assert!((limits.status().cost_usd - 1.05).abs() < 1e-9);
+        assert!(limits.check_budget().is_err());
+    }
+
+    #[test]
+    fn test_budget_resets_each_day() {
+        let mut budget = Budget::default();
+        budget.roll_over(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+        budget.prompt_tokens = 500;
+        budget.roll_over(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+        assert_eq!(budget.tokens(), 500);
+        budget.roll_over(NaiveDate::from_ymd_opt(2025, 1, 2).unwrap());
+        assert_eq!(budget.tokens(), 0);
+    }
+}
@@ -0,0 +1,326 @@
+//! Structured logs, kept in memory for debugging a live server.
+//!
+//! Log calls about a component, version or generation job carry
+//! `component_id`, `version` and `job_id` fields, on the event itself or on
+//! an enclosing span: each generation job runs in a `job` span with its
+//! `job_id` and `component_id`, so everything logged while it runs
+//! (including by the compiler) is attributed to it. The same fields work in
+//! `RUST_LOG` directives, e.g. `RUST_LOG=info,[job{component_id=chart}]=debug`
+//! turns on debug logs for one component.
+//!
+//! [`LogBuffer`] is a `tracing` layer keeping the most recent records of
+//! each component in a ring buffer of its own, so a chatty component can't
+//! push out another's history. `GET /api/logs?component=` reads it.
+
+use crate::{AppError, AppState};
+use axum::extract::{Query, State};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Records kept per component (and for logs about no component)
+pub const DEFAULT_CAPACITY: usize = 500;
+
+/// Records returned when the query doesn't set a limit
+const DEFAULT_LIMIT: usize = 200;
+
+/// One log event with the fields we index by
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct LogRecord {
+    /// Order across all components
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    /// Any other fields on the event or its spans
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+}
+
+/// Query for `GET /api/logs`
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct LogsQuery {
+    /// Only this component's logs
+    pub component: Option<String>,
+    /// Only this generation job's logs
+    pub job: Option<String>,
+    /// Minimum level: `error`, `warn`, `info`, `debug` or `trace`
+    pub level: Option<String>,
+    /// Most recent records to return (default 200)
+    pub limit: Option<usize>,
+}
+
+/// Recent log records, a ring buffer per component
+#[derive(Debug)]
+pub struct LogBuffer {
+    capacity: usize,
+    inner: Mutex<Buffers>,
+}
+
+#[derive(Debug, Default)]
+struct Buffers {
+    next_seq: u64,
+    by_component: HashMap<Option<String>, VecDeque<LogRecord>>,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(Buffers::default()),
+        }
+    }
+
+    /// Records per component from `MORPHEUS_LOG_BUFFER` (0 keeps none)
+    pub fn from_env() -> anyhow::Result<Self> {
+        let capacity = match std::env::var("MORPHEUS_LOG_BUFFER") {
+            Ok(value) => value.parse()?,
+            Err(_) => DEFAULT_CAPACITY,
+        };
+        Ok(Self::new(capacity))
+    }
+
+    /// A `tracing` layer recording into this buffer
+    pub fn layer(self: &Arc<Self>) -> BufferLayer {
+        BufferLayer { buffer: self.clone() }
+    }
+
+    /// Add a record, assigning its sequence number and evicting the
+    /// component's oldest record when full
+    pub fn push(&self, mut record: LogRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        record.seq = inner.next_seq;
+        inner.next_seq += 1;
+        let records = inner.by_component.entry(record.component_id.clone()).or_default();
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// The most recent matching records, oldest first
+    pub fn query(&self, query: &LogsQuery) -> Result<Vec<LogRecord>, String> {
+        let min_level = match &query.level {
+            Some(level) => Some(level.parse::<Level>().map_err(|_| format!("Unknown log level '{}'", level))?),
+            None => None,
+        };
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut records: Vec<LogRecord> = inner
+            .by_component
+            .iter()
+            .filter(|(component, _)| query.component.is_none() || **component == query.component)
+            .flat_map(|(_, records)| records)
+            .filter(|r| query.job.is_none() || r.job_id == query.job)
+            // Levels order by verbosity, so more severe levels compare lower
+            .filter(|r| min_level.is_none_or(|min| r.level.parse::<Level>().is_ok_and(|level| level <= min)))
+            .cloned()
+            .collect();
+        records.sort_by_key(|r| r.seq);
+        let skip = records.len().saturating_sub(limit);
+        Ok(records.split_off(skip))
+    }
+}
+
+/// Fields collected from an event and its spans
+#[derive(Debug, Default, Clone)]
+struct Fields {
+    message: String,
+    component_id: Option<String>,
+    version: Option<u64>,
+    job_id: Option<String>,
+    other: BTreeMap<String, String>,
+}
+
+impl Fields {
+    fn set(&mut self, field: &Field, value: String) {
+        match field.name() {
+            "message" => self.message = value,
+            "component_id" => self.component_id = Some(value),
+            "version" => self.version = value.parse().ok(),
+            "job_id" => self.job_id = Some(value),
+            name => {
+                self.other.insert(name.to_string(), value);
+            }
+        }
+    }
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.set(field, format!("{:?}", value));
+    }
+}
+
+/// Feeds events into a [`LogBuffer`]; see [`LogBuffer::layer`]
+pub struct BufferLayer {
+    buffer: Arc<LogBuffer>,
+}
+
+impl<S> Layer<S> for BufferLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(fields);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(fields) = span.extensions_mut().get_mut::<Fields>() {
+                values.record(fields);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        // Outer spans first, so inner spans and the event override them
+        let mut fields = Fields::default();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(span_fields) = span.extensions().get::<Fields>() {
+                    fields.component_id = span_fields.component_id.clone().or(fields.component_id);
+                    fields.version = span_fields.version.or(fields.version);
+                    fields.job_id = span_fields.job_id.clone().or(fields.job_id);
+                    fields.other.extend(span_fields.other.clone());
+                }
+            }
+        }
+        event.record(&mut fields);
+
+        let metadata = event.metadata();
+        self.buffer.push(LogRecord {
+            seq: 0,
+            timestamp: Utc::now(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: fields.message,
+            component_id: fields.component_id,
+            version: fields.version,
+            job_id: fields.job_id,
+            fields: fields.other,
+        });
+    }
+}
+
+/// Recent server logs, optionally for one component or job
+pub async fn get_logs(
+    State(state): State<AppState>,
+    Query(query): Query<LogsQuery>,
+) -> Result<Json<Vec<LogRecord>>, AppError> {
+    state.logs.query(&query).map(Json).map_err(AppError::ApiError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing::{info, info_span, warn};
+    use tracing_subscriber::prelude::*;
+
+    fn capture(capacity: usize, f: impl FnOnce()) -> Arc<LogBuffer> {
+        let buffer = Arc::new(LogBuffer::new(capacity));
+        let subscriber = tracing_subscriber::registry().with(buffer.layer());
+        tracing::subscriber::with_default(subscriber, f);
+        buffer
+    }
+
+    fn query(component: Option<&str>) -> LogsQuery {
+        LogsQuery {
+            component: component.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fields_come_from_events_and_spans() {
+        let buffer = capture(10, || {
+            let _job = info_span!("job", job_id = "j1", component_id = "chart").entered();
+            info!(version = 3, attempt = 2, "Compiled");
+            info!(component_id = "table", "Override");
+        });
+
+        let records = buffer.query(&LogsQuery::default()).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].message, "Compiled");
+        assert_eq!(records[0].component_id.as_deref(), Some("chart"));
+        assert_eq!(records[0].version, Some(3));
+        assert_eq!(records[0].job_id.as_deref(), Some("j1"));
+        assert_eq!(records[0].fields["attempt"], "2");
+        assert_eq!(records[1].component_id.as_deref(), Some("table"));
+        assert_eq!(records[1].job_id.as_deref(), Some("j1"));
+    }
+
+    #[test]
+    fn test_components_have_separate_rings() {
+        let buffer = capture(2, || {
+            info!(component_id = "quiet", "Only message");
+            for i in 0..5 {
+                info!(component_id = "chatty", "Message {}", i);
+            }
+            warn!("Server-wide");
+        });
+
+        let quiet = buffer.query(&query(Some("quiet"))).unwrap();
+        assert_eq!(quiet.len(), 1);
+        let chatty: Vec<String> = buffer.query(&query(Some("chatty"))).unwrap().into_iter().map(|r| r.message).collect();
+        assert_eq!(chatty, ["Message 3", "Message 4"]);
+
+        let all = buffer.query(&query(None)).unwrap();
+        assert_eq!(all.len(), 4);
+        assert!(all.windows(2).all(|w| w[0].seq < w[1].seq));
+    }
+
+    #[test]
+    fn test_query_filters_level_job_and_limit() {
+        let buffer = capture(10, || {
+            info!(job_id = "a", "Started");
+            warn!(job_id = "a", "Retrying");
+            info!(job_id = "b", "Started");
+        });
+
+        let warnings = LogsQuery {
+            level: Some("warn".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(buffer.query(&warnings).unwrap().len(), 1);
+        let job = LogsQuery {
+            job: Some("a".to_string()),
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(buffer.query(&job).unwrap()[0].message, "Retrying");
+        let bad = LogsQuery {
+            level: Some("loud".to_string()),
+            ..Default::default()
+        };
+        assert!(buffer.query(&bad).is_err());
+    }
+}
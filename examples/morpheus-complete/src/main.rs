@@ -8,20 +8,114 @@
 //! - State preservation (Phase 6)
 //! - Version history & rollback (Phase 6)
 
+mod adapters;
+mod autonomous;
+mod context;
+mod crashes;
+mod experiments;
+mod fewshot;
+mod gateway;
+mod git_history;
+mod headless;
+mod host_apis;
+mod i18n;
+mod jobs;
+mod language;
+mod limits;
+mod logs;
+mod mock;
+mod openapi;
+mod overview;
+mod pages;
+mod planner;
+mod preload;
+mod routing;
+mod search;
+mod sharing;
+mod state_sync;
+mod theme;
+mod verification;
+
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::{IntoResponse, Response},
-    routing::{get, post},
+    async_trait,
+    body::Bytes,
+    extract::{
+        ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
+        FromRequest, FromRequestParts, Path, Query, Request, State,
+    },
+    http::{header, request::Parts, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{any, get, post},
     Json, Router,
 };
-use chrono::{DateTime, Utc};
-use morpheus_compiler::{Compiler, SubprocessCompiler};
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures_util::stream::{self, Stream};
+use morpheus_compiler::element::{self, CustomElement};
+use morpheus_compiler::guardrails::{self, Guardrails};
+use morpheus_compiler::source;
+use morpheus_compiler::{
+    AdvisoryPolicy, HostCrate, Inspection, Sbom, SizeBudget, SizeReport, SnapshotOutcome, SourceIndex,
+    SubprocessCompiler, Target, Verification, Verifier, WasmOpt,
+};
+use morpheus_core::accessibility::AccessibilityViolation;
+use morpheus_core::artifact::{Artifact, ArtifactTarget, Environment};
+use morpheus_core::broker::{LocationGate, NotificationLimiter};
+use morpheus_core::capability::Capability;
+use morpheus_core::bundle::BundleManifest;
+use morpheus_core::catalog::{CatalogEntry, ComponentDescription};
+use morpheus_core::codec::Format;
+use morpheus_core::delta;
+use morpheus_core::events::{DomainEvent, EventLog, MergePatchReducer, Reducer};
+use morpheus_core::experiment::Variant;
+use morpheus_core::feedback::{self, Feedback};
+use morpheus_core::fallback::FallbackView;
+use morpheus_core::flags::{ComponentFlag, Fallback, RenderDecision, DEFAULT_PLACEHOLDER};
+use morpheus_core::interface::{Compatibility, Incompatibility};
+use morpheus_core::lifecycle::Lifecycle;
+use morpheus_core::manifest::{self, ComponentManifest, SlotDecl};
+use morpheus_core::component::{Author, ComponentId, ComponentMetadata, Provenance};
+use morpheus_core::performance::{PerformanceBudget, PerformanceMeasurements};
+use morpheus_core::permissions::Permissions;
+use morpheus_core::privacy::ScrubPolicy;
+use morpheus_core::profiler::Profiler;
+use morpheus_core::review::{Review, ReviewComment, ReviewStatus};
+use morpheus_core::screening::{Screener, Screening};
+use morpheus_core::semver::{Bump, SemVer};
+use morpheus_core::stats::CallSample;
+use morpheus_core::state::{Clock, CrdtDoc, SyncMessage, VersionedState};
+use morpheus_core::store::{self, SnapshotCodec, SnapshotStore};
+use morpheus_core::thumbnail::Thumbnail;
+use morpheus_runtime::headless::CompiledCache;
+use morpheus_runtime::store::{EncryptedStore, FsStore, LocalKey, S3Config, S3Store};
+use morpheus_runtime::{ComponentRegistry, SimilarComponent, SlotMount, WasmComponent};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
 use tower_http::{cors::CorsLayer, services::ServeDir};
+use autonomous::{Autonomous, AutonomousPolicy, Telemetry, TelemetryKind, TelemetryReport};
+use context::PromptContext;
+use experiments::Experiments;
+use fewshot::ExampleStore;
+use gateway::ModelEndpoint;
+use mock::MockGenerator;
+use routing::GenerationPolicy;
+use git_history::GitHistory;
+use headless::HeadlessRegistry;
+use jobs::{JobStatus, Jobs};
+use limits::{Limits, LimitsConfig};
+use logs::LogBuffer;
+use sharing::ShareLinks;
+use verification::{compile_verified, AccessibilityPolicy};
 use tracing::{error, info, warn};
+use tracing_subscriber::{prelude::*, EnvFilter};
 
 /// Application state
 #[derive(Clone)]
@@ -30,7 +124,180 @@ struct AppState {
     versions: Arc<Mutex<VersionHistory>>,
     conversation: Arc<Mutex<Vec<Message>>>,
     design_session: Arc<Mutex<Option<DesignSession>>>,
+    registry: Arc<Mutex<ComponentRegistry>>,
+    schedule: Arc<Mutex<Vec<ScheduledActivation>>>,
+    audit_log: Arc<Mutex<Vec<AuditEntry>>>,
+    /// Builds headless (server-side) components
+    headless_compiler: Arc<SubprocessCompiler>,
+    /// Headless components served under `/x/`
+    headless: Arc<Mutex<HeadlessRegistry>>,
+    /// Machine code of compiled headless modules, kept across restarts
+    compiled_cache: Option<CompiledCache>,
+    /// Errors, slow renders and feedback reported by clients
+    telemetry: Arc<Mutex<Telemetry>>,
+    /// Render timings reported by clients, by component
+    profiler: Arc<Mutex<Profiler>>,
+    /// Panics reported by components, with their last state
+    crashes: Arc<Mutex<crashes::Crashes>>,
+    /// Self-improvement loop driven by telemetry
+    autonomous: Arc<Autonomous>,
+    /// A/B experiments by component name
+    experiments: Arc<Mutex<Experiments>>,
+    /// Rate limits and the daily AI budget
+    limits: Arc<Limits>,
+    /// Queued and recent generation jobs
+    jobs: Arc<Jobs>,
+    /// Known-good generations retrieved as few-shot examples
+    examples: Arc<ExampleStore>,
+    /// Which models generations use
+    generation: Arc<GenerationPolicy>,
+    /// Recent log records per component, for `GET /api/logs`
+    logs: Arc<LogBuffer>,
+    /// Deterministic stand-in for the AI, if configured
+    mock: Option<Arc<MockGenerator>>,
+    /// Durable storage for version history, if configured
+    store: Option<Arc<dyn SnapshotStore>>,
+    /// Personal data that state updates must not contain
+    scrub_policy: Arc<ScrubPolicy>,
+    /// Personal data removed from telemetry before it reaches the AI
+    telemetry_policy: Arc<ScrubPolicy>,
+    /// What to do when a proposal introduces accessibility violations
+    accessibility_policy: AccessibilityPolicy,
+    /// Limits on first-render time, bundle size and long tasks
+    performance_budget: PerformanceBudget,
+    /// Prompt-injection and capability rules for user text bound for the AI
+    screener: Arc<Screener>,
+    /// Notifies state sync sockets that the CRDT document changed
+    state_sync: broadcast::Sender<()>,
+    /// Per-component rate limits on browser notifications
+    notifications: Arc<Mutex<NotificationLimiter>>,
+    /// Precision, caching and rate limits of positions given to components
+    locations: Arc<Mutex<LocationGate>>,
+    /// Read-only links to the live component
+    shares: Arc<Mutex<ShareLinks>>,
+    /// Translations of components' text, by locale
+    translations: Arc<Mutex<i18n::Translations>>,
+    /// Design tokens components are styled with, and the active theme
+    themes: Arc<theme::HostThemes>,
+    /// Source and symbols of stored versions, for `GET /api/search`
+    search_index: Arc<Mutex<SourceIndex>>,
+    /// Token required on API requests without a share link (`MORPHEUS_OWNER_TOKEN`)
+    owner_token: Option<String>,
     api_key: String,
+    /// Where completions go: OpenRouter, or an internal model gateway
+    model_endpoint: Arc<ModelEndpoint>,
+}
+
+impl AppState {
+    /// Whether AI requests can be answered, by the API or the mock
+    fn has_ai(&self) -> bool {
+        !self.api_key.is_empty() || self.model_endpoint.is_gateway() || self.mock.is_some()
+    }
+
+    /// State with nothing configured, as `main` builds it without any
+    /// environment variables; tests adjust the fields they need
+    #[cfg(test)]
+    async fn for_tests() -> Self {
+        Self {
+            compiler: Arc::new(SubprocessCompiler::new().await.expect("compiler work directory")),
+            versions: Arc::new(Mutex::new(VersionHistory::new())),
+            conversation: Arc::new(Mutex::new(Vec::new())),
+            design_session: Arc::new(Mutex::new(None)),
+            registry: Arc::new(Mutex::new(ComponentRegistry::new().with_compatibility(Compatibility::Warn))),
+            schedule: Arc::new(Mutex::new(Vec::new())),
+            audit_log: Arc::new(Mutex::new(Vec::new())),
+            headless_compiler: Arc::new(SubprocessCompiler::new().await.expect("compiler work directory")),
+            headless: Arc::new(Mutex::new(HeadlessRegistry::default())),
+            compiled_cache: None,
+            telemetry: Arc::new(Mutex::new(Telemetry::default())),
+            profiler: Arc::new(Mutex::new(Profiler::new())),
+            crashes: Arc::new(Mutex::new(crashes::Crashes::default())),
+            autonomous: Arc::new(Autonomous::new(None)),
+            experiments: Arc::new(Mutex::new(Experiments::new())),
+            limits: Arc::new(Limits::new(LimitsConfig::default())),
+            jobs: Arc::new(Jobs::default()),
+            examples: Arc::new(ExampleStore::new(fewshot::Embedder::Local, 3)),
+            generation: Arc::new(GenerationPolicy::default()),
+            logs: Arc::new(LogBuffer::new(100)),
+            mock: None,
+            store: None,
+            scrub_policy: Arc::new(ScrubPolicy::default()),
+            telemetry_policy: Arc::new(ScrubPolicy::default()),
+            accessibility_policy: AccessibilityPolicy::default(),
+            performance_budget: PerformanceBudget::default(),
+            screener: Arc::new(Screener::default()),
+            state_sync: broadcast::channel(16).0,
+            notifications: Arc::new(Mutex::new(NotificationLimiter::default())),
+            locations: Arc::new(Mutex::new(LocationGate::default())),
+            shares: Arc::new(Mutex::new(ShareLinks::default())),
+            translations: Arc::new(Mutex::new(i18n::Translations::default())),
+            themes: Arc::new(theme::HostThemes::default()),
+            search_index: Arc::new(Mutex::new(SourceIndex::new())),
+            owner_token: None,
+            api_key: String::new(),
+            model_endpoint: Arc::new(ModelEndpoint::openrouter()),
+        }
+    }
+}
+
+/// Default model for generation (OpenRouter model ID); see [`GenerationPolicy`]
+const AI_MODEL: &str = "anthropic/claude-3.5-sonnet";
+
+/// How often version history is written to the store
+const PERSIST_INTERVAL_SECS: u64 = 5;
+
+/// Store key of the version history index
+const HISTORY_KEY: &str = "history.json";
+
+/// Store key prefix of per-component event logs
+const EVENTS_PREFIX: &str = "events/";
+
+/// Store key prefix of per-component changelogs, as `{component}.md` and
+/// `{component}.json`
+const CHANGELOG_PREFIX: &str = "changelogs/";
+
+/// Actor name of the server's replica of the CRDT state
+const SERVER_ACTOR: &str = "server";
+
+/// How often the scheduler checks for due activations
+const SCHEDULER_INTERVAL_SECS: u64 = 5;
+
+/// Similar existing components suggested to the AI per generation
+const MAX_SIMILAR_COMPONENTS: usize = 3;
+
+/// A version activation scheduled for a later time
+#[derive(Clone, Serialize)]
+struct ScheduledActivation {
+    id: String,
+    version_id: usize,
+    activate_at: DateTime<Utc>,
+    status: ActivationStatus,
+    created_at: DateTime<Utc>,
+}
+
+/// Lifecycle of a scheduled activation
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ActivationStatus {
+    Pending,
+    Activated,
+    RolledBack,
+    /// Not activated because the version isn't approved
+    Blocked,
+    /// Not activated because the version no longer exists, or was replaced
+    /// by another activation before it could be rolled back
+    Failed,
+    Cancelled,
+}
+
+/// A record of an operational action
+#[derive(Clone, Serialize)]
+struct AuditEntry {
+    timestamp: DateTime<Utc>,
+    action: String,
+    version_id: Option<usize>,
+    outcome: String,
+    detail: String,
 }
 
 /// Interactive design session for iterative component development
@@ -41,6 +308,8 @@ struct DesignSession {
     drafts: Vec<ComponentDraft>,
     current_draft_index: usize,
     original_prompt: String,
+    /// Version current when the session started; commits fail if it has moved
+    base_version_id: Option<usize>,
     started_at: DateTime<Utc>,
 }
 
@@ -54,14 +323,90 @@ struct ComponentDraft {
     js_glue: Option<String>,
     compilation_error: Option<String>,
     created_at: DateTime<Utc>,
+    #[serde(default)]
+    sbom: Option<Sbom>,
+    #[serde(default)]
+    variants: Vec<Artifact>,
+    #[serde(default)]
+    accessibility: Option<Vec<AccessibilityViolation>>,
+    #[serde(default)]
+    performance: Option<PerformanceMeasurements>,
 }
 
 /// Version history manager
-#[derive(Clone)]
 struct VersionHistory {
     versions: Vec<ComponentVersion>,
     current_index: usize,
     current_state: Option<serde_json::Value>,
+    /// Bumped on every change to the current state, so clients can tell
+    /// newer snapshots from older ones
+    state_revision: u64,
+    /// Optional git mirror of every version
+    git: Option<GitHistory>,
+    /// Whether versions must be approved before activation
+    require_review: bool,
+    /// Limits each change must stay within to activate without an override
+    guardrails: Guardrails,
+    /// Size limit and compression for stored state snapshots
+    snapshot_codec: SnapshotCodec,
+    /// State snapshot sizes by component
+    snapshot_sizes: BTreeMap<String, SnapshotSizeStats>,
+    /// Server replica of the state, when clients merge concurrent edits
+    crdt: Option<CrdtDoc>,
+    /// Whether state is built from domain events instead of snapshots
+    event_sourced: bool,
+    /// Domain events by component
+    events: BTreeMap<String, EventLog>,
+    /// Recent states, for time-travel debugging
+    state_timeline: Option<VersionedState<TimelineEntry>>,
+    /// Timeline position being inspected (None when following the live state)
+    debug_position: Option<usize>,
+    /// Announces each version as it goes live
+    reloads: broadcast::Sender<ReloadEvent>,
+    /// Versions committed together, e.g. by a plan
+    groups: Vec<RollbackGroup>,
+}
+
+/// Versions committed together, which roll back together
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+struct RollbackGroup {
+    id: usize,
+    /// What the versions were committed for
+    label: String,
+    /// Version current before the group was committed
+    base_version_id: Option<usize>,
+    version_ids: Vec<usize>,
+    created_at: DateTime<Utc>,
+    #[serde(default)]
+    rolled_back_at: Option<DateTime<Utc>>,
+}
+
+/// A state on the time-travel timeline, with the version that was current
+#[derive(Clone, Serialize, Deserialize)]
+struct TimelineEntry {
+    version_id: Option<usize>,
+    state: serde_json::Value,
+}
+
+/// State snapshot sizes seen for one component
+#[derive(Clone, Default, Serialize, JsonSchema)]
+struct SnapshotSizeStats {
+    snapshots: u64,
+    rejected: u64,
+    last_bytes: usize,
+    last_stored_bytes: usize,
+    max_bytes: usize,
+    total_bytes: u64,
+}
+
+/// A version going live, pushed to `/api/reloads` subscribers
+#[derive(Clone, Serialize)]
+struct ReloadEvent {
+    version_id: usize,
+    name: String,
+    activated_at: DateTime<Utc>,
+    /// Module, glue and assets of the version, to swap in together
+    bundle: Option<BundleManifest>,
 }
 
 /// A versioned component snapshot
@@ -76,6 +421,92 @@ struct ComponentVersion {
     created_at: DateTime<Utc>,
     state_snapshot: Option<serde_json::Value>,
     ai_generated: bool,
+    #[serde(default)]
+    manifest: ComponentManifest,
+    #[serde(default)]
+    review: Review,
+    /// When the version first went live (None while awaiting review)
+    #[serde(default)]
+    activated_at: Option<DateTime<Utc>>,
+    /// AI-written explanation of what changed since an earlier version
+    #[serde(default)]
+    changelog: Option<ChangelogEntry>,
+    /// Guardrails this version exceeded relative to the version it replaced
+    #[serde(default)]
+    guardrail_violations: Vec<String>,
+    /// Explicit sign-off to activate despite guardrail violations
+    #[serde(default)]
+    guardrail_override: Option<GuardrailOverride>,
+    /// Where the code came from
+    #[serde(default)]
+    provenance: Provenance,
+    /// CycloneDX SBOM of the generated project's dependencies
+    #[serde(default)]
+    sbom: Option<Sbom>,
+    /// Ratings users gave the version while it was live
+    #[serde(default)]
+    feedback: Vec<Feedback>,
+    /// Builds for targets other than the browser, e.g. Node.js or WASI
+    #[serde(default)]
+    variants: Vec<Artifact>,
+    /// Stylesheets and other files served with the module, by file name
+    /// (base64)
+    #[serde(default)]
+    assets: BTreeMap<String, String>,
+    /// Preview of the version as rendered
+    #[serde(default)]
+    thumbnail: Option<Thumbnail>,
+    /// axe-core violations of the rendered version, if it was audited
+    #[serde(default)]
+    accessibility: Option<Vec<AccessibilityViolation>>,
+    /// How the version loaded in a headless browser, if it was measured
+    #[serde(default)]
+    performance: Option<PerformanceMeasurements>,
+}
+
+impl ComponentVersion {
+    /// The version's bundle manifest: its module, glue and assets
+    fn bundle(&self) -> Result<BundleManifest, AppError> {
+        let mut bundle = BundleManifest::new(self.id, &self.name, &base64_decode(&self.wasm_base64)?, &self.js_glue);
+        for (name, contents) in &self.assets {
+            bundle = bundle.with_asset(name, &base64_decode(contents)?);
+        }
+        Ok(bundle)
+    }
+
+    /// Every build of the version: the browser build, then its variants
+    fn artifacts(&self) -> Vec<Artifact> {
+        let web = Artifact {
+            target: ArtifactTarget::Web,
+            wasm_base64: self.wasm_base64.clone(),
+            js_glue: self.js_glue.clone(),
+        };
+        std::iter::once(web).chain(self.variants.iter().cloned()).collect()
+    }
+
+    /// Keep what verification in a headless browser found, if it ran
+    fn record_verification(&mut self, verification: Option<Verification>) {
+        if let Some(verification) = verification {
+            self.accessibility = Some(verification.accessibility);
+            self.performance = Some(verification.performance);
+        }
+    }
+}
+
+/// Sign-off to activate a version that exceeds guardrails
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+struct GuardrailOverride {
+    by: String,
+    reason: String,
+    at: DateTime<Utc>,
+}
+
+/// Human-readable summary of a version's changes
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+struct ChangelogEntry {
+    base_version_id: usize,
+    summary: String,
+    generated_at: DateTime<Utc>,
 }
 
 impl VersionHistory {
@@ -84,9 +515,61 @@ impl VersionHistory {
             versions: Vec::new(),
             current_index: 0,
             current_state: None,
+            state_revision: 0,
+            git: None,
+            require_review: false,
+            guardrails: Guardrails::default(),
+            snapshot_codec: SnapshotCodec::default(),
+            snapshot_sizes: BTreeMap::new(),
+            crdt: None,
+            event_sourced: false,
+            events: BTreeMap::new(),
+            state_timeline: None,
+            debug_position: None,
+            reloads: broadcast::channel(16).0,
+            groups: Vec::new(),
         }
     }
 
+    /// Build state from domain events emitted by components
+    fn with_event_sourcing(mut self, enabled: bool) -> Self {
+        self.event_sourced = enabled;
+        self
+    }
+
+    /// Merge concurrent state updates with a CRDT instead of last-writer-wins
+    fn with_crdt(mut self, enabled: bool) -> Self {
+        self.crdt = enabled.then(|| CrdtDoc::new(SERVER_ACTOR));
+        self
+    }
+
+    /// Limit and compress stored state snapshots
+    fn with_snapshot_codec(mut self, codec: SnapshotCodec) -> Self {
+        self.snapshot_codec = codec;
+        self
+    }
+
+    /// Check each change against budget guardrails
+    fn with_guardrails(mut self, guardrails: Guardrails) -> Self {
+        self.guardrails = guardrails;
+        self
+    }
+
+    /// Require approval before versions are activated
+    fn with_review_required(mut self, required: bool) -> Self {
+        self.require_review = required;
+        self
+    }
+
+    /// Mirror versions into a git repository
+    fn with_git(mut self, git: GitHistory) -> Self {
+        self.git = Some(git);
+        self
+    }
+
+    /// Add a version, activating it when `activate` is set and neither
+    /// review nor a guardrail override is required
+    #[allow(clippy::too_many_arguments)]
     fn add_version(
         &mut self,
         name: String,
@@ -95,8 +578,39 @@ impl VersionHistory {
         wasm_bytes: Vec<u8>,
         js_glue: String,
         ai_generated: bool,
+        manifest: ComponentManifest,
+        mut provenance: Provenance,
+        activate: bool,
     ) -> usize {
         let id = self.versions.len();
+        let previous = self.get_current();
+        if provenance.parent_version.is_none() {
+            provenance.parent_version = previous.map(|v| v.id as u32);
+        }
+        let rust_code = source::with_header(&rust_code, &manifest.name, &provenance);
+        let guardrail_violations = self
+            .guardrails
+            .evaluate(&guardrails::Change {
+                old_source: previous.map(|v| v.rust_code.as_str()),
+                new_source: &rust_code,
+                old_wasm_size: previous.and_then(|v| base64_decode(&v.wasm_base64).ok()).map(|w| w.len()),
+                new_wasm_size: wasm_bytes.len(),
+                old_permissions: previous.map(|v| &v.manifest.permissions),
+                new_permissions: &manifest.permissions,
+            })
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let needs_override = !guardrail_violations.is_empty();
+        // Assets carry over until a version replaces them
+        let assets = self
+            .versions
+            .iter()
+            .rev()
+            .find(|v| v.manifest.name == manifest.name)
+            .map(|v| v.assets.clone())
+            .unwrap_or_default();
+
         let version = ComponentVersion {
             id,
             name,
@@ -107,15 +621,187 @@ impl VersionHistory {
             created_at: Utc::now(),
             state_snapshot: self.current_state.clone(),
             ai_generated,
+            manifest,
+            review: Review::default(),
+            activated_at: None,
+            changelog: None,
+            guardrail_violations,
+            guardrail_override: None,
+            provenance,
+            sbom: None,
+            feedback: Vec::new(),
+            variants: Vec::new(),
+            assets,
+            thumbnail: None,
+            accessibility: None,
+            performance: None,
         };
 
+        if let Some(git) = &mut self.git {
+            if let Err(e) = git.commit_version(&version) {
+                warn!(version = id, component_id = %version.manifest.name, "Failed to record version in git: {}", e);
+            }
+        }
         self.versions.push(version);
-        self.current_index = id;
+        if activate && !self.require_review && !needs_override {
+            self.current_index = id;
+            self.mark_active(id);
+        }
         id
     }
 
+    /// Copy the live version of `component` as the first version of a new
+    /// component `name`, derived from it, with the same source, module,
+    /// permissions and slots
+    fn fork(&mut self, component: &str, name: &str) -> Result<usize, String> {
+        if self.versions.iter().any(|v| v.manifest.name == name) {
+            return Err(format!("Component '{}' already exists", name));
+        }
+        let original = self
+            .active_versions()
+            .into_iter()
+            .find(|v| v.manifest.name == component)
+            .ok_or_else(|| format!("Component '{}' has no live version to fork", component))?;
+        let wasm_bytes = base64_decode(&original.wasm_base64).map_err(|e| e.to_string())?;
+        let (sbom, variants) = (original.sbom.clone(), original.variants.clone());
+        let (audit, performance) = (original.accessibility.clone(), original.performance);
+        let provenance = original.provenance.clone().with_parent(original.id as u32).with_forked_from(component);
+        let id = self.add_version(
+            format!("Fork of {}", component),
+            original.description.clone(),
+            original.rust_code.clone(),
+            wasm_bytes,
+            original.js_glue.clone(),
+            original.ai_generated,
+            ComponentManifest {
+                name: name.to_string(),
+                ..original.manifest.clone()
+            },
+            provenance,
+            true,
+        );
+        self.versions[id].sbom = sbom;
+        self.versions[id].variants = variants;
+        self.versions[id].accessibility = audit;
+        self.versions[id].performance = performance;
+        Ok(id)
+    }
+
+    /// Check that a version may be activated
+    fn check_activation(&self, version_id: usize) -> Result<(), String> {
+        let version = self
+            .versions
+            .get(version_id)
+            .ok_or_else(|| format!("Version {} not found", version_id))?;
+        if self.require_review && !version.review.is_approved() {
+            return Err(format!("Version {} has not been approved", version_id));
+        }
+        if self.is_reverted(version_id) {
+            return Err(format!("Version {} was rolled back with its group", version_id));
+        }
+        if !version.guardrail_violations.is_empty() && version.guardrail_override.is_none() {
+            return Err(format!(
+                "Version {} exceeds guardrails and needs an override: {}",
+                version_id,
+                version.guardrail_violations.join("; ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Record activation of the now-current version
+    fn mark_active(&mut self, version_id: usize) {
+        if let Some(version) = self.versions.get_mut(version_id) {
+            let activated_at = *version.activated_at.get_or_insert_with(Utc::now);
+            let _ = self.reloads.send(ReloadEvent {
+                version_id,
+                name: version.name.clone(),
+                activated_at,
+                bundle: version.bundle().ok(),
+            });
+        }
+        if let Some(git) = &self.git {
+            if let Err(e) = git.mark_active(version_id) {
+                warn!(version = version_id, "Failed to tag version in git: {}", e);
+            }
+        }
+    }
+
     fn get_current(&self) -> Option<&ComponentVersion> {
-        self.versions.get(self.current_index)
+        self.versions
+            .get(self.current_index)
+            .filter(|v| v.activated_at.is_some() && !self.is_reverted(v.id))
+    }
+
+    /// Record versions committed together, so they can be rolled back together
+    fn add_group(&mut self, label: String, base_version_id: Option<usize>, version_ids: Vec<usize>) -> usize {
+        let id = self.groups.len();
+        self.groups.push(RollbackGroup {
+            id,
+            label,
+            base_version_id,
+            version_ids,
+            created_at: Utc::now(),
+            rolled_back_at: None,
+        });
+        id
+    }
+
+    /// Whether a version belongs to a rolled-back group
+    fn is_reverted(&self, version_id: usize) -> bool {
+        self.groups
+            .iter()
+            .any(|g| g.rolled_back_at.is_some() && g.version_ids.contains(&version_id))
+    }
+
+    /// Undo every version of a group: the version current before it is
+    /// restored, and each component the group touched goes back to its live
+    /// version from before the group. Returns those components with the
+    /// version they now run, or `None` for components the group created.
+    fn rollback_group(&mut self, group_id: usize) -> Result<Vec<(String, Option<usize>)>, String> {
+        let group = self
+            .groups
+            .get(group_id)
+            .ok_or_else(|| format!("Rollback group {} not found", group_id))?;
+        if group.rolled_back_at.is_some() {
+            return Err(format!("Rollback group {} was already rolled back", group_id));
+        }
+        let first = group.version_ids.iter().copied().min().unwrap_or(self.versions.len());
+        if let Some(later) = self
+            .active_versions()
+            .into_iter()
+            .find(|v| v.id > first && !group.version_ids.contains(&v.id))
+        {
+            return Err(format!(
+                "Version {} went live after the group; roll it back first",
+                later.id
+            ));
+        }
+        let (base_version_id, version_ids) = (group.base_version_id, group.version_ids.clone());
+
+        let mut components: Vec<String> = Vec::new();
+        for version in version_ids.iter().filter_map(|id| self.versions.get(*id)) {
+            if !components.contains(&version.manifest.name) {
+                components.push(version.manifest.name.clone());
+            }
+        }
+        let restored = components
+            .into_iter()
+            .map(|name| {
+                let previous = self.versions[..first]
+                    .iter()
+                    .rev()
+                    .find(|v| v.manifest.name == name && v.activated_at.is_some() && !self.is_reverted(v.id))
+                    .map(|v| v.id);
+                (name, previous)
+            })
+            .collect();
+
+        self.groups[group_id].rolled_back_at = Some(Utc::now());
+        if let Some(base) = base_version_id {
+            self.rollback_to(base);
+        }
+        Ok(restored)
     }
 
     fn rollback_to(&mut self, version_id: usize) -> Option<&ComponentVersion> {
@@ -124,17 +810,217 @@ impl VersionHistory {
             if let Some(version) = self.versions.get(version_id) {
                 self.current_state = version.state_snapshot.clone();
             }
+            if let (Some(doc), Some(state)) = (self.crdt.as_mut(), &self.current_state) {
+                if let Err(e) = doc.update(state) {
+                    warn!("Restored state can't be merged into the CRDT document: {}", e);
+                }
+            }
+            self.mark_active(version_id);
+            self.state_revision += 1;
+            self.record_timeline();
             self.get_current()
         } else {
             None
         }
     }
 
-    fn update_state(&mut self, state: serde_json::Value) {
+    /// Make a version current without restoring its state snapshot
+    fn activate(&mut self, version_id: usize) -> Option<&ComponentVersion> {
+        if version_id < self.versions.len() {
+            self.current_index = version_id;
+            self.mark_active(version_id);
+        }
+        self.versions.get(version_id)
+    }
+
+    /// Restore history saved by [`persist_history`]
+    async fn restore(&mut self, store: &dyn SnapshotStore) -> morpheus_core::errors::Result<()> {
+        let Some(index) = store.get(HISTORY_KEY).await? else {
+            return Ok(());
+        };
+        let persisted: PersistedHistory = serde_json::from_slice(&index)?;
+        let mut versions = persisted.versions;
+        for version in &mut versions {
+            if let Some(wasm) = store.get(&store::wasm_key(&version.id.to_string())).await? {
+                version.wasm_base64 = base64_encode(&wasm);
+            }
+            if let Some(snapshot) = store.get(&store::state_key(&version.id.to_string())).await? {
+                version.state_snapshot = Some(self.snapshot_codec.deserialize(snapshot)?);
+            }
+        }
+        if let Some(current) = store.get(&store::state_key("current")).await? {
+            self.current_state = self.snapshot_codec.deserialize(current)?;
+        }
+        for key in store.list(EVENTS_PREFIX).await? {
+            let component = key.trim_start_matches(EVENTS_PREFIX).trim_end_matches(".json").to_string();
+            if let Some(log) = store.get(&key).await? {
+                self.events.insert(component, self.snapshot_codec.deserialize(log)?);
+            }
+        }
+        if self.crdt.is_some() {
+            // Operation history isn't persisted; clients resync from a fresh document
+            let state = self.current_state.clone().unwrap_or_else(|| serde_json::json!({}));
+            self.crdt = Some(CrdtDoc::from_value(SERVER_ACTOR, &state)?);
+        }
+        self.versions = versions;
+        self.current_index = persisted.current_index;
+        self.groups = persisted.groups;
+        Ok(())
+    }
+
+    fn update_state(&mut self, state: serde_json::Value) -> morpheus_core::errors::Result<()> {
+        let doc = match &self.crdt {
+            Some(doc) => {
+                let mut doc = doc.clone();
+                doc.update(&state)?;
+                Some(doc)
+            }
+            None => None,
+        };
+        self.record_snapshot(&state)?;
+        if doc.is_some() {
+            self.crdt = doc;
+        }
+        self.current_state = Some(state);
+        self.state_revision += 1;
+        self.record_timeline();
+        Ok(())
+    }
+
+    /// Adopt a CRDT document after merging a client's operations
+    fn merge_state(&mut self, doc: CrdtDoc) -> morpheus_core::errors::Result<()> {
+        let state = doc.value();
+        self.record_snapshot(&state)?;
+        self.crdt = Some(doc);
         self.current_state = Some(state);
+        self.state_revision += 1;
+        self.record_timeline();
+        Ok(())
+    }
+
+    /// Append a domain event, folding it into the current state if it came
+    /// from the current component
+    fn record_event(&mut self, component: String, event: DomainEvent) -> morpheus_core::errors::Result<u64> {
+        let mut event = event.with_timestamp(Utc::now().to_rfc3339());
+        if let Some(version) = self.get_current() {
+            event = event.with_version(version.id as u32);
+        }
+        if component == self.current_component() {
+            let mut state = self.current_state.clone().unwrap_or_else(|| serde_json::json!({}));
+            MergePatchReducer.reduce(&mut state, &event);
+            self.record_snapshot(&state)?;
+            self.current_state = Some(state);
+            self.state_revision += 1;
+            self.record_timeline();
+        }
+        Ok(self.events.entry(component).or_default().append(event))
+    }
+
+    /// Add the current state to the time-travel timeline
+    fn record_timeline(&mut self) {
+        let entry = TimelineEntry {
+            version_id: self.get_current().map(|v| v.id),
+            state: self.current_state.clone().unwrap_or_default(),
+        };
+        match &mut self.state_timeline {
+            Some(timeline) => timeline.update(entry),
+            None => self.state_timeline = Some(VersionedState::new(entry)),
+        }
+    }
+
+    /// Move the time-travel cursor and describe where it lands
+    fn debug_step(&mut self, steps: i64, position: Option<usize>) -> Option<DebugStepResponse> {
+        let timeline = self.state_timeline.as_ref()?;
+        let mut cursor = timeline.time_travel();
+        if let Some(position) = position.or(self.debug_position) {
+            cursor.seek(position);
+        }
+        let entry = cursor.step(steps).clone();
+        let live = cursor.is_live();
+        let (position, positions, state_version) = (cursor.position(), cursor.positions(), cursor.version());
+        self.debug_position = (!live).then_some(position);
+
+        let version = entry.version_id.and_then(|id| self.versions.get(id));
+        Some(DebugStepResponse {
+            position,
+            positions,
+            live,
+            state_version,
+            version_id: entry.version_id,
+            state: entry.state,
+            wasm_base64: version.map(|v| v.wasm_base64.clone()),
+            js_glue: version.map(|v| v.js_glue.clone()),
+        })
+    }
+
+    /// Name of the current version's component
+    fn current_component(&self) -> String {
+        match self.get_current() {
+            Some(version) if !version.manifest.name.is_empty() => version.manifest.name.clone(),
+            _ => "main".to_string(),
+        }
+    }
+
+    /// Check new state against the snapshot size limit and record its size
+    fn record_snapshot(&mut self, state: &serde_json::Value) -> morpheus_core::errors::Result<()> {
+        let bytes = serde_json::to_vec(state)?;
+        let component = self.current_component();
+        let stats = self.snapshot_sizes.entry(component).or_default();
+        match self.snapshot_codec.encode(&bytes) {
+            Ok(stored) => {
+                stats.snapshots += 1;
+                stats.last_bytes = bytes.len();
+                stats.last_stored_bytes = stored.len();
+                stats.max_bytes = stats.max_bytes.max(bytes.len());
+                stats.total_bytes += bytes.len() as u64;
+                Ok(())
+            }
+            Err(e) => {
+                stats.rejected += 1;
+                Err(e)
+            }
+        }
+    }
+
+    /// The live version of each component: the current version, plus the
+    /// most recently activated version of every other component
+    fn active_versions(&self) -> Vec<&ComponentVersion> {
+        let mut active: Vec<&ComponentVersion> = self.get_current().into_iter().collect();
+        for version in self.versions.iter().rev().filter(|v| v.activated_at.is_some() && !self.is_reverted(v.id)) {
+            if !active.iter().any(|a| a.manifest.name == version.manifest.name) {
+                active.push(version);
+            }
+        }
+        active
+    }
+
+    /// Names of the components with versions, unnamed ones being `main`
+    fn components(&self) -> std::collections::BTreeSet<String> {
+        self.versions
+            .iter()
+            .map(|v| if v.manifest.name.is_empty() { default_component() } else { v.manifest.name.clone() })
+            .collect()
+    }
+
+    /// Release notes for a component's versions newer than `since`
+    fn release_notes(&self, component: &str, since: Option<usize>) -> Vec<ReleaseNote> {
+        self.versions
+            .iter()
+            .filter(|v| since.is_none_or(|since| v.id > since))
+            .filter(|v| v.manifest.name == component || (v.manifest.name.is_empty() && component == "main"))
+            .map(|v| ReleaseNote {
+                version_id: v.id,
+                name: v.name.clone(),
+                description: v.description.clone(),
+                created_at: v.created_at,
+                activated_at: v.activated_at,
+                summary: v.changelog.as_ref().map(|c| c.summary.clone()),
+            })
+            .collect()
     }
 
     fn get_history(&self) -> Vec<VersionSummary> {
+        let current = self.get_current().map(|v| v.id);
         self.versions
             .iter()
             .map(|v| VersionSummary {
@@ -142,15 +1028,37 @@ impl VersionHistory {
                 name: v.name.clone(),
                 description: v.description.clone(),
                 created_at: v.created_at.to_rfc3339(),
-                is_current: v.id == self.current_index,
+                is_current: Some(v.id) == current,
                 ai_generated: v.ai_generated,
+                review: v.review.clone(),
+                changelog: v.changelog.clone(),
+                guardrail_violations: v.guardrail_violations.clone(),
+                guardrail_override: v.guardrail_override.clone(),
+                provenance: v.provenance.clone(),
+                feedback_count: v.feedback.len(),
+                average_rating: feedback::average_rating(&v.feedback),
+                thumbnail_url: v.thumbnail.as_ref().map(|_| format!("/api/versions/{}/thumbnail", v.id)),
+                accessibility: v.accessibility.clone(),
+                performance: v.performance,
             })
             .collect()
     }
 }
 
+/// Version history as written to the store
+///
+/// Module bytes and state snapshots are stored as separate blobs, so
+/// versions are written with them stripped.
+#[derive(Serialize, Deserialize)]
+struct PersistedHistory {
+    versions: Vec<ComponentVersion>,
+    current_index: usize,
+    #[serde(default)]
+    groups: Vec<RollbackGroup>,
+}
+
 /// Version summary for history display
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 struct VersionSummary {
     id: usize,
     name: String,
@@ -158,6 +1066,19 @@ struct VersionSummary {
     created_at: String,
     is_current: bool,
     ai_generated: bool,
+    review: Review,
+    changelog: Option<ChangelogEntry>,
+    guardrail_violations: Vec<String>,
+    guardrail_override: Option<GuardrailOverride>,
+    provenance: Provenance,
+    feedback_count: usize,
+    average_rating: Option<f64>,
+    /// Where to get the version's preview, if it has one
+    thumbnail_url: Option<String>,
+    /// axe-core violations of the rendered version, if it was audited
+    accessibility: Option<Vec<AccessibilityViolation>>,
+    /// First-render time, bundle size and long tasks, if measured
+    performance: Option<PerformanceMeasurements>,
 }
 
 /// A message in the AI conversation
@@ -168,13 +1089,48 @@ struct Message {
 }
 
 /// Request to generate component with AI
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 struct GenerateRequest {
     prompt: String,
+    /// Component name (defaults to "main")
+    #[serde(default)]
+    component: Option<String>,
+    /// Existing components to embed into named slots
+    #[serde(default)]
+    slots: Vec<SlotDecl>,
+    /// Version the client based this request on; a newer version committed
+    /// in the meantime is a conflict
+    #[serde(default)]
+    base_version_id: Option<usize>,
+    /// What to do on conflict
+    #[serde(default)]
+    on_conflict: ConflictStrategy,
+    /// Permissions the component needs (none by default)
+    #[serde(default)]
+    permissions: Permissions,
+}
+
+/// How to resolve a concurrent modification
+#[derive(Deserialize, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum ConflictStrategy {
+    /// Return the conflict to the client
+    #[default]
+    Reject,
+    /// Re-prompt the AI against the new current version
+    Rebase,
+}
+
+/// A newer version was committed since the client's base version
+#[derive(Debug, Serialize, JsonSchema)]
+struct ConflictInfo {
+    base_version_id: usize,
+    current_version_id: usize,
+    current_description: String,
 }
 
 /// Response to generation request
-#[derive(Serialize)]
+#[derive(Clone, Serialize, JsonSchema)]
 struct GenerateResponse {
     success: bool,
     version_id: Option<usize>,
@@ -183,91 +1139,473 @@ struct GenerateResponse {
     error: Option<String>,
     iterations: u32,
     logs: Vec<String>,
+    slots: Vec<SlotMount>,
 }
 
-/// Request to update component state
+/// Query for components resembling a description
 #[derive(Deserialize)]
-struct UpdateStateRequest {
-    state: serde_json::Value,
+struct SimilarQuery {
+    q: String,
+    /// Most components to return (default 3)
+    #[serde(default)]
+    limit: Option<usize>,
+    /// Only components with this capability
+    #[serde(default)]
+    capability: Option<Capability>,
 }
 
-/// Response to state update
+/// Capabilities reported by a component's `__morpheus_describe()` export
+#[derive(Deserialize)]
+struct DescribeRequest {
+    /// The component that reported them
+    #[serde(default)]
+    component: Option<String>,
+    /// Or the version that was rendered, standing for its component
+    #[serde(default)]
+    version_id: Option<usize>,
+    description: ComponentDescription,
+}
+
+/// Interface a component reported, and the components it breaks
 #[derive(Serialize)]
-struct UpdateStateResponse {
+struct DescribeResponse {
     success: bool,
+    /// The component's version, bumped by how much its interface changed
+    version: Option<SemVer>,
+    interface_hash: Option<String>,
+    incompatibilities: Vec<Incompatibility>,
 }
 
-/// Request to rollback to a version
-#[derive(Deserialize)]
-struct RollbackRequest {
-    version_id: usize,
+/// A component's feature flag
+#[derive(Serialize)]
+struct FlagEntry {
+    component: String,
+    flag: ComponentFlag,
 }
 
-/// Response to rollback
-#[derive(Serialize)]
-struct RollbackResponse {
-    success: bool,
+/// Fork a component
+#[derive(Deserialize, JsonSchema)]
+struct ForkRequest {
+    /// Name of the new component
+    name: String,
+}
+
+/// A component forked from another
+#[derive(Serialize, JsonSchema)]
+struct ForkResponse {
+    component: String,
+    forked_from: String,
+    /// First version of the fork
     version_id: usize,
-    wasm_base64: String,
-    restored_state: Option<serde_json::Value>,
-    error: Option<String>,
+    slots: Vec<SlotMount>,
 }
 
-/// Get version history
-#[derive(Serialize)]
-struct HistoryResponse {
-    versions: Vec<VersionSummary>,
-    current_state: Option<serde_json::Value>,
+/// Deprecate a component
+#[derive(Deserialize, JsonSchema)]
+struct DeprecateRequest {
+    /// Component to use instead
+    #[serde(default)]
+    replacement: Option<String>,
+    /// Retire the component from then on, once nothing depends on it
+    #[serde(default)]
+    retire_after: Option<DateTime<Utc>>,
 }
 
-/// Request to fix a runtime error
-#[derive(Deserialize)]
-struct FixErrorRequest {
-    error_message: String,
+/// A component's lifecycle status and what still depends on it
+#[derive(Serialize, JsonSchema)]
+struct LifecycleResponse {
+    component: String,
+    lifecycle: Lifecycle,
+    /// Components that embed it, pin its interface or fall back to it
+    dependents: Vec<String>,
+}
+
+/// What clients render if a component fails to load
+#[derive(Serialize, JsonSchema)]
+struct FallbackResponse {
+    component: String,
+    /// The declared fallback; without one, clients show a short error message
+    fallback: Option<FallbackView>,
+}
+
+/// What the host should render for a component
+#[derive(Serialize, JsonSchema)]
+struct RenderResponse {
+    component: String,
+    /// "current", "experiment", "pinned", "previous_version" or "placeholder"
+    mode: String,
+    /// Variant the session was assigned, in "experiment" mode
+    variant: Option<Variant>,
     version_id: Option<usize>,
+    wasm_base64: Option<String>,
+    js_glue: Option<String>,
+    /// The version's module and glue as a matched pair, for loaders that
+    /// fetch and cache them separately
+    bundle: Option<BundleManifest>,
+    placeholder_html: Option<String>,
+    reason: Option<String>,
+    /// Deprecated components among it and its slots
+    warnings: Vec<String>,
+    /// What to render instead if the version fails to load
+    fallback: FallbackView,
 }
 
-// ============================================================================
-// Design Session API Structures
-// ============================================================================
+/// Query for what to render
+#[derive(Deserialize, JsonSchema)]
+struct RenderQuery {
+    /// Client session, for A/B experiment assignment
+    #[serde(default)]
+    session: Option<String>,
+    /// Leave out `wasm_base64` and `js_glue`; the caller loads the bundle
+    #[serde(default)]
+    manifest: bool,
+}
 
-/// Request to start a new design session
-#[derive(Deserialize)]
-struct DesignStartRequest {
-    prompt: String,
+/// Query for a component's build
+#[derive(Deserialize, JsonSchema)]
+struct ArtifactQuery {
+    /// Where the component will run (default: browser)
+    #[serde(default)]
+    environment: Environment,
 }
 
-/// Response to design session start
-#[derive(Serialize)]
-struct DesignStartResponse {
-    session_id: String,
-    draft: DraftInfo,
-    logs: Vec<String>,
+/// Request to schedule a version's activation
+#[derive(Deserialize)]
+struct ScheduleRequest {
+    version_id: usize,
+    activate_at: DateTime<Utc>,
 }
 
-/// Request to refine the current draft
+/// Query for a component's release notes
 #[derive(Deserialize)]
-struct DesignRefineRequest {
-    feedback: String,
+struct ChangelogQuery {
+    #[serde(default = "default_component")]
+    component: String,
+    /// Only include versions newer than this one
+    since: Option<usize>,
+    /// "json" (default) or "markdown"
+    #[serde(default)]
+    format: Option<String>,
 }
 
-/// Response to design refinement
+/// One version's entry in the release notes
 #[derive(Serialize)]
-struct DesignRefineResponse {
-    success: bool,
-    draft: DraftInfo,
-    logs: Vec<String>,
-    error: Option<String>,
+struct ReleaseNote {
+    version_id: usize,
+    name: String,
+    description: String,
+    created_at: DateTime<Utc>,
+    activated_at: Option<DateTime<Utc>>,
+    summary: Option<String>,
 }
 
-/// Request to commit the current design
+fn default_component() -> String {
+    "main".to_string()
+}
+
+/// Request to explain the changes between two versions
 #[derive(Deserialize)]
-struct DesignCommitRequest {
-    message: Option<String>,
+struct ExplainRequest {
+    from: usize,
+    to: usize,
 }
 
-/// Response to design commit
+/// AI explanation of the changes between two versions
+#[derive(Serialize)]
+struct ExplainResponse {
+    from: usize,
+    to: usize,
+    diff: String,
+    changelog: ChangelogEntry,
+}
+
+/// A reviewer's verdict on a version
+#[derive(Deserialize)]
+struct VerdictRequest {
+    reviewer: String,
+    status: ReviewStatus,
+}
+
+/// Query for a version packaged as a custom element
+#[derive(Deserialize)]
+struct ElementQuery {
+    /// Tag name (defaults to `morpheus-<component>`)
+    #[serde(default)]
+    tag: Option<String>,
+    /// Comma-separated props (defaults to the fields of the component's
+    /// reported state)
+    #[serde(default)]
+    props: Option<String>,
+    /// Comma-separated events (defaults to the component's reported `emits`)
+    #[serde(default)]
+    events: Option<String>,
+}
+
+/// Query for active components depending on a crate
+#[derive(Deserialize)]
+struct DependentsQuery {
+    #[serde(rename = "crate")]
+    crate_name: String,
+    /// Only match this version of the crate
+    #[serde(default)]
+    version: Option<String>,
+}
+
+/// An active component that depends on the queried crate
 #[derive(Serialize)]
+struct Dependent {
+    component: String,
+    version_id: usize,
+    crate_version: String,
+}
+
+/// Sign-off to activate a version despite guardrail violations
+#[derive(Deserialize)]
+struct OverrideRequest {
+    by: String,
+    reason: String,
+}
+
+/// Request to update component state
+#[derive(Deserialize, JsonSchema)]
+struct UpdateStateRequest {
+    state: serde_json::Value,
+}
+
+/// Response to state update
+#[derive(Serialize, JsonSchema)]
+struct UpdateStateResponse {
+    success: bool,
+    /// Revision the new state was stored as
+    revision: u64,
+}
+
+/// A domain event emitted by a component
+#[derive(Deserialize, JsonSchema)]
+struct EmitEventRequest {
+    #[serde(default = "default_component")]
+    component: String,
+    name: String,
+    #[serde(default)]
+    payload: serde_json::Value,
+}
+
+/// Response to an emitted event
+#[derive(Serialize, JsonSchema)]
+struct EmitEventResponse {
+    seq: u64,
+}
+
+/// Query for events after a sequence number
+#[derive(Deserialize, JsonSchema)]
+struct EventsQuery {
+    #[serde(default)]
+    since: u64,
+}
+
+/// Format of WebSocket sync messages
+#[derive(Deserialize)]
+struct SyncSocketQuery {
+    #[serde(default)]
+    format: Format,
+}
+
+/// Point to replay a component's events to: a sequence number, or the last
+/// event emitted by a version
+#[derive(Deserialize, JsonSchema)]
+struct ReplayQuery {
+    seq: Option<u64>,
+    version: Option<usize>,
+}
+
+/// State rebuilt from events
+#[derive(Serialize, JsonSchema)]
+struct ReplayResponse {
+    seq: u64,
+    state: serde_json::Value,
+}
+
+/// Move the time-travel cursor: by `steps` (negative is back), from
+/// `position` if given or else from where it was
+#[derive(Deserialize, Default, JsonSchema)]
+struct DebugStepRequest {
+    #[serde(default)]
+    steps: i64,
+    position: Option<usize>,
+}
+
+/// A point on the state timeline, with what is needed to re-render it
+#[derive(Serialize, JsonSchema)]
+struct DebugStepResponse {
+    position: usize,
+    positions: usize,
+    live: bool,
+    state_version: u64,
+    version_id: Option<usize>,
+    state: serde_json::Value,
+    wasm_base64: Option<String>,
+    js_glue: Option<String>,
+}
+
+/// Request to rollback to a version
+#[derive(Deserialize, JsonSchema)]
+struct RollbackRequest {
+    version_id: usize,
+}
+
+/// Response to rollback
+#[derive(Serialize, JsonSchema)]
+struct RollbackResponse {
+    success: bool,
+    version_id: usize,
+    wasm_base64: String,
+    restored_state: Option<serde_json::Value>,
+    error: Option<String>,
+}
+
+/// A component after its rollback group was undone
+#[derive(Serialize, JsonSchema)]
+struct RestoredComponent {
+    component: String,
+    /// Version it runs again (none if the group created it, so it was removed)
+    version_id: Option<usize>,
+}
+
+/// Response to rolling back a group
+#[derive(Serialize, JsonSchema)]
+struct GroupRollbackResponse {
+    success: bool,
+    group_id: usize,
+    /// Version current again
+    version_id: Option<usize>,
+    components: Vec<RestoredComponent>,
+    error: Option<String>,
+}
+
+/// Query for a version patch
+#[derive(Deserialize, JsonSchema)]
+struct PatchQuery {
+    from: usize,
+}
+
+/// A version's WASM encoded as a patch against another version
+#[derive(Serialize, JsonSchema)]
+struct PatchResponse {
+    version_id: usize,
+    base_version_id: usize,
+    wasm_size: usize,
+    patch_size: usize,
+    patch_base64: String,
+}
+
+/// Get version history
+#[derive(Serialize, JsonSchema)]
+struct HistoryResponse {
+    versions: Vec<VersionSummary>,
+    current_state: Option<serde_json::Value>,
+}
+
+/// Request to fix a runtime error
+#[derive(Deserialize, JsonSchema)]
+struct FixErrorRequest {
+    error_message: String,
+    version_id: Option<usize>,
+}
+
+/// Hand-edited source for a version
+#[derive(Deserialize, JsonSchema)]
+struct EditRequest {
+    /// The whole edited source
+    source: String,
+    /// What the edit changes
+    message: Option<String>,
+    /// Save even if another version was committed since the edited one
+    #[serde(default)]
+    force: bool,
+    /// Replace the version's assets, by file name (e.g. `styles.css`), with
+    /// these text contents; they go live together with the new module
+    assets: Option<BTreeMap<String, String>>,
+}
+
+/// A preview of a version, as rendered
+#[derive(Deserialize, JsonSchema)]
+struct ThumbnailRequest {
+    /// A PNG, JPEG or WebP `data:` URL of at most 64 KiB, as
+    /// `canvas.toDataURL()` returns
+    data_url: String,
+}
+
+/// A user's rating, sent by a component's feedback widget
+#[derive(Deserialize, JsonSchema)]
+struct FeedbackRequest {
+    /// 1 to 5
+    rating: u8,
+    #[serde(default)]
+    text: String,
+    /// Version being rated (defaults to the live version)
+    #[serde(default)]
+    version_id: Option<usize>,
+}
+
+/// Feedback recorded on a version
+#[derive(Serialize, JsonSchema)]
+struct FeedbackResponse {
+    version_id: usize,
+    feedback_count: usize,
+    average_rating: f64,
+}
+
+// ============================================================================
+// Design Session API Structures
+// ============================================================================
+
+/// Request to start a new design session
+#[derive(Deserialize, JsonSchema)]
+struct DesignStartRequest {
+    prompt: String,
+    /// Version the design builds on (defaults to the current version)
+    #[serde(default)]
+    base_version_id: Option<usize>,
+}
+
+/// Response to design session start
+#[derive(Serialize, JsonSchema)]
+struct DesignStartResponse {
+    session_id: String,
+    draft: DraftInfo,
+    logs: Vec<String>,
+}
+
+/// Request to refine the current draft
+#[derive(Deserialize, JsonSchema)]
+struct DesignRefineRequest {
+    feedback: String,
+    /// Draft iteration the client currently has loaded; when set, the new
+    /// WASM may be sent as a patch against it
+    #[serde(default)]
+    base_iteration: Option<usize>,
+}
+
+/// Response to design refinement
+#[derive(Serialize, JsonSchema)]
+struct DesignRefineResponse {
+    success: bool,
+    draft: DraftInfo,
+    logs: Vec<String>,
+    error: Option<String>,
+}
+
+/// Request to commit the current design
+#[derive(Deserialize, JsonSchema)]
+struct DesignCommitRequest {
+    message: Option<String>,
+    /// Commit even if another version was committed since the session started
+    #[serde(default)]
+    force: bool,
+}
+
+/// Response to design commit
+#[derive(Serialize, JsonSchema)]
 struct DesignCommitResponse {
     success: bool,
     version_id: usize,
@@ -276,7 +1614,7 @@ struct DesignCommitResponse {
 }
 
 /// Get current design preview
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 struct DesignPreviewResponse {
     active: bool,
     session_id: Option<String>,
@@ -285,18 +1623,21 @@ struct DesignPreviewResponse {
 }
 
 /// Information about a draft
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, JsonSchema)]
 struct DraftInfo {
     iteration: usize,
     prompt: String,
     wasm_base64: Option<String>,
+    /// Patch against draft `patch_base` (sent instead of `wasm_base64` when smaller)
+    wasm_patch: Option<String>,
+    patch_base: Option<usize>,
     js_glue: Option<String>,
     compilation_error: Option<String>,
     has_runtime_error: bool,
 }
 
 /// Conversation entry for display
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, JsonSchema)]
 struct ConversationEntry {
     role: String,
     content: String,
@@ -314,6 +1655,15 @@ struct ClaudeRequest {
 #[derive(Deserialize)]
 struct ClaudeResponse {
     choices: Vec<Choice>,
+    /// Tokens billed for the call, counted against the daily budget
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    prompt_tokens: u64,
+    completion_tokens: u64,
 }
 
 #[derive(Deserialize)]
@@ -328,54 +1678,453 @@ struct ResponseMessage {
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter("info,morpheus_compiler=debug")
+    // Initialize tracing; RUST_LOG overrides the default filter, and recent
+    // records are also kept in memory for GET /api/logs
+    let log_buffer = Arc::new(LogBuffer::from_env()?);
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,morpheus_compiler=debug")))
+        .with(tracing_subscriber::fmt::layer())
+        .with(log_buffer.layer())
         .init();
 
     info!("🧬 Starting Morpheus - Complete System");
 
     // Load environment variables
     dotenvy::dotenv().ok();
+    let mock = MockGenerator::from_env()?;
+    let model_endpoint = ModelEndpoint::from_env()?;
+    if model_endpoint.is_gateway() {
+        info!(
+            "✓ Completions go to the model gateway at {}{}{}",
+            model_endpoint.url(),
+            if model_endpoint.is_signed() { ", signed" } else { "" },
+            if model_endpoint.blocks_external() { ", external providers blocked" } else { "" }
+        );
+    }
     let api_key = std::env::var("OPENROUTER_API_KEY").unwrap_or_else(|_| {
-        warn!("OPENROUTER_API_KEY not set - AI features will not work!");
+        if mock.is_none() && !model_endpoint.is_gateway() {
+            warn!("OPENROUTER_API_KEY not set - AI features will not work!");
+        }
         String::new()
     });
+    if mock.is_some() {
+        warn!("Mock AI enabled - generations use canned templates, not a model");
+    }
+    let owner_token = std::env::var("MORPHEUS_OWNER_TOKEN").ok().filter(|token| !token.is_empty());
+    if owner_token.is_some() {
+        info!("✓ API requests need the owner token or a share link");
+    } else {
+        info!("Share links disabled (set MORPHEUS_OWNER_TOKEN to enable them)");
+    }
 
     // Check compiler tools
     SubprocessCompiler::check_tools()?;
     info!("✓ Rust compiler and wasm-pack available");
 
     // Initialize compiler
-    let compiler = SubprocessCompiler::new().await?;
-    info!("✓ Compiler initialized");
+    let snapshotting = env_flag("MORPHEUS_SNAPSHOT")?;
+    let advisory_policy = match std::env::var("MORPHEUS_ADVISORY_POLICY").as_deref() {
+        Ok("warn") => AdvisoryPolicy::Warn,
+        Ok("deny") => AdvisoryPolicy::Deny,
+        _ => AdvisoryPolicy::Off,
+    };
+    let wasm_opt = std::env::var("MORPHEUS_WASM_OPT").ok().filter(|level| !level.is_empty());
+    if wasm_opt.is_some() {
+        WasmOpt::check_tool()?;
+    }
+    let autofix = env_flag("MORPHEUS_AUTOFIX")?;
+    let verifier = std::env::var("MORPHEUS_VERIFIER_DIR").ok().map(Verifier::new);
+    if let Some(verifier) = &verifier {
+        verifier.check()?;
+    }
+    let accessibility_policy = AccessibilityPolicy::from_env();
+    let performance_budget = verification::performance_budget_from_env();
+    let extra_targets = std::env::var("MORPHEUS_EXTRA_TARGETS")
+        .unwrap_or_default()
+        .split(',')
+        .filter(|name| !name.trim().is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<ArtifactTarget>, _>>()?;
+    let mut host_crates = match std::env::var("MORPHEUS_HOST_CRATES") {
+        Ok(path) => HostCrate::load(std::path::Path::new(&path))?,
+        Err(_) => Vec::new(),
+    };
+    let types_dir = std::env::temp_dir().join("morpheus-compiler").join(morpheus_core::shared::CRATE_NAME);
+    host_crates.push(HostCrate::shared_types(&context::shared_types(), &types_dir)?);
+    let mut compiler = SubprocessCompiler::new()
+        .await?
+        .with_snapshotting(snapshotting)
+        .with_advisory_policy(advisory_policy)
+        .with_autofix(autofix)
+        .with_extra_targets(extra_targets)
+        .with_host_crates(host_crates.clone());
+    let mut headless_compiler = SubprocessCompiler::new()
+        .await?
+        .with_target(Target::Headless)
+        .with_snapshotting(snapshotting)
+        .with_advisory_policy(advisory_policy)
+        .with_autofix(autofix)
+        .with_host_crates(host_crates);
+    if let Some(verifier) = &verifier {
+        compiler = compiler.with_verifier(verifier.clone());
+    }
+    if let Some(level) = &wasm_opt {
+        compiler = compiler.with_transform(WasmOpt::new().with_level(level.as_str()));
+        headless_compiler = headless_compiler.with_transform(WasmOpt::new().with_level(level.as_str()));
+    }
+    let compiler = compiler.with_transform(SizeReport);
+    let headless_compiler = headless_compiler.with_transform(SizeReport);
+    let compiled_cache = match std::env::var("MORPHEUS_COMPILED_CACHE_DIR") {
+        Ok(dir) => {
+            let cache = CompiledCache::new(dir)?;
+            info!("✓ Compiled headless modules cached in {}", cache.dir().display());
+            Some(cache)
+        }
+        Err(_) => None,
+    };
+    // The budget goes last so it measures the module that ships
+    let compiler = match env_number("MORPHEUS_WASM_BUDGET_BYTES")? {
+        Some(max_bytes) => {
+            info!("✓ WASM size budget: {} bytes", max_bytes);
+            compiler.with_transform(SizeBudget::new(max_bytes))
+        }
+        None => compiler,
+    };
+    info!("✓ Compiler initialized (transforms: {})", compiler.transforms().join(", "));
+    if snapshotting {
+        // wasm-pack output always imports wasm-bindgen's `wbg` glue, which
+        // Wizer can't provide, so only headless builds are snapshotted
+        info!("✓ Pre-initialization snapshots enabled for headless components (browser builds ship unchanged)");
+    }
+    if autofix {
+        info!("✓ Pre-compile auto-fix enabled");
+    }
+    if !compiler.extra_targets().is_empty() {
+        let names: Vec<_> = compiler.extra_targets().iter().map(ToString::to_string).collect();
+        info!("✓ Extra build targets: {}", names.join(", "));
+    }
+    if advisory_policy != AdvisoryPolicy::Off {
+        info!("✓ RustSec advisory check enabled ({:?})", advisory_policy);
+    }
+    if let Some(verifier) = &verifier {
+        info!(
+            "✓ Builds rendered and audited in headless Chrome ({}), accessibility policy {:?}",
+            verifier.dir().display(),
+            accessibility_policy
+        );
+    }
+    if !performance_budget.is_empty() {
+        info!("✓ Performance budget: {:?}", performance_budget);
+    }
+    if !compiler.host_crates().is_empty() {
+        let names: Vec<_> = compiler.host_crates().iter().map(|c| c.name.as_str()).collect();
+        info!("✓ Host crates available to components: {}", names.join(", "));
+    }
+
+    // Optionally mirror version history into git
+    let require_review = env_flag("MORPHEUS_REQUIRE_REVIEW")?;
+    let mut versions = VersionHistory::new().with_review_required(require_review);
+    if require_review {
+        info!("✓ Review required before activation");
+    }
+    let guardrails = guardrails_from_env()?;
+    if guardrails.max_wasm_growth.is_some()
+        || guardrails.max_lines_changed.is_some()
+        || guardrails.max_complexity.is_some()
+        || !guardrails.banned_escalations.is_empty()
+    {
+        info!("✓ Change guardrails enabled: {:?}", guardrails);
+    }
+    versions = versions.with_guardrails(guardrails);
+    let snapshot_codec = snapshot_codec_from_env()?;
+    match snapshot_codec.max_size {
+        Some(limit) => info!("✓ State snapshots limited to {} bytes", limit),
+        None => warn!("State snapshot size limit disabled"),
+    }
+    versions = versions.with_snapshot_codec(snapshot_codec);
+    match std::env::var("MORPHEUS_STATE_MODE").as_deref() {
+        Ok("crdt") => {
+            versions = versions.with_crdt(true);
+            info!("✓ CRDT state merging enabled");
+        }
+        Ok("events") => {
+            versions = versions.with_event_sourcing(true);
+            info!("✓ Event-sourced state enabled");
+        }
+        _ => {}
+    }
+    let store = match std::env::var("MORPHEUS_STORE") {
+        Ok(location) => {
+            let store = open_store(&location).await?;
+            versions.restore(store.as_ref()).await?;
+            info!("✓ Version history stored at {} ({} versions restored)", location, versions.versions.len());
+            Some(store)
+        }
+        Err(_) => None,
+    };
+    if let Ok(path) = std::env::var("MORPHEUS_GIT_HISTORY") {
+        versions = versions.with_git(GitHistory::open_or_init(std::path::Path::new(&path))?);
+        info!("✓ Git history enabled at {}", path);
+    }
+
+    let scrub_policy = scrub_policy_from_env()?;
+    if !scrub_policy.is_empty() {
+        info!(
+            "✓ State scrubbing enforced ({} denied fields, {} redactions)",
+            scrub_policy.denied_fields.len(),
+            scrub_policy.redactions.len()
+        );
+    }
+
+    let telemetry_policy = telemetry_policy_from_env()?;
+    if telemetry_policy.is_empty() {
+        warn!("Telemetry scrubbing disabled - runtime errors and crash state reach the AI as reported");
+    }
+
+    let screener = screener_from_env()?;
+    if screener.is_empty() {
+        warn!("Request screening disabled - user text reaches the AI unchecked");
+    } else {
+        info!("✓ Request screening enforced ({} rules)", screener.rules.len());
+    }
+
+    let limits = LimitsConfig::from_env()?;
+    info!(
+        "✓ AI limits: {}/min per IP, {}/min per key ({} keys), {} concurrent generations",
+        limits.per_ip_per_minute,
+        limits.per_key_per_minute,
+        limits.api_keys.len(),
+        limits.max_concurrent
+    );
+    if let Some(tokens) = limits.daily_tokens {
+        info!("✓ Daily token budget: {}", tokens);
+    }
+    if let Some(cost) = limits.daily_cost_usd {
+        info!("✓ Daily cost budget: ${:.2}", cost);
+    }
+
+    let examples = ExampleStore::from_env(&model_endpoint)?;
+    if let Some(store) = &store {
+        let restored = examples.restore(store.as_ref()).await?;
+        info!("✓ {} few-shot examples restored", restored);
+    }
+    info!("✓ Few-shot examples embedded with {}", examples.embedder().name());
+
+    let generation = GenerationPolicy::from_env()?;
+    match &generation.cheap_model {
+        Some(cheap) => info!(
+            "✓ Model routing: {} for trivial tweaks, {} otherwise (escalating after {} failed compiles)",
+            cheap, generation.frontier_model, generation.escalate_after
+        ),
+        None => info!("✓ Generating with {}", generation.frontier_model),
+    }
+
+    let autonomous_policy = AutonomousPolicy::from_env()?;
+    if let Some(policy) = &autonomous_policy {
+        info!(
+            "✓ Autonomous mode every {}s (proposals {})",
+            policy.interval.as_secs(),
+            if policy.activate { "activate when policy allows" } else { "await activation" }
+        );
+    }
+
+    let translations = i18n::Translations::from_env()?;
+    let locales = translations.catalogs().locales();
+    if !locales.is_empty() {
+        info!("✓ Translations loaded: {}", locales.join(", "));
+    }
+
+    let themes = theme::HostThemes::from_env()?;
+    let (theme_names, active_theme) = themes.names().await;
+    info!("✓ Themes: {} (active: {})", theme_names.join(", "), active_theme);
+
+    let registry = registry_from_env()?;
 
     // Create application state
     let state = AppState {
         compiler: Arc::new(compiler),
-        versions: Arc::new(Mutex::new(VersionHistory::new())),
+        versions: Arc::new(Mutex::new(versions)),
         conversation: Arc::new(Mutex::new(Vec::new())),
         design_session: Arc::new(Mutex::new(None)),
+        registry: Arc::new(Mutex::new(registry)),
+        schedule: Arc::new(Mutex::new(Vec::new())),
+        audit_log: Arc::new(Mutex::new(Vec::new())),
+        headless_compiler: Arc::new(headless_compiler),
+        headless: Arc::new(Mutex::new(HeadlessRegistry::default())),
+        compiled_cache,
+        telemetry: Arc::new(Mutex::new(Telemetry::default())),
+        profiler: Arc::new(Mutex::new(Profiler::new())),
+        crashes: Arc::new(Mutex::new(crashes::Crashes::default())),
+        autonomous: Arc::new(Autonomous::new(autonomous_policy.clone())),
+        experiments: Arc::new(Mutex::new(Experiments::new())),
+        limits: Arc::new(Limits::new(limits)),
+        jobs: Arc::new(Jobs::default()),
+        examples: Arc::new(examples),
+        generation: Arc::new(generation),
+        logs: log_buffer,
+        mock: mock.map(Arc::new),
+        store,
+        scrub_policy: Arc::new(scrub_policy),
+        telemetry_policy: Arc::new(telemetry_policy),
+        accessibility_policy,
+        performance_budget,
+        screener: Arc::new(screener),
+        state_sync: broadcast::channel(16).0,
+        notifications: Arc::new(Mutex::new(NotificationLimiter::default())),
+        locations: Arc::new(Mutex::new(LocationGate::default())),
+        shares: Arc::new(Mutex::new(ShareLinks::default())),
+        translations: Arc::new(Mutex::new(translations)),
+        themes: Arc::new(themes),
+        search_index: Arc::new(Mutex::new(SourceIndex::new())),
+        owner_token,
         api_key,
+        model_endpoint: Arc::new(model_endpoint),
     };
 
-    // Build router
-    let app = Router::new()
+    // Apply scheduled activations in the background
+    tokio::spawn(run_scheduler(state.clone()));
+    info!("✓ Activation scheduler running");
+    tokio::spawn(jobs::run_worker(state.clone()));
+    if let Some(store) = state.store.clone() {
+        tokio::spawn(run_persistence(state.clone(), store));
+    }
+    if let Some(policy) = autonomous_policy {
+        tokio::spawn(autonomous::run_autonomous(state.clone(), policy));
+    }
+
+    // Endpoints that call the AI, behind rate limits and the budget
+    let generation = Router::new()
         // Legacy endpoints (for backwards compatibility)
         .route("/api/generate", post(generate_component))
+        .route("/api/plan", post(planner::create_plan))
         .route("/api/fix", post(fix_runtime_error))
-        // Design workflow endpoints
+        .route("/api/jobs", post(jobs::create_job))
         .route("/api/design/start", post(design_start))
         .route("/api/design/refine", post(design_refine))
+        .route("/api/explain", post(explain_change))
+        .route("/api/autonomous/run", post(autonomous::run_now))
+        .route("/api/headless/generate", post(headless::generate_headless))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), limits::limit_generation));
+
+    // Build router
+    let app = Router::new()
+        .merge(generation)
+        // Generation job status
+        .route("/api/jobs/:id", get(jobs::get_job).delete(jobs::cancel_job))
+        .route("/api/jobs/:id/events", get(jobs::job_events))
+        // Design workflow endpoints
         .route("/api/design/commit", post(design_commit))
         .route("/api/design/preview", get(design_preview))
         .route("/api/design/cancel", post(design_cancel))
+        .route("/api/examples", get(fewshot::get_examples))
         // State management endpoints
         .route("/api/state", post(update_state))
+        .route("/api/state/policy", get(get_scrub_policy))
+        .route("/api/state/metrics", get(get_snapshot_metrics))
+        .route("/api/state/sync", get(state_sync_socket).post(sync_state))
+        .route("/api/events", post(emit_event))
+        .route("/api/debug/step", get(get_debug_position).post(debug_step))
+        .route("/api/events/:component", get(list_events))
+        .route("/api/events/:component/replay", get(replay_events))
         .route("/api/rollback", post(rollback))
         .route("/api/history", get(get_history))
+        .route("/api/groups", get(list_groups))
+        .route("/api/groups/:id/rollback", post(rollback_group))
+        .route("/api/reloads", get(reload_events))
+        .route("/api/versions/:id/patch", get(get_version_patch))
+        .route("/api/versions/:id/bundle", get(get_version_bundle))
+        .route("/api/versions/:id/component.wasm", get(get_version_wasm))
+        .route("/api/versions/:id/component.js", get(get_version_glue))
+        .route("/api/assets/:fingerprint/:name", get(get_asset))
+        .route("/api/versions/:id/review", get(get_review).post(submit_verdict))
+        .route("/api/versions/:id/review/comments", post(add_review_comment))
+        .route("/api/versions/:id/override", post(override_guardrails))
+        .route("/api/versions/:id/sbom", get(get_version_sbom))
+        .route("/api/versions/:id/thumbnail", get(get_thumbnail).post(set_thumbnail))
+        .route("/api/versions/:id/inspect", get(inspect_version))
+        .route("/api/versions/:id/edit", post(edit_version))
+        .route("/api/language", post(language::analyze))
+        .route("/api/search", get(search::search))
+        .route("/api/versions/:id/element.js", get(get_version_element))
+        .route("/api/versions/:id/feedback", get(get_version_feedback))
+        .route("/api/feedback", post(submit_feedback))
+        .route("/api/sbom/dependents", get(find_dependents))
+        .route("/api/changelog", get(get_changelog))
         .route("/api/health", get(health_check))
+        .route("/api/openapi.json", get(get_openapi_spec))
+        .route("/api/client.ts", get(get_typescript_client))
+        .route("/api/adapters/:file", get(adapters::get_adapter))
+        // Component catalog endpoints
+        .route("/api/catalog", get(get_catalog))
+        .route("/api/catalog/describe", post(describe_component))
+        .route("/api/catalog/similar", get(find_similar_components))
+        // Feature flag endpoints
+        .route("/api/components", get(list_components))
+        .route("/api/components/stats", post(report_calls))
+        .route("/api/flags", get(list_flags))
+        .route(
+            "/api/components/:name/flag",
+            post(set_component_flag).delete(clear_component_flag),
+        )
+        .route("/api/components/:name/render", get(render_component))
+        .route(
+            "/api/components/:name/fallback",
+            post(set_component_fallback).delete(clear_component_fallback),
+        )
+        .route("/api/preload", get(preload::get_preload))
+        .route(
+            "/api/components/:name/lifecycle",
+            get(get_component_lifecycle).post(deprecate_component),
+        )
+        .route("/api/components/:name/retire", post(retire_component))
+        .route("/api/components/:name/fork", post(fork_component))
+        .route("/api/components/:name/artifact", get(get_component_artifact))
+        // A/B experiment endpoints
+        .route("/api/experiments", get(experiments::list_experiments).post(experiments::start_experiment))
+        .route("/api/experiments/:component/conversion", post(experiments::record_conversion))
+        .route("/api/experiments/:component/winner", post(experiments::declare_winner))
+        // Scheduled activation endpoints
+        .route("/api/schedule", get(list_schedule).post(schedule_activation))
+        .route("/api/schedule/:id", axum::routing::delete(cancel_activation))
+        .route("/api/audit", get(get_audit_log))
+        .route("/api/permissions", get(host_apis::get_permissions))
+        .route("/api/permissions/requests", post(host_apis::record_request))
+        .route("/api/notifications", post(host_apis::notify))
+        .route("/api/geolocation", post(host_apis::locate))
+        .route("/api/sandbox/frame", get(host_apis::get_sandbox_frame))
+        .route("/api/limits", get(limits::get_limits))
+        .route("/api/i18n", get(i18n::get_translations))
+        .route("/api/i18n/:locale", get(i18n::get_locale).post(i18n::update_locale))
+        .route("/api/themes", get(theme::list_themes).post(theme::register_theme))
+        .route("/api/themes/active", post(theme::set_active_theme))
+        .route("/api/themes/events", get(theme::theme_events))
+        .route("/api/logs", get(logs::get_logs))
+        .route("/api/overview", get(overview::get_overview))
+        // Read-only spectator links
+        .route("/api/share", get(sharing::list_shares).post(sharing::create_share))
+        .route("/api/share/:token", axum::routing::delete(sharing::revoke_share))
+        .route("/api/spectate", get(sharing::spectate))
+        // Telemetry and autonomous mode
+        .route("/api/telemetry", get(autonomous::list_telemetry).post(autonomous::report_telemetry))
+        .route("/api/profiler", get(autonomous::list_render_profiles).post(autonomous::report_renders))
+        .route("/api/crash", post(crashes::report_crash))
+        .route("/api/crashes", get(crashes::list_crashes))
+        .route("/api/autonomous", get(autonomous::get_status))
+        // Headless (backend) components
+        .route("/api/headless", get(headless::list_headless))
+        .route("/api/headless/:name/history", get(headless::headless_history))
+        .route("/api/headless/:name/rollback", post(headless::rollback_headless))
+        .route("/api/headless/:name/transform", post(headless::transform))
+        .route("/x/:name", any(headless::dispatch_root))
+        .route("/x/:name/*path", any(headless::dispatch_path))
+        // Server-rendered pages
+        .route("/pages/history", get(pages::history_page))
+        .route("/pages/history/:id", get(pages::version_page))
+        .route("/pages/components", get(pages::components_page))
+        .route("/pages/components/:name", get(pages::component_page))
+        .route("/pages/playground", get(pages::playground_page))
         .nest_service("/", ServeDir::new("examples/morpheus-complete/public"))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), sharing::authorize))
         .layer(CorsLayer::permissive())
         .with_state(state);
 
@@ -385,7 +2134,8 @@ async fn main() -> anyhow::Result<()> {
     info!("   The complete system - All 6 phases integrated!");
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    // Peer addresses are needed for per-IP rate limits
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
     Ok(())
 }
@@ -399,39 +2149,129 @@ async fn health_check() -> impl IntoResponse {
     }))
 }
 
-/// Generate component with AI (integrates Phase 5 + Phase 6)
+/// OpenAPI description of the API
+async fn get_openapi_spec() -> Json<serde_json::Value> {
+    Json(openapi::spec())
+}
+
+/// TypeScript client generated from the OpenAPI description
+async fn get_typescript_client() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/typescript; charset=utf-8")],
+        openapi::typescript_client(&openapi::spec()),
+    )
+}
+
+/// Generate component with AI, waiting for the job to finish
 async fn generate_component(
     State(state): State<AppState>,
     Json(req): Json<GenerateRequest>,
 ) -> Result<Json<GenerateResponse>, AppError> {
+    screen_request(&state, "generate", &req.prompt).await?;
+    let (job, outcome) = state.jobs.enqueue(req, true).await?;
+    let outcome = outcome.expect("waiting jobs have a reply channel");
+    match outcome.await {
+        Ok(response) => response.map(Json),
+        Err(_) => Err(AppError::ApiError(format!("Job {} was cancelled", job.id))),
+    }
+}
+
+/// Generate component with AI (integrates Phase 5 + Phase 6), reporting
+/// progress to the job running it
+async fn generate(
+    state: &AppState,
+    req: GenerateRequest,
+    progress: &jobs::Progress,
+) -> Result<GenerateResponse, AppError> {
     info!("AI generation request: {}", req.prompt);
 
     let mut logs = Vec::new();
     logs.push(format!("🎯 User request: {}", req.prompt));
 
     // Check API key
-    if state.api_key.is_empty() {
+    if !state.has_ai() {
         return Err(AppError::ApiError(
             "OPENROUTER_API_KEY not configured".to_string(),
         ));
     }
 
+    // Slots must reference components that already exist
+    let manifest = ComponentManifest {
+        name: req.component.clone().unwrap_or_else(|| "main".to_string()),
+        description: req.prompt.clone(),
+        slots: req.slots.clone(),
+        permissions: req.permissions.clone(),
+    };
+    let available = state.registry.lock().await.catalog();
+    check_slots(&manifest, &available)?;
+
     const MAX_ITERATIONS: u32 = 5;
     let mut iteration = 0;
+    let mut base_version_id = req.base_version_id;
+    let mut rebased = false;
+
+    // Users' feedback on the version being replaced steers the change
+    let feedback = state
+        .versions
+        .lock()
+        .await
+        .get_current()
+        .filter(|v| v.manifest.name == manifest.name)
+        .map(|v| v.feedback.clone())
+        .unwrap_or_default();
+    if !feedback.is_empty() {
+        logs.push(format!("⭐ Including {} feedback entries on the current version", feedback.len()));
+    }
 
     // Reset conversation
+    let examples = state.examples.similar(&req.prompt).await;
+    let used_examples = !examples.is_empty();
+    if used_examples {
+        logs.push(format!("📚 Including {} working example(s) for similar requests", examples.len()));
+    }
+    // Existing components the request might reuse rather than regenerate
+    let similar: Vec<_> = state
+        .registry
+        .lock()
+        .await
+        .find_similar(&req.prompt, MAX_SIMILAR_COMPONENTS + 1)
+        .into_iter()
+        .filter(|component| component.name != manifest.name)
+        .take(MAX_SIMILAR_COMPONENTS)
+        .collect();
+    if !similar.is_empty() {
+        let names: Vec<_> = similar.iter().map(|c| c.name.as_str()).collect();
+        logs.push(format!("♻️  Similar existing components: {}", names.join(", ")));
+    }
+    // Other components' code using names the request mentions
+    let usages = search::usages(state, &req.prompt, &manifest.name).await;
+    if !usages.is_empty() {
+        logs.push(format!("🔎 Including {} usage(s) of names in the request", usages.len()));
+    }
+    let context = PromptContext::gather(state, Some(&manifest.name))
+        .await
+        .with_examples(examples)
+        .with_similar(similar)
+        .with_usages(usages);
+    if context.has_source() {
+        logs.push(format!("📎 Including the current '{}' source", manifest.name));
+    }
     let mut conversation = state.conversation.lock().await;
     conversation.clear();
     conversation.push(Message {
         role: "user".to_string(),
-        content: create_system_prompt(),
+        content: context.system_prompt(),
     });
     conversation.push(Message {
         role: "user".to_string(),
-        content: format!("Create a WASM component: {}", req.prompt),
+        content: create_generation_request(&req.prompt, &manifest, &feedback),
     });
     drop(conversation);
 
+    let complexity = routing::classify(&req.prompt, context.has_source());
+    let mut compile_failures = 0;
+    let mut previous_model: Option<String> = None;
+
     // AI + Compilation retry loop
     loop {
         iteration += 1;
@@ -439,7 +2279,8 @@ async fn generate_component(
 
         if iteration > MAX_ITERATIONS {
             logs.push("❌ Max iterations reached".to_string());
-            return Ok(Json(GenerateResponse {
+            state.examples.record_outcome(used_examples, iteration - 1, false);
+            return Ok(GenerateResponse {
                 success: false,
                 version_id: None,
                 wasm_base64: None,
@@ -447,19 +2288,33 @@ async fn generate_component(
                 error: Some("Failed after 5 attempts".to_string()),
                 iterations: iteration - 1,
                 logs,
-            }));
+                slots: Vec::new(),
+            });
         }
 
         // Call AI
+        progress.set(JobStatus::AwaitingAi, iteration).await;
+        let model = state.generation.model_for(complexity, compile_failures).to_string();
+        match &previous_model {
+            None if model != state.generation.frontier_model => {
+                logs.push(format!("🧭 Routed to {} ({:?} change)", model, complexity))
+            }
+            Some(previous) if *previous != model => logs.push(format!(
+                "⬆️  Escalating from {} to {} after {} failed compiles",
+                previous, model, compile_failures
+            )),
+            _ => {}
+        }
+        previous_model = Some(model.clone());
         logs.push("🤖 Asking AI to generate Rust code...".to_string());
-        let rust_code = match call_claude_api(&state).await {
+        let rust_code = match call_claude_api(state, &model).await {
             Ok(code) => {
                 logs.push(format!("✓ AI generated {} bytes of code", code.len()));
                 code
             }
             Err(e) => {
                 error!("Claude API error: {}", e);
-                return Ok(Json(GenerateResponse {
+                return Ok(GenerateResponse {
                     success: false,
                     version_id: None,
                     wasm_base64: None,
@@ -467,29 +2322,80 @@ async fn generate_component(
                     error: Some(format!("AI API error: {}", e)),
                     iterations: iteration,
                     logs,
-                }));
+                    slots: Vec::new(),
+                });
             }
         };
 
         // Compile
+        progress.set(JobStatus::Compiling, iteration).await;
         logs.push("⚙️  Compiling Rust → WASM...".to_string());
-        match state.compiler.compile(&rust_code).await {
+        match compile_verified(state, &rust_code, &manifest.name).await {
             Ok(result) => {
                 // SUCCESS! Now save with state preservation (Phase 6)
+                let rust_code = source::tidy(result.fixed_source.as_deref().unwrap_or(&rust_code)).await;
                 logs.push(format!(
                     "✅ Compilation successful! {} bytes of WASM + {} bytes of JS glue",
                     result.wasm_bytes.len(),
                     result.js_glue.len()
                 ));
                 logs.push(format!("🎉 Component ready after {} iteration(s)", iteration));
+                for diagnostic in &result.diagnostics {
+                    logs.push(format!("🛡️  {}", diagnostic.message));
+                }
+                match &result.snapshot {
+                    Some(SnapshotOutcome::Snapshotted { original_size, snapshot_size }) => logs.push(format!(
+                        "❄️  Pre-initialized snapshot ({} → {} bytes)",
+                        original_size, snapshot_size
+                    )),
+                    Some(SnapshotOutcome::Skipped(reason)) => {
+                        logs.push(format!("❄️  Snapshot skipped: module {}", reason))
+                    }
+                    None => {}
+                }
 
-                // Get current state for preservation
+                // Detect versions committed since the client's base
+                progress.set(JobStatus::Saving, iteration).await;
                 let mut history = state.versions.lock().await;
+                if let Some(conflict) = detect_conflict(&history, base_version_id) {
+                    if req.on_conflict == ConflictStrategy::Rebase && !rebased {
+                        logs.push(format!(
+                            "🔀 Version {} was committed meanwhile, rebasing onto it...",
+                            conflict.current_version_id
+                        ));
+                        let current_code = history
+                            .get_current()
+                            .map(|v| v.rust_code.clone())
+                            .unwrap_or_default();
+                        drop(history);
+
+                        rebased = true;
+                        base_version_id = Some(conflict.current_version_id);
+                        let mut conversation = state.conversation.lock().await;
+                        conversation.push(Message {
+                            role: "assistant".to_string(),
+                            content: rust_code,
+                        });
+                        conversation.push(Message {
+                            role: "user".to_string(),
+                            content: create_rebase_prompt(&current_code, &req.prompt),
+                        });
+                        continue;
+                    }
+                    logs.push(format!(
+                        "⚠️  Conflict: version {} was committed since base version {}",
+                        conflict.current_version_id, conflict.base_version_id
+                    ));
+                    return Err(AppError::Conflict(conflict));
+                }
+
+                // Get current state for preservation
                 let restored_state = history.current_state.clone();
 
                 // Add to version history with state preservation
                 let version_name = format!("AI Generated: {}", truncate(&req.prompt, 40));
                 let version_desc = req.prompt.clone();
+                let example_source = rust_code.clone();
                 let version_id = history.add_version(
                     version_name,
                     version_desc,
@@ -497,16 +2403,40 @@ async fn generate_component(
                     result.wasm_bytes.clone(),
                     result.js_glue.clone(),
                     true, // AI generated
+                    manifest.clone(),
+                    Provenance::ai(req.prompt.clone(), model).with_toolchain(state.compiler.toolchain()),
+                    true,
                 );
 
                 logs.push(format!("📜 Saved as version {} in history", version_id));
+                info!(version = version_id, attempts = iteration, "📜 Saved generated version");
+                if history.require_review {
+                    logs.push("📝 Awaiting review before activation".to_string());
+                }
                 if restored_state.is_some() {
                     logs.push("🔒 State preserved from previous version!".to_string());
                 }
+                history.versions[version_id].sbom = result.sbom.clone();
+                history.versions[version_id].variants = result.variants.clone();
+                history.versions[version_id].record_verification(result.verification);
+                let violations = history.versions[version_id].guardrail_violations.clone();
+                let provenance = history.versions[version_id].provenance.clone();
+                let artifacts = history.versions[version_id].artifacts();
+
+                drop(history);
+                report_guardrails(state, version_id, &violations, &mut logs).await;
+
+                state.examples.record_outcome(used_examples, iteration, true);
+                state.examples.add(&req.prompt, &example_source, Some(version_id), state.store.as_deref()).await;
+
+                let slots = register_component(state, manifest, &result.wasm_bytes, provenance, artifacts).await?;
+                for slot in &slots {
+                    logs.push(format!("🧩 Slot '{}' mounts at #{}", slot.slot, slot.mount_point));
+                }
 
                 let wasm_base64 = base64_encode(&result.wasm_bytes);
 
-                return Ok(Json(GenerateResponse {
+                return Ok(GenerateResponse {
                     success: true,
                     version_id: Some(version_id),
                     wasm_base64: Some(wasm_base64),
@@ -514,11 +2444,14 @@ async fn generate_component(
                     error: None,
                     iterations: iteration,
                     logs,
-                }));
+                    slots,
+                });
             }
             Err(e) => {
                 // Compilation failed - feed error back to AI
+                compile_failures += 1;
                 let error_msg = e.to_string();
+                warn!(attempt = iteration, "❌ Compilation failed, retrying");
                 logs.push(format!("❌ Compilation failed:\n{}", error_msg));
                 logs.push("🔄 Feeding error back to AI for retry...".to_string());
 
@@ -548,13 +2481,14 @@ async fn fix_runtime_error(
     Json(req): Json<FixErrorRequest>,
 ) -> Result<Json<GenerateResponse>, AppError> {
     info!("Fix runtime error request: {}", req.error_message);
+    screen_request(&state, "fix", &req.error_message).await?;
 
     let mut logs = Vec::new();
     logs.push("🔧 Attempting to fix runtime error...".to_string());
     logs.push(format!("❌ Error: {}", req.error_message));
 
     // Check API key
-    if state.api_key.is_empty() {
+    if !state.has_ai() {
         return Err(AppError::ApiError(
             "OPENROUTER_API_KEY not configured".to_string(),
         ));
@@ -572,11 +2506,12 @@ async fn fix_runtime_error(
     logs.push(format!("📝 Original request: {}", original_prompt));
 
     // Update conversation with the error
+    let context = PromptContext::gather(&state, None).await;
     let mut conversation = state.conversation.lock().await;
     conversation.clear();
     conversation.push(Message {
         role: "user".to_string(),
-        content: create_system_prompt(),
+        content: context.system_prompt(),
     });
     conversation.push(Message {
         role: "user".to_string(),
@@ -613,12 +2548,13 @@ async fn fix_runtime_error(
                 error: Some("Failed to fix after 5 attempts".to_string()),
                 iterations: iteration - 1,
                 logs,
+                slots: Vec::new(),
             }));
         }
 
         // Call AI
         logs.push("🤖 Asking AI to fix the code...".to_string());
-        let rust_code = match call_claude_api(&state).await {
+        let rust_code = match call_claude_api(&state, &state.generation.frontier_model).await {
             Ok(code) => {
                 logs.push(format!("✓ AI generated {} bytes of fixed code", code.len()));
                 code
@@ -633,13 +2569,14 @@ async fn fix_runtime_error(
                     error: Some(format!("AI API error: {}", e)),
                     iterations: iteration,
                     logs,
+                    slots: Vec::new(),
                 }));
             }
         };
 
         // Compile
         logs.push("⚙️  Compiling fixed Rust → WASM...".to_string());
-        match state.compiler.compile(&rust_code).await {
+        match compile_verified(&state, &rust_code, "main").await {
             Ok(result) => {
                 logs.push(format!(
                     "✅ Compilation successful! {} bytes of WASM + {} bytes of JS glue",
@@ -647,6 +2584,10 @@ async fn fix_runtime_error(
                     result.js_glue.len()
                 ));
                 logs.push(format!("🎉 Fixed component ready after {} iteration(s)", iteration));
+                for diagnostic in &result.diagnostics {
+                    logs.push(format!("🛡️  {}", diagnostic.message));
+                }
+                let rust_code = source::tidy(result.fixed_source.as_deref().unwrap_or(&rust_code)).await;
 
                 // Get current state for preservation
                 let mut history = state.versions.lock().await;
@@ -655,6 +2596,7 @@ async fn fix_runtime_error(
                 // Add to version history with state preservation
                 let version_name = format!("AI Fixed: {}", truncate(&original_prompt, 40));
                 let version_desc = format!("{} (fixed runtime error)", original_prompt);
+                let manifest = ComponentManifest::new("main", version_desc.clone());
                 let new_version_id = history.add_version(
                     version_name,
                     version_desc,
@@ -662,12 +2604,24 @@ async fn fix_runtime_error(
                     result.wasm_bytes.clone(),
                     result.js_glue.clone(),
                     true, // AI generated
+                    manifest,
+                    Provenance::ai(original_prompt.clone(), state.generation.frontier_model.as_str()).with_toolchain(state.compiler.toolchain()),
+                    true,
                 );
 
                 logs.push(format!("📜 Saved as version {} in history", new_version_id));
+                if history.require_review {
+                    logs.push("📝 Awaiting review before activation".to_string());
+                }
                 if restored_state.is_some() {
                     logs.push("🔒 State preserved from previous version!".to_string());
                 }
+                history.versions[new_version_id].sbom = result.sbom.clone();
+                history.versions[new_version_id].variants = result.variants.clone();
+                history.versions[new_version_id].record_verification(result.verification);
+                let violations = history.versions[new_version_id].guardrail_violations.clone();
+                drop(history);
+                report_guardrails(&state, new_version_id, &violations, &mut logs).await;
 
                 let wasm_base64 = base64_encode(&result.wasm_bytes);
 
@@ -679,6 +2633,7 @@ async fn fix_runtime_error(
                     error: None,
                     iterations: iteration,
                     logs,
+                    slots: Vec::new(),
                 }));
             }
             Err(e) => {
@@ -701,97 +2656,1991 @@ async fn fix_runtime_error(
                 });
                 drop(conversation);
 
-                // Loop continues for retry
+                // Loop continues for retry
+            }
+        }
+    }
+}
+
+/// Update component state
+async fn update_state(
+    State(state): State<AppState>,
+    Accept(format): Accept,
+    Payload(req): Payload<UpdateStateRequest>,
+) -> Result<Encoded<UpdateStateResponse>, AppError> {
+    let revision = apply_state_update(&state, req.state).await?;
+    Ok(Encoded(format, UpdateStateResponse { success: true, revision }))
+}
+
+/// Replace the current state (the last write wins) with its scrubbed copy,
+/// notify sync sockets, and return the new revision
+async fn apply_state_update(state: &AppState, mut new_state: serde_json::Value) -> Result<u64, AppError> {
+    scrub_incoming(state, "state update", &mut new_state);
+    let mut history = state.versions.lock().await;
+    if let Err(e) = history.update_state(new_state) {
+        drop(history);
+        warn!("📦 Rejected state update: {}", e);
+        record_audit(state, "state_update", None, "rejected", e.to_string()).await;
+        return Err(e.into());
+    }
+    let revision = history.state_revision;
+    drop(history);
+    let _ = state.state_sync.send(());
+    Ok(revision)
+}
+
+/// Scrub personal data out of a value a client sent, before it's kept.
+/// Clients that scrubbed it first (see `GET /api/state/policy`) have
+/// nothing removed; the rest, like the bundled editor page, needn't
+/// implement the policy themselves.
+fn scrub_incoming(state: &AppState, what: &str, value: &mut serde_json::Value) {
+    let findings = state.scrub_policy.scrub(value);
+    if !findings.is_empty() {
+        let details: Vec<String> = findings.iter().map(ToString::to_string).collect();
+        info!("🔏 Scrubbed {}: {}", what, details.join("; "));
+    }
+}
+
+/// Merge CRDT operations from a client and return the ones it lacks
+async fn sync_state(
+    State(state): State<AppState>,
+    Accept(format): Accept,
+    Payload(message): Payload<SyncMessage>,
+) -> Result<Encoded<SyncMessage>, AppError> {
+    Ok(Encoded(format, merge_sync(&state, message).await?))
+}
+
+/// Apply a client's sync message to the server's CRDT document
+///
+/// Personal data the merge brings in is scrubbed by the server's own
+/// operations, which go back in the reply so the client drops it too.
+async fn merge_sync(state: &AppState, message: SyncMessage) -> morpheus_core::errors::Result<SyncMessage> {
+    let result = {
+        let mut history = state.versions.lock().await;
+        let Some(mut doc) = history.crdt.clone() else {
+            return Err(morpheus_core::errors::MorpheusError::InvalidState(
+                "CRDT state mode is off (set MORPHEUS_STATE_MODE=crdt)".to_string(),
+            ));
+        };
+        let before = doc.clock().clone();
+        doc.sync(message).and_then(|mut reply| {
+            let merged = doc.value();
+            let mut scrubbed = merged.clone();
+            scrub_incoming(state, "state sync", &mut scrubbed);
+            if scrubbed != merged {
+                reply.ops.extend(doc.update(&scrubbed)?);
+                reply.clock = doc.clock().clone();
+            }
+            let changed = doc.clock() != &before;
+            history.merge_state(doc)?;
+            Ok((reply, changed))
+        })
+    };
+    match result {
+        Ok((reply, changed)) => {
+            if changed {
+                let _ = state.state_sync.send(());
+            }
+            Ok(reply)
+        }
+        Err(e) => {
+            warn!("🔀 Rejected state sync: {}", e);
+            record_audit(state, "state_sync", None, "rejected", e.to_string()).await;
+            Err(e)
+        }
+    }
+}
+
+/// Sync state over a WebSocket
+///
+/// In CRDT mode, clients send `SyncMessage`s with their clock and new
+/// operations; the server replies with operations they lack, and pushes
+/// further changes as other clients make them. Otherwise clients exchange
+/// whole snapshots, see [`state_sync`]. With `?format=msgpack` or
+/// `?format=cbor` the server sends binary frames in that format; text
+/// frames are always JSON.
+async fn state_sync_socket(
+    State(state): State<AppState>,
+    Query(query): Query<SyncSocketQuery>,
+    spectator: Option<axum::Extension<sharing::Spectator>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let crdt = state.versions.lock().await.crdt.is_some();
+    let read_only = spectator.is_some();
+    if crdt {
+        ws.on_upgrade(move |socket| run_state_sync(state, socket, query.format, read_only))
+    } else {
+        ws.on_upgrade(move |socket| state_sync::run_snapshot_sync(state, socket, query.format, read_only))
+    }
+}
+
+/// Serve one CRDT sync client; `read_only` clients (spectators) get changes
+/// but can't send operations
+async fn run_state_sync(state: AppState, mut socket: WebSocket, format: Format, read_only: bool) {
+    let mut changes = state.state_sync.subscribe();
+    let mut peer_clock = Clock::new();
+    loop {
+        let reply = tokio::select! {
+            message = socket.recv() => {
+                let message = match message {
+                    Some(Ok(WsMessage::Text(text))) => Format::Json.from_slice::<SyncMessage>(text.as_bytes()),
+                    Some(Ok(WsMessage::Binary(bytes))) => format.from_slice::<SyncMessage>(&bytes),
+                    Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                let result = match message {
+                    Ok(message) if read_only && !message.ops.is_empty() => {
+                        let denied = sharing::AccessError::ReadOnly("changing state".to_string());
+                        Err(morpheus_core::errors::MorpheusError::Other(denied.to_string()))
+                    }
+                    Ok(message) => {
+                        merge_clock(&mut peer_clock, &message.clock);
+                        observe_ops(&mut peer_clock, &message.ops);
+                        merge_sync(&state, message).await
+                    }
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(reply) => reply,
+                    Err(e) => {
+                        let error = serde_json::json!({ "error": e.to_string() });
+                        let Some(frame) = ws_frame(format, &error) else { continue };
+                        if socket.send(frame).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                }
+            },
+            notification = changes.recv() => {
+                if matches!(notification, Err(broadcast::error::RecvError::Closed)) {
+                    break;
+                }
+                let history = state.versions.lock().await;
+                let Some(doc) = &history.crdt else { continue };
+                let ops = doc.changes_since(&peer_clock);
+                if ops.is_empty() {
+                    continue;
+                }
+                SyncMessage { clock: doc.clock().clone(), ops }
+            }
+        };
+        observe_ops(&mut peer_clock, &reply.ops);
+        let Some(frame) = ws_frame(format, &reply) else { continue };
+        if socket.send(frame).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Encode a WebSocket message: a text frame for JSON, binary otherwise
+fn ws_frame<T: Serialize>(format: Format, value: &T) -> Option<WsMessage> {
+    let bytes = format.to_vec(value).ok()?;
+    if format.is_binary() {
+        Some(WsMessage::Binary(bytes))
+    } else {
+        String::from_utf8(bytes).ok().map(WsMessage::Text)
+    }
+}
+
+/// Raise `clock` to cover everything in `other`
+fn merge_clock(clock: &mut Clock, other: &Clock) {
+    for (actor, &counter) in other {
+        let seen = clock.entry(actor.clone()).or_default();
+        *seen = (*seen).max(counter);
+    }
+}
+
+/// Raise `clock` to cover `ops`
+fn observe_ops(clock: &mut Clock, ops: &[morpheus_core::state::Op]) {
+    for op in ops {
+        let seen = clock.entry(op.id.actor.clone()).or_default();
+        *seen = (*seen).max(op.id.counter);
+    }
+}
+
+/// Record a domain event from a component
+async fn emit_event(
+    State(state): State<AppState>,
+    Accept(format): Accept,
+    Payload(req): Payload<EmitEventRequest>,
+) -> Result<Encoded<EmitEventResponse>, AppError> {
+    store::validate_key(&format!("{}{}.json", EVENTS_PREFIX, req.component))?;
+    let mut payload = req.payload;
+    scrub_incoming(&state, "event payload", &mut payload);
+    let result = {
+        let mut history = state.versions.lock().await;
+        if history.event_sourced {
+            history.record_event(req.component.clone(), DomainEvent::new(req.name, payload))
+        } else {
+            Err(morpheus_core::errors::MorpheusError::InvalidState(
+                "Event-sourced state mode is off (set MORPHEUS_STATE_MODE=events)".to_string(),
+            ))
+        }
+    };
+    match result {
+        Ok(seq) => {
+            let _ = state.state_sync.send(());
+            Ok(Encoded(format, EmitEventResponse { seq }))
+        }
+        Err(e) => {
+            warn!(component_id = %req.component, "📜 Rejected event: {}", e);
+            record_audit(&state, "emit_event", None, "rejected", e.to_string()).await;
+            Err(e.into())
+        }
+    }
+}
+
+/// A component's events after `since`
+async fn list_events(
+    State(state): State<AppState>,
+    Accept(format): Accept,
+    Path(component): Path<String>,
+    Query(query): Query<EventsQuery>,
+) -> Encoded<Vec<DomainEvent>> {
+    let history = state.versions.lock().await;
+    let events = history.events.get(&component).map(|log| log.since(query.since).to_vec());
+    Encoded(format, events.unwrap_or_default())
+}
+
+/// Rebuild a component's state by replaying its events
+async fn replay_events(
+    State(state): State<AppState>,
+    Accept(format): Accept,
+    Path(component): Path<String>,
+    Query(query): Query<ReplayQuery>,
+) -> Result<Encoded<ReplayResponse>, AppError> {
+    let history = state.versions.lock().await;
+    let Some(log) = history.events.get(&component) else {
+        return Err(AppError::ApiError(format!("No events for component '{}'", component)));
+    };
+    let seq = match (query.seq, query.version) {
+        (Some(seq), _) => seq.min(log.last_seq()),
+        (None, Some(version)) => log.last_seq_at_version(version as u32),
+        (None, None) => log.last_seq(),
+    };
+    let replayed = log.replay(serde_json::json!({}), &MergePatchReducer, Some(seq));
+    Ok(Encoded(format, ReplayResponse { seq, state: replayed }))
+}
+
+/// Where the time-travel cursor is
+async fn get_debug_position(
+    State(state): State<AppState>,
+    accept: Accept,
+) -> Result<Encoded<DebugStepResponse>, AppError> {
+    debug_step(State(state), accept, Payload(DebugStepRequest::default())).await
+}
+
+/// Step the current component backward or forward through its state history
+async fn debug_step(
+    State(state): State<AppState>,
+    Accept(format): Accept,
+    Payload(req): Payload<DebugStepRequest>,
+) -> Result<Encoded<DebugStepResponse>, AppError> {
+    state
+        .versions
+        .lock()
+        .await
+        .debug_step(req.steps, req.position)
+        .map(|response| Encoded(format, response))
+        .ok_or_else(|| AppError::ApiError("No state recorded yet".to_string()))
+}
+
+/// State snapshot sizes by component
+async fn get_snapshot_metrics(State(state): State<AppState>) -> Json<BTreeMap<String, SnapshotSizeStats>> {
+    Json(state.versions.lock().await.snapshot_sizes.clone())
+}
+
+/// Scrub policy clients must apply before sending state
+async fn get_scrub_policy(State(state): State<AppState>) -> Json<ScrubPolicy> {
+    Json(state.scrub_policy.as_ref().clone())
+}
+
+/// Rollback to previous version
+async fn rollback(
+    State(state): State<AppState>,
+    Json(req): Json<RollbackRequest>,
+) -> Result<Json<RollbackResponse>, AppError> {
+    info!(version = req.version_id, "Rolling back");
+
+    let mut history = state.versions.lock().await;
+
+    if let Err(reason) = history.check_activation(req.version_id) {
+        return Ok(Json(RollbackResponse {
+            success: false,
+            version_id: req.version_id,
+            wasm_base64: String::new(),
+            restored_state: None,
+            error: Some(reason),
+        }));
+    }
+
+    if let Some(version) = history.rollback_to(req.version_id) {
+        let _ = state.state_sync.send(());
+        Ok(Json(RollbackResponse {
+            success: true,
+            version_id: version.id,
+            wasm_base64: version.wasm_base64.clone(),
+            restored_state: version.state_snapshot.clone(),
+            error: None,
+        }))
+    } else {
+        Ok(Json(RollbackResponse {
+            success: false,
+            version_id: 0,
+            wasm_base64: String::new(),
+            restored_state: None,
+            error: Some(format!("Version {} not found", req.version_id)),
+        }))
+    }
+}
+
+/// List rollback groups, newest first
+async fn list_groups(State(state): State<AppState>) -> Json<Vec<RollbackGroup>> {
+    let history = state.versions.lock().await;
+    Json(history.groups.iter().rev().cloned().collect())
+}
+
+/// Roll back every version of a group, e.g. a plan, at once
+async fn rollback_group(
+    State(state): State<AppState>,
+    Path(group_id): Path<usize>,
+) -> Result<Json<GroupRollbackResponse>, AppError> {
+    info!(group = group_id, "Rolling back group");
+
+    let mut history = state.versions.lock().await;
+    let restored = match history.rollback_group(group_id) {
+        Ok(restored) => restored,
+        Err(reason) => {
+            return Ok(Json(GroupRollbackResponse {
+                success: false,
+                group_id,
+                version_id: None,
+                components: Vec::new(),
+                error: Some(reason),
+            }))
+        }
+    };
+    let version_id = history.get_current().map(|v| v.id);
+    let label = history.groups[group_id].label.clone();
+    let restore: Vec<_> = restored
+        .iter()
+        .map(|(name, id)| {
+            let version = id.map(|id| {
+                let v = &history.versions[id];
+                (v.manifest.clone(), base64_decode(&v.wasm_base64), v.provenance.clone(), v.artifacts())
+            });
+            (name.clone(), version)
+        })
+        .collect();
+    drop(history);
+
+    for (name, version) in restore {
+        match version {
+            Some((manifest, wasm_bytes, provenance, artifacts)) => {
+                register_component(&state, manifest, &wasm_bytes?, provenance, artifacts).await?;
+            }
+            None => {
+                let mut registry = state.registry.lock().await;
+                if let Some(id) = registry.find_by_name(&name) {
+                    registry.remove(&id);
+                }
+            }
+        }
+    }
+    let _ = state.state_sync.send(());
+    record_audit(&state, "rollback_group", version_id, "rolled_back", label).await;
+
+    Ok(Json(GroupRollbackResponse {
+        success: true,
+        group_id,
+        version_id,
+        components: restored
+            .into_iter()
+            .map(|(component, version_id)| RestoredComponent { component, version_id })
+            .collect(),
+        error: None,
+    }))
+}
+
+/// Get version history
+async fn get_history(State(state): State<AppState>) -> Result<Json<HistoryResponse>, AppError> {
+    let history = state.versions.lock().await;
+    Ok(Json(HistoryResponse {
+        versions: history.get_history(),
+        current_state: history.current_state.clone(),
+    }))
+}
+
+/// Stream version activations (generation, rollback, scheduled or reviewed
+/// activation) as server-sent `reload` events
+async fn reload_events(State(state): State<AppState>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let reloads = state.versions.lock().await.reloads.subscribe();
+    let events = stream::unfold(reloads, |mut reloads| async move {
+        loop {
+            match reloads.recv().await {
+                Ok(reload) => match Event::default().event("reload").json_data(&reload) {
+                    Ok(event) => return Some((Ok(event), reloads)),
+                    Err(e) => warn!("Failed to encode reload event: {}", e),
+                },
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Get a version's WASM as a patch against another version
+async fn get_version_patch(
+    State(state): State<AppState>,
+    Path(version_id): Path<usize>,
+    Query(query): Query<PatchQuery>,
+) -> Result<Json<PatchResponse>, AppError> {
+    let history = state.versions.lock().await;
+    let find = |id: usize| {
+        history
+            .versions
+            .get(id)
+            .ok_or_else(|| AppError::ApiError(format!("Version {} not found", id)))
+    };
+    let base = base64_decode(&find(query.from)?.wasm_base64)?;
+    let target = base64_decode(&find(version_id)?.wasm_base64)?;
+    drop(history);
+
+    let patch = delta::diff(&base, &target);
+    Ok(Json(PatchResponse {
+        version_id,
+        base_version_id: query.from,
+        wasm_size: target.len(),
+        patch_size: patch.len(),
+        patch_base64: base64_encode(&patch),
+    }))
+}
+
+/// A version's component name, WASM module and JS glue
+async fn version_files(state: &AppState, version_id: usize) -> Result<(String, Vec<u8>, String), AppError> {
+    let history = state.versions.lock().await;
+    let version = history
+        .versions
+        .get(version_id)
+        .ok_or_else(|| AppError::ApiError(format!("Version {} not found", version_id)))?;
+    Ok((version.name.clone(), base64_decode(&version.wasm_base64)?, version.js_glue.clone()))
+}
+
+/// Get a version's bundle manifest: its module, glue and assets, with
+/// integrity hashes
+async fn get_version_bundle(
+    State(state): State<AppState>,
+    Path(version_id): Path<usize>,
+) -> Result<Json<BundleManifest>, AppError> {
+    let history = state.versions.lock().await;
+    let version = history
+        .versions
+        .get(version_id)
+        .ok_or_else(|| AppError::ApiError(format!("Version {} not found", version_id)))?;
+    Ok(Json(version.bundle()?))
+}
+
+/// Whether `name` can name an asset: a plain file name, served under
+/// `/api/assets/{fingerprint}/{name}`
+fn is_asset_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+}
+
+/// Get an asset by content fingerprint. The URL changes whenever the
+/// contents do, so it can be cached forever
+async fn get_asset(
+    State(state): State<AppState>,
+    Path((fingerprint, name)): Path<(String, String)>,
+) -> Result<Response, AppError> {
+    let history = state.versions.lock().await;
+    let body = history
+        .versions
+        .iter()
+        .rev()
+        .filter_map(|v| v.assets.get(&name))
+        .filter_map(|contents| base64_decode(contents).ok())
+        .find(|bytes| morpheus_core::bundle::fingerprint(bytes) == fingerprint)
+        .ok_or_else(|| AppError::ApiError(format!("Asset {}/{} not found", fingerprint, name)))?;
+    let content_type = match name.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, "public, max-age=31536000, immutable"),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Serve one file of a bundle. Version IDs start over when history isn't
+/// persisted, so caches revalidate against the integrity hash as ETag
+/// instead of keeping files forever
+fn bundle_file(headers: &HeaderMap, content_type: &'static str, body: Vec<u8>) -> Response {
+    let etag = format!("\"{}\"", morpheus_core::bundle::integrity(&body));
+    let cache_headers = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, "no-cache".to_string()),
+    ];
+    if headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) == Some(etag.as_str()) {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+    let content_type = [(header::CONTENT_TYPE, content_type.to_string())];
+    (content_type, cache_headers, body).into_response()
+}
+
+/// Get a version's WASM module
+async fn get_version_wasm(
+    State(state): State<AppState>,
+    Path(version_id): Path<usize>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let (_, wasm, _) = version_files(&state, version_id).await?;
+    Ok(bundle_file(&headers, "application/wasm", wasm))
+}
+
+/// Get a version's JS glue
+async fn get_version_glue(
+    State(state): State<AppState>,
+    Path(version_id): Path<usize>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let (_, _, glue) = version_files(&state, version_id).await?;
+    Ok(bundle_file(&headers, "text/javascript; charset=utf-8", glue.into_bytes()))
+}
+
+/// Get a version's review
+async fn get_review(
+    State(state): State<AppState>,
+    Path(version_id): Path<usize>,
+) -> Result<Json<Review>, AppError> {
+    let history = state.versions.lock().await;
+    let version = history
+        .versions
+        .get(version_id)
+        .ok_or_else(|| AppError::ApiError(format!("Version {} not found", version_id)))?;
+    Ok(Json(version.review.clone()))
+}
+
+/// Add an inline comment on a range of a version's source
+async fn add_review_comment(
+    State(state): State<AppState>,
+    Path(version_id): Path<usize>,
+    Json(comment): Json<ReviewComment>,
+) -> Result<Json<Review>, AppError> {
+    let mut history = state.versions.lock().await;
+    let version = history
+        .versions
+        .get_mut(version_id)
+        .ok_or_else(|| AppError::ApiError(format!("Version {} not found", version_id)))?;
+    version.review.comment(&version.rust_code, comment)?;
+    Ok(Json(version.review.clone()))
+}
+
+/// Store a preview of a version, captured by a client. The live version's
+/// preview also shows in the component's metadata and catalog entry
+async fn set_thumbnail(
+    State(state): State<AppState>,
+    Path(version_id): Path<usize>,
+    Json(req): Json<ThumbnailRequest>,
+) -> Result<Json<Thumbnail>, AppError> {
+    let thumbnail = Thumbnail::from_data_url(&req.data_url)?;
+    let mut history = state.versions.lock().await;
+    let is_live = history.active_versions().iter().any(|v| v.id == version_id);
+    let version = history
+        .versions
+        .get_mut(version_id)
+        .ok_or_else(|| AppError::ApiError(format!("Version {} not found", version_id)))?;
+    version.thumbnail = Some(thumbnail.clone());
+    let name = version.manifest.name.clone();
+    drop(history);
+
+    if is_live {
+        let mut registry = state.registry.lock().await;
+        if let Some(id) = registry.find_by_name(&name) {
+            registry.set_thumbnail(id, Some(thumbnail.clone()))?;
+        }
+    }
+    record_audit(&state, "thumbnail", Some(version_id), "success", thumbnail.content_type.clone()).await;
+    Ok(Json(thumbnail))
+}
+
+/// Get a version's preview image
+async fn get_thumbnail(State(state): State<AppState>, Path(version_id): Path<usize>) -> Result<Response, AppError> {
+    let history = state.versions.lock().await;
+    let thumbnail = history
+        .versions
+        .get(version_id)
+        .and_then(|v| v.thumbnail.as_ref())
+        .ok_or_else(|| AppError::ApiError(format!("Version {} has no thumbnail", version_id)))?;
+    let body = base64_decode(&thumbnail.data_base64)?;
+    Ok(([(header::CONTENT_TYPE, thumbnail.content_type.clone())], body).into_response())
+}
+
+/// Approve a version or request changes
+async fn submit_verdict(
+    State(state): State<AppState>,
+    Path(version_id): Path<usize>,
+    Json(req): Json<VerdictRequest>,
+) -> Result<Json<Review>, AppError> {
+    let mut history = state.versions.lock().await;
+    let version = history
+        .versions
+        .get_mut(version_id)
+        .ok_or_else(|| AppError::ApiError(format!("Version {} not found", version_id)))?;
+    version.review.record_verdict(req.reviewer.clone(), req.status);
+    let review = version.review.clone();
+    drop(history);
+
+    info!(version = version_id, reviewer = %req.reviewer, "📝 Reviewed: {:?}", req.status);
+    let outcome = serde_json::to_value(req.status)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    record_audit(&state, "review", Some(version_id), &outcome, format!("by {}", req.reviewer)).await;
+    Ok(Json(review))
+}
+
+/// Allow a version that exceeds guardrails to be activated
+async fn override_guardrails(
+    State(state): State<AppState>,
+    Path(version_id): Path<usize>,
+    Json(req): Json<OverrideRequest>,
+) -> Result<Json<VersionSummary>, AppError> {
+    let mut history = state.versions.lock().await;
+    let version = history
+        .versions
+        .get_mut(version_id)
+        .ok_or_else(|| AppError::ApiError(format!("Version {} not found", version_id)))?;
+    if version.guardrail_violations.is_empty() {
+        return Err(AppError::ApiError(format!(
+            "Version {} is within guardrails; no override needed",
+            version_id
+        )));
+    }
+    version.guardrail_override = Some(GuardrailOverride {
+        by: req.by.clone(),
+        reason: req.reason.clone(),
+        at: Utc::now(),
+    });
+    let summary = history
+        .get_history()
+        .into_iter()
+        .nth(version_id)
+        .expect("version exists");
+    drop(history);
+
+    info!(version = version_id, by = %req.by, "🚧 Guardrails overridden");
+    record_audit(
+        &state,
+        "guardrail_override",
+        Some(version_id),
+        "overridden",
+        format!("by {}: {}", req.by, req.reason),
+    )
+    .await;
+    Ok(Json(summary))
+}
+
+/// What a version's module exports and imports, its memories, tables and
+/// custom sections, and why it wouldn't mount
+async fn inspect_version(
+    State(state): State<AppState>,
+    Path(version_id): Path<usize>,
+) -> Result<Json<Inspection>, AppError> {
+    let history = state.versions.lock().await;
+    let version = history
+        .versions
+        .get(version_id)
+        .ok_or_else(|| AppError::ApiError(format!("Version {} not found", version_id)))?;
+    let wasm_bytes = base64_decode(&version.wasm_base64)?;
+    drop(history);
+    Ok(Json(Inspection::of(&wasm_bytes)))
+}
+
+/// Compile hand-edited source of a version and save it as a new
+/// human-authored version derived from it, hot-reloading it like a
+/// generated one
+///
+/// Hand edits pass the same gates as AI proposals: [`compile_verified`]
+/// (with its accessibility policy and performance budget), then the
+/// guardrails and review requirement [`VersionHistory::add_version`]
+/// applies before activation.
+async fn edit_version(
+    State(state): State<AppState>,
+    Path(version_id): Path<usize>,
+    Json(req): Json<EditRequest>,
+) -> Result<Json<GenerateResponse>, AppError> {
+    info!(version = version_id, "Saving hand edit");
+    if let Some(name) = req.assets.iter().flat_map(|a| a.keys()).find(|name| !is_asset_name(name)) {
+        return Err(AppError::ApiError(format!("Invalid asset name '{}'", name)));
+    }
+    let manifest = {
+        let history = state.versions.lock().await;
+        let version = history
+            .versions
+            .get(version_id)
+            .ok_or_else(|| AppError::ApiError(format!("Version {} not found", version_id)))?;
+        if !req.force {
+            if let Some(conflict) = detect_conflict(&history, Some(version_id)) {
+                return Err(AppError::Conflict(conflict));
+            }
+        }
+        version.manifest.clone()
+    };
+
+    let mut logs = vec![format!("✏️  Hand edit of version {}", version_id)];
+    logs.push("⚙️  Compiling Rust → WASM...".to_string());
+    let result = match compile_verified(&state, &req.source, &manifest.name).await {
+        Ok(result) => result,
+        Err(e) => {
+            logs.push(format!("❌ Compilation failed:\n{}", e));
+            record_audit(&state, "edit", Some(version_id), "failed", e.to_string()).await;
+            return Ok(Json(GenerateResponse {
+                success: false,
+                version_id: None,
+                wasm_base64: None,
+                restored_state: None,
+                error: Some(e.to_string()),
+                iterations: 1,
+                logs,
+                slots: Vec::new(),
+            }));
+        }
+    };
+    logs.push(format!(
+        "✅ Compilation successful! {} bytes of WASM + {} bytes of JS glue",
+        result.wasm_bytes.len(),
+        result.js_glue.len()
+    ));
+    for diagnostic in &result.diagnostics {
+        logs.push(format!("🛡️  {}", diagnostic.message));
+    }
+    let rust_code = source::tidy(result.fixed_source.as_deref().unwrap_or(&req.source)).await;
+
+    let message = req.message.unwrap_or_else(|| format!("Hand edit of version {}", version_id));
+    let mut history = state.versions.lock().await;
+    let restored_state = history.current_state.clone();
+    let new_version_id = history.add_version(
+        format!("Edited: {}", truncate(&message, 40)),
+        message.clone(),
+        rust_code,
+        result.wasm_bytes.clone(),
+        result.js_glue.clone(),
+        false, // Written by hand
+        manifest.clone(),
+        Provenance::human().with_parent(version_id as u32).with_toolchain(state.compiler.toolchain()),
+        req.assets.is_none(),
+    );
+    // New assets must be in place before the version goes live, so the
+    // reload event announces them with the module
+    if let Some(assets) = req.assets {
+        logs.push(format!("🎨 {} asset(s)", assets.len()));
+        history.versions[new_version_id].assets =
+            assets.into_iter().map(|(name, contents)| (name, base64_encode(contents.as_bytes()))).collect();
+        if history.check_activation(new_version_id).is_ok() {
+            history.activate(new_version_id);
+        }
+    }
+    logs.push(format!("📜 Saved as version {} in history", new_version_id));
+    if history.require_review {
+        logs.push("📝 Awaiting review before activation".to_string());
+    }
+    history.versions[new_version_id].sbom = result.sbom.clone();
+    history.versions[new_version_id].variants = result.variants.clone();
+    history.versions[new_version_id].record_verification(result.verification);
+    let violations = history.versions[new_version_id].guardrail_violations.clone();
+    let provenance = history.versions[new_version_id].provenance.clone();
+    let artifacts = history.versions[new_version_id].artifacts();
+    drop(history);
+    report_guardrails(&state, new_version_id, &violations, &mut logs).await;
+
+    let slots = register_component(&state, manifest, &result.wasm_bytes, provenance, artifacts).await?;
+    record_audit(&state, "edit", Some(new_version_id), "success", truncate(&message, 80)).await;
+
+    Ok(Json(GenerateResponse {
+        success: true,
+        version_id: Some(new_version_id),
+        wasm_base64: Some(base64_encode(&result.wasm_bytes)),
+        restored_state,
+        error: None,
+        iterations: 1,
+        logs,
+        slots,
+    }))
+}
+
+/// Get the SBOM recorded for a version
+async fn get_version_sbom(
+    State(state): State<AppState>,
+    Path(version_id): Path<usize>,
+) -> Result<Json<Sbom>, AppError> {
+    let history = state.versions.lock().await;
+    let version = history
+        .versions
+        .get(version_id)
+        .ok_or_else(|| AppError::ApiError(format!("Version {} not found", version_id)))?;
+    version
+        .sbom
+        .clone()
+        .map(Json)
+        .ok_or_else(|| AppError::ApiError(format!("Version {} has no SBOM", version_id)))
+}
+
+/// A version as a custom element module, for pages outside Morpheus
+async fn get_version_element(
+    State(state): State<AppState>,
+    Path(version_id): Path<usize>,
+    Query(query): Query<ElementQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let (name, wasm_base64, js_glue) = {
+        let history = state.versions.lock().await;
+        let version = history
+            .versions
+            .get(version_id)
+            .ok_or_else(|| AppError::ApiError(format!("Version {} not found", version_id)))?;
+        (version.name.clone(), version.wasm_base64.clone(), version.js_glue.clone())
+    };
+    let description = state
+        .registry
+        .lock()
+        .await
+        .catalog()
+        .into_iter()
+        .find(|entry| entry.manifest.name == name)
+        .and_then(|entry| entry.description);
+
+    let tag = query.tag.unwrap_or_else(|| element::tag_for(&name));
+    let mut custom_element = match &description {
+        Some(description) => CustomElement::from_description(tag, description)?,
+        None => CustomElement::new(tag)?,
+    };
+    let list = |names: &str| {
+        names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    if let Some(props) = &query.props {
+        custom_element = custom_element.props(list(props));
+    }
+    if let Some(events) = &query.events {
+        custom_element = custom_element.events(list(events));
+    }
+    Ok((
+        [(header::CONTENT_TYPE, "text/javascript; charset=utf-8")],
+        custom_element.wrap(&wasm_base64, &js_glue),
+    ))
+}
+
+/// Record a user's rating of a version
+async fn submit_feedback(
+    State(state): State<AppState>,
+    Json(req): Json<FeedbackRequest>,
+) -> Result<Json<FeedbackResponse>, AppError> {
+    // Feedback ends up in AI prompts, so personal data is redacted first
+    let mut text = serde_json::Value::String(req.text);
+    state.scrub_policy.scrub(&mut text);
+    let text = text.as_str().unwrap_or_default().to_string();
+
+    let mut history = state.versions.lock().await;
+    let version_id = match req.version_id {
+        Some(id) => id,
+        None => history
+            .get_current()
+            .map(|v| v.id)
+            .ok_or_else(|| AppError::ApiError("No active version to rate".to_string()))?,
+    };
+    let entry = Feedback::new(req.rating, text.clone())?
+        .with_version(version_id as u32)
+        .with_timestamp(Utc::now().to_rfc3339());
+    let version = history
+        .versions
+        .get_mut(version_id)
+        .ok_or_else(|| AppError::ApiError(format!("Version {} not found", version_id)))?;
+    version.feedback.push(entry);
+    let response = FeedbackResponse {
+        version_id,
+        feedback_count: version.feedback.len(),
+        average_rating: feedback::average_rating(&version.feedback).unwrap_or_default(),
+    };
+    drop(history);
+    info!(version = version_id, "⭐ Rated {}/{}", req.rating, feedback::MAX_RATING);
+    experiments::record_rating(&mut *state.experiments.lock().await, version_id, req.rating);
+
+    // Autonomous mode treats feedback like any other telemetry
+    state.telemetry.lock().await.record(
+        TelemetryReport {
+            kind: TelemetryKind::Feedback,
+            version_id: Some(version_id),
+            message: format!("{}/{}: {}", req.rating, feedback::MAX_RATING, text),
+            duration_ms: None,
+        },
+        None,
+    );
+    Ok(Json(response))
+}
+
+/// Feedback recorded on a version
+async fn get_version_feedback(
+    State(state): State<AppState>,
+    Path(version_id): Path<usize>,
+) -> Result<Json<Vec<Feedback>>, AppError> {
+    let history = state.versions.lock().await;
+    let version = history
+        .versions
+        .get(version_id)
+        .ok_or_else(|| AppError::ApiError(format!("Version {} not found", version_id)))?;
+    Ok(Json(version.feedback.clone()))
+}
+
+/// Find active components whose dependency tree includes a crate
+async fn find_dependents(
+    State(state): State<AppState>,
+    Query(query): Query<DependentsQuery>,
+) -> Json<Vec<Dependent>> {
+    let history = state.versions.lock().await;
+    let dependents = history
+        .active_versions()
+        .into_iter()
+        .filter_map(|v| {
+            let found = v.sbom.as_ref()?.find(&query.crate_name, query.version.as_deref())?;
+            Some(Dependent {
+                component: v.manifest.name.clone(),
+                version_id: v.id,
+                crate_version: found.version.clone(),
+            })
+        })
+        .collect();
+    Json(dependents)
+}
+
+/// Log and audit the guardrails a new version exceeds
+async fn report_guardrails(state: &AppState, version_id: usize, violations: &[String], logs: &mut Vec<String>) {
+    if violations.is_empty() {
+        return;
+    }
+    for violation in violations {
+        logs.push(format!("🚧 Guardrail exceeded: {}", violation));
+    }
+    logs.push("🚧 Needs an explicit override before activation".to_string());
+    record_audit(state, "guardrails", Some(version_id), "violated", violations.join("; ")).await;
+}
+
+/// Ask the AI to explain what changed between two versions, storing the
+/// explanation as the newer version's changelog entry
+async fn explain_change(
+    State(state): State<AppState>,
+    Json(req): Json<ExplainRequest>,
+) -> Result<Json<ExplainResponse>, AppError> {
+    if !state.has_ai() {
+        return Err(AppError::ApiError(
+            "OPENROUTER_API_KEY not configured".to_string(),
+        ));
+    }
+
+    let diff = {
+        let history = state.versions.lock().await;
+        let find = |id: usize| {
+            history
+                .versions
+                .get(id)
+                .ok_or_else(|| AppError::ApiError(format!("Version {} not found", id)))
+        };
+        source_diff(&find(req.from)?.rust_code, &find(req.to)?.rust_code, req.from, req.to)
+    };
+
+    info!("📖 Explaining changes from version {} to {}", req.from, req.to);
+    let summary = complete(
+        &state,
+        vec![Message {
+            role: "user".to_string(),
+            content: create_explain_prompt(&diff),
+        }],
+    )
+    .await?;
+
+    // Store on the newer of the two versions
+    let changelog = ChangelogEntry {
+        base_version_id: req.from.min(req.to),
+        summary: summary.trim().to_string(),
+        generated_at: Utc::now(),
+    };
+    if let Some(version) = state.versions.lock().await.versions.get_mut(req.from.max(req.to)) {
+        version.changelog = Some(changelog.clone());
+    }
+
+    Ok(Json(ExplainResponse {
+        from: req.from,
+        to: req.to,
+        diff,
+        changelog,
+    }))
+}
+
+/// Get a component's release notes as JSON or markdown
+async fn get_changelog(
+    State(state): State<AppState>,
+    Query(query): Query<ChangelogQuery>,
+) -> Result<Response, AppError> {
+    let history = state.versions.lock().await;
+    let notes = history.release_notes(&query.component, query.since);
+    drop(history);
+
+    match query.format.as_deref() {
+        Some("markdown") | Some("md") => Ok((
+            [(axum::http::header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+            render_changelog_markdown(&query.component, &notes),
+        )
+            .into_response()),
+        None | Some("json") => Ok(Json(notes).into_response()),
+        Some(other) => Err(AppError::ApiError(format!("Unknown changelog format '{}'", other))),
+    }
+}
+
+/// Render release notes as a markdown changelog, newest first
+fn render_changelog_markdown(component: &str, notes: &[ReleaseNote]) -> String {
+    let mut markdown = format!("# Changelog: {}\n", component);
+    for note in notes.iter().rev() {
+        markdown.push_str(&format!(
+            "\n## Version {} ({})\n\n{}\n",
+            note.version_id,
+            note.created_at.format("%Y-%m-%d"),
+            note.description
+        ));
+        if note.activated_at.is_none() {
+            markdown.push_str("\n_Not yet activated._\n");
+        }
+        if let Some(summary) = &note.summary {
+            markdown.push_str(&format!("\n{}\n", summary));
+        }
+    }
+    markdown
+}
+
+/// Unified diff between two versions' source
+fn source_diff(old: &str, new: &str, old_id: usize, new_id: usize) -> String {
+    similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("v{}/component.rs", old_id), &format!("v{}/component.rs", new_id))
+        .to_string()
+}
+
+/// Create prompt asking the AI to summarize a source diff for users
+fn create_explain_prompt(diff: &str) -> String {
+    format!(
+        "Here is a diff between two versions of a WebAssembly UI component written in Rust:\n\n```diff\n{}\n```\n\nSummarize the behavioral differences a user would notice, as 1-5 short bullet points. Describe what changed in the UI and interactions, not the code. If nothing user-visible changed, say so. Output only the bullet points.",
+        diff
+    )
+}
+
+/// Get the component catalog
+async fn get_catalog(State(state): State<AppState>) -> Result<Json<Vec<CatalogEntry>>, AppError> {
+    let registry = state.registry.lock().await;
+    Ok(Json(registry.catalog()))
+}
+
+/// Record the capabilities a loaded component reported, and warn about
+/// components that relied on interface it no longer has
+async fn describe_component(
+    State(state): State<AppState>,
+    Json(req): Json<DescribeRequest>,
+) -> Result<Json<DescribeResponse>, AppError> {
+    let component = match (req.component, req.version_id) {
+        (Some(component), _) => component,
+        (None, Some(version_id)) => {
+            let history = state.versions.lock().await;
+            let version = history
+                .versions
+                .get(version_id)
+                .ok_or_else(|| AppError::BadRequest(format!("Version {} not found", version_id)))?;
+            version.manifest.name.clone()
+        }
+        (None, None) => {
+            return Err(AppError::BadRequest("Name the component, or the version that was rendered".to_string()));
+        }
+    };
+    let mut registry = state.registry.lock().await;
+    let id = registry
+        .find_by_name(&component)
+        .ok_or_else(|| AppError::ApiError(format!("Component '{}' not found", component)))?;
+    let incompatibilities = registry.set_description(id, req.description)?;
+    let (version, interface_hash) = registry
+        .metadata(&id)
+        .map(|m| (Some(m.version), m.interface_hash.clone()))
+        .unwrap_or_default();
+    drop(registry);
+
+    if !incompatibilities.is_empty() {
+        let reasons: Vec<_> = incompatibilities.iter().map(ToString::to_string).collect();
+        for reason in &reasons {
+            warn!(component_id = %component, "🧩 Breaking reload: {}", reason);
+        }
+        let version_id = state.versions.lock().await.get_current().map(|v| v.id);
+        record_audit(&state, "incompatible_reload", version_id, "warned", reasons.join("; ")).await;
+    }
+    Ok(Json(DescribeResponse {
+        success: true,
+        version,
+        interface_hash,
+        incompatibilities,
+    }))
+}
+
+/// Components resembling a description, e.g. `?q=a table of users`
+async fn find_similar_components(
+    State(state): State<AppState>,
+    Query(query): Query<SimilarQuery>,
+) -> Json<Vec<SimilarComponent>> {
+    let registry = state.registry.lock().await;
+    let mut similar = registry.find_similar(&query.q, usize::MAX);
+    if let Some(capability) = query.capability {
+        similar.retain(|component| component.capabilities.contains(&capability));
+    }
+    similar.truncate(query.limit.unwrap_or(MAX_SIMILAR_COMPONENTS));
+    Json(similar)
+}
+
+/// List loaded components with their provenance
+async fn list_components(State(state): State<AppState>) -> Json<Vec<ComponentMetadata>> {
+    let registry = state.registry.lock().await;
+    let mut components: Vec<_> = registry.list().cloned().collect();
+    components.sort_by(|a, b| a.name.cmp(&b.name));
+    Json(components)
+}
+
+/// Record export calls timed by a client; returns the loaded components
+/// with their updated statistics
+async fn report_calls(
+    State(state): State<AppState>,
+    Json(samples): Json<Vec<CallSample>>,
+) -> Result<Json<Vec<ComponentMetadata>>, AppError> {
+    let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    let mut registry = state.registry.lock().await;
+    for sample in samples {
+        // Calls reported after the component was replaced are dropped
+        let Some(id) = registry.find_by_name(&sample.component) else {
+            continue;
+        };
+        registry.record_call(id, &sample.export, sample.duration_ms, sample.trapped, &now)?;
+    }
+    let mut components: Vec<_> = registry.list().cloned().collect();
+    components.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Json(components))
+}
+
+/// List all feature flags
+async fn list_flags(State(state): State<AppState>) -> Result<Json<Vec<FlagEntry>>, AppError> {
+    let registry = state.registry.lock().await;
+    let flags = registry
+        .catalog()
+        .into_iter()
+        .filter_map(|entry| {
+            registry.flag(&entry.id).map(|flag| FlagEntry {
+                component: entry.manifest.name,
+                flag: flag.clone(),
+            })
+        })
+        .collect();
+    Ok(Json(flags))
+}
+
+/// Disable a component or pin it to a version
+async fn set_component_flag(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(flag): Json<ComponentFlag>,
+) -> Result<Json<FlagEntry>, AppError> {
+    let mut registry = state.registry.lock().await;
+    let id = find_component(&registry, &name)?;
+    warn!(
+        component_id = %name,
+        "🚩 Flag set: {:?}{}",
+        flag.state,
+        flag.reason.as_deref().map(|r| format!(" ({})", r)).unwrap_or_default()
+    );
+    registry.set_flag(id, flag.clone())?;
+    Ok(Json(FlagEntry { component: name, flag }))
+}
+
+/// Remove a component's flag, re-enabling its current version
+async fn clear_component_flag(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let mut registry = state.registry.lock().await;
+    let id = find_component(&registry, &name)?;
+    registry.clear_flag(&id);
+    info!(component_id = %name, "🚩 Flag cleared");
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+/// Declare what clients render if a component fails to load
+async fn set_component_fallback(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(fallback): Json<FallbackView>,
+) -> Result<Json<FallbackResponse>, AppError> {
+    let mut registry = state.registry.lock().await;
+    let id = find_component(&registry, &name)?;
+    registry.set_fallback(id, Some(fallback.clone()))?;
+    drop(registry);
+    info!(component_id = %name, "🩹 Fallback set: {:?}", fallback);
+    record_audit(&state, "fallback", None, "set", format!("{} falls back to {:?}", name, fallback)).await;
+    Ok(Json(FallbackResponse {
+        component: name,
+        fallback: Some(fallback),
+    }))
+}
+
+/// Remove a component's fallback, so clients show the default error message
+async fn clear_component_fallback(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<FallbackResponse>, AppError> {
+    let mut registry = state.registry.lock().await;
+    let id = find_component(&registry, &name)?;
+    registry.set_fallback(id, None)?;
+    info!(component_id = %name, "🩹 Fallback cleared");
+    Ok(Json(FallbackResponse {
+        component: name,
+        fallback: None,
+    }))
+}
+
+/// A component's lifecycle status
+async fn get_component_lifecycle(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<LifecycleResponse>, AppError> {
+    let registry = state.registry.lock().await;
+    let id = find_component(&registry, &name)?;
+    Ok(Json(LifecycleResponse {
+        lifecycle: registry.lifecycle(&id).cloned().unwrap_or_default(),
+        dependents: registry.dependents(&id),
+        component: name,
+    }))
+}
+
+/// Deprecate a component, optionally scheduling its retirement
+async fn deprecate_component(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(request): Json<DeprecateRequest>,
+) -> Result<Json<LifecycleResponse>, AppError> {
+    let mut registry = state.registry.lock().await;
+    let id = find_component(&registry, &name)?;
+    let retire_after = request.retire_after.map(|at| at.to_rfc3339_opts(SecondsFormat::Secs, true));
+    registry.deprecate(id, request.replacement, retire_after)?;
+    let response = LifecycleResponse {
+        lifecycle: registry.lifecycle(&id).cloned().unwrap_or_default(),
+        dependents: registry.dependents(&id),
+        component: name,
+    };
+    drop(registry);
+
+    let detail = response.lifecycle.warning(&response.component).unwrap_or_default();
+    warn!(component_id = %response.component, "🌅 {}", detail);
+    record_audit(&state, "deprecate", None, "deprecated", detail).await;
+    Ok(Json(response))
+}
+
+/// Fork a component under a new name, to take it in another direction
+/// without risking the original
+async fn fork_component(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(request): Json<ForkRequest>,
+) -> Result<Json<ForkResponse>, AppError> {
+    let id = {
+        let registry = state.registry.lock().await;
+        if registry.find_by_name(&request.name).is_some() {
+            return Err(AppError::ApiError(format!("Component '{}' already exists", request.name)));
+        }
+        find_component(&registry, &name)?
+    };
+    let version_id = state.versions.lock().await.fork(&name, &request.name).map_err(AppError::ApiError)?;
+
+    let mut registry = state.registry.lock().await;
+    let fork = registry.fork(&id, request.name.as_str())?;
+    let slots = registry.resolve_slots(&fork)?;
+    drop(registry);
+
+    info!(component_id = %request.name, version = version_id, "🍴 Forked from {}", name);
+    record_audit(&state, "fork", Some(version_id), "forked", format!("{} forked from {}", request.name, name)).await;
+    Ok(Json(ForkResponse {
+        component: request.name,
+        forked_from: name,
+        version_id,
+        slots,
+    }))
+}
+
+/// Retire a deprecated component now, if nothing depends on it any more
+async fn retire_component(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<LifecycleResponse>, AppError> {
+    let mut registry = state.registry.lock().await;
+    let id = find_component(&registry, &name)?;
+    let retired = registry.retire(&id)?;
+    drop(registry);
+
+    info!(component_id = %name, "🌅 Component retired");
+    record_audit(&state, "retire", None, "retired", format!("{} retired", name)).await;
+    Ok(Json(LifecycleResponse {
+        component: name,
+        lifecycle: retired.lifecycle,
+        dependents: Vec::new(),
+    }))
+}
+
+/// Resolve what the host should render for a component, honouring its flag
+async fn render_component(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<RenderQuery>,
+) -> Result<Json<RenderResponse>, AppError> {
+    Ok(Json(resolve_render(&state, name, &query).await?))
+}
+
+/// What to render for a component: the version its flag, the session's
+/// experiment variant or the current version says, or a placeholder
+async fn resolve_render(state: &AppState, name: String, query: &RenderQuery) -> Result<RenderResponse, AppError> {
+    let mut registry = state.registry.lock().await;
+    let id = find_component(&registry, &name)?;
+    registry.instance(&id)?;
+    let decision = registry.render_decision(&id)?;
+    let reason = registry.flag(&id).and_then(|flag| flag.reason.clone());
+    let warnings = registry.mount_warnings(&id);
+    let fallback = registry.metadata(&id).and_then(|m| m.fallback.clone()).unwrap_or_default();
+    drop(registry);
+    for warning in &warnings {
+        warn!(component_id = %name, "🌅 Mounting deprecated component: {}", warning);
+    }
+
+    // Flags override experiments, so only the current version is split
+    let assignment = match (&decision, &query.session) {
+        (RenderDecision::Current, Some(session)) => {
+            experiments::choose(&mut *state.experiments.lock().await, &name, session)
+        }
+        _ => None,
+    };
+
+    let history = state.versions.lock().await;
+    let (mode, version, placeholder_html) = match decision {
+        RenderDecision::Current => match assignment {
+            Some((_, version_id)) => ("experiment", history.versions.get(version_id), None),
+            None => ("current", history.get_current(), None),
+        },
+        RenderDecision::Version { version } => {
+            let pinned = history.versions.get(version as usize).ok_or_else(|| {
+                AppError::ApiError(format!("Pinned version {} not found", version))
+            })?;
+            ("pinned", Some(pinned), None)
+        }
+        RenderDecision::Fallback { fallback } => {
+            let previous = history
+                .current_index
+                .checked_sub(1)
+                .and_then(|index| history.versions.get(index));
+            match (fallback, previous) {
+                (Fallback::PreviousVersion, Some(previous)) => ("previous_version", Some(previous), None),
+                (Fallback::Placeholder { html }, _) => ("placeholder", None, Some(html)),
+                // Nothing earlier to fall back to
+                (Fallback::PreviousVersion, None) => {
+                    ("placeholder", None, Some(DEFAULT_PLACEHOLDER.to_string()))
+                }
+            }
+        }
+    };
+
+    let bundle = match version {
+        Some(v) => Some(v.bundle()?),
+        None => None,
+    };
+    let inline = !query.manifest;
+    Ok(RenderResponse {
+        component: name,
+        mode: mode.to_string(),
+        variant: assignment.map(|(variant, _)| variant),
+        version_id: version.map(|v| v.id),
+        wasm_base64: version.filter(|_| inline).map(|v| v.wasm_base64.clone()),
+        js_glue: version.filter(|_| inline).map(|v| v.js_glue.clone()),
+        bundle,
+        placeholder_html,
+        reason,
+        warnings,
+        fallback,
+    })
+}
+
+/// The build of a component to run in an environment, e.g. the Node.js
+/// or WASI build for a server-side harness
+async fn get_component_artifact(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(query): Query<ArtifactQuery>,
+) -> Result<Json<Artifact>, AppError> {
+    let registry = state.registry.lock().await;
+    let id = find_component(&registry, &name)?;
+    Ok(Json(registry.artifact(&id, query.environment)?.clone()))
+}
+
+/// Schedule a committed version to become current at a later time
+async fn schedule_activation(
+    State(state): State<AppState>,
+    Json(req): Json<ScheduleRequest>,
+) -> Result<Json<ScheduledActivation>, AppError> {
+    if req.version_id >= state.versions.lock().await.versions.len() {
+        return Err(AppError::ApiError(format!("Version {} not found", req.version_id)));
+    }
+
+    let activation = ScheduledActivation {
+        id: uuid::Uuid::new_v4().to_string(),
+        version_id: req.version_id,
+        activate_at: req.activate_at,
+        status: ActivationStatus::Pending,
+        created_at: Utc::now(),
+    };
+    info!(version = req.version_id, "⏰ Scheduled for {}", req.activate_at);
+    record_audit(
+        &state,
+        "schedule",
+        Some(req.version_id),
+        "pending",
+        format!("Activation scheduled for {}", req.activate_at.to_rfc3339()),
+    )
+    .await;
+
+    state.schedule.lock().await.push(activation.clone());
+    Ok(Json(activation))
+}
+
+/// List scheduled activations
+async fn list_schedule(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ScheduledActivation>>, AppError> {
+    Ok(Json(state.schedule.lock().await.clone()))
+}
+
+/// Cancel a pending activation
+async fn cancel_activation(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ScheduledActivation>, AppError> {
+    let mut schedule = state.schedule.lock().await;
+    let activation = schedule
+        .iter_mut()
+        .find(|a| a.id == id && a.status == ActivationStatus::Pending)
+        .ok_or_else(|| AppError::ApiError(format!("No pending activation {}", id)))?;
+    activation.status = ActivationStatus::Cancelled;
+    let activation = activation.clone();
+    drop(schedule);
+
+    record_audit(&state, "schedule", Some(activation.version_id), "cancelled", String::new()).await;
+    Ok(Json(activation))
+}
+
+/// Get the audit log
+async fn get_audit_log(State(state): State<AppState>) -> Result<Json<Vec<AuditEntry>>, AppError> {
+    Ok(Json(state.audit_log.lock().await.clone()))
+}
+
+/// Open the store named by `MORPHEUS_STORE`: a directory, or `s3://bucket/prefix`
+///
+/// With `MORPHEUS_STORE_KEY` (a base64 256-bit key) everything is encrypted
+/// at rest; `MORPHEUS_STORE_KEY_ID` names the key (default `local`), and
+/// `MORPHEUS_STORE_MIGRATE_PLAINTEXT=true` reads blobs written before
+/// encryption was enabled instead of refusing them.
+async fn open_store(location: &str) -> anyhow::Result<Arc<dyn SnapshotStore>> {
+    let store = open_backend(location).await?;
+    let Ok(encoded) = std::env::var("MORPHEUS_STORE_KEY") else {
+        return Ok(store);
+    };
+    let key: [u8; 32] = base64_decode(encoded.trim())
+        .map_err(|_| anyhow::anyhow!("MORPHEUS_STORE_KEY must be base64"))?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("MORPHEUS_STORE_KEY must be 32 bytes"))?;
+    let key_id = std::env::var("MORPHEUS_STORE_KEY_ID").unwrap_or_else(|_| "local".to_string());
+    let migrating = env_flag("MORPHEUS_STORE_MIGRATE_PLAINTEXT")?;
+    info!("✓ Store encryption enabled (key '{}')", key_id);
+    if migrating {
+        warn!("Reading unencrypted blobs while migrating the store; unset MORPHEUS_STORE_MIGRATE_PLAINTEXT once done");
+    }
+    let store = EncryptedStore::new(store, LocalKey::new(key_id, key)).with_plaintext_migration(migrating);
+    Ok(Arc::new(store))
+}
+
+/// Read a boolean environment variable: `true`, `1`, `yes` or `on` enable
+/// it; unset, empty, `false`, `0`, `no` or `off` don't
+fn env_flag(name: &str) -> anyhow::Result<bool> {
+    match std::env::var(name) {
+        Ok(value) => {
+            parse_flag(&value).ok_or_else(|| anyhow::anyhow!("{} must be true or false, not '{}'", name, value))
+        }
+        Err(_) => Ok(false),
+    }
+}
+
+/// Read a numeric environment variable; unset or empty means `None`, and
+/// anything that isn't a number is an error rather than no limit at all
+fn env_number<T: std::str::FromStr>(name: &str) -> anyhow::Result<Option<T>> {
+    match std::env::var(name) {
+        Ok(value) => parse_number(name, &value),
+        Err(_) => Ok(None),
+    }
+}
+
+/// The number an environment variable's value means
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> anyhow::Result<Option<T>> {
+    match value.trim() {
+        "" => Ok(None),
+        number => number
+            .parse()
+            .map(Some)
+            .map_err(|_| anyhow::anyhow!("{} must be a whole number, not '{}'", name, value)),
+    }
+}
+
+/// The boolean an environment variable's value means, if any
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "" | "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Open the unencrypted backend for a `MORPHEUS_STORE` location
+async fn open_backend(location: &str) -> anyhow::Result<Arc<dyn SnapshotStore>> {
+    if let Some(rest) = location.strip_prefix("s3://") {
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        let prefix = if prefix.is_empty() || prefix.ends_with('/') {
+            prefix.to_string()
+        } else {
+            format!("{}/", prefix)
+        };
+        return Ok(Arc::new(S3Store::new(S3Config::from_env(bucket, prefix)?)));
+    }
+    Ok(Arc::new(FsStore::open(location).await?))
+}
+
+/// Write version history to the store every few seconds
+async fn run_persistence(state: AppState, store: Arc<dyn SnapshotStore>) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(PERSIST_INTERVAL_SECS));
+    let mut written = PersistedBlobs::default();
+    loop {
+        interval.tick().await;
+        if let Err(e) = persist_history(&state, store.as_ref(), &mut written).await {
+            warn!("💾 Failed to persist version history: {}", e);
+        }
+    }
+}
+
+/// What [`persist_history`] has already written
+#[derive(Default)]
+struct PersistedBlobs {
+    /// Versions whose module and snapshot are stored (both are immutable)
+    versions: std::collections::HashSet<usize>,
+    index: Vec<u8>,
+    current_state: Vec<u8>,
+    /// Last stored event of each component's log
+    event_seqs: std::collections::HashMap<String, u64>,
+    /// Last stored release notes of each component
+    changelogs: std::collections::HashMap<String, Vec<u8>>,
+}
+
+/// Write new versions, the history index, the current state and each
+/// component's changelog, skipping anything unchanged since the last write
+async fn persist_history(
+    state: &AppState,
+    store: &dyn SnapshotStore,
+    written: &mut PersistedBlobs,
+) -> morpheus_core::errors::Result<()> {
+    let (blobs, index, current_state, event_logs, changelogs) = {
+        let history = state.versions.lock().await;
+        let mut blobs = Vec::new();
+        let mut versions = Vec::with_capacity(history.versions.len());
+        for version in &history.versions {
+            if !written.versions.contains(&version.id) {
+                let wasm = base64_decode(&version.wasm_base64).unwrap_or_default();
+                let snapshot = version
+                    .state_snapshot
+                    .as_ref()
+                    .map(|s| history.snapshot_codec.serialize(s))
+                    .transpose()?;
+                blobs.push((version.id, wasm, snapshot));
+            }
+            versions.push(ComponentVersion {
+                wasm_base64: String::new(),
+                state_snapshot: None,
+                ..version.clone()
+            });
+        }
+        let index = serde_json::to_vec(&PersistedHistory {
+            versions,
+            current_index: history.current_index,
+            groups: history.groups.clone(),
+        })?;
+        let current_state = history.snapshot_codec.serialize(&history.current_state)?;
+        let mut event_logs = Vec::new();
+        for (component, log) in &history.events {
+            if written.event_seqs.get(component) != Some(&log.last_seq()) {
+                let bytes = history.snapshot_codec.with_max_size(None).serialize(log)?;
+                event_logs.push((component.clone(), log.last_seq(), bytes));
+            }
+        }
+        let mut changelogs = Vec::new();
+        for component in history.components() {
+            let notes = history.release_notes(&component, None);
+            let json = serde_json::to_vec_pretty(&notes)?;
+            if written.changelogs.get(&component) != Some(&json) {
+                let markdown = render_changelog_markdown(&component, &notes);
+                changelogs.push((component, json, markdown));
+            }
+        }
+        (blobs, index, current_state, event_logs, changelogs)
+    };
+
+    for (id, wasm, snapshot) in blobs {
+        store.put(&store::wasm_key(&id.to_string()), wasm).await?;
+        if let Some(snapshot) = snapshot {
+            store.put(&store::state_key(&id.to_string()), snapshot).await?;
+        }
+        written.versions.insert(id);
+    }
+    if index != written.index {
+        store.put(HISTORY_KEY, index.clone()).await?;
+        written.index = index;
+    }
+    if current_state != written.current_state {
+        store.put(&store::state_key("current"), current_state.clone()).await?;
+        written.current_state = current_state;
+    }
+    for (component, seq, bytes) in event_logs {
+        store.put(&format!("{}{}.json", EVENTS_PREFIX, component), bytes).await?;
+        written.event_seqs.insert(component, seq);
+    }
+    for (component, json, markdown) in changelogs {
+        let key = format!("{}{}", CHANGELOG_PREFIX, component);
+        if store::validate_key(&key).is_err() {
+            continue;
+        }
+        store.put(&format!("{}.md", key), markdown.into_bytes()).await?;
+        store.put(&format!("{}.json", key), json.clone()).await?;
+        written.changelogs.insert(component, json);
+    }
+    Ok(())
+}
+
+/// Apply due activations, retire due deprecated components, and unload idle
+/// instances, every few seconds
+async fn run_scheduler(state: AppState) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(SCHEDULER_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        let mut registry = state.registry.lock().await;
+        let retired = registry.retire_due(&now);
+        for id in registry.unload_idle() {
+            let name = registry.metadata(&id).map(|m| m.name.clone()).unwrap_or_default();
+            info!(component_id = %name, "💤 Idle component unloaded");
+        }
+        drop(registry);
+        for metadata in retired {
+            info!(component_id = %metadata.name, "🌅 Deprecated component retired on schedule");
+            record_audit(&state, "retire", None, "retired", format!("{} retired on schedule", metadata.name)).await;
+        }
+
+        run_due_activations(&state).await;
+    }
+}
+
+/// Apply pending activations that are due, recording how each went
+async fn run_due_activations(state: &AppState) {
+    let due: Vec<ScheduledActivation> = state
+        .schedule
+        .lock()
+        .await
+        .iter()
+        .filter(|a| a.status == ActivationStatus::Pending && a.activate_at <= Utc::now())
+        .cloned()
+        .collect();
+
+    for activation in due {
+        let status = activate_scheduled(state, &activation).await;
+        if let Some(entry) = state.schedule.lock().await.iter_mut().find(|a| a.id == activation.id) {
+            entry.status = status;
+        }
+    }
+}
+
+/// Activate a version, verify it, and roll back if it is unhealthy
+async fn activate_scheduled(state: &AppState, activation: &ScheduledActivation) -> ActivationStatus {
+    let mut history = state.versions.lock().await;
+    if history.versions.get(activation.version_id).is_none() {
+        drop(history);
+        warn!(version = activation.version_id, "⏰ Scheduled version no longer exists");
+        record_audit(state, "activate", Some(activation.version_id), "failed", "Version no longer exists".to_string()).await;
+        return ActivationStatus::Failed;
+    }
+    if let Err(reason) = history.check_activation(activation.version_id) {
+        drop(history);
+        warn!(version = activation.version_id, "⏰ Scheduled activation blocked: {}", reason);
+        record_audit(state, "activate", Some(activation.version_id), "blocked", reason).await;
+        return ActivationStatus::Blocked;
+    }
+    let previous = history.current_index;
+    let version = history.activate(activation.version_id).cloned().expect("version exists");
+    drop(history);
+    info!(version = version.id, component_id = %version.manifest.name, "⏰ Activating scheduled version");
+
+    let result = match verify_version_health(&version) {
+        Ok(wasm_bytes) => {
+            register_component(
+                state,
+                version.manifest.clone(),
+                &wasm_bytes,
+                version.provenance.clone(),
+                version.artifacts(),
+            )
+            .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+        Err(reason) => Err(reason),
+    };
+
+    match result {
+        Ok(()) => {
+            info!(version = version.id, component_id = %version.manifest.name, "✅ Scheduled version is live");
+            record_audit(state, "activate", Some(version.id), "activated", format!("Replaced version {}", previous)).await;
+            ActivationStatus::Activated
+        }
+        Err(reason) => {
+            error!(version = version.id, component_id = %version.manifest.name, "❌ Scheduled version failed health check: {}", reason);
+            let mut history = state.versions.lock().await;
+            // Only undo this activation; a version activated meanwhile stays live
+            let current = history.current_index;
+            if current != version.id {
+                drop(history);
+                let detail = format!("Health check failed; version {} was activated meanwhile: {}", current, reason);
+                record_audit(state, "activate", Some(version.id), "failed", detail).await;
+                return ActivationStatus::Failed;
             }
+            history.activate(previous);
+            drop(history);
+            record_audit(
+                state,
+                "activate",
+                Some(version.id),
+                "rolled_back",
+                format!("Health check failed, restored version {}: {}", previous, reason),
+            )
+            .await;
+            ActivationStatus::RolledBack
         }
     }
 }
 
-/// Update component state
-async fn update_state(
-    State(state): State<AppState>,
-    Json(req): Json<UpdateStateRequest>,
-) -> Result<Json<UpdateStateResponse>, AppError> {
-    let mut history = state.versions.lock().await;
-    history.update_state(req.state);
-    Ok(Json(UpdateStateResponse { success: true }))
+/// Check that a version's module is valid and exports `render()`
+fn verify_version_health(version: &ComponentVersion) -> Result<Vec<u8>, String> {
+    let wasm_bytes = base64_decode(&version.wasm_base64).map_err(|e| e.to_string())?;
+    wasmparser::Validator::new()
+        .validate_all(&wasm_bytes)
+        .map_err(|e| format!("invalid module: {}", e))?;
+
+    let has_render = wasmparser::Parser::new(0)
+        .parse_all(&wasm_bytes)
+        .filter_map(|payload| match payload {
+            Ok(wasmparser::Payload::ExportSection(reader)) => Some(reader),
+            _ => None,
+        })
+        .flatten()
+        .any(|export| export.map(|e| e.name == "render").unwrap_or(false));
+    if !has_render {
+        return Err("module does not export render()".to_string());
+    }
+
+    Ok(wasm_bytes)
 }
 
-/// Rollback to previous version
-async fn rollback(
-    State(state): State<AppState>,
-    Json(req): Json<RollbackRequest>,
-) -> Result<Json<RollbackResponse>, AppError> {
-    info!("Rolling back to version {}", req.version_id);
+/// Read guardrail limits from `MORPHEUS_MAX_*` and `MORPHEUS_BANNED_ESCALATIONS`
+fn guardrails_from_env() -> anyhow::Result<Guardrails> {
+    let mut guardrails = Guardrails {
+        max_wasm_growth: env_number("MORPHEUS_MAX_WASM_GROWTH")?,
+        max_lines_changed: env_number("MORPHEUS_MAX_LINES_CHANGED")?,
+        max_complexity: env_number("MORPHEUS_MAX_COMPLEXITY")?,
+        banned_escalations: Vec::new(),
+    };
+    if let Ok(banned) = std::env::var("MORPHEUS_BANNED_ESCALATIONS") {
+        for capability in banned.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+            guardrails = guardrails.with_banned_escalation(capability);
+        }
+    }
+    Ok(guardrails)
+}
 
-    let mut history = state.versions.lock().await;
+/// Read snapshot limits from `MORPHEUS_MAX_SNAPSHOT_BYTES` (0 for no limit),
+/// `MORPHEUS_SNAPSHOT_COMPRESSION` (`off` to store uncompressed) and
+/// `MORPHEUS_SNAPSHOT_FORMAT` (`json`, `msgpack` or `cbor`)
+fn snapshot_codec_from_env() -> anyhow::Result<SnapshotCodec> {
+    let mut codec = SnapshotCodec::new();
+    if let Some(format) = std::env::var("MORPHEUS_SNAPSHOT_FORMAT").ok().as_deref().and_then(Format::from_name) {
+        codec = codec.with_format(format);
+    }
+    if let Some(limit) = env_number("MORPHEUS_MAX_SNAPSHOT_BYTES")? {
+        codec = codec.with_max_size(Some(limit).filter(|&limit| limit > 0));
+    }
+    if std::env::var("MORPHEUS_SNAPSHOT_COMPRESSION").as_deref() == Ok("off") {
+        codec = codec.with_compression(false);
+    }
+    Ok(codec)
+}
 
-    if let Some(version) = history.rollback_to(req.version_id) {
-        Ok(Json(RollbackResponse {
-            success: true,
-            version_id: version.id,
-            wasm_base64: version.wasm_base64.clone(),
-            restored_state: version.state_snapshot.clone(),
-            error: None,
-        }))
-    } else {
-        Ok(Json(RollbackResponse {
-            success: false,
-            version_id: 0,
-            wasm_base64: String::new(),
-            restored_state: None,
-            error: Some(format!("Version {} not found", req.version_id)),
-        }))
+/// Read the state scrub policy from `MORPHEUS_SCRUB_POLICY`
+///
+/// Either `recommended` or the path of a JSON policy file.
+fn scrub_policy_from_env() -> anyhow::Result<ScrubPolicy> {
+    match std::env::var("MORPHEUS_SCRUB_POLICY").as_deref() {
+        Ok("recommended") => Ok(ScrubPolicy::recommended()),
+        Ok(path) => Ok(serde_json::from_slice(&std::fs::read(path)?)?),
+        Err(_) => Ok(ScrubPolicy::new()),
     }
 }
 
-/// Get version history
-async fn get_history(State(state): State<AppState>) -> Result<Json<HistoryResponse>, AppError> {
-    let history = state.versions.lock().await;
-    Ok(Json(HistoryResponse {
-        versions: history.get_history(),
-        current_state: history.current_state.clone(),
-    }))
+/// Scrub policy for telemetry bound for the AI, from
+/// `MORPHEUS_TELEMETRY_POLICY`: `recommended` (the default), `off`, or the
+/// path of a JSON policy file
+///
+/// Hashes are keyed with `MORPHEUS_TELEMETRY_HASH_KEY`, or a key made up at
+/// startup, so they can't be reversed by hashing guesses.
+fn telemetry_policy_from_env() -> anyhow::Result<ScrubPolicy> {
+    let policy = match std::env::var("MORPHEUS_TELEMETRY_POLICY").as_deref() {
+        Ok("off") => return Ok(ScrubPolicy::new()),
+        Ok("recommended") | Err(_) => ScrubPolicy::recommended(),
+        Ok(path) => serde_json::from_slice(&std::fs::read(path)?)?,
+    };
+    let key = std::env::var("MORPHEUS_TELEMETRY_HASH_KEY").unwrap_or_else(|_| uuid::Uuid::new_v4().to_string());
+    Ok(policy.with_hash_key(key))
+}
+
+/// The component registry, unloading instances unused for
+/// `MORPHEUS_IDLE_UNLOAD_SECS` seconds if set
+fn registry_from_env() -> anyhow::Result<ComponentRegistry> {
+    // Components describe themselves once loaded, so breaking reloads can only be reported
+    let registry = ComponentRegistry::new().with_compatibility(Compatibility::Warn);
+    let Ok(secs) = std::env::var("MORPHEUS_IDLE_UNLOAD_SECS") else {
+        return Ok(registry);
+    };
+    let sweeps = secs.parse::<u64>()?.div_ceil(SCHEDULER_INTERVAL_SECS);
+    info!("✓ Unloading components idle for {}s", secs);
+    Ok(registry.with_idle_unloading(u32::try_from(sweeps)?))
+}
+
+/// Screening rules from `MORPHEUS_SCREENING`: `recommended` (the default),
+/// `off`, or the path of a JSON rule file
+fn screener_from_env() -> anyhow::Result<Screener> {
+    match std::env::var("MORPHEUS_SCREENING").as_deref() {
+        Ok("off") => Ok(Screener::new()),
+        Ok("recommended") | Err(_) => Ok(Screener::recommended()),
+        Ok(path) => Ok(serde_json::from_slice(&std::fs::read(path)?)?),
+    }
+}
+
+/// Screen user text before it reaches the AI: refuse it if a blocking rule
+/// matches, and audit flagged text
+async fn screen_request(state: &AppState, action: &str, text: &str) -> Result<(), AppError> {
+    let screening = state.screener.scan(text);
+    if screening.is_clean() {
+        return Ok(());
+    }
+    let details: Vec<String> = screening.findings.iter().map(|f| f.to_string()).collect();
+    if screening.is_blocked() {
+        warn!("🚫 Blocked {} request: {}", action, details.join("; "));
+        record_audit(state, action, None, "blocked", details.join("; ")).await;
+        return Err(AppError::Blocked(screening));
+    }
+    warn!("🚩 Flagged {} request: {}", action, details.join("; "));
+    record_audit(state, action, None, "flagged", details.join("; ")).await;
+    Ok(())
+}
+
+/// Append an entry to the audit log
+async fn record_audit(
+    state: &AppState,
+    action: &str,
+    version_id: Option<usize>,
+    outcome: &str,
+    detail: String,
+) {
+    state.audit_log.lock().await.push(AuditEntry {
+        timestamp: Utc::now(),
+        action: action.to_string(),
+        version_id,
+        outcome: outcome.to_string(),
+        detail,
+    });
+}
+
+/// Look up a registered component by name
+fn find_component(registry: &ComponentRegistry, name: &str) -> Result<ComponentId, AppError> {
+    registry
+        .find_by_name(name)
+        .ok_or_else(|| AppError::NotFound(format!("Component '{}' not found", name)))
 }
 
 /// Call Claude API
-async fn call_claude_api(state: &AppState) -> Result<String, AppError> {
+async fn call_claude_api(state: &AppState, model: &str) -> Result<String, AppError> {
     let conversation = state.conversation.lock().await;
     let messages = conversation.clone();
     drop(conversation);
 
-    let client = reqwest::Client::new();
-    let response = client
-        .post("https://openrouter.ai/api/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", &state.api_key))
-        .header("HTTP-Referer", "https://github.com/morpheus-project")
-        .header("X-Title", "Morpheus")
-        .header("Content-Type", "application/json")
-        .json(&ClaudeRequest {
-            model: "anthropic/claude-3.5-sonnet".to_string(),
-            max_tokens: 4096,
-            messages,
-        })
-        .send()
-        .await?;
+    let text = complete_with(state, model, messages).await?;
+    extract_rust_code(&text)
+}
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await?;
-        return Err(AppError::ApiError(format!(
-            "Claude API returned {}: {}",
-            status, body
-        )));
+/// Send messages to the frontier model and return its text response
+async fn complete(state: &AppState, messages: Vec<Message>) -> Result<String, AppError> {
+    complete_with(state, &state.generation.frontier_model, messages).await
+}
+
+/// Send messages to `model` and return its text response
+async fn complete_with(state: &AppState, model: &str, messages: Vec<Message>) -> Result<String, AppError> {
+    if let Some(mock) = &state.mock {
+        return Ok(mock.respond(&messages));
     }
+    state.limits.check_budget()?;
+    let request = ClaudeRequest {
+        model: model.to_string(),
+        max_tokens: 4096,
+        messages,
+    };
+    let body = state.model_endpoint.post(&request, &state.api_key).await?;
 
-    let claude_response: ClaudeResponse = response.json().await?;
-    let text = claude_response
+    let claude_response: ClaudeResponse = serde_json::from_str(&body).map_err(anyhow::Error::from)?;
+    if let Some(usage) = &claude_response.usage {
+        state.limits.record_usage(usage.prompt_tokens, usage.completion_tokens);
+    }
+    claude_response
         .choices
         .first()
         .map(|choice| choice.message.content.clone())
-        .ok_or_else(|| AppError::ApiError("No content in response".to_string()))?;
-
-    extract_rust_code(&text)
+        .ok_or_else(|| AppError::ApiError("No content in response".to_string()))
 }
 
 /// Extract Rust code from AI response
@@ -813,7 +4662,105 @@ fn extract_rust_code(text: &str) -> Result<String, AppError> {
     Ok(text.trim().to_string())
 }
 
-/// Create system prompt for AI
+/// Register a compiled component in the registry, replacing any previous
+/// component of the same name, and resolve its slots. `artifacts` are its
+/// builds per target, as returned by [`ComponentVersion::artifacts`].
+async fn register_component(
+    state: &AppState,
+    manifest: ComponentManifest,
+    wasm_bytes: &[u8],
+    provenance: Provenance,
+    artifacts: Vec<Artifact>,
+) -> Result<Vec<SlotMount>, AppError> {
+    let component = WasmComponent::load(wasm_bytes, manifest.permissions.clone()).await?;
+    let id = component.id();
+    let mut metadata = component.metadata().clone();
+    metadata.name = manifest.name.clone();
+    metadata.capabilities = Capability::infer(&manifest.description);
+    metadata.ai_generated = provenance.author == Author::Ai;
+    metadata.provenance = provenance;
+
+    let mut registry = state.registry.lock().await;
+    let mut flag = None;
+    if let Some(previous) = registry.find_by_name(&manifest.name) {
+        // Operator flags, deprecations and fallbacks outlive regeneration, and versions carry on
+        flag = registry.clear_flag(&previous);
+        if let Some(replaced) = registry.metadata(&previous) {
+            metadata.version = replaced.version.bump(Bump::Patch);
+            metadata.lifecycle = replaced.lifecycle.clone();
+            metadata.fallback = replaced.fallback.clone();
+        }
+        registry.remove(&previous);
+    }
+    registry.register(id, component, metadata);
+    registry.set_manifest(id, manifest)?;
+    registry.set_artifacts(id, artifacts)?;
+    if let Some(flag) = flag {
+        registry.set_flag(id, flag)?;
+    }
+    Ok(registry.resolve_slots(&id)?)
+}
+
+/// Check that each slot of `manifest` embeds a component in the catalog
+fn check_slots(manifest: &ComponentManifest, available: &[CatalogEntry]) -> Result<(), AppError> {
+    match manifest
+        .slots
+        .iter()
+        .find(|slot| !available.iter().any(|entry| entry.manifest.name == slot.component))
+    {
+        Some(slot) => Err(AppError::BadRequest(format!(
+            "Slot '{}' references unknown component '{}'",
+            slot.name, slot.component
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Detect a version committed since `base_version_id`
+///
+/// Versions are numbered in commit order, so only a live version newer than
+/// the base is one the edit would overwrite; rolling back to an older one is
+/// a deliberate choice, not a conflict.
+fn detect_conflict(history: &VersionHistory, base_version_id: Option<usize>) -> Option<ConflictInfo> {
+    let base_version_id = base_version_id?;
+    let current = history.get_current()?;
+    if current.id <= base_version_id {
+        return None;
+    }
+    Some(ConflictInfo {
+        base_version_id,
+        current_version_id: current.id,
+        current_description: current.description.clone(),
+    })
+}
+
+/// Ask the AI to reapply a request on top of a concurrently committed version
+fn create_rebase_prompt(current_code: &str, prompt: &str) -> String {
+    format!(
+        "Another change was committed while you were working. This is now the current component:\n\n```rust\n{}\n```\n\nReapply this request on top of it, keeping its changes: {}",
+        current_code, prompt
+    )
+}
+
+/// Create the user's generation request, including any slots to embed
+fn create_generation_request(prompt: &str, manifest: &ComponentManifest, feedback: &[Feedback]) -> String {
+    let mut request = format!("Create a WASM component: {}", prompt);
+    for slot in &manifest.slots {
+        request.push_str(&format!(
+            "\nEmbed the existing '{}' component by rendering an empty <div id=\"{}\"></div> where it belongs.",
+            slot.component,
+            manifest::slot_mount_point(&manifest.name, &slot.name)
+        ));
+    }
+    let section = feedback::prompt_section(feedback);
+    if !section.is_empty() {
+        request.push_str("\n\n");
+        request.push_str(&section);
+    }
+    request
+}
+
+/// Create system prompt for AI; [`PromptContext`] adds the project's facts
 fn create_system_prompt() -> String {
     r##"You are a Rust expert generating simple WebAssembly components that return HTML strings.
 
@@ -824,6 +4771,7 @@ CRITICAL RULES:
 4. The function must return static HTML as a String - NO DOM manipulation
 5. Use Tailwind CSS classes in your HTML strings for styling
 6. Keep it SIMPLE - just generate HTML strings
+7. Also export `__morpheus_describe()` returning a JSON string describing the component
 
 COMPONENT TEMPLATE:
 
@@ -832,37 +4780,74 @@ use wasm_bindgen::prelude::*;
 #[wasm_bindgen]
 pub fn render() -> String {
     r#"<div class="p-6 max-w-2xl mx-auto">
-    <h1 class="text-4xl font-bold text-gray-900 mb-4">Simple Component</h1>
+    <h1 class="text-4xl font-bold text-[color:var(--morpheus-color-text)] mb-4">Simple Component</h1>
     <button 
         onclick="alert('Clicked!')"
-        class="px-6 py-3 bg-blue-600 text-white rounded-lg hover:bg-blue-700 transition-colors">
+        class="px-6 py-3 bg-[var(--morpheus-color-primary)] text-[color:var(--morpheus-color-on-primary)] rounded-lg hover:opacity-90 transition-opacity">
         Click Me
     </button>
 </div>"#.to_string()
 }
 
-TAILWIND CSS CLASSES (use these for styling):
+#[wasm_bindgen]
+pub fn __morpheus_describe() -> String {
+    r#"{"exports": ["render"], "messages": [], "emits": [], "consumes": [], "state": {}}"#.to_string()
+}
+
+Components with state may also export `restore_state(state_json: &str)`; the host calls it with saved state before `render()`, and again when another client changes the state. To share a state change with other clients, call the host import `morpheus.setState(state_json)`.
+
+To animate list items as they are added or removed, give each a stable `data-key` and declare the transition with attributes instead of CSS keyframes: `data-transition-enter` (classes the item starts with, e.g. "opacity-0 -translate-y-2"), `data-transition-leave` (classes it ends with, e.g. "opacity-0"), `data-transition-duration` (ms) and `data-transition-easing` (a CSS timing function). The host does the rest.
+
+Give every form field, and every element that scrolls, a `data-morpheus-key` naming what it is (e.g. `<input data-morpheus-key="todo-input" ...>`), unique within the component. When changing an existing component, keep the keys it already has on the elements that keep their meaning, even if you restructure the markup around them: the host uses them to keep what the user typed, where they had scrolled and what had focus when the new version replaces the old one.
+
+For reorderable lists (kanban columns, ranked items), mark the list with `data-sortable="<list name>"` and each item with `draggable="true"` and a `data-key`, and export `on_reorder(list: &str, from: usize, to: usize)`: remove the item at `from`, insert it at `to`, and keep the new order in your state. The host handles dragging and calls `render()` afterwards.
+
+To copy or paste, or to read files the user picks, use these host imports (the calls are refused unless the user granted the component the Clipboard or Files permission):
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = morpheus, js_name = clipboardWrite)]
+    fn morpheus_clipboard_write(text: &str);
+    #[wasm_bindgen(js_namespace = morpheus, js_name = clipboardRead)]
+    fn morpheus_clipboard_read();
+    #[wasm_bindgen(js_namespace = morpheus, js_name = pickFiles)]
+    fn morpheus_pick_files(accept: &str, multiple: bool) -> u32;
+}
+Pasted text arrives through an exported `on_clipboard(text: &str)`; each picked file through `on_file_start(request: u32, name: &str, mime: &str, size: f64)`, then `on_file_chunk(request: u32, name: &str, chunk: &[u8])` calls, then `on_file_end(request: u32, name: &str)`. The host calls `render()` afterwards.
+
+To alert the user (a timer finished, a long task completed), call the host import `morpheus.notify(title, body)`, declared like the ones above as `fn morpheus_notify(title: &str, body: &str);`. It needs the Notifications permission; the host asks the user once, and a component may show only a few notifications a minute, so notify about events the user is waiting for, not every change.
+
+To use the user's location (weather, nearby places), call the host import `morpheus.getPosition()`, declared as `fn morpheus_get_position();`, and export `on_position(latitude: f64, longitude: f64, accuracy_m: f64)`; the host calls it, then `render()`. It needs the Geolocation permission. Unless the user granted exact precision the position is only accurate to about 10 km, and asking again within a minute returns the same position, so call it once when the component loads or on a button press; don't poll.
+
+For long lists and data tables (hundreds of rows or more), render only the rows in view: wrap them in `<div data-virtual-list="<list name>" style="height:600px;overflow-y:auto">`, give every row the same fixed height, put a spacer `<div style="height:Npx"></div>` above and below the rendered rows (`<tr>` spacers inside a table) for the rows left out, and export `on_scroll(list: &str, scroll_top: f64, viewport_height: f64)` that stores the scroll position; render the rows from `scroll_top / row_height` to the end of the viewport plus about 5 either side. The host calls `on_scroll` as the list scrolls, then `render()`.
+
+Don't hard-code the text users read (headings, labels, button text, placeholders, messages): pass each string through the host import `morpheus.t(key, fallback, args_json)`, declared as `fn t(key: &str, fallback: &str, args_json: &str) -> String;`. The key names what the text is, dotted and lowercase (`"todo.add_button"`); the fallback is the English text, with values as `{name}` placeholders filled from the JSON object in `args_json` (`t("todo.remaining", "{count} left", &format!(r#"{{"count": {}}}"#, remaining))`, or `"{}"` without values). Use string literals for the key and fallback. The host returns the text in the user's locale and re-renders when it changes. When changing a component, keep the keys of texts that keep their meaning so their translations still apply.
+
+Colors and fonts come from the host's theme, never fixed values: use its CSS variables `var(--morpheus-color-<role>)` (background, surface, text, muted, border, primary, on-primary, accent, danger, success), `var(--morpheus-spacing-<size>)` (xs, sm, md, lg, xl) and `var(--morpheus-typography-<use>)` (a `font` shorthand: body, heading, small, mono), through Tailwind arbitrary values as below or `style` attributes. Switching themes (e.g. to dark mode) then restyles the component without regenerating it; a request like "add dark mode" needs no color changes in the component at all. Where a value is needed in code rather than CSS (e.g. drawing on a canvas), call the host import `morpheus.themeToken(name)`, declared as `fn theme_token(name: &str) -> String;`, e.g. `theme_token("color.primary")`.
+
+Dark mode is automatic: unless a theme was chosen, the host uses the light or dark theme, whichever the user's OS prefers, and switches when the OS does. Don't use Tailwind `dark:` variants, `prefers-color-scheme` media queries or listeners of your own. Where something other than styling differs between light and dark (e.g. which image to show), call the host import `morpheus.colorScheme()`, declared as `fn color_scheme() -> String;`, which returns `"light"` or `"dark"`; the host re-renders the component when it changes.
+
+TAILWIND CSS CLASSES (use these for styling; colors are theme variables):
 
 Buttons:
-- Primary: "px-6 py-3 bg-blue-600 text-white rounded-lg hover:bg-blue-700 transition-colors"
-- Danger: "px-6 py-3 bg-red-600 text-white rounded-lg hover:bg-red-700 transition-colors"  
-- Success: "px-6 py-3 bg-green-600 text-white rounded-lg hover:bg-green-700 transition-colors"
+- Primary: "px-6 py-3 bg-[var(--morpheus-color-primary)] text-[color:var(--morpheus-color-on-primary)] rounded-lg hover:opacity-90 transition-opacity"
+- Danger: "px-6 py-3 bg-[var(--morpheus-color-danger)] text-[color:var(--morpheus-color-on-primary)] rounded-lg hover:opacity-90 transition-opacity"
+- Success: "px-6 py-3 bg-[var(--morpheus-color-success)] text-[color:var(--morpheus-color-on-primary)] rounded-lg hover:opacity-90 transition-opacity"
 
 Inputs:
-- "w-full px-4 py-3 border border-gray-300 rounded-lg focus:ring-2 focus:ring-blue-500"
+- "w-full px-4 py-3 bg-[var(--morpheus-color-background)] text-[color:var(--morpheus-color-text)] border border-[color:var(--morpheus-color-border)] rounded-lg focus:ring-2 focus:ring-[color:var(--morpheus-color-primary)]"
 
 Containers:
 - "max-w-2xl mx-auto px-4 py-6"
-- "bg-white rounded-lg shadow-md p-6"
+- "bg-[var(--morpheus-color-surface)] rounded-lg shadow-md p-6"
 
 Layout:
 - Flex: "flex gap-4 items-center justify-between"
 - Grid: "grid grid-cols-2 gap-4"
 
 Typography:
-- H1: "text-4xl font-bold text-gray-900"
-- H2: "text-2xl font-semibold text-gray-800"
-- Body: "text-base text-gray-600"
+- H1: "text-4xl font-bold text-[color:var(--morpheus-color-text)]"
+- H2: "text-2xl font-semibold text-[color:var(--morpheus-color-text)]"
+- Body: "text-base text-[color:var(--morpheus-color-muted)]"
 
 EXAMPLES:
 
@@ -872,7 +4857,7 @@ use wasm_bindgen::prelude::*;
 #[wasm_bindgen]
 pub fn render() -> String {
     r#"<div class="p-6 max-w-2xl mx-auto">
-    <button class="px-6 py-3 bg-blue-600 text-white rounded-lg hover:bg-blue-700">
+    <button class="px-6 py-3 bg-[var(--morpheus-color-primary)] text-[color:var(--morpheus-color-on-primary)] rounded-lg hover:opacity-90">
         Click Me
     </button>
 </div>"#.to_string()
@@ -886,17 +4871,17 @@ pub fn render() -> String {
     r#"<div class="p-6 max-w-2xl mx-auto">
     <h1 class="text-4xl font-bold mb-6">My List</h1>
     <ul class="space-y-2">
-        <li class="p-4 bg-white rounded-lg shadow">Item 1</li>
-        <li class="p-4 bg-white rounded-lg shadow">Item 2</li>
+        <li class="p-4 bg-[var(--morpheus-color-surface)] rounded-lg shadow">Item 1</li>
+        <li class="p-4 bg-[var(--morpheus-color-surface)] rounded-lg shadow">Item 2</li>
     </ul>
 </div>"#.to_string()
 }
 
 IMPORTANT:
 - Just return HTML strings - NO web-sys, NO document, NO DOM APIs
-- Use Tailwind classes for all styling
+- Use Tailwind classes for all styling, with the theme's CSS variables for colors
 - Keep HTML simple and static
-- ONLY use wasm_bindgen to export the function
+- ONLY use wasm_bindgen to export the functions
 - ONLY output Rust code, no explanations"##
         .to_string()
 }
@@ -929,9 +4914,25 @@ fn base64_decode(encoded: &str) -> Result<Vec<u8>, AppError> {
 enum AppError {
     Anyhow(anyhow::Error),
     Reqwest(reqwest::Error),
+    Morpheus(morpheus_core::errors::MorpheusError),
+    Conflict(ConflictInfo),
+    UnsupportedMediaType(String),
+    Limited(limits::LimitError),
+    Denied(sharing::AccessError),
+    Blocked(Screening),
+    /// A request the client must change, e.g. naming something that doesn't exist
+    BadRequest(String),
+    /// Something the request names that doesn't exist, e.g. an unknown job
+    NotFound(String),
     ApiError(String),
 }
 
+impl From<morpheus_core::errors::MorpheusError> for AppError {
+    fn from(err: morpheus_core::errors::MorpheusError) -> Self {
+        AppError::Morpheus(err)
+    }
+}
+
 impl From<anyhow::Error> for AppError {
     fn from(err: anyhow::Error) -> Self {
         AppError::Anyhow(err)
@@ -944,11 +4945,40 @@ impl From<reqwest::Error> for AppError {
     }
 }
 
+impl From<sharing::AccessError> for AppError {
+    fn from(err: sharing::AccessError) -> Self {
+        AppError::Denied(err)
+    }
+}
+
+impl From<limits::LimitError> for AppError {
+    fn from(err: limits::LimitError) -> Self {
+        AppError::Limited(err)
+    }
+}
+
 impl std::fmt::Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             AppError::Anyhow(e) => write!(f, "{}", e),
             AppError::Reqwest(e) => write!(f, "{}", e),
+            AppError::Morpheus(e) => write!(f, "{}", e),
+            AppError::Conflict(c) => write!(
+                f,
+                "Version {} was committed since base version {}",
+                c.current_version_id, c.base_version_id
+            ),
+            AppError::UnsupportedMediaType(content_type) => {
+                write!(f, "Unsupported content type '{}' (use JSON, MessagePack or CBOR)", content_type)
+            }
+            AppError::Limited(e) => write!(f, "{}", e),
+            AppError::Denied(e) => write!(f, "{}", e),
+            AppError::Blocked(screening) => {
+                let rules: Vec<&str> = screening.blocking().map(|f| f.rule.as_str()).collect();
+                write!(f, "Request blocked by screening rules: {}", rules.join(", "))
+            }
+            AppError::BadRequest(msg) => write!(f, "{}", msg),
+            AppError::NotFound(msg) => write!(f, "{}", msg),
             AppError::ApiError(msg) => write!(f, "{}", msg),
         }
     }
@@ -964,6 +4994,7 @@ async fn design_start(
     Json(req): Json<DesignStartRequest>,
 ) -> Result<Json<DesignStartResponse>, AppError> {
     info!("Starting design session: {}", req.prompt);
+    screen_request(&state, "design_start", &req.prompt).await?;
 
     // Check if there's already an active session
     let mut session_lock = state.design_session.lock().await;
@@ -978,10 +5009,14 @@ async fn design_start(
 
     // Create new session
     let session_id = uuid::Uuid::new_v4().to_string();
+    let base_version_id = match req.base_version_id {
+        Some(id) => Some(id),
+        None => state.versions.lock().await.get_current().map(|v| v.id),
+    };
     let mut conversation = Vec::new();
     conversation.push(Message {
         role: "user".to_string(),
-        content: create_system_prompt(),
+        content: PromptContext::gather(&state, None).await.system_prompt(),
     });
     conversation.push(Message {
         role: "user".to_string(),
@@ -998,6 +5033,7 @@ async fn design_start(
         drafts: vec![draft.clone()],
         current_draft_index: 0,
         original_prompt: req.prompt.clone(),
+        base_version_id,
         started_at: Utc::now(),
     };
 
@@ -1019,6 +5055,7 @@ async fn design_refine(
     Json(req): Json<DesignRefineRequest>,
 ) -> Result<Json<DesignRefineResponse>, AppError> {
     info!("Refining design: {}", req.feedback);
+    screen_request(&state, "design_refine", &req.feedback).await?;
 
     let mut session_lock = state.design_session.lock().await;
     let session = session_lock.as_mut()
@@ -1045,12 +5082,19 @@ async fn design_refine(
         &mut logs
     ).await?;
 
+    let mut draft_info = create_draft_info(&draft);
+    if let Some(base_iteration) = req.base_iteration {
+        if let Some(base) = session.drafts.iter().find(|d| d.iteration == base_iteration) {
+            if attach_patch(&mut draft_info, base)? {
+                logs.push(format!("📦 Sending patch against iteration {}", base_iteration));
+            }
+        }
+    }
+
     session.conversation = updated_conversation;
     session.drafts.push(draft.clone());
     session.current_draft_index = session.drafts.len() - 1;
 
-    let draft_info = create_draft_info(&draft);
-
     drop(session_lock);
 
     Ok(Json(DesignRefineResponse {
@@ -1069,6 +5113,16 @@ async fn design_commit(
     info!("Committing design");
 
     let mut session_lock = state.design_session.lock().await;
+    if !req.force {
+        let base_version_id = session_lock.as_ref().and_then(|s| s.base_version_id);
+        if let Some(conflict) = detect_conflict(&*state.versions.lock().await, base_version_id) {
+            warn!(
+                "Design commit conflicts with version {} (session based on {})",
+                conflict.current_version_id, conflict.base_version_id
+            );
+            return Err(AppError::Conflict(conflict));
+        }
+    }
     let session = session_lock.take()
         .ok_or_else(|| AppError::ApiError("No active design session".to_string()))?;
 
@@ -1089,6 +5143,7 @@ async fn design_commit(
     let commit_message = req.message.unwrap_or_else(|| session.original_prompt.clone());
     let version_name = format!("Design: {}", truncate(&commit_message, 40));
     
+    let manifest = ComponentManifest::new("main", commit_message.clone());
     let version_id = history.add_version(
         version_name,
         commit_message,
@@ -1096,10 +5151,30 @@ async fn design_commit(
         wasm_bytes.clone(),
         js_glue.clone(),
         true,
+        manifest,
+        Provenance::ai(session.original_prompt.clone(), state.generation.frontier_model.as_str()).with_toolchain(state.compiler.toolchain()),
+        true,
     );
+    history.versions[version_id].sbom = current_draft.sbom.clone();
+    history.versions[version_id].variants = current_draft.variants.clone();
+    history.versions[version_id].accessibility = current_draft.accessibility.clone();
+    history.versions[version_id].performance = current_draft.performance;
+    let violations = history.versions[version_id].guardrail_violations.clone();
+    let provenance = history.versions[version_id].provenance.clone();
+    let artifacts = history.versions[version_id].artifacts();
 
     drop(history);
     drop(session_lock);
+    report_guardrails(&state, version_id, &violations, &mut Vec::new()).await;
+
+    register_component(
+        &state,
+        ComponentManifest::new("main", session.original_prompt.clone()),
+        &wasm_bytes,
+        provenance,
+        artifacts,
+    )
+    .await?;
 
     Ok(Json(DesignCommitResponse {
         success: true,
@@ -1178,7 +5253,7 @@ async fn generate_draft(
         *conv_lock = conversation.clone();
         drop(conv_lock);
 
-        let rust_code = match call_claude_api(state).await {
+        let rust_code = match call_claude_api(state, &state.generation.frontier_model).await {
             Ok(code) => {
                 logs.push(format!("✓ Generated {} bytes of code", code.len()));
                 code
@@ -1196,10 +5271,13 @@ async fn generate_draft(
 
         // Try to compile
         logs.push("⚙️  Compiling...".to_string());
-        match state.compiler.compile(&rust_code).await {
+        match compile_verified(state, &rust_code, "main").await {
             Ok(result) => {
                 // SUCCESS! Return the working draft
                 logs.push(format!("✅ Compiled successfully! {} bytes WASM + {} bytes JS", result.wasm_bytes.len(), result.js_glue.len()));
+                for diagnostic in &result.diagnostics {
+                    logs.push(format!("🛡️  {}", diagnostic.message));
+                }
                 if attempt > 1 {
                     logs.push(format!("🎉 Success after {} attempts", attempt));
                 }
@@ -1207,11 +5285,15 @@ async fn generate_draft(
                 let draft = ComponentDraft {
                     iteration,
                     prompt: prompt.to_string(),
-                    rust_code,
+                    rust_code: source::tidy(result.fixed_source.as_deref().unwrap_or(&rust_code)).await,
                     wasm_base64: Some(base64_encode(&result.wasm_bytes)),
                     js_glue: Some(result.js_glue),
                     compilation_error: None,
                     created_at: Utc::now(),
+                    sbom: result.sbom,
+                    variants: result.variants,
+                    accessibility: result.verification.as_ref().map(|v| v.accessibility.clone()),
+                    performance: result.verification.map(|v| v.performance),
                 };
 
                 return Ok((draft, conversation));
@@ -1245,6 +5327,10 @@ async fn generate_draft(
                         js_glue: None,
                         compilation_error: Some(error_msg),
                         created_at: Utc::now(),
+                        sbom: None,
+                        variants: Vec::new(),
+                        accessibility: None,
+                        performance: None,
                     };
 
                     return Ok((draft, conversation));
@@ -1257,12 +5343,34 @@ async fn generate_draft(
     Err(AppError::ApiError("Unexpected error in generate_draft loop".to_string()))
 }
 
+// Helper to replace a draft's full WASM with a patch against the client's
+// base draft when the patch is smaller. Returns whether a patch was attached.
+fn attach_patch(info: &mut DraftInfo, base: &ComponentDraft) -> Result<bool, AppError> {
+    let (Some(base_wasm), Some(target_wasm)) = (&base.wasm_base64, &info.wasm_base64) else {
+        return Ok(false);
+    };
+
+    let base_bytes = base64_decode(base_wasm)?;
+    let target_bytes = base64_decode(target_wasm)?;
+    let patch = delta::diff(&base_bytes, &target_bytes);
+    if patch.len() >= target_bytes.len() {
+        return Ok(false);
+    }
+
+    info.wasm_patch = Some(base64_encode(&patch));
+    info.patch_base = Some(base.iteration);
+    info.wasm_base64 = None;
+    Ok(true)
+}
+
 // Helper to convert ComponentDraft to DraftInfo
 fn create_draft_info(draft: &ComponentDraft) -> DraftInfo {
     DraftInfo {
         iteration: draft.iteration,
         prompt: draft.prompt.clone(),
         wasm_base64: draft.wasm_base64.clone(),
+        wasm_patch: None,
+        patch_base: None,
         js_glue: draft.js_glue.clone(),
         compilation_error: draft.compilation_error.clone(),
         has_runtime_error: false, // Frontend will update this
@@ -1271,12 +5379,518 @@ fn create_draft_info(draft: &ComponentDraft) -> DraftInfo {
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            AppError::Anyhow(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
-            AppError::Reqwest(e) => (StatusCode::BAD_GATEWAY, e.to_string()),
-            AppError::ApiError(msg) => (StatusCode::BAD_GATEWAY, msg),
+        let message = self.to_string();
+        let status = match &self {
+            AppError::Anyhow(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Reqwest(_) => StatusCode::BAD_GATEWAY,
+            AppError::Morpheus(morpheus_core::errors::MorpheusError::SnapshotTooLarge { size, limit }) => {
+                let body = serde_json::json!({ "error": message, "size": size, "limit": limit });
+                return (StatusCode::PAYLOAD_TOO_LARGE, Json(body)).into_response();
+            }
+            AppError::Morpheus(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Conflict(conflict) => {
+                let body = serde_json::json!({ "error": message, "conflict": conflict });
+                return (StatusCode::CONFLICT, Json(body)).into_response();
+            }
+            AppError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::Limited(limit) => {
+                let retry_after = limit.retry_after().map(|wait| wait.as_secs().max(1));
+                let body = serde_json::json!({
+                    "error": message,
+                    "code": limit.code(),
+                    "retry_after_secs": retry_after,
+                });
+                let mut response = match retry_after {
+                    Some(_) => (StatusCode::TOO_MANY_REQUESTS, Json(body)).into_response(),
+                    None => (StatusCode::PAYMENT_REQUIRED, Json(body)).into_response(),
+                };
+                if let Some(secs) = retry_after {
+                    response.headers_mut().insert(header::RETRY_AFTER, secs.into());
+                }
+                return response;
+            }
+            AppError::Denied(denied) => {
+                let status = match denied {
+                    sharing::AccessError::ReadOnly(_) | sharing::AccessError::SharingDisabled => StatusCode::FORBIDDEN,
+                    _ => StatusCode::UNAUTHORIZED,
+                };
+                let body = serde_json::json!({ "error": message, "code": denied.code() });
+                return (status, Json(body)).into_response();
+            }
+            AppError::Blocked(screening) => {
+                let body = serde_json::json!({
+                    "error": message,
+                    "code": "request_blocked",
+                    "findings": screening.findings,
+                });
+                return (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response();
+            }
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::ApiError(_) => StatusCode::BAD_GATEWAY,
         };
 
         (status, Json(serde_json::json!({ "error": message }))).into_response()
     }
 }
+
+/// Request body decoded according to its `Content-Type`: JSON (the
+/// default), MessagePack or CBOR
+struct Payload<T>(T);
+
+#[async_trait]
+impl<S: Send + Sync, T: DeserializeOwned> FromRequest<S> for Payload<T> {
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let format = match req.headers().get(header::CONTENT_TYPE) {
+            Some(value) => {
+                let content_type = value.to_str().unwrap_or_default();
+                Format::from_content_type(content_type)
+                    .ok_or_else(|| AppError::UnsupportedMediaType(content_type.to_string()))?
+            }
+            None => Format::Json,
+        };
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to read request body: {}", e))?;
+        Ok(Payload(format.from_slice(&bytes)?))
+    }
+}
+
+/// Response format negotiated from the request's `Accept` header
+struct Accept(Format);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Accept {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let accept = parts.headers.get(header::ACCEPT).and_then(|v| v.to_str().ok());
+        Ok(Accept(accept.map_or(Format::Json, Format::negotiate)))
+    }
+}
+
+/// Response body serialized in a negotiated format
+struct Encoded<T>(Format, T);
+
+impl<T: Serialize> IntoResponse for Encoded<T> {
+    fn into_response(self) -> Response {
+        let Encoded(format, body) = self;
+        match format.to_vec(&body) {
+            Ok(bytes) => ([(header::CONTENT_TYPE, format.content_type())], bytes).into_response(),
+            Err(e) => AppError::from(e).into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// A module that passes [`verify_version_health`]
+    const RENDERABLE: &str = r#"(module (func (export "render")))"#;
+
+    /// Add a version of `manifest`'s component with `wat` as its module
+    async fn add_test_version(state: &AppState, manifest: ComponentManifest, wat: &str, activate: bool) -> usize {
+        let wasm = wat::parse_str(wat).unwrap();
+        let name = manifest.name.clone();
+        state.versions.lock().await.add_version(
+            name.clone(),
+            format!("The {} component", name),
+            "pub fn render() {}".to_string(),
+            wasm,
+            String::new(),
+            false,
+            manifest,
+            Provenance::default(),
+            activate,
+        )
+    }
+
+    fn scheduled(version_id: usize, activate_at: DateTime<Utc>) -> ScheduledActivation {
+        ScheduledActivation {
+            id: format!("activation-{}", version_id),
+            version_id,
+            activate_at,
+            status: ActivationStatus::Pending,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_only_versions_committed_since_the_base_conflict() {
+        let state = AppState::for_tests().await;
+        let counter = ComponentManifest::new("counter", "");
+        let first = add_test_version(&state, counter.clone(), RENDERABLE, true).await;
+        let second = add_test_version(&state, counter, RENDERABLE, true).await;
+        let mut history = state.versions.lock().await;
+
+        assert!(detect_conflict(&history, None).is_none());
+        assert!(detect_conflict(&history, Some(second)).is_none());
+        let conflict = detect_conflict(&history, Some(first)).unwrap();
+        assert_eq!((conflict.base_version_id, conflict.current_version_id), (first, second));
+        assert_eq!(conflict.current_description, "The counter component");
+
+        // Rolling back behind the base leaves nothing to overwrite
+        history.rollback_to(first);
+        assert!(detect_conflict(&history, Some(second)).is_none());
+        assert!(detect_conflict(&history, Some(first)).is_none());
+    }
+
+    #[test]
+    fn test_rebase_prompt_carries_the_current_code_and_request() {
+        let prompt = create_rebase_prompt("pub fn render() -> u32 { 2 }", "make the button red");
+        assert!(prompt.contains("```rust\npub fn render() -> u32 { 2 }\n```"));
+        assert!(prompt.ends_with("keeping its changes: make the button red"));
+    }
+
+    #[test]
+    fn test_source_diff_is_a_unified_diff_between_versions() {
+        let old = "fn render() {\n    \"Count: 0\"\n}\n";
+        let new = "fn render() {\n    \"Clicks: 0\"\n}\n";
+        let diff = source_diff(old, new, 3, 4);
+        assert!(diff.starts_with("--- v3/component.rs\n+++ v4/component.rs\n@@ -1,3 +1,3 @@\n"));
+        assert!(diff.contains("\n-    \"Count: 0\"\n+    \"Clicks: 0\"\n"));
+        assert!(diff.contains("\n fn render() {\n"));
+        assert!(source_diff(old, old, 3, 3).is_empty());
+    }
+
+    #[test]
+    fn test_explain_prompt_asks_for_user_visible_changes() {
+        let prompt = create_explain_prompt("-a\n+b");
+        assert!(prompt.contains("```diff\n-a\n+b\n```"));
+        assert!(prompt.contains("1-5 short bullet points"));
+        assert!(prompt.contains("not the code"));
+    }
+
+    #[tokio::test]
+    async fn test_explanations_are_stored_on_the_newer_version() {
+        let mut state = AppState::for_tests().await;
+        let request = || ExplainRequest { from: 1, to: 0 };
+        assert!(explain_change(State(state.clone()), Json(request())).await.is_err());
+
+        state.mock = Some(Arc::new(MockGenerator::new()));
+        let counter = ComponentManifest::new("counter", "");
+        add_test_version(&state, counter.clone(), RENDERABLE, true).await;
+        add_test_version(&state, counter, RENDERABLE, true).await;
+        state.versions.lock().await.versions[1].rust_code = "pub fn render() -> u32 { 1 }".to_string();
+
+        let Json(explained) = explain_change(State(state.clone()), Json(request())).await.unwrap();
+        assert!(explained.diff.starts_with("--- v1/component.rs\n+++ v0/component.rs\n"));
+        assert!(explained.diff.contains("\n-pub fn render() -> u32 { 1 }\n"));
+        assert_eq!(explained.changelog.base_version_id, 0);
+        assert_eq!(explained.changelog.summary, "- The component was regenerated (mock explanation)");
+        let history = state.versions.lock().await;
+        assert!(history.versions[0].changelog.is_none());
+        assert_eq!(history.versions[1].changelog.as_ref().unwrap().summary, explained.changelog.summary);
+    }
+
+    #[test]
+    fn test_changelog_markdown_lists_versions_newest_first() {
+        let day = |d: u32| Utc.with_ymd_and_hms(2025, 1, d, 12, 0, 0).unwrap();
+        let notes = vec![
+            ReleaseNote {
+                version_id: 1,
+                name: "Counter".to_string(),
+                description: "A counter".to_string(),
+                created_at: day(1),
+                activated_at: Some(day(1)),
+                summary: Some("- Shows a count".to_string()),
+            },
+            ReleaseNote {
+                version_id: 4,
+                name: "Design: Add a reset button".to_string(),
+                description: "Add a reset button".to_string(),
+                created_at: day(2),
+                activated_at: None,
+                summary: None,
+            },
+        ];
+        assert_eq!(
+            render_changelog_markdown("counter", &notes),
+            "# Changelog: counter\n\
+             \n## Version 4 (2025-01-02)\n\nAdd a reset button\n\
+             \n_Not yet activated._\n\
+             \n## Version 1 (2025-01-01)\n\nA counter\n\
+             \n- Shows a count\n"
+        );
+        assert_eq!(render_changelog_markdown("chart", &[]), "# Changelog: chart\n");
+    }
+
+    #[tokio::test]
+    async fn test_changelogs_are_persisted_with_their_versions() {
+        let state = AppState::for_tests().await;
+        let store = morpheus_core::store::MemoryStore::new();
+        let mut written = PersistedBlobs::default();
+        let counter = add_test_version(&state, ComponentManifest::new("counter", ""), RENDERABLE, true).await;
+        add_test_version(&state, ComponentManifest::new("chart", ""), RENDERABLE, false).await;
+        persist_history(&state, &store, &mut written).await.unwrap();
+
+        let markdown = |bytes: Option<Vec<u8>>| String::from_utf8(bytes.unwrap()).unwrap();
+        let counter_md = markdown(store.get("changelogs/counter.md").await.unwrap());
+        assert!(counter_md.starts_with("# Changelog: counter\n\n## Version 0 ("));
+        assert!(!counter_md.contains("_Not yet activated._"));
+        assert!(markdown(store.get("changelogs/chart.md").await.unwrap()).contains("_Not yet activated._"));
+        let notes: serde_json::Value =
+            serde_json::from_slice(&store.get("changelogs/counter.json").await.unwrap().unwrap()).unwrap();
+        assert_eq!(notes[0]["version_id"], 0);
+
+        // A new summary rewrites the component's changelog
+        state.versions.lock().await.versions[counter].changelog = Some(ChangelogEntry {
+            base_version_id: counter,
+            summary: "- Counts clicks".to_string(),
+            generated_at: Utc::now(),
+        });
+        persist_history(&state, &store, &mut written).await.unwrap();
+        assert!(markdown(store.get("changelogs/counter.md").await.unwrap()).ends_with("\n- Counts clicks\n"));
+        assert_eq!(state.versions.lock().await.release_notes("counter", Some(counter)).len(), 0);
+    }
+
+    fn edit(source: &str) -> EditRequest {
+        EditRequest {
+            source: source.to_string(),
+            message: None,
+            force: false,
+            assets: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hand_edits_that_fail_to_compile_save_nothing() {
+        let state = AppState::for_tests().await;
+        let version = add_test_version(&state, ComponentManifest::new("counter", ""), RENDERABLE, true).await;
+
+        let broken = Json(edit("fn render( {"));
+        let Json(response) = edit_version(State(state.clone()), Path(version), broken).await.unwrap();
+        assert!(!response.success);
+        assert_eq!(response.version_id, None);
+        assert!(response.error.is_some());
+        assert_eq!(state.versions.lock().await.versions.len(), 1);
+        let audit = last_audit(&state).await;
+        assert_eq!((audit.action.as_str(), audit.outcome.as_str()), ("edit", "failed"));
+    }
+
+    #[tokio::test]
+    async fn test_hand_edits_are_refused_before_compiling() {
+        let state = AppState::for_tests().await;
+        let counter = ComponentManifest::new("counter", "");
+        let first = add_test_version(&state, counter.clone(), RENDERABLE, true).await;
+        add_test_version(&state, counter, RENDERABLE, true).await;
+
+        let stale = edit_version(State(state.clone()), Path(first), Json(edit(""))).await;
+        assert!(matches!(stale, Err(AppError::Conflict(_))));
+        let mut assets = edit("");
+        assets.assets = Some(BTreeMap::from([("../secrets".to_string(), String::new())]));
+        assert!(edit_version(State(state.clone()), Path(first), Json(assets)).await.is_err());
+        assert!(edit_version(State(state.clone()), Path(7), Json(edit(""))).await.is_err());
+        assert_eq!(state.versions.lock().await.versions.len(), 2);
+    }
+
+    #[test]
+    fn test_hand_edits_are_held_to_the_guardrails() {
+        let mut history = VersionHistory::new().with_guardrails(Guardrails {
+            max_lines_changed: Some(1),
+            ..Guardrails::default()
+        });
+        let add = |history: &mut VersionHistory, code: &str| {
+            history.add_version(
+                "Edited".to_string(),
+                String::new(),
+                code.to_string(),
+                Vec::new(),
+                String::new(),
+                false, // Written by hand, as `edit_version` saves it
+                ComponentManifest::new("counter", ""),
+                Provenance::human(),
+                true,
+            )
+        };
+        let first = add(&mut history, "fn render() {}");
+        let edited = add(&mut history, "fn render() {}\nfn a() {}\nfn b() {}\nfn c() {}");
+
+        assert!(!history.versions[edited].guardrail_violations.is_empty());
+        assert_eq!(history.get_current().map(|v| v.id), Some(first));
+        assert!(history.check_activation(edited).unwrap_err().contains("needs an override"));
+    }
+
+    #[tokio::test]
+    async fn test_state_updates_are_scrubbed_rather_than_refused() {
+        let mut state = AppState::for_tests().await;
+        state.scrub_policy = Arc::new(ScrubPolicy::recommended());
+
+        let update = serde_json::json!({ "count": 3, "password": "hunter2", "note": "mail ada@example.com" });
+        let revision = apply_state_update(&state, update).await.unwrap();
+
+        let history = state.versions.lock().await;
+        assert_eq!(history.state_revision, revision);
+        assert_eq!(
+            history.current_state,
+            Some(serde_json::json!({ "count": 3, "note": "mail [REDACTED:email]" }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_synced_state_is_scrubbed_rather_than_refused() {
+        let mut state = AppState::for_tests().await;
+        state.scrub_policy = Arc::new(ScrubPolicy::recommended());
+        state.versions = Arc::new(Mutex::new(VersionHistory::new().with_crdt(true)));
+        let mut client = CrdtDoc::new("client");
+        let ops = client.update(&serde_json::json!({ "count": 3, "password": "hunter2" })).unwrap();
+
+        let reply = merge_sync(&state, SyncMessage { clock: Clock::default(), ops }).await.unwrap();
+
+        let expected = serde_json::json!({ "count": 3 });
+        assert_eq!(state.versions.lock().await.current_state, Some(expected.clone()));
+        client.sync(reply).unwrap();
+        assert_eq!(client.value(), expected);
+    }
+
+    #[test]
+    fn test_slots_must_embed_known_components() {
+        let chart = CatalogEntry {
+            id: ComponentId(1),
+            version: SemVer::INITIAL,
+            manifest: ComponentManifest::new("chart", "Sales chart"),
+            description: None,
+            thumbnail: None,
+        };
+        let dashboard = ComponentManifest::new("dashboard", "").with_slot("main", "chart");
+        assert!(check_slots(&dashboard, std::slice::from_ref(&chart)).is_ok());
+
+        let dashboard = dashboard.with_slot("side", "filters");
+        let refused = check_slots(&dashboard, &[chart]).unwrap_err();
+        assert!(refused.to_string().contains("'filters'"));
+        assert_eq!(refused.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_descriptions_go_to_the_rendered_versions_component() {
+        let state = AppState::for_tests().await;
+        let chart = ComponentManifest::new("chart", "Sales chart");
+        let wasm = wat::parse_str(RENDERABLE).unwrap();
+        register_component(&state, chart.clone(), &wasm, Provenance::default(), Vec::new()).await.unwrap();
+        let version = add_test_version(&state, chart, RENDERABLE, true).await;
+        let description = ComponentDescription {
+            exports: vec!["render".to_string()],
+            ..ComponentDescription::default()
+        };
+
+        let request = DescribeRequest {
+            component: None,
+            version_id: Some(version),
+            description: description.clone(),
+        };
+        assert!(describe_component(State(state.clone()), Json(request)).await.unwrap().success);
+        let catalog = state.registry.lock().await.catalog();
+        let chart = catalog.iter().find(|entry| entry.manifest.name == "chart").unwrap();
+        assert_eq!(chart.description, Some(description.clone()));
+
+        let unnamed = DescribeRequest {
+            component: None,
+            version_id: None,
+            description,
+        };
+        let refused = describe_component(State(state), Json(unnamed)).await.err().unwrap();
+        assert_eq!(refused.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    async fn last_audit(state: &AppState) -> AuditEntry {
+        state.audit_log.lock().await.last().cloned().expect("an audit entry")
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_activation_of_missing_version_fails() {
+        let state = AppState::for_tests().await;
+
+        let status = activate_scheduled(&state, &scheduled(7, Utc::now())).await;
+
+        assert_eq!(status, ActivationStatus::Failed);
+        let audit = last_audit(&state).await;
+        assert_eq!((audit.outcome.as_str(), audit.version_id), ("failed", Some(7)));
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_activation_registers_the_stored_manifest() {
+        let state = AppState::for_tests().await;
+        let header = ComponentManifest::new("header", "Page header");
+        let wasm = wat::parse_str(r#"(module (func (export "render")) (func (export "title")))"#).unwrap();
+        register_component(&state, header, &wasm, Provenance::default(), Vec::new()).await.unwrap();
+        let manifest = ComponentManifest::new("page", "A page").with_slot("top", "header");
+        let id = add_test_version(&state, manifest, RENDERABLE, false).await;
+
+        let status = activate_scheduled(&state, &scheduled(id, Utc::now())).await;
+
+        assert_eq!(status, ActivationStatus::Activated);
+        assert_eq!(state.versions.lock().await.current_index, id);
+        let registry = state.registry.lock().await;
+        let page = registry.find_by_name("page").expect("page is registered under its own name");
+        assert_eq!(registry.manifest(&page).unwrap().slots[0].component, "header");
+        assert!(registry.find_by_name("main").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_scheduled_version_rolls_back() {
+        let state = AppState::for_tests().await;
+        let good = add_test_version(&state, ComponentManifest::new("main", "v1"), RENDERABLE, true).await;
+        let bad = add_test_version(&state, ComponentManifest::new("main", "v2"), "(module)", false).await;
+
+        let status = activate_scheduled(&state, &scheduled(bad, Utc::now())).await;
+
+        assert_eq!(status, ActivationStatus::RolledBack);
+        assert_eq!(state.versions.lock().await.current_index, good);
+        let audit = last_audit(&state).await;
+        assert_eq!(audit.outcome, "rolled_back");
+        assert!(audit.detail.contains("render()"), "{}", audit.detail);
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_only_runs_due_activations() {
+        let state = AppState::for_tests().await;
+        add_test_version(&state, ComponentManifest::new("main", "v1"), RENDERABLE, true).await;
+        let due = add_test_version(&state, ComponentManifest::new("main", "v2"), RENDERABLE, false).await;
+        let later = add_test_version(&state, ComponentManifest::new("main", "v3"), RENDERABLE, false).await;
+        state.schedule.lock().await.extend([
+            scheduled(due, Utc::now() - chrono::Duration::seconds(1)),
+            scheduled(later, Utc::now() + chrono::Duration::hours(1)),
+        ]);
+
+        run_due_activations(&state).await;
+
+        let statuses: Vec<_> = state.schedule.lock().await.iter().map(|a| a.status).collect();
+        assert_eq!(statuses, [ActivationStatus::Activated, ActivationStatus::Pending]);
+        assert_eq!(state.versions.lock().await.current_index, due);
+    }
+
+    #[test]
+    fn test_unknown_components_are_not_found() {
+        let registry = ComponentRegistry::new();
+
+        let missing = find_component(&registry, "chart").unwrap_err();
+
+        assert_eq!(missing.to_string(), "Component 'chart' not found");
+        assert_eq!(missing.into_response().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_flags_parse_as_booleans() {
+        assert_eq!(parse_flag("true"), Some(true));
+        assert_eq!(parse_flag(" ON "), Some(true));
+        assert_eq!(parse_flag("1"), Some(true));
+        assert_eq!(parse_flag("false"), Some(false));
+        assert_eq!(parse_flag("0"), Some(false));
+        assert_eq!(parse_flag(""), Some(false));
+        assert_eq!(parse_flag("maybe"), None);
+    }
+
+    #[test]
+    fn test_numbers_that_do_not_parse_are_errors() {
+        assert_eq!(parse_number::<usize>("MORPHEUS_MAX_WASM_GROWTH", "4096").unwrap(), Some(4096));
+        assert_eq!(parse_number::<usize>("MORPHEUS_MAX_WASM_GROWTH", " 10 ").unwrap(), Some(10));
+        assert_eq!(parse_number::<usize>("MORPHEUS_MAX_WASM_GROWTH", "").unwrap(), None);
+        let error = parse_number::<usize>("MORPHEUS_MAX_WASM_GROWTH", "10%").unwrap_err();
+        assert_eq!(error.to_string(), "MORPHEUS_MAX_WASM_GROWTH must be a whole number, not '10%'");
+        assert!(parse_number::<usize>("MORPHEUS_MAX_COMPLEXITY", "-1").is_err());
+    }
+}
@@ -0,0 +1,315 @@
+//! Deterministic stand-in for the AI.
+//!
+//! With `MORPHEUS_MOCK_AI` set, [`crate::complete`] answers from a
+//! [`MockGenerator`] instead of calling OpenRouter, so the whole
+//! generate → compile → reload pipeline runs in CI and in demos without an
+//! API key, and always produces the same code for the same request.
+//!
+//! Components are filled into a template that renders the request. A
+//! request containing [`SEED_COMPILE_ERROR`] first gets code that fails to
+//! compile, then the fixed code when the compile error is fed back, so the
+//! retry loop can be exercised too. Planning requests get a two-step plan:
+//! a panel, and a page embedding it. Canned responses, matched by a
+//! substring of the request, take precedence over the templates.
+
+use crate::planner::{self, Plan, PlanStep, StepAction};
+use crate::Message;
+use morpheus_core::manifest::SlotDecl;
+use serde::Deserialize;
+
+/// Put this in a prompt to get code that fails to compile on the first try
+pub const SEED_COMPILE_ERROR: &str = "[mock:compile-error]";
+
+/// Marks the line of a seeded compile error, which fixes remove
+const SEEDED_LINE: &str = "// mock: seeded compile error";
+
+/// Longest request text put into a template
+const MAX_TITLE_CHARS: usize = 80;
+
+/// A fixed response for requests containing some text
+#[derive(Debug, Clone, Deserialize)]
+pub struct CannedResponse {
+    /// Text the user's request must contain
+    pub contains: String,
+    /// The AI's reply, usually a ```rust block
+    pub response: String,
+}
+
+/// Answers AI requests without a model
+#[derive(Debug, Clone, Default)]
+pub struct MockGenerator {
+    responses: Vec<CannedResponse>,
+}
+
+impl MockGenerator {
+    /// A generator that only uses templates
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read `MORPHEUS_MOCK_AI`: unset disables the mock; `1` or `true`
+    /// uses templates only; anything else is a JSON file of canned responses
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let Ok(value) = std::env::var("MORPHEUS_MOCK_AI") else {
+            return Ok(None);
+        };
+        match value.as_str() {
+            "" | "1" | "true" => Ok(Some(Self::new())),
+            path => {
+                let responses: Vec<CannedResponse> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+                let mock = responses
+                    .into_iter()
+                    .fold(Self::new(), |mock, canned| mock.with_response(canned.contains, canned.response));
+                Ok(Some(mock))
+            }
+        }
+    }
+
+    /// Reply with `response` to requests containing `contains`; earlier
+    /// canned responses win
+    pub fn with_response(mut self, contains: impl Into<String>, response: impl Into<String>) -> Self {
+        self.responses.push(CannedResponse {
+            contains: contains.into(),
+            response: response.into(),
+        });
+        self
+    }
+
+    /// The reply to a conversation
+    pub fn respond(&self, messages: &[Message]) -> String {
+        let last = messages.last().map(|m| m.content.as_str()).unwrap_or_default();
+
+        if last.starts_with("Here is a diff") {
+            return "- The component was regenerated (mock explanation)".to_string();
+        }
+        // A compile error fed back: fix the previous reply
+        if last.starts_with("That code failed to compile") {
+            if let Some(previous) = messages.iter().rev().find(|m| m.role == "assistant") {
+                let fixed: Vec<&str> = previous.content.lines().filter(|line| !line.contains(SEEDED_LINE)).collect();
+                return rust_block(&fixed.join("\n"));
+            }
+        }
+
+        let request = user_request(messages);
+        if let Some(canned) = self.responses.iter().find(|r| request.contains(&r.contains)) {
+            return canned.response.clone();
+        }
+
+        let system = messages.first().map(|m| m.content.as_str()).unwrap_or_default();
+        let title = title(request);
+        if system.starts_with(planner::SYSTEM_PROMPT_START) {
+            return plan_template(&title);
+        }
+        if system.starts_with("You write backend logic") {
+            return rust_block(&headless_template(system, &title));
+        }
+        // Runtime errors are fixed by regenerating, without the seeded error
+        let seeded = request.contains(SEED_COMPILE_ERROR) && !last.contains("failed at runtime");
+        rust_block(&component_template(&title, seeded))
+    }
+}
+
+/// The user's request: the first user message after the system prompt
+fn user_request(messages: &[Message]) -> &str {
+    messages
+        .iter()
+        .skip(1)
+        .find(|m| m.role == "user")
+        .or(messages.first())
+        .map(|m| m.content.as_str())
+        .unwrap_or_default()
+}
+
+/// The first line of the request without the framing the server adds
+fn title(request: &str) -> String {
+    let request = request.strip_prefix("Create a WASM component: ").unwrap_or(request);
+    let request = request.strip_prefix("Plan this request: ").unwrap_or(request);
+    let request = request.rsplit("Change it: ").next().unwrap_or(request);
+    let line = request.lines().next().unwrap_or_default().replace(SEED_COMPILE_ERROR, "");
+    line.trim().chars().take(MAX_TITLE_CHARS).collect()
+}
+
+fn rust_block(code: &str) -> String {
+    format!("```rust\n{}\n```", code.trim_end())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A component rendering `title`, optionally with a seeded compile error
+fn component_template(title: &str, seeded: bool) -> String {
+    let seed = if seeded {
+        format!("    let _seeded: u32 = \"not a number\"; {}\n", SEEDED_LINE)
+    } else {
+        String::new()
+    };
+    format!(
+        r##"use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub fn render() -> String {{
+{}    r#"<div class="p-6 max-w-2xl mx-auto">
+    <h1 class="text-4xl font-bold text-gray-900 mb-4">{}</h1>
+    <p class="text-base text-gray-600">Generated offline by the mock AI.</p>
+</div>"#.to_string()
+}}
+
+#[wasm_bindgen]
+pub fn __morpheus_describe() -> String {{
+    r#"{{"exports": ["render"], "messages": [], "emits": [], "consumes": [], "state": {{}}}}"#.to_string()
+}}"##,
+        seed,
+        escape_html(title)
+    )
+}
+
+/// A page embedding a panel, both named after `title`
+fn plan_template(title: &str) -> String {
+    let slug: String = title
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| word.len() > 1)
+        .take(3)
+        .collect::<Vec<_>>()
+        .join("-");
+    let page = if slug.is_empty() { "page".to_string() } else { slug };
+    let panel = format!("{}-panel", page);
+    let plan = Plan {
+        steps: vec![
+            PlanStep {
+                component: panel.clone(),
+                action: StepAction::Create,
+                prompt: format!("The main panel of: {}", title),
+                depends_on: Vec::new(),
+                slots: Vec::new(),
+            },
+            PlanStep {
+                component: page,
+                action: StepAction::Create,
+                prompt: title.to_string(),
+                depends_on: Vec::new(),
+                slots: vec![SlotDecl {
+                    name: "panel".to_string(),
+                    component: panel,
+                }],
+            },
+        ],
+    };
+    serde_json::to_string_pretty(&plan).unwrap_or_default()
+}
+
+/// A headless handler or transform echoing its input
+fn headless_template(system: &str, title: &str) -> String {
+    if system.contains("fn transform") {
+        format!(
+            "fn transform(input: Value) -> Value {{\n    json!({{ \"mock\": {:?}, \"input\": input }})\n}}",
+            title
+        )
+    } else {
+        format!(
+            "fn handle(request: Request) -> Response {{\n    Response::json(200, &json!({{ \"mock\": {:?}, \"path\": request.path }}))\n}}",
+            title
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract_rust_code;
+    use morpheus_compiler::{Compiler, SubprocessCompiler};
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    fn generate(prompt: &str) -> Vec<Message> {
+        vec![
+            message("user", "You are a Rust expert generating simple WebAssembly components"),
+            message("user", &format!("Create a WASM component: {}\n\nUSER FEEDBACK: ...", prompt)),
+        ]
+    }
+
+    #[test]
+    fn test_components_are_deterministic_and_escaped() {
+        let mock = MockGenerator::new();
+        let reply = mock.respond(&generate("A <b>\"bold\"</b> counter"));
+
+        assert_eq!(reply, mock.respond(&generate("A <b>\"bold\"</b> counter")));
+        let code = extract_rust_code(&reply).unwrap();
+        assert!(code.contains("mb-4\">A &lt;b&gt;&quot;bold&quot;&lt;/b&gt; counter</h1>"));
+        assert!(code.contains("pub fn __morpheus_describe()"));
+        assert!(!code.contains("USER FEEDBACK"));
+    }
+
+    #[test]
+    fn test_seeded_compile_error_is_fixed_on_retry() {
+        let mock = MockGenerator::new();
+        let mut messages = generate("A counter [mock:compile-error]");
+
+        let broken = mock.respond(&messages);
+        assert!(broken.contains(SEEDED_LINE));
+        assert!(!broken.contains("[mock:"));
+
+        messages.push(message("assistant", &extract_rust_code(&broken).unwrap()));
+        messages.push(message("user", "That code failed to compile with this error:\n\nmismatched types\n\nFix it."));
+        let fixed = mock.respond(&messages);
+        assert!(!fixed.contains(SEEDED_LINE));
+        assert!(fixed.contains("pub fn render()"));
+    }
+
+    #[test]
+    fn test_canned_responses_headless_and_explanations() {
+        let mock = MockGenerator::new().with_response("weather", "```rust\n// canned\n```");
+        assert_eq!(mock.respond(&generate("A weather widget")), "```rust\n// canned\n```");
+
+        let headless = [
+            message("user", "You write backend logic for Morpheus...\n\nWrite `fn transform(input: Value) -> Value`"),
+            message("user", "Current code:\n\n```rust\n...\n```\n\nChange it: Double every number"),
+        ];
+        let code = extract_rust_code(&mock.respond(&headless)).unwrap();
+        assert!(code.starts_with("fn transform(input: Value) -> Value"));
+        assert!(code.contains("\"Double every number\""));
+
+        let explain = [message("user", "Here is a diff between two versions...")];
+        assert!(mock.respond(&explain).starts_with("- "));
+    }
+
+    #[test]
+    fn test_plans_embed_a_panel_in_a_page() {
+        let request = [
+            message("user", &planner::system_prompt(&[], &[])),
+            message("user", "Plan this request: A CRM page"),
+        ];
+        let plan = Plan::parse(&MockGenerator::new().respond(&request)).unwrap();
+
+        let order: Vec<_> = plan.ordered(&[]).unwrap().iter().map(|s| s.component.as_str()).collect();
+        assert_eq!(order, ["crm-page-panel", "crm-page"]);
+        assert_eq!(plan.steps[1].slots[0].component, "crm-page-panel");
+    }
+
+    #[tokio::test]
+    async fn test_mock_components_compile() {
+        let compiler = match SubprocessCompiler::new().await {
+            Ok(c) => c,
+            Err(_) => return,
+        };
+        if SubprocessCompiler::check_tools().is_err() {
+            return;
+        }
+
+        let mock = MockGenerator::new();
+        let code = extract_rust_code(&mock.respond(&generate("A counter"))).unwrap();
+        if let Err(e) = compiler.compile(&code).await {
+            // Needs the wasm32 target and network access for dependencies
+            println!("✗ Compilation failed: {}", e);
+        }
+    }
+}
@@ -0,0 +1,847 @@
+//! OpenAPI description of the HTTP API
+//!
+//! Request and response types derive `JsonSchema`, so the spec served at
+//! `GET /api/openapi.json` can't drift from the handlers. The TypeScript
+//! client in `public/morpheus-client.ts` is generated from the same spec;
+//! after changing an API type, regenerate it with
+//! `MORPHEUS_UPDATE_CLIENT=1 cargo test -p morpheus-complete`.
+
+use crate::{
+    ArtifactQuery, DebugStepRequest, DebugStepResponse, DeprecateRequest, DesignCommitRequest, DesignCommitResponse,
+    DesignPreviewResponse, DesignRefineRequest, DesignRefineResponse, DesignStartRequest, DesignStartResponse,
+    EditRequest, EmitEventRequest, EmitEventResponse, EventsQuery, FallbackResponse, FeedbackRequest, FeedbackResponse,
+    FixErrorRequest, ForkRequest, ForkResponse, GenerateRequest, GenerateResponse, GroupRollbackResponse,
+    HistoryResponse, LifecycleResponse, PatchQuery, PatchResponse, RenderQuery, RenderResponse, ReplayQuery,
+    ReplayResponse, RollbackGroup, RollbackRequest, RollbackResponse, SnapshotSizeStats, ThumbnailRequest,
+    UpdateStateRequest, UpdateStateResponse,
+};
+use crate::autonomous::{AutonomousRun, AutonomousStatus, TelemetryEvent, TelemetryReport};
+use crate::crashes::{Crash, CrashResponse};
+use crate::experiments::{
+    ConversionRequest, ConversionResponse, ExperimentSummary, StartExperimentRequest, WinnerRequest,
+};
+use crate::headless::{
+    HeadlessGenerateRequest, HeadlessGenerateResponse, HeadlessRollbackRequest, HeadlessSummary, HeadlessVersion,
+};
+use crate::fewshot::ExamplesStatus;
+use crate::host_apis::{
+    HostApiRequest, HostPermissions, LocateRequest, LocateResult, NotifyRequest, NotifyResult, PermissionsQuery,
+};
+use crate::i18n::{TranslationsOverview, UpdateTranslationsRequest};
+use crate::jobs::Job;
+use crate::limits::LimitsStatus;
+use crate::logs::{LogRecord, LogsQuery};
+use crate::preload::{PreloadManifest, PreloadQuery};
+use crate::overview::{Overview, OverviewQuery};
+use crate::sharing::{ShareLink, ShareRequest, SpectateQuery, SpectatorView};
+use crate::planner::{PlanRequest, PlanResponse};
+use crate::theme::{SetThemeRequest, ThemesResponse};
+use morpheus_core::artifact::Artifact;
+use morpheus_core::bundle::BundleManifest;
+use morpheus_core::component::ComponentMetadata;
+use morpheus_core::crash::CrashReport;
+use morpheus_core::events::DomainEvent;
+use morpheus_core::fallback::FallbackView;
+use morpheus_core::feedback::Feedback;
+use morpheus_core::i18n::MessageCatalog;
+use morpheus_core::profiler::{RenderProfile, RenderSample};
+use morpheus_core::stats::CallSample;
+use morpheus_core::theme::{ActiveTheme, Theme};
+use morpheus_core::thumbnail::Thumbnail;
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// Media types the state endpoints accept and return
+const STATE_MEDIA_TYPES: [&str; 3] = ["application/json", "application/msgpack", "application/cbor"];
+
+/// Build the OpenAPI document
+pub fn spec() -> Value {
+    let mut api = Api::new();
+
+    api.get("/api/components", "listComponents", "Components", "List loaded components")
+        .returns::<Vec<ComponentMetadata>>();
+    api.post("/api/components/stats", "reportCalls", "Components", "Record export calls timed by a client")
+        .body::<Vec<CallSample>>()
+        .returns::<Vec<ComponentMetadata>>();
+    api.get(
+        "/api/components/{name}/render",
+        "renderComponent",
+        "Components",
+        "What to render for a component, honoring its feature flag",
+    )
+    .path::<String>("name")
+    .query::<RenderQuery>()
+    .returns::<RenderResponse>();
+    api.post(
+        "/api/components/{name}/fallback",
+        "setComponentFallback",
+        "Components",
+        "Declare the HTML or component clients render if a component fails to load",
+    )
+    .path::<String>("name")
+    .body::<FallbackView>()
+    .returns::<FallbackResponse>();
+    api.delete(
+        "/api/components/{name}/fallback",
+        "clearComponentFallback",
+        "Components",
+        "Remove a component's fallback, so clients show a default error message",
+    )
+    .path::<String>("name")
+    .returns::<FallbackResponse>();
+    api.get(
+        "/api/preload",
+        "getPreload",
+        "Components",
+        "What to render for every active component, with fetch priorities, to load before the first render",
+    )
+    .query::<PreloadQuery>()
+    .returns::<PreloadManifest>();
+    api.get(
+        "/api/components/{name}/artifact",
+        "getComponentArtifact",
+        "Components",
+        "A component's build for the browser or a server-side harness",
+    )
+    .path::<String>("name")
+    .query::<ArtifactQuery>()
+    .returns::<Artifact>();
+    api.get(
+        "/api/components/{name}/lifecycle",
+        "getComponentLifecycle",
+        "Components",
+        "Whether a component is deprecated, and what still depends on it",
+    )
+    .path::<String>("name")
+    .returns::<LifecycleResponse>();
+    api.post(
+        "/api/components/{name}/lifecycle",
+        "deprecateComponent",
+        "Components",
+        "Deprecate a component, optionally scheduling its retirement",
+    )
+    .path::<String>("name")
+    .body::<DeprecateRequest>()
+    .returns::<LifecycleResponse>();
+    api.post(
+        "/api/components/{name}/retire",
+        "retireComponent",
+        "Components",
+        "Retire a component nothing depends on any more",
+    )
+    .path::<String>("name")
+    .returns::<LifecycleResponse>();
+    api.post(
+        "/api/components/{name}/fork",
+        "forkComponent",
+        "Components",
+        "Copy a component under a new name, with its source, permissions and state shape",
+    )
+    .path::<String>("name")
+    .body::<ForkRequest>()
+    .returns::<ForkResponse>();
+
+    api.get("/api/experiments", "listExperiments", "Experiments", "A/B experiments and their metrics")
+        .returns::<Vec<ExperimentSummary>>();
+    api.post("/api/experiments", "startExperiment", "Experiments", "Split a component's sessions between two versions")
+        .body::<StartExperimentRequest>()
+        .returns::<ExperimentSummary>();
+    api.post(
+        "/api/experiments/{component}/conversion",
+        "recordConversion",
+        "Experiments",
+        "Count a conversion for a session's variant",
+    )
+    .path::<String>("component")
+    .body::<ConversionRequest>()
+    .returns::<ConversionResponse>();
+    api.post(
+        "/api/experiments/{component}/winner",
+        "declareWinner",
+        "Experiments",
+        "End an experiment; the winner becomes the live version",
+    )
+    .path::<String>("component")
+    .body::<WinnerRequest>()
+    .returns::<ExperimentSummary>();
+
+    api.get("/api/history", "getHistory", "Versions", "Version history and the current state")
+        .returns::<HistoryResponse>();
+    api.post("/api/rollback", "rollback", "Versions", "Make an earlier version current and restore its state")
+        .body::<RollbackRequest>()
+        .returns::<RollbackResponse>();
+    api.post("/api/share", "createShare", "Sharing", "Create a read-only link to the live component")
+        .body::<ShareRequest>()
+        .returns::<ShareLink>();
+    api.get("/api/share", "listShares", "Sharing", "List share links")
+        .returns::<Vec<ShareLink>>();
+    api.delete("/api/share/{token}", "revokeShare", "Sharing", "Revoke a share link")
+        .path::<String>("token")
+        .returns::<Value>();
+    api.get("/api/spectate", "spectate", "Sharing", "The live version and state, as a spectator sees them")
+        .query::<SpectateQuery>()
+        .returns::<SpectatorView>();
+    api.get("/api/groups", "listGroups", "Versions", "Versions committed together, newest first")
+        .returns::<Vec<RollbackGroup>>();
+    api.post("/api/groups/{id}/rollback", "rollbackGroup", "Versions", "Roll back every version of a group at once")
+        .path::<usize>("id")
+        .returns::<GroupRollbackResponse>();
+    api.get(
+        "/api/versions/{id}/patch",
+        "getVersionPatch",
+        "Versions",
+        "A version's WASM as a patch against another version",
+    )
+    .path::<usize>("id")
+    .query::<PatchQuery>()
+    .returns::<PatchResponse>();
+    api.get(
+        "/api/versions/{id}/bundle",
+        "getVersionBundle",
+        "Versions",
+        "A version's WASM module, JS glue and assets, with integrity hashes",
+    )
+    .path::<usize>("id")
+    .returns::<BundleManifest>();
+    api.post(
+        "/api/versions/{id}/edit",
+        "editVersion",
+        "Versions",
+        "Compile hand-edited source of a version and save it as a new version",
+    )
+    .path::<usize>("id")
+    .body::<EditRequest>()
+    .returns::<GenerateResponse>();
+    api.post(
+        "/api/versions/{id}/thumbnail",
+        "setVersionThumbnail",
+        "Versions",
+        "Store a preview of a version, as rendered",
+    )
+    .path::<usize>("id")
+    .body::<ThumbnailRequest>()
+    .returns::<Thumbnail>();
+    api.post("/api/feedback", "submitFeedback", "Versions", "Rate a version (defaults to the live one)")
+        .body::<FeedbackRequest>()
+        .returns::<FeedbackResponse>();
+    api.get("/api/versions/{id}/feedback", "getVersionFeedback", "Versions", "Ratings recorded on a version")
+        .path::<usize>("id")
+        .returns::<Vec<Feedback>>();
+
+    api.post("/api/state", "updateState", "State", "Replace the current component state")
+        .body::<UpdateStateRequest>()
+        .binary()
+        .returns::<UpdateStateResponse>();
+    api.get("/api/state/metrics", "getSnapshotMetrics", "State", "State snapshot sizes by component")
+        .returns::<BTreeMap<String, SnapshotSizeStats>>();
+    api.post("/api/events", "emitEvent", "State", "Record a domain event (event-sourced state mode)")
+        .body::<EmitEventRequest>()
+        .binary()
+        .returns::<EmitEventResponse>();
+    api.get("/api/events/{component}", "listEvents", "State", "A component's events after a sequence number")
+        .path::<String>("component")
+        .query::<EventsQuery>()
+        .binary()
+        .returns::<Vec<DomainEvent>>();
+    api.get(
+        "/api/events/{component}/replay",
+        "replayEvents",
+        "State",
+        "Rebuild a component's state by replaying its events",
+    )
+    .path::<String>("component")
+    .query::<ReplayQuery>()
+    .binary()
+    .returns::<ReplayResponse>();
+    api.get("/api/debug/step", "getDebugPosition", "State", "Where the time-travel cursor is")
+        .binary()
+        .returns::<DebugStepResponse>();
+    api.post("/api/debug/step", "debugStep", "State", "Move the time-travel cursor through state history")
+        .body::<DebugStepRequest>()
+        .binary()
+        .returns::<DebugStepResponse>();
+
+    api.post("/api/generate", "generate", "Generation", "Generate a component from a prompt, waiting for the result")
+        .body::<GenerateRequest>()
+        .returns::<GenerateResponse>();
+    api.post(
+        "/api/plan",
+        "plan",
+        "Generation",
+        "Split a request into several component changes and commit them together",
+    )
+    .body::<PlanRequest>()
+    .returns::<PlanResponse>();
+    api.post("/api/jobs", "createJob", "Generation", "Queue a generation and return its job right away")
+        .body::<GenerateRequest>()
+        .returns::<Job>();
+    api.get("/api/jobs/{id}", "getJob", "Generation", "A generation job's status, and its outcome once finished")
+        .path::<String>("id")
+        .returns::<Job>();
+    api.delete("/api/jobs/{id}", "cancelJob", "Generation", "Cancel a job that hasn't started saving")
+        .path::<String>("id")
+        .returns::<Job>();
+    api.post("/api/fix", "fixRuntimeError", "Generation", "Regenerate a component that failed at runtime")
+        .body::<FixErrorRequest>()
+        .returns::<GenerateResponse>();
+    api.post("/api/design/start", "startDesign", "Generation", "Start an interactive design session")
+        .body::<DesignStartRequest>()
+        .returns::<DesignStartResponse>();
+    api.post("/api/design/refine", "refineDesign", "Generation", "Refine the current draft with feedback")
+        .body::<DesignRefineRequest>()
+        .returns::<DesignRefineResponse>();
+    api.post("/api/design/commit", "commitDesign", "Generation", "Commit the current draft as a new version")
+        .body::<DesignCommitRequest>()
+        .returns::<DesignCommitResponse>();
+    api.get("/api/design/preview", "getDesignPreview", "Generation", "The design session's current draft")
+        .returns::<DesignPreviewResponse>();
+    api.post("/api/design/cancel", "cancelDesign", "Generation", "Discard the design session")
+        .returns::<Value>();
+    api.get("/api/examples", "getExamples", "Generation", "Few-shot examples and compile outcomes with and without them")
+        .returns::<ExamplesStatus>();
+
+    api.get("/api/headless", "listHeadless", "Headless", "List headless (server-side) components")
+        .returns::<Vec<HeadlessSummary>>();
+    api.post("/api/headless/generate", "generateHeadless", "Headless", "Generate or modify a headless component")
+        .body::<HeadlessGenerateRequest>()
+        .returns::<HeadlessGenerateResponse>();
+    api.get("/api/headless/{name}/history", "getHeadlessHistory", "Headless", "A headless component's versions")
+        .path::<String>("name")
+        .returns::<Vec<HeadlessVersion>>();
+    api.post(
+        "/api/headless/{name}/rollback",
+        "rollbackHeadless",
+        "Headless",
+        "Make an earlier version of a headless component live",
+    )
+    .path::<String>("name")
+    .body::<HeadlessRollbackRequest>()
+    .returns::<HeadlessSummary>();
+    api.post("/api/headless/{name}/transform", "transformHeadless", "Headless", "Run a transform component")
+        .path::<String>("name")
+        .body::<Value>()
+        .returns::<Value>();
+
+    api.post("/api/telemetry", "reportTelemetry", "Autonomous", "Report a runtime error, slow render or feedback")
+        .body::<TelemetryReport>()
+        .returns::<TelemetryEvent>();
+    api.get("/api/telemetry", "listTelemetry", "Autonomous", "Recent telemetry events")
+        .returns::<Vec<TelemetryEvent>>();
+    api.post("/api/profiler", "reportRenders", "Autonomous", "Record render timings from a client")
+        .body::<Vec<RenderSample>>()
+        .returns::<Vec<RenderProfile>>();
+    api.get("/api/profiler", "listRenderProfiles", "Autonomous", "How each component renders, slowest first")
+        .returns::<Vec<RenderProfile>>();
+    api.post("/api/crash", "reportCrash", "Autonomous", "Report a component panic and roll back with its last state")
+        .body::<CrashReport>()
+        .returns::<CrashResponse>();
+    api.get("/api/crashes", "listCrashes", "Autonomous", "Recent component panics, newest first")
+        .returns::<Vec<Crash>>();
+    api.get("/api/autonomous", "getAutonomousStatus", "Autonomous", "Autonomous mode's policy and recent runs")
+        .returns::<AutonomousStatus>();
+    api.post("/api/autonomous/run", "runAutonomous", "Autonomous", "Turn pending telemetry into a proposal now")
+        .returns::<AutonomousRun>();
+
+    api.get("/api/permissions", "getPermissions", "Permissions", "Which clipboard, file, notification and location imports a version may call")
+        .query::<PermissionsQuery>()
+        .returns::<HostPermissions>();
+    api.post(
+        "/api/permissions/requests",
+        "recordHostApiRequest",
+        "Permissions",
+        "Record a host API call the browser allowed or refused",
+    )
+    .body::<HostApiRequest>()
+    .returns::<Value>();
+    api.post(
+        "/api/notifications",
+        "notify",
+        "Permissions",
+        "Check a component's notification against its permission and rate limit",
+    )
+    .body::<NotifyRequest>()
+    .returns::<NotifyResult>();
+    api.post(
+        "/api/geolocation",
+        "locate",
+        "Permissions",
+        "Coarsen a reported position to the component's granted precision, with caching and rate limits",
+    )
+    .body::<LocateRequest>()
+    .returns::<LocateResult>();
+
+    api.get("/api/i18n", "getTranslations", "Translations", "Translation keys in use, and what each locale lacks")
+        .returns::<TranslationsOverview>();
+    api.get("/api/i18n/{locale}", "getLocale", "Translations", "Every message in a locale, with fallbacks applied")
+        .path::<String>("locale")
+        .returns::<MessageCatalog>();
+    api.post("/api/i18n/{locale}", "updateLocale", "Translations", "Add or change a locale's translations")
+        .path::<String>("locale")
+        .body::<UpdateTranslationsRequest>()
+        .returns::<MessageCatalog>();
+
+    api.get("/api/themes", "listThemes", "Themes", "Themes on offer, and which one is active")
+        .returns::<ThemesResponse>();
+    api.post("/api/themes", "registerTheme", "Themes", "Add a theme, or replace the one with its name")
+        .body::<Theme>()
+        .returns::<ThemesResponse>();
+    api.post("/api/themes/active", "setActiveTheme", "Themes", "Switch themes, restyling mounted components")
+        .body::<SetThemeRequest>()
+        .returns::<ActiveTheme>();
+
+    api.get("/api/health", "health", "Server", "Health check").returns::<Value>();
+    api.get("/api/limits", "getLimits", "Server", "Rate limits and today's AI usage against the budget")
+        .returns::<LimitsStatus>();
+    api.get("/api/logs", "getLogs", "Server", "Recent server logs, optionally for one component or job")
+        .query::<LogsQuery>()
+        .returns::<Vec<LogRecord>>();
+    api.get(
+        "/api/overview",
+        "getOverview",
+        "Server",
+        "Components, live versions, pending proposals, recent failures, AI spend and queue depth",
+    )
+    .query::<OverviewQuery>()
+    .returns::<Overview>();
+
+    api.finish()
+}
+
+/// Collects operations and the schemas they reference
+struct Api {
+    generator: SchemaGenerator,
+    paths: Map<String, Value>,
+}
+
+/// An operation being described
+struct Operation<'a> {
+    api: &'a mut Api,
+    method: &'static str,
+    path: &'static str,
+    fields: Map<String, Value>,
+    parameters: Vec<Value>,
+    media_types: &'static [&'static str],
+}
+
+impl Api {
+    fn new() -> Self {
+        Self {
+            generator: SchemaSettings::openapi3().into_generator(),
+            paths: Map::new(),
+        }
+    }
+
+    fn get(&mut self, path: &'static str, id: &str, tag: &str, summary: &str) -> Operation<'_> {
+        self.operation("get", path, id, tag, summary)
+    }
+
+    fn post(&mut self, path: &'static str, id: &str, tag: &str, summary: &str) -> Operation<'_> {
+        self.operation("post", path, id, tag, summary)
+    }
+
+    fn delete(&mut self, path: &'static str, id: &str, tag: &str, summary: &str) -> Operation<'_> {
+        self.operation("delete", path, id, tag, summary)
+    }
+
+    fn operation(&mut self, method: &'static str, path: &'static str, id: &str, tag: &str, summary: &str) -> Operation<'_> {
+        let mut fields = Map::new();
+        fields.insert("operationId".to_string(), json!(id));
+        fields.insert("tags".to_string(), json!([tag]));
+        fields.insert("summary".to_string(), json!(summary));
+        Operation {
+            api: self,
+            method,
+            path,
+            fields,
+            parameters: Vec::new(),
+            media_types: &STATE_MEDIA_TYPES[..1],
+        }
+    }
+
+    fn schema<T: JsonSchema>(&mut self) -> Value {
+        openapi_schema(serde_json::to_value(self.generator.subschema_for::<T>()).unwrap_or_default())
+    }
+
+    fn finish(self) -> Value {
+        let mut schemas: Map<String, Value> = self
+            .generator
+            .definitions()
+            .iter()
+            .map(|(name, schema)| (name.clone(), openapi_schema(serde_json::to_value(schema).unwrap_or_default())))
+            .collect();
+        schemas.insert(
+            "ErrorResponse".to_string(),
+            json!({
+                "description": "Body of every error response",
+                "type": "object",
+                "required": ["error"],
+                "properties": { "error": { "type": "string" } }
+            }),
+        );
+        json!({
+            "openapi": "3.0.3",
+            "info": {
+                "title": "Morpheus",
+                "description": "Generate, version and run AI-written WASM components",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "paths": self.paths,
+            "components": { "schemas": schemas },
+        })
+    }
+}
+
+impl Operation<'_> {
+    /// Add a path parameter
+    fn path<T: JsonSchema>(mut self, name: &str) -> Self {
+        let schema = self.api.schema::<T>();
+        self.parameters.push(json!({ "name": name, "in": "path", "required": true, "schema": schema }));
+        self
+    }
+
+    /// Add the fields of a query struct as query parameters
+    fn query<T: JsonSchema>(mut self) -> Self {
+        let reference = self.api.schema::<T>();
+        let name = schema_name(&reference).unwrap_or_default();
+        let definition = self.api.generator.definitions().get(name).cloned();
+        let definition = openapi_schema(serde_json::to_value(definition).unwrap_or_default());
+        let required = definition["required"].as_array().cloned().unwrap_or_default();
+        if let Some(properties) = definition["properties"].as_object() {
+            for (field, schema) in properties {
+                self.parameters.push(json!({
+                    "name": field,
+                    "in": "query",
+                    "required": required.contains(&json!(field)),
+                    "schema": schema,
+                }));
+            }
+        }
+        self.fields.insert("x-query-type".to_string(), json!(name));
+        self
+    }
+
+    /// Document the request body
+    fn body<T: JsonSchema>(mut self) -> Self {
+        let schema = self.api.schema::<T>();
+        self.fields.insert(
+            "requestBody".to_string(),
+            json!({ "required": true, "content": content(self.media_types, &schema) }),
+        );
+        self
+    }
+
+    /// Also accept and return MessagePack and CBOR
+    fn binary(mut self) -> Self {
+        self.media_types = &STATE_MEDIA_TYPES;
+        if let Some(body) = self.fields.get_mut("requestBody") {
+            let schema = body["content"]["application/json"]["schema"].clone();
+            body["content"] = content(self.media_types, &schema);
+        }
+        self
+    }
+
+    /// Document the success response and add the operation to the spec
+    fn returns<T: JsonSchema>(mut self) {
+        let schema = self.api.schema::<T>();
+        self.fields.insert(
+            "responses".to_string(),
+            json!({
+                "200": { "description": "Success", "content": content(self.media_types, &schema) },
+                "default": {
+                    "description": "Error",
+                    "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ErrorResponse" } } }
+                }
+            }),
+        );
+        if !self.parameters.is_empty() {
+            self.fields.insert("parameters".to_string(), Value::Array(self.parameters));
+        }
+        let item = self.api.paths.entry(self.path).or_insert_with(|| json!({}));
+        item[self.method] = Value::Object(self.fields);
+    }
+}
+
+fn content(media_types: &[&str], schema: &Value) -> Value {
+    media_types
+        .iter()
+        .map(|media_type| (media_type.to_string(), json!({ "schema": schema })))
+        .collect::<Map<_, _>>()
+        .into()
+}
+
+/// Name of the schema a `$ref` points to
+fn schema_name(schema: &Value) -> Option<&str> {
+    schema["$ref"].as_str()?.strip_prefix("#/components/schemas/")
+}
+
+/// Replace `true` ("anything") schemas, which OpenAPI 3.0 doesn't allow,
+/// with the equivalent `{}`
+fn openapi_schema(mut schema: Value) -> Value {
+    fn fix(value: &mut Value) {
+        match value {
+            Value::Bool(true) => *value = json!({}),
+            Value::Object(fields) => {
+                for (key, field) in fields.iter_mut() {
+                    match key.as_str() {
+                        "properties" | "definitions" => {
+                            if let Value::Object(children) = field {
+                                children.values_mut().for_each(fix);
+                            }
+                        }
+                        "items" | "additionalProperties" if field.is_object() || field == &Value::Bool(true) => {
+                            fix(field)
+                        }
+                        "allOf" | "anyOf" | "oneOf" => {
+                            if let Value::Array(children) = field {
+                                children.iter_mut().for_each(fix);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    fix(&mut schema);
+    schema
+}
+
+/// Generate a typed TypeScript client from the spec
+pub fn typescript_client(spec: &Value) -> String {
+    let mut out = String::from(
+        "// Generated from the Morpheus OpenAPI spec (GET /api/openapi.json). Do not edit.\n\
+         // Regenerate with `MORPHEUS_UPDATE_CLIENT=1 cargo test -p morpheus-complete`.\n",
+    );
+
+    if let Some(schemas) = spec["components"]["schemas"].as_object() {
+        for (name, schema) in schemas {
+            out.push('\n');
+            out.push_str(&doc_comment(schema, ""));
+            if schema["properties"].is_object() {
+                out.push_str(&format!("export interface {} {}\n", name, object_type(schema, "")));
+            } else {
+                out.push_str(&format!("export type {} = {};\n", name, ts_type(schema)));
+            }
+        }
+    }
+
+    out.push_str(CLIENT_PRELUDE);
+    if let Some(paths) = spec["paths"].as_object() {
+        for (path, item) in paths {
+            for method in ["get", "post", "delete"] {
+                if let Some(operation) = item.get(method) {
+                    out.push_str(&client_method(path, method, operation));
+                }
+            }
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+const CLIENT_PRELUDE: &str = r#"
+/** A non-2xx response from the server */
+export class MorpheusApiError extends Error {
+  constructor(
+    readonly status: number,
+    message: string,
+    readonly body: unknown,
+  ) {
+    super(message);
+  }
+}
+
+/** Typed client for the Morpheus HTTP API */
+export class MorpheusClient {
+  constructor(
+    private readonly baseUrl = "",
+    private readonly fetchImpl: typeof fetch = (input, init) => fetch(input, init),
+  ) {}
+
+  private async request<T>(method: string, path: string, query?: object, body?: unknown): Promise<T> {
+    let url = this.baseUrl + path;
+    if (query) {
+      const params = new URLSearchParams();
+      for (const [key, value] of Object.entries(query)) {
+        if (value !== undefined && value !== null) params.set(key, String(value));
+      }
+      const search = params.toString();
+      if (search) url += `?${search}`;
+    }
+    const response = await this.fetchImpl(url, {
+      method,
+      headers: body === undefined ? undefined : { "Content-Type": "application/json" },
+      body: body === undefined ? undefined : JSON.stringify(body),
+    });
+    const data = await response.json().catch(() => null);
+    if (!response.ok) {
+      throw new MorpheusApiError(response.status, data?.error ?? response.statusText, data);
+    }
+    return data as T;
+  }
+"#;
+
+fn client_method(path: &str, method: &str, operation: &Value) -> String {
+    let mut params = Vec::new();
+    let mut url = path.to_string();
+    for parameter in operation["parameters"].as_array().into_iter().flatten() {
+        if parameter["in"] == "path" {
+            let name = parameter["name"].as_str().unwrap_or_default();
+            params.push(format!("{}: {}", name, ts_type(&parameter["schema"])));
+            url = url.replace(&format!("{{{}}}", name), &format!("${{encodeURIComponent(String({}))}}", name));
+        }
+    }
+    if let Some(body) = operation["requestBody"]["content"]["application/json"].get("schema") {
+        params.push(format!("body: {}", ts_type(body)));
+    }
+    let mut query = "undefined";
+    if let Some(query_type) = operation["x-query-type"].as_str() {
+        let required = operation["parameters"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .any(|p| p["in"] == "query" && p["required"] == true);
+        params.push(format!("query{}: {}", if required { "" } else { "?" }, query_type));
+        query = "query";
+    }
+    let response = ts_type(&operation["responses"]["200"]["content"]["application/json"]["schema"]);
+    let body = if operation.get("requestBody").is_some() { ", body" } else { "" };
+    let query = if body.is_empty() && query == "undefined" { String::new() } else { format!(", {}", query) };
+    format!(
+        "\n  /** {} */\n  {}({}): Promise<{}> {{\n    return this.request(\"{}\", `{}`{}{});\n  }}\n",
+        operation["summary"].as_str().unwrap_or_default(),
+        operation["operationId"].as_str().unwrap_or_default(),
+        params.join(", "),
+        response,
+        method.to_uppercase(),
+        url,
+        query,
+        body,
+    )
+}
+
+fn doc_comment(schema: &Value, indent: &str) -> String {
+    match schema["description"].as_str() {
+        Some(description) => {
+            let description = description.split_whitespace().collect::<Vec<_>>().join(" ");
+            format!("{}/** {} */\n", indent, description)
+        }
+        None => String::new(),
+    }
+}
+
+fn object_type(schema: &Value, indent: &str) -> String {
+    let required = schema["required"].as_array().cloned().unwrap_or_default();
+    let mut out = String::from("{\n");
+    for (name, property) in schema["properties"].as_object().into_iter().flatten() {
+        let inner = format!("{}  ", indent);
+        out.push_str(&doc_comment(property, &inner));
+        let optional = if required.contains(&json!(name)) { "" } else { "?" };
+        out.push_str(&format!("{}{}{}: {};\n", inner, name, optional, ts_type(property)));
+    }
+    out.push_str(indent);
+    out.push('}');
+    out
+}
+
+/// TypeScript type for a JSON schema
+fn ts_type(schema: &Value) -> String {
+    let base = if let Some(name) = schema_name(schema) {
+        name.to_string()
+    } else if let Some(values) = schema["enum"].as_array() {
+        values.iter().map(Value::to_string).collect::<Vec<_>>().join(" | ")
+    } else if let Some(variants) = schema["oneOf"].as_array().or(schema["anyOf"].as_array()) {
+        variants.iter().map(ts_type).collect::<Vec<_>>().join(" | ")
+    } else if let Some([only]) = schema["allOf"].as_array().map(Vec::as_slice) {
+        ts_type(only)
+    } else {
+        match schema["type"].as_str() {
+            Some("string") => "string".to_string(),
+            Some("integer") | Some("number") => "number".to_string(),
+            Some("boolean") => "boolean".to_string(),
+            Some("array") => {
+                let items = ts_type(&schema["items"]);
+                if items.contains(' ') {
+                    format!("({})[]", items)
+                } else {
+                    format!("{}[]", items)
+                }
+            }
+            Some("object") if schema["properties"].is_object() => object_type(schema, ""),
+            Some("object") => match schema.get("additionalProperties") {
+                Some(values) if values.is_object() => format!("Record<string, {}>", ts_type(values)),
+                _ => "Record<string, unknown>".to_string(),
+            },
+            _ => "unknown".to_string(),
+        }
+    };
+    if schema["nullable"] == true && base != "unknown" {
+        format!("{} | null", base)
+    } else {
+        base
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refs(value: &Value, found: &mut Vec<String>) {
+        match value {
+            Value::Object(fields) => {
+                if let Some(name) = schema_name(value) {
+                    found.push(name.to_string());
+                }
+                fields.values().for_each(|v| refs(v, found));
+            }
+            Value::Array(items) => items.iter().for_each(|v| refs(v, found)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_spec_references_resolve() {
+        let spec = spec();
+        let mut found = Vec::new();
+        refs(&spec, &mut found);
+
+        assert!(found.contains(&"ComponentMetadata".to_string()));
+        for name in found {
+            assert!(spec["components"]["schemas"][&name].is_object(), "dangling $ref to {}", name);
+        }
+        let patch = &spec["paths"]["/api/versions/{id}/patch"]["get"];
+        assert_eq!(patch["parameters"][1], json!({
+            "name": "from",
+            "in": "query",
+            "required": true,
+            "schema": { "type": "integer", "format": "uint", "minimum": 0.0 }
+        }));
+        assert!(spec["paths"]["/api/state"]["post"]["requestBody"]["content"]["application/msgpack"].is_object());
+    }
+
+    #[test]
+    fn test_typescript_types() {
+        assert_eq!(ts_type(&json!({ "type": "array", "items": { "$ref": "#/components/schemas/Review" } })), "Review[]");
+        assert_eq!(ts_type(&json!({ "type": "string", "nullable": true })), "string | null");
+        assert_eq!(ts_type(&json!({ "type": "string", "enum": ["reject", "rebase"] })), "\"reject\" | \"rebase\"");
+        assert_eq!(ts_type(&json!({ "type": "object", "additionalProperties": { "type": "integer" } })), "Record<string, number>");
+        assert_eq!(ts_type(&json!({})), "unknown");
+    }
+
+    #[test]
+    fn test_checked_in_client_is_current() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/public/morpheus-client.ts");
+        let generated = typescript_client(&spec());
+        if std::env::var_os("MORPHEUS_UPDATE_CLIENT").is_some() {
+            std::fs::write(path, &generated).unwrap();
+        }
+        let checked_in = std::fs::read_to_string(path).unwrap_or_default();
+        assert!(
+            checked_in == generated,
+            "public/morpheus-client.ts is out of date; run MORPHEUS_UPDATE_CLIENT=1 cargo test -p morpheus-complete"
+        );
+    }
+}
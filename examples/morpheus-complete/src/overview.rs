@@ -0,0 +1,582 @@
+//! System overview for operator dashboards.
+//!
+//! `GET /api/overview` gathers what an operator checks first into one
+//! response: loaded components and the versions serving them, proposals
+//! waiting to go live, recent failures, today's AI spend and the generation
+//! queue. Each component comes with its execution statistics, so unused
+//! and slow components stand out. The last reload and the compile errors
+//! of the last failed generation are there for dev overlays.
+//! `?format=markdown` renders the same data as a short report, which also
+//! works as context for asking the AI how the system is doing.
+
+use crate::autonomous::{TelemetryEvent, TelemetryKind};
+use crate::jobs::{Job, JobStatus, QueueDepth};
+use crate::limits::LimitsStatus;
+use crate::{truncate, ActivationStatus, AppError, AppState, AuditEntry, ScheduledActivation, VersionHistory};
+use axum::extract::{Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use morpheus_core::component::Author;
+use morpheus_core::flags::FlagState;
+use morpheus_core::review::ReviewStatus;
+use morpheus_core::stats::ExecutionStats;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Most pending proposals and failures listed
+const MAX_ITEMS: usize = 20;
+
+/// Log prefix of a failed compile attempt
+const COMPILE_FAILED: &str = "❌ Compilation failed:\n";
+
+/// Audit outcomes that count as failures
+const FAILURE_OUTCOMES: [&str; 4] = ["failed", "blocked", "rejected", "rolled_back"];
+
+/// Query for `GET /api/overview`
+#[derive(Deserialize, JsonSchema)]
+pub struct OverviewQuery {
+    /// `json` (default) or `markdown`
+    pub format: Option<String>,
+}
+
+/// The system at a glance
+#[derive(Serialize, JsonSchema)]
+pub struct Overview {
+    pub generated_at: DateTime<Utc>,
+    /// Loaded components, by name
+    pub components: Vec<ComponentStatus>,
+    /// The live version
+    pub current_version: Option<VersionSummary>,
+    /// Versions that have never been live, newest first
+    pub pending_proposals: Vec<PendingProposal>,
+    /// Failed jobs, runtime errors and refused or failed operations, newest first
+    pub recent_failures: Vec<Failure>,
+    /// When a version last went live
+    pub last_reload: Option<DateTime<Utc>>,
+    /// The most recent generation that produced no version
+    pub last_failed_generation: Option<FailedGeneration>,
+    /// Rate limits and today's AI usage
+    pub ai: LimitsStatus,
+    /// Generation jobs waiting and running
+    pub queue: QueueDepth,
+}
+
+/// A loaded component
+#[derive(Serialize, JsonSchema)]
+pub struct ComponentStatus {
+    pub name: String,
+    /// Version serving it
+    pub version_id: Option<usize>,
+    pub loaded_at: String,
+    pub ai_generated: bool,
+    /// Feature flag, when one is set: `disabled` or `pinned to version N`
+    pub flag: Option<String>,
+    /// Calls, traps and call times since it was loaded
+    pub stats: ExecutionStats,
+}
+
+/// A version in brief
+#[derive(Serialize, JsonSchema)]
+pub struct VersionSummary {
+    pub id: usize,
+    pub component: String,
+    pub description: String,
+    pub author: Author,
+    pub created_at: DateTime<Utc>,
+    pub activated_at: Option<DateTime<Utc>>,
+}
+
+/// A version that has not gone live, and what it waits for
+#[derive(Serialize, JsonSchema)]
+pub struct PendingProposal {
+    #[serde(flatten)]
+    pub version: VersionSummary,
+    /// e.g. `review`, `guardrail override`, `scheduled for ...`; empty if
+    /// nothing stops activating it
+    pub waiting_for: Vec<String>,
+}
+
+/// Something that went wrong
+#[derive(Serialize, JsonSchema)]
+pub struct Failure {
+    pub at: DateTime<Utc>,
+    /// `job`, `runtime_error`, or the audited action (`activate`,
+    /// `autonomous_run`, `state_update`, ...)
+    pub source: String,
+    pub version_id: Option<usize>,
+    pub message: String,
+}
+
+/// A generation that produced no version, and why
+#[derive(Serialize, JsonSchema)]
+pub struct FailedGeneration {
+    pub job_id: String,
+    pub prompt: String,
+    pub at: DateTime<Utc>,
+    pub error: String,
+    /// Compiler output of each failed attempt, in order
+    pub compile_errors: Vec<String>,
+}
+
+/// Everything an overview is built from
+struct Inputs<'a> {
+    history: &'a VersionHistory,
+    schedule: &'a [ScheduledActivation],
+    jobs: &'a [Job],
+    audit: &'a [AuditEntry],
+    telemetry: &'a [TelemetryEvent],
+}
+
+impl VersionSummary {
+    fn of(version: &crate::ComponentVersion) -> Self {
+        Self {
+            id: version.id,
+            component: version.manifest.name.clone(),
+            description: truncate(&version.description, 120),
+            author: version.provenance.author,
+            created_at: version.created_at,
+            activated_at: version.activated_at,
+        }
+    }
+}
+
+/// The version serving `component`: the live one if it belongs to the
+/// component, otherwise the component's last activated version
+fn serving_version(history: &VersionHistory, component: &str) -> Option<usize> {
+    match history.get_current() {
+        Some(current) if current.manifest.name == component => Some(current.id),
+        _ => history
+            .versions
+            .iter()
+            .rev()
+            .find(|v| v.manifest.name == component && v.activated_at.is_some())
+            .map(|v| v.id),
+    }
+}
+
+fn pending_proposals(inputs: &Inputs) -> Vec<PendingProposal> {
+    let history = inputs.history;
+    history
+        .versions
+        .iter()
+        .rev()
+        .filter(|v| v.activated_at.is_none())
+        .take(MAX_ITEMS)
+        .map(|version| {
+            let mut waiting_for = Vec::new();
+            if history.require_review {
+                match version.review.status {
+                    ReviewStatus::Pending => waiting_for.push("review".to_string()),
+                    ReviewStatus::ChangesRequested => waiting_for.push("requested changes".to_string()),
+                    ReviewStatus::Approved => {}
+                }
+            }
+            if !version.guardrail_violations.is_empty() && version.guardrail_override.is_none() {
+                waiting_for.push("guardrail override".to_string());
+            }
+            for activation in inputs.schedule {
+                if activation.version_id == version.id && activation.status == ActivationStatus::Pending {
+                    waiting_for.push(format!("scheduled for {}", activation.activate_at.to_rfc3339()));
+                }
+            }
+            PendingProposal {
+                version: VersionSummary::of(version),
+                waiting_for,
+            }
+        })
+        .collect()
+}
+
+fn recent_failures(inputs: &Inputs) -> Vec<Failure> {
+    let jobs = inputs.jobs.iter().filter_map(|job| {
+        let message = match job.status {
+            JobStatus::Failed => job.error.clone(),
+            JobStatus::Done => job.result.as_ref().filter(|r| !r.success).map(|r| {
+                r.error.clone().unwrap_or_else(|| "Generation failed".to_string())
+            }),
+            _ => None,
+        }?;
+        Some(Failure {
+            at: job.finished_at.unwrap_or(job.created_at),
+            source: "job".to_string(),
+            version_id: None,
+            message: format!("{}: {}", truncate(&job.prompt, 60), message),
+        })
+    });
+    let audit = inputs
+        .audit
+        .iter()
+        .filter(|entry| FAILURE_OUTCOMES.contains(&entry.outcome.as_str()))
+        .map(|entry| Failure {
+            at: entry.timestamp,
+            source: entry.action.clone(),
+            version_id: entry.version_id,
+            message: format!("{}: {}", entry.outcome, entry.detail),
+        });
+    let runtime = inputs
+        .telemetry
+        .iter()
+        .filter(|event| event.kind == TelemetryKind::Error)
+        .map(|event| Failure {
+            at: event.received_at,
+            source: "runtime_error".to_string(),
+            version_id: event.version_id,
+            message: event.message.clone(),
+        });
+
+    let mut failures: Vec<Failure> = jobs.chain(audit).chain(runtime).collect();
+    failures.sort_by_key(|f| std::cmp::Reverse(f.at));
+    failures.truncate(MAX_ITEMS);
+    failures
+}
+
+/// The newest finished job that failed or gave up
+fn last_failed_generation(jobs: &[Job]) -> Option<FailedGeneration> {
+    jobs.iter()
+        .filter_map(|job| {
+            let result = job.result.as_ref();
+            let error = match job.status {
+                JobStatus::Failed => job.error.clone(),
+                JobStatus::Done => result.filter(|r| !r.success).map(|r| {
+                    r.error.clone().unwrap_or_else(|| "Generation failed".to_string())
+                }),
+                _ => None,
+            }?;
+            let compile_errors = result
+                .map(|r| r.logs.iter().filter_map(|line| line.strip_prefix(COMPILE_FAILED)))
+                .into_iter()
+                .flatten()
+                .map(str::to_string)
+                .collect();
+            Some(FailedGeneration {
+                job_id: job.id.clone(),
+                prompt: job.prompt.clone(),
+                at: job.finished_at.unwrap_or(job.created_at),
+                error,
+                compile_errors,
+            })
+        })
+        .max_by_key(|failure| failure.at)
+}
+
+/// Gather the overview; each lock is held only while its part is read
+pub async fn collect(state: &AppState) -> Overview {
+    let registry = state.registry.lock().await;
+    let mut components: Vec<(String, ComponentStatus)> = registry
+        .list()
+        .map(|metadata| {
+            let flag = registry.flag(&metadata.id).and_then(|flag| match flag.state {
+                FlagState::Enabled => None,
+                FlagState::Disabled => Some("disabled".to_string()),
+                FlagState::Pinned { version } => Some(format!("pinned to version {}", version)),
+            });
+            let status = ComponentStatus {
+                name: metadata.name.clone(),
+                version_id: None,
+                loaded_at: metadata.loaded_at.clone(),
+                ai_generated: metadata.ai_generated,
+                flag,
+                stats: metadata.stats.clone(),
+            };
+            (metadata.name.clone(), status)
+        })
+        .collect();
+    drop(registry);
+    components.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let schedule = state.schedule.lock().await.clone();
+    let audit = state.audit_log.lock().await.clone();
+    let telemetry = state.telemetry.lock().await.recent();
+    let jobs = state.jobs.recent().await;
+    let queue = state.jobs.depth().await;
+
+    let history = state.versions.lock().await;
+    let inputs = Inputs {
+        history: &history,
+        schedule: &schedule,
+        jobs: &jobs,
+        audit: &audit,
+        telemetry: &telemetry,
+    };
+    let components = components
+        .into_iter()
+        .map(|(name, mut status)| {
+            status.version_id = serving_version(&history, &name);
+            status
+        })
+        .collect();
+    let current_version = history.get_current().map(VersionSummary::of);
+    let pending_proposals = pending_proposals(&inputs);
+    let recent_failures = recent_failures(&inputs);
+    let last_reload = history.versions.iter().filter_map(|v| v.activated_at).max();
+    drop(history);
+
+    Overview {
+        generated_at: Utc::now(),
+        components,
+        current_version,
+        pending_proposals,
+        recent_failures,
+        last_reload,
+        last_failed_generation: last_failed_generation(&jobs),
+        ai: state.limits.status(),
+        queue,
+    }
+}
+
+/// Calls, traps and the slowest export, or `unused`
+fn usage(stats: &ExecutionStats) -> String {
+    if stats.calls == 0 {
+        return "unused".to_string();
+    }
+    let mut usage = format!("{} calls", stats.calls);
+    if stats.traps > 0 {
+        usage.push_str(&format!(", {} traps", stats.traps));
+    }
+    if let Some(export) = stats.slowest_export() {
+        usage.push_str(&format!(", slowest `{}` {:.1} ms p95", export, stats.exports[export].timing.p95_ms));
+    }
+    usage
+}
+
+/// Render an overview as a markdown report
+fn render_markdown(overview: &Overview) -> String {
+    let mut markdown = format!("# System overview ({})\n\n", overview.generated_at.format("%Y-%m-%d %H:%M UTC"));
+    match &overview.current_version {
+        Some(v) => markdown.push_str(&format!("- Live version: {} of `{}`: {}\n", v.id, v.component, v.description)),
+        None => markdown.push_str("- Live version: none\n"),
+    }
+    let components: Vec<String> = overview
+        .components
+        .iter()
+        .map(|c| {
+            let version = c.version_id.map(|id| format!(" v{}", id)).unwrap_or_default();
+            let flag = c.flag.as_ref().map(|f| format!(" ({})", f)).unwrap_or_default();
+            format!("`{}`{}{} ({})", c.name, version, flag, usage(&c.stats))
+        })
+        .collect();
+    markdown.push_str(&format!(
+        "- Components: {}\n",
+        if components.is_empty() { "none".to_string() } else { components.join(", ") }
+    ));
+    markdown.push_str(&format!(
+        "- Generation queue: {} queued, {} running (capacity {})\n",
+        overview.queue.queued, overview.queue.running, overview.queue.capacity
+    ));
+    let budget = overview
+        .ai
+        .daily_cost_budget_usd
+        .map(|budget| format!(" of ${:.2}", budget))
+        .unwrap_or_default();
+    markdown.push_str(&format!(
+        "- AI today: {} tokens, ${:.2}{}\n",
+        overview.ai.tokens_used, overview.ai.cost_usd, budget
+    ));
+
+    if !overview.pending_proposals.is_empty() {
+        markdown.push_str("\n## Pending proposals\n\n");
+        for proposal in &overview.pending_proposals {
+            let v = &proposal.version;
+            let waiting = if proposal.waiting_for.is_empty() {
+                "ready to activate".to_string()
+            } else {
+                format!("waiting for {}", proposal.waiting_for.join(", "))
+            };
+            markdown.push_str(&format!("- Version {} of `{}`: {} ({})\n", v.id, v.component, v.description, waiting));
+        }
+    }
+    if !overview.recent_failures.is_empty() {
+        markdown.push_str("\n## Recent failures\n\n");
+        for failure in &overview.recent_failures {
+            let version = failure.version_id.map(|id| format!(", version {}", id)).unwrap_or_default();
+            markdown.push_str(&format!(
+                "- {} {}{}: {}\n",
+                failure.at.format("%Y-%m-%d %H:%M"),
+                failure.source,
+                version,
+                failure.message
+            ));
+        }
+    }
+    markdown
+}
+
+/// Components, versions, proposals, failures, AI spend and queue depth in
+/// one response
+pub async fn get_overview(
+    State(state): State<AppState>,
+    Query(query): Query<OverviewQuery>,
+) -> Result<Response, AppError> {
+    let overview = collect(&state).await;
+    match query.format.as_deref() {
+        Some("markdown") | Some("md") => Ok((
+            [(header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+            render_markdown(&overview),
+        )
+            .into_response()),
+        None | Some("json") => Ok(Json(overview).into_response()),
+        Some(other) => Err(AppError::ApiError(format!("Unknown overview format '{}'", other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morpheus_core::component::Provenance;
+    use morpheus_core::manifest::ComponentManifest;
+
+    fn add(history: &mut VersionHistory, component: &str, description: &str) -> usize {
+        history.add_version(
+            description.to_string(),
+            description.to_string(),
+            "pub fn render() -> String { String::new() }".to_string(),
+            vec![0; 8],
+            String::new(),
+            true,
+            ComponentManifest {
+                name: component.to_string(),
+                ..Default::default()
+            },
+            Provenance::ai(description, "test-model"),
+            true,
+        )
+    }
+
+    fn inputs<'a>(
+        history: &'a VersionHistory,
+        schedule: &'a [ScheduledActivation],
+        jobs: &'a [Job],
+        audit: &'a [AuditEntry],
+    ) -> Inputs<'a> {
+        Inputs {
+            history,
+            schedule,
+            jobs,
+            audit,
+            telemetry: &[],
+        }
+    }
+
+    #[test]
+    fn test_usage_summarises_stats() {
+        let mut stats = ExecutionStats::default();
+        assert_eq!(usage(&stats), "unused");
+
+        stats.record("render", 2.0, false, "2026-01-01T00:00:00Z").unwrap();
+        stats.record("on_save", 40.0, true, "2026-01-01T00:00:00Z").unwrap();
+        assert_eq!(usage(&stats), "2 calls, 1 traps, slowest `on_save` 40.0 ms p95");
+    }
+
+    #[test]
+    fn test_pending_proposals_say_what_they_wait_for() {
+        let mut history = VersionHistory::new();
+        let live = add(&mut history, "chart", "A chart");
+        history.require_review = true;
+        let reviewed = add(&mut history, "chart", "A bar chart");
+        let scheduled = add(&mut history, "table", "A table");
+        history.versions[scheduled].review.record_verdict("ops", ReviewStatus::Approved);
+        let schedule = [ScheduledActivation {
+            id: "s1".to_string(),
+            version_id: scheduled,
+            activate_at: Utc::now(),
+            status: ActivationStatus::Pending,
+            created_at: Utc::now(),
+        }];
+
+        let proposals = pending_proposals(&inputs(&history, &schedule, &[], &[]));
+
+        let ids: Vec<usize> = proposals.iter().map(|p| p.version.id).collect();
+        assert_eq!(ids, [scheduled, reviewed]);
+        assert!(proposals[0].waiting_for[0].starts_with("scheduled for "));
+        assert_eq!(proposals[1].waiting_for, ["review"]);
+        assert_eq!(serving_version(&history, "chart"), Some(live));
+        assert_eq!(serving_version(&history, "table"), None);
+    }
+
+    #[test]
+    fn test_failures_are_merged_newest_first() {
+        let history = VersionHistory::new();
+        let earlier = Utc::now() - chrono::Duration::minutes(5);
+        let job = Job {
+            id: "j1".to_string(),
+            status: JobStatus::Failed,
+            iteration: 3,
+            position: None,
+            prompt: "A chart".to_string(),
+            created_at: earlier,
+            started_at: Some(earlier),
+            finished_at: Some(earlier),
+            result: None,
+            error: Some("Compilation failed after 3 attempts".to_string()),
+        };
+        let audit = [
+            AuditEntry {
+                timestamp: Utc::now(),
+                action: "activate".to_string(),
+                version_id: Some(4),
+                outcome: "rolled_back".to_string(),
+                detail: "Health check failed".to_string(),
+            },
+            AuditEntry {
+                timestamp: Utc::now(),
+                action: "review".to_string(),
+                version_id: Some(4),
+                outcome: "approved".to_string(),
+                detail: String::new(),
+            },
+        ];
+
+        let failures = recent_failures(&inputs(&history, &[], &[job], &audit));
+
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].source, "activate");
+        assert_eq!(failures[0].version_id, Some(4));
+        assert_eq!(failures[1].source, "job");
+        assert_eq!(failures[1].message, "A chart: Compilation failed after 3 attempts");
+    }
+
+    #[test]
+    fn test_last_failed_generation_keeps_compile_errors() {
+        let at = |minutes| Utc::now() - chrono::Duration::minutes(minutes);
+        let job = |id: &str, finished_at, result| Job {
+            id: id.to_string(),
+            status: JobStatus::Done,
+            iteration: 2,
+            position: None,
+            prompt: format!("Job {}", id),
+            created_at: finished_at,
+            started_at: Some(finished_at),
+            finished_at: Some(finished_at),
+            result: Some(result),
+            error: None,
+        };
+        let response = |success, logs: &[&str]| crate::GenerateResponse {
+            success,
+            version_id: None,
+            wasm_base64: None,
+            restored_state: None,
+            error: (!success).then(|| "Failed after 5 attempts".to_string()),
+            iterations: 2,
+            logs: logs.iter().map(|line| line.to_string()).collect(),
+            slots: Vec::new(),
+        };
+        let jobs = [
+            job("j3", at(1), response(true, &[])),
+            job(
+                "j2",
+                at(5),
+                response(false, &["\n━━━ Iteration 1 ━━━", "❌ Compilation failed:\nE0425", "❌ Compilation failed:\nE0308"]),
+            ),
+            job("j1", at(9), response(false, &[])),
+        ];
+
+        let failure = last_failed_generation(&jobs).unwrap();
+
+        assert_eq!(failure.job_id, "j2");
+        assert_eq!(failure.error, "Failed after 5 attempts");
+        assert_eq!(failure.compile_errors, ["E0425", "E0308"]);
+        assert!(last_failed_generation(&jobs[..1]).is_none());
+    }
+}
@@ -0,0 +1,566 @@
+//! Server-rendered pages
+//!
+//! The frontends under `public/` are static HTML that reads the JSON API,
+//! so a renamed field shows up as a blank column rather than an error.
+//! These pages are rendered with maud from the same types the API serves
+//! (`VersionSummary`, `ComponentMetadata`, the requests their forms post),
+//! so the page and the API change together or the server doesn't compile:
+//!
+//! - `GET /pages/history` lists versions, `GET /pages/history/{id}` shows
+//!   one with a rollback button and its code to review, with an outline,
+//!   diagnostics and jump-to-definition (see [`crate::language`])
+//! - `GET /pages/components` lists loaded components, and
+//!   `GET /pages/components/{name}` shows one's interface, slots and
+//!   lifecycle
+//! - `GET /pages/playground` posts generation requests
+//!
+//! Forms post JSON to the API endpoints named in their `data-api`
+//! attribute; their field names are checked against the request types'
+//! schemas in the tests.
+//!
+//! The pages show source and history and edit versions, so
+//! [`crate::sharing::authorize`] holds them to the owner token like the
+//! API and refuses share links.
+
+use crate::{AppError, AppState, ComponentVersion, VersionSummary};
+use axum::extract::{Path, State};
+use maud::{html, Markup, PreEscaped, DOCTYPE};
+use morpheus_compiler::symbols::{self, Symbol};
+use morpheus_core::component::{Author, ComponentMetadata};
+use morpheus_core::interface::ComponentInterface;
+use morpheus_core::lifecycle::Lifecycle;
+use morpheus_core::manifest::ComponentManifest;
+use morpheus_core::review::ReviewStatus;
+
+/// Style of links between pages
+const LINK: &str = "text-indigo-400 hover:underline";
+
+/// Posts a `data-api` form as JSON, numbers as numbers, and reports the
+/// outcome in its `<output>`
+const FORM_SCRIPT: &str = r#"
+for (const form of document.querySelectorAll('form[data-api]')) {
+    form.addEventListener('submit', async (event) => {
+        event.preventDefault();
+        const body = {};
+        for (const [key, value] of new FormData(form)) {
+            if (value !== '') body[key] = form.elements[key].type === 'number' ? Number(value) : value;
+        }
+        const output = form.querySelector('output');
+        output.textContent = 'Working...';
+        const response = await fetch(form.dataset.api, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify(body),
+        });
+        const data = await response.json().catch(() => ({}));
+        if (!response.ok || data.error) {
+            output.textContent = data.error || response.statusText;
+        } else if (data.version_id !== undefined && form.dataset.next) {
+            location.href = form.dataset.next + data.version_id;
+        } else {
+            location.reload();
+        }
+    });
+}
+"#;
+
+/// Underlines the diagnostics in the source under review and jumps to the
+/// definition of a double-clicked name, through `POST /api/language`
+const REVIEW_SCRIPT: &str = r#"
+const code = document.getElementById('source');
+const language = async (request) => {
+    const response = await fetch('/api/language', {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ source: code.textContent, ...request }),
+    });
+    return response.ok ? response.json() : null;
+};
+const diagnostics = document.getElementById('diagnostics');
+diagnostics.textContent = 'Checking...';
+language({ check: true }).then((result) => {
+    diagnostics.textContent = result && result.diagnostics.length === 0 ? 'No errors or warnings.' : '';
+    for (const d of result ? result.diagnostics : []) {
+        const line = d.line && document.getElementById('L' + d.line);
+        const color = d.severity === 'error' ? 'red' : 'amber';
+        if (line) {
+            line.classList.add('underline', 'decoration-wavy', 'decoration-' + color + '-500');
+            line.title = (line.title ? line.title + '
+' : '') + d.message;
+        }
+        const item = document.createElement(line ? 'a' : 'div');
+        if (line) item.href = '#L' + d.line;
+        item.className = 'block text-' + color + '-400';
+        item.textContent = (d.line ? d.line + ': ' : '') + d.message.split('
+')[0];
+        diagnostics.append(item);
+    }
+});
+code.addEventListener('dblclick', async () => {
+    const selection = getSelection();
+    const line = selection.anchorNode && selection.anchorNode.parentElement.closest('[id^=L]');
+    if (!line) return;
+    const result = await language({ line: Number(line.id.slice(1)), column: selection.anchorOffset + 1 });
+    if (result && result.definition) location.hash = 'L' + result.definition.line;
+});
+"#;
+
+/// Version history
+pub async fn history_page(State(state): State<AppState>) -> Markup {
+    let versions = state.versions.lock().await.get_history();
+    history(&versions)
+}
+
+/// One version, with its code
+pub async fn version_page(State(state): State<AppState>, Path(id): Path<usize>) -> Result<Markup, AppError> {
+    let history = state.versions.lock().await;
+    let version = history
+        .versions
+        .get(id)
+        .ok_or_else(|| AppError::NotFound(format!("Version {} not found", id)))?;
+    let is_current = history.get_current().map(|v| v.id) == Some(id);
+    Ok(version_detail(version, is_current))
+}
+
+/// Loaded components
+pub async fn components_page(State(state): State<AppState>) -> Markup {
+    let registry = state.registry.lock().await;
+    let mut components: Vec<_> = registry.list().cloned().collect();
+    components.sort_by(|a, b| a.name.cmp(&b.name));
+    component_list(&components)
+}
+
+/// One component: what it offers and what depends on it
+pub async fn component_page(State(state): State<AppState>, Path(name): Path<String>) -> Result<Markup, AppError> {
+    let registry = state.registry.lock().await;
+    let id = crate::find_component(&registry, &name)?;
+    let metadata = registry
+        .metadata(&id)
+        .ok_or_else(|| AppError::NotFound(format!("Component '{}' not found", name)))?;
+    Ok(component_detail(&ComponentView {
+        metadata,
+        manifest: registry.manifest(&id),
+        interface: registry.interface(&name),
+        dependents: registry.dependents(&id),
+        warnings: registry.mount_warnings(&id),
+    }))
+}
+
+/// Generation playground
+pub async fn playground_page(State(state): State<AppState>) -> Markup {
+    let current = state.versions.lock().await.get_current().map(|v| v.id);
+    let mut components: Vec<_> = state.registry.lock().await.list().map(|m| m.name.clone()).collect();
+    components.sort();
+    playground(&components, current)
+}
+
+/// Everything the component page shows
+struct ComponentView<'a> {
+    metadata: &'a ComponentMetadata,
+    manifest: Option<&'a ComponentManifest>,
+    interface: Option<&'a ComponentInterface>,
+    dependents: Vec<String>,
+    warnings: Vec<String>,
+}
+
+fn layout(title: &str, body: Markup) -> Markup {
+    html! {
+        (DOCTYPE)
+        html lang="en" {
+            head {
+                meta charset="UTF-8";
+                meta name="viewport" content="width=device-width, initial-scale=1.0";
+                title { "Morpheus - " (title) }
+                script src="https://cdn.tailwindcss.com" {}
+                style {
+                    "body { font-family: 'Inter', -apple-system, BlinkMacSystemFont, sans-serif; "
+                    "background: #0f172a; color: #e2e8f0; }"
+                }
+            }
+            body class="min-h-screen" {
+                header class="flex items-center gap-6 px-6 py-3 border-b border-slate-800" {
+                    a href="/" class="font-semibold" { "🧬 Morpheus" }
+                    nav class="flex gap-4 text-sm text-slate-400" {
+                        a href="/pages/history" class="hover:text-white" { "History" }
+                        a href="/pages/components" class="hover:text-white" { "Components" }
+                        a href="/pages/playground" class="hover:text-white" { "Playground" }
+                    }
+                }
+                main class="p-6 max-w-5xl" {
+                    h1 class="text-xl font-semibold mb-4" { (title) }
+                    (body)
+                }
+                script { (PreEscaped(FORM_SCRIPT)) }
+            }
+        }
+    }
+}
+
+fn history(versions: &[VersionSummary]) -> Markup {
+    layout(
+        "Version history",
+        html! {
+            @if versions.is_empty() {
+                p class="text-slate-400" {
+                    "No versions yet. Generate one in the " a href="/pages/playground" class=(LINK) { "playground" } "."
+                }
+            }
+            table class="w-full text-sm" {
+                @for version in versions.iter().rev() {
+                    tr class="border-b border-slate-800 align-top" {
+                        td class="py-2 pr-4" {
+                            a href={ "/pages/history/" (version.id) } class=(LINK) { "#" (version.id) }
+                            @if version.is_current { " " span class="text-xs text-green-400" { "live" } }
+                            @if let Some(url) = &version.thumbnail_url {
+                                img src=(url) alt="" class="mt-1 h-10 rounded border border-slate-700";
+                            }
+                        }
+                        td class="py-2 pr-4" { (version.name) }
+                        td class="py-2 pr-4" {
+                            (version.description)
+                            @if let Some(changelog) = &version.changelog {
+                                p class="text-slate-400" { (changelog.summary) }
+                            }
+                        }
+                        td class="py-2 pr-4 text-slate-400" { (author(version.provenance.author)) }
+                        td class="py-2 pr-4 text-slate-400" { (review(version.review.status)) }
+                        td class="py-2 pr-4 text-slate-400" {
+                            @if let Some(rating) = version.average_rating {
+                                (format!("★ {:.1} ({})", rating, version.feedback_count))
+                            }
+                        }
+                        td class="py-2 text-slate-500" { (version.created_at) }
+                    }
+                }
+            }
+        },
+    )
+}
+
+fn version_detail(version: &ComponentVersion, is_current: bool) -> Markup {
+    layout(
+        &format!("Version #{} of {}", version.id, version.name),
+        html! {
+            p class="mb-2" { (version.description) }
+            dl class="grid grid-cols-[10rem_1fr] gap-1 text-sm mb-4" {
+                dt class="text-slate-400" { "Created" } dd { (version.created_at.to_rfc3339()) }
+                dt class="text-slate-400" { "Live since" }
+                dd { (version.activated_at.map(|at| at.to_rfc3339()).unwrap_or_else(|| "never".to_string())) }
+                dt class="text-slate-400" { "Author" } dd { (author(version.provenance.author)) }
+                @if let Some(model) = &version.provenance.model {
+                    dt class="text-slate-400" { "Model" } dd { (model) }
+                }
+                @if let Some(prompt) = &version.provenance.prompt {
+                    dt class="text-slate-400" { "Prompt" } dd { (prompt) }
+                }
+                @if let Some(parent) = version.provenance.parent_version {
+                    dt class="text-slate-400" { "Derived from" }
+                    dd { a href={ "/pages/history/" (parent) } class=(LINK) { "#" (parent) } }
+                }
+                @if let Some(component) = &version.provenance.forked_from {
+                    dt class="text-slate-400" { "Forked from" }
+                    dd { a href={ "/pages/components/" (component) } class=(LINK) { (component) } }
+                }
+                dt class="text-slate-400" { "Review" } dd { (review(version.review.status)) }
+                @if let Some(changelog) = &version.changelog {
+                    dt class="text-slate-400" { "Changes" }
+                    dd { (changelog.summary) " (since #" (changelog.base_version_id) ")" }
+                }
+                @for violation in &version.guardrail_violations {
+                    dt class="text-slate-400" { "Guardrail" } dd class="text-amber-400" { (violation) }
+                }
+            }
+            @if is_current {
+                p class="text-green-400 mb-4" { "This is the live version." }
+            } @else {
+                form data-api="/api/rollback" class="mb-4" {
+                    input type="hidden" name="version_id" value=(version.id);
+                    button class="px-3 py-1 rounded bg-indigo-600 hover:bg-indigo-500" { "Roll back to this version" }
+                    " " output class="text-sm text-slate-400" {}
+                }
+            }
+            div class="grid grid-cols-[1fr_14rem] gap-4" {
+                pre class="text-xs bg-slate-900 border border-slate-800 rounded p-4 overflow-x-auto" {
+                    code #source {
+                        @for (i, line) in version.rust_code.lines().enumerate() {
+                            span id=(format!("L{}", i + 1)) class="target:bg-indigo-900" { (line) "\n" }
+                        }
+                    }
+                }
+                aside class="text-xs space-y-4" {
+                    section {
+                        h2 class="text-slate-400 mb-1" { "Outline" }
+                        (outline(&symbols::outline(&version.rust_code).unwrap_or_default()))
+                    }
+                    section {
+                        h2 class="text-slate-400 mb-1" { "Diagnostics" }
+                        div #diagnostics {}
+                    }
+                    p class="text-slate-500" { "Double-click a name to jump to its definition." }
+                }
+            }
+            details class="mt-4" {
+                summary class="cursor-pointer text-slate-400" { "Edit by hand" }
+                form data-api=(format!("/api/versions/{}/edit", version.id)) data-next="/pages/history/"
+                    class="flex flex-col gap-2 mt-2" {
+                    textarea name="source" rows="24" spellcheck="false"
+                        class="font-mono text-xs bg-slate-900 border border-slate-700 rounded p-2" {
+                        (version.rust_code)
+                    }
+                    input name="message" placeholder="What does the edit change?"
+                        class="bg-slate-900 border border-slate-700 rounded px-2 py-1";
+                    div {
+                        button class="px-3 py-1 rounded bg-indigo-600 hover:bg-indigo-500" { "Compile and save" }
+                        " " output class="text-sm text-slate-400" {}
+                    }
+                }
+            }
+            script { (PreEscaped(REVIEW_SCRIPT)) }
+        },
+    )
+}
+
+/// Symbols as nested links to their lines
+fn outline(symbols: &[Symbol]) -> Markup {
+    html! {
+        ul class="pl-3" {
+            @for symbol in symbols {
+                li {
+                    a href=(format!("#L{}", symbol.line)) class=(LINK) title=(symbol.kind) { (symbol.name) }
+                    @if !symbol.children.is_empty() {
+                        (outline(&symbol.children))
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn component_list(components: &[ComponentMetadata]) -> Markup {
+    layout(
+        "Components",
+        html! {
+            @if components.is_empty() {
+                p class="text-slate-400" { "No components loaded." }
+            }
+            table class="w-full text-sm" {
+                @for component in components {
+                    tr class="border-b border-slate-800" {
+                        td class="py-2 pr-4" {
+                            @if let Some(thumbnail) = &component.thumbnail {
+                                img src=(thumbnail.data_url()) alt="" class="mb-1 h-10 rounded border border-slate-700";
+                            }
+                            a href={ "/pages/components/" (component.name) } class=(LINK) { (component.name) }
+                        }
+                        td class="py-2 pr-4" { (component.version.to_string()) }
+                        td class="py-2 pr-4 text-slate-400" { (lifecycle(&component.lifecycle)) }
+                        td class="py-2 pr-4 text-slate-400" {
+                            (component.capabilities.iter().map(|c| c.name()).collect::<Vec<_>>().join(", "))
+                        }
+                        td class="py-2 text-slate-500" { (component.loaded_at) }
+                    }
+                }
+            }
+        },
+    )
+}
+
+fn component_detail(view: &ComponentView) -> Markup {
+    let metadata = view.metadata;
+    layout(
+        &format!("{} {}", metadata.name, metadata.version),
+        html! {
+            @for warning in &view.warnings {
+                p class="text-amber-400 mb-2" { "🌅 " (warning) }
+            }
+            @if let Some(manifest) = view.manifest {
+                p class="mb-4" { (manifest.description) }
+            }
+            dl class="grid grid-cols-[10rem_1fr] gap-1 text-sm mb-4" {
+                dt class="text-slate-400" { "Status" } dd { (lifecycle(&metadata.lifecycle)) }
+                dt class="text-slate-400" { "Loaded" } dd { (metadata.loaded_at) }
+                dt class="text-slate-400" { "Author" } dd { (author(metadata.provenance.author)) }
+                @if let Some(hash) = &metadata.interface_hash {
+                    dt class="text-slate-400" { "Interface" } dd class="font-mono" { (hash) }
+                }
+                dt class="text-slate-400" { "Depends on" }
+                dd {
+                    @for pin in &metadata.dependencies {
+                        a href={ "/pages/components/" (pin.component) } class=(LINK) { (pin.component) }
+                        " " (pin.version.to_string()) " "
+                    }
+                }
+                dt class="text-slate-400" { "Used by" }
+                dd {
+                    @for dependent in &view.dependents {
+                        a href={ "/pages/components/" (dependent) } class=(LINK) { (dependent) } " "
+                    }
+                }
+            }
+            @if let Some(manifest) = view.manifest.filter(|m| !m.slots.is_empty()) {
+                h2 class="font-semibold mt-6 mb-2" { "Slots" }
+                ul class="text-sm" {
+                    @for slot in &manifest.slots {
+                        li {
+                            (slot.name) ": "
+                            a href={ "/pages/components/" (slot.component) } class=(LINK) { (slot.component) }
+                        }
+                    }
+                }
+            }
+            @if let Some(interface) = view.interface {
+                h2 class="font-semibold mt-6 mb-2" { "Interface" }
+                dl class="grid grid-cols-[10rem_1fr] gap-1 text-sm font-mono" {
+                    dt class="text-slate-400 font-sans" { "Exports" } dd { (interface.exports.join(", ")) }
+                    dt class="text-slate-400 font-sans" { "Messages" } dd { (interface.messages.join(", ")) }
+                    dt class="text-slate-400 font-sans" { "Emits" } dd { (interface.emits.join(", ")) }
+                    dt class="text-slate-400 font-sans" { "Consumes" } dd { (interface.consumes.join(", ")) }
+                    dt class="text-slate-400 font-sans" { "State" } dd { (interface.state_fields.join(", ")) }
+                }
+            }
+        },
+    )
+}
+
+fn playground(components: &[String], current: Option<usize>) -> Markup {
+    layout(
+        "Playground",
+        html! {
+            form data-api="/api/generate" data-next="/pages/history/" class="flex flex-col gap-3 max-w-2xl" {
+                label class="text-sm text-slate-400" for="prompt" { "What should the component do?" }
+                textarea id="prompt" name="prompt" rows="5" required
+                    class="bg-slate-900 border border-slate-700 rounded p-2" {}
+                label class="text-sm text-slate-400" for="component" { "Component" }
+                input id="component" name="component" list="components" placeholder="main"
+                    class="bg-slate-900 border border-slate-700 rounded p-2";
+                datalist id="components" {
+                    @for name in components { option value=(name) {} }
+                }
+                @if let Some(current) = current {
+                    input type="number" name="base_version_id" value=(current) hidden;
+                }
+                div {
+                    button class="px-3 py-1 rounded bg-indigo-600 hover:bg-indigo-500" { "Generate" }
+                    " " output class="text-sm text-slate-400" {}
+                }
+            }
+        },
+    )
+}
+
+fn author(author: Author) -> &'static str {
+    match author {
+        Author::Unknown => "unknown",
+        Author::Human => "human",
+        Author::Ai => "AI",
+    }
+}
+
+fn review(status: ReviewStatus) -> &'static str {
+    match status {
+        ReviewStatus::Pending => "pending review",
+        ReviewStatus::Approved => "approved",
+        ReviewStatus::ChangesRequested => "changes requested",
+    }
+}
+
+fn lifecycle(lifecycle: &Lifecycle) -> String {
+    match lifecycle {
+        Lifecycle::Active => "active".to_string(),
+        Lifecycle::Deprecated { replacement: Some(replacement), .. } => format!("deprecated for {}", replacement),
+        Lifecycle::Deprecated { replacement: None, .. } => "deprecated".to_string(),
+        Lifecycle::Retired => "retired".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EditRequest, GenerateRequest, RollbackRequest, VersionHistory};
+    use morpheus_core::component::Provenance;
+    use morpheus_core::thumbnail::Thumbnail;
+    use schemars::JsonSchema;
+
+    fn add(history: &mut VersionHistory, description: &str) -> usize {
+        history.add_version(
+            "main".to_string(),
+            description.to_string(),
+            "pub fn render() -> String { \"<b>\".into() }".to_string(),
+            vec![0; 8],
+            String::new(),
+            true,
+            ComponentManifest::new("main", description),
+            Provenance::ai(description, "test-model"),
+            true,
+        )
+    }
+
+    /// Names of the fields a form in `page` posts
+    fn form_fields(page: &str) -> Vec<String> {
+        let form = &page[page.find("<form").unwrap()..page.find("</form>").unwrap()];
+        form.split(" name=\"").skip(1).filter_map(|rest| rest.split('"').next()).map(String::from).collect()
+    }
+
+    fn accepts<T: JsonSchema>(fields: &[String]) -> bool {
+        let schema = serde_json::to_value(schemars::schema_for!(T)).unwrap();
+        fields.iter().all(|field| schema["properties"].get(field).is_some())
+    }
+
+    #[test]
+    fn test_forms_post_what_the_api_accepts() {
+        let playground = playground(&["main".to_string()], Some(3)).into_string();
+        let fields = form_fields(&playground);
+        assert_eq!(fields, ["prompt", "component", "base_version_id"]);
+        assert!(accepts::<GenerateRequest>(&fields));
+
+        let mut history = VersionHistory::new();
+        let first = add(&mut history, "A counter");
+        add(&mut history, "A counter with reset");
+        let rollback = version_detail(&history.versions[first], false).into_string();
+        assert!(rollback.contains(r#"data-api="/api/rollback""#));
+        assert!(accepts::<RollbackRequest>(&form_fields(&rollback)));
+
+        let live = first + 1;
+        let edit = version_detail(&history.versions[live], true).into_string();
+        assert!(edit.contains(&format!(r#"data-api="/api/versions/{}/edit""#, live)));
+        assert!(accepts::<EditRequest>(&form_fields(&edit)));
+        assert!(edit.contains(&format!(r#"href="/pages/history/{}""#, first)), "links the parent version");
+    }
+
+    #[test]
+    fn test_history_escapes_and_marks_the_live_version() {
+        let mut history = VersionHistory::new();
+        add(&mut history, "A <script> counter");
+        let live = add(&mut history, "A counter with reset");
+
+        let page = super::history(&history.get_history()).into_string();
+        assert!(page.contains("A &lt;script&gt; counter"));
+        assert!(page.contains(&format!(r#"href="/pages/history/{}""#, live)));
+        assert_eq!(page.matches(">live<").count(), 1);
+        let code = version_detail(&history.versions[live], true).into_string();
+        assert!(code.contains("&quot;&lt;b&gt;&quot;"));
+        let render = history.versions[live].rust_code.lines().position(|l| l.contains("fn render")).unwrap() + 1;
+        assert!(code.contains(&format!(r##"<a href="#L{}" class="{}" title="fn">render</a>"##, render, LINK)));
+        assert!(code.contains(&format!(r#"<span id="L{}""#, render)));
+        assert!(!code.contains("/api/rollback"));
+
+        history.versions[live].thumbnail = Some(Thumbnail::from_bytes(b"\x89PNG\r\n\x1a\n").unwrap());
+        let page = super::history(&history.get_history()).into_string();
+        assert!(page.contains(&format!(r#"<img src="/api/versions/{}/thumbnail""#, live)));
+        assert_eq!(page.matches("<img").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_versions_and_components_are_not_found() {
+        use axum::http::StatusCode;
+        use axum::response::IntoResponse;
+
+        let state = AppState::for_tests().await;
+        add(&mut *state.versions.lock().await, "Counter");
+
+        let version = version_page(State(state.clone()), Path(3)).await;
+        assert_eq!(version.into_response().status(), StatusCode::NOT_FOUND);
+        let component = component_page(State(state), Path("chart".to_string())).await;
+        assert_eq!(component.into_response().status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,551 @@
+//! Planning big requests as several component changes.
+//!
+//! "Build me a CRM page" is too much for one component: generations either
+//! produce one monolithic component or fail. `POST /api/plan` first asks
+//! the AI to decompose the request into a [`Plan`]: components to create or
+//! modify, each with its own prompt, the components it depends on and the
+//! slots it embeds them in. The steps are then generated and compiled in
+//! dependency order, and committed together: if any step fails to compile,
+//! nothing is saved. Once they are saved, they form a rollback group, so
+//! `POST /api/groups/{group_id}/rollback` undoes the whole plan.
+//!
+//! Pass `dry_run` to only get the plan, and `plan` to execute a plan (for
+//! example an edited one) without planning again.
+
+use crate::{
+    base64_decode, complete, create_generation_request, detect_conflict, generate_draft, record_audit,
+    register_component, report_guardrails, screen_request, truncate, AppError, AppState, ComponentDraft, Message,
+    PromptContext,
+};
+use axum::{extract::State, Json};
+use morpheus_core::catalog::CatalogEntry;
+use morpheus_core::component::Provenance;
+use morpheus_core::manifest::{ComponentManifest, SlotDecl};
+use morpheus_runtime::{SimilarComponent, WasmComponent};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// Most steps a plan may have
+pub const MAX_STEPS: usize = 8;
+
+/// Start of the planner's system prompt (the mock AI recognizes it)
+pub const SYSTEM_PROMPT_START: &str = "You plan changes to a web app built from WebAssembly components.";
+
+/// Similar existing components shown to the planner
+const MAX_SIMILAR: usize = 5;
+
+/// Whether a step creates a component or changes an existing one
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StepAction {
+    Create,
+    Modify,
+}
+
+/// One component change of a plan
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PlanStep {
+    /// Component to create or modify
+    pub component: String,
+    pub action: StepAction,
+    /// What to generate, as for `POST /api/generate`
+    pub prompt: String,
+    /// Components (of the plan or existing) that must be built first
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Components to embed, which are dependencies too
+    #[serde(default)]
+    pub slots: Vec<SlotDecl>,
+}
+
+/// Component changes that together fulfil a request
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Plan {
+    pub steps: Vec<PlanStep>,
+}
+
+impl Plan {
+    /// Read the plan from the AI's reply: a JSON object, possibly fenced
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (Some(start), Some(end)) = (text.find('{'), text.rfind('}')) else {
+            return Err("The planner's reply contains no JSON plan".to_string());
+        };
+        serde_json::from_str(&text[start..=end]).map_err(|e| format!("The planner's reply is not a valid plan: {}", e))
+    }
+
+    /// Validate the plan against the `existing` components and order the
+    /// steps so each comes after its dependencies (otherwise keeping the
+    /// plan's order).
+    pub fn ordered(&self, existing: &[String]) -> Result<Vec<&PlanStep>, String> {
+        if self.steps.is_empty() {
+            return Err("The plan has no steps".to_string());
+        }
+        if self.steps.len() > MAX_STEPS {
+            return Err(format!("The plan has {} steps (at most {})", self.steps.len(), MAX_STEPS));
+        }
+        for (index, step) in self.steps.iter().enumerate() {
+            if step.component.trim().is_empty() || step.prompt.trim().is_empty() {
+                return Err(format!("Step {} needs a component and a prompt", index + 1));
+            }
+            if self.steps[..index].iter().any(|other| other.component == step.component) {
+                return Err(format!("Component '{}' appears in more than one step", step.component));
+            }
+            let exists = existing.contains(&step.component);
+            match step.action {
+                StepAction::Create if exists => {
+                    return Err(format!("Component '{}' already exists; modify it instead", step.component))
+                }
+                StepAction::Modify if !exists => {
+                    return Err(format!("Component '{}' doesn't exist, so it can't be modified", step.component))
+                }
+                _ => {}
+            }
+            for dependency in Self::dependencies(step) {
+                if !existing.contains(dependency) && !self.steps.iter().any(|s| &s.component == dependency) {
+                    return Err(format!("'{}' depends on unknown component '{}'", step.component, dependency));
+                }
+            }
+        }
+
+        let mut ordered: Vec<&PlanStep> = Vec::with_capacity(self.steps.len());
+        while ordered.len() < self.steps.len() {
+            let ready = self.steps.iter().find(|step| {
+                !ordered.iter().any(|done| done.component == step.component)
+                    && Self::dependencies(step).all(|dependency| {
+                        dependency == &step.component
+                            || !self.steps.iter().any(|s| &s.component == dependency)
+                            || ordered.iter().any(|done| &done.component == dependency)
+                    })
+            });
+            match ready {
+                Some(step) => ordered.push(step),
+                None => {
+                    let blocked: Vec<_> = self
+                        .steps
+                        .iter()
+                        .filter(|step| !ordered.iter().any(|done| done.component == step.component))
+                        .map(|step| step.component.as_str())
+                        .collect();
+                    return Err(format!("The plan's dependencies form a cycle: {}", blocked.join(", ")));
+                }
+            }
+        }
+        Ok(ordered)
+    }
+
+    fn dependencies(step: &PlanStep) -> impl Iterator<Item = &String> {
+        step.depends_on.iter().chain(step.slots.iter().map(|slot| &slot.component))
+    }
+}
+
+/// The planner's system prompt, listing the components that exist
+pub fn system_prompt(catalog: &[CatalogEntry], similar: &[SimilarComponent]) -> String {
+    let mut prompt = format!(
+        r#"{} Split the user's request into the smallest set of component changes that fulfil it. Each component renders one part of the page; a parent embeds its children in named slots. Prefer modifying or embedding an existing component to creating one that does the same job.
+
+Reply with only a JSON object, no explanations:
+{{"steps": [
+  {{"component": "contact-list", "action": "create", "prompt": "A searchable table of contacts with name, email and company", "depends_on": [], "slots": []}},
+  {{"component": "crm-page", "action": "create", "prompt": "A CRM page with a header and the contact list below it", "depends_on": [], "slots": [{{"name": "contacts", "component": "contact-list"}}]}}
+]}}
+
+Rules:
+- "action" is "create" for new components and "modify" for existing ones
+- component names are lowercase-with-dashes and unique in the plan
+- list a step's children in "slots" and anything else it needs built first in "depends_on"
+- at most {} steps"#,
+        SYSTEM_PROMPT_START, MAX_STEPS
+    );
+    if catalog.is_empty() {
+        prompt.push_str("\n\nThere are no existing components.");
+    } else {
+        prompt.push_str("\n\nEXISTING COMPONENTS:\n");
+        for entry in catalog {
+            prompt.push_str(&format!("- {}: {}\n", entry.manifest.name, entry.manifest.description));
+        }
+    }
+    if !similar.is_empty() {
+        prompt.push_str("\nMOST SIMILAR TO THE REQUEST (reuse these first):\n");
+        for component in similar {
+            prompt.push_str(&format!("- {}\n", component.name));
+        }
+    }
+    prompt.trim_end().to_string()
+}
+
+/// Request to plan, and optionally execute, a multi-component change
+#[derive(Deserialize, JsonSchema)]
+pub struct PlanRequest {
+    pub prompt: String,
+    /// Execute this plan instead of asking the AI for one
+    #[serde(default)]
+    pub plan: Option<Plan>,
+    /// Only return the plan
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// What became of a step
+#[derive(Clone, Debug, Serialize, JsonSchema)]
+pub struct StepResult {
+    pub component: String,
+    pub action: StepAction,
+    /// Version saved for the step (none unless the whole plan was committed)
+    pub version_id: Option<usize>,
+    pub error: Option<String>,
+}
+
+/// Outcome of a plan
+#[derive(Serialize, JsonSchema)]
+pub struct PlanResponse {
+    /// Whether every step was committed (or, for a dry run, the plan is valid)
+    pub success: bool,
+    pub plan: Option<Plan>,
+    /// Steps in the order they ran
+    pub steps: Vec<StepResult>,
+    /// Version current before the plan
+    pub base_version_id: Option<usize>,
+    /// Rollback group of the plan's versions, which undoes the whole plan
+    pub group_id: Option<usize>,
+    pub error: Option<String>,
+    pub logs: Vec<String>,
+}
+
+impl PlanResponse {
+    fn failed(plan: Option<Plan>, steps: Vec<StepResult>, error: String, logs: Vec<String>) -> Self {
+        Self {
+            success: false,
+            plan,
+            steps,
+            base_version_id: None,
+            group_id: None,
+            error: Some(error),
+            logs,
+        }
+    }
+}
+
+/// A step that compiled, waiting for the rest of the plan
+struct BuiltStep {
+    step: PlanStep,
+    manifest: ComponentManifest,
+    draft: ComponentDraft,
+    wasm_bytes: Vec<u8>,
+}
+
+/// Plan a request as several component changes, then build and commit them
+pub async fn create_plan(
+    State(state): State<AppState>,
+    Json(req): Json<PlanRequest>,
+) -> Result<Json<PlanResponse>, AppError> {
+    screen_request(&state, "plan", &req.prompt).await?;
+    if !state.has_ai() {
+        return Err(AppError::ApiError("OPENROUTER_API_KEY not configured".to_string()));
+    }
+    let mut logs = vec![format!("🗺️  Planning: {}", req.prompt)];
+
+    let catalog = state.registry.lock().await.catalog();
+    let mut existing: Vec<String> = catalog.iter().map(|entry| entry.manifest.name.clone()).collect();
+    let base_version_id = {
+        let history = state.versions.lock().await;
+        for version in &history.versions {
+            if !existing.contains(&version.manifest.name) {
+                existing.push(version.manifest.name.clone());
+            }
+        }
+        history.get_current().map(|v| v.id)
+    };
+
+    let plan = match req.plan {
+        Some(plan) => plan,
+        None => {
+            let similar = state.registry.lock().await.find_similar(&req.prompt, MAX_SIMILAR);
+            let messages = vec![
+                Message {
+                    role: "user".to_string(),
+                    content: system_prompt(&catalog, &similar),
+                },
+                Message {
+                    role: "user".to_string(),
+                    content: format!("Plan this request: {}", req.prompt),
+                },
+            ];
+            match Plan::parse(&complete(&state, messages).await?) {
+                Ok(plan) => plan,
+                Err(error) => return Ok(Json(PlanResponse::failed(None, Vec::new(), error, logs))),
+            }
+        }
+    };
+    let ordered = match plan.ordered(&existing) {
+        Ok(ordered) => ordered.into_iter().cloned().collect::<Vec<_>>(),
+        Err(error) => return Ok(Json(PlanResponse::failed(Some(plan), Vec::new(), error, logs))),
+    };
+    for (index, step) in ordered.iter().enumerate() {
+        logs.push(format!("  {}. {:?} '{}': {}", index + 1, step.action, step.component, truncate(&step.prompt, 60)));
+    }
+    let mut results: Vec<StepResult> = ordered
+        .iter()
+        .map(|step| StepResult {
+            component: step.component.clone(),
+            action: step.action,
+            version_id: None,
+            error: None,
+        })
+        .collect();
+    if req.dry_run {
+        return Ok(Json(PlanResponse {
+            success: true,
+            plan: Some(plan),
+            steps: results,
+            base_version_id,
+            group_id: None,
+            error: None,
+            logs,
+        }));
+    }
+
+    // Build every step before saving any
+    let mut built = Vec::with_capacity(ordered.len());
+    for (index, step) in ordered.into_iter().enumerate() {
+        logs.push(format!("\n━━━ Step {}: {} ━━━", index + 1, step.component));
+        match build_step(&state, step, index, &mut logs).await {
+            Ok(step) => built.push(step),
+            Err(error) => {
+                results[index].error = Some(error.clone());
+                logs.push("↩️  Nothing was saved".to_string());
+                warn!(step = index + 1, "🗺️  Plan step failed: {}", error);
+                let error = format!("Step {} ('{}') failed: {}", index + 1, results[index].component, error);
+                record_audit(&state, "plan", base_version_id, "failed", error.clone()).await;
+                return Ok(Json(PlanResponse::failed(Some(plan), results, error, logs)));
+            }
+        }
+    }
+
+    // Commit them together
+    let mut history = state.versions.lock().await;
+    if let Some(conflict) = detect_conflict(&history, base_version_id) {
+        return Err(AppError::Conflict(conflict));
+    }
+    let mut saved = Vec::with_capacity(built.len());
+    for (index, built) in built.into_iter().enumerate() {
+        let draft = built.draft;
+        let version_id = history.add_version(
+            format!("Plan: {}", truncate(&built.step.prompt, 40)),
+            built.step.prompt.clone(),
+            draft.rust_code,
+            built.wasm_bytes.clone(),
+            draft.js_glue.unwrap_or_default(),
+            true,
+            built.manifest.clone(),
+            Provenance::ai(built.step.prompt.clone(), state.generation.frontier_model.as_str())
+                .with_toolchain(state.compiler.toolchain()),
+            true,
+        );
+        history.versions[version_id].sbom = draft.sbom;
+        history.versions[version_id].variants = draft.variants;
+        results[index].version_id = Some(version_id);
+        logs.push(format!("📜 Saved '{}' as version {}", built.step.component, version_id));
+        let version = &history.versions[version_id];
+        saved.push((
+            version_id,
+            built.manifest,
+            built.wasm_bytes,
+            version.provenance.clone(),
+            version.artifacts(),
+            version.guardrail_violations.clone(),
+        ));
+    }
+    let group_id = history.add_group(
+        format!("Plan: {}", truncate(&req.prompt, 40)),
+        base_version_id,
+        results.iter().filter_map(|r| r.version_id).collect(),
+    );
+    logs.push(format!("🧺 Recorded as rollback group {}", group_id));
+    if history.require_review {
+        logs.push("📝 Awaiting review before activation".to_string());
+    }
+    drop(history);
+
+    for (version_id, manifest, wasm_bytes, provenance, artifacts, violations) in saved {
+        report_guardrails(&state, version_id, &violations, &mut logs).await;
+        register_component(&state, manifest, &wasm_bytes, provenance, artifacts).await?;
+    }
+    let components: Vec<_> = results.iter().map(|r| r.component.as_str()).collect();
+    info!(steps = results.len(), "🗺️  Plan committed: {}", components.join(", "));
+    record_audit(
+        &state,
+        "plan",
+        results.last().and_then(|r| r.version_id),
+        "committed",
+        format!("{} ({})", truncate(&req.prompt, 80), components.join(", ")),
+    )
+    .await;
+
+    Ok(Json(PlanResponse {
+        success: true,
+        plan: Some(plan),
+        steps: results,
+        base_version_id,
+        group_id: Some(group_id),
+        error: None,
+        logs,
+    }))
+}
+
+/// Generate and compile one step, without saving it
+async fn build_step(state: &AppState, step: PlanStep, index: usize, logs: &mut Vec<String>) -> Result<BuiltStep, String> {
+    let permissions = {
+        let registry = state.registry.lock().await;
+        registry
+            .find_by_name(&step.component)
+            .and_then(|id| registry.manifest(&id))
+            .map(|manifest| manifest.permissions.clone())
+            .unwrap_or_default()
+    };
+    let manifest = ComponentManifest {
+        name: step.component.clone(),
+        description: step.prompt.clone(),
+        slots: step.slots.clone(),
+        permissions,
+    };
+
+    let mut context = PromptContext::gather(state, Some(&step.component)).await;
+    if step.action == StepAction::Modify && !context.has_source() {
+        let history = state.versions.lock().await;
+        if let Some(version) = history.versions.iter().rev().find(|v| v.manifest.name == step.component) {
+            context = context.with_source(step.component.clone(), version.rust_code.clone());
+        }
+    }
+    let conversation = vec![
+        Message {
+            role: "user".to_string(),
+            content: context.system_prompt(),
+        },
+        Message {
+            role: "user".to_string(),
+            content: create_generation_request(&step.prompt, &manifest, &[]),
+        },
+    ];
+
+    let (draft, _) = generate_draft(state, conversation, &step.prompt, index + 1, logs)
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(error) = &draft.compilation_error {
+        return Err(error.clone());
+    }
+    let wasm_bytes = base64_decode(draft.wasm_base64.as_deref().unwrap_or_default()).map_err(|e| e.to_string())?;
+    // Catch modules the runtime won't load before anything is saved
+    WasmComponent::load(&wasm_bytes, manifest.permissions.clone())
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(BuiltStep {
+        step,
+        manifest,
+        draft,
+        wasm_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(component: &str, action: StepAction, depends_on: &[&str], slots: &[&str]) -> PlanStep {
+        PlanStep {
+            component: component.to_string(),
+            action,
+            prompt: format!("The {}", component),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            slots: slots
+                .iter()
+                .map(|child| SlotDecl {
+                    name: child.to_string(),
+                    component: child.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_fenced_plan() {
+        let reply = "```json\n{\"steps\": [{\"component\": \"crm-page\", \"action\": \"create\", \"prompt\": \"A CRM page\"}]}\n```";
+        let plan = Plan::parse(reply).unwrap();
+
+        assert_eq!(plan.steps.len(), 1);
+        assert_eq!(plan.steps[0].action, StepAction::Create);
+        assert!(plan.steps[0].slots.is_empty());
+        assert!(Plan::parse("I can't plan that").is_err());
+    }
+
+    #[test]
+    fn test_steps_follow_their_dependencies() {
+        let plan = Plan {
+            steps: vec![
+                step("crm-page", StepAction::Create, &[], &["contact-list", "deal-board"]),
+                step("contact-list", StepAction::Create, &[], &[]),
+                step("deal-board", StepAction::Create, &["contact-list"], &[]),
+                step("header", StepAction::Modify, &[], &[]),
+            ],
+        };
+        let existing = vec!["header".to_string()];
+
+        let order: Vec<_> = plan.ordered(&existing).unwrap().iter().map(|s| s.component.as_str()).collect();
+        assert_eq!(order, ["contact-list", "deal-board", "crm-page", "header"]);
+    }
+
+    #[test]
+    fn test_invalid_plans_are_rejected() {
+        let existing = vec!["header".to_string()];
+        let invalid = |steps: Vec<PlanStep>| Plan { steps }.ordered(&existing).unwrap_err();
+
+        assert!(invalid(Vec::new()).contains("no steps"));
+        assert!(invalid(vec![step("header", StepAction::Create, &[], &[])]).contains("modify it instead"));
+        assert!(invalid(vec![step("footer", StepAction::Modify, &[], &[])]).contains("doesn't exist"));
+        assert!(invalid(vec![step("page", StepAction::Create, &[], &["missing"])]).contains("unknown component"));
+        let cycle = invalid(vec![
+            step("a", StepAction::Create, &["b"], &[]),
+            step("b", StepAction::Create, &[], &["a"]),
+        ]);
+        assert!(cycle.contains("cycle: a, b"));
+    }
+
+    #[test]
+    fn test_rolling_back_a_plan_group_restores_what_it_replaced() {
+        let mut history = crate::VersionHistory::new();
+        let mut add = |component: &str| {
+            history.add_version(
+                component.to_string(),
+                component.to_string(),
+                "pub fn render() -> String { String::new() }".to_string(),
+                vec![0; 8],
+                String::new(),
+                true,
+                ComponentManifest {
+                    name: component.to_string(),
+                    ..Default::default()
+                },
+                Provenance::ai(component, "test-model"),
+                true,
+            )
+        };
+        let header = add("header");
+        let base = add("page");
+        let plan = vec![add("header"), add("contact-list"), add("page")];
+        let group = history.add_group("Plan: A CRM page".to_string(), Some(base), plan.clone());
+
+        let restored = history.rollback_group(group).unwrap();
+        assert_eq!(
+            restored,
+            [
+                ("header".to_string(), Some(header)),
+                ("contact-list".to_string(), None),
+                ("page".to_string(), Some(base)),
+            ]
+        );
+        assert_eq!(history.get_current().map(|v| v.id), Some(base));
+        assert!(history.active_versions().iter().all(|v| !plan.contains(&v.id)));
+        assert!(history.check_activation(plan[0]).is_err());
+        assert!(history.rollback_group(group).unwrap_err().contains("already"));
+    }
+}
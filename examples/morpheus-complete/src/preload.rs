@@ -0,0 +1,144 @@
+//! Startup preloading.
+//!
+//! A page that mounts its components one at a time waits for each render
+//! response, then each bundle, in turn. `GET /api/preload` answers for every
+//! active component at once: what `GET /api/components/{name}/render` would
+//! return with `manifest=true`, plus a fetch priority. The components the
+//! page mounts (`roots`) and everything in their slots are `high`, the rest
+//! `low`, so they download first without the others waiting for a later
+//! request.
+//!
+//! `preload()` in `morpheus-host.js` fetches and instantiates them all in
+//! parallel, with those priorities as `fetch()` hints, and hosts started
+//! afterwards render the preloaded instances instead of loading their own.
+
+use crate::{resolve_render, AppError, AppState, RenderQuery, RenderResponse};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use morpheus_runtime::{ComponentRegistry, SlotMount};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use tracing::warn;
+
+/// Query for the preload manifest
+#[derive(Deserialize, JsonSchema)]
+pub struct PreloadQuery {
+    /// Client session, for A/B experiment assignment
+    #[serde(default)]
+    session: Option<String>,
+    /// Comma-separated components the page mounts, e.g. `main,sidebar`
+    #[serde(default)]
+    roots: String,
+}
+
+/// How soon a client should fetch a component, as `fetch()`'s `priority`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FetchPriority {
+    /// Mounted by the page, or in the slots of a component it mounts
+    High,
+    /// The page didn't say what it mounts
+    Auto,
+    /// Not mounted yet, e.g. shown later or by another page
+    Low,
+}
+
+/// What to render for one component, and how soon to fetch it
+#[derive(Serialize, JsonSchema)]
+pub struct PreloadEntry {
+    priority: FetchPriority,
+    #[serde(flatten)]
+    render: RenderResponse,
+}
+
+/// What to render for every active component, highest priority first
+#[derive(Serialize, JsonSchema)]
+pub struct PreloadManifest {
+    components: Vec<PreloadEntry>,
+}
+
+/// What to render for every active component, for clients to load in
+/// parallel before their first render
+pub async fn get_preload(
+    State(state): State<AppState>,
+    Query(query): Query<PreloadQuery>,
+) -> Result<Json<PreloadManifest>, AppError> {
+    let roots: Vec<&str> = query.roots.split(',').map(str::trim).filter(|root| !root.is_empty()).collect();
+    let registry = state.registry.lock().await;
+    let mut mounted = BTreeSet::new();
+    for root in &roots {
+        if let Some(id) = registry.find_by_name(root) {
+            mounted.insert(root.to_string());
+            mounted_slots(&registry, &registry.resolve_slots(&id).unwrap_or_default(), &mut mounted);
+        }
+    }
+    let names = registry.list().map(|metadata| metadata.name.clone()).collect();
+    drop(registry);
+
+    let render_query = RenderQuery {
+        session: query.session,
+        manifest: true,
+    };
+    let mut components = Vec::new();
+    for (priority, name) in prioritise(names, !roots.is_empty(), &mounted) {
+        match resolve_render(&state, name.clone(), &render_query).await {
+            Ok(render) => components.push(PreloadEntry { priority, render }),
+            Err(e) => warn!(component_id = %name, "Not preloading component: {}", e),
+        }
+    }
+    Ok(Json(PreloadManifest { components }))
+}
+
+/// Add the components mounted in `slots`, and in theirs, to `mounted`
+fn mounted_slots(registry: &ComponentRegistry, slots: &[SlotMount], mounted: &mut BTreeSet<String>) {
+    for slot in slots {
+        if let Some(metadata) = registry.metadata(&slot.component) {
+            mounted.insert(metadata.name.clone());
+        }
+        mounted_slots(registry, &slot.children, mounted);
+    }
+}
+
+/// Each component's fetch priority, sorted highest first, then by name
+fn prioritise(names: Vec<String>, has_roots: bool, mounted: &BTreeSet<String>) -> Vec<(FetchPriority, String)> {
+    let mut prioritised: Vec<_> = names
+        .into_iter()
+        .map(|name| {
+            let priority = match (has_roots, mounted.contains(&name)) {
+                (false, _) => FetchPriority::Auto,
+                (true, true) => FetchPriority::High,
+                (true, false) => FetchPriority::Low,
+            };
+            (priority, name)
+        })
+        .collect();
+    prioritised.sort();
+    prioritised
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mounted_components_come_first() {
+        let names = ["sidebar", "main", "chart", "settings"].map(String::from).to_vec();
+        let mounted = BTreeSet::from(["main".to_string(), "chart".to_string()]);
+
+        let prioritised = prioritise(names.clone(), true, &mounted);
+        assert_eq!(
+            prioritised,
+            vec![
+                (FetchPriority::High, "chart".to_string()),
+                (FetchPriority::High, "main".to_string()),
+                (FetchPriority::Low, "settings".to_string()),
+                (FetchPriority::Low, "sidebar".to_string()),
+            ]
+        );
+        assert!(prioritise(names, false, &mounted).iter().all(|(priority, _)| *priority == FetchPriority::Auto));
+        assert_eq!(serde_json::to_value(FetchPriority::High).unwrap(), "high");
+    }
+}
@@ -0,0 +1,142 @@
+//! Model routing by task complexity.
+//!
+//! Most requests are small tweaks to an existing component ("make the
+//! button purple") that a small, cheap model handles fine; new components
+//! and structural changes need a frontier model. With
+//! `MORPHEUS_CHEAP_MODEL` set, generations classified as trivial go to the
+//! cheap model first and escalate to the frontier model once it has failed
+//! to compile `MORPHEUS_ESCALATE_AFTER` times (default 2). Everything else,
+//! and every other AI call, uses the frontier model (`MORPHEUS_MODEL`).
+
+use crate::AI_MODEL;
+use serde::Serialize;
+
+/// Failed compiles on the cheap model before escalating
+const DEFAULT_ESCALATE_AFTER: u32 = 2;
+
+/// Trivial requests are at most this many words
+const MAX_TRIVIAL_WORDS: usize = 12;
+
+/// Words that mark a request as a cosmetic tweak
+const TWEAK_WORDS: &[&str] = &[
+    "color", "colour", "background", "text", "rename", "label", "title", "font", "bold", "italic", "size",
+    "bigger", "smaller", "larger", "padding", "margin", "spacing", "border", "rounded", "shadow", "align",
+    "center", "wording", "typo", "darker", "lighter", "red", "blue", "green", "purple", "yellow", "orange",
+    "pink", "gray", "grey", "black", "white",
+];
+
+/// Words that mark a request as structural, whatever else it says
+const STRUCTURAL_WORDS: &[&str] = &[
+    "add", "remove", "new", "page", "list", "table", "form", "state", "event", "events", "component", "slot",
+    "layout", "feature", "logic", "filter", "sort", "search", "validate", "validation", "store", "save",
+    "load", "chart", "refactor", "rewrite", "split", "merge",
+];
+
+/// How much a request changes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Complexity {
+    /// A cosmetic tweak to an existing component
+    Trivial,
+    /// A new component or a change to its structure or behavior
+    Structural,
+}
+
+/// Guess a request's complexity. Only modifications of an existing
+/// component can be trivial.
+pub fn classify(prompt: &str, modifies_existing: bool) -> Complexity {
+    let prompt = prompt.to_lowercase();
+    let words: Vec<&str> = prompt.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect();
+    let trivial = modifies_existing
+        && words.len() <= MAX_TRIVIAL_WORDS
+        && words.iter().any(|w| TWEAK_WORDS.contains(w))
+        && !words.iter().any(|w| STRUCTURAL_WORDS.contains(w));
+    if trivial {
+        Complexity::Trivial
+    } else {
+        Complexity::Structural
+    }
+}
+
+/// Which models generations use
+#[derive(Clone, Debug)]
+pub struct GenerationPolicy {
+    /// Model for structural changes, escalations and all other AI calls
+    pub frontier_model: String,
+    /// Model for trivial tweaks; `None` sends everything to the frontier model
+    pub cheap_model: Option<String>,
+    /// Failed compiles on the cheap model before escalating
+    pub escalate_after: u32,
+}
+
+impl Default for GenerationPolicy {
+    fn default() -> Self {
+        Self {
+            frontier_model: AI_MODEL.to_string(),
+            cheap_model: None,
+            escalate_after: DEFAULT_ESCALATE_AFTER,
+        }
+    }
+}
+
+impl GenerationPolicy {
+    /// Read `MORPHEUS_MODEL`, `MORPHEUS_CHEAP_MODEL` and `MORPHEUS_ESCALATE_AFTER`
+    pub fn from_env() -> anyhow::Result<Self> {
+        let mut policy = Self::default();
+        if let Ok(model) = std::env::var("MORPHEUS_MODEL") {
+            policy.frontier_model = model;
+        }
+        policy.cheap_model = std::env::var("MORPHEUS_CHEAP_MODEL").ok().filter(|m| !m.is_empty());
+        if let Ok(after) = std::env::var("MORPHEUS_ESCALATE_AFTER") {
+            policy.escalate_after = after.parse()?;
+        }
+        Ok(policy)
+    }
+
+    /// Model for a generation's next attempt, after `failures` failed compiles
+    pub fn model_for(&self, complexity: Complexity, failures: u32) -> &str {
+        match (&self.cheap_model, complexity) {
+            (Some(cheap), Complexity::Trivial) if failures < self.escalate_after => cheap,
+            _ => &self.frontier_model,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("Change the color to purple", true), Complexity::Trivial);
+        assert_eq!(classify("Make the title bigger and bold", true), Complexity::Trivial);
+        // Structural words win
+        assert_eq!(classify("Add a purple reset button", true), Complexity::Structural);
+        // New components are never trivial
+        assert_eq!(classify("Change the color to purple", false), Complexity::Structural);
+        // Nor are long requests
+        let long = "Change the color of the header text to purple and also the footer text and the sidebar text too";
+        assert_eq!(classify(long, true), Complexity::Structural);
+        assert_eq!(classify("Make it better", true), Complexity::Structural);
+    }
+
+    #[test]
+    fn test_escalation() {
+        let policy = GenerationPolicy {
+            frontier_model: "frontier".to_string(),
+            cheap_model: Some("cheap".to_string()),
+            escalate_after: 2,
+        };
+
+        assert_eq!(policy.model_for(Complexity::Trivial, 0), "cheap");
+        assert_eq!(policy.model_for(Complexity::Trivial, 1), "cheap");
+        assert_eq!(policy.model_for(Complexity::Trivial, 2), "frontier");
+        assert_eq!(policy.model_for(Complexity::Structural, 0), "frontier");
+    }
+
+    #[test]
+    fn test_without_cheap_model_everything_goes_to_the_frontier() {
+        let policy = GenerationPolicy::default();
+        assert_eq!(policy.model_for(Complexity::Trivial, 0), AI_MODEL);
+    }
+}
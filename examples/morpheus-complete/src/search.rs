@@ -0,0 +1,156 @@
+//! Search across the source of stored versions
+//!
+//! `GET /api/search` finds where a function, type or Tailwind class is used
+//! across the components, as lines matching a literal or regular expression
+//! and as the items defined under that name. It searches each component's
+//! live version unless asked for every stored version. The index behind it
+//! ([`SourceIndex`]) is kept in the server state and picks up new versions
+//! as they are stored.
+//!
+//! Generation uses it too: [`usages`] finds the code-like terms of a
+//! request (`TodoItem`, `bg-slate-900`, backticked names) in the other
+//! components, so the AI keeps what it changes consistent with them.
+
+use crate::{AppError, AppState};
+use axum::extract::{Query, State};
+use axum::Json;
+use morpheus_compiler::search::{LineMatch, SourceIndex, SymbolMatch};
+use regex::{Regex, RegexBuilder};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use tokio::sync::MutexGuard;
+
+/// Lines returned when the query doesn't set a limit
+const DEFAULT_LIMIT: usize = 100;
+
+/// Usages of a request's terms included in the AI's context
+pub const MAX_USAGES: usize = 10;
+
+/// Query for `GET /api/search`
+#[derive(Deserialize, JsonSchema)]
+pub struct SearchQuery {
+    /// Text to find
+    pub q: String,
+    /// Treat `q` as a regular expression
+    #[serde(default)]
+    pub regex: bool,
+    /// Ignore case
+    #[serde(default)]
+    pub ignore_case: bool,
+    /// Only this component's versions
+    #[serde(default)]
+    pub component: Option<String>,
+    /// Search every stored version, not only each component's live one
+    #[serde(default)]
+    pub all_versions: bool,
+    /// Most lines to return (default 100)
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Where a search term occurs
+#[derive(Serialize)]
+pub struct SearchResponse {
+    /// Items named like `q` (for literal searches), exact names first
+    pub symbols: Vec<SymbolMatch>,
+    /// Matching lines, newest version first
+    pub lines: Vec<LineMatch>,
+    /// Whether more lines matched than were returned
+    pub truncated: bool,
+}
+
+/// Search the source of stored versions
+pub async fn search(
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<SearchResponse>, AppError> {
+    let literal = regex::escape(&query.q);
+    let pattern = RegexBuilder::new(if query.regex { &query.q } else { &literal })
+        .case_insensitive(query.ignore_case)
+        .build()
+        .map_err(|e| AppError::ApiError(format!("Invalid pattern: {}", e)))?;
+    let versions = searched_versions(&state, query.component.as_deref(), query.all_versions).await;
+
+    let index = index(&state).await;
+    let mut lines = index.grep(&pattern, |id| versions.contains(&id));
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    let truncated = lines.len() > limit;
+    lines.truncate(limit);
+    let symbols = match query.regex {
+        true => Vec::new(),
+        false => index.symbols(&query.q, |id| versions.contains(&id)),
+    };
+    Ok(Json(SearchResponse { symbols, lines, truncated }))
+}
+
+/// Lines of the live components other than `component` that use the
+/// code-like terms of `prompt`, at most [`MAX_USAGES`]
+pub async fn usages(state: &AppState, prompt: &str, component: &str) -> Vec<LineMatch> {
+    let terms = code_terms(prompt);
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    let alternatives: Vec<_> = terms.iter().map(|term| regex::escape(term)).collect();
+    let Ok(pattern) = Regex::new(&format!(r"(^|[^\w-])({})($|[^\w-])", alternatives.join("|"))) else {
+        return Vec::new();
+    };
+    let versions = searched_versions(state, None, false).await;
+    let mut lines = index(state).await.grep(&pattern, |id| versions.contains(&id));
+    lines.retain(|line| line.component != component);
+    lines.truncate(MAX_USAGES);
+    lines
+}
+
+/// Words of a request that look like code: backticked, `snake_case`,
+/// `kebab-case` (Tailwind classes), `CamelCase` or paths
+fn code_terms(prompt: &str) -> BTreeSet<String> {
+    let mut terms: BTreeSet<String> = prompt.split('`').skip(1).step_by(2).map(|t| t.trim().to_string()).collect();
+    for word in prompt.split_whitespace() {
+        let word = word.trim_matches(|c: char| !c.is_alphanumeric() && c != '_');
+        let inner_upper = word.chars().skip(1).any(|c| c.is_uppercase()) && word.chars().any(|c| c.is_lowercase());
+        if word.len() >= 3 && (word.contains(['_', '-', ':']) || inner_upper) {
+            terms.insert(word.to_string());
+        }
+    }
+    terms.retain(|term| term.len() >= 3);
+    terms
+}
+
+/// IDs of the versions a search covers
+async fn searched_versions(state: &AppState, component: Option<&str>, all_versions: bool) -> BTreeSet<usize> {
+    let history = state.versions.lock().await;
+    let versions = match all_versions {
+        true => history.versions.iter().collect(),
+        false => history.active_versions(),
+    };
+    versions
+        .into_iter()
+        .filter(|v| component.is_none_or(|component| v.manifest.name == component))
+        .map(|v| v.id)
+        .collect()
+}
+
+/// The search index, with every stored version added
+async fn index(state: &AppState) -> MutexGuard<'_, SourceIndex> {
+    let mut index = state.search_index.lock().await;
+    let history = state.versions.lock().await;
+    for version in &history.versions {
+        if !index.contains(version.id) {
+            index.add(version.id, version.manifest.name.as_str(), &version.rust_code);
+        }
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_terms_skip_plain_words() {
+        let terms = code_terms("Make the TodoItem rows bg-slate-900, like `render_row` in the header. Thanks!");
+        assert_eq!(terms.into_iter().collect::<Vec<_>>(), ["TodoItem", "bg-slate-900", "render_row"]);
+        assert!(code_terms("Add a counter with a reset button").is_empty());
+    }
+}
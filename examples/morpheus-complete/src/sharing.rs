@@ -0,0 +1,374 @@
+//! Read-only spectator links.
+//!
+//! `POST /api/share` creates a link to the live component,
+//! `/spectate.html?share=<token>`. Whoever opens it sees the current version
+//! and its state as they change, through the same reload events and state
+//! sync the editor uses, but can't generate, roll back or change state:
+//! [`authorize`] lets requests carrying a share token (the `share` query
+//! parameter, or the `X-Morpheus-Share` header) reach only the endpoints a
+//! spectator needs, and state sync ignores their writes.
+//!
+//! The API is otherwise open, so sharing needs `MORPHEUS_OWNER_TOKEN`:
+//! with it, every other API request needs `Authorization: Bearer <token>`,
+//! and dropping the share token from a link doesn't give full access.
+//! Without it, links can't be created and share tokens are refused.
+
+use crate::{record_audit, AppError, AppState};
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderMap, Method},
+    middleware::Next,
+    response::Response,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+use tracing::warn;
+
+/// Header carrying a share token (where the query parameter can't be used)
+pub const SHARE_HEADER: &str = "x-morpheus-share";
+
+/// Query parameter carrying a share token; browsers can't add headers to
+/// `EventSource` and `WebSocket` requests
+const SHARE_PARAM: &str = "share";
+
+/// A link granting read-only access to the live component
+#[derive(Clone, Debug, Serialize, JsonSchema)]
+pub struct ShareLink {
+    pub token: String,
+    pub label: Option<String>,
+    /// Page spectators open
+    pub url: String,
+    pub created_at: DateTime<Utc>,
+    /// When the link stops working (never if unset)
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Share links that haven't been revoked
+#[derive(Default)]
+pub struct ShareLinks {
+    links: Vec<ShareLink>,
+}
+
+impl ShareLinks {
+    /// Create a link, valid for `ttl` if given
+    pub fn create(&mut self, label: Option<String>, ttl: Option<Duration>) -> ShareLink {
+        let now = Utc::now();
+        self.links.retain(|link| link.expires_at.is_none_or(|expires| expires > now));
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        let link = ShareLink {
+            url: format!("/spectate.html?{}={}", SHARE_PARAM, token),
+            token,
+            label,
+            created_at: now,
+            expires_at: ttl.and_then(|ttl| now.checked_add_signed(ttl)),
+        };
+        self.links.push(link.clone());
+        link
+    }
+
+    /// Revoke a link; false if there was none
+    pub fn revoke(&mut self, token: &str) -> bool {
+        let before = self.links.len();
+        self.links.retain(|link| link.token != token);
+        self.links.len() < before
+    }
+
+    /// The link with `token`, unless it has expired
+    pub fn find(&self, token: &str, now: DateTime<Utc>) -> Option<&ShareLink> {
+        self.links
+            .iter()
+            .find(|link| link.token == token && link.expires_at.is_none_or(|expires| expires > now))
+    }
+
+    pub fn list(&self) -> Vec<ShareLink> {
+        self.links.clone()
+    }
+}
+
+/// Marks a request made with a share link
+#[derive(Clone, Copy, Debug)]
+pub struct Spectator;
+
+/// Why a request was refused
+#[derive(Debug, thiserror::Error)]
+pub enum AccessError {
+    #[error("This share link is invalid, expired or revoked")]
+    InvalidLink,
+    #[error("Share links are read-only; {0} is not available to spectators")]
+    ReadOnly(String),
+    #[error("Missing or wrong owner token")]
+    Unauthorized,
+    #[error("Share links need MORPHEUS_OWNER_TOKEN; without it, a link without its share token would give full access")]
+    SharingDisabled,
+}
+
+impl AccessError {
+    /// Machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            AccessError::InvalidLink => "invalid_share_link",
+            AccessError::ReadOnly(_) => "read_only",
+            AccessError::Unauthorized => "unauthorized",
+            AccessError::SharingDisabled => "sharing_disabled",
+        }
+    }
+}
+
+/// The share token a request carries, if any
+pub fn share_token(headers: &HeaderMap, query: Option<&str>) -> Option<String> {
+    if let Some(token) = headers.get(SHARE_HEADER).and_then(|value| value.to_str().ok()) {
+        return Some(token.to_string());
+    }
+    query?.split('&').find_map(|pair| match pair.split_once('=') {
+        Some((SHARE_PARAM, token)) => Some(token.to_string()),
+        _ => None,
+    })
+}
+
+/// Whether a request carries the owner token, compared in constant time
+fn is_owner(headers: &HeaderMap, owner_token: &str) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|bearer| bool::from(bearer.as_bytes().ct_eq(owner_token.as_bytes())))
+}
+
+/// Whether a request needs the owner token: the API, and the server-rendered
+/// pages, which show source and history and edit versions
+fn owner_only(path: &str) -> bool {
+    (path.starts_with("/api/") && path != "/api/health") || path.starts_with("/pages/")
+}
+
+/// Whether a spectator may make a request: watching the live version (its
+/// module, reload events, state sync, translations and theme) from the
+/// spectator page. Anything not listed here is refused.
+fn spectator_allowed(method: &Method, path: &str, current_version: Option<usize>) -> bool {
+    if method != Method::GET {
+        return false;
+    }
+    match path {
+        "/spectate.html" | "/api/spectate" | "/api/reloads" | "/api/state/sync" | "/api/themes/events" => true,
+        "/api/health" => true,
+        _ if path.strip_prefix("/api/i18n/").is_some_and(|locale| !locale.is_empty() && !locale.contains('/')) => true,
+        _ => path
+            .strip_prefix("/api/versions/")
+            .and_then(|rest| rest.split_once('/'))
+            .is_some_and(|(id, file)| {
+                matches!(file, "component.wasm" | "component.js") && id.parse().ok() == current_version
+            }),
+    }
+}
+
+/// Restrict share links to spectating and, with `MORPHEUS_OWNER_TOKEN`,
+/// everything else to the owner; without it, refuse share links
+pub async fn authorize(State(state): State<AppState>, mut request: Request, next: Next) -> Result<Response, AppError> {
+    let path = request.uri().path().to_string();
+    if let Some(token) = share_token(request.headers(), request.uri().query()) {
+        if state.owner_token.is_none() {
+            return Err(AccessError::SharingDisabled.into());
+        }
+        if state.shares.lock().await.find(&token, Utc::now()).is_none() {
+            return Err(AccessError::InvalidLink.into());
+        }
+        let current_version = state.versions.lock().await.get_current().map(|v| v.id);
+        if !spectator_allowed(request.method(), &path, current_version) {
+            warn!("👀 Refused {} {} with a share link", request.method(), path);
+            return Err(AccessError::ReadOnly(format!("{} {}", request.method(), path)).into());
+        }
+        request.extensions_mut().insert(Spectator);
+    } else if let Some(owner_token) = &state.owner_token {
+        if owner_only(&path) && !is_owner(request.headers(), owner_token) {
+            return Err(AccessError::Unauthorized.into());
+        }
+    }
+    Ok(next.run(request).await)
+}
+
+/// Request to create a share link
+#[derive(Deserialize, JsonSchema)]
+pub struct ShareRequest {
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Seconds until the link expires (never if unset)
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+}
+
+/// Create a read-only link to the live component; only with an owner token
+pub async fn create_share(
+    State(state): State<AppState>,
+    Json(req): Json<ShareRequest>,
+) -> Result<Json<ShareLink>, AppError> {
+    if state.owner_token.is_none() {
+        return Err(AccessError::SharingDisabled.into());
+    }
+    let ttl = req.ttl_secs.and_then(|secs| Duration::try_seconds(i64::try_from(secs).ok()?));
+    let link = state.shares.lock().await.create(req.label, ttl);
+    let detail = link.label.clone().unwrap_or_default();
+    record_audit(&state, "share", None, "created", detail).await;
+    Ok(Json(link))
+}
+
+/// List share links
+pub async fn list_shares(State(state): State<AppState>) -> Json<Vec<ShareLink>> {
+    Json(state.shares.lock().await.list())
+}
+
+/// Revoke a share link; spectators using it are refused from then on
+pub async fn revoke_share(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    if !state.shares.lock().await.revoke(&token) {
+        return Err(AppError::ApiError("Share link not found".to_string()));
+    }
+    record_audit(&state, "share", None, "revoked", String::new()).await;
+    Ok(Json(serde_json::json!({ "revoked": true })))
+}
+
+/// What a spectator sees: the live version and its state
+#[derive(Serialize, JsonSchema)]
+pub struct SpectatorView {
+    /// The share link's label
+    pub label: Option<String>,
+    pub version_id: Option<usize>,
+    pub name: Option<String>,
+    pub wasm_base64: Option<String>,
+    pub js_glue: Option<String>,
+    pub state: Option<serde_json::Value>,
+    /// Revision of `state`, as in state sync snapshots
+    pub state_revision: u64,
+}
+
+/// Query naming the share link
+#[derive(Deserialize, JsonSchema)]
+pub struct SpectateQuery {
+    #[serde(default)]
+    pub share: Option<String>,
+}
+
+/// The live version and state, for a spectator's first render
+pub async fn spectate(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<SpectateQuery>,
+) -> Json<SpectatorView> {
+    let label = match share_token(&headers, None).or(query.share) {
+        Some(token) => state.shares.lock().await.find(&token, Utc::now()).and_then(|link| link.label.clone()),
+        None => None,
+    };
+    let history = state.versions.lock().await;
+    let current = history.get_current();
+    Json(SpectatorView {
+        label,
+        version_id: current.map(|v| v.id),
+        name: current.map(|v| v.name.clone()),
+        wasm_base64: current.map(|v| v.wasm_base64.clone()),
+        js_glue: current.map(|v| v.js_glue.clone()),
+        state: history.current_state.clone(),
+        state_revision: history.state_revision,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_links_expire_and_revoke() {
+        let mut links = ShareLinks::default();
+        let forever = links.create(Some("Demo".to_string()), None);
+        let brief = links.create(None, Some(Duration::minutes(5)));
+        let now = Utc::now();
+
+        assert_eq!(forever.url, format!("/spectate.html?share={}", forever.token));
+        assert!(links.find(&brief.token, now).is_some());
+        assert!(links.find(&brief.token, now + Duration::minutes(6)).is_none());
+        assert!(links.revoke(&forever.token));
+        assert!(links.find(&forever.token, now).is_none());
+        assert!(!links.revoke(&forever.token));
+    }
+
+    #[test]
+    fn test_spectators_can_only_watch() {
+        let get = |path: &str| spectator_allowed(&Method::GET, path, Some(3));
+
+        assert!(get("/spectate.html"));
+        assert!(get("/api/reloads"));
+        assert!(get("/api/state/sync"));
+        assert!(get("/api/versions/3/component.wasm"));
+        assert!(get("/api/i18n/fr"));
+        assert!(!get("/api/i18n"));
+        assert!(get("/api/themes/events"));
+        assert!(!get("/api/themes"));
+        assert!(!get("/api/versions/2/component.wasm"));
+        assert!(!get("/api/versions/3/sbom"));
+        assert!(!get("/api/history"));
+        assert!(!get("/x/orders"));
+        assert!(!get("/index.html"));
+        assert!(!get("/"));
+        assert!(!get("/api/i18n/fr/extra"));
+        assert!(!get("/pages/history"));
+        assert!(!get("/pages/history/3"));
+        assert!(!spectator_allowed(&Method::POST, "/api/generate", Some(3)));
+        assert!(!spectator_allowed(&Method::POST, "/api/rollback", Some(3)));
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(share_token(&headers, Some("format=json&share=abc")).as_deref(), Some("abc"));
+        headers.insert(SHARE_HEADER, "def".parse().unwrap());
+        assert_eq!(share_token(&headers, None).as_deref(), Some("def"));
+    }
+
+    #[test]
+    fn test_owner_token_must_match_exactly() {
+        let bearer = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::AUTHORIZATION, value.parse().unwrap());
+            headers
+        };
+
+        assert!(is_owner(&bearer("Bearer s3cret"), "s3cret"));
+        assert!(!is_owner(&bearer("Bearer s3cre"), "s3cret"));
+        assert!(!is_owner(&bearer("Bearer s3cret2"), "s3cret"));
+        assert!(!is_owner(&bearer("s3cret"), "s3cret"));
+        assert!(!is_owner(&HeaderMap::new(), "s3cret"));
+        assert!(owner_only("/api/history"));
+        assert!(owner_only("/pages/history"));
+        assert!(!owner_only("/api/health"));
+        assert!(!owner_only("/spectate.html"));
+    }
+
+    #[tokio::test]
+    async fn test_spectators_cannot_reach_pages_or_edit() {
+        use axum::{body::Body, http::StatusCode, routing::get, Router};
+        use tower::ServiceExt;
+
+        let mut state = AppState::for_tests().await;
+        state.owner_token = Some("s3cret".to_string());
+        let share = state.shares.lock().await.create(None, None).token;
+        let app = Router::new()
+            .route("/pages/history", get(|| async { "history" }))
+            .route("/api/versions/:id/edit", axum::routing::post(|| async { "edited" }))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), authorize));
+        let send = |method: Method, uri: String, auth: Option<&str>| {
+            let mut request = Request::builder().method(method).uri(uri);
+            if let Some(auth) = auth {
+                request = request.header(header::AUTHORIZATION, auth);
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        let spectating = send(Method::GET, format!("/pages/history?share={}", share), None).await.unwrap();
+        assert_eq!(spectating.status(), StatusCode::FORBIDDEN);
+        let editing = send(Method::POST, format!("/api/versions/1/edit?share={}", share), None).await.unwrap();
+        assert_eq!(editing.status(), StatusCode::FORBIDDEN);
+        let anonymous = send(Method::GET, "/pages/history".to_string(), None).await.unwrap();
+        assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+        let owner = send(Method::GET, "/pages/history".to_string(), Some("Bearer s3cret")).await.unwrap();
+        assert_eq!(owner.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,144 @@
+//! Snapshot state sync between connected clients.
+//!
+//! Outside CRDT mode, `/api/state/sync` exchanges whole state snapshots.
+//! Each client gets the current [`StateSnapshot`] when it connects and
+//! again whenever the state changes (through the socket, `POST /api/state`,
+//! a domain event or a rollback), and may send `{"state": ...}` over the
+//! socket instead of posting it (unless they're spectators, see
+//! [`crate::sharing`]). The last write wins: every change gets the
+//! next revision, and clients ignore snapshots older than the one they have.
+//!
+//! Changes arriving within [`DEBOUNCE`] of each other are sent as one
+//! snapshot, so a client dragging a slider doesn't flood the others.
+
+use crate::sharing::AccessError;
+use crate::{apply_state_update, ws_frame, AppError, AppState, UpdateStateRequest};
+use axum::extract::ws::{Message as WsMessage, WebSocket};
+use morpheus_core::codec::Format;
+use serde::Serialize;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Window in which state changes are coalesced into one snapshot
+pub const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// The current state, pushed to sync clients
+#[derive(Debug, Clone, Serialize)]
+pub struct StateSnapshot {
+    /// Increases with every change; newer snapshots win
+    pub revision: u64,
+    /// Version that was live when the state was written
+    pub version_id: Option<usize>,
+    pub state: serde_json::Value,
+}
+
+/// Wait out the debounce window, absorbing notifications that arrive in
+/// it; false once the channel is closed
+async fn coalesce(changes: &mut broadcast::Receiver<()>, window: Duration) -> bool {
+    tokio::time::sleep(window).await;
+    loop {
+        match changes.try_recv() {
+            Ok(()) | Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+            Err(broadcast::error::TryRecvError::Empty) => return true,
+            Err(broadcast::error::TryRecvError::Closed) => return false,
+        }
+    }
+}
+
+/// The current snapshot, unless the client already has it
+async fn snapshot_after(state: &AppState, sent: Option<u64>) -> Option<StateSnapshot> {
+    let history = state.versions.lock().await;
+    if sent.is_some_and(|sent| sent >= history.state_revision) {
+        return None;
+    }
+    Some(StateSnapshot {
+        revision: history.state_revision,
+        version_id: history.get_current().map(|v| v.id),
+        state: history.current_state.clone().unwrap_or_default(),
+    })
+}
+
+/// Serve one snapshot sync client; `read_only` clients (spectators) get
+/// snapshots but can't send state
+pub async fn run_snapshot_sync(state: AppState, mut socket: WebSocket, format: Format, read_only: bool) {
+    let mut changes = state.state_sync.subscribe();
+    let mut sent = None;
+    loop {
+        if let Some(snapshot) = snapshot_after(&state, sent).await {
+            let Some(frame) = ws_frame(format, &snapshot) else { break };
+            if socket.send(frame).await.is_err() {
+                break;
+            }
+            sent = Some(snapshot.revision);
+        }
+
+        tokio::select! {
+            message = socket.recv() => {
+                let update = match message {
+                    Some(Ok(WsMessage::Text(text))) => Format::Json.from_slice::<UpdateStateRequest>(text.as_bytes()),
+                    Some(Ok(WsMessage::Binary(bytes))) => format.from_slice::<UpdateStateRequest>(&bytes),
+                    Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                // The client's own write comes back as the next snapshot,
+                // telling it the revision
+                let result = match update {
+                    Ok(_) if read_only => Err(AccessError::ReadOnly("changing state".to_string()).into()),
+                    Ok(update) => apply_state_update(&state, update.state).await,
+                    Err(e) => Err(AppError::from(e)),
+                };
+                if let Err(e) = result {
+                    let error = serde_json::json!({ "error": e.to_string() });
+                    let Some(frame) = ws_frame(format, &error) else { continue };
+                    if socket.send(frame).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            notification = changes.recv() => {
+                if matches!(notification, Err(broadcast::error::RecvError::Closed)) {
+                    break;
+                }
+                if !coalesce(&mut changes, DEBOUNCE).await {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_coalesce_absorbs_a_burst() {
+        let (tx, mut rx) = broadcast::channel(4);
+        for _ in 0..10 {
+            let _ = tx.send(());
+        }
+        // Lagged past the burst, then drained
+        assert!(coalesce(&mut rx, Duration::ZERO).await);
+        assert!(matches!(rx.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+
+        drop(tx);
+        assert!(!coalesce(&mut rx, Duration::ZERO).await);
+    }
+
+    #[test]
+    fn test_every_state_change_bumps_the_revision() {
+        let mut history = crate::VersionHistory::new();
+        assert_eq!(history.state_revision, 0);
+
+        history.update_state(serde_json::json!({ "count": 1 })).unwrap();
+        history.update_state(serde_json::json!({ "count": 2 })).unwrap();
+        assert_eq!(history.state_revision, 2);
+
+        let mut history = crate::VersionHistory::new().with_event_sourcing(true);
+        let component = history.current_component();
+        let event = morpheus_core::events::DomainEvent::new("set", serde_json::json!({ "count": 3 }));
+        history.record_event(component, event).unwrap();
+        assert_eq!(history.state_revision, 1);
+        assert_eq!(history.current_state, Some(serde_json::json!({ "count": 3 })));
+    }
+}
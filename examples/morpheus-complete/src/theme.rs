@@ -0,0 +1,226 @@
+//! Themes components are styled with
+//!
+//! The system prompt has the AI color components with the theme's CSS
+//! variables (`var(--morpheus-color-primary)`, see [`morpheus_core::theme`])
+//! rather than fixed colors, so switching to a dark theme restyles every
+//! mounted component without regenerating any of them:
+//!
+//! - `GET /api/themes`: the themes on offer, and which one is active
+//! - `POST /api/themes`: add a theme, or replace one with the same name
+//! - `POST /api/themes/active`: switch themes
+//! - `GET /api/themes/events`: the active theme, then each switch, as
+//!   server-sent `theme` events hosts set the variables from
+//!
+//! The active theme starts as `system`: each page uses the light or dark
+//! theme, whichever its OS prefers, and switches when the OS does, so
+//! events carry the theme for either color scheme (see
+//! [`ActiveTheme`]).
+//!
+//! `MORPHEUS_THEMES` names a JSON file of extra themes (an array of
+//! `{name, colors, spacing, typography}`), and `MORPHEUS_THEME` the one to
+//! start with.
+
+use crate::{AppError, AppState};
+use axum::{
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+    Json,
+};
+use futures_util::stream::{self, Stream};
+use morpheus_core::theme::{ActiveTheme, Theme, Themes};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{info, warn};
+
+/// The host's themes, announcing changes to the active one
+pub struct HostThemes {
+    themes: Mutex<Themes>,
+    changes: broadcast::Sender<ActiveTheme>,
+}
+
+impl Default for HostThemes {
+    fn default() -> Self {
+        Self::new(Themes::new())
+    }
+}
+
+impl HostThemes {
+    /// Offer `themes`
+    pub fn new(themes: Themes) -> Self {
+        Self {
+            themes: Mutex::new(themes),
+            changes: broadcast::channel(16).0,
+        }
+    }
+
+    /// The built-in themes plus those in `MORPHEUS_THEMES`, starting with
+    /// `MORPHEUS_THEME`
+    pub fn from_env() -> anyhow::Result<Self> {
+        let mut themes = Themes::new();
+        if let Ok(path) = std::env::var("MORPHEUS_THEMES") {
+            let extra: Vec<Theme> = serde_json::from_slice(&std::fs::read(&path)?)
+                .map_err(|e| anyhow::anyhow!("{}: {}", path, e))?;
+            for theme in extra {
+                themes.register(theme).map_err(|e| anyhow::anyhow!("{}: {}", path, e))?;
+            }
+        }
+        if let Ok(name) = std::env::var("MORPHEUS_THEME") {
+            themes.set_theme(&name).map_err(|e| anyhow::anyhow!("MORPHEUS_THEME: {}", e))?;
+        }
+        Ok(Self::new(themes))
+    }
+
+    /// Names of the themes on offer, and the active one's (possibly
+    /// `system`)
+    pub async fn names(&self) -> (Vec<String>, String) {
+        let themes = self.themes.lock().await;
+        let names = themes.list().into_iter().map(|theme| theme.name.clone()).collect();
+        (names, themes.active_name().to_string())
+    }
+
+    /// The active theme, for either color scheme
+    pub async fn current(&self) -> ActiveTheme {
+        self.themes.lock().await.current()
+    }
+
+    /// Add or replace a theme; replacing one in use restyles components
+    async fn register(&self, theme: Theme) -> morpheus_core::errors::Result<()> {
+        let mut themes = self.themes.lock().await;
+        let before = themes.current();
+        themes.register(theme)?;
+        let after = themes.current();
+        if after != before {
+            let _ = self.changes.send(after);
+        }
+        Ok(())
+    }
+
+    /// Make `name` (or `system`) the active theme, announcing it if it
+    /// wasn't already
+    async fn set_theme(&self, name: &str) -> morpheus_core::errors::Result<ActiveTheme> {
+        let mut themes = self.themes.lock().await;
+        let changed = themes.active_name() != name;
+        let theme = themes.set_theme(name)?;
+        if changed {
+            let _ = self.changes.send(theme.clone());
+        }
+        Ok(theme)
+    }
+}
+
+/// Response for `GET /api/themes`
+#[derive(Serialize, JsonSchema)]
+pub struct ThemesResponse {
+    /// Name of the active theme, or `system`
+    pub active: String,
+    pub themes: Vec<Theme>,
+}
+
+/// Request for `POST /api/themes/active`
+#[derive(Deserialize, JsonSchema)]
+pub struct SetThemeRequest {
+    /// A theme's name, or `system` to follow each user's OS
+    pub name: String,
+}
+
+/// The themes on offer, and which one is active
+pub async fn list_themes(State(state): State<AppState>) -> Json<ThemesResponse> {
+    let themes = state.themes.themes.lock().await;
+    Json(ThemesResponse {
+        active: themes.active_name().to_string(),
+        themes: themes.list().into_iter().cloned().collect(),
+    })
+}
+
+/// Add a theme, or replace the one with its name
+pub async fn register_theme(
+    State(state): State<AppState>,
+    Json(theme): Json<Theme>,
+) -> Result<Json<ThemesResponse>, AppError> {
+    let name = theme.name.clone();
+    state.themes.register(theme).await?;
+    info!(theme = %name, "🎨 Registered theme");
+    Ok(list_themes(State(state)).await)
+}
+
+/// Switch themes, restyling every mounted component
+pub async fn set_active_theme(
+    State(state): State<AppState>,
+    Json(req): Json<SetThemeRequest>,
+) -> Result<Json<ActiveTheme>, AppError> {
+    let theme = state.themes.set_theme(&req.name).await?;
+    info!(theme = %theme.name, "🎨 Switched theme");
+    Ok(Json(theme))
+}
+
+/// Stream the active theme, then each switch, as server-sent `theme` events
+pub async fn theme_events(State(state): State<AppState>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    // Subscribe first so no switch between the snapshot and the stream is lost
+    let changes = state.themes.changes.subscribe();
+    let current = state.themes.current().await;
+    let events = stream::unfold((Some(current), changes), |(next, mut changes)| async move {
+        let theme = match next {
+            Some(theme) => theme,
+            None => loop {
+                match changes.recv().await {
+                    Ok(theme) => break theme,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            },
+        };
+        let event = Event::default().event("theme").json_data(&theme).unwrap_or_else(|e| {
+            warn!("Failed to encode theme event: {}", e);
+            Event::default().event("theme")
+        });
+        Some((Ok(event), (None, changes)))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morpheus_core::media::ColorScheme;
+
+    #[tokio::test]
+    async fn test_switches_and_active_replacements_are_announced() {
+        let themes = HostThemes::default();
+        let mut changes = themes.changes.subscribe();
+
+        themes.set_theme("system").await.unwrap();
+        assert!(changes.try_recv().is_err());
+        assert!(themes.set_theme("solarized").await.is_err());
+
+        let dark = themes.set_theme("dark").await.unwrap();
+        assert_eq!((dark.light.name.as_str(), dark.dark.name.as_str()), ("dark", "dark"));
+        assert_eq!(changes.try_recv().unwrap().name, "dark");
+
+        themes.register(Theme::light().with_color("primary", "#0d9488")).await.unwrap();
+        assert!(changes.try_recv().is_err());
+        let mut dark = Theme::dark();
+        dark.colors.insert("primary".to_string(), "#2dd4bf".to_string());
+        themes.register(dark).await.unwrap();
+        assert_eq!(changes.try_recv().unwrap().light.token("color.primary"), Some("#2dd4bf"));
+
+        let (names, active) = themes.names().await;
+        assert_eq!(names, vec!["dark", "light"]);
+        assert_eq!(active, "dark");
+    }
+
+    #[tokio::test]
+    async fn test_system_theme_has_a_theme_per_color_scheme() {
+        let themes = HostThemes::default();
+        let system = themes.current().await;
+        assert_eq!(system.name, "system");
+        assert_eq!(system.for_scheme(ColorScheme::Light).name, "light");
+        assert_eq!(system.for_scheme(ColorScheme::Dark).name, "dark");
+
+        // Changing the theme either scheme uses is a change for some pages
+        let mut changes = themes.changes.subscribe();
+        themes.register(Theme::light().with_color("primary", "#0d9488")).await.unwrap();
+        assert_eq!(changes.try_recv().unwrap().light.token("color.primary"), Some("#0d9488"));
+    }
+}
@@ -0,0 +1,192 @@
+//! Accessibility and performance gates
+//!
+//! With `MORPHEUS_VERIFIER_DIR` set, the compiler renders each browser
+//! build in headless Chrome, times its first render, counts long tasks and
+//! audits it with axe-core (see `morpheus_compiler::verify`). Every
+//! generated, fixed, autonomous, drafted or hand-edited proposal goes through
+//! [`compile_verified`], which compares the results with the live version's.
+//!
+//! The policy applies to the serious and critical accessibility violations
+//! the proposal *introduces*:
+//!
+//! - `warn` (the default): they are reported as diagnostics, and the
+//!   proposal is saved
+//! - `deny`: the proposal fails like a compile error, so the AI fix loops
+//!   feed the violations back to the AI and ask for another attempt
+//! - `off`: audits are only recorded
+//!
+//! Measurements are reported next to the live version's, and a proposal over
+//! the performance budget (`MORPHEUS_MAX_FIRST_RENDER_MS`,
+//! `MORPHEUS_MAX_BUNDLE_BYTES`, `MORPHEUS_MAX_LONG_TASKS`) fails the same
+//! way.
+//!
+//! Each version keeps its audit and measurements, as the baseline for the
+//! next proposal. When the live version was never audited, every violation
+//! counts as new.
+
+use crate::AppState;
+use morpheus_compiler::{CompilationError, CompilationResult, Compiler, Severity};
+use morpheus_core::accessibility::{self, AccessibilityViolation, Impact};
+use morpheus_core::errors::{MorpheusError, Result};
+use morpheus_core::performance::{PerformanceBudget, PerformanceMeasurements};
+use tracing::warn;
+
+/// Violations below this impact never block or warn
+const MIN_IMPACT: Impact = Impact::Serious;
+
+/// What to do when a proposal introduces serious accessibility violations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AccessibilityPolicy {
+    /// Record audits only
+    Off,
+    /// Report the violations as warnings
+    #[default]
+    Warn,
+    /// Fail the proposal, and have the AI fix them
+    Deny,
+}
+
+impl AccessibilityPolicy {
+    /// Read `MORPHEUS_ACCESSIBILITY_POLICY`: `off`, `warn` or `deny`
+    pub fn from_env() -> Self {
+        match std::env::var("MORPHEUS_ACCESSIBILITY_POLICY").as_deref() {
+            Ok("off") => AccessibilityPolicy::Off,
+            Ok("deny") => AccessibilityPolicy::Deny,
+            _ => AccessibilityPolicy::Warn,
+        }
+    }
+}
+
+/// Read the performance budget from `MORPHEUS_MAX_FIRST_RENDER_MS`,
+/// `MORPHEUS_MAX_BUNDLE_BYTES` and `MORPHEUS_MAX_LONG_TASKS`
+pub fn performance_budget_from_env() -> PerformanceBudget {
+    PerformanceBudget {
+        max_first_render_ms: std::env::var("MORPHEUS_MAX_FIRST_RENDER_MS").ok().and_then(|v| v.parse().ok()),
+        max_bundle_bytes: std::env::var("MORPHEUS_MAX_BUNDLE_BYTES").ok().and_then(|v| v.parse().ok()),
+        max_long_tasks: std::env::var("MORPHEUS_MAX_LONG_TASKS").ok().and_then(|v| v.parse().ok()),
+    }
+}
+
+/// Compile `source` as a version of `component`, and apply the
+/// accessibility policy and performance budget to its verification
+pub async fn compile_verified(state: &AppState, source: &str, component: &str) -> Result<CompilationResult> {
+    let mut result = state.compiler.compile(source).await?;
+    let Some(verification) = &result.verification else {
+        return Ok(result);
+    };
+    let (baseline, live_performance) = {
+        let history = state.versions.lock().await;
+        let live = history.active_versions().into_iter().find(|version| version.manifest.name == component);
+        (
+            live.and_then(|version| version.accessibility.clone()).unwrap_or_default(),
+            live.and_then(|version| version.performance),
+        )
+    };
+    let introduced = accessibility::introduced(&verification.accessibility, &baseline, MIN_IMPACT);
+    if !introduced.is_empty() {
+        let violations = introduced.len();
+        warn!(component_id = %component, violations, "♿ Proposal introduces accessibility violations");
+    }
+    gate(state.accessibility_policy, &introduced, &mut result.diagnostics)?;
+    check_budget(&state.performance_budget, &verification.performance, live_performance.as_ref())?;
+    result.diagnostics.push(CompilationError {
+        message: format!("⏱️  {}", verification.performance.compare(live_performance.as_ref())),
+        file: None,
+        line: None,
+        column: None,
+        severity: Severity::Note,
+    });
+    Ok(result)
+}
+
+/// Apply `policy` to the `introduced` violations: an error under `Deny`,
+/// otherwise warnings added to `diagnostics`
+fn gate(
+    policy: AccessibilityPolicy,
+    introduced: &[AccessibilityViolation],
+    diagnostics: &mut Vec<CompilationError>,
+) -> Result<()> {
+    if introduced.is_empty() {
+        return Ok(());
+    }
+    match policy {
+        AccessibilityPolicy::Off => Ok(()),
+        AccessibilityPolicy::Warn => {
+            diagnostics.extend(introduced.iter().map(|violation| CompilationError {
+                message: format!("♿ {}", accessibility::describe(std::slice::from_ref(violation))),
+                file: None,
+                line: None,
+                column: None,
+                severity: Severity::Warning,
+            }));
+            Ok(())
+        }
+        AccessibilityPolicy::Deny => Err(MorpheusError::CompilationError(format!(
+            "The component compiles, but its rendered HTML fails these accessibility checks (axe-core):\n{}",
+            accessibility::describe(introduced)
+        ))),
+    }
+}
+
+/// Fail a proposal over `budget`, with its and the live version's
+/// measurements for the AI to improve on
+fn check_budget(
+    budget: &PerformanceBudget,
+    measurements: &PerformanceMeasurements,
+    live: Option<&PerformanceMeasurements>,
+) -> Result<()> {
+    let exceeded = budget.check(measurements);
+    if exceeded.is_empty() {
+        return Ok(());
+    }
+    Err(MorpheusError::CompilationError(format!(
+        "The component compiles, but is over its performance budget in a browser:\n- {}\nMeasured: {}",
+        exceeded.join("\n- "),
+        measurements.compare(live)
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_decides_what_introduced_violations_do() {
+        let introduced =
+            vec![AccessibilityViolation::new("button-name", Impact::Critical, "Buttons must have text", ["button"])];
+        let mut diagnostics = Vec::new();
+
+        assert!(gate(AccessibilityPolicy::Deny, &[], &mut diagnostics).is_ok());
+        assert!(gate(AccessibilityPolicy::Off, &introduced, &mut diagnostics).is_ok());
+        assert!(diagnostics.is_empty());
+
+        gate(AccessibilityPolicy::Warn, &introduced, &mut diagnostics).unwrap();
+        assert_eq!(diagnostics[0].message, "♿ button-name (critical): Buttons must have text - button");
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+
+        let error = gate(AccessibilityPolicy::Deny, &introduced, &mut diagnostics).unwrap_err();
+        assert!(error.to_string().contains("button-name (critical): Buttons must have text - button"));
+    }
+
+    #[test]
+    fn test_over_budget_proposals_fail_with_their_measurements() {
+        let live = PerformanceMeasurements {
+            first_render_ms: 10.0,
+            bundle_bytes: 50_000,
+            long_tasks: 0,
+        };
+        let proposed = PerformanceMeasurements {
+            long_tasks: 3,
+            ..live
+        };
+        let budget = PerformanceBudget {
+            max_long_tasks: Some(1),
+            ..Default::default()
+        };
+
+        assert!(check_budget(&budget, &live, None).is_ok());
+        let error = check_budget(&budget, &proposed, Some(&live)).unwrap_err().to_string();
+        assert!(error.contains("3 long task(s) blocked the main thread, over the budget of 1"));
+        assert!(error.contains("3 long task(s) (live 0)"));
+    }
+}
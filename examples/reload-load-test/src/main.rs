@@ -0,0 +1,219 @@
+//! Load test for the hot-reload path.
+//!
+//! Drives a running Morpheus server the way a busy deployment would: N
+//! components with a few versions each, M clients subscribed to
+//! `GET /api/reloads`, and a stream of activations. Every client fetches the
+//! new module as a binary patch against the version it had, like the browser
+//! does, and the run ends with a report of push latency, patch latency,
+//! dropped events and bytes saved by patching.
+//!
+//! ```text
+//! MORPHEUS_MOCK_AI=1 cargo run --bin morpheus &
+//! cargo run --release --bin morpheus-loadtest -- --components 4 --clients 100 --reloads 200
+//! ```
+
+mod stats;
+
+use anyhow::{bail, Context};
+use morpheus_client::{GenerateRequest, MorpheusClient, ReloadStream};
+use stats::{Activation, Received, Report};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// Versions generated per component, so activations have something to
+/// switch between
+const VARIANTS: usize = 2;
+
+/// Prefix of the prompts of versions the load test generates
+const PROMPT_PREFIX: &str = "Load test component";
+
+/// How long clients wait for stragglers after the last activation
+const DRAIN: Duration = Duration::from_secs(5);
+
+/// Command-line options
+struct Options {
+    url: String,
+    components: usize,
+    clients: usize,
+    reloads: usize,
+    interval: Duration,
+    report: Option<String>,
+}
+
+impl Options {
+    fn parse() -> anyhow::Result<Self> {
+        let mut options = Self {
+            url: "http://127.0.0.1:3002".to_string(),
+            components: 4,
+            clients: 50,
+            reloads: 100,
+            interval: Duration::ZERO,
+            report: None,
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(flag) = args.next() {
+            if flag == "--help" || flag == "-h" {
+                println!(
+                    "Usage: morpheus-loadtest [--url URL] [--components N] [--clients M] [--reloads R] \
+                     [--interval-ms MS] [--report PATH.json]"
+                );
+                std::process::exit(0);
+            }
+            let value = args.next().with_context(|| format!("{} needs a value", flag))?;
+            let number = || value.parse::<usize>().with_context(|| format!("{} takes a number", flag));
+            match flag.as_str() {
+                "--url" => options.url = value.clone(),
+                "--components" => options.components = number()?.max(1),
+                "--clients" => options.clients = number()?.max(1),
+                "--reloads" => options.reloads = number()?,
+                "--interval-ms" => options.interval = Duration::from_millis(number()? as u64),
+                "--report" => options.report = Some(value.clone()),
+                _ => bail!("unknown option {}", flag),
+            }
+        }
+        Ok(options)
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let options = Options::parse()?;
+    let morpheus = MorpheusClient::new(options.url.as_str());
+    morpheus.health().await.with_context(|| format!("no Morpheus server at {}", options.url))?;
+
+    let versions = prepare_versions(&morpheus, options.components).await?;
+    let initial = morpheus
+        .history()
+        .await?
+        .versions
+        .iter()
+        .find(|v| v.is_current)
+        .map(|v| v.id)
+        .unwrap_or(versions[0]);
+    println!(
+        "▶ {} components ({} versions) × {} clients, {} reloads",
+        options.components,
+        versions.len(),
+        options.clients,
+        options.reloads
+    );
+
+    // Subscribe every client before the first activation
+    let (done_tx, done_rx) = watch::channel(None::<Arc<Vec<Activation>>>);
+    let mut clients = Vec::new();
+    for _ in 0..options.clients {
+        let reloads = morpheus.subscribe_reloads().await?;
+        clients.push(tokio::spawn(run_client(morpheus.clone(), reloads, initial, done_rx.clone())));
+    }
+
+    // Rotate through the versions, starting after the current one so every
+    // activation changes something
+    let start = versions.iter().position(|&v| v == initial).map_or(0, |i| i + 1);
+    let started = Instant::now();
+    let mut activations = Vec::with_capacity(options.reloads);
+    for round in 0..options.reloads {
+        let version_id = versions[(start + round) % versions.len()];
+        let sent_at = Instant::now();
+        let response = morpheus.rollback(version_id).await?;
+        if !response.success {
+            bail!("activating version {} failed: {}", version_id, response.error.unwrap_or_default());
+        }
+        activations.push(Activation { version_id, sent_at });
+        if !options.interval.is_zero() {
+            tokio::time::sleep(options.interval).await;
+        }
+    }
+    let duration = started.elapsed();
+    let activations = Arc::new(activations);
+    let _ = done_tx.send(Some(activations.clone()));
+
+    let mut received = Vec::new();
+    for client in clients {
+        received.push(client.await??);
+    }
+
+    let report = Report::new(options.components, &activations, &received, duration);
+    println!("\n{}", report.render());
+    if let Some(path) = &options.report {
+        std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+        println!("Report written to {}", path);
+    }
+    Ok(())
+}
+
+/// Find or generate `VARIANTS` versions of each load-test component,
+/// returning their IDs in activation order (alternating components).
+async fn prepare_versions(morpheus: &MorpheusClient, components: usize) -> anyhow::Result<Vec<usize>> {
+    let history = morpheus.history().await?;
+    let mut versions = Vec::new();
+    for variant in 0..VARIANTS {
+        for component in 0..components {
+            let prompt = format!("{} {} variant {}", PROMPT_PREFIX, component, variant);
+            if let Some(existing) = history.versions.iter().find(|v| v.description == prompt) {
+                versions.push(existing.id);
+                continue;
+            }
+            println!("⚙️  Generating {}", prompt);
+            let request = GenerateRequest::new(prompt.as_str()).with_component(format!("load-{}", component));
+            let generated = morpheus.generate(&request).await?;
+            match generated.version_id {
+                Some(id) if generated.success => versions.push(id),
+                _ => bail!(
+                    "generating '{}' failed: {} (run the server with MORPHEUS_MOCK_AI=1 to generate offline)",
+                    prompt,
+                    generated.error.unwrap_or_default()
+                ),
+            }
+        }
+    }
+    Ok(versions)
+}
+
+/// Follow reloads like a browser: on each event, fetch the new module as a
+/// patch against the version the client has. Stops once the last
+/// activation arrives, or `DRAIN` after the driver finishes.
+async fn run_client(
+    morpheus: MorpheusClient,
+    mut reloads: ReloadStream,
+    mut current: usize,
+    mut done: watch::Receiver<Option<Arc<Vec<Activation>>>>,
+) -> anyhow::Result<Vec<Received>> {
+    let mut received = Vec::new();
+    let mut deadline: Option<tokio::time::Instant> = None;
+    let caught_up = |received: &[Received], done: &watch::Receiver<Option<Arc<Vec<Activation>>>>| {
+        done.borrow().as_ref().is_some_and(|activations| stats::match_events(activations, received).caught_up)
+    };
+    loop {
+        if caught_up(&received, &done) {
+            break;
+        }
+        let next = async {
+            match deadline {
+                Some(deadline) => tokio::time::timeout_at(deadline, reloads.next()).await.ok(),
+                None => Some(reloads.next().await),
+            }
+        };
+        let event = tokio::select! {
+            event = next => event,
+            _ = done.changed(), if deadline.is_none() => {
+                deadline = Some(tokio::time::Instant::now() + DRAIN);
+                continue;
+            }
+        };
+        let Some(event) = event.transpose()?.flatten() else { break };
+        let received_at = Instant::now();
+
+        let fetch = Instant::now();
+        let patch = morpheus.patch(event.version_id, current).await?;
+        received.push(Received {
+            version_id: event.version_id,
+            received_at,
+            patch_latency: Some(fetch.elapsed()),
+            patch_size: patch.patch_size,
+            wasm_size: patch.wasm_size,
+        });
+        current = event.version_id;
+    }
+    Ok(received)
+}
@@ -0,0 +1,235 @@
+//! Matching received reloads to the activations that caused them, and the
+//! report built from them.
+
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// A version the driver activated, and when it sent the request.
+#[derive(Debug, Clone, Copy)]
+pub struct Activation {
+    pub version_id: usize,
+    pub sent_at: Instant,
+}
+
+/// A reload event one client received.
+#[derive(Debug, Clone, Copy)]
+pub struct Received {
+    pub version_id: usize,
+    pub received_at: Instant,
+    /// Time to fetch the patch against the client's previous version
+    pub patch_latency: Option<Duration>,
+    pub patch_size: usize,
+    pub wasm_size: usize,
+}
+
+/// What one client saw, matched against the activations.
+#[derive(Debug, Default)]
+pub struct Delivery {
+    /// Activation to receipt, for each event delivered
+    pub latencies: Vec<Duration>,
+    /// Activations the client never heard about (the server drops events
+    /// for subscribers that fall behind)
+    pub missed: usize,
+    /// Events that matched no activation, e.g. a reload by someone else
+    pub unexpected: usize,
+    /// Whether the client received the last activation
+    pub caught_up: bool,
+}
+
+/// Match a client's events to activations. Events arrive in activation
+/// order, so each one matches the next activation of its version; the
+/// activations skipped on the way were missed.
+pub fn match_events(activations: &[Activation], received: &[Received]) -> Delivery {
+    let mut delivery = Delivery::default();
+    let mut next = 0;
+    for event in received {
+        let found = activations[next..].iter().position(|a| a.version_id == event.version_id);
+        match found {
+            Some(skipped) => {
+                let activation = activations[next + skipped];
+                delivery.latencies.push(event.received_at.saturating_duration_since(activation.sent_at));
+                delivery.missed += skipped;
+                next += skipped + 1;
+            }
+            None => delivery.unexpected += 1,
+        }
+    }
+    delivery.missed += activations.len() - next;
+    delivery.caught_up = next == activations.len();
+    delivery
+}
+
+/// Latency percentiles in milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Percentiles {
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl Percentiles {
+    /// Nearest-rank percentiles; all zero without samples.
+    pub fn of(samples: &[Duration]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let rank = |p: f64| {
+            let Some(last) = sorted.len().checked_sub(1) else { return 0.0 };
+            let index = ((p / 100.0 * sorted.len() as f64).ceil() as usize).saturating_sub(1).min(last);
+            sorted[index].as_secs_f64() * 1000.0
+        };
+        Self {
+            p50: rank(50.0),
+            p95: rank(95.0),
+            p99: rank(99.0),
+            max: rank(100.0),
+        }
+    }
+}
+
+/// Results of a run.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub components: usize,
+    pub clients: usize,
+    pub reloads: usize,
+    pub duration_secs: f64,
+    /// Reload events delivered to clients
+    pub delivered: usize,
+    /// `reloads × clients`
+    pub expected: usize,
+    pub missed: usize,
+    pub unexpected: usize,
+    /// Activations per second the server accepted
+    pub reloads_per_sec: f64,
+    /// Events per second delivered across all clients
+    pub deliveries_per_sec: f64,
+    /// Activation request sent → event received
+    pub push_latency_ms: Percentiles,
+    /// Event received → patch downloaded
+    pub patch_latency_ms: Percentiles,
+    pub avg_wasm_bytes: f64,
+    pub avg_patch_bytes: f64,
+    /// Bytes saved by sending patches instead of whole modules
+    pub patch_savings_percent: f64,
+}
+
+impl Report {
+    pub fn new(
+        components: usize,
+        activations: &[Activation],
+        clients: &[Vec<Received>],
+        duration: Duration,
+    ) -> Self {
+        let mut latencies = Vec::new();
+        let (mut missed, mut unexpected) = (0, 0);
+        for received in clients {
+            let delivery = match_events(activations, received);
+            latencies.extend(delivery.latencies);
+            missed += delivery.missed;
+            unexpected += delivery.unexpected;
+        }
+        let events: Vec<&Received> = clients.iter().flatten().collect();
+        let patched: Vec<&&Received> = events.iter().filter(|e| e.patch_latency.is_some()).collect();
+        let patch_latencies: Vec<Duration> = patched.iter().filter_map(|e| e.patch_latency).collect();
+        let average = |total: usize| if patched.is_empty() { 0.0 } else { total as f64 / patched.len() as f64 };
+        let avg_wasm_bytes = average(patched.iter().map(|e| e.wasm_size).sum());
+        let avg_patch_bytes = average(patched.iter().map(|e| e.patch_size).sum());
+        let secs = duration.as_secs_f64().max(f64::EPSILON);
+
+        Self {
+            components,
+            clients: clients.len(),
+            reloads: activations.len(),
+            duration_secs: duration.as_secs_f64(),
+            delivered: latencies.len(),
+            expected: activations.len() * clients.len(),
+            missed,
+            unexpected,
+            reloads_per_sec: activations.len() as f64 / secs,
+            deliveries_per_sec: latencies.len() as f64 / secs,
+            push_latency_ms: Percentiles::of(&latencies),
+            patch_latency_ms: Percentiles::of(&patch_latencies),
+            avg_wasm_bytes,
+            avg_patch_bytes,
+            patch_savings_percent: if avg_wasm_bytes > 0.0 {
+                100.0 * (1.0 - avg_patch_bytes / avg_wasm_bytes)
+            } else {
+                0.0
+            },
+        }
+    }
+
+    /// The report as a markdown table.
+    pub fn render(&self) -> String {
+        let percentiles =
+            |p: &Percentiles| format!("{:.1} / {:.1} / {:.1} / {:.1}", p.p50, p.p95, p.p99, p.max);
+        let rows = [
+            ("Components × clients", format!("{} × {}", self.components, self.clients)),
+            ("Reloads", format!("{} in {:.2}s ({:.1}/s)", self.reloads, self.duration_secs, self.reloads_per_sec)),
+            (
+                "Delivered",
+                format!("{} of {} ({:.1}/s)", self.delivered, self.expected, self.deliveries_per_sec),
+            ),
+            ("Missed / unexpected", format!("{} / {}", self.missed, self.unexpected)),
+            ("Push latency p50/p95/p99/max (ms)", percentiles(&self.push_latency_ms)),
+            ("Patch fetch p50/p95/p99/max (ms)", percentiles(&self.patch_latency_ms)),
+            (
+                "Patch vs module",
+                format!(
+                    "{:.0} vs {:.0} bytes ({:.1}% saved)",
+                    self.avg_patch_bytes, self.avg_wasm_bytes, self.patch_savings_percent
+                ),
+            ),
+        ];
+        let mut table = String::from("| Metric | Value |\n|---|---|\n");
+        for (metric, value) in rows {
+            table.push_str(&format!("| {} | {} |\n", metric, value));
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn test_match_events_counts_missed_and_unexpected() {
+        let start = Instant::now();
+        let activations: Vec<Activation> = [1, 2, 1, 2]
+            .iter()
+            .enumerate()
+            .map(|(i, &version_id)| Activation { version_id, sent_at: start + ms(10 * i as u64) })
+            .collect();
+        let received = |version_id, at| Received {
+            version_id,
+            received_at: start + ms(at),
+            patch_latency: None,
+            patch_size: 0,
+            wasm_size: 0,
+        };
+
+        // Misses the second activation, then sees a reload it didn't cause
+        let delivery = match_events(&activations, &[received(1, 3), received(1, 25), received(9, 26)]);
+
+        assert_eq!(delivery.latencies, [ms(3), ms(5)]);
+        assert_eq!(delivery.missed, 2);
+        assert_eq!(delivery.unexpected, 1);
+        assert!(!delivery.caught_up);
+        assert!(match_events(&activations, &[received(2, 14), received(2, 40)]).caught_up);
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let samples: Vec<Duration> = (1..=100).map(ms).collect();
+        let p = Percentiles::of(&samples);
+
+        assert_eq!((p.p50, p.p95, p.p99, p.max), (50.0, 95.0, 99.0, 100.0));
+        assert_eq!(Percentiles::of(&[]), Percentiles::default());
+    }
+}